@@ -4,28 +4,28 @@
 
 extern crate alloc;
 use alloc::collections::VecDeque;
-use core::cell::RefCell;
+use std::sync::Mutex;
 
 pub struct SharedBuffer {
-    queue: RefCell<VecDeque<u8>>,
+    queue: Mutex<VecDeque<u8>>,
 }
 
 impl SharedBuffer {
     pub fn new() -> Self {
         SharedBuffer {
-            queue: RefCell::new(VecDeque::<u8>::new()),
+            queue: Mutex::new(VecDeque::<u8>::new()),
         }
     }
     pub fn set_buffer(&self, b: &[u8]) {
         log::info!("send    {:02x?}\n", b);
-        let mut queue = self.queue.borrow_mut();
+        let mut queue = self.queue.lock().unwrap();
         for i in b {
             queue.push_back(*i);
         }
     }
 
     pub fn get_buffer(&self, b: &mut [u8]) -> usize {
-        let mut queue = self.queue.borrow_mut();
+        let mut queue = self.queue.lock().unwrap();
         let mut len = 0usize;
         for i in b.iter_mut() {
             if queue.is_empty() {
@@ -32,6 +32,7 @@ fn fuzz_send_receive_spdm_algorithm(fuzzdata: &[u8]) {
         req_config_info,
         req_provision_info,
     );
+    set_negotiated_capabilities(&mut requester.common);
 
     let _ = requester.send_receive_spdm_algorithm().is_err();
 }
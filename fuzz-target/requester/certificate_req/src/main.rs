@@ -30,6 +30,7 @@ fn fuzz_send_receive_spdm_certificate(fuzzdata: &[u8]) {
             rsp_config_info,
             rsp_provision_info,
         );
+        set_negotiated_capabilities(&mut responder.common);
 
         responder.common.reset_runtime_info();
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
@@ -47,6 +48,7 @@ fn fuzz_send_receive_spdm_certificate(fuzzdata: &[u8]) {
             req_config_info,
             req_provision_info,
         );
+        set_negotiated_capabilities(&mut requester.common);
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         requester.common.negotiate_info.base_asym_sel =
@@ -69,6 +71,7 @@ fn fuzz_send_receive_spdm_certificate(fuzzdata: &[u8]) {
             rsp_config_info1,
             rsp_provision_info1,
         );
+        set_negotiated_capabilities(&mut responder.common);
         responder.common.reset_runtime_info();
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         responder.common.negotiate_info.base_asym_sel =
@@ -85,6 +88,7 @@ fn fuzz_send_receive_spdm_certificate(fuzzdata: &[u8]) {
             req_config_info1,
             req_provision_info1,
         );
+        set_negotiated_capabilities(&mut requester.common);
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         requester.common.negotiate_info.base_asym_sel =
@@ -106,6 +110,7 @@ fn fuzz_send_receive_spdm_certificate(fuzzdata: &[u8]) {
             rsp_config_info2,
             rsp_provision_info2,
         );
+        set_negotiated_capabilities(&mut responder.common);
         responder.common.reset_runtime_info();
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         responder.common.negotiate_info.base_asym_sel =
@@ -122,6 +127,7 @@ fn fuzz_send_receive_spdm_certificate(fuzzdata: &[u8]) {
             req_config_info2,
             req_provision_info2,
         );
+        set_negotiated_capabilities(&mut requester.common);
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         requester.common.negotiate_info.base_asym_sel =
@@ -150,6 +156,7 @@ fn fuzz_send_receive_spdm_certificate(fuzzdata: &[u8]) {
             rsp_config_info3,
             rsp_provision_info3,
         );
+        set_negotiated_capabilities(&mut responder.common);
 
         responder.common.reset_runtime_info();
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
@@ -169,6 +176,7 @@ fn fuzz_send_receive_spdm_certificate(fuzzdata: &[u8]) {
             req_config_info3,
             req_provision_info3,
         );
+        set_negotiated_capabilities(&mut requester.common);
         let mut tmp = requester
             .common
             .provision_info
@@ -193,6 +201,7 @@ fn fuzz_send_receive_spdm_certificate(fuzzdata: &[u8]) {
             rsp_config_info4,
             rsp_provision_info4,
         );
+        set_negotiated_capabilities(&mut responder.common);
 
         responder.common.reset_runtime_info();
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
@@ -210,6 +219,7 @@ fn fuzz_send_receive_spdm_certificate(fuzzdata: &[u8]) {
             req_config_info4,
             req_provision_info4,
         );
+        set_negotiated_capabilities(&mut requester.common);
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         requester.common.negotiate_info.base_asym_sel =
             SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
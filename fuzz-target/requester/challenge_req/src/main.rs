@@ -22,6 +22,7 @@ fn fuzz_send_receive_spdm_challenge(fuzzdata: &[u8]) {
         rsp_config_info,
         rsp_provision_info,
     );
+    set_negotiated_capabilities(&mut responder.common);
 
     responder.common.reset_runtime_info();
     responder.common.provision_info.my_cert_chain = Some(SpdmCertChainData {
@@ -43,6 +44,7 @@ fn fuzz_send_receive_spdm_challenge(fuzzdata: &[u8]) {
         req_config_info,
         req_provision_info,
     );
+    set_negotiated_capabilities(&mut requester.common);
 
     requester.common.reset_runtime_info();
 
@@ -22,6 +22,7 @@ fn fuzz_send_receive_spdm_digest(fuzzdata: &[u8]) {
         rsp_config_info,
         rsp_provision_info,
     );
+    set_negotiated_capabilities(&mut responder.common);
     responder.common.provision_info.my_cert_chain = Some(SpdmCertChainData {
         data_size: 512u16,
         data: [0u8; config::MAX_SPDM_CERT_CHAIN_DATA_SIZE],
@@ -38,6 +39,7 @@ fn fuzz_send_receive_spdm_digest(fuzzdata: &[u8]) {
         req_config_info,
         req_provision_info,
     );
+    set_negotiated_capabilities(&mut requester.common);
 
     requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
 
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
 use fuzzlib::{
-    spdmlib::session::{SpdmSession, SpdmSessionState},
+    spdmlib::session::{SpdmSessionState, SpdmSessionTable},
     *,
 };
 
@@ -53,7 +53,7 @@ fn fuzz_send_receive_spdm_end_session(fuzzdata: &[u8]) {
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
 
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(4294901758).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -105,7 +105,7 @@ fn fuzz_send_receive_spdm_end_session(fuzzdata: &[u8]) {
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
 
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(4294901758).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
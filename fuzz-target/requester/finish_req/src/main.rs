@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
 use fuzzlib::{
-    spdmlib::session::{SpdmSession, SpdmSessionState},
+    spdmlib::session::{SpdmSessionState, SpdmSessionTable},
     *,
 };
 
@@ -50,7 +50,7 @@ fn fuzz_send_receive_spdm_finish(fuzzdata: &[u8]) {
 
         responder.common.reset_runtime_info();
 
-        responder.common.session = [SpdmSession::new(); 4];
+        responder.common.session = SpdmSessionTable::new();
         responder.common.session[0].setup(4294901758).unwrap();
         responder.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -92,7 +92,7 @@ fn fuzz_send_receive_spdm_finish(fuzzdata: &[u8]) {
 
         requester.common.reset_runtime_info();
 
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(4294901758).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -146,7 +146,7 @@ fn fuzz_send_receive_spdm_finish(fuzzdata: &[u8]) {
 
         responder.common.reset_runtime_info();
 
-        responder.common.session = [SpdmSession::new(); 4];
+        responder.common.session = SpdmSessionTable::new();
         responder.common.session[0].setup(4294901758).unwrap();
         responder.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -186,7 +186,7 @@ fn fuzz_send_receive_spdm_finish(fuzzdata: &[u8]) {
 
         requester.common.reset_runtime_info();
 
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(4294901758).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -232,7 +232,7 @@ fn fuzz_send_receive_spdm_finish(fuzzdata: &[u8]) {
 
         responder.common.reset_runtime_info();
 
-        responder.common.session = [SpdmSession::new(); 4];
+        responder.common.session = SpdmSessionTable::new();
         responder.common.session[0].setup(4294901758).unwrap();
         responder.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -272,7 +272,7 @@ fn fuzz_send_receive_spdm_finish(fuzzdata: &[u8]) {
 
         requester.common.reset_runtime_info();
 
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(4294901758).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
 use fuzzlib::{
-    spdmlib::session::{SpdmSession, SpdmSessionState},
+    spdmlib::session::{SpdmSessionState, SpdmSessionTable},
     *,
 };
 
@@ -28,10 +28,11 @@ fn fuzz_send_receive_spdm_heartbeat(fuzzdata: &[u8]) {
             rsp_config_info,
             rsp_provision_info,
         );
+        set_negotiated_capabilities(&mut responder.common);
 
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
 
-        responder.common.session = [SpdmSession::new(); 4];
+        responder.common.session = SpdmSessionTable::new();
         responder.common.session[0].setup(4294901758).unwrap();
         responder.common.session[0].set_session_state(SpdmSessionState::SpdmSessionEstablished);
 
@@ -51,10 +52,11 @@ fn fuzz_send_receive_spdm_heartbeat(fuzzdata: &[u8]) {
             req_config_info,
             req_provision_info,
         );
+        set_negotiated_capabilities(&mut requester.common);
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
 
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(4294901758).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -81,10 +83,11 @@ fn fuzz_send_receive_spdm_heartbeat(fuzzdata: &[u8]) {
             rsp_config_info1,
             rsp_provision_info1,
         );
+        set_negotiated_capabilities(&mut responder.common);
 
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
 
-        responder.common.session = [SpdmSession::new(); 4];
+        responder.common.session = SpdmSessionTable::new();
         responder.common.session[0].setup(4294901758).unwrap();
         responder.common.session[0].set_session_state(SpdmSessionState::SpdmSessionEstablished);
 
@@ -104,10 +107,11 @@ fn fuzz_send_receive_spdm_heartbeat(fuzzdata: &[u8]) {
             req_config_info1,
             req_provision_info1,
         );
+        set_negotiated_capabilities(&mut requester.common);
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
 
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(4294901758).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -26,6 +26,7 @@ fn fuzz_send_receive_spdm_key_exchange(fuzzdata: &[u8]) {
             rsp_config_info,
             rsp_provision_info,
         );
+        set_negotiated_capabilities(&mut responder.common);
 
         responder.common.provision_info.my_cert_chain = Some(SpdmCertChainData {
             data_size: 512u16,
@@ -57,6 +58,7 @@ fn fuzz_send_receive_spdm_key_exchange(fuzzdata: &[u8]) {
             req_config_info,
             req_provision_info,
         );
+        set_negotiated_capabilities(&mut requester.common);
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         requester.common.negotiate_info.aead_sel = SpdmAeadAlgo::AES_128_GCM;
@@ -95,6 +97,7 @@ fn fuzz_send_receive_spdm_key_exchange(fuzzdata: &[u8]) {
             rsp_config_info1,
             rsp_provision_info1,
         );
+        set_negotiated_capabilities(&mut responder.common);
 
         responder.common.provision_info.my_cert_chain = Some(SpdmCertChainData {
             data_size: 512u16,
@@ -126,6 +129,7 @@ fn fuzz_send_receive_spdm_key_exchange(fuzzdata: &[u8]) {
             req_config_info1,
             req_provision_info1,
         );
+        set_negotiated_capabilities(&mut requester.common);
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         requester.common.negotiate_info.aead_sel = SpdmAeadAlgo::AES_128_GCM;
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
 use fuzzlib::{
-    spdmlib::session::{SpdmSession, SpdmSessionState},
+    spdmlib::session::{SpdmSessionState, SpdmSessionTable},
     *,
 };
 
@@ -30,6 +30,7 @@ fn fuzz_send_receive_spdm_key_update(fuzzdata: &[u8]) {
             rsp_config_info,
             rsp_provision_info,
         );
+        set_negotiated_capabilities(&mut responder.common);
 
         responder.common.session[0].setup(4294901758).unwrap();
         responder.common.session[0].set_session_state(SpdmSessionState::SpdmSessionHandshaking);
@@ -50,7 +51,8 @@ fn fuzz_send_receive_spdm_key_update(fuzzdata: &[u8]) {
             req_config_info,
             req_provision_info,
         );
-        requester.common.session = [SpdmSession::new(); 4];
+        set_negotiated_capabilities(&mut requester.common);
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(4294901758).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -78,6 +80,7 @@ fn fuzz_send_receive_spdm_key_update(fuzzdata: &[u8]) {
             rsp_config_info1,
             rsp_provision_info1,
         );
+        set_negotiated_capabilities(&mut responder.common);
 
         responder.common.session[0].setup(4294901758).unwrap();
         responder.common.session[0].set_session_state(SpdmSessionState::SpdmSessionHandshaking);
@@ -98,8 +101,9 @@ fn fuzz_send_receive_spdm_key_update(fuzzdata: &[u8]) {
             req_config_info1,
             req_provision_info1,
         );
+        set_negotiated_capabilities(&mut requester.common);
 
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(4294901758).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -126,6 +130,7 @@ fn fuzz_send_receive_spdm_key_update(fuzzdata: &[u8]) {
             rsp_config_info2,
             rsp_provision_info2,
         );
+        set_negotiated_capabilities(&mut responder.common);
 
         responder.common.session[0].setup(4294901758).unwrap();
         responder.common.session[0].set_session_state(SpdmSessionState::SpdmSessionHandshaking);
@@ -146,8 +151,9 @@ fn fuzz_send_receive_spdm_key_update(fuzzdata: &[u8]) {
             req_config_info2,
             req_provision_info2,
         );
+        set_negotiated_capabilities(&mut requester.common);
 
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(4294901758).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
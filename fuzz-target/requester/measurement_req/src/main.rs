@@ -33,7 +33,7 @@ fn fuzz_send_receive_spdm_measurement(fuzzdata: &[u8]) {
 
         responder.common.negotiate_info.rsp_ct_exponent_sel = 0;
         responder.common.negotiate_info.rsp_capabilities_sel =
-            SpdmResponseCapabilityFlags::CERT_CAP;
+            SpdmResponseCapabilityFlags::CERT_CAP | SpdmResponseCapabilityFlags::MEAS_CAP_SIG;
 
         responder
             .common
@@ -69,7 +69,7 @@ fn fuzz_send_receive_spdm_measurement(fuzzdata: &[u8]) {
 
         requester.common.negotiate_info.rsp_ct_exponent_sel = 0;
         requester.common.negotiate_info.rsp_capabilities_sel =
-            SpdmResponseCapabilityFlags::CERT_CAP;
+            SpdmResponseCapabilityFlags::CERT_CAP | SpdmResponseCapabilityFlags::MEAS_CAP_SIG;
         requester
             .common
             .negotiate_info
@@ -104,7 +104,7 @@ fn fuzz_send_receive_spdm_measurement(fuzzdata: &[u8]) {
 
         responder.common.negotiate_info.rsp_ct_exponent_sel = 0;
         responder.common.negotiate_info.rsp_capabilities_sel =
-            SpdmResponseCapabilityFlags::CERT_CAP;
+            SpdmResponseCapabilityFlags::CERT_CAP | SpdmResponseCapabilityFlags::MEAS_CAP_SIG;
 
         responder
             .common
@@ -140,7 +140,7 @@ fn fuzz_send_receive_spdm_measurement(fuzzdata: &[u8]) {
 
         requester.common.negotiate_info.rsp_ct_exponent_sel = 0;
         requester.common.negotiate_info.rsp_capabilities_sel =
-            SpdmResponseCapabilityFlags::CERT_CAP;
+            SpdmResponseCapabilityFlags::CERT_CAP | SpdmResponseCapabilityFlags::MEAS_CAP_SIG;
         requester
             .common
             .negotiate_info
@@ -176,7 +176,7 @@ fn fuzz_send_receive_spdm_measurement(fuzzdata: &[u8]) {
 
         responder.common.negotiate_info.rsp_ct_exponent_sel = 0;
         responder.common.negotiate_info.rsp_capabilities_sel =
-            SpdmResponseCapabilityFlags::CERT_CAP;
+            SpdmResponseCapabilityFlags::CERT_CAP | SpdmResponseCapabilityFlags::MEAS_CAP_SIG;
 
         responder
             .common
@@ -212,7 +212,7 @@ fn fuzz_send_receive_spdm_measurement(fuzzdata: &[u8]) {
 
         requester.common.negotiate_info.rsp_ct_exponent_sel = 0;
         requester.common.negotiate_info.rsp_capabilities_sel =
-            SpdmResponseCapabilityFlags::CERT_CAP;
+            SpdmResponseCapabilityFlags::CERT_CAP | SpdmResponseCapabilityFlags::MEAS_CAP_SIG;
         requester
             .common
             .negotiate_info
@@ -247,7 +247,7 @@ fn fuzz_send_receive_spdm_measurement(fuzzdata: &[u8]) {
 
         responder.common.negotiate_info.rsp_ct_exponent_sel = 0;
         responder.common.negotiate_info.rsp_capabilities_sel =
-            SpdmResponseCapabilityFlags::CERT_CAP;
+            SpdmResponseCapabilityFlags::CERT_CAP | SpdmResponseCapabilityFlags::MEAS_CAP_SIG;
 
         responder
             .common
@@ -283,7 +283,7 @@ fn fuzz_send_receive_spdm_measurement(fuzzdata: &[u8]) {
 
         requester.common.negotiate_info.rsp_ct_exponent_sel = 0;
         requester.common.negotiate_info.rsp_capabilities_sel =
-            SpdmResponseCapabilityFlags::CERT_CAP;
+            SpdmResponseCapabilityFlags::CERT_CAP | SpdmResponseCapabilityFlags::MEAS_CAP_SIG;
         requester
             .common
             .negotiate_info
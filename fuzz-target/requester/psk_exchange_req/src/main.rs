@@ -23,6 +23,7 @@ fn fuzz_send_receive_spdm_psk_exchange(fuzzdata: &[u8]) {
             rsp_config_info,
             rsp_provision_info,
         );
+        set_negotiated_capabilities(&mut responder.common);
 
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         responder.common.negotiate_info.aead_sel = SpdmAeadAlgo::AES_256_GCM;
@@ -38,6 +39,7 @@ fn fuzz_send_receive_spdm_psk_exchange(fuzzdata: &[u8]) {
             req_config_info,
             req_provision_info,
         );
+        set_negotiated_capabilities(&mut requester.common);
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         requester.common.negotiate_info.aead_sel = SpdmAeadAlgo::AES_256_GCM;
@@ -62,6 +64,7 @@ fn fuzz_send_receive_spdm_psk_exchange(fuzzdata: &[u8]) {
             rsp_config_info1,
             rsp_provision_info1,
         );
+        set_negotiated_capabilities(&mut responder.common);
 
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         responder.common.negotiate_info.aead_sel = SpdmAeadAlgo::AES_256_GCM;
@@ -77,6 +80,7 @@ fn fuzz_send_receive_spdm_psk_exchange(fuzzdata: &[u8]) {
             req_config_info1,
             req_provision_info1,
         );
+        set_negotiated_capabilities(&mut requester.common);
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         requester.common.negotiate_info.aead_sel = SpdmAeadAlgo::AES_256_GCM;
@@ -172,7 +172,7 @@ fn fuzz_send_receive_spdm_version(fuzzdata: &[u8]) {
             rsp_config_info3,
             rsp_provision_info3,
         );
-        responder.common.session = [spdmlib::session::SpdmSession::new(); 4];
+        responder.common.session = spdmlib::session::SpdmSessionTable::new();
         responder.common.session[0].setup(4294901758).unwrap();
         responder.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -194,7 +194,7 @@ fn fuzz_send_receive_spdm_version(fuzzdata: &[u8]) {
             req_provision_info3,
         );
 
-        requester.common.session = [spdmlib::session::SpdmSession::new(); 4];
+        requester.common.session = spdmlib::session::SpdmSessionTable::new();
         requester.common.session[0].setup(4294901758).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
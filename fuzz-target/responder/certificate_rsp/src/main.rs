@@ -24,6 +24,7 @@ fn fuzz_handle_spdm_certificate(data: &[u8]) {
         config_info,
         provision_info,
     );
+    set_negotiated_capabilities(&mut context.common);
 
     context.common.provision_info.my_cert_chain = Some(REQ_CERT_CHAIN_DATA);
     context.handle_spdm_certificate(data);
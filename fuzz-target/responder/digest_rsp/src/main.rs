@@ -24,6 +24,7 @@ fn fuzz_handle_spdm_digest(data: &[u8]) {
         config_info,
         provision_info,
     );
+    set_negotiated_capabilities(&mut context.common);
 
     context.common.provision_info.my_cert_chain = Some(SpdmCertChainData {
         data_size: 512u16,
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
 use fuzzlib::{
-    spdmlib::session::{SpdmSession, SpdmSessionState},
+    spdmlib::session::{SpdmSessionState, SpdmSessionTable},
     *,
 };
 
@@ -27,9 +27,10 @@ fn fuzz_handle_spdm_key_update(data: &[u8]) {
         config_info,
         provision_info,
     );
+    set_negotiated_capabilities(&mut context.common);
 
     context.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
-    context.common.session = [SpdmSession::new(); 4];
+    context.common.session = SpdmSessionTable::new();
     context.common.session[0].setup(4294901758).unwrap();
     context.common.session[0].set_crypto_param(
         SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -27,6 +27,7 @@ fn fuzz_handle_spdm_key_exchange(data: &[u8]) {
             config_info,
             provision_info,
         );
+        set_negotiated_capabilities(&mut context.common);
         // algorithm_rsp
         context.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         context.common.negotiate_info.base_asym_sel = SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
@@ -54,6 +55,7 @@ fn fuzz_handle_spdm_key_exchange(data: &[u8]) {
             config_info1,
             provision_info1,
         );
+        set_negotiated_capabilities(&mut context.common);
 
         context.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         context.common.negotiate_info.base_asym_sel = SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
@@ -88,6 +90,7 @@ fn fuzz_handle_spdm_key_exchange(data: &[u8]) {
             config_info2,
             provision_info2,
         );
+        set_negotiated_capabilities(&mut context.common);
 
         context.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         context.common.negotiate_info.base_asym_sel = SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
-use fuzzlib::{spdmlib::session::SpdmSession, *};
+use fuzzlib::{spdmlib::session::SpdmSessionTable, *};
 
 fn fuzz_handle_spdm_psk_exchange(data: &[u8]) {
     let (config_info, provision_info) = rsp_create_info();
@@ -26,6 +26,7 @@ fn fuzz_handle_spdm_psk_exchange(data: &[u8]) {
             config_info,
             provision_info,
         );
+        set_negotiated_capabilities(&mut context.common);
 
         context.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         context.common.negotiate_info.base_asym_sel = SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
@@ -54,6 +55,7 @@ fn fuzz_handle_spdm_psk_exchange(data: &[u8]) {
             config_info1,
             provision_info1,
         );
+        set_negotiated_capabilities(&mut context.common);
 
         context.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         context.common.negotiate_info.base_asym_sel = SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
@@ -63,7 +65,7 @@ fn fuzz_handle_spdm_psk_exchange(data: &[u8]) {
         context.common.negotiate_info.key_schedule_sel = SpdmKeyScheduleAlgo::SPDM_KEY_SCHEDULE;
 
         context.common.reset_runtime_info();
-        context.common.session = [SpdmSession::new(); 4];
+        context.common.session = SpdmSessionTable::new();
         context.common.session[0].setup(4294901758).unwrap();
         context.common.session[1].setup(4294901758).unwrap();
         context.common.session[2].setup(4294901758).unwrap();
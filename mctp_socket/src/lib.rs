@@ -0,0 +1,29 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+//! SpdmDeviceIo over Linux AF_MCTP sockets, for use with MctpTransportEncap.
+//!
+//! The crate is empty on other targets.
+
+#![deny(unsafe_code)]
+
+#[cfg(target_os = "linux")]
+#[macro_use]
+extern crate log;
+
+#[cfg(target_os = "linux")]
+mod socket;
+#[cfg(target_os = "linux")]
+pub use socket::*;
+
+#[cfg(target_os = "linux")]
+#[allow(unsafe_code)]
+mod af_mctp;
+#[cfg(target_os = "linux")]
+pub use af_mctp::*;
+
+#[cfg(target_os = "linux")]
+mod socket_pair;
+#[cfg(target_os = "linux")]
+pub use socket_pair::*;
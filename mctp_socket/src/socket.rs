@@ -0,0 +1,225 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use spdmlib::common::SpdmDeviceIo;
+use spdmlib::error::SpdmResult;
+use spdmlib::{spdm_err, spdm_result_err};
+
+pub const MCTP_NET_ANY: u32 = 0x0;
+pub const MCTP_ADDR_ANY: u8 = 0xFF;
+pub const MCTP_TAG_MASK: u8 = 0x07;
+pub const MCTP_TAG_OWNER: u8 = 0x08;
+
+/// The peer of an MCTP socket message, as in the kernel sockaddr_mctp.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct MctpSockAddr {
+    pub network: u32,
+    pub eid: u8,
+    pub message_type: u8,
+    pub tag: u8,
+}
+
+/// An MCTP datagram socket.
+///
+/// As with the kernel sockets, the message type byte is not in the buffer
+/// but in `MctpSockAddr::message_type`: it is put in front of the buffer
+/// sent and taken off the message received. Sending with
+/// MCTP_TAG_OWNER in the tag lets the kernel allocate the tag, and only
+/// the responses with that tag are received on the socket.
+pub trait MctpSocket {
+    fn send_to(&mut self, buffer: &[u8], addr: &MctpSockAddr) -> SpdmResult;
+
+    /// Return the full size of the message, which is truncated if it is
+    /// larger than `buffer`.
+    fn recv_from(&mut self, buffer: &mut [u8]) -> SpdmResult<(usize, MctpSockAddr)>;
+
+    /// Like `recv_from`, but the message is left to the next `recv_from`.
+    fn peek_from(&mut self, buffer: &mut [u8]) -> SpdmResult<(usize, MctpSockAddr)>;
+}
+
+/// An SpdmDeviceIo over an MCTP socket.
+///
+/// The kernel packetizes the messages. The requester sends each request as
+/// tag owner and accepts only the responses from `remote_eid`. The
+/// responder accepts requests from `remote_eid`, or any endpoint with
+/// MCTP_ADDR_ANY, and answers the last one with its tag.
+pub struct MctpSocketIo<'a> {
+    socket: &'a mut (dyn MctpSocket + Send),
+    network: u32,
+    remote_eid: u8,
+    is_requester: bool,
+    request: Option<MctpSockAddr>,
+}
+
+impl<'a> MctpSocketIo<'a> {
+    pub fn new(
+        socket: &'a mut (dyn MctpSocket + Send),
+        network: u32,
+        remote_eid: u8,
+        is_requester: bool,
+    ) -> Self {
+        MctpSocketIo {
+            socket,
+            network,
+            remote_eid,
+            is_requester,
+            request: None,
+        }
+    }
+}
+
+fn payload(buffer: &mut [u8]) -> &mut [u8] {
+    match buffer.len() {
+        0 => buffer,
+        _ => &mut buffer[1..],
+    }
+}
+
+impl MctpSocketIo<'_> {
+    fn accepts(&self, addr: &MctpSockAddr) -> bool {
+        if self.is_requester {
+            if addr.tag & MCTP_TAG_OWNER != 0 || addr.eid != self.remote_eid {
+                info!("mctp: drop message which is not a response\n");
+                return false;
+            }
+        } else {
+            if addr.tag & MCTP_TAG_OWNER == 0 {
+                info!("mctp: drop message without tag owner\n");
+                return false;
+            }
+            if self.remote_eid != MCTP_ADDR_ANY && addr.eid != self.remote_eid {
+                info!("mctp: drop request from eid {}\n", addr.eid);
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl SpdmDeviceIo for MctpSocketIo<'_> {
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        // the socket sends the message type byte (with IC) from the address
+        let message_type = *buffer.first().ok_or(spdm_err!(EINVAL))?;
+        let addr = if self.is_requester {
+            MctpSockAddr {
+                network: self.network,
+                eid: self.remote_eid,
+                message_type,
+                tag: MCTP_TAG_OWNER,
+            }
+        } else {
+            let request = match self.request.take() {
+                Some(request) => request,
+                // nothing to respond to
+                None => return spdm_result_err!(EIO),
+            };
+            MctpSockAddr {
+                message_type,
+                tag: request.tag & MCTP_TAG_MASK,
+                ..request
+            }
+        };
+        self.socket.send_to(&buffer[1..], &addr)
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+        loop {
+            // the message type byte comes in the address
+            let (size, addr) = self.socket.peek_from(payload(buffer)).map_err(|_| 0usize)?;
+            let accepted = self.accepts(&addr);
+            if accepted && buffer.len() < size + 1 {
+                // left for a receive with a large enough buffer
+                return Err(size + 1);
+            }
+            self.socket.recv_from(payload(buffer)).map_err(|_| 0usize)?;
+            if !accepted {
+                continue;
+            }
+            buffer[0] = addr.message_type;
+            if !self.is_requester {
+                self.request = Some(addr);
+            }
+            return Ok(size + 1);
+        }
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::socket_pair::*;
+
+    #[test]
+    fn test_case0_mctp_socket_io() {
+        let (mut requester_socket, mut responder_socket) = mctp_socket_pair(1, 8, 9).unwrap();
+        let mut requester = MctpSocketIo::new(&mut requester_socket, 1, 9, true);
+        let mut responder = MctpSocketIo::new(&mut responder_socket, 1, MCTP_ADDR_ANY, false);
+        let mut buffer = [0u8; 16];
+
+        // nothing to respond to
+        assert!(responder.send(&[0x05, 0x11, 0x04]).is_err());
+
+        requester.send(&[0x05, 0x11, 0x84, 0x00, 0x00]).unwrap();
+        assert_eq!(responder.receive(&mut buffer), Ok(5));
+        assert_eq!(&buffer[..5], &[0x05, 0x11, 0x84, 0x00, 0x00]);
+        responder.send(&[0x05, 0x11, 0x04]).unwrap();
+        assert_eq!(requester.receive(&mut buffer), Ok(3));
+        assert_eq!(&buffer[..3], &[0x05, 0x11, 0x04]);
+
+        // too small a buffer, the message is kept
+        requester.send(&[0x06; 8]).unwrap();
+        assert_eq!(responder.receive(&mut buffer[..4]), Err(8));
+        assert_eq!(responder.receive(&mut buffer), Ok(8));
+        assert_eq!(&buffer[..8], &[0x06; 8]);
+    }
+    #[test]
+    fn test_case1_mctp_socket_io() {
+        let (mut socket_a, mut socket_b) = mctp_socket_pair(1, 8, 9).unwrap();
+        let mut buffer = [0u8; 16];
+        let addr = MctpSockAddr {
+            network: 1,
+            eid: 8,
+            message_type: 0x05,
+            tag: MCTP_TAG_OWNER,
+        };
+
+        let mut requester = MctpSocketIo::new(&mut socket_a, 1, 9, true);
+        requester.send(&[0x05, 0x11, 0xE0]).unwrap();
+
+        // a request is not a response
+        socket_b.send_to(&[0x11, 0x84], &addr).unwrap();
+        // a response to a tag which was never allocated
+        socket_b
+            .send_to(&[0x11, 0x04], &MctpSockAddr { tag: 0x03, ..addr })
+            .unwrap();
+
+        // the message type byte is in the address only
+        let (size, request) = socket_b.recv_from(&mut buffer).unwrap();
+        assert_eq!(size, 2);
+        assert_eq!(&buffer[..2], &[0x11, 0xE0]);
+        assert_eq!(request.message_type, 0x05);
+        assert_eq!(request.eid, 8);
+        assert_ne!(request.tag & MCTP_TAG_OWNER, 0);
+        socket_b
+            .send_to(
+                &[0x11, 0x60],
+                &MctpSockAddr {
+                    tag: request.tag & MCTP_TAG_MASK,
+                    ..request
+                },
+            )
+            .unwrap();
+        assert_eq!(requester.receive(&mut buffer), Ok(3));
+        assert_eq!(&buffer[..3], &[0x05, 0x11, 0x60]);
+
+        // no route to eid 7
+        assert!(socket_b
+            .send_to(&[0x11, 0x84], &MctpSockAddr { eid: 7, ..addr })
+            .is_err());
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::socket::*;
+use spdmlib::config;
+use spdmlib::error::SpdmResult;
+use spdmlib::{spdm_err, spdm_result_err};
+use std::os::unix::net::UnixDatagram;
+
+// network, source and destination EID and tag; the MCTP message follows
+const MCTP_SOCKET_PAIR_HEADER_SIZE: usize = 7;
+
+/// One end of a connected pair of sockets standing in for AF_MCTP, for
+/// tests on hosts without kernel MCTP.
+///
+/// Like the kernel, it puts the message type byte of the address in front
+/// of the message sent, takes it off the message received, allocates the
+/// tag of a message sent as tag owner and
+/// only delivers responses for the tags it allocated, each one once.
+/// Dropping one end fails the receive on the other.
+pub struct MctpSocketPairEnd {
+    socket: UnixDatagram,
+    network: u32,
+    local_eid: u8,
+    remote_eid: u8,
+    next_tag: u8,
+    allocated_tags: u8, // bitmap
+    peeked: Option<(Vec<u8>, MctpSockAddr)>,
+}
+
+/// Create a pair of sockets in `network`, with endpoints `eid_a` and `eid_b`.
+pub fn mctp_socket_pair(
+    network: u32,
+    eid_a: u8,
+    eid_b: u8,
+) -> SpdmResult<(MctpSocketPairEnd, MctpSocketPairEnd)> {
+    let (socket_a, socket_b) = UnixDatagram::pair().map_err(|_| spdm_err!(EIO))?;
+    let end = |socket, local_eid, remote_eid| MctpSocketPairEnd {
+        socket,
+        network,
+        local_eid,
+        remote_eid,
+        next_tag: 0,
+        allocated_tags: 0,
+        peeked: None,
+    };
+    Ok((end(socket_a, eid_a, eid_b), end(socket_b, eid_b, eid_a)))
+}
+
+impl MctpSocket for MctpSocketPairEnd {
+    fn send_to(&mut self, buffer: &[u8], addr: &MctpSockAddr) -> SpdmResult {
+        if addr.network != self.network || addr.eid != self.remote_eid {
+            return spdm_result_err!(EIO);
+        }
+        let tag = if addr.tag & MCTP_TAG_OWNER != 0 {
+            let tag = self.next_tag;
+            self.next_tag = (self.next_tag + 1) & MCTP_TAG_MASK;
+            self.allocated_tags |= 1 << tag;
+            MCTP_TAG_OWNER | tag
+        } else {
+            addr.tag & MCTP_TAG_MASK
+        };
+
+        let mut message = [0u8; MCTP_SOCKET_PAIR_HEADER_SIZE + config::MAX_SPDM_TRANSPORT_SIZE];
+        if buffer.len() >= config::MAX_SPDM_TRANSPORT_SIZE {
+            return spdm_result_err!(EINVAL);
+        }
+        let used = MCTP_SOCKET_PAIR_HEADER_SIZE + 1 + buffer.len();
+        message[..4].copy_from_slice(&self.network.to_le_bytes());
+        message[4] = self.local_eid;
+        message[5] = addr.eid;
+        message[6] = tag;
+        message[MCTP_SOCKET_PAIR_HEADER_SIZE] = addr.message_type;
+        message[(MCTP_SOCKET_PAIR_HEADER_SIZE + 1)..used].copy_from_slice(buffer);
+        self.socket
+            .send(&message[..used])
+            .map_err(|_| spdm_err!(EIO))?;
+        Ok(())
+    }
+
+    fn recv_from(&mut self, buffer: &mut [u8]) -> SpdmResult<(usize, MctpSockAddr)> {
+        let (size, addr) = self.peek_from(buffer)?;
+        self.peeked = None;
+        if addr.tag & MCTP_TAG_OWNER == 0 {
+            self.allocated_tags &= !(1 << (addr.tag & MCTP_TAG_MASK));
+        }
+        Ok((size, addr))
+    }
+
+    fn peek_from(&mut self, buffer: &mut [u8]) -> SpdmResult<(usize, MctpSockAddr)> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.receive_message()?);
+        }
+        let (message, addr) = self.peeked.as_ref().unwrap();
+        let copy_size = message.len().min(buffer.len());
+        buffer[..copy_size].copy_from_slice(&message[..copy_size]);
+        Ok((message.len(), *addr))
+    }
+}
+
+impl MctpSocketPairEnd {
+    fn receive_message(&mut self) -> SpdmResult<(Vec<u8>, MctpSockAddr)> {
+        let mut message = [0u8; MCTP_SOCKET_PAIR_HEADER_SIZE + config::MAX_SPDM_TRANSPORT_SIZE];
+        loop {
+            let used = self.socket.recv(&mut message).map_err(|_| spdm_err!(EIO))?;
+            // an empty datagram is sent when the peer is dropped
+            if used <= MCTP_SOCKET_PAIR_HEADER_SIZE {
+                return spdm_result_err!(EIO);
+            }
+            let mut network = [0u8; 4];
+            network.copy_from_slice(&message[..4]);
+            let addr = MctpSockAddr {
+                network: u32::from_le_bytes(network),
+                eid: message[4],
+                message_type: message[MCTP_SOCKET_PAIR_HEADER_SIZE],
+                tag: message[6],
+            };
+            if addr.tag & MCTP_TAG_OWNER == 0
+                && self.allocated_tags & (1 << (addr.tag & MCTP_TAG_MASK)) == 0
+            {
+                debug!("mctp: no socket for tag {}\n", addr.tag);
+                continue;
+            }
+            return Ok((
+                message[(MCTP_SOCKET_PAIR_HEADER_SIZE + 1)..used].to_vec(),
+                addr,
+            ));
+        }
+    }
+}
+
+impl Drop for MctpSocketPairEnd {
+    fn drop(&mut self) {
+        let _ = self.socket.send(&[]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spdmlib::common::SpdmDeviceIo;
+
+    #[test]
+    fn test_case0_mctp_socket_pair_wire() {
+        let (mut requester_socket, responder_socket) = mctp_socket_pair(1, 8, 9).unwrap();
+        let mut requester = MctpSocketIo::new(&mut requester_socket, 1, 9, true);
+        requester.send(&[0x05, 0x11, 0x84, 0x00, 0x00]).unwrap();
+
+        // the message type byte goes out once
+        let mut wire = [0u8; 32];
+        let used = responder_socket.socket.recv(&mut wire).unwrap();
+        assert_eq!(
+            &wire[..used],
+            &[0x01, 0x00, 0x00, 0x00, 0x08, 0x09, 0x08, 0x05, 0x11, 0x84, 0x00, 0x00]
+        );
+
+        // and comes back in front of the message
+        responder_socket
+            .socket
+            .send(&[0x01, 0x00, 0x00, 0x00, 0x09, 0x08, 0x00, 0x05, 0x11, 0x04])
+            .unwrap();
+        let mut buffer = [0u8; 16];
+        assert_eq!(requester.receive(&mut buffer), Ok(3));
+        assert_eq!(&buffer[..3], &[0x05, 0x11, 0x04]);
+    }
+}
@@ -4,7 +4,7 @@
 
 use codec::enum_builder;
 use codec::{Codec, Reader, Writer};
-use spdmlib::common::SpdmTransportEncap;
+use spdmlib::common::{SpdmAppMessageType, SpdmTransportEncap};
 use spdmlib::error::SpdmResult;
 use spdmlib::{spdm_err, spdm_result_err};
 
@@ -131,6 +131,51 @@ impl SpdmTransportEncap for MctpTransportEncap {
         Ok(payload_size)
     }
 
+    fn encap_app_message(
+        &mut self,
+        message_type: u8,
+        message: &[u8],
+        app_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        if message_type == MctpMessageType::MctpMessageTypeSpdm.get_u8()
+            || message_type == MctpMessageType::MctpMessageTypeSecuredMctp.get_u8()
+        {
+            return spdm_result_err!(EINVAL);
+        }
+        let payload_len = message.len();
+        let mut writer = Writer::init(&mut *app_buffer);
+        message_type.encode(&mut writer);
+        let header_size = writer.used();
+        if app_buffer.len() < header_size + payload_len {
+            return spdm_result_err!(EINVAL);
+        }
+        app_buffer[header_size..(header_size + payload_len)].copy_from_slice(message);
+        Ok(header_size + payload_len)
+    }
+
+    fn decap_app_message(
+        &mut self,
+        app_buffer: &[u8],
+        message: &mut [u8],
+    ) -> SpdmResult<(usize, SpdmAppMessageType)> {
+        let mut reader = Reader::init(app_buffer);
+        let message_type = match MctpMessageHeader::read(&mut reader) {
+            Some(mctp_header) => match mctp_header.r#type {
+                MctpMessageType::MctpMessageTypeSpdm => SpdmAppMessageType::Spdm,
+                MctpMessageType::MctpMessageTypeSecuredMctp => return spdm_result_err!(EINVAL),
+                r#type => SpdmAppMessageType::Other(r#type.get_u8()),
+            },
+            None => return spdm_result_err!(EIO),
+        };
+        let header_size = reader.used();
+        let payload_size = app_buffer.len() - header_size;
+        if message.len() < payload_size {
+            return spdm_result_err!(EINVAL);
+        }
+        message[..payload_size].copy_from_slice(&app_buffer[header_size..]);
+        Ok((payload_size, message_type))
+    }
+
     fn get_sequence_number_count(&mut self) -> u8 {
         2
     }
@@ -264,6 +309,42 @@ mod tests {
         assert!(status);
     }
     #[test]
+    fn test_case0_encap_decap_app_message() {
+        let mut mctp_transport_encap = MctpTransportEncap {};
+        let mut app_buffer = [0u8; 16];
+        let mut message = [0u8; 16];
+
+        let pldm = MctpMessageType::MctpMessageTypePldm.get_u8();
+        let used = mctp_transport_encap
+            .encap_app_message(pldm, &[0x80, 0x00, 0x02], &mut app_buffer)
+            .unwrap();
+        assert_eq!(&app_buffer[..used], &[0x01, 0x80, 0x00, 0x02]);
+        let (size, message_type) = mctp_transport_encap
+            .decap_app_message(&app_buffer[..used], &mut message)
+            .unwrap();
+        assert_eq!(message_type, SpdmAppMessageType::Other(pldm));
+        assert_eq!(&message[..size], &[0x80, 0x00, 0x02]);
+
+        let used = mctp_transport_encap
+            .encap_app(&[0x11, 0x84], &mut app_buffer)
+            .unwrap();
+        let (_, message_type) = mctp_transport_encap
+            .decap_app_message(&app_buffer[..used], &mut message)
+            .unwrap();
+        assert_eq!(message_type, SpdmAppMessageType::Spdm);
+
+        let spdm = MctpMessageType::MctpMessageTypeSpdm.get_u8();
+        assert!(mctp_transport_encap
+            .encap_app_message(spdm, &[0x11, 0x84], &mut app_buffer)
+            .is_err());
+        assert!(mctp_transport_encap
+            .decap_app_message(&[0x06, 0x00], &mut message)
+            .is_err());
+        assert!(mctp_transport_encap
+            .encap_app_message(pldm, &[0u8; 16], &mut app_buffer)
+            .is_err());
+    }
+    #[test]
     fn test_case0_get_sequence_number_count() {
         let mut mctp_transport_encap = MctpTransportEncap {};
         assert_eq!(mctp_transport_encap.get_sequence_number_count(), 2);
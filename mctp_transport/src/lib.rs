@@ -5,5 +5,14 @@
 #![forbid(unsafe_code)]
 #![no_std]
 
+#[macro_use]
+extern crate log;
+
 mod header;
 pub use header::*;
+
+mod packet;
+pub use packet::*;
+
+mod serial;
+pub use serial::*;
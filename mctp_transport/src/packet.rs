@@ -0,0 +1,553 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use codec::{Codec, Reader, Writer};
+use spdmlib::common::SpdmDeviceIo;
+use spdmlib::config;
+use spdmlib::error::SpdmResult;
+use spdmlib::{spdm_err, spdm_result_err};
+
+/// DSP0236: every MCTP medium shall support a 64 byte packet payload.
+pub const MCTP_BASELINE_TRANSMISSION_UNIT: usize = 64;
+
+pub const MCTP_TRANSPORT_HEADER_VERSION: u8 = 0x01;
+pub const MCTP_TRANSPORT_HEADER_SIZE: usize = 4;
+pub const MCTP_MAX_PACKET_SIZE: usize =
+    MCTP_TRANSPORT_HEADER_SIZE + MCTP_BASELINE_TRANSMISSION_UNIT;
+
+pub const MCTP_NULL_EID: u8 = 0x00;
+pub const MCTP_BROADCAST_EID: u8 = 0xFF;
+
+const MCTP_FLAG_SOM: u8 = 0x80;
+const MCTP_FLAG_EOM: u8 = 0x40;
+const MCTP_FLAG_TO: u8 = 0x08;
+const MCTP_PKT_SEQ_SHIFT: u8 = 4;
+const MCTP_PKT_SEQ_MASK: u8 = 0x03;
+const MCTP_MSG_TAG_MASK: u8 = 0x07;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct MctpTransportHeader {
+    pub dest_eid: u8,
+    pub source_eid: u8,
+    pub som: bool,
+    pub eom: bool,
+    pub pkt_seq: u8,
+    pub tag_owner: bool,
+    pub msg_tag: u8,
+}
+
+impl Codec for MctpTransportHeader {
+    fn encode(&self, bytes: &mut Writer) {
+        MCTP_TRANSPORT_HEADER_VERSION.encode(bytes);
+        self.dest_eid.encode(bytes);
+        self.source_eid.encode(bytes);
+        let mut flags = ((self.pkt_seq & MCTP_PKT_SEQ_MASK) << MCTP_PKT_SEQ_SHIFT)
+            | (self.msg_tag & MCTP_MSG_TAG_MASK);
+        if self.som {
+            flags |= MCTP_FLAG_SOM;
+        }
+        if self.eom {
+            flags |= MCTP_FLAG_EOM;
+        }
+        if self.tag_owner {
+            flags |= MCTP_FLAG_TO;
+        }
+        flags.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<MctpTransportHeader> {
+        let version = u8::read(r)?;
+        if version & 0x0F != MCTP_TRANSPORT_HEADER_VERSION {
+            return None;
+        }
+        let dest_eid = u8::read(r)?;
+        let source_eid = u8::read(r)?;
+        let flags = u8::read(r)?;
+        Some(MctpTransportHeader {
+            dest_eid,
+            source_eid,
+            som: flags & MCTP_FLAG_SOM != 0,
+            eom: flags & MCTP_FLAG_EOM != 0,
+            pkt_seq: (flags >> MCTP_PKT_SEQ_SHIFT) & MCTP_PKT_SEQ_MASK,
+            tag_owner: flags & MCTP_FLAG_TO != 0,
+            msg_tag: flags & MCTP_MSG_TAG_MASK,
+        })
+    }
+}
+
+/// Split one MCTP message (message type byte followed by the body, as
+/// produced by MctpTransportEncap) into MCTP packets of at most
+/// `transmission_unit` payload bytes.
+pub struct MctpFragmenter<'a> {
+    message: &'a [u8],
+    offset: usize,
+    transmission_unit: usize,
+    header: MctpTransportHeader,
+}
+
+impl<'a> MctpFragmenter<'a> {
+    pub fn new(
+        message: &'a [u8],
+        source_eid: u8,
+        dest_eid: u8,
+        tag_owner: bool,
+        msg_tag: u8,
+        transmission_unit: usize,
+    ) -> Self {
+        MctpFragmenter {
+            message,
+            offset: 0,
+            transmission_unit,
+            header: MctpTransportHeader {
+                dest_eid,
+                source_eid,
+                som: true,
+                eom: false,
+                pkt_seq: 0,
+                tag_owner,
+                msg_tag: msg_tag & MCTP_MSG_TAG_MASK,
+            },
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.offset != 0 && self.offset >= self.message.len()
+    }
+
+    /// Write the next packet into `packet_buffer` and return its size.
+    pub fn next_packet(&mut self, packet_buffer: &mut [u8]) -> SpdmResult<usize> {
+        if self.message.is_empty() || self.transmission_unit == 0 {
+            return spdm_result_err!(EINVAL);
+        }
+        if self.is_done() {
+            return spdm_result_err!(ENOENT);
+        }
+        let payload_size = core::cmp::min(self.message.len() - self.offset, self.transmission_unit);
+        if packet_buffer.len() < MCTP_TRANSPORT_HEADER_SIZE + payload_size {
+            return spdm_result_err!(EINVAL);
+        }
+
+        self.header.eom = self.offset + payload_size == self.message.len();
+        let mut writer = Writer::init(packet_buffer);
+        self.header.encode(&mut writer);
+        writer
+            .extend_from_slice(&self.message[self.offset..(self.offset + payload_size)])
+            .ok_or(spdm_err!(ENOMEM))?;
+
+        self.offset += payload_size;
+        self.header.som = false;
+        self.header.pkt_seq = (self.header.pkt_seq + 1) & MCTP_PKT_SEQ_MASK;
+        Ok(writer.used())
+    }
+}
+
+/// Collect MCTP packets addressed to `local_eid` back into one message.
+///
+/// A packet with SOM set always starts a new message, dropping any partial
+/// one. Every following packet must come from the same source with the same
+/// tag, carry the next packet sequence number, and (unless it is the last)
+/// the same payload size as the first packet.
+pub struct MctpReassembler {
+    local_eid: u8,
+    buffer: [u8; config::MAX_SPDM_TRANSPORT_SIZE],
+    used: usize,
+    in_progress: bool,
+    fragment_size: usize,
+    header: MctpTransportHeader,
+}
+
+impl MctpReassembler {
+    pub fn new(local_eid: u8) -> Self {
+        MctpReassembler {
+            local_eid,
+            buffer: [0u8; config::MAX_SPDM_TRANSPORT_SIZE],
+            used: 0,
+            in_progress: false,
+            fragment_size: 0,
+            header: MctpTransportHeader::default(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.used = 0;
+        self.in_progress = false;
+        self.fragment_size = 0;
+    }
+
+    /// Feed one packet.
+    ///
+    /// Return Ok(Some(size)) once the EOM packet completes a message, which is
+    /// then available via `message()`, or Ok(None) if more packets are needed.
+    /// Packets for another endpoint are ignored.
+    pub fn receive_packet(&mut self, packet: &[u8]) -> SpdmResult<Option<usize>> {
+        let mut reader = Reader::init(packet);
+        let header = MctpTransportHeader::read(&mut reader).ok_or(spdm_err!(EIO))?;
+        if header.dest_eid != self.local_eid
+            && header.dest_eid != MCTP_NULL_EID
+            && header.dest_eid != MCTP_BROADCAST_EID
+        {
+            return Ok(None);
+        }
+        let payload = reader.rest();
+        if payload.is_empty() {
+            self.reset();
+            return spdm_result_err!(EIO);
+        }
+
+        if header.som {
+            if self.in_progress {
+                info!("mctp: new SOM, drop partial message\n");
+            }
+            self.reset();
+            self.in_progress = true;
+            self.fragment_size = payload.len();
+            self.header = header;
+        } else {
+            if !self.in_progress {
+                error!("mctp: missing SOM\n");
+                return spdm_result_err!(EIO);
+            }
+            if header.source_eid != self.header.source_eid
+                || header.tag_owner != self.header.tag_owner
+                || header.msg_tag != self.header.msg_tag
+            {
+                error!("mctp: tag mismatch\n");
+                self.reset();
+                return spdm_result_err!(EIO);
+            }
+            if header.pkt_seq != (self.header.pkt_seq + 1) & MCTP_PKT_SEQ_MASK {
+                error!("mctp: out of order or missing packet\n");
+                self.reset();
+                return spdm_result_err!(EIO);
+            }
+            if payload.len() > self.fragment_size
+                || (!header.eom && payload.len() != self.fragment_size)
+            {
+                error!("mctp: unexpected packet size\n");
+                self.reset();
+                return spdm_result_err!(EIO);
+            }
+            self.header.pkt_seq = header.pkt_seq;
+        }
+
+        if self.used + payload.len() > self.buffer.len() {
+            error!("mctp: message too large\n");
+            self.reset();
+            return spdm_result_err!(ENOMEM);
+        }
+        self.buffer[self.used..(self.used + payload.len())].copy_from_slice(payload);
+        self.used += payload.len();
+
+        if header.eom {
+            self.in_progress = false;
+            Ok(Some(self.used))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The last complete message.
+    pub fn message(&self) -> &[u8] {
+        &self.buffer[..self.used]
+    }
+
+    /// The transport header of the first packet of the last message.
+    pub fn message_header(&self) -> &MctpTransportHeader {
+        &self.header
+    }
+}
+
+/// An SpdmDeviceIo that carries whole MCTP messages over a link which moves
+/// one MCTP packet per send/receive.
+///
+/// The requester owns the message tags: each request gets a new tag with TO
+/// set and only a response with TO clear and the same tag is accepted. The
+/// responder answers with TO clear and the tag of the last request.
+pub struct MctpPacketIo<'a> {
+    link: &'a mut (dyn SpdmDeviceIo + Send),
+    local_eid: u8,
+    remote_eid: u8,
+    transmission_unit: usize,
+    is_requester: bool,
+    msg_tag: u8,
+    reassembler: MctpReassembler,
+}
+
+impl<'a> MctpPacketIo<'a> {
+    pub fn new(
+        link: &'a mut (dyn SpdmDeviceIo + Send),
+        local_eid: u8,
+        remote_eid: u8,
+        is_requester: bool,
+    ) -> Self {
+        MctpPacketIo {
+            link,
+            local_eid,
+            remote_eid,
+            transmission_unit: MCTP_BASELINE_TRANSMISSION_UNIT,
+            is_requester,
+            msg_tag: 0,
+            reassembler: MctpReassembler::new(local_eid),
+        }
+    }
+
+    /// Use a transmission unit larger than the baseline, if the medium
+    /// supports it.
+    pub fn set_transmission_unit(&mut self, transmission_unit: usize) {
+        self.transmission_unit = transmission_unit;
+    }
+}
+
+impl<'a> SpdmDeviceIo for MctpPacketIo<'a> {
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        if self.is_requester {
+            self.msg_tag = (self.msg_tag + 1) & MCTP_MSG_TAG_MASK;
+        }
+        let mut fragmenter = MctpFragmenter::new(
+            buffer,
+            self.local_eid,
+            self.remote_eid,
+            self.is_requester,
+            self.msg_tag,
+            self.transmission_unit,
+        );
+        let mut packet = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        while !fragmenter.is_done() {
+            let used = fragmenter.next_packet(&mut packet)?;
+            self.link.send(&packet[..used])?;
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+        let mut packet = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        loop {
+            let used = self.link.receive(&mut packet)?;
+            let size = match self.reassembler.receive_packet(&packet[..used]) {
+                Ok(Some(size)) => size,
+                Ok(None) => continue,
+                Err(_) => return Err(0),
+            };
+
+            let header = *self.reassembler.message_header();
+            if self.is_requester {
+                if header.tag_owner || header.msg_tag != self.msg_tag {
+                    info!("mctp: drop response with stale tag\n");
+                    continue;
+                }
+            } else {
+                if !header.tag_owner {
+                    info!("mctp: drop message without tag owner\n");
+                    continue;
+                }
+                self.msg_tag = header.msg_tag;
+            }
+
+            if buffer.len() < size {
+                return Err(size);
+            }
+            buffer[..size].copy_from_slice(self.reassembler.message());
+            return Ok(size);
+        }
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        self.reassembler.reset();
+        self.link.flush_all()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_QUEUED_PACKETS: usize = 32;
+
+    struct FakeLink {
+        packets: [[u8; MCTP_MAX_PACKET_SIZE]; MAX_QUEUED_PACKETS],
+        sizes: [usize; MAX_QUEUED_PACKETS],
+        head: usize,
+        tail: usize,
+    }
+
+    impl FakeLink {
+        fn new() -> Self {
+            FakeLink {
+                packets: [[0u8; MCTP_MAX_PACKET_SIZE]; MAX_QUEUED_PACKETS],
+                sizes: [0usize; MAX_QUEUED_PACKETS],
+                head: 0,
+                tail: 0,
+            }
+        }
+    }
+
+    impl SpdmDeviceIo for FakeLink {
+        fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+            self.packets[self.tail][..buffer.len()].copy_from_slice(buffer);
+            self.sizes[self.tail] = buffer.len();
+            self.tail += 1;
+            Ok(())
+        }
+
+        fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+            if self.head == self.tail {
+                return Err(0);
+            }
+            let size = self.sizes[self.head];
+            buffer[..size].copy_from_slice(&self.packets[self.head][..size]);
+            self.head += 1;
+            Ok(size)
+        }
+
+        fn flush_all(&mut self) -> SpdmResult {
+            Ok(())
+        }
+    }
+
+    fn fragment(message: &[u8], packets: &mut [[u8; MCTP_MAX_PACKET_SIZE]]) -> usize {
+        let mut fragmenter = MctpFragmenter::new(message, 8, 9, true, 3, 64);
+        let mut count = 0;
+        while !fragmenter.is_done() {
+            fragmenter.next_packet(&mut packets[count]).unwrap();
+            count += 1;
+        }
+        count
+    }
+
+    #[test]
+    fn test_case0_mctp_transport_header() {
+        let u8_slice = &mut [0u8; 4];
+        let mut writer = Writer::init(u8_slice);
+        let value = MctpTransportHeader {
+            dest_eid: 0x09,
+            source_eid: 0x08,
+            som: true,
+            eom: false,
+            pkt_seq: 2,
+            tag_owner: true,
+            msg_tag: 5,
+        };
+        value.encode(&mut writer);
+        assert_eq!(u8_slice, &[0x01, 0x09, 0x08, 0xAD]);
+
+        let mut reader = Reader::init(u8_slice);
+        assert_eq!(MctpTransportHeader::read(&mut reader).unwrap(), value);
+
+        let mut reader = Reader::init(&[0x02, 0x09, 0x08, 0xAD]);
+        assert!(MctpTransportHeader::read(&mut reader).is_none());
+    }
+    #[test]
+    fn test_case0_fragment_and_reassemble() {
+        let mut message = [0u8; 200];
+        for (i, b) in message.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let mut fragmenter = MctpFragmenter::new(&message, 8, 9, true, 3, 64);
+        let mut reassembler = MctpReassembler::new(9);
+        let mut packet = [0u8; MCTP_MAX_PACKET_SIZE];
+        let mut count = 0u8;
+        let mut result = None;
+        while !fragmenter.is_done() {
+            let used = fragmenter.next_packet(&mut packet).unwrap();
+            let header = MctpTransportHeader::read_bytes(&packet[..used]).unwrap();
+            assert_eq!(header.som, count == 0);
+            assert_eq!(header.eom, fragmenter.is_done());
+            assert_eq!(header.pkt_seq, count & 0x3);
+            result = reassembler.receive_packet(&packet[..used]).unwrap();
+            count += 1;
+        }
+        assert_eq!(count, 4);
+        assert_eq!(result, Some(200));
+        assert_eq!(reassembler.message(), &message[..]);
+        assert!(reassembler.message_header().tag_owner);
+        assert_eq!(reassembler.message_header().msg_tag, 3);
+    }
+    #[test]
+    fn test_case0_reassemble_out_of_order() {
+        let message = [0x5au8; 200];
+        let mut packets = [[0u8; MCTP_MAX_PACKET_SIZE]; 4];
+        assert_eq!(fragment(&message, &mut packets), 4);
+
+        let mut reassembler = MctpReassembler::new(9);
+        assert_eq!(reassembler.receive_packet(&packets[0]).unwrap(), None);
+        assert!(reassembler.receive_packet(&packets[2]).is_err());
+        // the partial message is gone after the error
+        assert!(reassembler.receive_packet(&packets[3][..12]).is_err());
+    }
+    #[test]
+    fn test_case0_reassemble_missing_som() {
+        let message = [0x5au8; 100];
+        let mut packets = [[0u8; MCTP_MAX_PACKET_SIZE]; 2];
+        assert_eq!(fragment(&message, &mut packets[..]), 2);
+
+        let mut reassembler = MctpReassembler::new(9);
+        assert!(reassembler.receive_packet(&packets[1][..40]).is_err());
+
+        // a new SOM restarts reassembly
+        assert_eq!(reassembler.receive_packet(&packets[0]).unwrap(), None);
+        assert_eq!(reassembler.receive_packet(&packets[0]).unwrap(), None);
+        assert_eq!(
+            reassembler.receive_packet(&packets[1][..40]).unwrap(),
+            Some(100)
+        );
+    }
+    #[test]
+    fn test_case0_reassemble_tag_mismatch() {
+        let message = [0x5au8; 100];
+        let mut packets = [[0u8; MCTP_MAX_PACKET_SIZE]; 2];
+        assert_eq!(fragment(&message, &mut packets[..]), 2);
+        packets[1][3] = (packets[1][3] & !MCTP_MSG_TAG_MASK) | 4;
+
+        let mut reassembler = MctpReassembler::new(9);
+        assert_eq!(reassembler.receive_packet(&packets[0]).unwrap(), None);
+        assert!(reassembler.receive_packet(&packets[1][..40]).is_err());
+
+        // packets for other endpoints are ignored
+        let mut reassembler = MctpReassembler::new(10);
+        assert_eq!(reassembler.receive_packet(&packets[0]).unwrap(), None);
+    }
+    #[test]
+    fn test_case0_packet_io() {
+        let mut link = FakeLink::new();
+        let message = [0x11u8; 150];
+        let mut buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+
+        let tag = {
+            let mut requester = MctpPacketIo::new(&mut link, 8, 9, true);
+            requester.send(&message).unwrap();
+            requester.msg_tag
+        };
+        assert_eq!(link.tail, 3);
+
+        {
+            let mut responder = MctpPacketIo::new(&mut link, 9, 8, false);
+            assert_eq!(responder.receive(&mut buffer), Ok(150));
+            assert_eq!(&buffer[..150], &message[..]);
+            assert_eq!(responder.msg_tag, tag);
+            responder.send(&message[..20]).unwrap();
+        }
+
+        let mut requester = MctpPacketIo::new(&mut link, 8, 9, true);
+        requester.msg_tag = tag;
+        assert_eq!(requester.receive(&mut buffer), Ok(20));
+    }
+    #[test]
+    fn test_case0_packet_io_stale_tag() {
+        let mut link = FakeLink::new();
+        let mut buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+
+        {
+            let mut responder = MctpPacketIo::new(&mut link, 9, 8, false);
+            responder.msg_tag = 2;
+            responder.send(&[0x05, 0x11]).unwrap();
+            responder.msg_tag = 3;
+            responder.send(&[0x05, 0x12]).unwrap();
+        }
+
+        let mut requester = MctpPacketIo::new(&mut link, 8, 9, true);
+        requester.msg_tag = 3;
+        assert_eq!(requester.receive(&mut buffer), Ok(2));
+        assert_eq!(&buffer[..2], &[0x05, 0x12]);
+    }
+}
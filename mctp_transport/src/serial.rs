@@ -0,0 +1,431 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use spdmlib::common::SpdmDeviceIo;
+use spdmlib::error::SpdmResult;
+use spdmlib::{spdm_err, spdm_result_err};
+
+/// DSP0253 MCTP serial transport binding.
+///
+/// frame: flag(0x7E) revision byte_count packet[byte_count] fcs_high fcs_low flag(0x7E)
+///
+/// 0x7E and 0x7D inside the packet are sent as 0x7D followed by the byte
+/// XOR 0x20. The FCS is the RFC1662 FCS-16 over revision, byte_count and
+/// the unescaped packet.
+pub const MCTP_SERIAL_FRAME_FLAG: u8 = 0x7E;
+pub const MCTP_SERIAL_ESCAPE: u8 = 0x7D;
+pub const MCTP_SERIAL_ESCAPE_XOR: u8 = 0x20;
+pub const MCTP_SERIAL_REVISION: u8 = 0x01;
+pub const MCTP_SERIAL_FCS_INIT: u16 = 0xFFFF;
+
+pub const MCTP_SERIAL_MAX_PACKET_SIZE: usize = 0xFF;
+/// every packet byte escaped, plus flags, revision, byte count and FCS.
+pub const MCTP_SERIAL_MAX_FRAME_SIZE: usize = MCTP_SERIAL_MAX_PACKET_SIZE * 2 + 6;
+
+/// Accumulate `data` into the RFC1662 FCS-16 (reflected 0x1021).
+pub fn mctp_serial_fcs(fcs: u16, data: &[u8]) -> u16 {
+    let mut fcs = fcs;
+    for byte in data.iter() {
+        fcs ^= *byte as u16;
+        for _ in 0..8 {
+            if fcs & 1 != 0 {
+                fcs = (fcs >> 1) ^ 0x8408;
+            } else {
+                fcs >>= 1;
+            }
+        }
+    }
+    fcs
+}
+
+/// Wrap one MCTP packet into a serial frame.
+pub fn mctp_serial_encode_frame(packet: &[u8], frame: &mut [u8]) -> SpdmResult<usize> {
+    if packet.is_empty() || packet.len() > MCTP_SERIAL_MAX_PACKET_SIZE {
+        return spdm_result_err!(EINVAL);
+    }
+    let header = [MCTP_SERIAL_REVISION, packet.len() as u8];
+    let fcs = !mctp_serial_fcs(mctp_serial_fcs(MCTP_SERIAL_FCS_INIT, &header), packet);
+
+    let mut used = 0;
+    let mut push = |byte: u8| -> SpdmResult {
+        *frame.get_mut(used).ok_or(spdm_err!(EINVAL))? = byte;
+        used += 1;
+        Ok(())
+    };
+    push(MCTP_SERIAL_FRAME_FLAG)?;
+    push(header[0])?;
+    push(header[1])?;
+    for byte in packet.iter() {
+        if *byte == MCTP_SERIAL_FRAME_FLAG || *byte == MCTP_SERIAL_ESCAPE {
+            push(MCTP_SERIAL_ESCAPE)?;
+            push(*byte ^ MCTP_SERIAL_ESCAPE_XOR)?;
+        } else {
+            push(*byte)?;
+        }
+    }
+    push((fcs >> 8) as u8)?;
+    push((fcs & 0xFF) as u8)?;
+    push(MCTP_SERIAL_FRAME_FLAG)?;
+    Ok(used)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum MctpSerialDecodeState {
+    WaitStart,
+    Revision,
+    ByteCount,
+    Data,
+    DataEscaped,
+    FcsHigh,
+    FcsLow,
+    End,
+}
+
+/// Byte-at-a-time receiver for serial frames.
+pub struct MctpSerialDecoder {
+    state: MctpSerialDecodeState,
+    packet: [u8; MCTP_SERIAL_MAX_PACKET_SIZE],
+    byte_count: usize,
+    used: usize,
+    fcs: u16,
+}
+
+impl Default for MctpSerialDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MctpSerialDecoder {
+    pub fn new() -> Self {
+        MctpSerialDecoder {
+            state: MctpSerialDecodeState::WaitStart,
+            packet: [0u8; MCTP_SERIAL_MAX_PACKET_SIZE],
+            byte_count: 0,
+            used: 0,
+            fcs: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.state = MctpSerialDecodeState::WaitStart;
+        self.used = 0;
+    }
+
+    /// Feed one received byte.
+    ///
+    /// Return Ok(Some(size)) when a frame with a good FCS is complete, and
+    /// the packet is available via `packet()`. A malformed frame is reported
+    /// once as an error and then skipped.
+    pub fn push_byte(&mut self, byte: u8) -> SpdmResult<Option<usize>> {
+        match self.state {
+            MctpSerialDecodeState::WaitStart => {
+                if byte == MCTP_SERIAL_FRAME_FLAG {
+                    self.state = MctpSerialDecodeState::Revision;
+                }
+            }
+            MctpSerialDecodeState::Revision => {
+                // back to back frames may or may not share a flag
+                if byte != MCTP_SERIAL_FRAME_FLAG {
+                    if byte != MCTP_SERIAL_REVISION {
+                        return self.fail();
+                    }
+                    self.state = MctpSerialDecodeState::ByteCount;
+                }
+            }
+            MctpSerialDecodeState::ByteCount => {
+                if byte == 0 {
+                    return self.fail();
+                }
+                self.byte_count = byte as usize;
+                self.used = 0;
+                self.state = MctpSerialDecodeState::Data;
+            }
+            MctpSerialDecodeState::Data => match byte {
+                MCTP_SERIAL_FRAME_FLAG => {
+                    error!("mctp serial: truncated frame\n");
+                    self.used = 0;
+                    self.state = MctpSerialDecodeState::Revision;
+                    return spdm_result_err!(EIO);
+                }
+                MCTP_SERIAL_ESCAPE => self.state = MctpSerialDecodeState::DataEscaped,
+                _ => self.store(byte),
+            },
+            MctpSerialDecodeState::DataEscaped => {
+                if byte == MCTP_SERIAL_FRAME_FLAG {
+                    return self.fail();
+                }
+                self.state = MctpSerialDecodeState::Data;
+                self.store(byte ^ MCTP_SERIAL_ESCAPE_XOR);
+            }
+            MctpSerialDecodeState::FcsHigh => {
+                self.fcs = (byte as u16) << 8;
+                self.state = MctpSerialDecodeState::FcsLow;
+            }
+            MctpSerialDecodeState::FcsLow => {
+                self.fcs |= byte as u16;
+                self.state = MctpSerialDecodeState::End;
+            }
+            MctpSerialDecodeState::End => {
+                if byte != MCTP_SERIAL_FRAME_FLAG {
+                    return self.fail();
+                }
+                self.state = MctpSerialDecodeState::Revision;
+                let header = [MCTP_SERIAL_REVISION, self.byte_count as u8];
+                let fcs = !mctp_serial_fcs(
+                    mctp_serial_fcs(MCTP_SERIAL_FCS_INIT, &header),
+                    &self.packet[..self.used],
+                );
+                if fcs != self.fcs {
+                    error!("mctp serial: FCS mismatch\n");
+                    return spdm_result_err!(EIO);
+                }
+                return Ok(Some(self.used));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The packet of the last good frame.
+    pub fn packet(&self) -> &[u8] {
+        &self.packet[..self.used]
+    }
+
+    fn store(&mut self, byte: u8) {
+        self.packet[self.used] = byte;
+        self.used += 1;
+        if self.used == self.byte_count {
+            self.state = MctpSerialDecodeState::FcsHigh;
+        }
+    }
+
+    fn fail(&mut self) -> SpdmResult<Option<usize>> {
+        error!("mctp serial: malformed frame\n");
+        self.reset();
+        spdm_result_err!(EIO)
+    }
+}
+
+/// A byte stream such as a UART.
+pub trait MctpSerialPort {
+    fn write(&mut self, bytes: &[u8]) -> SpdmResult;
+
+    /// Block until at least one byte is read. 0 means the port is closed.
+    fn read(&mut self, bytes: &mut [u8]) -> SpdmResult<usize>;
+}
+
+/// Moves one MCTP packet per send/receive over a serial port.
+///
+/// It is the link under MctpPacketIo, which does the MCTP packetization.
+/// Frames with a bad FCS are dropped.
+pub struct MctpSerialLink<'a> {
+    port: &'a mut (dyn MctpSerialPort + Send),
+    decoder: MctpSerialDecoder,
+    rx_buffer: [u8; MCTP_SERIAL_MAX_FRAME_SIZE],
+    rx_used: usize,
+    rx_offset: usize,
+}
+
+impl<'a> MctpSerialLink<'a> {
+    pub fn new(port: &'a mut (dyn MctpSerialPort + Send)) -> Self {
+        MctpSerialLink {
+            port,
+            decoder: MctpSerialDecoder::new(),
+            rx_buffer: [0u8; MCTP_SERIAL_MAX_FRAME_SIZE],
+            rx_used: 0,
+            rx_offset: 0,
+        }
+    }
+}
+
+impl<'a> SpdmDeviceIo for MctpSerialLink<'a> {
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        let mut frame = [0u8; MCTP_SERIAL_MAX_FRAME_SIZE];
+        let used = mctp_serial_encode_frame(buffer, &mut frame)?;
+        self.port.write(&frame[..used])
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+        loop {
+            if self.rx_offset == self.rx_used {
+                self.rx_offset = 0;
+                self.rx_used = self.port.read(&mut self.rx_buffer).map_err(|_| 0usize)?;
+                if self.rx_used == 0 {
+                    return Err(0);
+                }
+            }
+            let byte = self.rx_buffer[self.rx_offset];
+            self.rx_offset += 1;
+
+            if let Ok(Some(size)) = self.decoder.push_byte(byte) {
+                if buffer.len() < size {
+                    return Err(size);
+                }
+                buffer[..size].copy_from_slice(self.decoder.packet());
+                return Ok(size);
+            }
+        }
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        self.decoder.reset();
+        self.rx_used = 0;
+        self.rx_offset = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MctpPacketIo, MctpTransportEncap};
+    use spdmlib::common::SpdmTransportEncap;
+    use spdmlib::config;
+
+    struct FakeSerialPort {
+        data: [u8; 4096],
+        written: usize,
+        read: usize,
+        chunk: usize,
+    }
+
+    impl FakeSerialPort {
+        fn new(chunk: usize) -> Self {
+            FakeSerialPort {
+                data: [0u8; 4096],
+                written: 0,
+                read: 0,
+                chunk,
+            }
+        }
+    }
+
+    impl MctpSerialPort for FakeSerialPort {
+        fn write(&mut self, bytes: &[u8]) -> SpdmResult {
+            self.data[self.written..(self.written + bytes.len())].copy_from_slice(bytes);
+            self.written += bytes.len();
+            Ok(())
+        }
+
+        fn read(&mut self, bytes: &mut [u8]) -> SpdmResult<usize> {
+            let size = core::cmp::min(
+                core::cmp::min(self.chunk, bytes.len()),
+                self.written - self.read,
+            );
+            bytes[..size].copy_from_slice(&self.data[self.read..(self.read + size)]);
+            self.read += size;
+            Ok(size)
+        }
+    }
+
+    #[test]
+    fn test_case0_mctp_serial_fcs() {
+        assert_eq!(!mctp_serial_fcs(MCTP_SERIAL_FCS_INIT, b"123456789"), 0x906E);
+    }
+    #[test]
+    fn test_case0_encode_decode_frame() {
+        let packet = [0x01, 0x09, 0x08, 0xC8, 0x05, 0x7E, 0x10, 0x7D, 0x84];
+        let mut frame = [0u8; MCTP_SERIAL_MAX_FRAME_SIZE];
+        let used = mctp_serial_encode_frame(&packet, &mut frame).unwrap();
+        assert_eq!(used, packet.len() + 2 + 6);
+        assert_eq!(&frame[..3], &[0x7E, 0x01, 0x09]);
+        assert_eq!(&frame[8..10], &[0x7D, 0x5E]);
+        assert_eq!(&frame[11..13], &[0x7D, 0x5D]);
+        assert_eq!(frame[used - 1], 0x7E);
+        assert!(frame[1..(used - 1)]
+            .iter()
+            .all(|byte| *byte != MCTP_SERIAL_FRAME_FLAG));
+
+        let mut decoder = MctpSerialDecoder::new();
+        for byte in frame[..(used - 1)].iter() {
+            assert_eq!(decoder.push_byte(*byte).unwrap(), None);
+        }
+        assert_eq!(decoder.push_byte(frame[used - 1]).unwrap(), Some(9));
+        assert_eq!(decoder.packet(), &packet[..]);
+
+        // the next frame reuses the closing flag as its opening flag
+        for byte in frame[1..(used - 1)].iter() {
+            assert_eq!(decoder.push_byte(*byte).unwrap(), None);
+        }
+        assert_eq!(decoder.push_byte(frame[used - 1]).unwrap(), Some(9));
+    }
+    #[test]
+    fn test_case0_decode_bad_frame() {
+        let packet = [0x01, 0x09, 0x08, 0xC8, 0x05, 0x10];
+        let mut frame = [0u8; MCTP_SERIAL_MAX_FRAME_SIZE];
+        let used = mctp_serial_encode_frame(&packet, &mut frame).unwrap();
+
+        let mut corrupted = frame;
+        corrupted[5] ^= 0x01;
+        let mut decoder = MctpSerialDecoder::new();
+        for byte in corrupted[..(used - 1)].iter() {
+            assert_eq!(decoder.push_byte(*byte).unwrap(), None);
+        }
+        assert!(decoder.push_byte(corrupted[used - 1]).is_err());
+
+        // a flag in the middle of the data aborts the frame
+        let mut decoder = MctpSerialDecoder::new();
+        for byte in frame[..6].iter() {
+            assert_eq!(decoder.push_byte(*byte).unwrap(), None);
+        }
+        assert!(decoder.push_byte(MCTP_SERIAL_FRAME_FLAG).is_err());
+        for byte in frame[1..used].iter() {
+            if let Some(size) = decoder.push_byte(*byte).unwrap() {
+                assert_eq!(size, packet.len());
+            }
+        }
+        assert_eq!(decoder.packet(), &packet[..]);
+
+        let mut decoder = MctpSerialDecoder::new();
+        assert_eq!(decoder.push_byte(MCTP_SERIAL_FRAME_FLAG).unwrap(), None);
+        assert!(decoder.push_byte(0x02).is_err());
+
+        assert!(mctp_serial_encode_frame(&[0u8; 256], &mut frame).is_err());
+        assert!(mctp_serial_encode_frame(&packet, &mut frame[..10]).is_err());
+    }
+    #[test]
+    fn test_case0_serial_link() {
+        let mut port = FakeSerialPort::new(7);
+        let mut link = MctpSerialLink::new(&mut port);
+        let mut buffer = [0u8; 64];
+
+        link.send(&[0x01, 0x09, 0x08, 0xC8, 0x7E]).unwrap();
+        link.send(&[0x01, 0x09, 0x08, 0xC8, 0x7D, 0x00]).unwrap();
+        assert_eq!(link.receive(&mut buffer), Ok(5));
+        assert_eq!(&buffer[..5], &[0x01, 0x09, 0x08, 0xC8, 0x7E]);
+        assert_eq!(link.receive(&mut buffer), Ok(6));
+        assert_eq!(link.receive(&mut buffer), Err(0));
+    }
+    #[test]
+    fn test_case0_serial_mctp_transport() {
+        let mut port = FakeSerialPort::new(16);
+        let mut mctp_transport_encap = MctpTransportEncap {};
+        let mut spdm_buffer = [0u8; 200];
+        for (i, b) in spdm_buffer.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let used = mctp_transport_encap
+            .encap(&spdm_buffer, &mut transport_buffer, false)
+            .unwrap();
+
+        {
+            let mut link = MctpSerialLink::new(&mut port);
+            let mut requester = MctpPacketIo::new(&mut link, 8, 9, true);
+            requester.send(&transport_buffer[..used]).unwrap();
+        }
+
+        let mut link = MctpSerialLink::new(&mut port);
+        let mut responder = MctpPacketIo::new(&mut link, 9, 8, false);
+        let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        assert_eq!(responder.receive(&mut receive_buffer), Ok(used));
+
+        let mut received = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let (size, secured_message) = mctp_transport_encap
+            .decap(&receive_buffer[..used], &mut received)
+            .unwrap();
+        assert!(!secured_message);
+        assert_eq!(&received[..size], &spdm_buffer[..]);
+    }
+}
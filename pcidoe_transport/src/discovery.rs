@@ -0,0 +1,281 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::header::*;
+use crate::mailbox::PciDoeMailbox;
+use codec::{Codec, Reader, Writer};
+use spdmlib::error::SpdmResult;
+use spdmlib::{spdm_err, spdm_result_err};
+
+pub const PCI_DOE_MAX_PROTOCOL_COUNT: usize = 16;
+
+const PCI_DOE_DISCOVERY_OBJECT_SIZE: usize = 12;
+
+/// DOE discovery request, one DW after the DOE header.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct PciDoeDiscoveryRequest {
+    pub index: u8,
+}
+
+impl Codec for PciDoeDiscoveryRequest {
+    fn encode(&self, bytes: &mut Writer) {
+        self.index.encode(bytes);
+        0u8.encode(bytes); // reserved
+        0u16.encode(bytes); // reserved
+    }
+
+    fn read(r: &mut Reader) -> Option<PciDoeDiscoveryRequest> {
+        let index = u8::read(r)?;
+        u8::read(r)?;
+        u16::read(r)?;
+        Some(PciDoeDiscoveryRequest { index })
+    }
+}
+
+/// DOE discovery response, one DW after the DOE header.
+/// next_index 0 means this is the last protocol.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct PciDoeDiscoveryResponse {
+    pub vendor_id: PciDoeVendorId,
+    pub data_object_type: PciDoeDataObjectType,
+    pub next_index: u8,
+}
+
+impl Codec for PciDoeDiscoveryResponse {
+    fn encode(&self, bytes: &mut Writer) {
+        self.vendor_id.encode(bytes);
+        self.data_object_type.encode(bytes);
+        self.next_index.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<PciDoeDiscoveryResponse> {
+        let vendor_id = PciDoeVendorId::read(r)?;
+        let data_object_type = PciDoeDataObjectType::read(r)?;
+        let next_index = u8::read(r)?;
+        Some(PciDoeDiscoveryResponse {
+            vendor_id,
+            data_object_type,
+            next_index,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct PciDoeProtocol {
+    pub vendor_id: PciDoeVendorId,
+    pub data_object_type: PciDoeDataObjectType,
+}
+
+/// The protocols reported by a mailbox, in discovery index order.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct PciDoeProtocolList {
+    pub protocols: [PciDoeProtocol; PCI_DOE_MAX_PROTOCOL_COUNT],
+    pub count: usize,
+}
+
+impl PciDoeProtocolList {
+    pub fn contains(
+        &self,
+        vendor_id: PciDoeVendorId,
+        data_object_type: PciDoeDataObjectType,
+    ) -> bool {
+        self.protocols[..self.count].iter().any(|protocol| {
+            protocol.vendor_id == vendor_id && protocol.data_object_type == data_object_type
+        })
+    }
+
+    pub fn supports_spdm(&self) -> bool {
+        self.contains(
+            PciDoeVendorId::PciDoeVendorIdPciSig,
+            PciDoeDataObjectType::PciDoeDataObjectTypeSpdm,
+        )
+    }
+
+    pub fn supports_secured_spdm(&self) -> bool {
+        self.contains(
+            PciDoeVendorId::PciDoeVendorIdPciSig,
+            PciDoeDataObjectType::PciDoeDataObjectTypeSecuredSpdm,
+        )
+    }
+}
+
+fn pci_doe_discover_index(
+    mailbox: &mut dyn PciDoeMailbox,
+    index: u8,
+) -> SpdmResult<PciDoeDiscoveryResponse> {
+    let mut request = [0u8; PCI_DOE_DISCOVERY_OBJECT_SIZE];
+    let mut writer = Writer::init(&mut request);
+    PciDoeMessageHeader {
+        vendor_id: PciDoeVendorId::PciDoeVendorIdPciSig,
+        data_object_type: PciDoeDataObjectType::PciDoeDataObjectTypeDoeDiscovery,
+        payload_length: 4,
+    }
+    .encode(&mut writer);
+    PciDoeDiscoveryRequest { index }.encode(&mut writer);
+    mailbox.write_data_object(&request)?;
+
+    let mut response = [0u8; PCI_DOE_DISCOVERY_OBJECT_SIZE];
+    let used = mailbox.read_data_object(&mut response)?;
+    let mut reader = Reader::init(&response[..used]);
+    let header = PciDoeMessageHeader::read(&mut reader).ok_or(spdm_err!(EIO))?;
+    if header.vendor_id != PciDoeVendorId::PciDoeVendorIdPciSig
+        || header.data_object_type != PciDoeDataObjectType::PciDoeDataObjectTypeDoeDiscovery
+        || header.payload_length != 4
+    {
+        return spdm_result_err!(EIO);
+    }
+    PciDoeDiscoveryResponse::read(&mut reader).ok_or(spdm_err!(EIO))
+}
+
+/// Walk the DOE discovery table of a mailbox.
+pub fn pci_doe_discover(mailbox: &mut dyn PciDoeMailbox) -> SpdmResult<PciDoeProtocolList> {
+    let mut list = PciDoeProtocolList::default();
+    let mut index = 0u8;
+    loop {
+        let response = pci_doe_discover_index(mailbox, index)?;
+        if list.count == PCI_DOE_MAX_PROTOCOL_COUNT {
+            return spdm_result_err!(ENOMEM);
+        }
+        list.protocols[list.count] = PciDoeProtocol {
+            vendor_id: response.vendor_id,
+            data_object_type: response.data_object_type,
+        };
+        list.count += 1;
+
+        if response.next_index == 0 {
+            return Ok(list);
+        }
+        // the table is walked forward only, which also stops a looping device
+        if response.next_index <= index {
+            return spdm_result_err!(EIO);
+        }
+        index = response.next_index;
+    }
+}
+
+impl PciDoeProbedTransportEncap {
+    /// Discover the protocols of `mailbox` and return a transport for it.
+    ///
+    /// Fail with ENODEV if the mailbox does not support CMA/SPDM.
+    pub fn probe(mailbox: &mut dyn PciDoeMailbox) -> SpdmResult<PciDoeProbedTransportEncap> {
+        let protocols = pci_doe_discover(mailbox)?;
+        if !protocols.supports_spdm() {
+            return spdm_result_err!(ENODEV);
+        }
+        Ok(PciDoeProbedTransportEncap::new(protocols))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mailbox::PciDoeSimulatedMailbox;
+    use spdmlib::common::SpdmTransportEncap;
+
+    const SPDM: PciDoeProtocol = PciDoeProtocol {
+        vendor_id: PciDoeVendorId::PciDoeVendorIdPciSig,
+        data_object_type: PciDoeDataObjectType::PciDoeDataObjectTypeSpdm,
+    };
+    const SECURED_SPDM: PciDoeProtocol = PciDoeProtocol {
+        vendor_id: PciDoeVendorId::PciDoeVendorIdPciSig,
+        data_object_type: PciDoeDataObjectType::PciDoeDataObjectTypeSecuredSpdm,
+    };
+    const VENDOR: PciDoeProtocol = PciDoeProtocol {
+        vendor_id: PciDoeVendorId::Unknown(0x8086),
+        data_object_type: PciDoeDataObjectType::PciDoeDataObjectTypeSpdm,
+    };
+
+    #[test]
+    fn test_case0_discovery_codec() {
+        let u8_slice = &mut [0u8; 4];
+        let mut writer = Writer::init(u8_slice);
+        PciDoeDiscoveryResponse {
+            vendor_id: PciDoeVendorId::PciDoeVendorIdPciSig,
+            data_object_type: PciDoeDataObjectType::PciDoeDataObjectTypeSecuredSpdm,
+            next_index: 3,
+        }
+        .encode(&mut writer);
+        assert_eq!(u8_slice, &[0x01, 0x00, 0x02, 0x03]);
+
+        let mut reader = Reader::init(u8_slice);
+        let response = PciDoeDiscoveryResponse::read(&mut reader).unwrap();
+        assert_eq!(response.next_index, 3);
+        assert_eq!(
+            response.data_object_type,
+            PciDoeDataObjectType::PciDoeDataObjectTypeSecuredSpdm
+        );
+
+        let mut writer = Writer::init(u8_slice);
+        PciDoeDiscoveryRequest { index: 2 }.encode(&mut writer);
+        assert_eq!(u8_slice, &[0x02, 0x00, 0x00, 0x00]);
+        let mut reader = Reader::init(&u8_slice[..3]);
+        assert!(PciDoeDiscoveryRequest::read(&mut reader).is_none());
+    }
+    #[test]
+    fn test_case0_pci_doe_discover() {
+        let mut mailbox = PciDoeSimulatedMailbox::new(&[VENDOR, SPDM, SECURED_SPDM]);
+        let list = pci_doe_discover(&mut mailbox).unwrap();
+        // the discovery protocol itself is index 0
+        assert_eq!(list.count, 4);
+        assert_eq!(
+            list.protocols[0].data_object_type,
+            PciDoeDataObjectType::PciDoeDataObjectTypeDoeDiscovery
+        );
+        assert_eq!(list.protocols[1], VENDOR);
+        assert!(list.supports_spdm());
+        assert!(list.supports_secured_spdm());
+
+        assert!(PciDoeProbedTransportEncap::probe(&mut mailbox).is_ok());
+    }
+    #[test]
+    fn test_case1_pci_doe_discover() {
+        let mut mailbox = PciDoeSimulatedMailbox::new(&[VENDOR]);
+        let list = pci_doe_discover(&mut mailbox).unwrap();
+        assert_eq!(list.count, 2);
+        assert!(!list.supports_spdm());
+        assert!(PciDoeProbedTransportEncap::probe(&mut mailbox).is_err());
+
+        let mut mailbox = PciDoeSimulatedMailbox::new(&[]);
+        assert!(PciDoeProbedTransportEncap::probe(&mut mailbox).is_err());
+
+        let mut mailbox = PciDoeSimulatedMailbox::new(&[SPDM; PCI_DOE_MAX_PROTOCOL_COUNT]);
+        assert!(pci_doe_discover(&mut mailbox).is_err());
+    }
+    #[test]
+    fn test_case0_probe() {
+        let spdm = &[0x10u8, 0x84, 0x00, 0x00];
+        let transport_buffer = &mut [0u8; 64];
+        let spdm_buffer = &mut [0u8; 64];
+
+        let mut mailbox = PciDoeSimulatedMailbox::new(&[SPDM, SECURED_SPDM]);
+        let mut transport = PciDoeProbedTransportEncap::probe(&mut mailbox).unwrap();
+        assert_eq!(transport.protocols().count, 3);
+        let used = transport.encap(spdm, transport_buffer, true).unwrap();
+        assert_eq!(
+            transport
+                .decap(&transport_buffer[..used], spdm_buffer)
+                .unwrap(),
+            (4, true)
+        );
+
+        // no secured CMA/SPDM
+        let mut mailbox = PciDoeSimulatedMailbox::new(&[SPDM]);
+        let mut transport = PciDoeProbedTransportEncap::probe(&mut mailbox).unwrap();
+        assert!(transport.protocols().supports_spdm());
+        let used = transport.encap(spdm, transport_buffer, false).unwrap();
+        assert_eq!(
+            transport
+                .decap(&transport_buffer[..used], spdm_buffer)
+                .unwrap(),
+            (4, false)
+        );
+        assert!(transport.encap(spdm, transport_buffer, true).is_err());
+        let used = PciDoeTransportEncap {}
+            .encap(spdm, transport_buffer, true)
+            .unwrap();
+        assert!(transport
+            .decap(&transport_buffer[..used], spdm_buffer)
+            .is_err());
+    }
+}
@@ -2,6 +2,7 @@
 //
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
+use crate::discovery::PciDoeProtocolList;
 use codec::enum_builder;
 use codec::{Codec, Reader, Writer};
 use spdmlib::common::SpdmTransportEncap;
@@ -149,6 +150,69 @@ impl SpdmTransportEncap for PciDoeTransportEncap {
     }
 }
 
+/// A PciDoeTransportEncap for a mailbox whose protocols are known, e.g.
+/// from `probe`. Secured SPDM data objects are only sent and accepted if
+/// the mailbox lists secured CMA/SPDM, otherwise it fails with ENODEV.
+#[derive(Debug, Copy, Clone)]
+pub struct PciDoeProbedTransportEncap {
+    protocols: PciDoeProtocolList,
+}
+
+impl PciDoeProbedTransportEncap {
+    pub fn new(protocols: PciDoeProtocolList) -> Self {
+        PciDoeProbedTransportEncap { protocols }
+    }
+
+    pub fn protocols(&self) -> &PciDoeProtocolList {
+        &self.protocols
+    }
+
+    fn check_protocol(&self, secured_message: bool) -> SpdmResult {
+        if secured_message && !self.protocols.supports_secured_spdm() {
+            return spdm_result_err!(ENODEV);
+        }
+        Ok(())
+    }
+}
+
+impl SpdmTransportEncap for PciDoeProbedTransportEncap {
+    fn encap(
+        &mut self,
+        spdm_buffer: &[u8],
+        transport_buffer: &mut [u8],
+        secured_message: bool,
+    ) -> SpdmResult<usize> {
+        self.check_protocol(secured_message)?;
+        PciDoeTransportEncap {}.encap(spdm_buffer, transport_buffer, secured_message)
+    }
+
+    fn decap(
+        &mut self,
+        transport_buffer: &[u8],
+        spdm_buffer: &mut [u8],
+    ) -> SpdmResult<(usize, bool)> {
+        let (used, secured_message) =
+            PciDoeTransportEncap {}.decap(transport_buffer, spdm_buffer)?;
+        self.check_protocol(secured_message)?;
+        Ok((used, secured_message))
+    }
+
+    fn encap_app(&mut self, spdm_buffer: &[u8], app_buffer: &mut [u8]) -> SpdmResult<usize> {
+        PciDoeTransportEncap {}.encap_app(spdm_buffer, app_buffer)
+    }
+
+    fn decap_app(&mut self, app_buffer: &[u8], spdm_buffer: &mut [u8]) -> SpdmResult<usize> {
+        PciDoeTransportEncap {}.decap_app(app_buffer, spdm_buffer)
+    }
+
+    fn get_sequence_number_count(&mut self) -> u8 {
+        PciDoeTransportEncap {}.get_sequence_number_count()
+    }
+    fn get_max_random_count(&mut self) -> u16 {
+        PciDoeTransportEncap {}.get_max_random_count()
+    }
+}
+
 #[cfg(test)]
 mod tests_header {
     use super::*;
@@ -7,3 +7,12 @@
 
 mod header;
 pub use header::*;
+
+mod discovery;
+pub use discovery::*;
+
+mod mailbox;
+pub use mailbox::*;
+
+mod mailbox_model;
+pub use mailbox_model::*;
@@ -0,0 +1,177 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::discovery::*;
+use crate::header::*;
+use codec::{Codec, Reader, Writer};
+use spdmlib::common::SpdmDeviceIo;
+use spdmlib::config;
+use spdmlib::error::SpdmResult;
+use spdmlib::{spdm_err, spdm_result_err};
+
+/// A DOE mailbox of a PCIe function.
+///
+/// Data objects are passed whole, DOE header included.
+pub trait PciDoeMailbox {
+    fn write_data_object(&mut self, data_object: &[u8]) -> SpdmResult;
+
+    fn read_data_object(&mut self, data_object: &mut [u8]) -> SpdmResult<usize>;
+}
+
+/// SpdmDeviceIo over a DOE mailbox, for use with PciDoeTransportEncap.
+pub struct PciDoeMailboxIo<'a> {
+    pub mailbox: &'a mut dyn PciDoeMailbox,
+}
+
+impl<'a> PciDoeMailboxIo<'a> {
+    pub fn new(mailbox: &'a mut dyn PciDoeMailbox) -> Self {
+        PciDoeMailboxIo { mailbox }
+    }
+}
+
+impl SpdmDeviceIo for PciDoeMailboxIo<'_> {
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        self.mailbox.write_data_object(buffer)
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+        self.mailbox.read_data_object(buffer).map_err(|_| 0usize)
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        Ok(())
+    }
+}
+
+// the discovery index is one byte
+const SIMULATED_MAILBOX_MAX_PROTOCOL_COUNT: usize = 0x100;
+
+/// A software mailbox for tests.
+///
+/// Index 0 of the discovery table is DOE discovery itself, followed by
+/// `protocols`. A data object of any other supported protocol is looped
+/// back as the response, an unsupported one is rejected.
+pub struct PciDoeSimulatedMailbox {
+    pub protocols: [PciDoeProtocol; SIMULATED_MAILBOX_MAX_PROTOCOL_COUNT],
+    pub protocol_count: usize,
+    response: [u8; config::MAX_SPDM_TRANSPORT_SIZE],
+    response_size: usize,
+}
+
+impl PciDoeSimulatedMailbox {
+    pub fn new(protocols: &[PciDoeProtocol]) -> Self {
+        let mut mailbox = PciDoeSimulatedMailbox {
+            protocols: [PciDoeProtocol::default(); SIMULATED_MAILBOX_MAX_PROTOCOL_COUNT],
+            protocol_count: protocols.len() + 1,
+            response: [0u8; config::MAX_SPDM_TRANSPORT_SIZE],
+            response_size: 0,
+        };
+        mailbox.protocols[0] = PciDoeProtocol {
+            vendor_id: PciDoeVendorId::PciDoeVendorIdPciSig,
+            data_object_type: PciDoeDataObjectType::PciDoeDataObjectTypeDoeDiscovery,
+        };
+        mailbox.protocols[1..(protocols.len() + 1)].copy_from_slice(protocols);
+        mailbox
+    }
+
+    fn discovery_response(&mut self, index: u8) -> SpdmResult {
+        let index = index as usize;
+        if index >= self.protocol_count {
+            return spdm_result_err!(EINVAL);
+        }
+        let next_index = if index + 1 == self.protocol_count {
+            0
+        } else {
+            index + 1
+        };
+        let mut writer = Writer::init(&mut self.response);
+        PciDoeMessageHeader {
+            vendor_id: PciDoeVendorId::PciDoeVendorIdPciSig,
+            data_object_type: PciDoeDataObjectType::PciDoeDataObjectTypeDoeDiscovery,
+            payload_length: 4,
+        }
+        .encode(&mut writer);
+        PciDoeDiscoveryResponse {
+            vendor_id: self.protocols[index].vendor_id,
+            data_object_type: self.protocols[index].data_object_type,
+            next_index: next_index as u8,
+        }
+        .encode(&mut writer);
+        self.response_size = writer.used();
+        Ok(())
+    }
+}
+
+impl PciDoeMailbox for PciDoeSimulatedMailbox {
+    fn write_data_object(&mut self, data_object: &[u8]) -> SpdmResult {
+        self.response_size = 0;
+        let mut reader = Reader::init(data_object);
+        let header = PciDoeMessageHeader::read(&mut reader).ok_or(spdm_err!(EINVAL))?;
+        let protocol = PciDoeProtocol {
+            vendor_id: header.vendor_id,
+            data_object_type: header.data_object_type,
+        };
+        if !self.protocols[..self.protocol_count].contains(&protocol) {
+            return spdm_result_err!(EINVAL);
+        }
+        if protocol == self.protocols[0] {
+            let request = PciDoeDiscoveryRequest::read(&mut reader).ok_or(spdm_err!(EINVAL))?;
+            return self.discovery_response(request.index);
+        }
+        if data_object.len() > self.response.len() {
+            return spdm_result_err!(EINVAL);
+        }
+        self.response[..data_object.len()].copy_from_slice(data_object);
+        self.response_size = data_object.len();
+        Ok(())
+    }
+
+    fn read_data_object(&mut self, data_object: &mut [u8]) -> SpdmResult<usize> {
+        if self.response_size == 0 {
+            return spdm_result_err!(EIO);
+        }
+        let size = self.response_size;
+        if data_object.len() < size {
+            return spdm_result_err!(EINVAL);
+        }
+        data_object[..size].copy_from_slice(&self.response[..size]);
+        self.response_size = 0;
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spdmlib::common::SpdmTransportEncap;
+
+    #[test]
+    fn test_case0_mailbox_io() {
+        let mut mailbox = PciDoeSimulatedMailbox::new(&[PciDoeProtocol {
+            vendor_id: PciDoeVendorId::PciDoeVendorIdPciSig,
+            data_object_type: PciDoeDataObjectType::PciDoeDataObjectTypeSpdm,
+        }]);
+        let mut transport_encap = PciDoeProbedTransportEncap::probe(&mut mailbox).unwrap();
+        let mut mailbox_io = PciDoeMailboxIo::new(&mut mailbox);
+
+        let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let used = transport_encap
+            .encap(&[0x11, 0x84, 0x00, 0x00], &mut transport_buffer, false)
+            .unwrap();
+        mailbox_io.send(&transport_buffer[..used]).unwrap();
+        let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        assert_eq!(mailbox_io.receive(&mut receive_buffer), Ok(used));
+        assert_eq!(mailbox_io.receive(&mut receive_buffer), Err(0));
+
+        // secured SPDM is not in the discovery table
+        assert!(transport_encap
+            .encap(&[0x11, 0x84, 0x00, 0x00], &mut transport_buffer, true)
+            .is_err());
+        let used = PciDoeTransportEncap {}
+            .encap(&[0x11, 0x84, 0x00, 0x00], &mut transport_buffer, true)
+            .unwrap();
+        assert!(mailbox_io.send(&transport_buffer[..used]).is_err());
+        assert_eq!(mailbox_io.receive(&mut receive_buffer), Err(0));
+    }
+}
@@ -0,0 +1,510 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use spdmlib::common::SpdmDeviceIo;
+use spdmlib::config;
+use spdmlib::error::SpdmResult;
+use spdmlib::{spdm_err, spdm_result_err};
+use spin::Mutex;
+
+pub const PCI_DOE_CONTROL_ABORT: u32 = 0x0000_0001;
+pub const PCI_DOE_CONTROL_INT_ENABLE: u32 = 0x0000_0002;
+pub const PCI_DOE_CONTROL_GO: u32 = 0x8000_0000;
+
+pub const PCI_DOE_STATUS_BUSY: u32 = 0x0000_0001;
+pub const PCI_DOE_STATUS_INT_STATUS: u32 = 0x0000_0002;
+pub const PCI_DOE_STATUS_ERROR: u32 = 0x0000_0004;
+pub const PCI_DOE_STATUS_DATA_OBJECT_READY: u32 = 0x8000_0000;
+
+/// The length field of DW1 of a data object, 0 means 2^18 DW.
+pub const PCI_DOE_LENGTH_MASK: u32 = 0x0003_FFFF;
+pub const PCI_DOE_MAX_DATA_OBJECT_DW: usize = 0x40000;
+
+pub const PCI_DOE_DEFAULT_POLL_LIMIT: usize = 16;
+
+const PCI_DOE_MODEL_MAX_DW: usize = config::MAX_SPDM_TRANSPORT_SIZE / 4;
+
+fn pci_doe_object_length(dw1: u32) -> usize {
+    match (dw1 & PCI_DOE_LENGTH_MASK) as usize {
+        0 => PCI_DOE_MAX_DATA_OBJECT_DW,
+        length => length,
+    }
+}
+
+/// A software model of the DOE capability registers.
+///
+/// The host side uses the control/status/mailbox registers like a driver,
+/// the device side takes the request and posts the response. A mailbox only
+/// holds MAX_SPDM_TRANSPORT_SIZE, a bigger data object sets Error.
+pub struct PciDoeMailboxModel {
+    control: u32,
+    status: u32,
+    write_mailbox: [u32; PCI_DOE_MODEL_MAX_DW],
+    write_count: usize,
+    request_pending: bool,
+    read_mailbox: [u32; PCI_DOE_MODEL_MAX_DW],
+    read_count: usize,
+    read_offset: usize,
+    injected_busy: usize,
+}
+
+impl Default for PciDoeMailboxModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PciDoeMailboxModel {
+    pub fn new() -> Self {
+        PciDoeMailboxModel {
+            control: 0,
+            status: 0,
+            write_mailbox: [0u32; PCI_DOE_MODEL_MAX_DW],
+            write_count: 0,
+            request_pending: false,
+            read_mailbox: [0u32; PCI_DOE_MODEL_MAX_DW],
+            read_count: 0,
+            read_offset: 0,
+            injected_busy: 0,
+        }
+    }
+
+    // host side registers
+
+    /// Abort and Go always read as 0.
+    pub fn read_control(&self) -> u32 {
+        self.control & PCI_DOE_CONTROL_INT_ENABLE
+    }
+
+    pub fn write_control(&mut self, value: u32) {
+        self.control = value & PCI_DOE_CONTROL_INT_ENABLE;
+        if value & PCI_DOE_CONTROL_ABORT != 0 {
+            self.abort();
+        } else if value & PCI_DOE_CONTROL_GO != 0 {
+            self.go();
+        }
+    }
+
+    pub fn read_status(&mut self) -> u32 {
+        if self.injected_busy > 0 {
+            self.injected_busy -= 1;
+            return self.status | PCI_DOE_STATUS_BUSY;
+        }
+        self.status
+    }
+
+    /// Interrupt Status is RW1C, the other bits are read only.
+    pub fn write_status(&mut self, value: u32) {
+        if value & PCI_DOE_STATUS_INT_STATUS != 0 {
+            self.status &= !PCI_DOE_STATUS_INT_STATUS;
+        }
+    }
+
+    pub fn write_write_mailbox(&mut self, value: u32) {
+        if self.status & PCI_DOE_STATUS_ERROR != 0 {
+            return;
+        }
+        if self.status & PCI_DOE_STATUS_BUSY != 0 || self.write_count == PCI_DOE_MODEL_MAX_DW {
+            self.set_error();
+            return;
+        }
+        self.write_mailbox[self.write_count] = value;
+        self.write_count += 1;
+    }
+
+    pub fn read_read_mailbox(&self) -> u32 {
+        if self.status & PCI_DOE_STATUS_DATA_OBJECT_READY == 0 {
+            return 0;
+        }
+        self.read_mailbox[self.read_offset]
+    }
+
+    /// Any write moves the read mailbox to the next DW.
+    pub fn write_read_mailbox(&mut self, _value: u32) {
+        if self.status & PCI_DOE_STATUS_DATA_OBJECT_READY == 0 {
+            return;
+        }
+        self.read_offset += 1;
+        if self.read_offset == self.read_count {
+            self.status &= !PCI_DOE_STATUS_DATA_OBJECT_READY;
+        }
+    }
+
+    // device side
+
+    /// Take the data object written by the host, if Go was set.
+    pub fn take_request(&mut self, buffer: &mut [u8]) -> Option<usize> {
+        if !self.request_pending {
+            return None;
+        }
+        let size = self.write_count * 4;
+        if buffer.len() < size {
+            return None;
+        }
+        for (dw, bytes) in self.write_mailbox[..self.write_count]
+            .iter()
+            .zip(buffer.chunks_mut(4))
+        {
+            bytes.copy_from_slice(&dw.to_le_bytes());
+        }
+        self.request_pending = false;
+        self.write_count = 0;
+        Some(size)
+    }
+
+    /// Post the response of the request in flight and set Data Object Ready.
+    pub fn post_response(&mut self, response: &[u8]) -> SpdmResult {
+        if self.status & PCI_DOE_STATUS_BUSY == 0 || self.request_pending {
+            // aborted, or no request has been taken
+            return spdm_result_err!(EIO);
+        }
+        if response.len() & 3 != 0 || response.len() < 8 {
+            return spdm_result_err!(EINVAL);
+        }
+        let count = response.len() / 4;
+        if count > PCI_DOE_MODEL_MAX_DW {
+            self.set_error();
+            return spdm_result_err!(ENOMEM);
+        }
+        for (dw, bytes) in self.read_mailbox[..count]
+            .iter_mut()
+            .zip(response.chunks(4))
+        {
+            *dw = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        if pci_doe_object_length(self.read_mailbox[1]) != count {
+            self.set_error();
+            return spdm_result_err!(EINVAL);
+        }
+        self.read_count = count;
+        self.read_offset = 0;
+        self.status &= !PCI_DOE_STATUS_BUSY;
+        self.status |= PCI_DOE_STATUS_DATA_OBJECT_READY;
+        self.raise_interrupt();
+        Ok(())
+    }
+
+    // fault injection
+
+    /// Report Busy for the next `polls` reads of the status register.
+    pub fn inject_busy(&mut self, polls: usize) {
+        self.injected_busy = polls;
+    }
+
+    /// Set Error, as if the device had failed the data object in flight.
+    pub fn inject_error(&mut self) {
+        self.set_error();
+    }
+
+    /// Drop everything in flight, as if an Abort had been issued.
+    pub fn inject_abort(&mut self) {
+        self.abort();
+    }
+
+    fn go(&mut self) {
+        if self.status & (PCI_DOE_STATUS_BUSY | PCI_DOE_STATUS_ERROR) != 0
+            || self.write_count < 2
+            || pci_doe_object_length(self.write_mailbox[1]) != self.write_count
+        {
+            self.set_error();
+            return;
+        }
+        self.request_pending = true;
+        self.status |= PCI_DOE_STATUS_BUSY;
+    }
+
+    fn abort(&mut self) {
+        self.write_count = 0;
+        self.request_pending = false;
+        self.read_count = 0;
+        self.read_offset = 0;
+        self.injected_busy = 0;
+        self.status &=
+            !(PCI_DOE_STATUS_BUSY | PCI_DOE_STATUS_ERROR | PCI_DOE_STATUS_DATA_OBJECT_READY);
+    }
+
+    fn set_error(&mut self) {
+        self.write_count = 0;
+        self.request_pending = false;
+        self.status &= !(PCI_DOE_STATUS_BUSY | PCI_DOE_STATUS_DATA_OBJECT_READY);
+        self.status |= PCI_DOE_STATUS_ERROR;
+        self.raise_interrupt();
+    }
+
+    fn raise_interrupt(&mut self) {
+        if self.control & PCI_DOE_CONTROL_INT_ENABLE != 0 {
+            self.status |= PCI_DOE_STATUS_INT_STATUS;
+        }
+    }
+}
+
+/// Host side SpdmDeviceIo, driving the mailbox registers as a DOE driver.
+///
+/// Busy is polled up to `poll_limit` times before giving up with Abort.
+/// Error is cleared with Abort.
+pub struct PciDoeHostIo<'a> {
+    pub mailbox: &'a Mutex<PciDoeMailboxModel>,
+    pub poll_limit: usize,
+}
+
+impl<'a> PciDoeHostIo<'a> {
+    pub fn new(mailbox: &'a Mutex<PciDoeMailboxModel>) -> Self {
+        PciDoeHostIo {
+            mailbox,
+            poll_limit: PCI_DOE_DEFAULT_POLL_LIMIT,
+        }
+    }
+
+    pub fn abort(&mut self) {
+        self.mailbox.lock().write_control(PCI_DOE_CONTROL_ABORT);
+    }
+
+    fn wait_idle(&mut self) -> SpdmResult {
+        for _ in 0..self.poll_limit {
+            let status = self.mailbox.lock().read_status();
+            if status & PCI_DOE_STATUS_ERROR != 0 {
+                self.abort();
+            } else if status & PCI_DOE_STATUS_BUSY == 0 {
+                return Ok(());
+            }
+        }
+        self.abort();
+        spdm_result_err!(EBUSY)
+    }
+
+    fn wait_data_object_ready(&mut self) -> SpdmResult {
+        for _ in 0..self.poll_limit {
+            let status = self.mailbox.lock().read_status();
+            if status & PCI_DOE_STATUS_ERROR != 0 {
+                self.abort();
+                return spdm_result_err!(EIO);
+            }
+            if status & PCI_DOE_STATUS_DATA_OBJECT_READY != 0 {
+                return Ok(());
+            }
+        }
+        self.abort();
+        spdm_result_err!(EBUSY)
+    }
+
+    fn read_dw(&mut self) -> u32 {
+        let mut mailbox = self.mailbox.lock();
+        let value = mailbox.read_read_mailbox();
+        mailbox.write_read_mailbox(0);
+        value
+    }
+}
+
+impl SpdmDeviceIo for PciDoeHostIo<'_> {
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        if buffer.len() & 3 != 0 || buffer.len() < 8 {
+            return spdm_result_err!(EINVAL);
+        }
+        self.wait_idle()?;
+
+        let mut mailbox = self.mailbox.lock();
+        for bytes in buffer.chunks(4) {
+            mailbox
+                .write_write_mailbox(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        }
+        let control = mailbox.read_control();
+        mailbox.write_control(control | PCI_DOE_CONTROL_GO);
+        if mailbox.read_status() & PCI_DOE_STATUS_ERROR != 0 {
+            mailbox.write_control(PCI_DOE_CONTROL_ABORT);
+            return spdm_result_err!(EIO);
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+        self.wait_data_object_ready().map_err(|_| 0usize)?;
+
+        let dw0 = self.read_dw();
+        let dw1 = self.read_dw();
+        let length = pci_doe_object_length(dw1);
+        if length < 2 || buffer.len() < length * 4 {
+            self.abort();
+            return Err(0);
+        }
+        buffer[..4].copy_from_slice(&dw0.to_le_bytes());
+        buffer[4..8].copy_from_slice(&dw1.to_le_bytes());
+        for index in 2..length {
+            let dw = self.read_dw();
+            buffer[(index * 4)..(index * 4 + 4)].copy_from_slice(&dw.to_le_bytes());
+        }
+        Ok(length * 4)
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        Ok(())
+    }
+}
+
+/// Device side SpdmDeviceIo, for a responder behind the mailbox.
+pub struct PciDoeDeviceIo<'a> {
+    pub mailbox: &'a Mutex<PciDoeMailboxModel>,
+}
+
+impl<'a> PciDoeDeviceIo<'a> {
+    pub fn new(mailbox: &'a Mutex<PciDoeMailboxModel>) -> Self {
+        PciDoeDeviceIo { mailbox }
+    }
+}
+
+impl SpdmDeviceIo for PciDoeDeviceIo<'_> {
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        self.mailbox.lock().post_response(buffer)
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+        self.mailbox.lock().take_request(buffer).ok_or(0usize)
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PciDoeTransportEncap;
+    use spdmlib::common::SpdmTransportEncap;
+
+    fn data_object(payload: &[u8], buffer: &mut [u8]) -> usize {
+        PciDoeTransportEncap {}
+            .encap(payload, buffer, false)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_case0_mailbox_model_exchange() {
+        let mailbox = Mutex::new(PciDoeMailboxModel::new());
+        let mut host = PciDoeHostIo::new(&mailbox);
+        let mut device = PciDoeDeviceIo::new(&mailbox);
+
+        let mut request = [0u8; 64];
+        let request_size = data_object(&[0x11, 0x84, 0x00, 0x00, 0x01], &mut request);
+        assert_eq!(request_size, 16);
+        host.send(&request[..request_size]).unwrap();
+        assert_eq!(
+            mailbox.lock().read_status() & PCI_DOE_STATUS_BUSY,
+            PCI_DOE_STATUS_BUSY
+        );
+        // nothing to read while the device is busy
+        let mut buffer = [0u8; 64];
+        assert_eq!(host.receive(&mut buffer), Err(0));
+
+        host.send(&request[..request_size]).unwrap();
+        assert_eq!(device.receive(&mut buffer), Ok(request_size));
+        assert_eq!(&buffer[..request_size], &request[..request_size]);
+        assert_eq!(device.receive(&mut buffer), Err(0));
+
+        let mut response = [0u8; 64];
+        let response_size = data_object(&[0x11, 0x04, 0x00, 0x00], &mut response);
+        device.send(&response[..response_size]).unwrap();
+        assert_eq!(
+            mailbox.lock().read_status(),
+            PCI_DOE_STATUS_DATA_OBJECT_READY
+        );
+        assert_eq!(host.receive(&mut buffer), Ok(response_size));
+        assert_eq!(&buffer[..response_size], &response[..response_size]);
+        assert_eq!(mailbox.lock().read_status(), 0);
+
+        // not DW aligned
+        assert!(host.send(&request[..(request_size - 1)]).is_err());
+    }
+    #[test]
+    fn test_case0_mailbox_model_registers() {
+        let mut model = PciDoeMailboxModel::new();
+        model.write_control(PCI_DOE_CONTROL_INT_ENABLE);
+        assert_eq!(model.read_control(), PCI_DOE_CONTROL_INT_ENABLE);
+
+        // the length field does not match the DWs written
+        model.write_write_mailbox(0x0001_0001);
+        model.write_write_mailbox(3);
+        model.write_control(PCI_DOE_CONTROL_INT_ENABLE | PCI_DOE_CONTROL_GO);
+        assert_eq!(
+            model.read_status(),
+            PCI_DOE_STATUS_ERROR | PCI_DOE_STATUS_INT_STATUS
+        );
+        model.write_status(PCI_DOE_STATUS_INT_STATUS);
+        assert_eq!(model.read_status(), PCI_DOE_STATUS_ERROR);
+        // writes are dropped until Abort
+        model.write_write_mailbox(0x0001_0001);
+        model.write_control(PCI_DOE_CONTROL_ABORT);
+        assert_eq!(model.read_status(), 0);
+        assert_eq!(model.read_control(), 0);
+
+        // length 0 means 2^18 DW
+        assert_eq!(pci_doe_object_length(0), PCI_DOE_MAX_DATA_OBJECT_DW);
+        assert_eq!(pci_doe_object_length(0xFFFC_0002), 2);
+        model.write_write_mailbox(0x0001_0001);
+        model.write_write_mailbox(0);
+        model.write_control(PCI_DOE_CONTROL_GO);
+        assert_eq!(model.read_status(), PCI_DOE_STATUS_ERROR);
+        model.write_control(PCI_DOE_CONTROL_ABORT);
+
+        // a data object bigger than the mailbox
+        for _ in 0..(PCI_DOE_MODEL_MAX_DW + 1) {
+            model.write_write_mailbox(0);
+        }
+        assert_eq!(model.read_status(), PCI_DOE_STATUS_ERROR);
+        model.write_control(PCI_DOE_CONTROL_ABORT);
+
+        // Abort in the middle of a read
+        model.write_write_mailbox(0x0001_0001);
+        model.write_write_mailbox(2);
+        model.write_control(PCI_DOE_CONTROL_GO);
+        let mut buffer = [0u8; 8];
+        assert_eq!(model.take_request(&mut buffer), Some(8));
+        model
+            .post_response(&[0x01, 0x00, 0x01, 0x00, 0x03, 0x00, 0x00, 0x00, 1, 2, 3, 4])
+            .unwrap();
+        assert_eq!(model.read_read_mailbox(), 0x0001_0001);
+        model.write_read_mailbox(0);
+        model.write_control(PCI_DOE_CONTROL_ABORT);
+        assert_eq!(model.read_status(), 0);
+        assert_eq!(model.read_read_mailbox(), 0);
+    }
+    #[test]
+    fn test_case0_mailbox_model_injection() {
+        let mailbox = Mutex::new(PciDoeMailboxModel::new());
+        let mut host = PciDoeHostIo::new(&mailbox);
+        let mut device = PciDoeDeviceIo::new(&mailbox);
+        let mut request = [0u8; 64];
+        let request_size = data_object(&[0x11, 0x84, 0x00, 0x00], &mut request);
+        let mut buffer = [0u8; 64];
+
+        // busy for a while, then idle
+        mailbox.lock().inject_busy(PCI_DOE_DEFAULT_POLL_LIMIT - 1);
+        host.send(&request[..request_size]).unwrap();
+        assert_eq!(device.receive(&mut buffer), Ok(request_size));
+        device.send(&request[..request_size]).unwrap();
+        assert_eq!(host.receive(&mut buffer), Ok(request_size));
+
+        // busy for too long, the host gives up and aborts
+        mailbox.lock().inject_busy(PCI_DOE_DEFAULT_POLL_LIMIT);
+        assert!(host.send(&request[..request_size]).is_err());
+        assert_eq!(mailbox.lock().read_status(), 0);
+
+        // an error left over is cleared before the next request
+        mailbox.lock().inject_error();
+        host.send(&request[..request_size]).unwrap();
+        assert_eq!(device.receive(&mut buffer), Ok(request_size));
+
+        // the device fails the request in flight
+        mailbox.lock().inject_error();
+        assert!(device.send(&request[..request_size]).is_err());
+        assert_eq!(host.receive(&mut buffer), Err(0));
+        assert_eq!(mailbox.lock().read_status(), 0);
+
+        // the request is aborted before the response is posted
+        host.send(&request[..request_size]).unwrap();
+        assert_eq!(device.receive(&mut buffer), Ok(request_size));
+        mailbox.lock().inject_abort();
+        assert!(device.send(&request[..request_size]).is_err());
+        assert_eq!(host.receive(&mut buffer), Err(0));
+    }
+}
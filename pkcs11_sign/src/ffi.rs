@@ -0,0 +1,204 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+//! The part of the PKCS#11 (v2.40) interface the signer needs.
+
+#![allow(dead_code)]
+
+use std::os::raw::{c_ulong, c_void};
+
+pub type CkUlong = c_ulong;
+pub type CkRv = CkUlong;
+pub type CkFlags = CkUlong;
+pub type CkSlotId = CkUlong;
+pub type CkSessionHandle = CkUlong;
+pub type CkObjectHandle = CkUlong;
+pub type CkObjectClass = CkUlong;
+pub type CkKeyType = CkUlong;
+pub type CkAttributeType = CkUlong;
+pub type CkMechanismType = CkUlong;
+pub type CkUserType = CkUlong;
+
+pub const CKR_OK: CkRv = 0;
+pub const CKR_USER_ALREADY_LOGGED_IN: CkRv = 0x100;
+pub const CKR_CRYPTOKI_ALREADY_INITIALIZED: CkRv = 0x191;
+
+pub const CKF_OS_LOCKING_OK: CkFlags = 1 << 1;
+pub const CKF_SERIAL_SESSION: CkFlags = 1 << 2;
+
+pub const CKU_USER: CkUserType = 1;
+
+pub const CKA_CLASS: CkAttributeType = 0;
+pub const CKA_LABEL: CkAttributeType = 3;
+pub const CKA_KEY_TYPE: CkAttributeType = 0x100;
+
+pub const CKO_PRIVATE_KEY: CkObjectClass = 3;
+
+pub const CKK_RSA: CkKeyType = 0;
+pub const CKK_EC: CkKeyType = 3;
+
+pub const CKM_RSA_PKCS: CkMechanismType = 1;
+pub const CKM_RSA_PKCS_PSS: CkMechanismType = 0xd;
+pub const CKM_SHA256: CkMechanismType = 0x250;
+pub const CKM_SHA384: CkMechanismType = 0x260;
+pub const CKM_SHA512: CkMechanismType = 0x270;
+pub const CKM_SHA3_256: CkMechanismType = 0x2b0;
+pub const CKM_SHA3_384: CkMechanismType = 0x2c0;
+pub const CKM_SHA3_512: CkMechanismType = 0x2d0;
+pub const CKM_ECDSA: CkMechanismType = 0x1041;
+
+pub const CKG_MGF1_SHA256: CkUlong = 2;
+pub const CKG_MGF1_SHA384: CkUlong = 3;
+pub const CKG_MGF1_SHA512: CkUlong = 4;
+pub const CKG_MGF1_SHA3_256: CkUlong = 7;
+pub const CKG_MGF1_SHA3_384: CkUlong = 8;
+pub const CKG_MGF1_SHA3_512: CkUlong = 9;
+
+// Windows packs the PKCS#11 structures to one byte, Unix does not.
+#[cfg_attr(windows, repr(C, packed))]
+#[cfg_attr(not(windows), repr(C))]
+pub struct CkVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+#[cfg_attr(windows, repr(C, packed))]
+#[cfg_attr(not(windows), repr(C))]
+pub struct CkAttribute {
+    pub attribute_type: CkAttributeType,
+    pub value: *mut c_void,
+    pub value_len: CkUlong,
+}
+
+#[cfg_attr(windows, repr(C, packed))]
+#[cfg_attr(not(windows), repr(C))]
+pub struct CkMechanism {
+    pub mechanism: CkMechanismType,
+    pub parameter: *mut c_void,
+    pub parameter_len: CkUlong,
+}
+
+#[cfg_attr(windows, repr(C, packed))]
+#[cfg_attr(not(windows), repr(C))]
+pub struct CkRsaPkcsPssParams {
+    pub hash_alg: CkMechanismType,
+    pub mgf: CkUlong,
+    pub s_len: CkUlong,
+}
+
+type Unused = Option<unsafe extern "C" fn()>;
+
+#[cfg_attr(windows, repr(C, packed))]
+#[cfg_attr(not(windows), repr(C))]
+pub struct CkCInitializeArgs {
+    pub create_mutex: Unused,
+    pub destroy_mutex: Unused,
+    pub lock_mutex: Unused,
+    pub unlock_mutex: Unused,
+    pub flags: CkFlags,
+    pub reserved: *mut c_void,
+}
+
+pub type CGetFunctionList = unsafe extern "C" fn(list: *mut *const CkFunctionList) -> CkRv;
+
+/// CK_FUNCTION_LIST up to C_Sign. The module owns the list, so the fields
+/// after it are never read.
+#[cfg_attr(windows, repr(C, packed))]
+#[cfg_attr(not(windows), repr(C))]
+pub struct CkFunctionList {
+    pub version: CkVersion,
+    pub c_initialize: Option<unsafe extern "C" fn(init_args: *mut c_void) -> CkRv>,
+    pub c_finalize: Unused,
+    pub c_get_info: Unused,
+    pub c_get_function_list: Unused,
+    pub c_get_slot_list: Unused,
+    pub c_get_slot_info: Unused,
+    pub c_get_token_info: Unused,
+    pub c_get_mechanism_list: Unused,
+    pub c_get_mechanism_info: Unused,
+    pub c_init_token: Unused,
+    pub c_init_pin: Unused,
+    pub c_set_pin: Unused,
+    pub c_open_session: Option<
+        unsafe extern "C" fn(
+            slot: CkSlotId,
+            flags: CkFlags,
+            application: *mut c_void,
+            notify: Unused,
+            session: *mut CkSessionHandle,
+        ) -> CkRv,
+    >,
+    pub c_close_session: Option<unsafe extern "C" fn(session: CkSessionHandle) -> CkRv>,
+    pub c_close_all_sessions: Unused,
+    pub c_get_session_info: Unused,
+    pub c_get_operation_state: Unused,
+    pub c_set_operation_state: Unused,
+    pub c_login: Option<
+        unsafe extern "C" fn(
+            session: CkSessionHandle,
+            user_type: CkUserType,
+            pin: *const u8,
+            pin_len: CkUlong,
+        ) -> CkRv,
+    >,
+    pub c_logout: Unused,
+    pub c_create_object: Unused,
+    pub c_copy_object: Unused,
+    pub c_destroy_object: Unused,
+    pub c_get_object_size: Unused,
+    pub c_get_attribute_value: Option<
+        unsafe extern "C" fn(
+            session: CkSessionHandle,
+            object: CkObjectHandle,
+            template: *mut CkAttribute,
+            count: CkUlong,
+        ) -> CkRv,
+    >,
+    pub c_set_attribute_value: Unused,
+    pub c_find_objects_init: Option<
+        unsafe extern "C" fn(
+            session: CkSessionHandle,
+            template: *mut CkAttribute,
+            count: CkUlong,
+        ) -> CkRv,
+    >,
+    pub c_find_objects: Option<
+        unsafe extern "C" fn(
+            session: CkSessionHandle,
+            objects: *mut CkObjectHandle,
+            max_count: CkUlong,
+            count: *mut CkUlong,
+        ) -> CkRv,
+    >,
+    pub c_find_objects_final: Option<unsafe extern "C" fn(session: CkSessionHandle) -> CkRv>,
+    pub c_encrypt_init: Unused,
+    pub c_encrypt: Unused,
+    pub c_encrypt_update: Unused,
+    pub c_encrypt_final: Unused,
+    pub c_decrypt_init: Unused,
+    pub c_decrypt: Unused,
+    pub c_decrypt_update: Unused,
+    pub c_decrypt_final: Unused,
+    pub c_digest_init: Unused,
+    pub c_digest: Unused,
+    pub c_digest_update: Unused,
+    pub c_digest_key: Unused,
+    pub c_digest_final: Unused,
+    pub c_sign_init: Option<
+        unsafe extern "C" fn(
+            session: CkSessionHandle,
+            mechanism: *mut CkMechanism,
+            key: CkObjectHandle,
+        ) -> CkRv,
+    >,
+    pub c_sign: Option<
+        unsafe extern "C" fn(
+            session: CkSessionHandle,
+            data: *const u8,
+            data_len: CkUlong,
+            signature: *mut u8,
+            signature_len: *mut CkUlong,
+        ) -> CkRv,
+    >,
+}
@@ -0,0 +1,16 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+//! An `SpdmAsymSign` that signs with a private key held by a PKCS#11
+//! token, e.g. an HSM or SoftHSM.
+//!
+//! Unlike the other crates this one calls into a C library, so it cannot
+//! forbid unsafe code.
+
+#[macro_use]
+extern crate spdmlib;
+
+mod ffi;
+mod sign;
+pub use sign::*;
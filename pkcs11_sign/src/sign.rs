@@ -0,0 +1,462 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::ffi::*;
+use conquer_once::spin::OnceCell;
+use libloading::{Library, Symbol};
+use spdmlib::crypto::{self, SpdmAsymSign};
+use spdmlib::error::SpdmResult;
+use spdmlib::msgs::{
+    SpdmBaseAsymAlgo, SpdmBaseHashAlgo, SpdmSignatureStruct, SPDM_MAX_ASYM_KEY_SIZE,
+};
+use std::mem::size_of;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::Mutex;
+
+static PKCS11_KEY: OnceCell<Mutex<Pkcs11Key>> = OnceCell::uninit();
+
+/// Signs with the key given to `set_pkcs11_key`. ECDSA and RSASSA/RSAPSS,
+/// the message is hashed here and the token only signs the digest.
+pub static PKCS11: SpdmAsymSign = SpdmAsymSign { sign_cb: asym_sign };
+
+/// A private key on a PKCS#11 token.
+pub struct Pkcs11KeyConfig<'a> {
+    /// The PKCS#11 module, e.g. /usr/lib/softhsm/libsofthsm2.so
+    pub module: &'a str,
+    pub slot: u64,
+    /// CKA_LABEL of the private key
+    pub key_label: &'a str,
+    /// The user PIN, None if the token needs no login
+    pub pin: Option<&'a str>,
+}
+
+/// Open the key `PKCS11` signs with. It can be set once.
+pub fn set_pkcs11_key(config: &Pkcs11KeyConfig) -> SpdmResult {
+    let key = Pkcs11Key::open(config)?;
+    PKCS11_KEY
+        .try_init_once(|| Mutex::new(key))
+        .map_err(|_| spdm_err!(EBUSY))
+}
+
+fn asym_sign(
+    base_hash_algo: SpdmBaseHashAlgo,
+    base_asym_algo: SpdmBaseAsymAlgo,
+    _context: &[u8],
+    data: &[u8],
+) -> Option<SpdmSignatureStruct> {
+    PKCS11_KEY
+        .get()?
+        .lock()
+        .ok()?
+        .sign(base_hash_algo, base_asym_algo, data)
+}
+
+struct Pkcs11Key {
+    functions: *const CkFunctionList,
+    session: CkSessionHandle,
+    key: CkObjectHandle,
+    key_type: CkKeyType,
+    // Dropped last, the function list lives in it
+    _library: Library,
+}
+
+// The session is only used under the mutex in PKCS11_KEY.
+unsafe impl Send for Pkcs11Key {}
+
+impl Pkcs11Key {
+    fn open(config: &Pkcs11KeyConfig) -> SpdmResult<Self> {
+        let library = unsafe { Library::new(config.module) }.map_err(|_| spdm_err!(ENOENT))?;
+        let mut functions: *const CkFunctionList = ptr::null();
+        unsafe {
+            let get_function_list: Symbol<CGetFunctionList> = library
+                .get(b"C_GetFunctionList\0")
+                .map_err(|_| spdm_err!(ENOENT))?;
+            if get_function_list(&mut functions) != CKR_OK || functions.is_null() {
+                return spdm_result_err!(EIO);
+            }
+        }
+        let f = unsafe { &*functions };
+
+        let mut init_args = CkCInitializeArgs {
+            create_mutex: None,
+            destroy_mutex: None,
+            lock_mutex: None,
+            unlock_mutex: None,
+            flags: CKF_OS_LOCKING_OK,
+            reserved: ptr::null_mut(),
+        };
+        let c_initialize = f.c_initialize.ok_or(spdm_err!(ENOSYS))?;
+        match unsafe { c_initialize(&mut init_args as *mut _ as *mut c_void) } {
+            CKR_OK | CKR_CRYPTOKI_ALREADY_INITIALIZED => {}
+            _ => return spdm_result_err!(EIO),
+        }
+
+        let mut session = 0;
+        let c_open_session = f.c_open_session.ok_or(spdm_err!(ENOSYS))?;
+        let rv = unsafe {
+            c_open_session(
+                config.slot as CkSlotId,
+                CKF_SERIAL_SESSION,
+                ptr::null_mut(),
+                None,
+                &mut session,
+            )
+        };
+        if rv != CKR_OK {
+            return spdm_result_err!(ENODEV);
+        }
+        // From here on drop closes the session
+        let mut key = Pkcs11Key {
+            functions,
+            session,
+            key: 0,
+            key_type: 0,
+            _library: library,
+        };
+
+        if let Some(pin) = config.pin {
+            let c_login = f.c_login.ok_or(spdm_err!(ENOSYS))?;
+            match unsafe { c_login(session, CKU_USER, pin.as_ptr(), pin.len() as CkUlong) } {
+                CKR_OK | CKR_USER_ALREADY_LOGGED_IN => {}
+                _ => return spdm_result_err!(EPERM),
+            }
+        }
+        key.key = key.find_private_key(config.key_label)?;
+        key.key_type = key.key_type()?;
+        Ok(key)
+    }
+
+    fn functions(&self) -> &CkFunctionList {
+        unsafe { &*self.functions }
+    }
+
+    fn find_private_key(&self, label: &str) -> SpdmResult<CkObjectHandle> {
+        let f = self.functions();
+        let c_find_objects_init = f.c_find_objects_init.ok_or(spdm_err!(ENOSYS))?;
+        let c_find_objects = f.c_find_objects.ok_or(spdm_err!(ENOSYS))?;
+        let c_find_objects_final = f.c_find_objects_final.ok_or(spdm_err!(ENOSYS))?;
+
+        let mut class = CKO_PRIVATE_KEY;
+        let mut template = [
+            CkAttribute {
+                attribute_type: CKA_CLASS,
+                value: &mut class as *mut _ as *mut c_void,
+                value_len: size_of::<CkObjectClass>() as CkUlong,
+            },
+            CkAttribute {
+                attribute_type: CKA_LABEL,
+                value: label.as_ptr() as *mut c_void,
+                value_len: label.len() as CkUlong,
+            },
+        ];
+        let mut objects: [CkObjectHandle; 2] = [0; 2];
+        let mut count = 0;
+        unsafe {
+            if c_find_objects_init(
+                self.session,
+                template.as_mut_ptr(),
+                template.len() as CkUlong,
+            ) != CKR_OK
+            {
+                return spdm_result_err!(EIO);
+            }
+            let rv = c_find_objects(
+                self.session,
+                objects.as_mut_ptr(),
+                objects.len() as CkUlong,
+                &mut count,
+            );
+            c_find_objects_final(self.session);
+            if rv != CKR_OK {
+                return spdm_result_err!(EIO);
+            }
+        }
+        match count {
+            0 => spdm_result_err!(ENOENT),
+            1 => Ok(objects[0]),
+            // More than one key has the label
+            _ => spdm_result_err!(EINVAL),
+        }
+    }
+
+    fn key_type(&self) -> SpdmResult<CkKeyType> {
+        let c_get_attribute_value = self
+            .functions()
+            .c_get_attribute_value
+            .ok_or(spdm_err!(ENOSYS))?;
+        let mut key_type: CkKeyType = 0;
+        let mut template = [CkAttribute {
+            attribute_type: CKA_KEY_TYPE,
+            value: &mut key_type as *mut _ as *mut c_void,
+            value_len: size_of::<CkKeyType>() as CkUlong,
+        }];
+        let rv = unsafe {
+            c_get_attribute_value(
+                self.session,
+                self.key,
+                template.as_mut_ptr(),
+                template.len() as CkUlong,
+            )
+        };
+        if rv != CKR_OK {
+            return spdm_result_err!(EIO);
+        }
+        Ok(key_type)
+    }
+
+    fn sign(
+        &self,
+        base_hash_algo: SpdmBaseHashAlgo,
+        base_asym_algo: SpdmBaseAsymAlgo,
+        data: &[u8],
+    ) -> Option<SpdmSignatureStruct> {
+        let digest = crypto::hash::hash_all(base_hash_algo, data)?;
+        let digest = digest.as_ref();
+
+        let mut pss_params;
+        let (mut mechanism, message) = match base_asym_algo {
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256
+            | SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384
+            | SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521
+                if self.key_type == CKK_EC =>
+            {
+                (mechanism(CKM_ECDSA), digest.to_vec())
+            }
+            SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048
+            | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072
+            | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096
+                if self.key_type == CKK_RSA =>
+            {
+                // CKM_RSA_PKCS pads what it is given, so that is the DigestInfo
+                let mut message = digest_info_prefix(base_hash_algo)?.to_vec();
+                message.extend_from_slice(digest);
+                (mechanism(CKM_RSA_PKCS), message)
+            }
+            SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_2048
+            | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072
+            | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096
+                if self.key_type == CKK_RSA =>
+            {
+                let (hash_alg, mgf) = pss_hash(base_hash_algo)?;
+                pss_params = CkRsaPkcsPssParams {
+                    hash_alg,
+                    mgf,
+                    s_len: digest.len() as CkUlong,
+                };
+                let mechanism = CkMechanism {
+                    mechanism: CKM_RSA_PKCS_PSS,
+                    parameter: &mut pss_params as *mut _ as *mut c_void,
+                    parameter_len: size_of::<CkRsaPkcsPssParams>() as CkUlong,
+                };
+                (mechanism, digest.to_vec())
+            }
+            _ => return None,
+        };
+
+        let f = self.functions();
+        let c_sign_init = f.c_sign_init?;
+        let c_sign = f.c_sign?;
+        let key_size = base_asym_algo.get_size() as usize;
+        let mut signature = Vec::new();
+        unsafe {
+            if c_sign_init(self.session, &mut mechanism, self.key) != CKR_OK {
+                return None;
+            }
+            // Ask for the length first, so a key bigger than expected does
+            // not leave the operation active
+            let mut signature_len = 0;
+            let rv = c_sign(
+                self.session,
+                message.as_ptr(),
+                message.len() as CkUlong,
+                ptr::null_mut(),
+                &mut signature_len,
+            );
+            if rv != CKR_OK {
+                return None;
+            }
+            signature.resize(signature_len as usize, 0);
+            let rv = c_sign(
+                self.session,
+                message.as_ptr(),
+                message.len() as CkUlong,
+                signature.as_mut_ptr(),
+                &mut signature_len,
+            );
+            if rv != CKR_OK {
+                return None;
+            }
+            signature.truncate(signature_len as usize);
+        }
+        // CKM_ECDSA gives r and s at their full width, back to back, which
+        // is what SPDM wants
+        if signature.len() != key_size || key_size > SPDM_MAX_ASYM_KEY_SIZE {
+            return None;
+        }
+
+        let mut data = [0u8; SPDM_MAX_ASYM_KEY_SIZE];
+        data[..key_size].copy_from_slice(&signature);
+        Some(SpdmSignatureStruct {
+            data_size: key_size as u16,
+            data,
+        })
+    }
+}
+
+impl Drop for Pkcs11Key {
+    fn drop(&mut self) {
+        if let Some(c_close_session) = self.functions().c_close_session {
+            unsafe {
+                c_close_session(self.session);
+            }
+        }
+    }
+}
+
+fn mechanism(mechanism: CkMechanismType) -> CkMechanism {
+    CkMechanism {
+        mechanism,
+        parameter: ptr::null_mut(),
+        parameter_len: 0,
+    }
+}
+
+/// The DER DigestInfo up to the digest itself, see RFC 8017 9.2.
+fn digest_info_prefix(base_hash_algo: SpdmBaseHashAlgo) -> Option<&'static [u8]> {
+    match base_hash_algo {
+        SpdmBaseHashAlgo::TPM_ALG_SHA_256 => Some(&[
+            0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x01, 0x05, 0x00, 0x04, 0x20,
+        ]),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_384 => Some(&[
+            0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x02, 0x05, 0x00, 0x04, 0x30,
+        ]),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_512 => Some(&[
+            0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x03, 0x05, 0x00, 0x04, 0x40,
+        ]),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => Some(&[
+            0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x08, 0x05, 0x00, 0x04, 0x20,
+        ]),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => Some(&[
+            0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x09, 0x05, 0x00, 0x04, 0x30,
+        ]),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => Some(&[
+            0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x0a, 0x05, 0x00, 0x04, 0x40,
+        ]),
+        _ => None,
+    }
+}
+
+fn pss_hash(base_hash_algo: SpdmBaseHashAlgo) -> Option<(CkMechanismType, CkUlong)> {
+    match base_hash_algo {
+        SpdmBaseHashAlgo::TPM_ALG_SHA_256 => Some((CKM_SHA256, CKG_MGF1_SHA256)),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_384 => Some((CKM_SHA384, CKG_MGF1_SHA384)),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_512 => Some((CKM_SHA512, CKG_MGF1_SHA512)),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => Some((CKM_SHA3_256, CKG_MGF1_SHA3_256)),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => Some((CKM_SHA3_384, CKG_MGF1_SHA3_384)),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => Some((CKM_SHA3_512, CKG_MGF1_SHA3_512)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // Needs a token with the test keys on it, see readme.md:
+    // SPDM_PKCS11_MODULE=... SPDM_PKCS11_SLOT=... SPDM_PKCS11_PIN=... \
+    //     cargo test -p pkcs11_sign -- --ignored
+    #[test]
+    #[ignore]
+    fn test_case0_asym_sign() {
+        let module = std::env::var("SPDM_PKCS11_MODULE").unwrap();
+        let slot = std::env::var("SPDM_PKCS11_SLOT").unwrap();
+        let pin = std::env::var("SPDM_PKCS11_PIN").ok();
+        let data = &[0x10u8; 4096];
+        for &(key_label, base_asym_algo, base_hash_algo) in [
+            (
+                "EcP256",
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256,
+                SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            ),
+            (
+                "EcP384",
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+                SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+            ),
+            (
+                "Rsa3072",
+                SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072,
+                SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+            ),
+            (
+                "Rsa3072",
+                SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072,
+                SpdmBaseHashAlgo::TPM_ALG_SHA_512,
+            ),
+        ]
+        .iter()
+        {
+            let key = Pkcs11Key::open(&Pkcs11KeyConfig {
+                module: &module,
+                slot: slot.parse().unwrap(),
+                key_label,
+                pin: pin.as_deref(),
+            })
+            .unwrap();
+            let signature = key.sign(base_hash_algo, base_asym_algo, data).unwrap();
+            assert_eq!(signature.data_size, base_asym_algo.get_size());
+
+            let cert_chain = std::fs::read(
+                PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("../test_key")
+                    .join(key_label)
+                    .join("bundle_responder.certchain.der"),
+            )
+            .unwrap();
+            assert!(crypto::asym_verify::verify(
+                base_hash_algo,
+                base_asym_algo,
+                &[],
+                &cert_chain,
+                data,
+                &signature
+            )
+            .is_ok());
+
+            // The key is ECDSA or RSA, not both
+            let other_asym_algo = if key_label.starts_with("Ec") {
+                SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072
+            } else {
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384
+            };
+            assert!(key.sign(base_hash_algo, other_asym_algo, data).is_none());
+        }
+    }
+
+    #[test]
+    fn test_case1_asym_sign() {
+        assert!(Pkcs11Key::open(&Pkcs11KeyConfig {
+            module: "/nonexistent/libpkcs11.so",
+            slot: 0,
+            key_label: "EcP384",
+            pin: None,
+        })
+        .is_err());
+        assert!(asym_sign(
+            SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+            &[],
+            &[0x10u8; 16],
+        )
+        .is_none());
+    }
+}
@@ -107,7 +107,8 @@ pub const MAX_SPDM_PSK_CONTEXT_SIZE: usize = {psk_ctx_sz};
 /// This is used in SpdmPskExchangeRequestPayload / SpdmPskExchangeResponsePayload
 pub const MAX_SPDM_PSK_HINT_SIZE: usize = {psk_hint_sz};
 
-/// This is used in SpdmContext
+/// This is used in SpdmSessionTable <- SpdmContext
+/// With the alloc feature it is only the default capacity, see SpdmSessionTable::with_capacity
 pub const MAX_SPDM_SESSION_COUNT: usize = {session_cnt};
 
 /// This is used in SpdmRuntimeInfo. max cached size
@@ -167,4 +168,13 @@ fn main() {
     println!("cargo:rerun-if-changed=../Cargo.lock");
     println!("cargo:rerun-if-changed={}", SPDM_CONFIG_JSON_DEFAULT_PATH);
     println!("cargo:rerun-if-env-changed={}", SPDM_CONFIG_ENV);
+
+    // openssl-sys passes on the version of the OpenSSL linked. SM4-GCM is
+    // only reachable through the OpenSSL 3 fetch API.
+    println!("cargo:rustc-check-cfg=cfg(ossl300)");
+    if let Ok(version) = env::var("DEP_OPENSSL_VERSION_NUMBER") {
+        if matches!(u64::from_str_radix(&version, 16), Ok(version) if version >= 0x3000_0000) {
+            println!("cargo:rustc-cfg=ossl300");
+        }
+    }
 }
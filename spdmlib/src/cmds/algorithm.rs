@@ -14,6 +14,10 @@ pub struct SpdmNegotiateAlgorithmsRequestPayload {
     pub measurement_specification: SpdmMeasurementSpecification,
     pub base_asym_algo: SpdmBaseAsymAlgo,
     pub base_hash_algo: SpdmBaseHashAlgo,
+    pub ext_asym_count: u8,
+    pub ext_asym: [SpdmExtAlgStruct; config::MAX_SPDM_EXTEND_ASYM_ALGO_COUNT],
+    pub ext_hash_count: u8,
+    pub ext_hash: [SpdmExtAlgStruct; config::MAX_SPDM_EXTEND_HASH_ALGO_COUNT],
     pub alg_struct_count: u8,
     pub alg_struct: [SpdmAlgStruct; config::MAX_SPDM_ALG_STRUCT_COUNT],
 }
@@ -23,9 +27,10 @@ impl SpdmCodec for SpdmNegotiateAlgorithmsRequestPayload {
         self.alg_struct_count.encode(bytes); // param1
         0u8.encode(bytes); // param1
 
-        let mut length: u16 = 32;
+        let mut length: u16 =
+            32 + (4 * self.ext_asym_count as u16) + (4 * self.ext_hash_count as u16);
         for algo in self.alg_struct.iter().take(self.alg_struct_count as usize) {
-            length += 2 + algo.alg_fixed_count as u16;
+            length += 2 + algo.alg_fixed_count as u16 + (4 * algo.alg_ext_count as u16);
         }
         length.encode(bytes);
 
@@ -38,9 +43,15 @@ impl SpdmCodec for SpdmNegotiateAlgorithmsRequestPayload {
             0u8.encode(bytes); // reserved2
         }
 
-        0u8.encode(bytes); // ext_asym_count
+        self.ext_asym_count.encode(bytes);
+        for algo in self.ext_asym.iter().take(self.ext_asym_count as usize) {
+            algo.encode(bytes);
+        }
 
-        0u8.encode(bytes); // ext_hash_count
+        self.ext_hash_count.encode(bytes);
+        for algo in self.ext_hash.iter().take(self.ext_hash_count as usize) {
+            algo.encode(bytes);
+        }
 
         0u16.encode(bytes); // reserved3
 
@@ -67,18 +78,20 @@ impl SpdmCodec for SpdmNegotiateAlgorithmsRequestPayload {
             u8::read(r)?; // reserved2
         }
 
-        let ext_asym_count = u8::read(r)?;
-        for _ in 0..(ext_asym_count as usize) {
-            SpdmExtAlgStruct::read(r)?;
-        }
+        // the extended algorithms beyond ours are skipped
+        let ext_asym_total = u8::read(r)?;
+        let mut ext_asym = [SpdmExtAlgStruct::default(); config::MAX_SPDM_EXTEND_ASYM_ALGO_COUNT];
+        let ext_asym_count = read_ext_algs(r, ext_asym_total, &mut ext_asym)?;
 
-        let ext_hash_count = u8::read(r)?;
-        for _ in 0..(ext_hash_count as usize) {
-            SpdmExtAlgStruct::read(r)?;
-        }
+        let ext_hash_total = u8::read(r)?;
+        let mut ext_hash = [SpdmExtAlgStruct::default(); config::MAX_SPDM_EXTEND_HASH_ALGO_COUNT];
+        let ext_hash_count = read_ext_algs(r, ext_hash_total, &mut ext_hash)?;
 
         u16::read(r)?; // reserved3
 
+        // the alg structs are counted as read, skipped extended algorithms
+        // included
+        let alg_struct_start = r.used();
         let mut alg_struct = [SpdmAlgStruct::default(); config::MAX_SPDM_ALG_STRUCT_COUNT];
         for algo in alg_struct.iter_mut().take(alg_struct_count as usize) {
             *algo = SpdmAlgStruct::read(r)?;
@@ -87,12 +100,12 @@ impl SpdmCodec for SpdmNegotiateAlgorithmsRequestPayload {
         //
         // check length
         //
-        let mut calc_length: u16 = 32 + (4 * ext_asym_count as u16) + (4 * ext_hash_count as u16);
-        for alg in alg_struct.iter().take(alg_struct_count as usize) {
-            calc_length += 2 + alg.alg_fixed_count as u16 + (4 * alg.alg_ext_count as u16);
-        }
+        let calc_length = 32
+            + (4 * ext_asym_total as usize)
+            + (4 * ext_hash_total as usize)
+            + (r.used() - alg_struct_start);
 
-        if length != calc_length {
+        if length as usize != calc_length {
             return None;
         }
 
@@ -100,6 +113,10 @@ impl SpdmCodec for SpdmNegotiateAlgorithmsRequestPayload {
             measurement_specification,
             base_asym_algo,
             base_hash_algo,
+            ext_asym_count,
+            ext_asym,
+            ext_hash_count,
+            ext_hash,
             alg_struct_count,
             alg_struct,
         })
@@ -112,6 +129,11 @@ pub struct SpdmAlgorithmsResponsePayload {
     pub measurement_hash_algo: SpdmMeasurementHashAlgo,
     pub base_asym_sel: SpdmBaseAsymAlgo,
     pub base_hash_sel: SpdmBaseHashAlgo,
+    // the selected extended algorithm, if the count is 1
+    pub ext_asym_sel_count: u8,
+    pub ext_asym_sel: SpdmExtAlgStruct,
+    pub ext_hash_sel_count: u8,
+    pub ext_hash_sel: SpdmExtAlgStruct,
     pub alg_struct_count: u8,
     pub alg_struct: [SpdmAlgStruct; config::MAX_SPDM_ALG_STRUCT_COUNT],
 }
@@ -121,9 +143,10 @@ impl SpdmCodec for SpdmAlgorithmsResponsePayload {
         self.alg_struct_count.encode(bytes); // param1
         0u8.encode(bytes); // param2
 
-        let mut length: u16 = 36;
+        let mut length: u16 =
+            36 + (4 * self.ext_asym_sel_count as u16) + (4 * self.ext_hash_sel_count as u16);
         for alg in self.alg_struct.iter().take(self.alg_struct_count as usize) {
-            length += 2 + alg.alg_fixed_count as u16;
+            length += 2 + alg.alg_fixed_count as u16 + (4 * alg.alg_ext_count as u16);
         }
         length.encode(bytes);
 
@@ -137,9 +160,16 @@ impl SpdmCodec for SpdmAlgorithmsResponsePayload {
             0u8.encode(bytes); // reserved2
         }
 
-        0u8.encode(bytes); // ext_asym_count
+        assert!(self.ext_asym_sel_count <= 1 && self.ext_hash_sel_count <= 1);
+        self.ext_asym_sel_count.encode(bytes);
+        if self.ext_asym_sel_count == 1 {
+            self.ext_asym_sel.encode(bytes);
+        }
 
-        0u8.encode(bytes); // ext_hash_count
+        self.ext_hash_sel_count.encode(bytes);
+        if self.ext_hash_sel_count == 1 {
+            self.ext_hash_sel.encode(bytes);
+        }
 
         0u16.encode(bytes); // reserved3
 
@@ -168,29 +198,39 @@ impl SpdmCodec for SpdmAlgorithmsResponsePayload {
             u8::read(r)?; // reserved2
         }
 
-        let ext_asym_count = u8::read(r)?;
-        for _ in 0..(ext_asym_count as usize) {
-            SpdmExtAlgStruct::read(r)?;
+        // at most one extended algorithm is selected
+        let ext_asym_sel_count = u8::read(r)?;
+        if ext_asym_sel_count > 1 {
+            return None;
+        }
+        let mut ext_asym_sel = SpdmExtAlgStruct::default();
+        if ext_asym_sel_count == 1 {
+            ext_asym_sel = SpdmExtAlgStruct::read(r)?;
         }
 
-        let ext_hash_count = u8::read(r)?;
-        for _ in 0..(ext_hash_count as usize) {
-            SpdmExtAlgStruct::read(r)?;
+        let ext_hash_sel_count = u8::read(r)?;
+        if ext_hash_sel_count > 1 {
+            return None;
+        }
+        let mut ext_hash_sel = SpdmExtAlgStruct::default();
+        if ext_hash_sel_count == 1 {
+            ext_hash_sel = SpdmExtAlgStruct::read(r)?;
         }
 
         u16::read(r)?; // reserved3
 
+        let alg_struct_start = r.used();
         let mut alg_struct = [SpdmAlgStruct::default(); config::MAX_SPDM_ALG_STRUCT_COUNT];
         for algo in alg_struct.iter_mut().take(alg_struct_count as usize) {
             *algo = SpdmAlgStruct::read(r)?;
         }
 
-        let mut calc_length: u16 = 36 + (4 * ext_asym_count as u16) + (4 * ext_hash_count as u16);
-        for algo in alg_struct.iter().take(alg_struct_count as usize) {
-            calc_length += 2 + algo.alg_fixed_count as u16 + (4 * algo.alg_ext_count as u16);
-        }
+        let calc_length = 36
+            + (4 * ext_asym_sel_count as usize)
+            + (4 * ext_hash_sel_count as usize)
+            + (r.used() - alg_struct_start);
 
-        if length != calc_length {
+        if length as usize != calc_length {
             return None;
         }
 
@@ -199,6 +239,10 @@ impl SpdmCodec for SpdmAlgorithmsResponsePayload {
             measurement_hash_algo,
             base_asym_sel,
             base_hash_sel,
+            ext_asym_sel_count,
+            ext_asym_sel,
+            ext_hash_sel_count,
+            ext_hash_sel,
             alg_struct_count,
             alg_struct,
         })
@@ -224,7 +268,9 @@ mod tests {
                 alg_fixed_count: 2,
                 alg_supported: SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::FFDHE_2048),
                 alg_ext_count: 0,
+                ..Default::default()
             }; config::MAX_SPDM_ALG_STRUCT_COUNT],
+            ..Default::default()
         };
         let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
         let my_spdm_device_io = &mut MySpdmDeviceIo;
@@ -272,6 +318,7 @@ mod tests {
             base_hash_algo: SpdmBaseHashAlgo::empty(),
             alg_struct_count: 0,
             alg_struct: [SpdmAlgStruct::default(); config::MAX_SPDM_ALG_STRUCT_COUNT],
+            ..Default::default()
         };
 
         let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
@@ -301,6 +348,7 @@ mod tests {
             base_hash_algo: SpdmBaseHashAlgo::TPM_ALG_SHA_256,
             alg_struct_count: 0,
             alg_struct: [SpdmAlgStruct::default(); config::MAX_SPDM_ALG_STRUCT_COUNT],
+            ..Default::default()
         };
 
         let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
@@ -331,7 +379,9 @@ mod tests {
                 alg_fixed_count: 2,
                 alg_supported: SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::FFDHE_2048),
                 alg_ext_count: 0,
+                ..Default::default()
             }; config::MAX_SPDM_ALG_STRUCT_COUNT],
+            ..Default::default()
         };
 
         let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
@@ -385,6 +435,7 @@ mod tests {
             base_hash_sel: SpdmBaseHashAlgo::TPM_ALG_SHA_256,
             alg_struct_count: 0,
             alg_struct: [SpdmAlgStruct::default(); config::MAX_SPDM_ALG_STRUCT_COUNT],
+            ..Default::default()
         };
 
         let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
@@ -412,6 +463,7 @@ mod tests {
             base_hash_sel: SpdmBaseHashAlgo::empty(),
             alg_struct_count: 0,
             alg_struct: [SpdmAlgStruct::default(); config::MAX_SPDM_ALG_STRUCT_COUNT],
+            ..Default::default()
         };
 
         let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
@@ -436,4 +488,149 @@ mod tests {
         assert_eq!(spdm_sturct_data.alg_struct_count, 0);
         assert_eq!(16, reader.left());
     }
+    #[test]
+    fn test_case3_spdm_algorithms_ext_algo() {
+        let ext_algo = SpdmExtAlgStruct {
+            registry_id: SpdmStandardId::SpdmStandardIdIANA,
+            reserved: 0,
+            algorithm_id: 0x8001,
+        };
+        let mut alg_struct = [SpdmAlgStruct::default(); config::MAX_SPDM_ALG_STRUCT_COUNT];
+        alg_struct[0] = SpdmAlgStruct {
+            alg_type: SpdmAlgType::SpdmAlgTypeDHE,
+            alg_fixed_count: 2,
+            alg_supported: SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::SECP_384_R1),
+            alg_ext_count: 1,
+            alg_ext: [ext_algo; config::MAX_SPDM_EXTEND_ALG_STRUCT_COUNT],
+        };
+
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+        let my_spdm_device_io = &mut MySpdmDeviceIo;
+        let mut context = new_context(my_spdm_device_io, pcidoe_transport_encap);
+
+        let u8_slice = &mut [0u8; 64];
+        let mut writer = Writer::init(u8_slice);
+        let value = SpdmNegotiateAlgorithmsRequestPayload {
+            ext_asym_count: 2,
+            ext_asym: [ext_algo; config::MAX_SPDM_EXTEND_ASYM_ALGO_COUNT],
+            ext_hash_count: 1,
+            ext_hash: [ext_algo; config::MAX_SPDM_EXTEND_HASH_ALGO_COUNT],
+            alg_struct_count: 1,
+            alg_struct,
+            ..Default::default()
+        };
+        value.spdm_encode(&mut context, &mut writer);
+        // 30 bytes after the message header, 3 ext algorithms, and an
+        // alg struct of 4 bytes with 1 ext algorithm
+        let used = writer.used();
+        assert_eq!(used, 30 + 3 * 4 + 4 + 4);
+        let mut reader = Reader::init(&u8_slice[..used]);
+        let request =
+            SpdmNegotiateAlgorithmsRequestPayload::spdm_read(&mut context, &mut reader).unwrap();
+        assert_eq!(request.ext_asym_count, 2);
+        assert_eq!(request.ext_asym[1], ext_algo);
+        assert_eq!(request.ext_hash_count, 1);
+        assert_eq!(request.ext_hash[0], ext_algo);
+        assert_eq!(request.alg_struct[0].alg_ext_count, 1);
+        assert_eq!(request.alg_struct[0].alg_ext[0], ext_algo);
+
+        let u8_slice = &mut [0u8; 64];
+        let mut writer = Writer::init(u8_slice);
+        let value = SpdmAlgorithmsResponsePayload {
+            ext_asym_sel_count: 1,
+            ext_asym_sel: ext_algo,
+            ..Default::default()
+        };
+        value.spdm_encode(&mut context, &mut writer);
+        let used = writer.used();
+        let mut reader = Reader::init(&u8_slice[..used]);
+        let response = SpdmAlgorithmsResponsePayload::spdm_read(&mut context, &mut reader).unwrap();
+        assert_eq!(response.ext_asym_sel_count, 1);
+        assert_eq!(response.ext_asym_sel, ext_algo);
+        assert_eq!(response.ext_hash_sel_count, 0);
+
+        // at most one extended algorithm is selected
+        u8_slice[30] = 2;
+        let mut reader = Reader::init(&u8_slice[..used]);
+        assert!(SpdmAlgorithmsResponsePayload::spdm_read(&mut context, &mut reader).is_none());
+    }
+    #[test]
+    fn test_case4_spdm_algorithms_ext_algo_skipped() {
+        let ext_algo = |algorithm_id: usize| SpdmExtAlgStruct {
+            registry_id: SpdmStandardId::SpdmStandardIdIANA,
+            reserved: 0,
+            algorithm_id: algorithm_id as u16,
+        };
+        let ext_asym_total = config::MAX_SPDM_EXTEND_ASYM_ALGO_COUNT + 1;
+        let ext_hash_total = config::MAX_SPDM_EXTEND_HASH_ALGO_COUNT + 1;
+        let alg_ext_total = config::MAX_SPDM_EXTEND_ALG_STRUCT_COUNT + 1;
+
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+        let my_spdm_device_io = &mut MySpdmDeviceIo;
+        let mut context = new_context(my_spdm_device_io, pcidoe_transport_encap);
+
+        let u8_slice = &mut [0u8; 256];
+        let mut writer = Writer::init(u8_slice);
+        1u8.encode(&mut writer); // param1
+        0u8.encode(&mut writer); // param2
+        let length = 32 + 4 * ext_asym_total + 4 * ext_hash_total + 4 + 4 * alg_ext_total;
+        (length as u16).encode(&mut writer);
+        SpdmMeasurementSpecification::DMTF.encode(&mut writer);
+        0u8.encode(&mut writer); // reserved
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384.encode(&mut writer);
+        SpdmBaseHashAlgo::TPM_ALG_SHA_384.encode(&mut writer);
+        for _i in 0..12 {
+            0u8.encode(&mut writer); // reserved2
+        }
+        (ext_asym_total as u8).encode(&mut writer);
+        for i in 0..ext_asym_total {
+            ext_algo(i).encode(&mut writer);
+        }
+        (ext_hash_total as u8).encode(&mut writer);
+        for i in 0..ext_hash_total {
+            ext_algo(i).encode(&mut writer);
+        }
+        0u16.encode(&mut writer); // reserved3
+        SpdmAlgType::SpdmAlgTypeDHE.encode(&mut writer);
+        (0x20 | alg_ext_total as u8).encode(&mut writer);
+        SpdmDheAlgo::SECP_384_R1.encode(&mut writer);
+        for i in 0..alg_ext_total {
+            ext_algo(i).encode(&mut writer);
+        }
+        let used = writer.used();
+
+        // the extended algorithms beyond ours are read and dropped
+        let mut reader = Reader::init(&u8_slice[..used]);
+        let request =
+            SpdmNegotiateAlgorithmsRequestPayload::spdm_read(&mut context, &mut reader).unwrap();
+        assert!(!reader.any_left());
+        assert_eq!(
+            request.ext_asym_count as usize,
+            config::MAX_SPDM_EXTEND_ASYM_ALGO_COUNT
+        );
+        assert_eq!(
+            request.ext_asym[config::MAX_SPDM_EXTEND_ASYM_ALGO_COUNT - 1],
+            ext_algo(config::MAX_SPDM_EXTEND_ASYM_ALGO_COUNT - 1)
+        );
+        assert_eq!(
+            request.ext_hash_count as usize,
+            config::MAX_SPDM_EXTEND_HASH_ALGO_COUNT
+        );
+        assert_eq!(request.alg_struct_count, 1);
+        assert_eq!(
+            request.alg_struct[0].alg_ext_count as usize,
+            config::MAX_SPDM_EXTEND_ALG_STRUCT_COUNT
+        );
+        assert_eq!(
+            request.alg_struct[0].alg_supported,
+            SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::SECP_384_R1)
+        );
+
+        // the length still counts them
+        ((length - 4) as u16).encode(&mut Writer::init(&mut u8_slice[2..4]));
+        let mut reader = Reader::init(&u8_slice[..used]);
+        assert!(
+            SpdmNegotiateAlgorithmsRequestPayload::spdm_read(&mut context, &mut reader).is_none()
+        );
+    }
 }
@@ -8,6 +8,9 @@ use crate::error::SpdmResult;
 use crate::msgs::*;
 use crate::session::*;
 use codec::Writer;
+use core::future::Future;
+use core::pin::{pin, Pin};
+use core::task::{Context, Poll, Waker};
 
 pub const OPAQUE_DATA_SUPPORT_VERSION: [u8; 20] = [
     0x46, 0x54, 0x4d, 0x44, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x01, 0x01, 0x01, 0x00,
@@ -17,6 +20,12 @@ pub const OPAQUE_DATA_VERSION_SELECTION: [u8; 16] = [
     0x46, 0x54, 0x4d, 0x44, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x01, 0x00, 0x00, 0x11,
 ];
 
+// The DSP0274 1.2 signing contexts, given to the asym sign and verify
+// callbacks. SM2 signs with the context as the signer ID.
+pub const SPDM_SIGNING_CONTEXT_CHALLENGE_AUTH: &[u8] = b"responder-challenge_auth signing";
+pub const SPDM_SIGNING_CONTEXT_MEASUREMENTS: &[u8] = b"responder-measurements signing";
+pub const SPDM_SIGNING_CONTEXT_KEY_EXCHANGE_RSP: &[u8] = b"responder-key_exchange_rsp signing";
+
 pub trait SpdmDeviceIo {
     fn send(&mut self, buffer: &[u8]) -> SpdmResult;
 
@@ -32,6 +41,158 @@ impl Debug for dyn SpdmDeviceIo {
     }
 }
 
+/// Device IO for the async requester and responder APIs.
+///
+/// The methods are polled like core::future::Future, so any executor can
+/// drive them: return Poll::Pending and wake the task in `cx` when the IO
+/// can make progress. A pending send is polled again with the same buffer,
+/// and completes when the whole message is sent. A receive completes with
+/// one whole transport message, or Err(needed size) as
+/// SpdmDeviceIo::receive does.
+///
+/// poll_delay is the timer of the requester before it sends a request
+/// answered with Busy again. It is polled with the same `us` until it
+/// completes with true, `us` microseconds after the first poll. Without a
+/// timer it completes with false at once, and Busy is not retried.
+pub trait SpdmAsyncDeviceIo {
+    fn poll_send(&mut self, cx: &mut Context<'_>, buffer: &[u8]) -> Poll<SpdmResult>;
+
+    fn poll_receive(
+        &mut self,
+        cx: &mut Context<'_>,
+        buffer: &mut [u8],
+    ) -> Poll<Result<usize, usize>>;
+
+    fn poll_delay(&mut self, _cx: &mut Context<'_>, _us: u64) -> Poll<bool> {
+        Poll::Ready(false)
+    }
+}
+
+impl Debug for dyn SpdmAsyncDeviceIo {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Dyn SpdmAsyncDeviceIo")
+    }
+}
+
+/// The future of sending one message on an SpdmAsyncDeviceIo.
+pub struct SpdmAsyncSend<'b, D: SpdmAsyncDeviceIo + ?Sized> {
+    device_io: &'b mut D,
+    buffer: &'b [u8],
+}
+
+impl<'b, D: SpdmAsyncDeviceIo + ?Sized> SpdmAsyncSend<'b, D> {
+    pub fn new(device_io: &'b mut D, buffer: &'b [u8]) -> Self {
+        SpdmAsyncSend { device_io, buffer }
+    }
+}
+
+impl<D: SpdmAsyncDeviceIo + ?Sized> Future for SpdmAsyncSend<'_, D> {
+    type Output = SpdmResult;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        this.device_io.poll_send(cx, this.buffer)
+    }
+}
+
+/// The future of receiving one message on an SpdmAsyncDeviceIo.
+pub struct SpdmAsyncReceive<'b, D: SpdmAsyncDeviceIo + ?Sized> {
+    device_io: &'b mut D,
+    buffer: &'b mut [u8],
+}
+
+impl<'b, D: SpdmAsyncDeviceIo + ?Sized> SpdmAsyncReceive<'b, D> {
+    pub fn new(device_io: &'b mut D, buffer: &'b mut [u8]) -> Self {
+        SpdmAsyncReceive { device_io, buffer }
+    }
+}
+
+impl<D: SpdmAsyncDeviceIo + ?Sized> Future for SpdmAsyncReceive<'_, D> {
+    type Output = Result<usize, usize>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        this.device_io.poll_receive(cx, this.buffer)
+    }
+}
+
+/// The future of waiting on an SpdmAsyncDeviceIo, true if it waited.
+pub struct SpdmAsyncDelay<'b, D: SpdmAsyncDeviceIo + ?Sized> {
+    device_io: &'b mut D,
+    us: u64,
+}
+
+impl<'b, D: SpdmAsyncDeviceIo + ?Sized> SpdmAsyncDelay<'b, D> {
+    pub fn new(device_io: &'b mut D, us: u64) -> Self {
+        SpdmAsyncDelay { device_io, us }
+    }
+}
+
+impl<D: SpdmAsyncDeviceIo + ?Sized> Future for SpdmAsyncDelay<'_, D> {
+    type Output = bool;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        this.device_io.poll_delay(cx, this.us)
+    }
+}
+
+/// Wait for the given number of microseconds, blocking the thread.
+pub type SpdmDelay = fn(us: u64);
+
+/// A blocking SpdmDeviceIo seen as an SpdmAsyncDeviceIo, for running the
+/// async flows in the blocking API. Its sends, receives and delays are
+/// always ready.
+pub struct SpdmBlockingDeviceIo<'a, 'b> {
+    pub device_io: &'a mut (dyn SpdmDeviceIo + Send + 'b),
+    pub delay: Option<SpdmDelay>,
+}
+
+impl SpdmAsyncDeviceIo for SpdmBlockingDeviceIo<'_, '_> {
+    fn poll_send(&mut self, _cx: &mut Context<'_>, buffer: &[u8]) -> Poll<SpdmResult> {
+        Poll::Ready(self.device_io.send(buffer))
+    }
+
+    fn poll_receive(
+        &mut self,
+        _cx: &mut Context<'_>,
+        buffer: &mut [u8],
+    ) -> Poll<Result<usize, usize>> {
+        Poll::Ready(self.device_io.receive(buffer))
+    }
+
+    fn poll_delay(&mut self, _cx: &mut Context<'_>, us: u64) -> Poll<bool> {
+        Poll::Ready(match self.delay {
+            Some(delay) => {
+                delay(us);
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+/// Run `future` to completion on this thread. It is polled again at once
+/// while it is pending, which never happens with an SpdmBlockingDeviceIo.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// What the application data of a secured message carries: an SPDM
+/// message, or a message of another protocol tagged with its transport
+/// message type, such as PLDM (MCTP message type 0x01) over MCTP.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SpdmAppMessageType {
+    Spdm,
+    Other(u8),
+}
+
 pub trait SpdmTransportEncap {
     fn encap(
         &mut self,
@@ -50,6 +211,26 @@ pub trait SpdmTransportEncap {
 
     fn decap_app(&mut self, app_buffer: &[u8], spdm_buffer: &mut [u8]) -> SpdmResult<usize>;
 
+    // for non-SPDM application messages in a secured session,
+    // only supported by transports with a message type in the app header
+    fn encap_app_message(
+        &mut self,
+        _message_type: u8,
+        _message: &[u8],
+        _app_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        spdm_result_err!(ENOSYS)
+    }
+
+    fn decap_app_message(
+        &mut self,
+        app_buffer: &[u8],
+        message: &mut [u8],
+    ) -> SpdmResult<(usize, SpdmAppMessageType)> {
+        let used = self.decap_app(app_buffer, message)?;
+        Ok((used, SpdmAppMessageType::Spdm))
+    }
+
     // for session
     fn get_sequence_number_count(&mut self) -> u8;
     fn get_max_random_count(&mut self) -> u16;
@@ -62,8 +243,8 @@ impl Debug for dyn SpdmTransportEncap {
 }
 
 pub struct SpdmContext<'a> {
-    pub device_io: &'a mut dyn SpdmDeviceIo,
-    pub transport_encap: &'a mut dyn SpdmTransportEncap,
+    pub device_io: Option<&'a mut (dyn SpdmDeviceIo + Send)>,
+    pub transport_encap: &'a mut (dyn SpdmTransportEncap + Send),
 
     pub config_info: SpdmConfigInfo,
     pub negotiate_info: SpdmNegotiateInfo,
@@ -72,33 +253,65 @@ pub struct SpdmContext<'a> {
     pub provision_info: SpdmProvisionInfo,
     pub peer_info: SpdmPeerInfo,
 
-    pub session: [SpdmSession; config::MAX_SPDM_SESSION_COUNT],
+    pub session: SpdmSessionTable,
 }
 
 impl<'a> SpdmContext<'a> {
     pub fn new(
-        device_io: &'a mut dyn SpdmDeviceIo,
-        transport_encap: &'a mut dyn SpdmTransportEncap,
+        device_io: &'a mut (dyn SpdmDeviceIo + Send),
+        transport_encap: &'a mut (dyn SpdmTransportEncap + Send),
+        config_info: SpdmConfigInfo,
+        provision_info: SpdmProvisionInfo,
+    ) -> Self {
+        let mut context = Self::new_without_device_io(transport_encap, config_info, provision_info);
+        context.device_io = Some(device_io);
+        context
+    }
+
+    /// A context whose transport messages are passed in and out by the
+    /// caller. Sending or receiving with the device IO fails with ENODEV.
+    pub fn new_without_device_io(
+        transport_encap: &'a mut (dyn SpdmTransportEncap + Send),
         config_info: SpdmConfigInfo,
         provision_info: SpdmProvisionInfo,
     ) -> Self {
         SpdmContext {
-            device_io,
+            device_io: None,
             transport_encap,
             config_info,
             negotiate_info: SpdmNegotiateInfo::default(),
             runtime_info: SpdmRuntimeInfo::default(),
             provision_info,
             peer_info: SpdmPeerInfo::default(),
-            session: [SpdmSession::new(); config::MAX_SPDM_SESSION_COUNT],
+            session: SpdmSessionTable::new(),
+        }
+    }
+
+    pub fn device_io_send(&mut self, buffer: &[u8]) -> SpdmResult {
+        match self.device_io.as_mut() {
+            Some(device_io) => device_io.send(buffer),
+            None => spdm_result_err!(ENODEV),
+        }
+    }
+
+    pub fn device_io_receive(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+        match self.device_io.as_mut() {
+            Some(device_io) => device_io.receive(buffer),
+            None => Err(0),
         }
     }
 
     pub fn get_hash_size(&self) -> u16 {
-        self.negotiate_info.base_hash_sel.get_size()
+        match &self.negotiate_info.ext_hash_sel {
+            Some(ext_hash_sel) => crypto::ext_alg::get_hash_size(ext_hash_sel).unwrap_or(0),
+            None => self.negotiate_info.base_hash_sel.get_size(),
+        }
     }
     pub fn get_asym_key_size(&self) -> u16 {
-        self.negotiate_info.base_asym_sel.get_size()
+        match &self.negotiate_info.ext_asym_sel {
+            Some(ext_asym_sel) => crypto::ext_alg::get_signature_size(ext_asym_sel).unwrap_or(0),
+            None => self.negotiate_info.base_asym_sel.get_size(),
+        }
     }
     pub fn get_dhe_key_size(&self) -> u16 {
         self.negotiate_info.dhe_sel.get_size()
@@ -108,26 +321,141 @@ impl<'a> SpdmContext<'a> {
         self.runtime_info = SpdmRuntimeInfo::default();
     }
 
-    pub fn get_immutable_session_via_id(&self, session_id: u32) -> Option<&SpdmSession> {
-        for session in self.session.iter() {
-            if session.get_session_id() == session_id {
-                return Some(session);
+    /// Tear down all the sessions, e.g. when the connection starts over.
+    pub fn teardown_sessions(&mut self) {
+        for session in self.session.iter_mut() {
+            let session_id = session.get_session_id();
+            if session_id != 0 {
+                let _ = session.teardown(session_id);
             }
         }
-        None
     }
 
-    pub fn get_session_via_id(&mut self, session_id: u32) -> Option<&mut SpdmSession> {
-        for session in self.session.iter_mut() {
-            if session.get_session_id() == session_id {
-                return Some(session);
+    /// Whether the capabilities from GET_CAPABILITIES/CAPABILITIES allow the
+    /// request. Either side may ask, the selection is the same for both.
+    /// Responses and unknown codes are never supported.
+    pub fn is_request_supported(&self, request_response_code: SpdmResponseResponseCode) -> bool {
+        let req_capabilities = self.negotiate_info.req_capabilities_sel;
+        let rsp_capabilities = self.negotiate_info.rsp_capabilities_sel;
+        match request_response_code {
+            SpdmResponseResponseCode::SpdmRequestGetVersion
+            | SpdmResponseResponseCode::SpdmRequestGetCapabilities
+            | SpdmResponseResponseCode::SpdmRequestNegotiateAlgorithms
+            | SpdmResponseResponseCode::SpdmRequestEndSession => true,
+            SpdmResponseResponseCode::SpdmRequestGetDigests
+            | SpdmResponseResponseCode::SpdmRequestGetCertificate => {
+                rsp_capabilities.contains(SpdmResponseCapabilityFlags::CERT_CAP)
+            }
+            SpdmResponseResponseCode::SpdmRequestChallenge => {
+                rsp_capabilities.contains(SpdmResponseCapabilityFlags::CHAL_CAP)
+            }
+            SpdmResponseResponseCode::SpdmRequestGetMeasurements => {
+                rsp_capabilities.intersects(SpdmResponseCapabilityFlags::MEAS_CAP_MASK)
+            }
+            SpdmResponseResponseCode::SpdmRequestKeyExchange
+            | SpdmResponseResponseCode::SpdmRequestFinish => {
+                req_capabilities.contains(SpdmRequestCapabilityFlags::KEY_EX_CAP)
+                    && rsp_capabilities.contains(SpdmResponseCapabilityFlags::KEY_EX_CAP)
+            }
+            SpdmResponseResponseCode::SpdmRequestPskExchange
+            | SpdmResponseResponseCode::SpdmRequestPskFinish => {
+                req_capabilities.intersects(SpdmRequestCapabilityFlags::PSK_CAP_MASK)
+                    && rsp_capabilities.intersects(SpdmResponseCapabilityFlags::PSK_CAP_MASK)
+            }
+            SpdmResponseResponseCode::SpdmRequestHeartbeat => {
+                req_capabilities.contains(SpdmRequestCapabilityFlags::HBEAT_CAP)
+                    && rsp_capabilities.contains(SpdmResponseCapabilityFlags::HBEAT_CAP)
+            }
+            SpdmResponseResponseCode::SpdmRequestKeyUpdate => {
+                req_capabilities.contains(SpdmRequestCapabilityFlags::KEY_UPD_CAP)
+                    && rsp_capabilities.contains(SpdmResponseCapabilityFlags::KEY_UPD_CAP)
+            }
+            _ => false,
+        }
+    }
+
+    /// The algorithm classes ALGORITHMS must select one algorithm of, given
+    /// the negotiated capabilities. The base hash is always required.
+    pub fn get_required_algorithms(&self) -> SpdmRequiredAlgorithms {
+        let req_capabilities = self.negotiate_info.req_capabilities_sel;
+        let rsp_capabilities = self.negotiate_info.rsp_capabilities_sel;
+        let key_ex_cap = req_capabilities.contains(SpdmRequestCapabilityFlags::KEY_EX_CAP)
+            && rsp_capabilities.contains(SpdmResponseCapabilityFlags::KEY_EX_CAP);
+        let psk_cap = req_capabilities.intersects(SpdmRequestCapabilityFlags::PSK_CAP_MASK)
+            && rsp_capabilities.intersects(SpdmResponseCapabilityFlags::PSK_CAP_MASK);
+        SpdmRequiredAlgorithms {
+            measurement: rsp_capabilities.intersects(SpdmResponseCapabilityFlags::MEAS_CAP_MASK),
+            base_asym: key_ex_cap
+                || rsp_capabilities.intersects(
+                    SpdmResponseCapabilityFlags::CHAL_CAP
+                        | SpdmResponseCapabilityFlags::MEAS_CAP_SIG,
+                ),
+            dhe: key_ex_cap,
+            aead: key_ex_cap || psk_cap,
+            req_asym: req_capabilities.contains(SpdmRequestCapabilityFlags::MUT_AUTH_CAP)
+                && rsp_capabilities.contains(SpdmResponseCapabilityFlags::MUT_AUTH_CAP),
+            key_schedule: key_ex_cap || psk_cap,
+        }
+    }
+
+    /// Hash with the negotiated hash algorithm, base or extended.
+    pub fn hash_all(&self, data: &[u8]) -> Option<SpdmDigestStruct> {
+        match &self.negotiate_info.ext_hash_sel {
+            Some(ext_hash_sel) => crypto::ext_alg::hash_all(ext_hash_sel, data),
+            None => crypto::hash::hash_all(self.negotiate_info.base_hash_sel, data),
+        }
+    }
+
+    fn asym_sign(&self, context: &[u8], data: &[u8]) -> Option<SpdmSignatureStruct> {
+        match &self.negotiate_info.ext_asym_sel {
+            Some(ext_asym_sel) => {
+                crypto::ext_alg::sign(self.negotiate_info.base_hash_sel, ext_asym_sel, data)
             }
+            None => crypto::asym_sign::sign(
+                self.negotiate_info.base_hash_sel,
+                self.negotiate_info.base_asym_sel,
+                context,
+                data,
+            ),
+        }
+    }
+
+    fn asym_verify(
+        &self,
+        context: &[u8],
+        public_cert_der: &[u8],
+        data: &[u8],
+        signature: &SpdmSignatureStruct,
+    ) -> SpdmResult {
+        match &self.negotiate_info.ext_asym_sel {
+            Some(ext_asym_sel) => crypto::ext_alg::verify(
+                self.negotiate_info.base_hash_sel,
+                ext_asym_sel,
+                public_cert_der,
+                data,
+                signature,
+            ),
+            None => crypto::asym_verify::verify(
+                self.negotiate_info.base_hash_sel,
+                self.negotiate_info.base_asym_sel,
+                context,
+                public_cert_der,
+                data,
+                signature,
+            ),
         }
-        None
+    }
+
+    pub fn get_immutable_session_via_id(&self, session_id: u32) -> Option<&SpdmSession> {
+        self.session.get(session_id)
+    }
+
+    pub fn get_session_via_id(&mut self, session_id: u32) -> Option<&mut SpdmSession> {
+        self.session.get_mut(session_id)
     }
 
     pub fn get_next_avaiable_session(&mut self) -> Option<&mut SpdmSession> {
-        self.get_session_via_id(0)
+        self.session.allocate()
     }
 
     pub fn calc_req_transcript_data(
@@ -143,11 +471,11 @@ impl<'a> SpdmContext<'a> {
         debug!("message_a - {:02x?}", self.runtime_info.message_a.as_ref());
         if !use_psk {
             let cert_chain_data = &self.peer_info.peer_cert_chain.cert_chain.data[(4usize
-                + self.negotiate_info.base_hash_sel.get_size() as usize)
+                + self.get_hash_size() as usize)
                 ..(self.peer_info.peer_cert_chain.cert_chain.data_size as usize)];
-            let cert_chain_hash =
-                crypto::hash::hash_all(self.negotiate_info.base_hash_sel, cert_chain_data)
-                    .ok_or_else(|| spdm_err!(EFAULT))?;
+            let cert_chain_hash = self
+                .hash_all(cert_chain_data)
+                .ok_or_else(|| spdm_err!(EFAULT))?;
             message
                 .append_message(cert_chain_hash.as_ref())
                 .ok_or_else(|| spdm_err!(ENOMEM))?;
@@ -184,9 +512,9 @@ impl<'a> SpdmContext<'a> {
         if !use_psk {
             let my_cert_chain_data = self.provision_info.my_cert_chain_data.unwrap();
             let cert_chain_data = my_cert_chain_data.as_ref();
-            let cert_chain_hash =
-                crypto::hash::hash_all(self.negotiate_info.base_hash_sel, cert_chain_data)
-                    .ok_or_else(|| spdm_err!(EFAULT))?;
+            let cert_chain_hash = self
+                .hash_all(cert_chain_data)
+                .ok_or_else(|| spdm_err!(EFAULT))?;
 
             message
                 .append_message(cert_chain_hash.as_ref())
@@ -215,9 +543,9 @@ impl<'a> SpdmContext<'a> {
     ) -> SpdmResult<SpdmDigestStruct> {
         let message = self.calc_req_transcript_data(use_psk, message_k, message_f)?;
 
-        let transcript_hash =
-            crypto::hash::hash_all(self.negotiate_info.base_hash_sel, message.as_ref())
-                .ok_or_else(|| spdm_err!(EFAULT))?;
+        let transcript_hash = self
+            .hash_all(message.as_ref())
+            .ok_or_else(|| spdm_err!(EFAULT))?;
         Ok(transcript_hash)
     }
 
@@ -229,9 +557,9 @@ impl<'a> SpdmContext<'a> {
     ) -> SpdmResult<SpdmDigestStruct> {
         let message = self.calc_rsp_transcript_data(use_psk, message_k, message_f)?;
 
-        let transcript_hash =
-            crypto::hash::hash_all(self.negotiate_info.base_hash_sel, message.as_ref())
-                .ok_or_else(|| spdm_err!(EFAULT))?;
+        let transcript_hash = self
+            .hash_all(message.as_ref())
+            .ok_or_else(|| spdm_err!(EFAULT))?;
         Ok(transcript_hash)
     }
 
@@ -251,18 +579,17 @@ impl<'a> SpdmContext<'a> {
             .ok_or_else(|| spdm_err!(ENOMEM))?;
         // we dont need create message hash for verify
         // we just print message hash for debug purpose
-        let message_hash =
-            crypto::hash::hash_all(self.negotiate_info.base_hash_sel, message.as_ref())
-                .ok_or_else(|| spdm_err!(EFAULT))?;
+        let message_hash = self
+            .hash_all(message.as_ref())
+            .ok_or_else(|| spdm_err!(EFAULT))?;
         debug!("message_hash - {:02x?}", message_hash.as_ref());
 
         let cert_chain_data = &self.peer_info.peer_cert_chain.cert_chain.data[(4usize
-            + self.negotiate_info.base_hash_sel.get_size() as usize)
+            + self.get_hash_size() as usize)
             ..(self.peer_info.peer_cert_chain.cert_chain.data_size as usize)];
 
-        crypto::asym_verify::verify(
-            self.negotiate_info.base_hash_sel,
-            self.negotiate_info.base_asym_sel,
+        self.asym_verify(
+            SPDM_SIGNING_CONTEXT_CHALLENGE_AUTH,
             cert_chain_data,
             message.as_ref(),
             signature,
@@ -282,17 +609,13 @@ impl<'a> SpdmContext<'a> {
             .ok_or_else(|| spdm_err!(ENOMEM))?;
         // we dont need create message hash for verify
         // we just print message hash for debug purpose
-        let message_hash =
-            crypto::hash::hash_all(self.negotiate_info.base_hash_sel, message.as_ref())
-                .ok_or_else(|| spdm_err!(EFAULT))?;
+        let message_hash = self
+            .hash_all(message.as_ref())
+            .ok_or_else(|| spdm_err!(EFAULT))?;
         debug!("message_hash - {:02x?}", message_hash.as_ref());
 
-        crypto::asym_sign::sign(
-            self.negotiate_info.base_hash_sel,
-            self.negotiate_info.base_asym_sel,
-            message.as_ref(),
-        )
-        .ok_or_else(|| spdm_err!(EFAULT))
+        self.asym_sign(SPDM_SIGNING_CONTEXT_CHALLENGE_AUTH, message.as_ref())
+            .ok_or_else(|| spdm_err!(EFAULT))
     }
 
     pub fn verify_measurement_signature(&mut self, signature: &SpdmSignatureStruct) -> SpdmResult {
@@ -302,18 +625,17 @@ impl<'a> SpdmContext<'a> {
             .ok_or_else(|| spdm_err!(ENOMEM))?;
         // we dont need create message hash for verify
         // we just print message hash for debug purpose
-        let message_hash =
-            crypto::hash::hash_all(self.negotiate_info.base_hash_sel, message.as_ref())
-                .ok_or_else(|| spdm_err!(EFAULT))?;
+        let message_hash = self
+            .hash_all(message.as_ref())
+            .ok_or_else(|| spdm_err!(EFAULT))?;
         debug!("message_hash - {:02x?}", message_hash.as_ref());
 
         let cert_chain_data = &self.peer_info.peer_cert_chain.cert_chain.data[(4usize
-            + self.negotiate_info.base_hash_sel.get_size() as usize)
+            + self.get_hash_size() as usize)
             ..(self.peer_info.peer_cert_chain.cert_chain.data_size as usize)];
 
-        crypto::asym_verify::verify(
-            self.negotiate_info.base_hash_sel,
-            self.negotiate_info.base_asym_sel,
+        self.asym_verify(
+            SPDM_SIGNING_CONTEXT_MEASUREMENTS,
             cert_chain_data,
             message.as_ref(),
             signature,
@@ -327,17 +649,13 @@ impl<'a> SpdmContext<'a> {
             .ok_or_else(|| spdm_err!(ENOMEM))?;
         // we dont need create message hash for verify
         // we just print message hash for debug purpose
-        let message_hash =
-            crypto::hash::hash_all(self.negotiate_info.base_hash_sel, message.as_ref())
-                .ok_or_else(|| spdm_err!(EFAULT))?;
+        let message_hash = self
+            .hash_all(message.as_ref())
+            .ok_or_else(|| spdm_err!(EFAULT))?;
         debug!("message_hash - {:02x?}", message_hash.as_ref());
 
-        crypto::asym_sign::sign(
-            self.negotiate_info.base_hash_sel,
-            self.negotiate_info.base_asym_sel,
-            message.as_ref(),
-        )
-        .ok_or_else(|| spdm_err!(EFAULT))
+        self.asym_sign(SPDM_SIGNING_CONTEXT_MEASUREMENTS, message.as_ref())
+            .ok_or_else(|| spdm_err!(EFAULT))
     }
 
     pub fn verify_key_exchange_rsp_signature(
@@ -348,18 +666,17 @@ impl<'a> SpdmContext<'a> {
         let message = self.calc_req_transcript_data(false, message_k, None)?;
         // we dont need create message hash for verify
         // we just print message hash for debug purpose
-        let message_hash =
-            crypto::hash::hash_all(self.negotiate_info.base_hash_sel, message.as_ref())
-                .ok_or_else(|| spdm_err!(EFAULT))?;
+        let message_hash = self
+            .hash_all(message.as_ref())
+            .ok_or_else(|| spdm_err!(EFAULT))?;
         debug!("message_hash - {:02x?}", message_hash.as_ref());
 
         let cert_chain_data = &self.peer_info.peer_cert_chain.cert_chain.data[(4usize
-            + self.negotiate_info.base_hash_sel.get_size() as usize)
+            + self.get_hash_size() as usize)
             ..(self.peer_info.peer_cert_chain.cert_chain.data_size as usize)];
 
-        crypto::asym_verify::verify(
-            self.negotiate_info.base_hash_sel,
-            self.negotiate_info.base_asym_sel,
+        self.asym_verify(
+            SPDM_SIGNING_CONTEXT_KEY_EXCHANGE_RSP,
             cert_chain_data,
             message.as_ref(),
             signature,
@@ -373,17 +690,13 @@ impl<'a> SpdmContext<'a> {
         let message = self.calc_rsp_transcript_data(false, message_k, None)?;
         // we dont need create message hash for verify
         // we just print message hash for debug purpose
-        let message_hash =
-            crypto::hash::hash_all(self.negotiate_info.base_hash_sel, message.as_ref())
-                .ok_or_else(|| spdm_err!(EFAULT))?;
+        let message_hash = self
+            .hash_all(message.as_ref())
+            .ok_or_else(|| spdm_err!(EFAULT))?;
         debug!("message_hash - {:02x?}", message_hash.as_ref());
 
-        crypto::asym_sign::sign(
-            self.negotiate_info.base_hash_sel,
-            self.negotiate_info.base_asym_sel,
-            message.as_ref(),
-        )
-        .ok_or_else(|| spdm_err!(EFAULT))
+        self.asym_sign(SPDM_SIGNING_CONTEXT_KEY_EXCHANGE_RSP, message.as_ref())
+            .ok_or_else(|| spdm_err!(EFAULT))
     }
 
     pub fn encap(&mut self, send_buffer: &[u8], transport_buffer: &mut [u8]) -> SpdmResult<usize> {
@@ -403,13 +716,52 @@ impl<'a> SpdmContext<'a> {
             .transport_encap
             .encap_app(send_buffer, &mut app_buffer)?;
 
+        self.encode_secured_app_data(
+            session_id,
+            &app_buffer[0..used],
+            transport_buffer,
+            is_requester,
+        )
+    }
+
+    /// Encode a non-SPDM application message, only in an established session.
+    pub fn encode_secured_app_message(
+        &mut self,
+        session_id: u32,
+        message_type: u8,
+        send_buffer: &[u8],
+        transport_buffer: &mut [u8],
+        is_requester: bool,
+    ) -> SpdmResult<usize> {
+        self.check_session_established(session_id)?;
+
+        let mut app_buffer = [0u8; config::MAX_SPDM_MESSAGE_BUFFER_SIZE];
+        let used =
+            self.transport_encap
+                .encap_app_message(message_type, send_buffer, &mut app_buffer)?;
+
+        self.encode_secured_app_data(
+            session_id,
+            &app_buffer[0..used],
+            transport_buffer,
+            is_requester,
+        )
+    }
+
+    fn encode_secured_app_data(
+        &mut self,
+        session_id: u32,
+        app_buffer: &[u8],
+        transport_buffer: &mut [u8],
+        is_requester: bool,
+    ) -> SpdmResult<usize> {
         let spdm_session = self
             .get_session_via_id(session_id)
             .ok_or(spdm_err!(EINVAL))?;
 
         let mut encoded_send_buffer = [0u8; config::MAX_SPDM_MESSAGE_BUFFER_SIZE];
         let encode_size = spdm_session.encode_spdm_secured_message(
-            &app_buffer[0..used],
+            app_buffer,
             &mut encoded_send_buffer,
             is_requester,
         )?;
@@ -418,6 +770,16 @@ impl<'a> SpdmContext<'a> {
             .encap(&encoded_send_buffer[..encode_size], transport_buffer, true)
     }
 
+    pub fn check_session_established(&self, session_id: u32) -> SpdmResult {
+        let spdm_session = self
+            .get_immutable_session_via_id(session_id)
+            .ok_or(spdm_err!(EINVAL))?;
+        if spdm_session.get_session_state() != SpdmSessionState::SpdmSessionEstablished {
+            return spdm_result_err!(EINVAL);
+        }
+        Ok(())
+    }
+
     pub fn decap(
         &mut self,
         transport_buffer: &[u8],
@@ -439,6 +801,42 @@ impl<'a> SpdmContext<'a> {
         session_id: u32,
         transport_buffer: &[u8],
         receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        let mut app_buffer = [0u8; config::MAX_SPDM_MESSAGE_BUFFER_SIZE];
+        let decode_size =
+            self.decode_secured_app_data(session_id, transport_buffer, &mut app_buffer)?;
+
+        let used = self
+            .transport_encap
+            .decap_app(&app_buffer[0..decode_size], receive_buffer)?;
+
+        Ok(used)
+    }
+
+    /// Decode a secured message carrying either SPDM or another protocol,
+    /// in an established session.
+    pub fn decode_secured_app_message(
+        &mut self,
+        session_id: u32,
+        transport_buffer: &[u8],
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<(usize, SpdmAppMessageType)> {
+        // before decryption, which would advance the sequence number
+        self.check_session_established(session_id)?;
+
+        let mut app_buffer = [0u8; config::MAX_SPDM_MESSAGE_BUFFER_SIZE];
+        let decode_size =
+            self.decode_secured_app_data(session_id, transport_buffer, &mut app_buffer)?;
+
+        self.transport_encap
+            .decap_app_message(&app_buffer[0..decode_size], receive_buffer)
+    }
+
+    fn decode_secured_app_data(
+        &mut self,
+        session_id: u32,
+        transport_buffer: &[u8],
+        app_buffer: &mut [u8],
     ) -> SpdmResult<usize> {
         let mut encoded_receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let (used, secured_message) = self
@@ -453,18 +851,7 @@ impl<'a> SpdmContext<'a> {
             .get_session_via_id(session_id)
             .ok_or(spdm_err!(EINVAL))?;
 
-        let mut app_buffer = [0u8; config::MAX_SPDM_MESSAGE_BUFFER_SIZE];
-        let decode_size = spdm_session.decode_spdm_secured_message(
-            &encoded_receive_buffer[..used],
-            &mut app_buffer,
-            false,
-        )?;
-
-        let used = self
-            .transport_encap
-            .decap_app(&app_buffer[0..decode_size], receive_buffer)?;
-
-        Ok(used)
+        spdm_session.decode_spdm_secured_message(&encoded_receive_buffer[..used], app_buffer, false)
     }
 }
 
@@ -483,9 +870,77 @@ pub struct SpdmConfigInfo {
     pub aead_algo: SpdmAeadAlgo,
     pub req_asym_algo: SpdmReqAsymAlgo,
     pub key_schedule_algo: SpdmKeyScheduleAlgo,
+    // Ordered algorithm preference, most preferred first. Empty entries
+    // are ignored, and a list without any entry keeps the default order.
+    // The key schedule has none: SPDM 1.1 defines a single one.
+    pub measurement_hash_priority: [SpdmMeasurementHashAlgo; SPDM_MAX_ALGO_PRIORITY_COUNT],
+    pub base_hash_priority: [SpdmBaseHashAlgo; SPDM_MAX_ALGO_PRIORITY_COUNT],
+    pub base_asym_priority: [SpdmBaseAsymAlgo; SPDM_MAX_ALGO_PRIORITY_COUNT],
+    pub dhe_priority: [SpdmDheAlgo; SPDM_MAX_ALGO_PRIORITY_COUNT],
+    pub aead_priority: [SpdmAeadAlgo; SPDM_MAX_ALGO_PRIORITY_COUNT],
+    pub req_asym_priority: [SpdmReqAsymAlgo; SPDM_MAX_ALGO_PRIORITY_COUNT],
+    // Algorithms weaker than this security strength, in bits, are never
+    // negotiated. 0 means no minimum.
+    pub min_security_strength: u16,
+    // Extended algorithms, most preferred first. One is only selected when
+    // there is no common base algorithm and crypto::ext_alg supports it.
+    pub ext_asym_algo_count: u8,
+    pub ext_asym_algo: [SpdmExtAlgStruct; config::MAX_SPDM_EXTEND_ASYM_ALGO_COUNT],
+    pub ext_hash_algo_count: u8,
+    pub ext_hash_algo: [SpdmExtAlgStruct; config::MAX_SPDM_EXTEND_HASH_ALGO_COUNT],
 }
 
-#[derive(Debug, Default)]
+impl SpdmConfigInfo {
+    // The algorithms we may negotiate: the configured ones, limited by the
+    // preference lists, the minimum security strength and, but for the
+    // measurement hash, what the registered crypto implements.
+    pub fn get_acceptable_measurement_hash_algo(&self) -> SpdmMeasurementHashAlgo {
+        self.measurement_hash_algo
+            .acceptable(&self.measurement_hash_priority, self.min_security_strength)
+    }
+    pub fn get_acceptable_base_hash_algo(&self) -> SpdmBaseHashAlgo {
+        self.base_hash_algo
+            .acceptable(&self.base_hash_priority, self.min_security_strength)
+            & crypto::hash::supported_algo()
+    }
+    pub fn get_acceptable_base_asym_algo(&self) -> SpdmBaseAsymAlgo {
+        self.base_asym_algo
+            .acceptable(&self.base_asym_priority, self.min_security_strength)
+            & crypto::asym_verify::supported_algo()
+    }
+    pub fn get_acceptable_dhe_algo(&self) -> SpdmDheAlgo {
+        self.dhe_algo
+            .acceptable(&self.dhe_priority, self.min_security_strength)
+            & crypto::dhe::supported_algo()
+    }
+    pub fn get_acceptable_aead_algo(&self) -> SpdmAeadAlgo {
+        self.aead_algo
+            .acceptable(&self.aead_priority, self.min_security_strength)
+            & crypto::aead::supported_algo()
+    }
+    pub fn get_acceptable_req_asym_algo(&self) -> SpdmReqAsymAlgo {
+        // the same bits as SpdmBaseAsymAlgo
+        let supported = SpdmReqAsymAlgo::from_bits_truncate(
+            crypto::asym_verify::supported_algo().bits() as u16,
+        );
+        self.req_asym_algo
+            .acceptable(&self.req_asym_priority, self.min_security_strength)
+            & supported
+    }
+}
+
+/// See SpdmContext::get_required_algorithms.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SpdmRequiredAlgorithms {
+    pub measurement: bool,
+    pub base_asym: bool,
+    pub dhe: bool,
+    pub aead: bool,
+    pub req_asym: bool,
+    pub key_schedule: bool,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct SpdmNegotiateInfo {
     pub spdm_version_sel: SpdmVersion,
     pub req_capabilities_sel: SpdmRequestCapabilityFlags,
@@ -500,6 +955,9 @@ pub struct SpdmNegotiateInfo {
     pub aead_sel: SpdmAeadAlgo,
     pub req_asym_sel: SpdmReqAsymAlgo,
     pub key_schedule_sel: SpdmKeyScheduleAlgo,
+    // set instead of base_asym_sel / base_hash_sel
+    pub ext_asym_sel: Option<SpdmExtAlgStruct>,
+    pub ext_hash_sel: Option<SpdmExtAlgStruct>,
 }
 
 // TBD ManagedSmallBuffer
@@ -531,8 +989,25 @@ impl Default for ManagedBuffer {
     }
 }
 
+/// The connection states, in the order they are reached.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default)]
+pub enum SpdmConnectionState {
+    // Before GET_VERSION
+    #[default]
+    SpdmConnectionNotStarted,
+    // After GET_VERSION, before GET_CAPABILITIES
+    SpdmConnectionAfterVersion,
+    // After GET_CAPABILITIES, before NEGOTIATE_ALGORITHMS
+    SpdmConnectionAfterCapabilities,
+    // After NEGOTIATE_ALGORITHMS
+    SpdmConnectionNegotiated,
+    // After CHALLENGE
+    SpdmConnectionAuthenticated,
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 pub struct SpdmRuntimeInfo {
+    pub connection_state: SpdmConnectionState,
     pub need_measurement_summary_hash: bool,
     pub need_measurement_signature: bool,
     pub message_a: ManagedBuffer,
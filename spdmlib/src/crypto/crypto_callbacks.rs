@@ -9,12 +9,16 @@ use alloc::boxed::Box;
 
 use crate::msgs::{
     SpdmAeadAlgo, SpdmBaseAsymAlgo, SpdmBaseHashAlgo, SpdmDheAlgo, SpdmDheExchangeStruct,
-    SpdmDheFinalKeyStruct, SpdmDigestStruct, SpdmSignatureStruct,
+    SpdmDheFinalKeyStruct, SpdmDigestStruct, SpdmExtAlgStruct, SpdmSignatureStruct,
 };
 
+/// `supported_algo_cb` returns the hash algorithms that the registered hash,
+/// HMAC and HKDF callbacks all implement; only those are negotiated.
 #[derive(Clone, Copy)]
 pub struct SpdmHash {
     pub hash_all_cb: fn(base_hash_algo: SpdmBaseHashAlgo, data: &[u8]) -> Option<SpdmDigestStruct>,
+
+    pub supported_algo_cb: fn() -> SpdmBaseHashAlgo,
 }
 
 #[derive(Clone, Copy)]
@@ -50,27 +54,68 @@ type DecryptCb = fn(
     plain_text: &mut [u8],
 ) -> SpdmResult<usize>;
 
+/// `supported_algo_cb` returns the AEAD algorithms implemented; only those
+/// are negotiated.
 #[derive(Clone, Copy)]
 pub struct SpdmAead {
     pub encrypt_cb: EncryptCb,
 
     pub decrypt_cb: DecryptCb,
+
+    pub supported_algo_cb: fn() -> SpdmAeadAlgo,
 }
 
+/// `context` names the signer and the message signed, see
+/// `common::SPDM_SIGNING_CONTEXT_*`. SM2 uses it as the signer ID, the
+/// other algorithms ignore it.
 #[derive(Clone, Copy)]
 pub struct SpdmAsymSign {
     pub sign_cb: fn(
         base_hash_algo: SpdmBaseHashAlgo,
         base_asym_algo: SpdmBaseAsymAlgo,
+        context: &[u8],
         data: &[u8],
     ) -> Option<SpdmSignatureStruct>,
 }
 
+type AsymVerifyCb = fn(
+    base_hash_algo: SpdmBaseHashAlgo,
+    base_asym_algo: SpdmBaseAsymAlgo,
+    context: &[u8],
+    public_cert_der: &[u8],
+    data: &[u8],
+    signature: &SpdmSignatureStruct,
+) -> SpdmResult;
+
+/// `supported_algo_cb` returns the signature algorithms verified; only
+/// those are negotiated, for the responder and the requester signatures.
 #[derive(Clone, Copy)]
 pub struct SpdmAsymVerify {
+    pub verify_cb: AsymVerifyCb,
+
+    pub supported_algo_cb: fn() -> SpdmBaseAsymAlgo,
+}
+
+/// Extended (registry or vendor defined) algorithms. An algorithm is
+/// supported if its size callback returns a size of at most
+/// SPDM_MAX_HASH_SIZE or SPDM_MAX_ASYM_KEY_SIZE.
+#[derive(Clone, Copy)]
+pub struct SpdmExtAlg {
+    pub hash_size_cb: fn(ext_hash_algo: &SpdmExtAlgStruct) -> Option<u16>,
+
+    pub signature_size_cb: fn(ext_asym_algo: &SpdmExtAlgStruct) -> Option<u16>,
+
+    pub hash_all_cb: fn(ext_hash_algo: &SpdmExtAlgStruct, data: &[u8]) -> Option<SpdmDigestStruct>,
+
+    pub sign_cb: fn(
+        base_hash_algo: SpdmBaseHashAlgo,
+        ext_asym_algo: &SpdmExtAlgStruct,
+        data: &[u8],
+    ) -> Option<SpdmSignatureStruct>,
+
     pub verify_cb: fn(
         base_hash_algo: SpdmBaseHashAlgo,
-        base_asym_algo: SpdmBaseAsymAlgo,
+        ext_asym_algo: &SpdmExtAlgStruct,
         public_cert_der: &[u8],
         data: &[u8],
         signature: &SpdmSignatureStruct,
@@ -99,12 +144,18 @@ pub struct SpdmCertOperation {
 type GenerateKeyPairCb =
     fn(dhe_algo: SpdmDheAlgo) -> Option<(SpdmDheExchangeStruct, Box<dyn SpdmDheKeyExchange>)>;
 
+/// `supported_algo_cb` returns the key exchange groups implemented; only
+/// those are negotiated.
 #[derive(Clone, Copy)]
 pub struct SpdmDhe {
     pub generate_key_pair_cb: GenerateKeyPairCb,
+
+    pub supported_algo_cb: fn() -> SpdmDheAlgo,
 }
 
-pub trait SpdmDheKeyExchange {
+/// The private key is kept across the await of the key exchange response,
+/// so it is Send for the async requester.
+pub trait SpdmDheKeyExchange: Send {
     fn compute_final_key(
         self: Box<Self>,
         peer_pub_key: &SpdmDheExchangeStruct,
@@ -0,0 +1,472 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+//! Finite field Diffie-Hellman over the RFC 7919 groups.
+//!
+//! Neither ring nor the RustCrypto curve crates offer these groups, so the
+//! exponentiation is done with crypto-bigint's constant time Montgomery
+//! arithmetic. The openssl crate cannot name the groups either and takes
+//! just the primes.
+
+#[cfg(any(feature = "spdm-ring", feature = "spdm-rustcrypto"))]
+extern crate alloc;
+#[cfg(any(feature = "spdm-ring", feature = "spdm-rustcrypto"))]
+use alloc::vec::Vec;
+#[cfg(any(feature = "spdm-ring", feature = "spdm-rustcrypto"))]
+use crypto_bigint::modular::runtime_mod::{DynResidue, DynResidueParams};
+#[cfg(any(feature = "spdm-ring", feature = "spdm-rustcrypto"))]
+use crypto_bigint::{Uint, U2048, U3072, U384, U4096};
+
+// RFC 7919 appendix A.
+const FFDHE_2048_P: [u8; 256] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xad, 0xf8, 0x54, 0x58, 0xa2, 0xbb, 0x4a, 0x9a,
+    0xaf, 0xdc, 0x56, 0x20, 0x27, 0x3d, 0x3c, 0xf1, 0xd8, 0xb9, 0xc5, 0x83, 0xce, 0x2d, 0x36, 0x95,
+    0xa9, 0xe1, 0x36, 0x41, 0x14, 0x64, 0x33, 0xfb, 0xcc, 0x93, 0x9d, 0xce, 0x24, 0x9b, 0x3e, 0xf9,
+    0x7d, 0x2f, 0xe3, 0x63, 0x63, 0x0c, 0x75, 0xd8, 0xf6, 0x81, 0xb2, 0x02, 0xae, 0xc4, 0x61, 0x7a,
+    0xd3, 0xdf, 0x1e, 0xd5, 0xd5, 0xfd, 0x65, 0x61, 0x24, 0x33, 0xf5, 0x1f, 0x5f, 0x06, 0x6e, 0xd0,
+    0x85, 0x63, 0x65, 0x55, 0x3d, 0xed, 0x1a, 0xf3, 0xb5, 0x57, 0x13, 0x5e, 0x7f, 0x57, 0xc9, 0x35,
+    0x98, 0x4f, 0x0c, 0x70, 0xe0, 0xe6, 0x8b, 0x77, 0xe2, 0xa6, 0x89, 0xda, 0xf3, 0xef, 0xe8, 0x72,
+    0x1d, 0xf1, 0x58, 0xa1, 0x36, 0xad, 0xe7, 0x35, 0x30, 0xac, 0xca, 0x4f, 0x48, 0x3a, 0x79, 0x7a,
+    0xbc, 0x0a, 0xb1, 0x82, 0xb3, 0x24, 0xfb, 0x61, 0xd1, 0x08, 0xa9, 0x4b, 0xb2, 0xc8, 0xe3, 0xfb,
+    0xb9, 0x6a, 0xda, 0xb7, 0x60, 0xd7, 0xf4, 0x68, 0x1d, 0x4f, 0x42, 0xa3, 0xde, 0x39, 0x4d, 0xf4,
+    0xae, 0x56, 0xed, 0xe7, 0x63, 0x72, 0xbb, 0x19, 0x0b, 0x07, 0xa7, 0xc8, 0xee, 0x0a, 0x6d, 0x70,
+    0x9e, 0x02, 0xfc, 0xe1, 0xcd, 0xf7, 0xe2, 0xec, 0xc0, 0x34, 0x04, 0xcd, 0x28, 0x34, 0x2f, 0x61,
+    0x91, 0x72, 0xfe, 0x9c, 0xe9, 0x85, 0x83, 0xff, 0x8e, 0x4f, 0x12, 0x32, 0xee, 0xf2, 0x81, 0x83,
+    0xc3, 0xfe, 0x3b, 0x1b, 0x4c, 0x6f, 0xad, 0x73, 0x3b, 0xb5, 0xfc, 0xbc, 0x2e, 0xc2, 0x20, 0x05,
+    0xc5, 0x8e, 0xf1, 0x83, 0x7d, 0x16, 0x83, 0xb2, 0xc6, 0xf3, 0x4a, 0x26, 0xc1, 0xb2, 0xef, 0xfa,
+    0x88, 0x6b, 0x42, 0x38, 0x61, 0x28, 0x5c, 0x97, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+];
+
+const FFDHE_3072_P: [u8; 384] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xad, 0xf8, 0x54, 0x58, 0xa2, 0xbb, 0x4a, 0x9a,
+    0xaf, 0xdc, 0x56, 0x20, 0x27, 0x3d, 0x3c, 0xf1, 0xd8, 0xb9, 0xc5, 0x83, 0xce, 0x2d, 0x36, 0x95,
+    0xa9, 0xe1, 0x36, 0x41, 0x14, 0x64, 0x33, 0xfb, 0xcc, 0x93, 0x9d, 0xce, 0x24, 0x9b, 0x3e, 0xf9,
+    0x7d, 0x2f, 0xe3, 0x63, 0x63, 0x0c, 0x75, 0xd8, 0xf6, 0x81, 0xb2, 0x02, 0xae, 0xc4, 0x61, 0x7a,
+    0xd3, 0xdf, 0x1e, 0xd5, 0xd5, 0xfd, 0x65, 0x61, 0x24, 0x33, 0xf5, 0x1f, 0x5f, 0x06, 0x6e, 0xd0,
+    0x85, 0x63, 0x65, 0x55, 0x3d, 0xed, 0x1a, 0xf3, 0xb5, 0x57, 0x13, 0x5e, 0x7f, 0x57, 0xc9, 0x35,
+    0x98, 0x4f, 0x0c, 0x70, 0xe0, 0xe6, 0x8b, 0x77, 0xe2, 0xa6, 0x89, 0xda, 0xf3, 0xef, 0xe8, 0x72,
+    0x1d, 0xf1, 0x58, 0xa1, 0x36, 0xad, 0xe7, 0x35, 0x30, 0xac, 0xca, 0x4f, 0x48, 0x3a, 0x79, 0x7a,
+    0xbc, 0x0a, 0xb1, 0x82, 0xb3, 0x24, 0xfb, 0x61, 0xd1, 0x08, 0xa9, 0x4b, 0xb2, 0xc8, 0xe3, 0xfb,
+    0xb9, 0x6a, 0xda, 0xb7, 0x60, 0xd7, 0xf4, 0x68, 0x1d, 0x4f, 0x42, 0xa3, 0xde, 0x39, 0x4d, 0xf4,
+    0xae, 0x56, 0xed, 0xe7, 0x63, 0x72, 0xbb, 0x19, 0x0b, 0x07, 0xa7, 0xc8, 0xee, 0x0a, 0x6d, 0x70,
+    0x9e, 0x02, 0xfc, 0xe1, 0xcd, 0xf7, 0xe2, 0xec, 0xc0, 0x34, 0x04, 0xcd, 0x28, 0x34, 0x2f, 0x61,
+    0x91, 0x72, 0xfe, 0x9c, 0xe9, 0x85, 0x83, 0xff, 0x8e, 0x4f, 0x12, 0x32, 0xee, 0xf2, 0x81, 0x83,
+    0xc3, 0xfe, 0x3b, 0x1b, 0x4c, 0x6f, 0xad, 0x73, 0x3b, 0xb5, 0xfc, 0xbc, 0x2e, 0xc2, 0x20, 0x05,
+    0xc5, 0x8e, 0xf1, 0x83, 0x7d, 0x16, 0x83, 0xb2, 0xc6, 0xf3, 0x4a, 0x26, 0xc1, 0xb2, 0xef, 0xfa,
+    0x88, 0x6b, 0x42, 0x38, 0x61, 0x1f, 0xcf, 0xdc, 0xde, 0x35, 0x5b, 0x3b, 0x65, 0x19, 0x03, 0x5b,
+    0xbc, 0x34, 0xf4, 0xde, 0xf9, 0x9c, 0x02, 0x38, 0x61, 0xb4, 0x6f, 0xc9, 0xd6, 0xe6, 0xc9, 0x07,
+    0x7a, 0xd9, 0x1d, 0x26, 0x91, 0xf7, 0xf7, 0xee, 0x59, 0x8c, 0xb0, 0xfa, 0xc1, 0x86, 0xd9, 0x1c,
+    0xae, 0xfe, 0x13, 0x09, 0x85, 0x13, 0x92, 0x70, 0xb4, 0x13, 0x0c, 0x93, 0xbc, 0x43, 0x79, 0x44,
+    0xf4, 0xfd, 0x44, 0x52, 0xe2, 0xd7, 0x4d, 0xd3, 0x64, 0xf2, 0xe2, 0x1e, 0x71, 0xf5, 0x4b, 0xff,
+    0x5c, 0xae, 0x82, 0xab, 0x9c, 0x9d, 0xf6, 0x9e, 0xe8, 0x6d, 0x2b, 0xc5, 0x22, 0x36, 0x3a, 0x0d,
+    0xab, 0xc5, 0x21, 0x97, 0x9b, 0x0d, 0xea, 0xda, 0x1d, 0xbf, 0x9a, 0x42, 0xd5, 0xc4, 0x48, 0x4e,
+    0x0a, 0xbc, 0xd0, 0x6b, 0xfa, 0x53, 0xdd, 0xef, 0x3c, 0x1b, 0x20, 0xee, 0x3f, 0xd5, 0x9d, 0x7c,
+    0x25, 0xe4, 0x1d, 0x2b, 0x66, 0xc6, 0x2e, 0x37, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+];
+
+const FFDHE_4096_P: [u8; 512] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xad, 0xf8, 0x54, 0x58, 0xa2, 0xbb, 0x4a, 0x9a,
+    0xaf, 0xdc, 0x56, 0x20, 0x27, 0x3d, 0x3c, 0xf1, 0xd8, 0xb9, 0xc5, 0x83, 0xce, 0x2d, 0x36, 0x95,
+    0xa9, 0xe1, 0x36, 0x41, 0x14, 0x64, 0x33, 0xfb, 0xcc, 0x93, 0x9d, 0xce, 0x24, 0x9b, 0x3e, 0xf9,
+    0x7d, 0x2f, 0xe3, 0x63, 0x63, 0x0c, 0x75, 0xd8, 0xf6, 0x81, 0xb2, 0x02, 0xae, 0xc4, 0x61, 0x7a,
+    0xd3, 0xdf, 0x1e, 0xd5, 0xd5, 0xfd, 0x65, 0x61, 0x24, 0x33, 0xf5, 0x1f, 0x5f, 0x06, 0x6e, 0xd0,
+    0x85, 0x63, 0x65, 0x55, 0x3d, 0xed, 0x1a, 0xf3, 0xb5, 0x57, 0x13, 0x5e, 0x7f, 0x57, 0xc9, 0x35,
+    0x98, 0x4f, 0x0c, 0x70, 0xe0, 0xe6, 0x8b, 0x77, 0xe2, 0xa6, 0x89, 0xda, 0xf3, 0xef, 0xe8, 0x72,
+    0x1d, 0xf1, 0x58, 0xa1, 0x36, 0xad, 0xe7, 0x35, 0x30, 0xac, 0xca, 0x4f, 0x48, 0x3a, 0x79, 0x7a,
+    0xbc, 0x0a, 0xb1, 0x82, 0xb3, 0x24, 0xfb, 0x61, 0xd1, 0x08, 0xa9, 0x4b, 0xb2, 0xc8, 0xe3, 0xfb,
+    0xb9, 0x6a, 0xda, 0xb7, 0x60, 0xd7, 0xf4, 0x68, 0x1d, 0x4f, 0x42, 0xa3, 0xde, 0x39, 0x4d, 0xf4,
+    0xae, 0x56, 0xed, 0xe7, 0x63, 0x72, 0xbb, 0x19, 0x0b, 0x07, 0xa7, 0xc8, 0xee, 0x0a, 0x6d, 0x70,
+    0x9e, 0x02, 0xfc, 0xe1, 0xcd, 0xf7, 0xe2, 0xec, 0xc0, 0x34, 0x04, 0xcd, 0x28, 0x34, 0x2f, 0x61,
+    0x91, 0x72, 0xfe, 0x9c, 0xe9, 0x85, 0x83, 0xff, 0x8e, 0x4f, 0x12, 0x32, 0xee, 0xf2, 0x81, 0x83,
+    0xc3, 0xfe, 0x3b, 0x1b, 0x4c, 0x6f, 0xad, 0x73, 0x3b, 0xb5, 0xfc, 0xbc, 0x2e, 0xc2, 0x20, 0x05,
+    0xc5, 0x8e, 0xf1, 0x83, 0x7d, 0x16, 0x83, 0xb2, 0xc6, 0xf3, 0x4a, 0x26, 0xc1, 0xb2, 0xef, 0xfa,
+    0x88, 0x6b, 0x42, 0x38, 0x61, 0x1f, 0xcf, 0xdc, 0xde, 0x35, 0x5b, 0x3b, 0x65, 0x19, 0x03, 0x5b,
+    0xbc, 0x34, 0xf4, 0xde, 0xf9, 0x9c, 0x02, 0x38, 0x61, 0xb4, 0x6f, 0xc9, 0xd6, 0xe6, 0xc9, 0x07,
+    0x7a, 0xd9, 0x1d, 0x26, 0x91, 0xf7, 0xf7, 0xee, 0x59, 0x8c, 0xb0, 0xfa, 0xc1, 0x86, 0xd9, 0x1c,
+    0xae, 0xfe, 0x13, 0x09, 0x85, 0x13, 0x92, 0x70, 0xb4, 0x13, 0x0c, 0x93, 0xbc, 0x43, 0x79, 0x44,
+    0xf4, 0xfd, 0x44, 0x52, 0xe2, 0xd7, 0x4d, 0xd3, 0x64, 0xf2, 0xe2, 0x1e, 0x71, 0xf5, 0x4b, 0xff,
+    0x5c, 0xae, 0x82, 0xab, 0x9c, 0x9d, 0xf6, 0x9e, 0xe8, 0x6d, 0x2b, 0xc5, 0x22, 0x36, 0x3a, 0x0d,
+    0xab, 0xc5, 0x21, 0x97, 0x9b, 0x0d, 0xea, 0xda, 0x1d, 0xbf, 0x9a, 0x42, 0xd5, 0xc4, 0x48, 0x4e,
+    0x0a, 0xbc, 0xd0, 0x6b, 0xfa, 0x53, 0xdd, 0xef, 0x3c, 0x1b, 0x20, 0xee, 0x3f, 0xd5, 0x9d, 0x7c,
+    0x25, 0xe4, 0x1d, 0x2b, 0x66, 0x9e, 0x1e, 0xf1, 0x6e, 0x6f, 0x52, 0xc3, 0x16, 0x4d, 0xf4, 0xfb,
+    0x79, 0x30, 0xe9, 0xe4, 0xe5, 0x88, 0x57, 0xb6, 0xac, 0x7d, 0x5f, 0x42, 0xd6, 0x9f, 0x6d, 0x18,
+    0x77, 0x63, 0xcf, 0x1d, 0x55, 0x03, 0x40, 0x04, 0x87, 0xf5, 0x5b, 0xa5, 0x7e, 0x31, 0xcc, 0x7a,
+    0x71, 0x35, 0xc8, 0x86, 0xef, 0xb4, 0x31, 0x8a, 0xed, 0x6a, 0x1e, 0x01, 0x2d, 0x9e, 0x68, 0x32,
+    0xa9, 0x07, 0x60, 0x0a, 0x91, 0x81, 0x30, 0xc4, 0x6d, 0xc7, 0x78, 0xf9, 0x71, 0xad, 0x00, 0x38,
+    0x09, 0x29, 0x99, 0xa3, 0x33, 0xcb, 0x8b, 0x7a, 0x1a, 0x1d, 0xb9, 0x3d, 0x71, 0x40, 0x00, 0x3c,
+    0x2a, 0x4e, 0xce, 0xa9, 0xf9, 0x8d, 0x0a, 0xcc, 0x0a, 0x82, 0x91, 0xcd, 0xce, 0xc9, 0x7d, 0xcf,
+    0x8e, 0xc9, 0xb5, 0x5a, 0x7f, 0x88, 0xa4, 0x6b, 0x4d, 0xb5, 0xa8, 0x51, 0xf4, 0x41, 0x82, 0xe1,
+    0xc6, 0x8a, 0x00, 0x7e, 0x5e, 0x65, 0x5f, 0x6a, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+];
+
+pub struct FfdheGroup {
+    /// The safe prime p, big endian.
+    pub prime: &'static [u8],
+    /// Private exponent length, RFC 7919 section 5.2.
+    pub exponent_bits: usize,
+}
+
+/// Private exponent, wide enough for the largest `exponent_bits`.
+#[cfg(any(feature = "spdm-ring", feature = "spdm-rustcrypto"))]
+pub type FfdheExponent = U384;
+
+pub static FFDHE_2048: FfdheGroup = FfdheGroup {
+    prime: &FFDHE_2048_P,
+    exponent_bits: 225,
+};
+
+pub static FFDHE_3072: FfdheGroup = FfdheGroup {
+    prime: &FFDHE_3072_P,
+    exponent_bits: 275,
+};
+
+pub static FFDHE_4096: FfdheGroup = FfdheGroup {
+    prime: &FFDHE_4096_P,
+    exponent_bits: 325,
+};
+
+impl FfdheGroup {
+    pub fn size(&self) -> usize {
+        self.prime.len()
+    }
+}
+
+#[cfg(any(feature = "spdm-ring", feature = "spdm-rustcrypto"))]
+impl FfdheGroup {
+    /// Generate a private exponent from `random`, which must hold at least
+    /// `exponent_bits` bits, and return it with the matching public value g^x mod p.
+    pub fn generate_key_pair(&self, random: &[u8]) -> Option<(FfdheExponent, Vec<u8>)> {
+        let exponent = self.exponent_from_random(random)?;
+        let mut generator = alloc::vec![0u8; self.size()];
+        generator[self.size() - 1] = 2;
+        let public_key = self.mod_exp(&generator, &exponent)?;
+        Some((exponent, public_key))
+    }
+
+    /// Compute the shared secret y^x mod p, zero padded to the size of p.
+    /// `peer_pub_key` must be the size of p and satisfy 1 < y < p - 1.
+    pub fn compute_shared_secret(
+        &self,
+        exponent: &FfdheExponent,
+        peer_pub_key: &[u8],
+    ) -> Option<Vec<u8>> {
+        if peer_pub_key.len() != self.size() {
+            return None;
+        }
+        self.mod_exp(peer_pub_key, exponent)
+    }
+
+    fn exponent_from_random(&self, random: &[u8]) -> Option<FfdheExponent> {
+        let bytes = (self.exponent_bits - 1) / 8 + 1;
+        if random.len() < bytes {
+            return None;
+        }
+        let mut padded = [0u8; FfdheExponent::BYTES];
+        padded[FfdheExponent::BYTES - bytes..].copy_from_slice(&random[..bytes]);
+        let mask = FfdheExponent::MAX.shr_vartime(FfdheExponent::BITS - self.exponent_bits);
+        let exponent = FfdheExponent::from_be_slice(&padded).bitand(&mask);
+        if exponent == FfdheExponent::ZERO {
+            return None;
+        }
+        Some(exponent)
+    }
+
+    /// base^exponent mod p, big endian and the size of p.
+    fn mod_exp(&self, base: &[u8], exponent: &FfdheExponent) -> Option<Vec<u8>> {
+        match self.size() {
+            256 => mod_exp::<{ U2048::LIMBS }>(self.prime, base, exponent, self.exponent_bits),
+            384 => mod_exp::<{ U3072::LIMBS }>(self.prime, base, exponent, self.exponent_bits),
+            512 => mod_exp::<{ U4096::LIMBS }>(self.prime, base, exponent, self.exponent_bits),
+            _ => None,
+        }
+    }
+}
+
+/// `prime` and `base` are big endian and exactly LIMBS wide; `base` must
+/// satisfy 1 < base < p - 1. Only the low `exponent_bits` of `exponent`
+/// are walked, which is public, so the timing does not depend on it.
+#[cfg(any(feature = "spdm-ring", feature = "spdm-rustcrypto"))]
+fn mod_exp<const LIMBS: usize>(
+    prime: &[u8],
+    base: &[u8],
+    exponent: &FfdheExponent,
+    exponent_bits: usize,
+) -> Option<Vec<u8>> {
+    let p = Uint::<LIMBS>::from_be_slice(prime);
+    let base = Uint::<LIMBS>::from_be_slice(base);
+    if base <= Uint::ONE || base >= p.wrapping_sub(&Uint::ONE) {
+        return None;
+    }
+
+    let params = DynResidueParams::new(&p);
+    let result = DynResidue::new(&base, params)
+        .pow_bounded_exp(exponent, exponent_bits)
+        .retrieve();
+    Some(
+        result
+            .as_words()
+            .iter()
+            .rev()
+            .flat_map(|word| word.to_be_bytes())
+            .collect(),
+    )
+}
+
+#[cfg(all(test, any(feature = "spdm-ring", feature = "spdm-rustcrypto")))]
+mod tests {
+    use super::*;
+
+    // Generated with Python: x = int.from_bytes(random[:(bits - 1) // 8 + 1], "big")
+    // masked to exponent_bits, public key pow(2, x_a, p), shared secret
+    // pow(pow(2, x_b, p), x_a, p), with random_a = bytes(range(64)) and
+    // random_b = bytes(range(64, 128)).
+    const FFDHE_2048_PUBLIC_KEY: [u8; 256] = [
+        0xfb, 0xe0, 0xbf, 0x3a, 0x26, 0x99, 0x07, 0xdc, 0x37, 0xf2, 0xb9, 0x2a, 0x40, 0xb5, 0xd9,
+        0x78, 0x30, 0x47, 0x68, 0x6b, 0xbe, 0x21, 0xdb, 0xf5, 0x26, 0xb7, 0x9d, 0x14, 0xea, 0x33,
+        0x3d, 0x8c, 0x02, 0x55, 0xde, 0xd1, 0xe3, 0x56, 0x3c, 0x1d, 0x6b, 0xcd, 0x55, 0xb2, 0x1a,
+        0x36, 0xbd, 0x83, 0xfd, 0x81, 0xd7, 0x81, 0xc3, 0xf4, 0x90, 0xda, 0x3c, 0xa5, 0x46, 0x34,
+        0x2f, 0xa2, 0x48, 0x25, 0xdf, 0x7a, 0x13, 0x68, 0x37, 0x5f, 0x8a, 0x0e, 0x6f, 0x67, 0x22,
+        0xe4, 0xb8, 0x2d, 0xb9, 0x63, 0x7b, 0x1c, 0xe5, 0x84, 0x08, 0x09, 0x02, 0xb7, 0xc7, 0x61,
+        0x70, 0xbe, 0x83, 0x98, 0x40, 0x51, 0xa6, 0x61, 0x29, 0x0a, 0x2f, 0x2f, 0xb1, 0x3a, 0x9d,
+        0x5d, 0xdc, 0xc0, 0xd3, 0xc5, 0xcb, 0xe8, 0x6b, 0xfe, 0xa0, 0x8d, 0x8d, 0xe8, 0x6f, 0x77,
+        0xed, 0xdd, 0x29, 0x90, 0xf0, 0x80, 0x07, 0xac, 0x30, 0x47, 0x6d, 0x4f, 0xd8, 0x71, 0x41,
+        0x38, 0xe5, 0xca, 0xe5, 0x6e, 0x56, 0xb9, 0x78, 0xdd, 0xfa, 0x3f, 0x7e, 0x92, 0x58, 0x0d,
+        0xed, 0x61, 0x61, 0xf3, 0x62, 0x1e, 0x03, 0x74, 0xa9, 0x47, 0xed, 0xfe, 0x67, 0x6c, 0x57,
+        0x46, 0x55, 0xfe, 0xb3, 0xb2, 0x5d, 0xbf, 0x81, 0x82, 0x63, 0x25, 0x70, 0x99, 0x4a, 0xad,
+        0xb3, 0xde, 0x08, 0x76, 0x22, 0x81, 0x35, 0xb5, 0x56, 0xcf, 0x9d, 0x37, 0x02, 0x34, 0x80,
+        0xf4, 0x59, 0x07, 0xfe, 0x0a, 0xa9, 0x31, 0x6b, 0xe9, 0x9c, 0x63, 0x39, 0x9d, 0x99, 0x5e,
+        0x1c, 0xbe, 0x22, 0x4d, 0xe6, 0x43, 0x76, 0x51, 0x4c, 0x89, 0x2e, 0x7f, 0xe5, 0xcc, 0x55,
+        0x7b, 0xde, 0xde, 0xa3, 0x75, 0x22, 0x75, 0x54, 0xc4, 0xfd, 0xeb, 0x0f, 0xeb, 0x35, 0xf6,
+        0x8c, 0x4f, 0x3f, 0x29, 0xd2, 0x1f, 0xbc, 0xce, 0xda, 0x5e, 0x77, 0xe6, 0x3b, 0x8a, 0x86,
+        0x27,
+    ];
+
+    const FFDHE_2048_SHARED_SECRET: [u8; 256] = [
+        0x7a, 0xdc, 0x30, 0xe6, 0x9f, 0x75, 0xd1, 0xbe, 0xda, 0x8d, 0xfa, 0x7c, 0x69, 0xaa, 0x80,
+        0x02, 0xa7, 0xe4, 0x96, 0x05, 0xcb, 0x7c, 0xe8, 0x64, 0x38, 0x09, 0xab, 0x41, 0x27, 0xef,
+        0x1d, 0x8c, 0xed, 0xa0, 0x16, 0x8c, 0x29, 0x6a, 0x2a, 0x82, 0x7e, 0x5c, 0xe2, 0x31, 0xc0,
+        0xb9, 0x13, 0xb8, 0x1c, 0x39, 0xd3, 0x92, 0x69, 0x28, 0x43, 0x37, 0x28, 0x21, 0x57, 0xf9,
+        0xcf, 0x28, 0x58, 0xab, 0x9d, 0xfb, 0x8e, 0x7f, 0xa5, 0x94, 0x20, 0x3f, 0x78, 0x82, 0xf8,
+        0x9a, 0xad, 0x23, 0xab, 0x43, 0xb3, 0xba, 0x58, 0xf5, 0xc5, 0xf3, 0xfc, 0x3d, 0x8c, 0x79,
+        0xd5, 0x72, 0x5f, 0xb8, 0xea, 0xf1, 0xc2, 0x28, 0xf6, 0x81, 0x84, 0xe8, 0x51, 0x2c, 0x18,
+        0xcd, 0x5b, 0xae, 0x18, 0x46, 0xc9, 0x1b, 0x1e, 0x2a, 0xc5, 0xfd, 0xc4, 0x02, 0x9e, 0xc6,
+        0x8f, 0x23, 0xcc, 0x38, 0x94, 0x4d, 0x1b, 0xdd, 0x74, 0xd5, 0xea, 0x06, 0x7f, 0x9e, 0xc5,
+        0xc0, 0xd8, 0x61, 0x5c, 0x4a, 0xa0, 0x15, 0x4a, 0x1d, 0xb8, 0xad, 0x7c, 0xef, 0x29, 0x8c,
+        0x84, 0x95, 0x18, 0x45, 0x8e, 0x27, 0x12, 0xd5, 0xab, 0xb8, 0x68, 0xdd, 0x16, 0x39, 0xf6,
+        0x82, 0x54, 0x7d, 0xc5, 0x26, 0xce, 0x5e, 0x4a, 0xcf, 0xd3, 0xf3, 0x1c, 0x52, 0x23, 0x5c,
+        0xbc, 0x23, 0x36, 0xbf, 0x93, 0xf4, 0xec, 0x8d, 0x7a, 0x7a, 0xb3, 0xae, 0xf1, 0x06, 0x69,
+        0x6a, 0x1c, 0x16, 0xfa, 0xfe, 0x15, 0x61, 0x32, 0x47, 0xac, 0x78, 0x01, 0x52, 0x3f, 0xfe,
+        0x00, 0x4a, 0x1d, 0xd1, 0x5d, 0x62, 0x12, 0xbc, 0x97, 0xb5, 0x0c, 0x4e, 0xcd, 0x38, 0xc0,
+        0x89, 0x0e, 0x2c, 0x86, 0xcc, 0x62, 0x0f, 0x6a, 0xf9, 0x23, 0x8c, 0x3a, 0xfb, 0x51, 0xb4,
+        0x47, 0x03, 0x44, 0x49, 0x90, 0xcc, 0x21, 0x1b, 0xb6, 0x4d, 0x1e, 0x02, 0xe1, 0x88, 0x7a,
+        0x5d,
+    ];
+
+    const FFDHE_3072_PUBLIC_KEY: [u8; 384] = [
+        0x5e, 0x14, 0x38, 0xcb, 0x1c, 0xcc, 0x7b, 0xf8, 0x61, 0xe0, 0x8c, 0x5a, 0xa0, 0xf6, 0xf5,
+        0x32, 0x5f, 0x77, 0xba, 0x89, 0x45, 0xd6, 0x23, 0x36, 0xc7, 0x9f, 0x14, 0x28, 0x65, 0x24,
+        0x55, 0xf7, 0x11, 0x11, 0xca, 0x17, 0xed, 0xcf, 0xff, 0xa5, 0x92, 0x45, 0xdd, 0x2b, 0x65,
+        0xce, 0x4a, 0x81, 0x8d, 0x14, 0xd2, 0x44, 0x28, 0xf6, 0x10, 0xfe, 0xd4, 0xbf, 0xe9, 0x90,
+        0x4c, 0x0e, 0xc6, 0xc7, 0xf9, 0x44, 0xed, 0x29, 0xe1, 0x70, 0x83, 0xaf, 0x82, 0x73, 0xf5,
+        0x73, 0x1a, 0x9d, 0xec, 0xf8, 0xe6, 0x03, 0x25, 0x5e, 0x79, 0x1f, 0x4d, 0xbe, 0x0e, 0xa5,
+        0xc2, 0xe9, 0xd8, 0x29, 0x59, 0x5c, 0x99, 0xee, 0x1e, 0x6d, 0xe9, 0x82, 0x7c, 0xc1, 0x75,
+        0x2b, 0xe1, 0x99, 0x0f, 0x79, 0xcf, 0x05, 0x27, 0x5c, 0xc4, 0xd9, 0x78, 0xc9, 0xfc, 0x31,
+        0xeb, 0xf8, 0xc6, 0x70, 0xe5, 0x79, 0xa1, 0x19, 0x5c, 0x41, 0x2b, 0x40, 0xa3, 0xb8, 0xda,
+        0x18, 0x81, 0x32, 0x73, 0x03, 0x9d, 0x8a, 0x54, 0x92, 0x7e, 0xa9, 0xaa, 0x05, 0xc1, 0x6d,
+        0xfb, 0x0d, 0x6b, 0x49, 0x52, 0x64, 0xfe, 0x7b, 0xff, 0x3c, 0x64, 0x01, 0x87, 0x0b, 0x2f,
+        0x93, 0x33, 0x76, 0x10, 0x7c, 0xfe, 0x9d, 0xb1, 0x4f, 0xf4, 0x9f, 0xda, 0x1e, 0xfc, 0xaf,
+        0x53, 0xe5, 0x99, 0xa0, 0x89, 0x63, 0xd8, 0xb2, 0x12, 0x21, 0x82, 0x50, 0x28, 0x08, 0x57,
+        0x64, 0xf7, 0xb2, 0x85, 0x47, 0xae, 0x6c, 0xc3, 0x26, 0x2f, 0xcc, 0x31, 0xa5, 0x73, 0xb0,
+        0x5f, 0x7f, 0xbf, 0xa3, 0x5e, 0x48, 0xfb, 0x09, 0xb3, 0x19, 0x20, 0xf3, 0x33, 0xac, 0xb6,
+        0x00, 0xb1, 0x57, 0xc4, 0x4c, 0xa7, 0xd4, 0x11, 0xbd, 0x55, 0x9f, 0x38, 0xb3, 0xf1, 0xf1,
+        0x9f, 0xa8, 0x58, 0x44, 0x41, 0x85, 0x6d, 0x67, 0x6e, 0x9f, 0x0d, 0x6b, 0xa9, 0x29, 0xfd,
+        0x3f, 0x9a, 0x36, 0xc0, 0xca, 0x9a, 0x73, 0x25, 0x72, 0x43, 0x22, 0x53, 0x6c, 0x66, 0x25,
+        0x11, 0xe7, 0x99, 0xaa, 0x31, 0x6f, 0xc8, 0xa6, 0x46, 0x8d, 0x41, 0x76, 0x1b, 0xca, 0xd2,
+        0x30, 0x01, 0xab, 0x2b, 0x4e, 0xa9, 0x24, 0x58, 0xce, 0x38, 0x96, 0xee, 0x9f, 0xc9, 0x2a,
+        0x11, 0x29, 0x3e, 0x3e, 0x12, 0xeb, 0x2b, 0xae, 0x93, 0x17, 0x4e, 0xdf, 0x8e, 0x09, 0xeb,
+        0x38, 0xf7, 0x0c, 0x35, 0x7c, 0xbe, 0x52, 0xd0, 0x81, 0x3b, 0x18, 0xdb, 0xe5, 0x4e, 0xfb,
+        0xad, 0x4c, 0x87, 0x57, 0x3e, 0x95, 0x63, 0x3a, 0xb1, 0xa4, 0xdc, 0x5b, 0xaf, 0x97, 0x0d,
+        0xcb, 0x26, 0x66, 0x67, 0x20, 0x27, 0x49, 0x42, 0xbe, 0x2e, 0x02, 0xfd, 0xda, 0x07, 0xdf,
+        0x58, 0x37, 0xe7, 0x0a, 0x0c, 0x87, 0x07, 0x0f, 0x55, 0xe0, 0x17, 0x61, 0xf9, 0x09, 0x48,
+        0x4d, 0x79, 0x1a, 0x1b, 0x6b, 0x17, 0x98, 0x2d, 0xf9,
+    ];
+
+    const FFDHE_3072_SHARED_SECRET: [u8; 384] = [
+        0xda, 0x95, 0x42, 0x90, 0x51, 0x6a, 0x08, 0xbb, 0x66, 0xac, 0x78, 0x5d, 0x68, 0x62, 0xcc,
+        0x8a, 0xbf, 0xa4, 0x9b, 0x87, 0x91, 0xda, 0x33, 0xf3, 0x13, 0xc2, 0x57, 0x4a, 0xc2, 0x96,
+        0x51, 0x79, 0x1c, 0x88, 0x34, 0x5b, 0xac, 0x52, 0x95, 0xbd, 0x9c, 0x1a, 0xc5, 0x43, 0x0e,
+        0xc1, 0x0f, 0x67, 0x59, 0xef, 0x09, 0x87, 0xbb, 0x25, 0xcd, 0x81, 0xcb, 0x9f, 0x3c, 0x19,
+        0xab, 0x15, 0x07, 0x18, 0xee, 0x25, 0xbb, 0xb6, 0x96, 0xe7, 0x40, 0x6d, 0xbe, 0x02, 0xd7,
+        0x6a, 0xa9, 0x8f, 0xef, 0x9f, 0xfb, 0xce, 0xe6, 0xfd, 0x84, 0x72, 0x97, 0xdb, 0xc8, 0x28,
+        0x24, 0xc2, 0xa9, 0x75, 0xc9, 0x13, 0xfd, 0x1b, 0x2c, 0x96, 0xc9, 0x97, 0x1b, 0x7a, 0xc0,
+        0x25, 0xd9, 0x32, 0x3a, 0x07, 0x5a, 0xf2, 0x7a, 0x5e, 0xd0, 0x29, 0x2e, 0x9c, 0x1e, 0x7a,
+        0xf4, 0x5c, 0x16, 0x6e, 0x0f, 0xf6, 0x00, 0x19, 0x81, 0xa5, 0xfe, 0xb7, 0x0f, 0xe7, 0x7e,
+        0x92, 0xed, 0x55, 0xca, 0xa4, 0x3b, 0xe8, 0x57, 0x10, 0x42, 0xdc, 0x4f, 0xa1, 0x84, 0x3c,
+        0xab, 0x8a, 0x4d, 0x85, 0x34, 0x31, 0xa5, 0x82, 0x98, 0x88, 0x39, 0x27, 0x4a, 0x5e, 0xc5,
+        0xec, 0x9c, 0xb5, 0x9d, 0x6e, 0x24, 0x02, 0x2f, 0xb2, 0x1a, 0x39, 0x69, 0xfc, 0x05, 0xa1,
+        0x67, 0xc2, 0x23, 0x7b, 0x11, 0x46, 0x55, 0xca, 0xe4, 0x17, 0xc0, 0x9b, 0x6d, 0x28, 0x74,
+        0x08, 0x31, 0x46, 0x04, 0x8f, 0x9b, 0xa5, 0x23, 0x7e, 0x80, 0xa2, 0x60, 0x01, 0x1c, 0xa9,
+        0x48, 0xad, 0xcc, 0xdc, 0xa4, 0xec, 0xd5, 0x7b, 0xe4, 0x85, 0xc7, 0x30, 0x86, 0x37, 0xef,
+        0x6b, 0xb3, 0x74, 0x28, 0x27, 0xd4, 0x9b, 0xdd, 0x05, 0x9b, 0x3b, 0xce, 0xb6, 0x4f, 0x9a,
+        0xd6, 0x5d, 0xf2, 0xf9, 0xb0, 0xe6, 0x3f, 0xc5, 0x8e, 0x1d, 0x1c, 0x89, 0x66, 0x3b, 0x3e,
+        0xd0, 0x5e, 0xd7, 0x4c, 0x1c, 0xcc, 0xd4, 0x4c, 0x30, 0xd7, 0x4f, 0x52, 0x1c, 0xe7, 0x57,
+        0x02, 0x3b, 0x1d, 0x10, 0x04, 0xec, 0x69, 0x30, 0xa9, 0xb2, 0x7e, 0xa5, 0x97, 0x8e, 0xcb,
+        0xcc, 0x98, 0xfd, 0x33, 0x57, 0x1a, 0xa9, 0x05, 0x25, 0x08, 0x16, 0x99, 0x1f, 0x00, 0x20,
+        0x2f, 0xcd, 0x27, 0xb7, 0x5a, 0x45, 0xd1, 0x96, 0x26, 0x03, 0x84, 0x02, 0x91, 0xf0, 0x02,
+        0x06, 0xdf, 0xf2, 0x0a, 0x6e, 0x74, 0xe8, 0x61, 0xa2, 0xee, 0x33, 0x4f, 0x0f, 0xb0, 0xb8,
+        0x8f, 0x46, 0x41, 0xa5, 0xd9, 0x33, 0xb5, 0x8b, 0x84, 0xea, 0xb8, 0xdd, 0x8d, 0x11, 0xd5,
+        0x7d, 0x4a, 0x0c, 0xc2, 0xb9, 0xd6, 0x2e, 0xa5, 0xbd, 0xb6, 0x14, 0xd9, 0x02, 0x7d, 0xb8,
+        0xcc, 0xbe, 0x55, 0x15, 0xeb, 0xaa, 0x87, 0x3f, 0xdc, 0x40, 0x98, 0x31, 0x5f, 0x3c, 0xd8,
+        0xc1, 0xc6, 0x6d, 0xa1, 0x61, 0x0c, 0xd4, 0xcc, 0x94,
+    ];
+
+    const FFDHE_4096_PUBLIC_KEY: [u8; 512] = [
+        0x8e, 0x24, 0x63, 0x15, 0x9a, 0xa0, 0x72, 0x16, 0xf3, 0x24, 0x73, 0xf3, 0x14, 0x24, 0x25,
+        0xe6, 0x97, 0xd2, 0x36, 0x69, 0xc9, 0xfd, 0x1c, 0x87, 0x57, 0x42, 0x83, 0x8b, 0x37, 0x67,
+        0xe8, 0xff, 0x1e, 0x98, 0x34, 0x11, 0x60, 0x60, 0xc0, 0xc8, 0xac, 0xbc, 0x88, 0x98, 0x28,
+        0x87, 0x1e, 0x5a, 0x90, 0xb3, 0x8f, 0xf8, 0xdb, 0x73, 0x4c, 0xb7, 0x07, 0x00, 0xd9, 0xc8,
+        0xdb, 0xcb, 0x1e, 0xae, 0x69, 0x9d, 0x4b, 0x4e, 0xcb, 0xdc, 0x4b, 0x93, 0x3a, 0xa7, 0x90,
+        0xc1, 0xdd, 0xda, 0x2f, 0xaa, 0x65, 0x2a, 0xcd, 0xe8, 0x97, 0xa8, 0xa8, 0x25, 0xd2, 0x36,
+        0x10, 0x03, 0x4f, 0xd9, 0x87, 0x4b, 0x07, 0x87, 0x54, 0x07, 0x80, 0x82, 0x3d, 0xb6, 0x91,
+        0x90, 0xb4, 0x40, 0x8e, 0x29, 0xa7, 0x2c, 0x43, 0x55, 0xbb, 0x82, 0x71, 0xe2, 0x7a, 0x21,
+        0x98, 0xe7, 0xc1, 0x78, 0x29, 0x11, 0xde, 0x02, 0xb0, 0x6b, 0xf6, 0x35, 0xb8, 0xa4, 0xc7,
+        0x7a, 0x0e, 0x34, 0xd1, 0x07, 0x53, 0xf8, 0x02, 0x31, 0x3f, 0x9d, 0xdd, 0x3d, 0xc1, 0xf8,
+        0xdb, 0x36, 0x07, 0x24, 0x3e, 0xd4, 0x80, 0xe2, 0xb4, 0xec, 0xc1, 0x3e, 0x9f, 0x61, 0xbf,
+        0xb5, 0x6a, 0x07, 0x99, 0x8a, 0x83, 0xb8, 0xf2, 0xc0, 0x3f, 0xcc, 0x33, 0xd6, 0xfb, 0x66,
+        0x10, 0x43, 0xd1, 0x79, 0x7d, 0x8e, 0xbd, 0x24, 0xbc, 0xa4, 0x84, 0x96, 0x06, 0xf1, 0xaf,
+        0xd6, 0x92, 0x40, 0x26, 0xa4, 0x2d, 0xbf, 0xdb, 0x4f, 0x21, 0xdc, 0x4b, 0x5b, 0x95, 0x86,
+        0xdd, 0x1a, 0xd4, 0x79, 0xad, 0x73, 0x9b, 0xdd, 0x16, 0x98, 0x07, 0xff, 0xa3, 0x03, 0xc4,
+        0x33, 0x9c, 0xb2, 0x71, 0xbf, 0x5d, 0x1c, 0x63, 0x96, 0x3a, 0xef, 0x1c, 0xd2, 0x21, 0x13,
+        0x99, 0xa9, 0x30, 0xe2, 0xe9, 0xef, 0x5e, 0x45, 0xea, 0xe0, 0xec, 0x01, 0x7d, 0x78, 0x68,
+        0x2c, 0xd6, 0xf4, 0xc3, 0xf1, 0x03, 0x54, 0xeb, 0x38, 0x74, 0xc7, 0x5e, 0x7a, 0xa4, 0x45,
+        0xd7, 0x93, 0xeb, 0x65, 0x07, 0x5f, 0xd4, 0x0f, 0xda, 0xf9, 0xd3, 0x66, 0xbb, 0x30, 0x4b,
+        0x86, 0x47, 0xc5, 0xae, 0x0a, 0x06, 0x2c, 0xdc, 0x02, 0x44, 0xa0, 0x6f, 0xdb, 0x9b, 0x67,
+        0xe9, 0xc8, 0xb3, 0x67, 0xed, 0xef, 0xdd, 0x27, 0xd0, 0xbe, 0x01, 0xe3, 0x9d, 0xae, 0xc7,
+        0xd0, 0xa1, 0xed, 0x62, 0xbc, 0x3e, 0x39, 0x85, 0xf8, 0x65, 0x03, 0xf9, 0x02, 0x7a, 0x88,
+        0x6b, 0x7e, 0x11, 0xff, 0xa4, 0x64, 0x3c, 0x60, 0x69, 0x7d, 0xc7, 0x9a, 0xfa, 0xf4, 0x63,
+        0x05, 0xb3, 0xcb, 0xe5, 0x7e, 0x07, 0xb9, 0xcc, 0xeb, 0x08, 0x1f, 0xf3, 0x30, 0x31, 0x7b,
+        0xd2, 0x7f, 0x35, 0x8f, 0xf2, 0x76, 0xb1, 0xbc, 0x60, 0xae, 0xcc, 0xd7, 0xb8, 0xb0, 0xe0,
+        0x72, 0x1c, 0xfb, 0x95, 0xf2, 0x00, 0x86, 0x7f, 0x84, 0xcb, 0x35, 0xc9, 0xc3, 0xec, 0x8b,
+        0x2d, 0x47, 0xc1, 0xce, 0xa0, 0x4f, 0xb5, 0x0e, 0x4a, 0xd3, 0xdb, 0xfb, 0xc9, 0x24, 0x58,
+        0x20, 0x82, 0x5b, 0xd2, 0xbd, 0xee, 0x21, 0xe7, 0x99, 0x8f, 0x47, 0x92, 0x7d, 0x1e, 0x8b,
+        0x7b, 0xe1, 0xd9, 0xe4, 0x58, 0x0e, 0xdd, 0xa3, 0x0f, 0x83, 0x1c, 0x89, 0xa0, 0x4b, 0xec,
+        0x3b, 0xce, 0x5f, 0x05, 0x98, 0xf6, 0xdc, 0xdf, 0x34, 0xb9, 0x4e, 0xc7, 0x20, 0xd9, 0xc1,
+        0x45, 0x52, 0x16, 0x22, 0x80, 0x97, 0x1d, 0xe0, 0x2e, 0x8f, 0xda, 0xc8, 0x29, 0x85, 0xd5,
+        0x9d, 0x0c, 0x8e, 0x07, 0xd2, 0xfe, 0x81, 0xca, 0x39, 0x9d, 0xe2, 0x51, 0x46, 0xdb, 0x52,
+        0xa1, 0xe6, 0xc8, 0x8c, 0xd0, 0x52, 0x73, 0x0b, 0x2b, 0x34, 0xc0, 0x74, 0xb3, 0x09, 0x26,
+        0x7b, 0x1b, 0xfe, 0x5b, 0x23, 0x5c, 0x93, 0xda, 0xbd, 0xf5, 0xd4, 0x35, 0x02, 0xa8, 0xaf,
+        0x9c, 0x4a,
+    ];
+
+    const FFDHE_4096_SHARED_SECRET: [u8; 512] = [
+        0x51, 0x73, 0x04, 0x03, 0xa4, 0x43, 0xfe, 0x51, 0xc7, 0x10, 0x6a, 0x16, 0x22, 0xdc, 0x32,
+        0x42, 0xb6, 0x7a, 0xc6, 0xca, 0x3d, 0xfd, 0x58, 0xfe, 0xa5, 0x60, 0xd0, 0xe5, 0xed, 0x2c,
+        0xe4, 0xf1, 0xf6, 0xbd, 0xfc, 0x12, 0x87, 0x94, 0xbc, 0x63, 0xbc, 0x53, 0x6e, 0x84, 0xf8,
+        0xac, 0x3a, 0x51, 0x62, 0xf9, 0x29, 0x13, 0x10, 0x03, 0x72, 0xb8, 0x52, 0x68, 0x7a, 0x71,
+        0x93, 0x90, 0x5b, 0xc0, 0xb8, 0xe9, 0x16, 0x4a, 0xe0, 0x8e, 0xd5, 0x77, 0xc3, 0x69, 0xe8,
+        0xc3, 0x70, 0xde, 0x76, 0xe9, 0x84, 0x1a, 0xfb, 0xf5, 0x28, 0x83, 0xb6, 0x88, 0xd5, 0x75,
+        0x39, 0xb3, 0x22, 0x74, 0x43, 0x40, 0x32, 0xc4, 0x56, 0x87, 0xd5, 0x49, 0x83, 0x3b, 0x47,
+        0xc6, 0x82, 0x8d, 0xa3, 0xdf, 0x20, 0x91, 0x07, 0xc7, 0x58, 0x75, 0xb4, 0x38, 0x66, 0x89,
+        0x20, 0x03, 0x5e, 0x3b, 0x78, 0xf9, 0xfe, 0xa2, 0xf7, 0xda, 0x13, 0xd3, 0xd4, 0xd9, 0x75,
+        0x5d, 0xad, 0x4d, 0x71, 0x7b, 0x3d, 0x31, 0x3a, 0x05, 0xde, 0x9f, 0x3b, 0xa0, 0x37, 0x66,
+        0xe2, 0x38, 0x05, 0x48, 0x25, 0xed, 0x14, 0x8b, 0x2a, 0xbd, 0x21, 0xb0, 0xe2, 0xb2, 0xf2,
+        0xd6, 0x7d, 0x26, 0xf7, 0xa3, 0xba, 0x4a, 0x8c, 0x11, 0xde, 0xe2, 0xf6, 0x22, 0x02, 0x02,
+        0xf5, 0x9f, 0x2e, 0xa3, 0x71, 0x86, 0xfd, 0x2e, 0xa5, 0xdb, 0x07, 0x29, 0x08, 0xc8, 0xdf,
+        0x99, 0x5b, 0x09, 0x32, 0x1e, 0xa2, 0x83, 0x69, 0x25, 0x76, 0xb8, 0x2e, 0x20, 0x47, 0xdc,
+        0x68, 0x52, 0x0e, 0x41, 0xdc, 0x83, 0x38, 0x08, 0x68, 0x89, 0x14, 0x9a, 0xaa, 0x2c, 0x19,
+        0xe9, 0xfb, 0x65, 0xe4, 0x8e, 0xf3, 0x84, 0x80, 0xac, 0x96, 0x5b, 0x2a, 0xa1, 0x85, 0x67,
+        0x46, 0x7e, 0xd2, 0xd4, 0x6d, 0xc4, 0xa2, 0x4e, 0x86, 0x4c, 0x32, 0x87, 0xe6, 0xd6, 0xa5,
+        0x92, 0x35, 0x2b, 0x36, 0x5e, 0xa8, 0x5a, 0xf3, 0x57, 0xd9, 0x9c, 0x40, 0x5c, 0xb9, 0xab,
+        0x05, 0xb1, 0x63, 0xc9, 0x34, 0x11, 0x64, 0x79, 0x64, 0xda, 0x89, 0x39, 0x8e, 0x95, 0xf6,
+        0x76, 0x64, 0x50, 0xaa, 0x4c, 0x4f, 0xbb, 0xd2, 0xb1, 0xde, 0xd6, 0xee, 0x54, 0x2c, 0x22,
+        0x2c, 0x4b, 0x52, 0xcf, 0xad, 0x4c, 0x69, 0x92, 0xd9, 0x38, 0x0d, 0x27, 0xd6, 0x27, 0x03,
+        0xd5, 0x89, 0x15, 0xfc, 0x20, 0x1f, 0x17, 0x49, 0x31, 0x98, 0xf6, 0x07, 0x29, 0x3d, 0xee,
+        0xac, 0xe3, 0xc0, 0x80, 0xed, 0x82, 0x32, 0x38, 0xb0, 0xd8, 0x68, 0xc0, 0xce, 0x57, 0x44,
+        0x78, 0x09, 0x5d, 0x00, 0xef, 0xb3, 0x57, 0x6c, 0x09, 0x3a, 0xca, 0x5b, 0x33, 0x8b, 0x5a,
+        0x31, 0x2f, 0x40, 0x01, 0x45, 0x6d, 0xdb, 0xf5, 0xe9, 0xc3, 0x4a, 0xe8, 0x23, 0xc2, 0x88,
+        0xd9, 0x05, 0x7f, 0xed, 0x1e, 0x4b, 0x57, 0x11, 0xaf, 0xf3, 0x6f, 0xa1, 0x8a, 0xec, 0xad,
+        0x57, 0x55, 0x51, 0x3d, 0x5f, 0x7c, 0x4a, 0xa1, 0x3e, 0x54, 0x66, 0x47, 0xd1, 0xff, 0x04,
+        0xbe, 0x98, 0xea, 0x5e, 0xd3, 0x1a, 0x64, 0xed, 0x99, 0x01, 0x83, 0x04, 0xad, 0x58, 0x22,
+        0x42, 0x28, 0xfc, 0xa1, 0x96, 0x0e, 0xb8, 0x45, 0x7e, 0x2e, 0x33, 0xa7, 0xda, 0x4b, 0xdb,
+        0xcb, 0x9b, 0x6c, 0xed, 0xb5, 0x7b, 0x8f, 0xfd, 0x57, 0xfe, 0xfc, 0x4a, 0x2d, 0xd1, 0x87,
+        0xe0, 0x3d, 0xa8, 0x33, 0x8a, 0x0d, 0x72, 0x56, 0x2b, 0x29, 0x9d, 0x60, 0xe5, 0xee, 0x73,
+        0xa3, 0xe8, 0xbd, 0x3c, 0x17, 0x5b, 0x26, 0x29, 0xd5, 0x58, 0x05, 0xfb, 0xd1, 0xc8, 0x9e,
+        0x27, 0xf7, 0x79, 0x55, 0x34, 0x37, 0xb2, 0x5d, 0x54, 0xa1, 0x68, 0xaa, 0x1a, 0x1f, 0x0d,
+        0x9d, 0x07, 0x9d, 0xee, 0x1b, 0xaf, 0xb3, 0x2a, 0x8a, 0x86, 0x81, 0x3e, 0x57, 0x09, 0xb6,
+        0xba, 0xd2,
+    ];
+
+    fn random(first: u8) -> [u8; 64] {
+        let mut random = [0u8; 64];
+        for (i, byte) in random.iter_mut().enumerate() {
+            *byte = first + i as u8;
+        }
+        random
+    }
+
+    #[test]
+    fn test_case0_ffdhe_known_answer() {
+        for (group, public_key, shared_secret) in [
+            (
+                &FFDHE_2048,
+                &FFDHE_2048_PUBLIC_KEY[..],
+                &FFDHE_2048_SHARED_SECRET[..],
+            ),
+            (
+                &FFDHE_3072,
+                &FFDHE_3072_PUBLIC_KEY[..],
+                &FFDHE_3072_SHARED_SECRET[..],
+            ),
+            (
+                &FFDHE_4096,
+                &FFDHE_4096_PUBLIC_KEY[..],
+                &FFDHE_4096_SHARED_SECRET[..],
+            ),
+        ]
+        .iter()
+        {
+            let (exponent_a, public_key_a) = group.generate_key_pair(&random(0)).unwrap();
+            let (exponent_b, public_key_b) = group.generate_key_pair(&random(64)).unwrap();
+            assert_eq!(public_key_a, public_key.to_vec());
+            assert_eq!(
+                group.compute_shared_secret(&exponent_a, &public_key_b),
+                Some(shared_secret.to_vec())
+            );
+            assert_eq!(
+                group.compute_shared_secret(&exponent_b, &public_key_a),
+                Some(shared_secret.to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn test_case1_ffdhe_peer_public_key() {
+        let group = &FFDHE_2048;
+        let (exponent, _) = group.generate_key_pair(&random(0)).unwrap();
+        let mut one = [0u8; 256];
+        one[255] = 1;
+        let mut p_minus_one = FFDHE_2048_P;
+        p_minus_one[255] -= 1;
+        for peer in [
+            &[0u8; 256][..],
+            &one,
+            &p_minus_one,
+            &FFDHE_2048_P,
+            &[2u8; 255],
+        ]
+        .iter()
+        {
+            assert!(group.compute_shared_secret(&exponent, peer).is_none());
+        }
+    }
+
+    #[test]
+    fn test_case2_ffdhe_exponent() {
+        let group = &FFDHE_2048;
+        let (exponent, _) = group.generate_key_pair(&[0xff; 64]).unwrap();
+        assert_eq!(exponent.bits(), group.exponent_bits);
+        assert!(group.generate_key_pair(&[0; 64]).is_none());
+        assert!(group.generate_key_pair(&[0xff; 16]).is_none());
+    }
+}
@@ -4,12 +4,30 @@
 
 mod crypto_callbacks;
 
+// spdm-openssl, when enabled, takes over from the other backends
+#[cfg(feature = "spdm-openssl")]
+mod spdm_openssl;
 #[cfg(feature = "spdm-ring")]
+#[cfg_attr(feature = "spdm-openssl", allow(dead_code))]
 mod spdm_ring;
+#[cfg(feature = "spdm-rustcrypto")]
+#[cfg_attr(any(feature = "spdm-ring", feature = "spdm-openssl"), allow(dead_code))]
+mod spdm_rustcrypto;
+
+#[cfg(any(
+    feature = "spdm-ring",
+    feature = "spdm-rustcrypto",
+    feature = "spdm-openssl"
+))]
+#[cfg_attr(
+    not(any(feature = "spdm-ring", feature = "spdm-rustcrypto")),
+    allow(dead_code)
+)]
+mod ffdhe;
 
 pub use crypto_callbacks::{
     SpdmAead, SpdmAsymSign, SpdmAsymVerify, SpdmCertOperation, SpdmCryptoRandom, SpdmDhe,
-    SpdmDheKeyExchange, SpdmHash, SpdmHkdf, SpdmHmac,
+    SpdmDheKeyExchange, SpdmExtAlg, SpdmHash, SpdmHkdf, SpdmHmac,
 };
 
 use conquer_once::spin::OnceCell;
@@ -23,21 +41,34 @@ static CRYPTO_DHE: OnceCell<SpdmDhe> = OnceCell::uninit();
 static CRYPTO_CERT_OPERATION: OnceCell<SpdmCertOperation> = OnceCell::uninit();
 static CRYPTO_HKDF: OnceCell<SpdmHkdf> = OnceCell::uninit();
 static CRYPTO_RAND: OnceCell<SpdmCryptoRandom> = OnceCell::uninit();
+static CRYPTO_EXT_ALG: OnceCell<SpdmExtAlg> = OnceCell::uninit();
 
 pub mod hash {
     use super::CRYPTO_HASH;
     use crate::crypto::SpdmHash;
     use crate::msgs::{SpdmBaseHashAlgo, SpdmDigestStruct};
 
-    #[cfg(not(any(feature = "spdm-ring")))]
+    #[cfg(not(any(
+        feature = "spdm-ring",
+        feature = "spdm-rustcrypto",
+        feature = "spdm-openssl"
+    )))]
     static DEFAULT: SpdmHash = SpdmHash {
         hash_all_cb: |_base_hash_algo: SpdmBaseHashAlgo,
                       _data: &[u8]|
          -> Option<SpdmDigestStruct> { unimplemented!() },
+        supported_algo_cb: SpdmBaseHashAlgo::empty,
     };
 
-    #[cfg(feature = "spdm-ring")]
+    #[cfg(feature = "spdm-openssl")]
+    use super::spdm_openssl::hash_impl::DEFAULT;
+    #[cfg(all(feature = "spdm-ring", not(feature = "spdm-openssl")))]
     use super::spdm_ring::hash_impl::DEFAULT;
+    #[cfg(all(
+        feature = "spdm-rustcrypto",
+        not(any(feature = "spdm-ring", feature = "spdm-openssl"))
+    ))]
+    use super::spdm_rustcrypto::hash_impl::DEFAULT;
 
     pub fn register(context: SpdmHash) -> bool {
         CRYPTO_HASH.try_init_once(|| context).is_ok()
@@ -46,6 +77,12 @@ pub mod hash {
     pub fn hash_all(base_hash_algo: SpdmBaseHashAlgo, data: &[u8]) -> Option<SpdmDigestStruct> {
         (CRYPTO_HASH.try_get_or_init(|| DEFAULT).ok()?.hash_all_cb)(base_hash_algo, data)
     }
+
+    pub fn supported_algo() -> SpdmBaseHashAlgo {
+        CRYPTO_HASH
+            .try_get_or_init(|| DEFAULT)
+            .map_or(SpdmBaseHashAlgo::empty(), |hash| (hash.supported_algo_cb)())
+    }
 }
 
 pub mod hmac {
@@ -54,7 +91,11 @@ pub mod hmac {
     use crate::error::SpdmResult;
     use crate::msgs::{SpdmBaseHashAlgo, SpdmDigestStruct};
 
-    #[cfg(not(any(feature = "spdm-ring")))]
+    #[cfg(not(any(
+        feature = "spdm-ring",
+        feature = "spdm-rustcrypto",
+        feature = "spdm-openssl"
+    )))]
     static DEFAULT: SpdmHmac = SpdmHmac {
         hmac_cb: |_base_hash_algo: SpdmBaseHashAlgo,
                   _key: &[u8],
@@ -67,8 +108,15 @@ pub mod hmac {
          -> SpdmResult { unimplemented!() },
     };
 
-    #[cfg(feature = "spdm-ring")]
+    #[cfg(feature = "spdm-openssl")]
+    use super::spdm_openssl::hmac_impl::DEFAULT;
+    #[cfg(all(feature = "spdm-ring", not(feature = "spdm-openssl")))]
     use super::spdm_ring::hmac_impl::DEFAULT;
+    #[cfg(all(
+        feature = "spdm-rustcrypto",
+        not(any(feature = "spdm-ring", feature = "spdm-openssl"))
+    ))]
+    use super::spdm_rustcrypto::hmac_impl::DEFAULT;
 
     pub fn register(context: SpdmHmac) -> bool {
         CRYPTO_HMAC.try_init_once(|| context).is_ok()
@@ -104,21 +152,30 @@ pub mod asym_sign {
         CRYPTO_ASYM_SIGN.try_init_once(|| context).is_ok()
     }
 
+    #[cfg(not(feature = "spdm-openssl"))]
     static DEFAULT: SpdmAsymSign = SpdmAsymSign {
         sign_cb: |_base_hash_algo: SpdmBaseHashAlgo,
                   _base_asym_algo: SpdmBaseAsymAlgo,
+                  _context: &[u8],
                   _data: &[u8]|
          -> Option<SpdmSignatureStruct> { unimplemented!() },
     };
 
+    #[cfg(feature = "spdm-openssl")]
+    pub use super::spdm_openssl::asym_sign_impl::set_private_key;
+    #[cfg(feature = "spdm-openssl")]
+    use super::spdm_openssl::asym_sign_impl::DEFAULT;
+
     pub fn sign(
         base_hash_algo: SpdmBaseHashAlgo,
         base_asym_algo: SpdmBaseAsymAlgo,
+        context: &[u8],
         data: &[u8],
     ) -> Option<SpdmSignatureStruct> {
         (CRYPTO_ASYM_SIGN.try_get_or_init(|| DEFAULT).ok()?.sign_cb)(
             base_hash_algo,
             base_asym_algo,
+            context,
             data,
         )
     }
@@ -130,18 +187,31 @@ pub mod asym_verify {
     use crate::error::SpdmResult;
     use crate::msgs::{SpdmBaseAsymAlgo, SpdmBaseHashAlgo, SpdmSignatureStruct};
 
-    #[cfg(not(any(feature = "spdm-ring")))]
+    #[cfg(not(any(
+        feature = "spdm-ring",
+        feature = "spdm-rustcrypto",
+        feature = "spdm-openssl"
+    )))]
     static DEFAULT: SpdmAsymVerify = SpdmAsymVerify {
         verify_cb: |_base_hash_algo: SpdmBaseHashAlgo,
                     _base_asym_algo: SpdmBaseAsymAlgo,
+                    _context: &[u8],
                     _public_cert_der: &[u8],
                     _data: &[u8],
                     _signature: &SpdmSignatureStruct|
          -> SpdmResult { unimplemented!() },
+        supported_algo_cb: SpdmBaseAsymAlgo::empty,
     };
 
-    #[cfg(feature = "spdm-ring")]
+    #[cfg(feature = "spdm-openssl")]
+    use super::spdm_openssl::asym_verify_impl::DEFAULT;
+    #[cfg(all(feature = "spdm-ring", not(feature = "spdm-openssl")))]
     use super::spdm_ring::asym_verify_impl::DEFAULT;
+    #[cfg(all(
+        feature = "spdm-rustcrypto",
+        not(any(feature = "spdm-ring", feature = "spdm-openssl"))
+    ))]
+    use super::spdm_rustcrypto::asym_verify_impl::DEFAULT;
 
     pub fn register(context: SpdmAsymVerify) -> bool {
         CRYPTO_ASYM_VERIFY.try_get_or_init(|| context).is_ok()
@@ -150,6 +220,7 @@ pub mod asym_verify {
     pub fn verify(
         base_hash_algo: SpdmBaseHashAlgo,
         base_asym_algo: SpdmBaseAsymAlgo,
+        context: &[u8],
         public_cert_der: &[u8],
         data: &[u8],
         signature: &SpdmSignatureStruct,
@@ -160,11 +231,20 @@ pub mod asym_verify {
             .verify_cb)(
             base_hash_algo,
             base_asym_algo,
+            context,
             public_cert_der,
             data,
             signature,
         )
     }
+
+    pub fn supported_algo() -> SpdmBaseAsymAlgo {
+        CRYPTO_ASYM_VERIFY
+            .try_get_or_init(|| DEFAULT)
+            .map_or(SpdmBaseAsymAlgo::empty(), |asym_verify| {
+                (asym_verify.supported_algo_cb)()
+            })
+    }
 }
 
 pub mod dhe {
@@ -175,16 +255,28 @@ pub mod dhe {
     use crate::crypto::{SpdmDhe, SpdmDheKeyExchange};
     use crate::msgs::{SpdmDheAlgo, SpdmDheExchangeStruct};
 
-    #[cfg(not(any(feature = "spdm-ring")))]
+    #[cfg(not(any(
+        feature = "spdm-ring",
+        feature = "spdm-rustcrypto",
+        feature = "spdm-openssl"
+    )))]
     static DEFAULT: SpdmDhe =
         SpdmDhe {
             generate_key_pair_cb: |_dhe_algo: SpdmDheAlgo| -> Option<(
                 SpdmDheExchangeStruct,
                 Box<dyn SpdmDheKeyExchange>,
             )> { unimplemented!() },
+            supported_algo_cb: SpdmDheAlgo::empty,
         };
-    #[cfg(feature = "spdm-ring")]
+    #[cfg(feature = "spdm-openssl")]
+    use super::spdm_openssl::dhe_impl::DEFAULT;
+    #[cfg(all(feature = "spdm-ring", not(feature = "spdm-openssl")))]
     use super::spdm_ring::dhe_impl::DEFAULT;
+    #[cfg(all(
+        feature = "spdm-rustcrypto",
+        not(any(feature = "spdm-ring", feature = "spdm-openssl"))
+    ))]
+    use super::spdm_rustcrypto::dhe_impl::DEFAULT;
 
     pub fn register(context: SpdmDhe) -> bool {
         CRYPTO_DHE.try_init_once(|| context).is_ok()
@@ -198,6 +290,12 @@ pub mod dhe {
             .ok()?
             .generate_key_pair_cb)(dhe_algo)
     }
+
+    pub fn supported_algo() -> SpdmDheAlgo {
+        CRYPTO_DHE
+            .try_get_or_init(|| DEFAULT)
+            .map_or(SpdmDheAlgo::empty(), |dhe| (dhe.supported_algo_cb)())
+    }
 }
 
 pub mod cert_operation {
@@ -205,7 +303,11 @@ pub mod cert_operation {
     use crate::crypto::SpdmCertOperation;
     use crate::error::SpdmResult;
 
-    #[cfg(not(any(feature = "spdm-ring")))]
+    #[cfg(not(any(
+        feature = "spdm-ring",
+        feature = "spdm-rustcrypto",
+        feature = "spdm-openssl"
+    )))]
     static DEFAULT: SpdmCertOperation = SpdmCertOperation {
         get_cert_from_cert_chain_cb: |_cert_chain: &[u8],
                                       _index: isize|
@@ -213,8 +315,15 @@ pub mod cert_operation {
         verify_cert_chain_cb: |_cert_chain: &[u8]| -> SpdmResult { unimplemented!() },
     };
 
-    #[cfg(feature = "spdm-ring")]
+    #[cfg(feature = "spdm-openssl")]
+    use super::spdm_openssl::cert_operation_impl::DEFAULT;
+    #[cfg(all(feature = "spdm-ring", not(feature = "spdm-openssl")))]
     use super::spdm_ring::cert_operation_impl::DEFAULT;
+    #[cfg(all(
+        feature = "spdm-rustcrypto",
+        not(any(feature = "spdm-ring", feature = "spdm-openssl"))
+    ))]
+    use super::spdm_rustcrypto::cert_operation_impl::DEFAULT;
 
     pub fn register(context: SpdmCertOperation) -> bool {
         CRYPTO_CERT_OPERATION.try_init_once(|| context).is_ok()
@@ -240,7 +349,11 @@ pub mod hkdf {
     use crate::crypto::SpdmHkdf;
     use crate::msgs::{SpdmBaseHashAlgo, SpdmDigestStruct};
 
-    #[cfg(not(any(feature = "spdm-ring")))]
+    #[cfg(not(any(
+        feature = "spdm-ring",
+        feature = "spdm-rustcrypto",
+        feature = "spdm-openssl"
+    )))]
     static DEFAULT: SpdmHkdf = SpdmHkdf {
         hkdf_expand_cb: |_hash_algo: SpdmBaseHashAlgo,
                          _pk: &[u8],
@@ -249,8 +362,15 @@ pub mod hkdf {
          -> Option<SpdmDigestStruct> { unimplemented!() },
     };
 
-    #[cfg(feature = "spdm-ring")]
+    #[cfg(feature = "spdm-openssl")]
+    use super::spdm_openssl::hkdf_impl::DEFAULT;
+    #[cfg(all(feature = "spdm-ring", not(feature = "spdm-openssl")))]
     use super::spdm_ring::hkdf_impl::DEFAULT;
+    #[cfg(all(
+        feature = "spdm-rustcrypto",
+        not(any(feature = "spdm-ring", feature = "spdm-openssl"))
+    ))]
+    use super::spdm_rustcrypto::hkdf_impl::DEFAULT;
 
     pub fn register(context: SpdmHkdf) -> bool {
         CRYPTO_HKDF.try_init_once(|| context).is_ok()
@@ -274,7 +394,11 @@ pub mod aead {
     use crate::error::SpdmResult;
     use crate::msgs::SpdmAeadAlgo;
 
-    #[cfg(not(any(feature = "spdm-ring")))]
+    #[cfg(not(any(
+        feature = "spdm-ring",
+        feature = "spdm-rustcrypto",
+        feature = "spdm-openssl"
+    )))]
     static DEFAULT: SpdmAead = SpdmAead {
         encrypt_cb: |_aead_algo: SpdmAeadAlgo,
                      _key: &[u8],
@@ -292,10 +416,18 @@ pub mod aead {
                      _tag: &[u8],
                      _plain_text: &mut [u8]|
          -> SpdmResult<usize> { unimplemented!() },
+        supported_algo_cb: SpdmAeadAlgo::empty,
     };
 
-    #[cfg(feature = "spdm-ring")]
+    #[cfg(feature = "spdm-openssl")]
+    use super::spdm_openssl::aead_impl::DEFAULT;
+    #[cfg(all(feature = "spdm-ring", not(feature = "spdm-openssl")))]
     use super::spdm_ring::aead_impl::DEFAULT;
+    #[cfg(all(
+        feature = "spdm-rustcrypto",
+        not(any(feature = "spdm-ring", feature = "spdm-openssl"))
+    ))]
+    use super::spdm_rustcrypto::aead_impl::DEFAULT;
 
     pub fn register(context: SpdmAead) -> bool {
         CRYPTO_AEAD.try_init_once(|| context).is_ok()
@@ -330,6 +462,12 @@ pub mod aead {
             .map_err(|_| spdm_err!(EFAULT))?
             .decrypt_cb)(aead_algo, key, iv, aad, cipher_text, tag, plain_text)
     }
+
+    pub fn supported_algo() -> SpdmAeadAlgo {
+        CRYPTO_AEAD
+            .try_get_or_init(|| DEFAULT)
+            .map_or(SpdmAeadAlgo::empty(), |aead| (aead.supported_algo_cb)())
+    }
 }
 
 pub mod rand {
@@ -337,13 +475,24 @@ pub mod rand {
     use crate::crypto::SpdmCryptoRandom;
     use crate::error::SpdmResult;
 
-    #[cfg(not(any(feature = "spdm-ring")))]
+    #[cfg(not(any(
+        feature = "spdm-ring",
+        feature = "spdm-rustcrypto",
+        feature = "spdm-openssl"
+    )))]
     static DEFAULT: SpdmCryptoRandom = SpdmCryptoRandom {
         get_random_cb: |_data: &mut [u8]| -> SpdmResult<usize> { unimplemented!() },
     };
 
-    #[cfg(feature = "spdm-ring")]
+    #[cfg(feature = "spdm-openssl")]
+    use super::spdm_openssl::rand_impl::DEFAULT;
+    #[cfg(all(feature = "spdm-ring", not(feature = "spdm-openssl")))]
     use super::spdm_ring::rand_impl::DEFAULT;
+    #[cfg(all(
+        feature = "spdm-rustcrypto",
+        not(any(feature = "spdm-ring", feature = "spdm-openssl"))
+    ))]
+    use super::spdm_rustcrypto::rand_impl::DEFAULT;
 
     pub fn register(context: SpdmCryptoRandom) -> bool {
         CRYPTO_RAND.try_init_once(|| context).is_ok()
@@ -357,11 +506,108 @@ pub mod rand {
     }
 }
 
+pub mod ext_alg {
+    use super::CRYPTO_EXT_ALG;
+    use crate::crypto::SpdmExtAlg;
+    use crate::error::SpdmResult;
+    use crate::msgs::{
+        SpdmBaseHashAlgo, SpdmDigestStruct, SpdmExtAlgStruct, SpdmSignatureStruct,
+        SPDM_MAX_ASYM_KEY_SIZE, SPDM_MAX_HASH_SIZE,
+    };
+
+    // no extended algorithm is supported unless registered
+    static DEFAULT: SpdmExtAlg = SpdmExtAlg {
+        hash_size_cb: |_ext_hash_algo: &SpdmExtAlgStruct| -> Option<u16> { None },
+        signature_size_cb: |_ext_asym_algo: &SpdmExtAlgStruct| -> Option<u16> { None },
+        hash_all_cb: |_ext_hash_algo: &SpdmExtAlgStruct,
+                      _data: &[u8]|
+         -> Option<SpdmDigestStruct> { None },
+        sign_cb: |_base_hash_algo: SpdmBaseHashAlgo,
+                  _ext_asym_algo: &SpdmExtAlgStruct,
+                  _data: &[u8]|
+         -> Option<SpdmSignatureStruct> { None },
+        verify_cb: |_base_hash_algo: SpdmBaseHashAlgo,
+                    _ext_asym_algo: &SpdmExtAlgStruct,
+                    _public_cert_der: &[u8],
+                    _data: &[u8],
+                    _signature: &SpdmSignatureStruct|
+         -> SpdmResult { spdm_result_err!(ENOSYS) },
+    };
+
+    pub fn register(context: SpdmExtAlg) -> bool {
+        CRYPTO_EXT_ALG.try_init_once(|| context).is_ok()
+    }
+
+    // None also for a size that does not fit in SpdmDigestStruct, so such
+    // an algorithm is never selected.
+    pub fn get_hash_size(ext_hash_algo: &SpdmExtAlgStruct) -> Option<u16> {
+        (CRYPTO_EXT_ALG
+            .try_get_or_init(|| DEFAULT)
+            .ok()?
+            .hash_size_cb)(ext_hash_algo)
+        .filter(|size| *size != 0 && *size as usize <= SPDM_MAX_HASH_SIZE)
+    }
+
+    // None also for a size that does not fit in SpdmSignatureStruct.
+    pub fn get_signature_size(ext_asym_algo: &SpdmExtAlgStruct) -> Option<u16> {
+        (CRYPTO_EXT_ALG
+            .try_get_or_init(|| DEFAULT)
+            .ok()?
+            .signature_size_cb)(ext_asym_algo)
+        .filter(|size| *size != 0 && *size as usize <= SPDM_MAX_ASYM_KEY_SIZE)
+    }
+
+    pub fn hash_all(ext_hash_algo: &SpdmExtAlgStruct, data: &[u8]) -> Option<SpdmDigestStruct> {
+        (CRYPTO_EXT_ALG.try_get_or_init(|| DEFAULT).ok()?.hash_all_cb)(ext_hash_algo, data)
+    }
+
+    pub fn sign(
+        base_hash_algo: SpdmBaseHashAlgo,
+        ext_asym_algo: &SpdmExtAlgStruct,
+        data: &[u8],
+    ) -> Option<SpdmSignatureStruct> {
+        (CRYPTO_EXT_ALG.try_get_or_init(|| DEFAULT).ok()?.sign_cb)(
+            base_hash_algo,
+            ext_asym_algo,
+            data,
+        )
+    }
+
+    pub fn verify(
+        base_hash_algo: SpdmBaseHashAlgo,
+        ext_asym_algo: &SpdmExtAlgStruct,
+        public_cert_der: &[u8],
+        data: &[u8],
+        signature: &SpdmSignatureStruct,
+    ) -> SpdmResult {
+        (CRYPTO_EXT_ALG
+            .try_get_or_init(|| DEFAULT)
+            .map_err(|_| spdm_err!(EFAULT))?
+            .verify_cb)(
+            base_hash_algo,
+            ext_asym_algo,
+            public_cert_der,
+            data,
+            signature,
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::testlib::*;
 
+    #[cfg(feature = "spdm-openssl")]
+    use super::spdm_openssl as backend;
+    #[cfg(all(feature = "spdm-ring", not(feature = "spdm-openssl")))]
+    use super::spdm_ring as backend;
+    #[cfg(all(
+        feature = "spdm-rustcrypto",
+        not(any(feature = "spdm-ring", feature = "spdm-openssl"))
+    ))]
+    use super::spdm_rustcrypto as backend;
+
     #[test]
     fn test_case0_cert_operation_register() {
         let context = SpdmCertOperation {
@@ -378,27 +624,27 @@ mod tests {
     }
     #[test]
     fn test_case0_hash_register() {
-        let state = hash::register(spdm_ring::hash_impl::DEFAULT);
+        let state = hash::register(backend::hash_impl::DEFAULT);
         assert_eq!(state, true);
     }
     #[test]
     fn test_case0_asym_verify_register() {
-        let state = asym_verify::register(spdm_ring::asym_verify_impl::DEFAULT);
+        let state = asym_verify::register(backend::asym_verify_impl::DEFAULT);
         assert_eq!(state, true);
     }
     #[test]
     fn test_case0_dhe_register() {
-        let state = dhe::register(spdm_ring::dhe_impl::DEFAULT);
+        let state = dhe::register(backend::dhe_impl::DEFAULT);
         assert_eq!(state, true);
     }
     #[test]
     fn test_case0_hkdf_register() {
-        let state = hkdf::register(spdm_ring::hkdf_impl::DEFAULT);
+        let state = hkdf::register(backend::hkdf_impl::DEFAULT);
         assert_eq!(state, true);
     }
     #[test]
     fn test_case0_aead_register() {
-        let state = aead::register(spdm_ring::aead_impl::DEFAULT);
+        let state = aead::register(backend::aead_impl::DEFAULT);
         assert_eq!(state, true);
     }
     #[test]
@@ -0,0 +1,355 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::crypto::SpdmAead;
+use crate::error::SpdmResult;
+use openssl::symm::{self, Cipher};
+
+use crate::msgs::SpdmAeadAlgo;
+
+pub static DEFAULT: SpdmAead = SpdmAead {
+    encrypt_cb: encrypt,
+    decrypt_cb: decrypt,
+    supported_algo_cb: supported_algo,
+};
+
+fn supported_algo() -> SpdmAeadAlgo {
+    let mut supported =
+        SpdmAeadAlgo::AES_128_GCM | SpdmAeadAlgo::AES_256_GCM | SpdmAeadAlgo::CHACHA20_POLY1305;
+    if sm4_gcm_supported() {
+        supported |= SpdmAeadAlgo::SM4_GCM;
+    }
+    supported
+}
+
+fn encrypt(
+    aead_algo: SpdmAeadAlgo,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    plain_text: &[u8],
+    tag: &mut [u8],
+    cipher_text: &mut [u8],
+) -> SpdmResult<(usize, usize)> {
+    check_sizes(
+        aead_algo,
+        key,
+        iv,
+        tag.len(),
+        plain_text.len(),
+        cipher_text.len(),
+    );
+
+    let out = match aead_algo {
+        SpdmAeadAlgo::SM4_GCM => sm4_gcm_encrypt(key, iv, aad, plain_text, tag)?,
+        _ => symm::encrypt_aead(cipher(aead_algo), key, Some(iv), aad, plain_text, tag)
+            .map_err(|_| spdm_err!(EFAULT))?,
+    };
+    cipher_text.copy_from_slice(&out);
+    Ok((cipher_text.len(), tag.len()))
+}
+
+fn decrypt(
+    aead_algo: SpdmAeadAlgo,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    cipher_text: &[u8],
+    tag: &[u8],
+    plain_text: &mut [u8],
+) -> SpdmResult<usize> {
+    check_sizes(
+        aead_algo,
+        key,
+        iv,
+        tag.len(),
+        plain_text.len(),
+        cipher_text.len(),
+    );
+
+    let res = match aead_algo {
+        SpdmAeadAlgo::SM4_GCM => sm4_gcm_decrypt(key, iv, aad, cipher_text, tag),
+        _ => symm::decrypt_aead(cipher(aead_algo), key, Some(iv), aad, cipher_text, tag)
+            .map_err(|_| spdm_err!(EFAULT)),
+    };
+    match res {
+        Ok(out) => {
+            plain_text.copy_from_slice(&out);
+            Ok(plain_text.len())
+        }
+        Err(err) => {
+            // as the other backends, leave nothing behind on failure
+            plain_text.iter_mut().for_each(|byte| *byte = 0);
+            Err(err)
+        }
+    }
+}
+
+// The same size checks as the ring backend, caller errors rather than data errors.
+fn check_sizes(
+    aead_algo: SpdmAeadAlgo,
+    key: &[u8],
+    iv: &[u8],
+    tag_size: usize,
+    plain_text_size: usize,
+    cipher_text_size: usize,
+) {
+    match aead_algo {
+        SpdmAeadAlgo::AES_128_GCM
+        | SpdmAeadAlgo::AES_256_GCM
+        | SpdmAeadAlgo::CHACHA20_POLY1305
+        | SpdmAeadAlgo::SM4_GCM => {}
+        _ => {
+            panic!();
+        }
+    }
+    if key.len() != aead_algo.get_key_size() as usize {
+        panic!("key len invalid");
+    }
+    if iv.len() != aead_algo.get_iv_size() as usize {
+        panic!("iv len invalid");
+    }
+    if tag_size != aead_algo.get_tag_size() as usize {
+        panic!("tag len invalid");
+    }
+    if cipher_text_size != plain_text_size {
+        panic!("cipher_text len invalid");
+    }
+}
+
+fn cipher(aead_algo: SpdmAeadAlgo) -> Cipher {
+    match aead_algo {
+        SpdmAeadAlgo::AES_128_GCM => Cipher::aes_128_gcm(),
+        SpdmAeadAlgo::AES_256_GCM => Cipher::aes_256_gcm(),
+        SpdmAeadAlgo::CHACHA20_POLY1305 => Cipher::chacha20_poly1305(),
+        _ => unreachable!(),
+    }
+}
+
+// The openssl crate has no SM4-GCM constructor, it is fetched by name, which
+// takes OpenSSL 3. Builds without SM4 fail the fetch.
+#[cfg(ossl300)]
+fn sm4_gcm() -> SpdmResult<openssl::cipher::Cipher> {
+    openssl::cipher::Cipher::fetch(None, "SM4-GCM", None).map_err(|_| spdm_err!(ENOSYS))
+}
+
+#[cfg(ossl300)]
+fn sm4_gcm_supported() -> bool {
+    sm4_gcm().is_ok()
+}
+
+#[cfg(ossl300)]
+fn sm4_gcm_encrypt(
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    plain_text: &[u8],
+    tag: &mut [u8],
+) -> SpdmResult<Vec<u8>> {
+    let cipher = sm4_gcm()?;
+    let mut ctx = openssl::cipher_ctx::CipherCtx::new().map_err(|_| spdm_err!(ENOMEM))?;
+    let mut out = Vec::new();
+    ctx.encrypt_init(Some(&cipher), Some(key), Some(iv))
+        .and_then(|_| ctx.cipher_update(aad, None))
+        .and_then(|_| ctx.cipher_update_vec(plain_text, &mut out))
+        .and_then(|_| ctx.cipher_final_vec(&mut out))
+        .and_then(|_| ctx.tag(tag))
+        .map_err(|_| spdm_err!(EFAULT))?;
+    Ok(out)
+}
+
+#[cfg(ossl300)]
+fn sm4_gcm_decrypt(
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    cipher_text: &[u8],
+    tag: &[u8],
+) -> SpdmResult<Vec<u8>> {
+    let cipher = sm4_gcm()?;
+    let mut ctx = openssl::cipher_ctx::CipherCtx::new().map_err(|_| spdm_err!(ENOMEM))?;
+    let mut out = Vec::new();
+    ctx.decrypt_init(Some(&cipher), Some(key), Some(iv))
+        .and_then(|_| ctx.cipher_update(aad, None))
+        .and_then(|_| ctx.cipher_update_vec(cipher_text, &mut out))
+        .and_then(|_| ctx.set_tag(tag))
+        .and_then(|_| ctx.cipher_final_vec(&mut out))
+        .map_err(|_| spdm_err!(EFAULT))?;
+    Ok(out)
+}
+
+#[cfg(not(ossl300))]
+fn sm4_gcm_supported() -> bool {
+    false
+}
+
+#[cfg(not(ossl300))]
+fn sm4_gcm_encrypt(
+    _key: &[u8],
+    _iv: &[u8],
+    _aad: &[u8],
+    _plain_text: &[u8],
+    _tag: &mut [u8],
+) -> SpdmResult<Vec<u8>> {
+    spdm_result_err!(ENOSYS)
+}
+
+#[cfg(not(ossl300))]
+fn sm4_gcm_decrypt(
+    _key: &[u8],
+    _iv: &[u8],
+    _aad: &[u8],
+    _cipher_text: &[u8],
+    _tag: &[u8],
+) -> SpdmResult<Vec<u8>> {
+    spdm_result_err!(ENOSYS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case0_encrypt() {
+        // McGrew and Viega GCM test case 2, AES-128 with a zero key and block
+        let expected_cipher_text = [
+            0x03, 0x88, 0xda, 0xce, 0x60, 0xb6, 0xa3, 0x92, 0xf3, 0x28, 0xc2, 0xb9, 0x71, 0xb2,
+            0xfe, 0x78,
+        ];
+        let expected_tag = [
+            0xab, 0x6e, 0x47, 0xd4, 0x2c, 0xec, 0x13, 0xbd, 0xf5, 0x3a, 0x67, 0xb2, 0x12, 0x57,
+            0xbd, 0xdf,
+        ];
+        let tag = &mut [0u8; 16];
+        let cipher_text = &mut [0u8; 16];
+        let status = encrypt(
+            SpdmAeadAlgo::AES_128_GCM,
+            &[0u8; 16],
+            &[0u8; 12],
+            &[],
+            &[0u8; 16],
+            tag,
+            cipher_text,
+        );
+        assert_eq!(status.unwrap(), (16, 16));
+        assert_eq!(cipher_text, &expected_cipher_text);
+        assert_eq!(tag, &expected_tag);
+    }
+    #[test]
+    fn test_case1_encrypt() {
+        for aead_algo in [
+            SpdmAeadAlgo::AES_128_GCM,
+            SpdmAeadAlgo::AES_256_GCM,
+            SpdmAeadAlgo::CHACHA20_POLY1305,
+            SpdmAeadAlgo::SM4_GCM,
+        ]
+        .iter()
+        {
+            let key = &[100u8; 32][..aead_algo.get_key_size() as usize];
+            let iv = &[100u8; 12];
+            let aad = &[100u8; 16];
+            let plain_text = &[100u8; 64];
+            let tag = &mut [0u8; 16];
+            let cipher_text = &mut [0u8; 64];
+            if !supported_algo().contains(*aead_algo) {
+                // SM4-GCM takes OpenSSL 3, and is left out of some builds
+                assert_eq!(*aead_algo, SpdmAeadAlgo::SM4_GCM);
+                continue;
+            }
+            encrypt(*aead_algo, key, iv, aad, plain_text, tag, cipher_text).unwrap();
+
+            let decrypted = &mut [0u8; 64];
+            let status = decrypt(*aead_algo, key, iv, aad, cipher_text, tag, decrypted);
+            assert_eq!(status.unwrap(), 64);
+            assert_eq!(&decrypted[..], &plain_text[..]);
+
+            tag[0] ^= 1;
+            let status = decrypt(*aead_algo, key, iv, aad, cipher_text, tag, decrypted);
+            assert!(status.is_err());
+            assert!(decrypted.iter().all(|byte| *byte == 0));
+        }
+    }
+    #[test]
+    fn test_case4_encrypt() {
+        // RFC 8998 A.1, SM4-GCM
+        if !supported_algo().contains(SpdmAeadAlgo::SM4_GCM) {
+            let tag = &mut [0u8; 16];
+            let status = encrypt(
+                SpdmAeadAlgo::SM4_GCM,
+                &[0u8; 16],
+                &[0u8; 12],
+                &[],
+                &[0u8; 16],
+                tag,
+                &mut [0u8; 16],
+            );
+            assert!(status.is_err());
+            return;
+        }
+        let key = [
+            0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0xfe, 0xdc, 0xba, 0x98, 0x76, 0x54,
+            0x32, 0x10,
+        ];
+        let iv = [
+            0x00, 0x00, 0x12, 0x34, 0x56, 0x78, 0x00, 0x00, 0x00, 0x00, 0xab, 0xcd,
+        ];
+        let aad = [
+            0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad, 0xbe, 0xef, 0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad,
+            0xbe, 0xef, 0xab, 0xad, 0xda, 0xd2,
+        ];
+        let mut plain_text = [0u8; 64];
+        for (i, byte) in plain_text.iter_mut().enumerate() {
+            *byte = [0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, 0xee, 0xaa][i / 8];
+        }
+        let expected_cipher_text = [
+            0x17, 0xf3, 0x99, 0xf0, 0x8c, 0x67, 0xd5, 0xee, 0x19, 0xd0, 0xdc, 0x99, 0x69, 0xc4,
+            0xbb, 0x7d, 0x5f, 0xd4, 0x6f, 0xd3, 0x75, 0x64, 0x89, 0x06, 0x91, 0x57, 0xb2, 0x82,
+            0xbb, 0x20, 0x07, 0x35, 0xd8, 0x27, 0x10, 0xca, 0x5c, 0x22, 0xf0, 0xcc, 0xfa, 0x7c,
+            0xbf, 0x93, 0xd4, 0x96, 0xac, 0x15, 0xa5, 0x68, 0x34, 0xcb, 0xcf, 0x98, 0xc3, 0x97,
+            0xb4, 0x02, 0x4a, 0x26, 0x91, 0x23, 0x3b, 0x8d,
+        ];
+        let expected_tag = [
+            0x83, 0xde, 0x35, 0x41, 0xe4, 0xc2, 0xb5, 0x81, 0x77, 0xe0, 0x65, 0xa9, 0xbf, 0x7b,
+            0x62, 0xec,
+        ];
+        let tag = &mut [0u8; 16];
+        let cipher_text = &mut [0u8; 64];
+        let status = encrypt(
+            SpdmAeadAlgo::SM4_GCM,
+            &key,
+            &iv,
+            &aad,
+            &plain_text,
+            tag,
+            cipher_text,
+        );
+        assert_eq!(status.unwrap(), (64, 16));
+        assert_eq!(&cipher_text[..], &expected_cipher_text[..]);
+        assert_eq!(tag, &expected_tag);
+    }
+    #[test]
+    #[should_panic]
+    fn test_case2_encrypt() {
+        let aead_algo = SpdmAeadAlgo::empty();
+        let key = &mut [100u8; 1];
+        let iv = &mut [100u8; 12];
+        let plain_text = &mut [100u8; 16];
+        let tag = &mut [100u8; 16];
+        let aad = &mut [100u8; 16];
+        let cipher_text = &mut [100u8; 16];
+        let _ = encrypt(aead_algo, key, iv, aad, plain_text, tag, cipher_text);
+    }
+    #[test]
+    #[should_panic]
+    fn test_case3_encrypt() {
+        let aead_algo = SpdmAeadAlgo::CHACHA20_POLY1305;
+        let key = &mut [100u8; 32];
+        let iv = &mut [100u8; 12];
+        let plain_text = &mut [100u8; 16];
+        let tag = &mut [100u8; 16];
+        let aad = &mut [100u8; 16];
+        let cipher_text = &mut [100u8; 1];
+        let _ = encrypt(aead_algo, key, iv, aad, plain_text, tag, cipher_text);
+    }
+}
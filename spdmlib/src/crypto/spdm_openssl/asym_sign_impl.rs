@@ -0,0 +1,289 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use super::asym_verify_impl::{ecdsa_curve, eddsa_id, rsa_padding, sm2_digest, sm2_public_key};
+use super::hash_impl::message_digest;
+use crate::crypto::SpdmAsymSign;
+use crate::error::SpdmResult;
+use crate::msgs::{SpdmBaseAsymAlgo, SpdmBaseHashAlgo, SpdmSignatureStruct};
+use bytes::BytesMut;
+use conquer_once::spin::OnceCell;
+use openssl::ecdsa::EcdsaSig;
+use openssl::pkey::{PKey, Private};
+use openssl::pkey_ctx::PkeyCtx;
+use openssl::rsa::Padding;
+use openssl::sign::{RsaPssSaltlen, Signer};
+
+static PRIVATE_KEY: OnceCell<PKey<Private>> = OnceCell::uninit();
+
+pub static DEFAULT: SpdmAsymSign = SpdmAsymSign { sign_cb: asym_sign };
+
+/// Set the key the default signer uses, a DER encoded PKCS#8, RSA or EC
+/// private key. SM2 and EdDSA keys must be PKCS#8. It can be set once.
+pub fn set_private_key(der: &[u8]) -> SpdmResult {
+    let private_key = PKey::private_key_from_der(der).map_err(|_| spdm_err!(EINVAL))?;
+    PRIVATE_KEY
+        .try_init_once(|| private_key)
+        .map_err(|_| spdm_err!(EBUSY))
+}
+
+fn asym_sign(
+    base_hash_algo: SpdmBaseHashAlgo,
+    base_asym_algo: SpdmBaseAsymAlgo,
+    context: &[u8],
+    data: &[u8],
+) -> Option<SpdmSignatureStruct> {
+    sign_with(
+        PRIVATE_KEY.get()?,
+        base_hash_algo,
+        base_asym_algo,
+        context,
+        data,
+    )
+}
+
+fn sign_with(
+    private_key: &PKey<Private>,
+    base_hash_algo: SpdmBaseHashAlgo,
+    base_asym_algo: SpdmBaseAsymAlgo,
+    context: &[u8],
+    data: &[u8],
+) -> Option<SpdmSignatureStruct> {
+    let md = message_digest(base_hash_algo)?;
+
+    let signature = if let Some(curve) = ecdsa_curve(base_asym_algo) {
+        let key_size = base_asym_algo.get_size() as usize;
+        let ec_key = private_key.ec_key().ok()?;
+        if ec_key.group().curve_name() != Some(curve) {
+            return None;
+        }
+        let digest = openssl::hash::hash(md, data).ok()?;
+        let signature = EcdsaSig::sign(&digest, &ec_key).ok()?;
+        fixed_size_signature(&signature, key_size)?
+    } else if let Some(padding) = rsa_padding(base_asym_algo) {
+        let key_size = base_asym_algo.get_size() as usize;
+        if private_key.rsa().is_err() || private_key.size() != key_size {
+            return None;
+        }
+        let mut signer = Signer::new(md, private_key).ok()?;
+        signer.set_rsa_padding(padding).ok()?;
+        if padding == Padding::PKCS1_PSS {
+            signer
+                .set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)
+                .ok()?;
+        }
+        signer.sign_oneshot_to_vec(data).ok()?
+    } else if base_asym_algo == SpdmBaseAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256 {
+        if base_hash_algo != SpdmBaseHashAlgo::TPM_ALG_SM3_256 {
+            return None;
+        }
+        let ec_key = sm2_public_key(private_key)?;
+        let digest = sm2_digest(&ec_key, context, data).ok()?;
+        let mut ctx = PkeyCtx::new(private_key).ok()?;
+        ctx.sign_init().ok()?;
+        let mut signature = Vec::new();
+        ctx.sign_to_vec(&digest, &mut signature).ok()?;
+        let signature = EcdsaSig::from_der(&signature).ok()?;
+        fixed_size_signature(&signature, base_asym_algo.get_size() as usize)?
+    } else if let Some(id) = eddsa_id(base_asym_algo) {
+        if private_key.id() != id {
+            return None;
+        }
+        let mut signer = Signer::new_without_digest(private_key).ok()?;
+        signer.sign_oneshot_to_vec(data).ok()?
+    } else {
+        return None;
+    };
+
+    Some(SpdmSignatureStruct::from(BytesMut::from(&signature[..])))
+}
+
+// SPDM wants r and s at their full width, back to back
+fn fixed_size_signature(signature: &EcdsaSig, key_size: usize) -> Option<Vec<u8>> {
+    let mut fixed = signature.r().to_vec_padded(key_size as i32 / 2).ok()?;
+    fixed.extend(signature.s().to_vec_padded(key_size as i32 / 2).ok()?);
+    Some(fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::asym_verify_impl::asym_verify;
+    use super::*;
+    use crate::testlib::*;
+
+    fn read_key_and_chain(key_dir: &str, key_file: &str) -> (PKey<Private>, Vec<u8>) {
+        let key_dir = get_test_key_directory().join(key_dir);
+        let mut cert_chain = Vec::new();
+        for cert in ["ca.cert.der", "inter.cert.der", "end_responder.cert.der"].iter() {
+            cert_chain.extend(std::fs::read(key_dir.join(cert)).unwrap());
+        }
+        let der = std::fs::read(key_dir.join(key_file)).unwrap();
+        (PKey::private_key_from_der(&der).unwrap(), cert_chain)
+    }
+
+    #[test]
+    fn test_case0_asym_sign() {
+        let data = &[0x10u8; 4096];
+        for (key_dir, key_file, base_asym_algo, base_hash_algo) in [
+            (
+                "test_key/EcP256",
+                "end_responder.key.p8",
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256,
+                SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            ),
+            (
+                "test_key/EcP384",
+                "end_responder.key.p8",
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+                SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            ),
+            (
+                "test_key/EcP521",
+                "end_responder.key.p8",
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
+                SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            ),
+            (
+                "test_key/Rsa3072",
+                "end_responder.key.der",
+                SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072,
+                SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+            ),
+            (
+                "test_key/Rsa3072",
+                "end_responder.key.der",
+                SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072,
+                SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+            ),
+            (
+                "test_key/Sm2",
+                "end_responder.key.der",
+                SpdmBaseAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256,
+                SpdmBaseHashAlgo::TPM_ALG_SM3_256,
+            ),
+            (
+                "test_key/Ed25519",
+                "end_responder.key.der",
+                SpdmBaseAsymAlgo::EDDSA_ED25519,
+                SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            ),
+            (
+                "test_key/Ed448",
+                "end_responder.key.der",
+                SpdmBaseAsymAlgo::EDDSA_ED448,
+                SpdmBaseHashAlgo::TPM_ALG_SHA_512,
+            ),
+        ]
+        .iter()
+        {
+            let (private_key, cert_chain) = read_key_and_chain(key_dir, key_file);
+            let context = crate::common::SPDM_SIGNING_CONTEXT_CHALLENGE_AUTH;
+            let mut signature = sign_with(
+                &private_key,
+                *base_hash_algo,
+                *base_asym_algo,
+                context,
+                data,
+            )
+            .unwrap();
+            assert_eq!(signature.data_size, base_asym_algo.get_size());
+
+            assert!(asym_verify(
+                *base_hash_algo,
+                *base_asym_algo,
+                context,
+                &cert_chain,
+                data,
+                &signature
+            )
+            .is_ok());
+            signature.data[1] ^= 0x01;
+            assert!(asym_verify(
+                *base_hash_algo,
+                *base_asym_algo,
+                context,
+                &cert_chain,
+                data,
+                &signature
+            )
+            .is_err());
+        }
+    }
+    #[test]
+    fn test_case1_asym_sign() {
+        let (private_key, _) = read_key_and_chain("test_key/EcP384", "end_responder.key.p8");
+        let data = &[0x10u8; 64];
+        let base_hash_algo = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
+        // the key does not fit the algorithm
+        for base_asym_algo in [
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256,
+            SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072,
+            SpdmBaseAsymAlgo::empty(),
+        ]
+        .iter()
+        {
+            assert!(sign_with(&private_key, base_hash_algo, *base_asym_algo, b"", data).is_none());
+        }
+        assert!(sign_with(
+            &private_key,
+            SpdmBaseHashAlgo::empty(),
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+            b"",
+            data
+        )
+        .is_none());
+    }
+    #[test]
+    fn test_case2_asym_sign() {
+        let (private_key, cert_chain) = read_key_and_chain("test_key/Sm2", "end_responder.key.der");
+        let data = &[0x10u8; 64];
+        let base_hash_algo = SpdmBaseHashAlgo::TPM_ALG_SM3_256;
+        let base_asym_algo = SpdmBaseAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256;
+        let signature = sign_with(
+            &private_key,
+            base_hash_algo,
+            base_asym_algo,
+            crate::common::SPDM_SIGNING_CONTEXT_MEASUREMENTS,
+            data,
+        )
+        .unwrap();
+        // the signer ID is part of the SM2 signature
+        assert!(asym_verify(
+            base_hash_algo,
+            base_asym_algo,
+            crate::common::SPDM_SIGNING_CONTEXT_MEASUREMENTS,
+            &cert_chain,
+            data,
+            &signature
+        )
+        .is_ok());
+        assert!(asym_verify(
+            base_hash_algo,
+            base_asym_algo,
+            crate::common::SPDM_SIGNING_CONTEXT_CHALLENGE_AUTH,
+            &cert_chain,
+            data,
+            &signature
+        )
+        .is_err());
+        // SM2 goes with SM3 only
+        assert!(sign_with(
+            &private_key,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            base_asym_algo,
+            b"",
+            data
+        )
+        .is_none());
+        // and an SM2 key is not an ECDSA key
+        assert!(sign_with(
+            &private_key,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256,
+            b"",
+            data
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use super::hash_impl::{self, message_digest};
+use crate::crypto::{self, SpdmAsymVerify};
+use crate::error::SpdmResult;
+use crate::msgs::{SpdmBaseAsymAlgo, SpdmBaseHashAlgo, SpdmSignatureStruct};
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey, EcKeyRef};
+use openssl::ecdsa::EcdsaSig;
+use openssl::error::ErrorStack;
+use openssl::hash::{DigestBytes, Hasher, MessageDigest};
+use openssl::nid::Nid;
+use openssl::pkey::{HasPublic, Id, PKeyRef, Public};
+use openssl::pkey_ctx::PkeyCtx;
+use openssl::rsa::Padding;
+use openssl::sign::{RsaPssSaltlen, Verifier};
+use openssl::x509::X509;
+
+pub static DEFAULT: SpdmAsymVerify = SpdmAsymVerify {
+    verify_cb: asym_verify,
+    supported_algo_cb: supported_algo,
+};
+
+fn supported_algo() -> SpdmBaseAsymAlgo {
+    let mut supported = SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048
+        | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072
+        | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_2048
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096
+        | SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256
+        | SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384
+        | SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521
+        | SpdmBaseAsymAlgo::EDDSA_ED25519
+        | SpdmBaseAsymAlgo::EDDSA_ED448;
+    if hash_impl::supported_algo().contains(SpdmBaseHashAlgo::TPM_ALG_SM3_256)
+        && EcGroup::from_curve_name(Nid::SM2).is_ok()
+    {
+        supported |= SpdmBaseAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256;
+    }
+    supported
+}
+
+pub(crate) fn asym_verify(
+    base_hash_algo: SpdmBaseHashAlgo,
+    base_asym_algo: SpdmBaseAsymAlgo,
+    context: &[u8],
+    public_cert_der: &[u8],
+    data: &[u8],
+    signature: &SpdmSignatureStruct,
+) -> SpdmResult {
+    let (leaf_begin, leaf_end) =
+        crypto::cert_operation::get_cert_from_cert_chain(public_cert_der, -1)?;
+    let leaf_cert =
+        X509::from_der(&public_cert_der[leaf_begin..leaf_end]).map_err(|_| spdm_err!(EFAULT))?;
+    let public_key = leaf_cert.public_key().map_err(|_| spdm_err!(EFAULT))?;
+    let md = message_digest(base_hash_algo).ok_or(spdm_err!(EFAULT))?;
+    let signature = signature.as_ref();
+
+    let res = if let Some(curve) = ecdsa_curve(base_asym_algo) {
+        let key_size = base_asym_algo.get_size() as usize;
+        let ec_key = public_key.ec_key().map_err(|_| spdm_err!(EFAULT))?;
+        if ec_key.group().curve_name() != Some(curve) || signature.len() != key_size {
+            return spdm_result_err!(EFAULT);
+        }
+        let r = BigNum::from_slice(&signature[..key_size / 2]).map_err(|_| spdm_err!(EFAULT))?;
+        let s = BigNum::from_slice(&signature[key_size / 2..]).map_err(|_| spdm_err!(EFAULT))?;
+        let signature = EcdsaSig::from_private_components(r, s).map_err(|_| spdm_err!(EFAULT))?;
+        let digest = openssl::hash::hash(md, data).map_err(|_| spdm_err!(EFAULT))?;
+        signature.verify(&digest, &ec_key)
+    } else if let Some(padding) = rsa_padding(base_asym_algo) {
+        let key_size = base_asym_algo.get_size() as usize;
+        if public_key.rsa().is_err() || public_key.size() != key_size {
+            return spdm_result_err!(EFAULT);
+        }
+        let mut verifier = Verifier::new(md, &public_key).map_err(|_| spdm_err!(EFAULT))?;
+        verifier
+            .set_rsa_padding(padding)
+            .map_err(|_| spdm_err!(EFAULT))?;
+        if padding == Padding::PKCS1_PSS {
+            verifier
+                .set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)
+                .map_err(|_| spdm_err!(EFAULT))?;
+        }
+        verifier.verify_oneshot(signature, data)
+    } else if base_asym_algo == SpdmBaseAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256 {
+        let key_size = base_asym_algo.get_size() as usize;
+        let ec_key = sm2_public_key(&public_key).ok_or(spdm_err!(EFAULT))?;
+        if base_hash_algo != SpdmBaseHashAlgo::TPM_ALG_SM3_256 || signature.len() != key_size {
+            return spdm_result_err!(EFAULT);
+        }
+        let r = BigNum::from_slice(&signature[..key_size / 2]).map_err(|_| spdm_err!(EFAULT))?;
+        let s = BigNum::from_slice(&signature[key_size / 2..]).map_err(|_| spdm_err!(EFAULT))?;
+        let signature = EcdsaSig::from_private_components(r, s)
+            .and_then(|signature| signature.to_der())
+            .map_err(|_| spdm_err!(EFAULT))?;
+        let digest = sm2_digest(&ec_key, context, data).map_err(|_| spdm_err!(EFAULT))?;
+        PkeyCtx::new(&public_key).and_then(|mut ctx| {
+            ctx.verify_init()
+                .and_then(|_| ctx.verify(&digest, &signature))
+        })
+    } else if let Some(id) = eddsa_id(base_asym_algo) {
+        if public_key.id() != id || signature.len() != base_asym_algo.get_size() as usize {
+            return spdm_result_err!(EFAULT);
+        }
+        Verifier::new_without_digest(&public_key)
+            .and_then(|mut verifier| verifier.verify_oneshot(signature, data))
+    } else {
+        return spdm_result_err!(EFAULT);
+    };
+
+    match res {
+        Ok(true) => Ok(()),
+        _ => spdm_result_err!(EFAULT),
+    }
+}
+
+pub(crate) fn ecdsa_curve(base_asym_algo: SpdmBaseAsymAlgo) -> Option<Nid> {
+    match base_asym_algo {
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256 => Some(Nid::X9_62_PRIME256V1),
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384 => Some(Nid::SECP384R1),
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521 => Some(Nid::SECP521R1),
+        _ => None,
+    }
+}
+
+pub(crate) fn eddsa_id(base_asym_algo: SpdmBaseAsymAlgo) -> Option<Id> {
+    match base_asym_algo {
+        SpdmBaseAsymAlgo::EDDSA_ED25519 => Some(Id::ED25519),
+        SpdmBaseAsymAlgo::EDDSA_ED448 => Some(Id::ED448),
+        _ => None,
+    }
+}
+
+/// The public key of `pkey` if it is on the SM2 curve. OpenSSL 3 loads SM2
+/// keys through its provider, where neither `id()` nor `ec_key()` sees them.
+pub(crate) fn sm2_public_key<T: HasPublic>(pkey: &PKeyRef<T>) -> Option<EcKey<Public>> {
+    let ec_key = EcKey::public_key_from_der(&pkey.public_key_to_der().ok()?).ok()?;
+    if ec_key.group().curve_name() == Some(Nid::SM2) {
+        Some(ec_key)
+    } else {
+        None
+    }
+}
+
+/// The SM2 message digest e = SM3(Z || M), where the signer's
+/// Z = SM3(ENTL || ID || a || b || xG || yG || xA || yA), see GB/T 32918.2.
+/// OpenSSL signs and verifies e as is.
+pub(crate) fn sm2_digest<T: HasPublic>(
+    ec_key: &EcKeyRef<T>,
+    id: &[u8],
+    data: &[u8],
+) -> Result<DigestBytes, ErrorStack> {
+    let group = ec_key.group();
+    let mut ctx = BigNumContext::new()?;
+    let (mut p, mut a, mut b) = (BigNum::new()?, BigNum::new()?, BigNum::new()?);
+    group.components_gfp(&mut p, &mut a, &mut b, &mut ctx)?;
+    let (mut x_g, mut y_g) = (BigNum::new()?, BigNum::new()?);
+    group
+        .generator_opt()
+        .ok_or_else(ErrorStack::get)?
+        .affine_coordinates(group, &mut x_g, &mut y_g, &mut ctx)?;
+    let (mut x_a, mut y_a) = (BigNum::new()?, BigNum::new()?);
+    ec_key
+        .public_key()
+        .affine_coordinates(group, &mut x_a, &mut y_a, &mut ctx)?;
+
+    let mut hasher = Hasher::new(MessageDigest::sm3())?;
+    hasher.update(&((id.len() * 8) as u16).to_be_bytes())?;
+    hasher.update(id)?;
+    for n in [&a, &b, &x_g, &y_g, &x_a, &y_a].iter() {
+        hasher.update(&n.to_vec_padded(p.num_bytes())?)?;
+    }
+    let z = hasher.finish()?;
+
+    let mut hasher = Hasher::new(MessageDigest::sm3())?;
+    hasher.update(&z)?;
+    hasher.update(data)?;
+    hasher.finish()
+}
+
+pub(crate) fn rsa_padding(base_asym_algo: SpdmBaseAsymAlgo) -> Option<Padding> {
+    match base_asym_algo {
+        SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048
+        | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072
+        | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096 => Some(Padding::PKCS1),
+        SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_2048
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096 => Some(Padding::PKCS1_PSS),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testlib::*;
+
+    #[test]
+    fn test_case0_asym_verify() {
+        let base_hash_algo = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
+        let base_asym_algo = SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
+        let mut signature = SpdmSignatureStruct {
+            data_size: 96,
+            data: [0x00u8; crate::msgs::SPDM_MAX_ASYM_KEY_SIZE],
+        };
+        signature.data[10] = 0x10;
+        signature.data[60] = 0x10;
+
+        let public_cert_der = &mut cert_chain_array();
+        let data = &mut [0x10u8; 4096];
+
+        for base_asym_algo in [
+            base_asym_algo,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256,
+            SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072,
+            SpdmBaseAsymAlgo::empty(),
+        ]
+        .iter()
+        {
+            let asym_verify = asym_verify(
+                base_hash_algo,
+                *base_asym_algo,
+                b"",
+                public_cert_der,
+                data,
+                &signature,
+            );
+            assert!(asym_verify.is_err());
+        }
+    }
+}
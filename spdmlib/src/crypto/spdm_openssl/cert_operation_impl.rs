@@ -0,0 +1,175 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::crypto::SpdmCertOperation;
+use crate::error::SpdmResult;
+use openssl::error::ErrorStack;
+use openssl::stack::Stack;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::verify::X509VerifyFlags;
+use openssl::x509::{X509PurposeId, X509StoreContext, X509};
+
+pub static DEFAULT: SpdmCertOperation = SpdmCertOperation {
+    get_cert_from_cert_chain_cb: get_cert_from_cert_chain,
+    verify_cert_chain_cb: verify_cert_chain,
+};
+
+fn get_cert_from_cert_chain(cert_chain: &[u8], index: isize) -> SpdmResult<(usize, usize)> {
+    let mut offset = 0usize;
+    let mut this_index = 0isize;
+    loop {
+        if offset > cert_chain.len() || cert_chain[offset..].len() < 4 {
+            return spdm_result_err!(EINVAL);
+        }
+        if cert_chain[offset] != 0x30 || cert_chain[offset + 1] != 0x82 {
+            return spdm_result_err!(EINVAL);
+        }
+        let this_cert_len =
+            ((cert_chain[offset + 2] as usize) << 8) + (cert_chain[offset + 3] as usize) + 4;
+        if offset + this_cert_len > cert_chain.len() {
+            return spdm_result_err!(EINVAL);
+        }
+        if this_index == index {
+            // return the this one
+            return Ok((offset, offset + this_cert_len));
+        }
+        this_index += 1;
+        if (offset + this_cert_len == cert_chain.len()) && (index == -1) {
+            // return the last one
+            return Ok((offset, offset + this_cert_len));
+        }
+        offset += this_cert_len;
+    }
+}
+
+fn verify_cert_chain(cert_chain: &[u8]) -> SpdmResult {
+    verify_cert_chain_with_flags(cert_chain, X509VerifyFlags::PARTIAL_CHAIN)
+}
+
+//
+// The first certificate is the trust anchor, whether self-signed or not
+// (hence PARTIAL_CHAIN). The leaf must be fit for responder authentication,
+// which OpenSSL checks as the SSL server purpose.
+//
+fn verify_cert_chain_with_flags(cert_chain: &[u8], flags: X509VerifyFlags) -> SpdmResult {
+    let mut certs = Vec::new();
+    let mut offset = 0usize;
+    while offset < cert_chain.len() {
+        let (_, cert_len) = get_cert_from_cert_chain(&cert_chain[offset..], 0)?;
+        let cert = X509::from_der(&cert_chain[offset..(offset + cert_len)])
+            .map_err(|_| spdm_err!(EINVAL))?;
+        certs.push(cert);
+        offset += cert_len;
+    }
+    if certs.len() < 2 {
+        return spdm_result_err!(EINVAL);
+    }
+
+    if let Ok(true) = verify_certs(&certs, flags) {
+        info!("Cert verification Pass\n");
+        Ok(())
+    } else {
+        error!("Cert verification Fail\n");
+        spdm_result_err!(EFAULT)
+    }
+}
+
+fn verify_certs(certs: &[X509], flags: X509VerifyFlags) -> Result<bool, ErrorStack> {
+    let (leaf, issuers) = certs.split_last().unwrap();
+
+    let mut store = X509StoreBuilder::new()?;
+    store.add_cert(issuers[0].clone())?;
+    store.set_flags(flags)?;
+    store.set_purpose(X509PurposeId::SSL_SERVER)?;
+    let store = store.build();
+
+    let mut chain = Stack::new()?;
+    for cert in issuers[1..].iter() {
+        chain.push(cert.clone())?;
+    }
+
+    let mut context = X509StoreContext::new()?;
+    context.init(&store, leaf, &chain, |context| {
+        let res = context.verify_cert();
+        if let Ok(false) = res {
+            error!("{}\n", context.error());
+        }
+        res
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testlib::*;
+
+    // the test certificates have expired
+    fn flags() -> X509VerifyFlags {
+        X509VerifyFlags::PARTIAL_CHAIN | X509VerifyFlags::NO_CHECK_TIME
+    }
+
+    fn read_cert_chain(key_dir: &str) -> Vec<u8> {
+        let key_dir = get_test_key_directory().join("test_key").join(key_dir);
+        let mut cert_chain = Vec::new();
+        for cert in ["ca.cert.der", "inter.cert.der", "end_responder.cert.der"].iter() {
+            cert_chain.extend(std::fs::read(key_dir.join(cert)).unwrap());
+        }
+        cert_chain
+    }
+
+    #[test]
+    fn test_case0_cert_from_cert_chain() {
+        let cert_chain = &mut cert_chain_array();
+        assert!(get_cert_from_cert_chain(cert_chain, -1).is_ok());
+        assert!(get_cert_from_cert_chain(cert_chain, 0).is_ok());
+        assert!(get_cert_from_cert_chain(cert_chain, 1).is_ok());
+        assert!(get_cert_from_cert_chain(cert_chain, 3).is_err());
+    }
+    #[test]
+    fn test_case1_cert_from_cert_chain() {
+        let cert_chain = &mut [0x1u8; 4096];
+        cert_chain[0] = 0x00;
+        cert_chain[1] = 0x00;
+        assert!(get_cert_from_cert_chain(cert_chain, 0).is_err());
+
+        // the length runs past the end of the chain
+        cert_chain[0] = 0x30;
+        cert_chain[1] = 0x82;
+        cert_chain[2] = 0xff;
+        assert!(get_cert_from_cert_chain(cert_chain, 0).is_err());
+
+        let cert_chain = &mut [0x11u8; 3];
+        assert!(get_cert_from_cert_chain(cert_chain, 0).is_err());
+    }
+    #[test]
+    fn test_case2_verify_cert_chain() {
+        for key_dir in ["EcP256", "EcP384", "EcP521", "Rsa3072"].iter() {
+            let cert_chain = read_cert_chain(key_dir);
+            assert!(verify_cert_chain_with_flags(&cert_chain, flags()).is_ok());
+
+            // anchored at the intermediate
+            let (inter_begin, _) = get_cert_from_cert_chain(&cert_chain, 1).unwrap();
+            assert!(verify_cert_chain_with_flags(&cert_chain[inter_begin..], flags()).is_ok());
+        }
+    }
+    #[test]
+    fn test_case3_verify_cert_chain() {
+        let cert_chain = read_cert_chain("EcP384");
+        let (leaf_begin, leaf_end) = get_cert_from_cert_chain(&cert_chain, -1).unwrap();
+
+        // a corrupted leaf signature
+        let mut bad_chain = cert_chain.clone();
+        bad_chain[leaf_end - 8] ^= 0x01;
+        assert!(verify_cert_chain_with_flags(&bad_chain, flags()).is_err());
+
+        // the leaf is not issued by the root
+        let (inter_begin, _) = get_cert_from_cert_chain(&cert_chain, 1).unwrap();
+        let mut bad_chain = cert_chain[..inter_begin].to_vec();
+        bad_chain.extend_from_slice(&cert_chain[leaf_begin..]);
+        assert!(verify_cert_chain_with_flags(&bad_chain, flags()).is_err());
+
+        // the root alone is not a chain
+        assert!(verify_cert_chain_with_flags(&cert_chain[..inter_begin], flags()).is_err());
+    }
+}
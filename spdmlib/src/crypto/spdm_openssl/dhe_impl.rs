@@ -0,0 +1,196 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::crypto::ffdhe::{self, FfdheGroup};
+use crate::crypto::{SpdmDhe, SpdmDheKeyExchange};
+use crate::msgs::{SpdmDheAlgo, SpdmDheExchangeStruct, SpdmDheFinalKeyStruct};
+use bytes::BytesMut;
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::derive::Deriver;
+use openssl::dh::Dh;
+use openssl::ec::{EcGroup, EcKey, EcPoint, PointConversionForm};
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+
+pub static DEFAULT: SpdmDhe = SpdmDhe {
+    generate_key_pair_cb: generate_key_pair,
+    supported_algo_cb: supported_algo,
+};
+
+fn supported_algo() -> SpdmDheAlgo {
+    SpdmDheAlgo::SECP_256_R1
+        | SpdmDheAlgo::SECP_384_R1
+        | SpdmDheAlgo::SECP_521_R1
+        | SpdmDheAlgo::FFDHE_2048
+        | SpdmDheAlgo::FFDHE_3072
+        | SpdmDheAlgo::FFDHE_4096
+}
+
+fn generate_key_pair(
+    dhe_algo: SpdmDheAlgo,
+) -> Option<(SpdmDheExchangeStruct, Box<dyn SpdmDheKeyExchange>)> {
+    match dhe_algo {
+        SpdmDheAlgo::SECP_256_R1 => SpdmDheKeyExchangeEc::generate_key_pair(Nid::X9_62_PRIME256V1),
+        SpdmDheAlgo::SECP_384_R1 => SpdmDheKeyExchangeEc::generate_key_pair(Nid::SECP384R1),
+        SpdmDheAlgo::SECP_521_R1 => SpdmDheKeyExchangeEc::generate_key_pair(Nid::SECP521R1),
+        SpdmDheAlgo::FFDHE_2048 => SpdmDheKeyExchangeFfdhe::generate_key_pair(&ffdhe::FFDHE_2048),
+        SpdmDheAlgo::FFDHE_3072 => SpdmDheKeyExchangeFfdhe::generate_key_pair(&ffdhe::FFDHE_3072),
+        SpdmDheAlgo::FFDHE_4096 => SpdmDheKeyExchangeFfdhe::generate_key_pair(&ffdhe::FFDHE_4096),
+        // SM2_P256 is the GB/T 32918.3 key exchange, which OpenSSL does not offer
+        _ => None,
+    }
+}
+
+struct SpdmDheKeyExchangeEc(EcKey<Private>);
+
+impl SpdmDheKeyExchange for SpdmDheKeyExchangeEc {
+    fn compute_final_key(
+        self: Box<Self>,
+        peer_pub_key: &SpdmDheExchangeStruct,
+    ) -> Option<SpdmDheFinalKeyStruct> {
+        // SPDM carries the uncompressed point without the 0x04 prefix.
+        let mut pubkey = BytesMut::new();
+        pubkey.extend_from_slice(&[0x4u8]);
+        pubkey.extend_from_slice(peer_pub_key.as_ref());
+
+        let mut ctx = BigNumContext::new().ok()?;
+        let peer_point = EcPoint::from_bytes(self.0.group(), &pubkey, &mut ctx).ok()?;
+        let peer_key = EcKey::from_public_key(self.0.group(), &peer_point).ok()?;
+        peer_key.check_key().ok()?;
+
+        let private_key = PKey::from_ec_key(self.0).ok()?;
+        let peer_key = PKey::from_ec_key(peer_key).ok()?;
+        let mut deriver = Deriver::new(&private_key).ok()?;
+        deriver.set_peer(&peer_key).ok()?;
+        let final_key = deriver.derive_to_vec().ok()?;
+        Some(SpdmDheFinalKeyStruct::from(BytesMut::from(&final_key[..])))
+    }
+}
+
+impl SpdmDheKeyExchangeEc {
+    fn generate_key_pair(
+        curve: Nid,
+    ) -> Option<(SpdmDheExchangeStruct, Box<dyn SpdmDheKeyExchange>)> {
+        let group = EcGroup::from_curve_name(curve).ok()?;
+        let private_key = EcKey::generate(&group).ok()?;
+        let mut ctx = BigNumContext::new().ok()?;
+        let public_key = private_key
+            .public_key()
+            .to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut ctx)
+            .ok()?;
+        let public_key = BytesMut::from(&public_key[1..]);
+
+        let res: Box<dyn SpdmDheKeyExchange> = Box::new(Self(private_key));
+
+        Some((SpdmDheExchangeStruct::from(public_key), res))
+    }
+}
+
+struct SpdmDheKeyExchangeFfdhe {
+    size: usize,
+    dh: Dh<Private>,
+}
+
+impl SpdmDheKeyExchange for SpdmDheKeyExchangeFfdhe {
+    fn compute_final_key(
+        self: Box<Self>,
+        peer_pub_key: &SpdmDheExchangeStruct,
+    ) -> Option<SpdmDheFinalKeyStruct> {
+        if peer_pub_key.as_ref().len() != self.size {
+            return None;
+        }
+        let peer_pub_key = BigNum::from_slice(peer_pub_key.as_ref()).ok()?;
+        // OpenSSL checks the peer value against q, and strips leading zeros
+        let final_key = self.dh.compute_key(&peer_pub_key).ok()?;
+        let mut padded = BytesMut::from(&vec![0u8; self.size - final_key.len()][..]);
+        padded.extend_from_slice(&final_key);
+        Some(SpdmDheFinalKeyStruct::from(padded))
+    }
+}
+
+impl SpdmDheKeyExchangeFfdhe {
+    fn generate_key_pair(
+        group: &'static FfdheGroup,
+    ) -> Option<(SpdmDheExchangeStruct, Box<dyn SpdmDheKeyExchange>)> {
+        let p = BigNum::from_slice(group.prime).ok()?;
+        // the RFC 7919 primes are safe primes, q = (p - 1) / 2
+        let mut q = BigNum::new().ok()?;
+        q.rshift1(&p).ok()?;
+        let g = BigNum::from_u32(2).ok()?;
+        let dh = Dh::from_pqg(p, Some(q), g).ok()?.generate_key().ok()?;
+
+        let size = group.size();
+        let public_key = dh.public_key().to_vec_padded(size as i32).ok()?;
+        let res: Box<dyn SpdmDheKeyExchange> = Box::new(Self { size, dh });
+
+        Some((
+            SpdmDheExchangeStruct::from(BytesMut::from(&public_key[..])),
+            res,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case0_dhe() {
+        for dhe_algo in [
+            SpdmDheAlgo::SECP_256_R1,
+            SpdmDheAlgo::SECP_384_R1,
+            SpdmDheAlgo::SECP_521_R1,
+            SpdmDheAlgo::FFDHE_2048,
+            SpdmDheAlgo::FFDHE_3072,
+            SpdmDheAlgo::FFDHE_4096,
+        ]
+        .iter()
+        {
+            let (exchange1, private1) = generate_key_pair(*dhe_algo).unwrap();
+            let (exchange2, private2) = generate_key_pair(*dhe_algo).unwrap();
+
+            let peer1 = private1.compute_final_key(&exchange2).unwrap();
+            let peer2 = private2.compute_final_key(&exchange1).unwrap();
+
+            assert_eq!(exchange1.as_ref().len(), dhe_algo.get_size() as usize);
+            assert_eq!(peer1.as_ref(), peer2.as_ref());
+        }
+    }
+    #[test]
+    fn test_case1_dhe() {
+        assert!(generate_key_pair(SpdmDheAlgo::empty()).is_none());
+
+        // not a point on the curve
+        for dhe_algo in [SpdmDheAlgo::SECP_256_R1, SpdmDheAlgo::SECP_384_R1].iter() {
+            let (_, private) = generate_key_pair(*dhe_algo).unwrap();
+            let peer = vec![0x5au8; dhe_algo.get_size() as usize];
+            let peer = SpdmDheExchangeStruct::from(BytesMut::from(peer.as_slice()));
+            assert!(private.compute_final_key(&peer).is_none());
+        }
+    }
+    #[test]
+    fn test_case2_dhe() {
+        for (dhe_algo, group) in [
+            (SpdmDheAlgo::FFDHE_2048, &ffdhe::FFDHE_2048),
+            (SpdmDheAlgo::FFDHE_3072, &ffdhe::FFDHE_3072),
+            (SpdmDheAlgo::FFDHE_4096, &ffdhe::FFDHE_4096),
+        ]
+        .iter()
+        {
+            let size = group.size();
+            let zero = vec![0u8; size];
+            let mut one = vec![0u8; size];
+            one[size - 1] = 1;
+            let mut p_minus_one = group.prime.to_vec();
+            p_minus_one[size - 1] -= 1;
+            let short = vec![2u8; size - 1];
+
+            for peer in [zero, one, p_minus_one, group.prime.to_vec(), short].iter() {
+                let (_, private) = generate_key_pair(*dhe_algo).unwrap();
+                let peer = SpdmDheExchangeStruct::from(BytesMut::from(peer.as_slice()));
+                assert!(private.compute_final_key(&peer).is_none());
+            }
+        }
+    }
+}
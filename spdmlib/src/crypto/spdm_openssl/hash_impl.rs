@@ -0,0 +1,92 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::crypto::SpdmHash;
+use crate::msgs::{SpdmBaseHashAlgo, SpdmDigestStruct};
+use openssl::hash::{Hasher, MessageDigest};
+
+pub static DEFAULT: SpdmHash = SpdmHash {
+    hash_all_cb: hash_all,
+    supported_algo_cb: supported_algo,
+};
+
+// SM3 is left out of some OpenSSL builds
+pub(crate) fn supported_algo() -> SpdmBaseHashAlgo {
+    let mut supported = SpdmBaseHashAlgo::TPM_ALG_SHA_256
+        | SpdmBaseHashAlgo::TPM_ALG_SHA_384
+        | SpdmBaseHashAlgo::TPM_ALG_SHA_512
+        | SpdmBaseHashAlgo::TPM_ALG_SHA3_256
+        | SpdmBaseHashAlgo::TPM_ALG_SHA3_384
+        | SpdmBaseHashAlgo::TPM_ALG_SHA3_512;
+    if Hasher::new(MessageDigest::sm3()).is_ok() {
+        supported |= SpdmBaseHashAlgo::TPM_ALG_SM3_256;
+    }
+    supported
+}
+
+fn hash_all(base_hash_algo: SpdmBaseHashAlgo, data: &[u8]) -> Option<SpdmDigestStruct> {
+    let digest = openssl::hash::hash(message_digest(base_hash_algo)?, data).ok()?;
+    Some(SpdmDigestStruct::from(&digest[..]))
+}
+
+pub(crate) fn message_digest(base_hash_algo: SpdmBaseHashAlgo) -> Option<MessageDigest> {
+    match base_hash_algo {
+        SpdmBaseHashAlgo::TPM_ALG_SHA_256 => Some(MessageDigest::sha256()),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_384 => Some(MessageDigest::sha384()),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_512 => Some(MessageDigest::sha512()),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => Some(MessageDigest::sha3_256()),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => Some(MessageDigest::sha3_384()),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => Some(MessageDigest::sha3_512()),
+        SpdmBaseHashAlgo::TPM_ALG_SM3_256 => Some(MessageDigest::sm3()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case0_hash_all() {
+        // FIPS 180-2, FIPS 202 and GB/T 32905 "abc"
+        let expected_sha256 = [
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+            0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+            0xf2, 0x00, 0x15, 0xad,
+        ];
+        let expected_sha3_256 = [
+            0x3a, 0x98, 0x5d, 0xa7, 0x4f, 0xe2, 0x25, 0xb2, 0x04, 0x5c, 0x17, 0x2d, 0x6b, 0xd3,
+            0x90, 0xbd, 0x85, 0x5f, 0x08, 0x6e, 0x3e, 0x9d, 0x52, 0x5b, 0x46, 0xbf, 0xe2, 0x45,
+            0x11, 0x43, 0x15, 0x32,
+        ];
+        let expected_sm3_256 = [
+            0x66, 0xc7, 0xf0, 0xf4, 0x62, 0xee, 0xed, 0xd9, 0xd1, 0xf2, 0xd4, 0x6b, 0xdc, 0x10,
+            0xe4, 0xe2, 0x41, 0x67, 0xc4, 0x87, 0x5c, 0xf2, 0xf7, 0xa2, 0x29, 0x7d, 0xa0, 0x2b,
+            0x8f, 0x4b, 0xa8, 0xe0,
+        ];
+        let digest = hash_all(SpdmBaseHashAlgo::TPM_ALG_SHA_256, b"abc").unwrap();
+        assert_eq!(digest.as_ref(), &expected_sha256[..]);
+        let digest = hash_all(SpdmBaseHashAlgo::TPM_ALG_SHA3_256, b"abc").unwrap();
+        assert_eq!(digest.as_ref(), &expected_sha3_256[..]);
+        let digest = hash_all(SpdmBaseHashAlgo::TPM_ALG_SM3_256, b"abc").unwrap();
+        assert_eq!(digest.as_ref(), &expected_sm3_256[..]);
+
+        for base_hash_algo in [
+            SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_512,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+        ]
+        .iter()
+        {
+            let digest = hash_all(*base_hash_algo, &[0u8; 64]).unwrap();
+            assert_eq!(digest.data_size, base_hash_algo.get_size());
+        }
+    }
+    #[test]
+    fn test_case1_hash_all() {
+        let digest = hash_all(SpdmBaseHashAlgo::empty(), &[0u8; 64]);
+        assert!(digest.is_none());
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use super::hash_impl::message_digest;
+use crate::crypto::SpdmHkdf;
+use crate::msgs::{SpdmBaseHashAlgo, SpdmDigestStruct};
+use openssl::md::Md;
+use openssl::pkey::Id;
+use openssl::pkey_ctx::{HkdfMode, PkeyCtx};
+
+pub static DEFAULT: SpdmHkdf = SpdmHkdf {
+    hkdf_expand_cb: hkdf_expand,
+};
+
+fn hkdf_expand(
+    hash_algo: SpdmBaseHashAlgo,
+    pk: &[u8],
+    info: &[u8],
+    out_size: u16,
+) -> Option<SpdmDigestStruct> {
+    let md = Md::from_nid(message_digest(hash_algo)?.type_())?;
+    let mut ret = SpdmDigestStruct {
+        data_size: out_size,
+        ..Default::default()
+    };
+
+    let mut ctx = PkeyCtx::new_id(Id::HKDF).ok()?;
+    ctx.derive_init().ok()?;
+    ctx.set_hkdf_mode(HkdfMode::EXPAND_ONLY).ok()?;
+    ctx.set_hkdf_md(md).ok()?;
+    ctx.set_hkdf_key(pk).ok()?;
+    ctx.add_hkdf_info(info).ok()?;
+    ctx.derive(Some(ret.data.get_mut(..out_size as usize)?))
+        .ok()?;
+    Some(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case0_hkdf_expand() {
+        // RFC 5869 test case 1
+        let prk = [
+            0x07, 0x77, 0x09, 0x36, 0x2c, 0x2e, 0x32, 0xdf, 0x0d, 0xdc, 0x3f, 0x0d, 0xc4, 0x7b,
+            0xba, 0x63, 0x90, 0xb6, 0xc7, 0x3b, 0xb5, 0x0f, 0x9c, 0x31, 0x22, 0xec, 0x84, 0x4a,
+            0xd7, 0xc2, 0xb3, 0xe5,
+        ];
+        let info = [0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9];
+        let expected = [
+            0x3c, 0xb2, 0x5f, 0x25, 0xfa, 0xac, 0xd5, 0x7a, 0x90, 0x43, 0x4f, 0x64, 0xd0, 0x36,
+            0x2f, 0x2a, 0x2d, 0x2d, 0x0a, 0x90, 0xcf, 0x1a, 0x5a, 0x4c, 0x5d, 0xb0, 0x2d, 0x56,
+            0xec, 0xc4, 0xc5, 0xbf, 0x34, 0x00, 0x72, 0x08, 0xd5, 0xb8, 0x87, 0x18, 0x58, 0x65,
+        ];
+        let okm = hkdf_expand(SpdmBaseHashAlgo::TPM_ALG_SHA_256, &prk, &info, 42).unwrap();
+        assert_eq!(okm.as_ref(), &expected[..]);
+    }
+    #[test]
+    fn test_case1_hkdf_expand() {
+        for base_hash_algo in [
+            SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_512,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+            SpdmBaseHashAlgo::TPM_ALG_SM3_256,
+        ]
+        .iter()
+        {
+            let pk = &[100u8; 64][..base_hash_algo.get_size() as usize];
+            let info = &mut [100u8; 64];
+            let out_size = base_hash_algo.get_size();
+            let okm = hkdf_expand(*base_hash_algo, pk, info, out_size).unwrap();
+            assert_eq!(okm.data_size, out_size);
+
+            // like the other backends, a PRK shorter than the hash is fine
+            assert!(hkdf_expand(*base_hash_algo, &pk[1..], info, out_size).is_some());
+        }
+        assert!(hkdf_expand(SpdmBaseHashAlgo::empty(), &[100u8; 64], &[], 32).is_none());
+    }
+}
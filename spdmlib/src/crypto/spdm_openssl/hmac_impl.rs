@@ -0,0 +1,100 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use super::hash_impl::message_digest;
+use crate::crypto::SpdmHmac;
+use crate::error::SpdmResult;
+use crate::msgs::{SpdmBaseHashAlgo, SpdmDigestStruct};
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+
+pub static DEFAULT: SpdmHmac = SpdmHmac {
+    hmac_cb: hmac,
+    hmac_verify_cb: hmac_verify,
+};
+
+fn hmac(base_hash_algo: SpdmBaseHashAlgo, key: &[u8], data: &[u8]) -> Option<SpdmDigestStruct> {
+    // HMAC zero pads the key to the block size, so an empty key is the same
+    // as a single zero byte, which unlike the empty key OpenSSL accepts
+    let key = PKey::hmac(if key.is_empty() { &[0u8] } else { key }).ok()?;
+    let mut signer = Signer::new(message_digest(base_hash_algo)?, &key).ok()?;
+    signer.update(data).ok()?;
+    let tag = signer.sign_to_vec().ok()?;
+    Some(SpdmDigestStruct::from(&tag[..]))
+}
+
+fn hmac_verify(
+    base_hash_algo: SpdmBaseHashAlgo,
+    key: &[u8],
+    data: &[u8],
+    hmac: &SpdmDigestStruct,
+) -> SpdmResult {
+    if message_digest(base_hash_algo).is_none() {
+        return spdm_result_err!(EINVAL);
+    }
+    let expected = self::hmac(base_hash_algo, key, data).ok_or(spdm_err!(EFAULT))?;
+    if expected.data_size != hmac.data_size
+        || !openssl::memcmp::eq(expected.as_ref(), hmac.as_ref())
+    {
+        return spdm_result_err!(EFAULT);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case0_hmac_verify() {
+        // RFC 4231 test case 2
+        let expected = [
+            0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+            0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+            0x64, 0xec, 0x38, 0x43,
+        ];
+        let spdm_digest = hmac(
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            b"Jefe",
+            b"what do ya want for nothing?",
+        )
+        .unwrap();
+        assert_eq!(spdm_digest.as_ref(), &expected[..]);
+
+        let key = &mut [10u8; 128];
+        let data = &mut [10u8; 128];
+        for base_hash_algo in [
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_512,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+        ]
+        .iter()
+        {
+            let spdm_digest = hmac(*base_hash_algo, key, data).unwrap();
+            assert_eq!(spdm_digest.data_size, base_hash_algo.get_size());
+            assert!(hmac_verify(*base_hash_algo, key, data, &spdm_digest).is_ok());
+            assert!(hmac_verify(*base_hash_algo, key, &data[1..], &spdm_digest).is_err());
+        }
+    }
+    #[test]
+    fn test_case1_hmac_verify() {
+        let base_hash_algo = SpdmBaseHashAlgo::empty();
+        let key = &mut [10u8; 128];
+        let data = &mut [10u8; 128];
+        assert!(hmac(base_hash_algo, key, data).is_none());
+        assert!(hmac_verify(base_hash_algo, key, data, &SpdmDigestStruct::default()).is_err());
+        // the key schedule may start from an empty secret
+        assert_eq!(
+            hmac(SpdmBaseHashAlgo::TPM_ALG_SHA_256, &[], data)
+                .unwrap()
+                .as_ref(),
+            hmac(SpdmBaseHashAlgo::TPM_ALG_SHA_256, &[0u8; 64], data)
+                .unwrap()
+                .as_ref()
+        );
+    }
+}
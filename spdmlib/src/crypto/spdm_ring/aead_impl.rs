@@ -11,8 +11,13 @@ use crate::msgs::SpdmAeadAlgo;
 pub static DEFAULT: SpdmAead = SpdmAead {
     encrypt_cb: encrypt,
     decrypt_cb: decrypt,
+    supported_algo_cb: supported_algo,
 };
 
+fn supported_algo() -> SpdmAeadAlgo {
+    SpdmAeadAlgo::AES_128_GCM | SpdmAeadAlgo::AES_256_GCM | SpdmAeadAlgo::CHACHA20_POLY1305
+}
+
 fn encrypt(
     aead_algo: SpdmAeadAlgo,
     key: &[u8],
@@ -5,24 +5,37 @@
 extern crate alloc;
 use alloc::boxed::Box;
 
+use crate::crypto::ffdhe::{self, FfdheExponent, FfdheGroup};
 use crate::crypto::{SpdmDhe, SpdmDheKeyExchange};
 use crate::msgs::{SpdmDheAlgo, SpdmDheExchangeStruct, SpdmDheFinalKeyStruct};
 use bytes::{BufMut, BytesMut};
+use p521::elliptic_curve::sec1::ToEncodedPoint;
+use ring::rand::SecureRandom;
 
 pub static DEFAULT: SpdmDhe = SpdmDhe {
     generate_key_pair_cb: generate_key_pair,
+    supported_algo_cb: supported_algo,
 };
 
+fn supported_algo() -> SpdmDheAlgo {
+    SpdmDheAlgo::SECP_256_R1
+        | SpdmDheAlgo::SECP_384_R1
+        | SpdmDheAlgo::SECP_521_R1
+        | SpdmDheAlgo::FFDHE_2048
+        | SpdmDheAlgo::FFDHE_3072
+        | SpdmDheAlgo::FFDHE_4096
+}
+
 fn generate_key_pair(
     dhe_algo: SpdmDheAlgo,
 ) -> Option<(SpdmDheExchangeStruct, Box<dyn SpdmDheKeyExchange>)> {
     match dhe_algo {
         SpdmDheAlgo::SECP_256_R1 => SpdmDheKeyExchangeP256::generate_key_pair(),
         SpdmDheAlgo::SECP_384_R1 => SpdmDheKeyExchangeP384::generate_key_pair(),
-        SpdmDheAlgo::SECP_521_R1 => None,
-        SpdmDheAlgo::FFDHE_2048 => None,
-        SpdmDheAlgo::FFDHE_3072 => None,
-        SpdmDheAlgo::FFDHE_4096 => None,
+        SpdmDheAlgo::SECP_521_R1 => SpdmDheKeyExchangeP521::generate_key_pair(),
+        SpdmDheAlgo::FFDHE_2048 => SpdmDheKeyExchangeFfdhe::generate_key_pair(&ffdhe::FFDHE_2048),
+        SpdmDheAlgo::FFDHE_3072 => SpdmDheKeyExchangeFfdhe::generate_key_pair(&ffdhe::FFDHE_3072),
+        SpdmDheAlgo::FFDHE_4096 => SpdmDheKeyExchangeFfdhe::generate_key_pair(&ffdhe::FFDHE_4096),
         _ => None,
     }
 }
@@ -115,34 +128,137 @@ impl SpdmDheKeyExchangeP384 {
     }
 }
 
+// ring has no P-521, so it goes through the RustCrypto p521 crate.
+struct SpdmDheKeyExchangeP521(p521::SecretKey);
+
+impl SpdmDheKeyExchange for SpdmDheKeyExchangeP521 {
+    fn compute_final_key(
+        self: Box<Self>,
+        peer_pub_key: &SpdmDheExchangeStruct,
+    ) -> Option<SpdmDheFinalKeyStruct> {
+        let mut pubkey = BytesMut::new();
+        pubkey.put_u8(0x4u8);
+        pubkey.extend_from_slice(peer_pub_key.as_ref());
+
+        let peer_public_key = p521::PublicKey::from_sec1_bytes(pubkey.as_ref()).ok()?;
+        let shared_secret =
+            p521::ecdh::diffie_hellman(self.0.to_nonzero_scalar(), peer_public_key.as_affine());
+        Some(SpdmDheFinalKeyStruct::from(BytesMut::from(
+            &shared_secret.raw_secret_bytes()[..],
+        )))
+    }
+}
+
+impl SpdmDheKeyExchangeP521 {
+    fn generate_key_pair() -> Option<(SpdmDheExchangeStruct, Box<dyn SpdmDheKeyExchange>)> {
+        let rng = ring::rand::SystemRandom::new();
+        let mut random = [0u8; 66];
+        // rejection sampling: a 521 bit candidate must be below the group order
+        let private_key = loop {
+            rng.fill(&mut random).ok()?;
+            random[0] &= 0x01;
+            if let Ok(private_key) = p521::SecretKey::from_slice(&random) {
+                break private_key;
+            }
+        };
+        let public_key_old = private_key.public_key().to_encoded_point(false);
+        let public_key = BytesMut::from(&public_key_old.as_bytes()[1..]);
+
+        let res: Box<dyn SpdmDheKeyExchange> = Box::new(Self(private_key));
+
+        Some((SpdmDheExchangeStruct::from(public_key), res))
+    }
+}
+
+struct SpdmDheKeyExchangeFfdhe {
+    group: &'static FfdheGroup,
+    exponent: FfdheExponent,
+}
+
+impl SpdmDheKeyExchange for SpdmDheKeyExchangeFfdhe {
+    fn compute_final_key(
+        self: Box<Self>,
+        peer_pub_key: &SpdmDheExchangeStruct,
+    ) -> Option<SpdmDheFinalKeyStruct> {
+        let final_key = self
+            .group
+            .compute_shared_secret(&self.exponent, peer_pub_key.as_ref())?;
+        Some(SpdmDheFinalKeyStruct::from(BytesMut::from(
+            final_key.as_slice(),
+        )))
+    }
+}
+
+impl SpdmDheKeyExchangeFfdhe {
+    fn generate_key_pair(
+        group: &'static FfdheGroup,
+    ) -> Option<(SpdmDheExchangeStruct, Box<dyn SpdmDheKeyExchange>)> {
+        let rng = ring::rand::SystemRandom::new();
+        let mut random = [0u8; 64];
+        // retry on the (negligible) chance of an all zero exponent
+        loop {
+            rng.fill(&mut random).ok()?;
+            if let Some((exponent, public_key)) = group.generate_key_pair(&random) {
+                let res: Box<dyn SpdmDheKeyExchange> = Box::new(Self { group, exponent });
+                return Some((
+                    SpdmDheExchangeStruct::from(BytesMut::from(public_key.as_slice())),
+                    res,
+                ));
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[test]
     fn test_case0_dhe() {
-        for dhe_algo in [SpdmDheAlgo::SECP_256_R1, SpdmDheAlgo::SECP_384_R1].iter() {
+        for dhe_algo in [
+            SpdmDheAlgo::SECP_256_R1,
+            SpdmDheAlgo::SECP_384_R1,
+            SpdmDheAlgo::SECP_521_R1,
+            SpdmDheAlgo::FFDHE_2048,
+            SpdmDheAlgo::FFDHE_3072,
+            SpdmDheAlgo::FFDHE_4096,
+        ]
+        .iter()
+        {
             let (exchange1, private1) = generate_key_pair(*dhe_algo).unwrap();
             let (exchange2, private2) = generate_key_pair(*dhe_algo).unwrap();
 
             let peer1 = private1.compute_final_key(&exchange2).unwrap();
             let peer2 = private2.compute_final_key(&exchange1).unwrap();
 
+            assert_eq!(exchange1.as_ref().len(), dhe_algo.get_size() as usize);
             assert_eq!(peer1.as_ref(), peer2.as_ref());
         }
     }
     #[test]
     fn test_case1_dhe() {
-        for dhe_algo in [
-            SpdmDheAlgo::SECP_521_R1,
-            SpdmDheAlgo::FFDHE_2048,
-            SpdmDheAlgo::FFDHE_3072,
-            SpdmDheAlgo::FFDHE_4096,
-            SpdmDheAlgo::empty(),
-        ]
-        .iter()
-        {
-            assert_eq!(generate_key_pair(*dhe_algo).is_none(), true);
+        assert_eq!(generate_key_pair(SpdmDheAlgo::empty()).is_none(), true);
+    }
+    #[test]
+    fn test_case2_dhe() {
+        for group in [&ffdhe::FFDHE_2048, &ffdhe::FFDHE_3072, &ffdhe::FFDHE_4096].iter() {
+            let size = group.size();
+            let zero = vec![0u8; size];
+            let mut one = vec![0u8; size];
+            one[size - 1] = 1;
+            let mut p_minus_one = group.prime.to_vec();
+            p_minus_one[size - 1] -= 1;
+            let short = vec![2u8; size - 1];
+
+            for peer in [zero, one, p_minus_one, group.prime.to_vec(), short].iter() {
+                let private: Box<dyn SpdmDheKeyExchange> = Box::new(SpdmDheKeyExchangeFfdhe {
+                    group,
+                    exponent: FfdheExponent::ONE,
+                });
+                let peer = SpdmDheExchangeStruct::from(BytesMut::from(peer.as_slice()));
+                assert!(private.compute_final_key(&peer).is_none());
+            }
         }
     }
 }
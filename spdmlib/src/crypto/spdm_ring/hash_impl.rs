@@ -4,16 +4,37 @@
 
 use crate::crypto::SpdmHash;
 use crate::msgs::{SpdmBaseHashAlgo, SpdmDigestStruct};
+use sha3::Digest;
 
 pub static DEFAULT: SpdmHash = SpdmHash {
     hash_all_cb: hash_all,
+    supported_algo_cb: supported_algo,
 };
 
-fn hash_all(base_hash_algo: SpdmBaseHashAlgo, data: &[u8]) -> Option<SpdmDigestStruct> {
+fn supported_algo() -> SpdmBaseHashAlgo {
+    SpdmBaseHashAlgo::TPM_ALG_SHA_256
+        | SpdmBaseHashAlgo::TPM_ALG_SHA_384
+        | SpdmBaseHashAlgo::TPM_ALG_SHA_512
+        | SpdmBaseHashAlgo::TPM_ALG_SHA3_256
+        | SpdmBaseHashAlgo::TPM_ALG_SHA3_384
+        | SpdmBaseHashAlgo::TPM_ALG_SHA3_512
+}
+
+pub(super) fn hash_all(base_hash_algo: SpdmBaseHashAlgo, data: &[u8]) -> Option<SpdmDigestStruct> {
     let algorithm = match base_hash_algo {
         SpdmBaseHashAlgo::TPM_ALG_SHA_256 => &ring::digest::SHA256,
         SpdmBaseHashAlgo::TPM_ALG_SHA_384 => &ring::digest::SHA384,
         SpdmBaseHashAlgo::TPM_ALG_SHA_512 => &ring::digest::SHA512,
+        // ring has no SHA3
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => {
+            return Some(SpdmDigestStruct::from(&sha3::Sha3_256::digest(data)[..]))
+        }
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => {
+            return Some(SpdmDigestStruct::from(&sha3::Sha3_384::digest(data)[..]))
+        }
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => {
+            return Some(SpdmDigestStruct::from(&sha3::Sha3_512::digest(data)[..]))
+        }
         _ => return None,
     };
     let digest_value = ring::digest::digest(algorithm, data);
@@ -48,4 +69,25 @@ mod tests {
         let hash_all = hash_all(base_hash_algo, data);
         assert_eq!(hash_all.is_none(), true);
     }
+    #[test]
+    fn test_case3_hash_all() {
+        // FIPS 202 SHA3-256("abc")
+        let expected = [
+            0x3a, 0x98, 0x5d, 0xa7, 0x4f, 0xe2, 0x25, 0xb2, 0x04, 0x5c, 0x17, 0x2d, 0x6b, 0xd3,
+            0x90, 0xbd, 0x85, 0x5f, 0x08, 0x6e, 0x3e, 0x9d, 0x52, 0x5b, 0x46, 0xbf, 0xe2, 0x45,
+            0x11, 0x43, 0x15, 0x32,
+        ];
+        let digest = hash_all(SpdmBaseHashAlgo::TPM_ALG_SHA3_256, b"abc").unwrap();
+        assert_eq!(digest.as_ref(), &expected[..]);
+
+        for (base_hash_algo, size) in [
+            (SpdmBaseHashAlgo::TPM_ALG_SHA3_384, 48),
+            (SpdmBaseHashAlgo::TPM_ALG_SHA3_512, 64),
+        ]
+        .iter()
+        {
+            let digest = hash_all(*base_hash_algo, b"abc").unwrap();
+            assert_eq!(digest.data_size, *size);
+        }
+    }
 }
@@ -4,6 +4,9 @@
 
 use crate::crypto::SpdmHkdf;
 use crate::msgs::{SpdmBaseHashAlgo, SpdmDigestStruct};
+use hmac::digest::core_api::BlockSizeUser;
+use hmac::digest::Digest;
+use hmac::{Mac, SimpleHmac};
 
 pub static DEFAULT: SpdmHkdf = SpdmHkdf {
     hkdf_expand_cb: hkdf_expand,
@@ -16,11 +19,21 @@ fn hkdf_expand(
     out_size: u16,
 ) -> Option<SpdmDigestStruct> {
     let algo = match hash_algo {
-        SpdmBaseHashAlgo::TPM_ALG_SHA_256 => Some(ring::hkdf::HKDF_SHA256),
-        SpdmBaseHashAlgo::TPM_ALG_SHA_384 => Some(ring::hkdf::HKDF_SHA384),
-        SpdmBaseHashAlgo::TPM_ALG_SHA_512 => Some(ring::hkdf::HKDF_SHA512),
-        _ => None,
-    }?;
+        SpdmBaseHashAlgo::TPM_ALG_SHA_256 => ring::hkdf::HKDF_SHA256,
+        SpdmBaseHashAlgo::TPM_ALG_SHA_384 => ring::hkdf::HKDF_SHA384,
+        SpdmBaseHashAlgo::TPM_ALG_SHA_512 => ring::hkdf::HKDF_SHA512,
+        // ring has no SHA3
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => {
+            return sha3_hkdf_expand::<sha3::Sha3_256>(pk, info, out_size)
+        }
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => {
+            return sha3_hkdf_expand::<sha3::Sha3_384>(pk, info, out_size)
+        }
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => {
+            return sha3_hkdf_expand::<sha3::Sha3_512>(pk, info, out_size)
+        }
+        _ => return None,
+    };
     let pkr = ring::hkdf::Prk::new_less_safe(algo, pk);
 
     let mut ret = SpdmDigestStruct::default();
@@ -37,6 +50,42 @@ fn hkdf_expand(
     }
 }
 
+// RFC 5869 expand with the RustCrypto HMAC. Written out rather than using
+// hkdf::SimpleHkdf::from_prk, which refuses a PRK shorter than the hash.
+fn sha3_hkdf_expand<D: Digest + BlockSizeUser>(
+    pk: &[u8],
+    info: &[u8],
+    out_size: u16,
+) -> Option<SpdmDigestStruct> {
+    let hash_size = <D as Digest>::output_size();
+    let out_size = out_size as usize;
+    if out_size > 255 * hash_size {
+        return None;
+    }
+    let mut ret = SpdmDigestStruct {
+        data_size: out_size as u16,
+        ..Default::default()
+    };
+    let okm = ret.data.get_mut(..out_size)?;
+
+    let mut offset = 0;
+    let mut counter = 1u8;
+    while offset < out_size {
+        let mut mac = <SimpleHmac<D> as Mac>::new_from_slice(pk).ok()?;
+        if offset > 0 {
+            mac.update(&okm[offset - hash_size..offset]);
+        }
+        mac.update(info);
+        mac.update(&[counter]);
+        let block = mac.finalize().into_bytes();
+        let len = core::cmp::min(hash_size, out_size - offset);
+        okm[offset..offset + len].copy_from_slice(&block[..len]);
+        offset += len;
+        counter = counter.wrapping_add(1);
+    }
+    Some(ret)
+}
+
 struct SpdmCryptoHkdfKeyLen {
     out_size: usize,
 }
@@ -93,4 +142,23 @@ mod tests {
             }
         }
     }
+    #[test]
+    fn test_case2_hkdf_expand() {
+        for base_hash_algo in [
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+        ]
+        .iter()
+        {
+            let pk = &[100u8; 64][..base_hash_algo.get_size() as usize];
+            let info = &mut [100u8; 64];
+            let out_size = base_hash_algo.get_size();
+            let okm = hkdf_expand(*base_hash_algo, pk, info, out_size).unwrap();
+            assert_eq!(okm.data_size, out_size);
+
+            // as with SHA-2, a PRK shorter than the hash is fine
+            assert!(hkdf_expand(*base_hash_algo, &pk[1..], info, out_size).is_some());
+        }
+    }
 }
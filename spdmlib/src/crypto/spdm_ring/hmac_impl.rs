@@ -5,6 +5,9 @@
 use crate::crypto::SpdmHmac;
 use crate::error::SpdmResult;
 use crate::msgs::{SpdmBaseHashAlgo, SpdmDigestStruct};
+use hmac::digest::core_api::BlockSizeUser;
+use hmac::digest::Digest;
+use hmac::{Mac, SimpleHmac};
 
 pub static DEFAULT: SpdmHmac = SpdmHmac {
     hmac_cb: hmac,
@@ -16,6 +19,9 @@ fn hmac(base_hash_algo: SpdmBaseHashAlgo, key: &[u8], data: &[u8]) -> Option<Spd
         SpdmBaseHashAlgo::TPM_ALG_SHA_256 => ring::hmac::HMAC_SHA256,
         SpdmBaseHashAlgo::TPM_ALG_SHA_384 => ring::hmac::HMAC_SHA384,
         SpdmBaseHashAlgo::TPM_ALG_SHA_512 => ring::hmac::HMAC_SHA512,
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => return sha3_hmac::<sha3::Sha3_256>(key, data),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => return sha3_hmac::<sha3::Sha3_384>(key, data),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => return sha3_hmac::<sha3::Sha3_512>(key, data),
         _ => {
             panic!();
         }
@@ -37,6 +43,15 @@ fn hmac_verify(
         SpdmBaseHashAlgo::TPM_ALG_SHA_256 => ring::hmac::HMAC_SHA256,
         SpdmBaseHashAlgo::TPM_ALG_SHA_384 => ring::hmac::HMAC_SHA384,
         SpdmBaseHashAlgo::TPM_ALG_SHA_512 => ring::hmac::HMAC_SHA512,
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => {
+            return sha3_hmac_verify::<sha3::Sha3_256>(key, data, hmac)
+        }
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => {
+            return sha3_hmac_verify::<sha3::Sha3_384>(key, data, hmac)
+        }
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => {
+            return sha3_hmac_verify::<sha3::Sha3_512>(key, data, hmac)
+        }
         _ => {
             panic!();
         }
@@ -49,6 +64,24 @@ fn hmac_verify(
     }
 }
 
+// ring has no SHA3, so those go through the RustCrypto HMAC.
+fn sha3_hmac<D: Digest + BlockSizeUser>(key: &[u8], data: &[u8]) -> Option<SpdmDigestStruct> {
+    let mut mac = <SimpleHmac<D> as Mac>::new_from_slice(key).ok()?;
+    mac.update(data);
+    Some(SpdmDigestStruct::from(&mac.finalize().into_bytes()[..]))
+}
+
+fn sha3_hmac_verify<D: Digest + BlockSizeUser>(
+    key: &[u8],
+    data: &[u8],
+    hmac: &SpdmDigestStruct,
+) -> SpdmResult {
+    let mut mac = <SimpleHmac<D> as Mac>::new_from_slice(key).map_err(|_| spdm_err!(EFAULT))?;
+    mac.update(data);
+    mac.verify_slice(&hmac.data[..(hmac.data_size as usize)])
+        .map_err(|_| spdm_err!(EFAULT))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,4 +139,36 @@ mod tests {
             }
         }
     }
+    #[test]
+    fn test_case3_hmac_verify() {
+        // NIST HMAC-SHA3-256 example, key 00..1f
+        let key: [u8; 32] = {
+            let mut key = [0u8; 32];
+            for (i, k) in key.iter_mut().enumerate() {
+                *k = i as u8;
+            }
+            key
+        };
+        let expected = [
+            0x4f, 0xe8, 0xe2, 0x02, 0xc4, 0xf0, 0x58, 0xe8, 0xdd, 0xdc, 0x23, 0xd8, 0xc3, 0x4e,
+            0x46, 0x73, 0x43, 0xe2, 0x35, 0x55, 0xe2, 0x4f, 0xc2, 0xf0, 0x25, 0xd5, 0x98, 0xf5,
+            0x58, 0xf6, 0x72, 0x05,
+        ];
+        let data = b"Sample message for keylen<blocklen";
+        let spdm_digest = hmac(SpdmBaseHashAlgo::TPM_ALG_SHA3_256, &key, data).unwrap();
+        assert_eq!(spdm_digest.as_ref(), &expected[..]);
+
+        for base_hash_algo in [
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+        ]
+        .iter()
+        {
+            let spdm_digest = hmac(*base_hash_algo, &key, data).unwrap();
+            assert_eq!(spdm_digest.data_size, base_hash_algo.get_size());
+            assert!(hmac_verify(*base_hash_algo, &key, data, &spdm_digest).is_ok());
+            assert!(hmac_verify(*base_hash_algo, &key, b"other data", &spdm_digest).is_err());
+        }
+    }
 }
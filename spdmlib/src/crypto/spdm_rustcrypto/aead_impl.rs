@@ -0,0 +1,233 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::crypto::SpdmAead;
+use crate::error::SpdmResult;
+use aes_gcm::aead::{AeadInPlace, KeyInit, Nonce, Tag};
+use aes_gcm::{Aes128Gcm, Aes256Gcm};
+use chacha20poly1305::ChaCha20Poly1305;
+
+use crate::msgs::SpdmAeadAlgo;
+
+pub static DEFAULT: SpdmAead = SpdmAead {
+    encrypt_cb: encrypt,
+    decrypt_cb: decrypt,
+    supported_algo_cb: supported_algo,
+};
+
+fn supported_algo() -> SpdmAeadAlgo {
+    SpdmAeadAlgo::AES_128_GCM | SpdmAeadAlgo::AES_256_GCM | SpdmAeadAlgo::CHACHA20_POLY1305
+}
+
+fn encrypt(
+    aead_algo: SpdmAeadAlgo,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    plain_text: &[u8],
+    tag: &mut [u8],
+    cipher_text: &mut [u8],
+) -> SpdmResult<(usize, usize)> {
+    check_sizes(
+        aead_algo,
+        key,
+        iv,
+        tag.len(),
+        plain_text.len(),
+        cipher_text.len(),
+    );
+
+    cipher_text.copy_from_slice(plain_text);
+    match aead_algo {
+        SpdmAeadAlgo::AES_128_GCM => encrypt_with::<Aes128Gcm>(key, iv, aad, tag, cipher_text),
+        SpdmAeadAlgo::AES_256_GCM => encrypt_with::<Aes256Gcm>(key, iv, aad, tag, cipher_text),
+        SpdmAeadAlgo::CHACHA20_POLY1305 => {
+            encrypt_with::<ChaCha20Poly1305>(key, iv, aad, tag, cipher_text)
+        }
+        _ => unreachable!(),
+    }?;
+    Ok((cipher_text.len(), tag.len()))
+}
+
+fn decrypt(
+    aead_algo: SpdmAeadAlgo,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    cipher_text: &[u8],
+    tag: &[u8],
+    plain_text: &mut [u8],
+) -> SpdmResult<usize> {
+    check_sizes(
+        aead_algo,
+        key,
+        iv,
+        tag.len(),
+        plain_text.len(),
+        cipher_text.len(),
+    );
+
+    plain_text.copy_from_slice(cipher_text);
+    let res = match aead_algo {
+        SpdmAeadAlgo::AES_128_GCM => decrypt_with::<Aes128Gcm>(key, iv, aad, tag, plain_text),
+        SpdmAeadAlgo::AES_256_GCM => decrypt_with::<Aes256Gcm>(key, iv, aad, tag, plain_text),
+        SpdmAeadAlgo::CHACHA20_POLY1305 => {
+            decrypt_with::<ChaCha20Poly1305>(key, iv, aad, tag, plain_text)
+        }
+        _ => unreachable!(),
+    };
+    if let Err(e) = res {
+        // do not hand back the unauthenticated text
+        plain_text.iter_mut().for_each(|byte| *byte = 0);
+        return Err(e);
+    }
+    Ok(plain_text.len())
+}
+
+// The same size checks as the ring backend, caller errors rather than data errors.
+fn check_sizes(
+    aead_algo: SpdmAeadAlgo,
+    key: &[u8],
+    iv: &[u8],
+    tag_size: usize,
+    plain_text_size: usize,
+    cipher_text_size: usize,
+) {
+    match aead_algo {
+        SpdmAeadAlgo::AES_128_GCM | SpdmAeadAlgo::AES_256_GCM | SpdmAeadAlgo::CHACHA20_POLY1305 => {
+        }
+        _ => {
+            panic!();
+        }
+    }
+    if key.len() != aead_algo.get_key_size() as usize {
+        panic!("key len invalid");
+    }
+    if iv.len() != aead_algo.get_iv_size() as usize {
+        panic!("iv len invalid");
+    }
+    if tag_size != aead_algo.get_tag_size() as usize {
+        panic!("tag len invalid");
+    }
+    if cipher_text_size != plain_text_size {
+        panic!("cipher_text len invalid");
+    }
+}
+
+fn encrypt_with<A: AeadInPlace + KeyInit>(
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    tag: &mut [u8],
+    in_out: &mut [u8],
+) -> SpdmResult {
+    let cipher = A::new_from_slice(key).map_err(|_| spdm_err!(EFAULT))?;
+    let res = cipher
+        .encrypt_in_place_detached(Nonce::<A>::from_slice(iv), aad, in_out)
+        .map_err(|_| spdm_err!(EFAULT))?;
+    tag.copy_from_slice(&res);
+    Ok(())
+}
+
+fn decrypt_with<A: AeadInPlace + KeyInit>(
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    tag: &[u8],
+    in_out: &mut [u8],
+) -> SpdmResult {
+    let cipher = A::new_from_slice(key).map_err(|_| spdm_err!(EFAULT))?;
+    cipher
+        .decrypt_in_place_detached(
+            Nonce::<A>::from_slice(iv),
+            aad,
+            in_out,
+            Tag::<A>::from_slice(tag),
+        )
+        .map_err(|_| spdm_err!(EFAULT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case0_encrypt() {
+        // McGrew and Viega GCM test case 2, AES-128 with a zero key and block
+        let expected_cipher_text = [
+            0x03, 0x88, 0xda, 0xce, 0x60, 0xb6, 0xa3, 0x92, 0xf3, 0x28, 0xc2, 0xb9, 0x71, 0xb2,
+            0xfe, 0x78,
+        ];
+        let expected_tag = [
+            0xab, 0x6e, 0x47, 0xd4, 0x2c, 0xec, 0x13, 0xbd, 0xf5, 0x3a, 0x67, 0xb2, 0x12, 0x57,
+            0xbd, 0xdf,
+        ];
+        let tag = &mut [0u8; 16];
+        let cipher_text = &mut [0u8; 16];
+        let status = encrypt(
+            SpdmAeadAlgo::AES_128_GCM,
+            &[0u8; 16],
+            &[0u8; 12],
+            &[],
+            &[0u8; 16],
+            tag,
+            cipher_text,
+        );
+        assert_eq!(status.unwrap(), (16, 16));
+        assert_eq!(cipher_text, &expected_cipher_text);
+        assert_eq!(tag, &expected_tag);
+    }
+    #[test]
+    fn test_case1_encrypt() {
+        for aead_algo in [
+            SpdmAeadAlgo::AES_128_GCM,
+            SpdmAeadAlgo::AES_256_GCM,
+            SpdmAeadAlgo::CHACHA20_POLY1305,
+        ]
+        .iter()
+        {
+            let key = &[100u8; 32][..aead_algo.get_key_size() as usize];
+            let iv = &[100u8; 12];
+            let aad = &[100u8; 16];
+            let plain_text = &[100u8; 64];
+            let tag = &mut [0u8; 16];
+            let cipher_text = &mut [0u8; 64];
+            encrypt(*aead_algo, key, iv, aad, plain_text, tag, cipher_text).unwrap();
+
+            let decrypted = &mut [0u8; 64];
+            let status = decrypt(*aead_algo, key, iv, aad, cipher_text, tag, decrypted);
+            assert_eq!(status.unwrap(), 64);
+            assert_eq!(&decrypted[..], &plain_text[..]);
+
+            tag[0] ^= 1;
+            let status = decrypt(*aead_algo, key, iv, aad, cipher_text, tag, decrypted);
+            assert!(status.is_err());
+            assert!(decrypted.iter().all(|byte| *byte == 0));
+        }
+    }
+    #[test]
+    #[should_panic]
+    fn test_case2_encrypt() {
+        let aead_algo = SpdmAeadAlgo::empty();
+        let key = &mut [100u8; 1];
+        let iv = &mut [100u8; 12];
+        let plain_text = &mut [100u8; 16];
+        let tag = &mut [100u8; 16];
+        let aad = &mut [100u8; 16];
+        let cipher_text = &mut [100u8; 16];
+        let _ = encrypt(aead_algo, key, iv, aad, plain_text, tag, cipher_text);
+    }
+    #[test]
+    #[should_panic]
+    fn test_case3_encrypt() {
+        let aead_algo = SpdmAeadAlgo::CHACHA20_POLY1305;
+        let key = &mut [100u8; 32];
+        let iv = &mut [100u8; 12];
+        let plain_text = &mut [100u8; 16];
+        let tag = &mut [100u8; 16];
+        let aad = &mut [100u8; 16];
+        let cipher_text = &mut [100u8; 1];
+        let _ = encrypt(aead_algo, key, iv, aad, plain_text, tag, cipher_text);
+    }
+}
@@ -0,0 +1,371 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+extern crate alloc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::hash_impl::hash_all;
+use crate::crypto::{self, SpdmAsymVerify};
+use crate::error::SpdmResult;
+use crate::msgs::{SpdmBaseAsymAlgo, SpdmBaseHashAlgo, SpdmSignatureStruct};
+use core::convert::TryInto;
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::traits::PublicKeyParts;
+use rsa::{Pkcs1v15Sign, Pss};
+use x509_cert::der::Decode;
+
+pub static DEFAULT: SpdmAsymVerify = SpdmAsymVerify {
+    verify_cb: asym_verify,
+    supported_algo_cb: supported_algo,
+};
+
+fn supported_algo() -> SpdmBaseAsymAlgo {
+    SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048
+        | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072
+        | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_2048
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096
+        | SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256
+        | SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384
+        | SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521
+        | SpdmBaseAsymAlgo::EDDSA_ED25519
+}
+
+pub(crate) fn asym_verify(
+    base_hash_algo: SpdmBaseHashAlgo,
+    base_asym_algo: SpdmBaseAsymAlgo,
+    _context: &[u8],
+    public_cert_der: &[u8],
+    data: &[u8],
+    signature: &SpdmSignatureStruct,
+) -> SpdmResult {
+    let (leaf_begin, leaf_end) =
+        crypto::cert_operation::get_cert_from_cert_chain(public_cert_der, -1)?;
+    let leaf_cert = x509_cert::Certificate::from_der(&public_cert_der[leaf_begin..leaf_end])
+        .map_err(|_| spdm_err!(EFAULT))?;
+    let public_key = leaf_cert
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key
+        .as_bytes()
+        .ok_or(spdm_err!(EFAULT))?;
+    // EdDSA signs the message itself, whatever the hash
+    if base_asym_algo == SpdmBaseAsymAlgo::EDDSA_ED25519 {
+        return verify_ed25519(public_key, data, signature.as_ref());
+    }
+    let digest = hash_all(base_hash_algo, data).ok_or(spdm_err!(EFAULT))?;
+
+    verify_digest(
+        base_hash_algo,
+        base_asym_algo,
+        public_key,
+        digest.as_ref(),
+        signature.as_ref(),
+    )
+}
+
+macro_rules! ecdsa_verify_prehash {
+    ($curve:ident, $public_key:expr, $prehash:expr, $signature:expr) => {{
+        let verifying_key = $curve::ecdsa::VerifyingKey::from_sec1_bytes($public_key)
+            .map_err(|_| spdm_err!(EFAULT))?;
+        let signature =
+            $curve::ecdsa::Signature::from_slice($signature).map_err(|_| spdm_err!(EFAULT))?;
+        verifying_key.verify_prehash($prehash, &signature)
+    }};
+}
+
+macro_rules! rsa_signature_scheme {
+    ($scheme:ident, $base_hash_algo:expr) => {
+        match $base_hash_algo {
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256 => $scheme::new::<sha2::Sha256>(),
+            SpdmBaseHashAlgo::TPM_ALG_SHA_384 => $scheme::new::<sha2::Sha384>(),
+            SpdmBaseHashAlgo::TPM_ALG_SHA_512 => $scheme::new::<sha2::Sha512>(),
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => $scheme::new::<sha3::Sha3_256>(),
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => $scheme::new::<sha3::Sha3_384>(),
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => $scheme::new::<sha3::Sha3_512>(),
+            _ => return spdm_result_err!(EFAULT),
+        }
+    };
+}
+
+/// Check a fixed size (r || s for ECDSA) `signature` over the
+/// `base_hash_algo` `digest` against a certificate's subject public key.
+pub(crate) fn verify_digest(
+    base_hash_algo: SpdmBaseHashAlgo,
+    base_asym_algo: SpdmBaseAsymAlgo,
+    public_key: &[u8],
+    digest: &[u8],
+    signature: &[u8],
+) -> SpdmResult {
+    let res = match base_asym_algo {
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256 => {
+            let prehash = ecdsa_prehash(digest, base_asym_algo);
+            ecdsa_verify_prehash!(p256, public_key, &prehash, signature)
+        }
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384 => {
+            let prehash = ecdsa_prehash(digest, base_asym_algo);
+            ecdsa_verify_prehash!(p384, public_key, &prehash, signature)
+        }
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521 => {
+            let prehash = ecdsa_prehash(digest, base_asym_algo);
+            ecdsa_verify_prehash!(p521, public_key, &prehash, signature)
+        }
+        SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048
+        | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072
+        | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096 => {
+            let public_key = rsa_public_key(public_key, base_asym_algo)?;
+            let scheme = rsa_signature_scheme!(Pkcs1v15Sign, base_hash_algo);
+            public_key
+                .verify(scheme, digest, signature)
+                .map_err(|_| p256::ecdsa::Error::new())
+        }
+        SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_2048
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096 => {
+            let public_key = rsa_public_key(public_key, base_asym_algo)?;
+            let scheme = rsa_signature_scheme!(Pss, base_hash_algo);
+            public_key
+                .verify(scheme, digest, signature)
+                .map_err(|_| p256::ecdsa::Error::new())
+        }
+        _ => return spdm_result_err!(EFAULT),
+    };
+
+    match res {
+        Ok(()) => Ok(()),
+        Err(_) => spdm_result_err!(EFAULT),
+    }
+}
+
+pub(crate) fn verify_ed25519(public_key: &[u8], data: &[u8], signature: &[u8]) -> SpdmResult {
+    let public_key = public_key.try_into().map_err(|_| spdm_err!(EFAULT))?;
+    let verifying_key =
+        ed25519_dalek::VerifyingKey::from_bytes(public_key).map_err(|_| spdm_err!(EFAULT))?;
+    let signature =
+        ed25519_dalek::Signature::from_slice(signature).map_err(|_| spdm_err!(EFAULT))?;
+    verifying_key
+        .verify_strict(data, &signature)
+        .map_err(|_| spdm_err!(EFAULT))
+}
+
+// FIPS 186-4 uses a digest shorter than the group order as is. RustCrypto
+// rejects digests under half the field size (SHA-256 with P-521), so left pad.
+fn ecdsa_prehash(digest: &[u8], base_asym_algo: SpdmBaseAsymAlgo) -> Vec<u8> {
+    let field_size = base_asym_algo.get_size() as usize / 2;
+    let mut prehash = vec![0u8; field_size.saturating_sub(digest.len())];
+    prehash.extend_from_slice(digest);
+    prehash
+}
+
+fn rsa_public_key(
+    public_key: &[u8],
+    base_asym_algo: SpdmBaseAsymAlgo,
+) -> SpdmResult<rsa::RsaPublicKey> {
+    let public_key =
+        rsa::RsaPublicKey::from_pkcs1_der(public_key).map_err(|_| spdm_err!(EFAULT))?;
+    if public_key.size() != base_asym_algo.get_size() as usize {
+        return spdm_result_err!(EFAULT);
+    }
+    Ok(public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testlib::*;
+
+    #[test]
+    fn test_case0_asym_verify() {
+        let base_hash_algo = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
+        let base_asym_algo = SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
+        let mut signature = SpdmSignatureStruct {
+            data_size: 96,
+            data: [0x00u8; crate::msgs::SPDM_MAX_ASYM_KEY_SIZE],
+        };
+        signature.data[10] = 0x10;
+        signature.data[60] = 0x10;
+
+        let public_cert_der = &mut cert_chain_array();
+        let data = &mut [0x10u8; 4096];
+
+        for base_asym_algo in [
+            base_asym_algo,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256,
+            SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072,
+            SpdmBaseAsymAlgo::empty(),
+        ]
+        .iter()
+        {
+            let asym_verify = asym_verify(
+                base_hash_algo,
+                *base_asym_algo,
+                b"",
+                public_cert_der,
+                data,
+                &signature,
+            );
+            assert!(asym_verify.is_err());
+        }
+    }
+    #[test]
+    fn test_case1_asym_verify() {
+        use p521::ecdsa::signature::hazmat::PrehashSigner;
+        use p521::pkcs8::DecodePrivateKey;
+
+        let key_dir = get_test_key_directory().join("test_key/EcP521");
+        let mut public_cert_der = Vec::new();
+        for cert in ["ca.cert.der", "inter.cert.der", "end_responder.cert.der"].iter() {
+            public_cert_der.extend(std::fs::read(key_dir.join(cert)).unwrap());
+        }
+        let key_p8 = std::fs::read(key_dir.join("end_responder.key.p8")).unwrap();
+        let secret_key = p521::SecretKey::from_pkcs8_der(&key_p8).unwrap();
+        let signing_key = p521::ecdsa::SigningKey::from_bytes(&secret_key.to_bytes()).unwrap();
+
+        let data = &[0x10u8; 4096];
+        for base_hash_algo in [
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+        ]
+        .iter()
+        {
+            let digest = hash_all(*base_hash_algo, data).unwrap();
+            let signature: p521::ecdsa::Signature = signing_key
+                .sign_prehash(&ecdsa_prehash(
+                    digest.as_ref(),
+                    SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
+                ))
+                .unwrap();
+            let mut signature =
+                SpdmSignatureStruct::from(bytes::BytesMut::from(&signature.to_bytes()[..]));
+
+            assert!(asym_verify(
+                *base_hash_algo,
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
+                b"",
+                &public_cert_der,
+                data,
+                &signature,
+            )
+            .is_ok());
+
+            signature.data[0] ^= 0x01;
+            assert!(asym_verify(
+                *base_hash_algo,
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
+                b"",
+                &public_cert_der,
+                data,
+                &signature,
+            )
+            .is_err());
+        }
+    }
+    #[test]
+    fn test_case2_asym_verify() {
+        use rsa::pkcs1::DecodeRsaPrivateKey;
+
+        let key_dir = get_test_key_directory().join("test_key/Rsa3072");
+        let mut public_cert_der = Vec::new();
+        for cert in ["ca.cert.der", "inter.cert.der", "end_responder.cert.der"].iter() {
+            public_cert_der.extend(std::fs::read(key_dir.join(cert)).unwrap());
+        }
+        let key_der = std::fs::read(key_dir.join("end_responder.key.der")).unwrap();
+        let private_key = rsa::RsaPrivateKey::from_pkcs1_der(&key_der).unwrap();
+
+        let data = &[0x10u8; 4096];
+        let digest = hash_all(SpdmBaseHashAlgo::TPM_ALG_SHA3_384, data).unwrap();
+        let signature = private_key
+            .sign(Pkcs1v15Sign::new::<sha3::Sha3_384>(), digest.as_ref())
+            .unwrap();
+        let signature = SpdmSignatureStruct::from(bytes::BytesMut::from(&signature[..]));
+
+        assert!(asym_verify(
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072,
+            b"",
+            &public_cert_der,
+            data,
+            &signature,
+        )
+        .is_ok());
+        // the key size must match the negotiated algorithm
+        assert!(asym_verify(
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048,
+            b"",
+            &public_cert_der,
+            data,
+            &signature,
+        )
+        .is_err());
+        assert!(asym_verify(
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256,
+            SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072,
+            b"",
+            &public_cert_der,
+            data,
+            &signature,
+        )
+        .is_err());
+    }
+    #[test]
+    fn test_case3_asym_verify() {
+        use ed25519_dalek::Signer;
+
+        let key_dir = get_test_key_directory().join("test_key/Ed25519");
+        let mut public_cert_der = Vec::new();
+        for cert in ["ca.cert.der", "inter.cert.der", "end_responder.cert.der"].iter() {
+            public_cert_der.extend(std::fs::read(key_dir.join(cert)).unwrap());
+        }
+        // the PKCS#8 DER of an Ed25519 key ends with the 32 byte secret
+        let key_der = std::fs::read(key_dir.join("end_responder.key.der")).unwrap();
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(
+            key_der[key_der.len() - 32..].try_into().unwrap(),
+        );
+
+        let data = &[0x10u8; 4096];
+        let signature = signing_key.sign(data);
+        let mut signature =
+            SpdmSignatureStruct::from(bytes::BytesMut::from(&signature.to_bytes()[..]));
+        for base_hash_algo in [
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+        ]
+        .iter()
+        {
+            assert!(asym_verify(
+                *base_hash_algo,
+                SpdmBaseAsymAlgo::EDDSA_ED25519,
+                b"",
+                &public_cert_der,
+                data,
+                &signature,
+            )
+            .is_ok());
+        }
+        assert!(asym_verify(
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            SpdmBaseAsymAlgo::EDDSA_ED25519,
+            b"",
+            &public_cert_der,
+            &data[1..],
+            &signature,
+        )
+        .is_err());
+        signature.data_size -= 1;
+        assert!(asym_verify(
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            SpdmBaseAsymAlgo::EDDSA_ED25519,
+            b"",
+            &public_cert_der,
+            data,
+            &signature,
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,332 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+extern crate alloc;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use super::asym_verify_impl::{verify_digest, verify_ed25519};
+use super::hash_impl::hash_all;
+use crate::crypto::SpdmCertOperation;
+use crate::error::SpdmResult;
+use crate::msgs::{SpdmBaseAsymAlgo, SpdmBaseHashAlgo};
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::traits::PublicKeyParts;
+use x509_cert::der::oid::db::{rfc5912, rfc8410};
+use x509_cert::der::oid::ObjectIdentifier;
+use x509_cert::der::{Decode, Encode};
+use x509_cert::ext::pkix::{BasicConstraints, ExtendedKeyUsage};
+use x509_cert::Certificate;
+
+pub static DEFAULT: SpdmCertOperation = SpdmCertOperation {
+    get_cert_from_cert_chain_cb: get_cert_from_cert_chain,
+    verify_cert_chain_cb: verify_cert_chain,
+};
+
+pub(crate) fn get_cert_from_cert_chain(
+    cert_chain: &[u8],
+    index: isize,
+) -> SpdmResult<(usize, usize)> {
+    let mut offset = 0usize;
+    let mut this_index = 0isize;
+    loop {
+        if offset > cert_chain.len() || cert_chain[offset..].len() < 4 {
+            return spdm_result_err!(EINVAL);
+        }
+        if cert_chain[offset] != 0x30 || cert_chain[offset + 1] != 0x82 {
+            return spdm_result_err!(EINVAL);
+        }
+        let this_cert_len =
+            ((cert_chain[offset + 2] as usize) << 8) + (cert_chain[offset + 3] as usize) + 4;
+        if offset + this_cert_len > cert_chain.len() {
+            return spdm_result_err!(EINVAL);
+        }
+        if this_index == index {
+            // return the this one
+            return Ok((offset, offset + this_cert_len));
+        }
+        this_index += 1;
+        if (offset + this_cert_len == cert_chain.len()) && (index == -1) {
+            // return the last one
+            return Ok((offset, offset + this_cert_len));
+        }
+        offset += this_cert_len;
+    }
+}
+
+//
+// The first certificate is the trust anchor. Every other one must be
+// currently valid, issued and signed by the one before it, and allowed for
+// responder authentication (id-kp-serverAuth) if it limits its key usage.
+//
+fn verify_cert_chain(cert_chain: &[u8]) -> SpdmResult {
+    let mut certs = Vec::new();
+    let mut offset = 0usize;
+    while offset < cert_chain.len() {
+        let (_, cert_len) = get_cert_from_cert_chain(&cert_chain[offset..], 0)?;
+        let cert = Certificate::from_der(&cert_chain[offset..(offset + cert_len)])
+            .map_err(|_| spdm_err!(EINVAL))?;
+        certs.push(cert);
+        offset += cert_len;
+    }
+    if certs.len() < 2 {
+        return spdm_result_err!(EINVAL);
+    }
+
+    let now = current_time()?;
+    let res = certs.windows(2).try_for_each(|pair| {
+        let (issuer, cert) = (&pair[0], &pair[1]);
+        if cert.tbs_certificate.issuer != issuer.tbs_certificate.subject
+            || !is_ca(issuer)?
+            || !allows_responder_auth(cert)?
+        {
+            return spdm_result_err!(EFAULT);
+        }
+        check_validity(cert, now)?;
+        verify_cert_signature(issuer, cert)
+    });
+
+    if res.is_ok() {
+        info!("Cert verification Pass\n");
+        Ok(())
+    } else {
+        error!("Cert verification Fail\n");
+        spdm_result_err!(EFAULT)
+    }
+}
+
+fn current_time() -> SpdmResult<Duration> {
+    #[cfg(any(target_os = "uefi", target_os = "none"))]
+    let now = Duration::from_secs(uefi_time::get_rtc_time() as u64);
+
+    #[cfg(feature = "std")]
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|_| spdm_err!(EFAULT))?;
+
+    Ok(now)
+}
+
+fn check_validity(cert: &Certificate, now: Duration) -> SpdmResult {
+    let validity = &cert.tbs_certificate.validity;
+    if now < validity.not_before.to_unix_duration() || now > validity.not_after.to_unix_duration() {
+        return spdm_result_err!(EFAULT);
+    }
+    Ok(())
+}
+
+fn extension<'a, T: Decode<'a>>(
+    cert: &'a Certificate,
+    extn_id: ObjectIdentifier,
+) -> SpdmResult<Option<T>> {
+    let extensions = match &cert.tbs_certificate.extensions {
+        Some(extensions) => extensions,
+        None => return Ok(None),
+    };
+    match extensions.iter().find(|ext| ext.extn_id == extn_id) {
+        Some(ext) => T::from_der(ext.extn_value.as_bytes())
+            .map(Some)
+            .map_err(|_| spdm_err!(EINVAL)),
+        None => Ok(None),
+    }
+}
+
+fn is_ca(cert: &Certificate) -> SpdmResult<bool> {
+    let basic_constraints: Option<BasicConstraints> =
+        extension(cert, rfc5912::ID_CE_BASIC_CONSTRAINTS)?;
+    Ok(matches!(basic_constraints, Some(basic_constraints) if basic_constraints.ca))
+}
+
+fn allows_responder_auth(cert: &Certificate) -> SpdmResult<bool> {
+    let ext_key_usage: Option<ExtendedKeyUsage> = extension(cert, rfc5912::ID_CE_EXT_KEY_USAGE)?;
+    // no extended key usage means any usage
+    Ok(match ext_key_usage {
+        Some(ext_key_usage) => ext_key_usage.0.contains(&rfc5912::ID_KP_SERVER_AUTH),
+        None => true,
+    })
+}
+
+fn verify_cert_signature(issuer: &Certificate, cert: &Certificate) -> SpdmResult {
+    let (base_hash_algo, key_algorithm) = match cert.signature_algorithm.oid {
+        rfc5912::ECDSA_WITH_SHA_256 => {
+            (SpdmBaseHashAlgo::TPM_ALG_SHA_256, rfc5912::ID_EC_PUBLIC_KEY)
+        }
+        rfc5912::ECDSA_WITH_SHA_384 => {
+            (SpdmBaseHashAlgo::TPM_ALG_SHA_384, rfc5912::ID_EC_PUBLIC_KEY)
+        }
+        rfc5912::ECDSA_WITH_SHA_512 => {
+            (SpdmBaseHashAlgo::TPM_ALG_SHA_512, rfc5912::ID_EC_PUBLIC_KEY)
+        }
+        rfc5912::SHA_256_WITH_RSA_ENCRYPTION => {
+            (SpdmBaseHashAlgo::TPM_ALG_SHA_256, rfc5912::RSA_ENCRYPTION)
+        }
+        rfc5912::SHA_384_WITH_RSA_ENCRYPTION => {
+            (SpdmBaseHashAlgo::TPM_ALG_SHA_384, rfc5912::RSA_ENCRYPTION)
+        }
+        rfc5912::SHA_512_WITH_RSA_ENCRYPTION => {
+            (SpdmBaseHashAlgo::TPM_ALG_SHA_512, rfc5912::RSA_ENCRYPTION)
+        }
+        rfc8410::ID_ED_25519 => (SpdmBaseHashAlgo::empty(), rfc8410::ID_ED_25519),
+        _ => return spdm_result_err!(EFAULT),
+    };
+
+    let spki = &issuer.tbs_certificate.subject_public_key_info;
+    if spki.algorithm.oid != key_algorithm {
+        return spdm_result_err!(EFAULT);
+    }
+    let public_key = spki
+        .subject_public_key
+        .as_bytes()
+        .ok_or(spdm_err!(EFAULT))?;
+    let signature = cert.signature.as_bytes().ok_or(spdm_err!(EFAULT))?;
+    let tbs_certificate = cert
+        .tbs_certificate
+        .to_der()
+        .map_err(|_| spdm_err!(EFAULT))?;
+    if key_algorithm == rfc8410::ID_ED_25519 {
+        // EdDSA signs the TBSCertificate itself
+        return verify_ed25519(public_key, &tbs_certificate, signature);
+    }
+    let digest = hash_all(base_hash_algo, &tbs_certificate).ok_or(spdm_err!(EFAULT))?;
+
+    // certificates carry DER encoded ECDSA signatures, SPDM the fixed size form
+    let (base_asym_algo, signature) = if key_algorithm == rfc5912::ID_EC_PUBLIC_KEY {
+        let curve: ObjectIdentifier = spki
+            .algorithm
+            .parameters
+            .as_ref()
+            .ok_or(spdm_err!(EFAULT))?
+            .decode_as()
+            .map_err(|_| spdm_err!(EFAULT))?;
+        match curve {
+            rfc5912::SECP_256_R_1 => (
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256,
+                p256::ecdsa::Signature::from_der(signature)
+                    .map_err(|_| spdm_err!(EFAULT))?
+                    .to_bytes()
+                    .to_vec(),
+            ),
+            rfc5912::SECP_384_R_1 => (
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+                p384::ecdsa::Signature::from_der(signature)
+                    .map_err(|_| spdm_err!(EFAULT))?
+                    .to_bytes()
+                    .to_vec(),
+            ),
+            rfc5912::SECP_521_R_1 => (
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
+                p521::ecdsa::Signature::from_der(signature)
+                    .map_err(|_| spdm_err!(EFAULT))?
+                    .to_bytes()
+                    .to_vec(),
+            ),
+            _ => return spdm_result_err!(EFAULT),
+        }
+    } else {
+        let public_key =
+            rsa::RsaPublicKey::from_pkcs1_der(public_key).map_err(|_| spdm_err!(EFAULT))?;
+        let base_asym_algo = match public_key.size() {
+            256 => SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048,
+            384 => SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072,
+            512 => SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096,
+            _ => return spdm_result_err!(EFAULT),
+        };
+        (base_asym_algo, signature.to_vec())
+    };
+
+    verify_digest(
+        base_hash_algo,
+        base_asym_algo,
+        public_key,
+        digest.as_ref(),
+        &signature,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testlib::*;
+
+    fn read_cert_chain(key_dir: &str) -> Vec<u8> {
+        let key_dir = get_test_key_directory().join("test_key").join(key_dir);
+        let mut cert_chain = Vec::new();
+        for cert in ["ca.cert.der", "inter.cert.der", "end_responder.cert.der"].iter() {
+            cert_chain.extend(std::fs::read(key_dir.join(cert)).unwrap());
+        }
+        cert_chain
+    }
+
+    #[test]
+    fn test_case0_cert_from_cert_chain() {
+        let cert_chain = &mut cert_chain_array();
+        assert!(get_cert_from_cert_chain(cert_chain, -1).is_ok());
+        assert!(get_cert_from_cert_chain(cert_chain, 0).is_ok());
+        assert!(get_cert_from_cert_chain(cert_chain, 1).is_ok());
+        assert!(get_cert_from_cert_chain(cert_chain, 3).is_err());
+    }
+    #[test]
+    fn test_case1_cert_from_cert_chain() {
+        let cert_chain = &mut [0x1u8; 4096];
+        cert_chain[0] = 0x00;
+        cert_chain[1] = 0x00;
+        assert!(get_cert_from_cert_chain(cert_chain, 0).is_err());
+
+        // the length runs past the end of the chain
+        cert_chain[0] = 0x30;
+        cert_chain[1] = 0x82;
+        cert_chain[2] = 0xff;
+        assert!(get_cert_from_cert_chain(cert_chain, 0).is_err());
+
+        let cert_chain = &mut [0x11u8; 3];
+        assert!(get_cert_from_cert_chain(cert_chain, 0).is_err());
+    }
+    #[test]
+    fn test_case2_verify_cert_chain() {
+        for key_dir in ["EcP256", "EcP384", "EcP521", "Rsa3072", "Ed25519"].iter() {
+            let cert_chain = read_cert_chain(key_dir);
+            let leaf = &cert_chain[get_cert_from_cert_chain(&cert_chain, -1).unwrap().0..];
+            let leaf = Certificate::from_der(leaf).unwrap();
+            let (issuer_begin, issuer_end) = get_cert_from_cert_chain(&cert_chain, 1).unwrap();
+            let issuer = Certificate::from_der(&cert_chain[issuer_begin..issuer_end]).unwrap();
+
+            assert!(verify_cert_signature(&issuer, &leaf).is_ok());
+            assert!(is_ca(&issuer).unwrap());
+            assert!(!is_ca(&leaf).unwrap());
+            assert!(allows_responder_auth(&leaf).unwrap());
+        }
+    }
+    #[test]
+    fn test_case3_verify_cert_chain() {
+        let cert_chain = read_cert_chain("EcP384");
+        let (leaf_begin, leaf_end) = get_cert_from_cert_chain(&cert_chain, -1).unwrap();
+
+        // a corrupted leaf signature
+        let mut bad_chain = cert_chain.clone();
+        bad_chain[leaf_end - 8] ^= 0x01;
+        assert!(verify_cert_chain(&bad_chain).is_err());
+
+        // the leaf is not issued by the root
+        let (inter_begin, _) = get_cert_from_cert_chain(&cert_chain, 1).unwrap();
+        let mut bad_chain = cert_chain[..inter_begin].to_vec();
+        bad_chain.extend_from_slice(&cert_chain[leaf_begin..]);
+        assert!(verify_cert_chain(&bad_chain).is_err());
+
+        // the root alone is not a chain
+        assert!(verify_cert_chain(&cert_chain[..inter_begin]).is_err());
+    }
+    #[test]
+    fn test_case4_verify_cert_chain() {
+        let cert_chain = read_cert_chain("EcP384");
+        let (leaf_begin, _) = get_cert_from_cert_chain(&cert_chain, -1).unwrap();
+        let leaf = Certificate::from_der(&cert_chain[leaf_begin..]).unwrap();
+        let not_before = leaf.tbs_certificate.validity.not_before.to_unix_duration();
+        let not_after = leaf.tbs_certificate.validity.not_after.to_unix_duration();
+
+        assert!(check_validity(&leaf, not_before).is_ok());
+        assert!(check_validity(&leaf, not_after).is_ok());
+        assert!(check_validity(&leaf, not_before - Duration::from_secs(1)).is_err());
+        assert!(check_validity(&leaf, not_after + Duration::from_secs(1)).is_err());
+    }
+}
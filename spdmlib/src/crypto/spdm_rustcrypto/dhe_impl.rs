@@ -0,0 +1,182 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+extern crate alloc;
+use alloc::boxed::Box;
+
+use super::rand_impl::get_random;
+use crate::crypto::ffdhe::{self, FfdheExponent, FfdheGroup};
+use crate::crypto::{SpdmDhe, SpdmDheKeyExchange};
+use crate::msgs::{SpdmDheAlgo, SpdmDheExchangeStruct, SpdmDheFinalKeyStruct};
+use bytes::{BufMut, BytesMut};
+
+pub static DEFAULT: SpdmDhe = SpdmDhe {
+    generate_key_pair_cb: generate_key_pair,
+    supported_algo_cb: supported_algo,
+};
+
+fn supported_algo() -> SpdmDheAlgo {
+    SpdmDheAlgo::SECP_256_R1
+        | SpdmDheAlgo::SECP_384_R1
+        | SpdmDheAlgo::SECP_521_R1
+        | SpdmDheAlgo::FFDHE_2048
+        | SpdmDheAlgo::FFDHE_3072
+        | SpdmDheAlgo::FFDHE_4096
+}
+
+pub(crate) fn generate_key_pair(
+    dhe_algo: SpdmDheAlgo,
+) -> Option<(SpdmDheExchangeStruct, Box<dyn SpdmDheKeyExchange>)> {
+    match dhe_algo {
+        SpdmDheAlgo::SECP_256_R1 => SpdmDheKeyExchangeP256::generate_key_pair(),
+        SpdmDheAlgo::SECP_384_R1 => SpdmDheKeyExchangeP384::generate_key_pair(),
+        SpdmDheAlgo::SECP_521_R1 => SpdmDheKeyExchangeP521::generate_key_pair(),
+        SpdmDheAlgo::FFDHE_2048 => SpdmDheKeyExchangeFfdhe::generate_key_pair(&ffdhe::FFDHE_2048),
+        SpdmDheAlgo::FFDHE_3072 => SpdmDheKeyExchangeFfdhe::generate_key_pair(&ffdhe::FFDHE_3072),
+        SpdmDheAlgo::FFDHE_4096 => SpdmDheKeyExchangeFfdhe::generate_key_pair(&ffdhe::FFDHE_4096),
+        _ => None,
+    }
+}
+
+// SPDM carries the uncompressed point without the 0x04 prefix.
+macro_rules! ecdh_key_exchange {
+    ($name:ident, $curve:ident) => {
+        struct $name($curve::ecdh::EphemeralSecret);
+
+        impl SpdmDheKeyExchange for $name {
+            fn compute_final_key(
+                self: Box<Self>,
+                peer_pub_key: &SpdmDheExchangeStruct,
+            ) -> Option<SpdmDheFinalKeyStruct> {
+                let mut pubkey = BytesMut::new();
+                pubkey.put_u8(0x4u8);
+                pubkey.extend_from_slice(peer_pub_key.as_ref());
+
+                let peer_public_key = $curve::PublicKey::from_sec1_bytes(pubkey.as_ref()).ok()?;
+                let shared_secret = self.0.diffie_hellman(&peer_public_key);
+                Some(SpdmDheFinalKeyStruct::from(BytesMut::from(
+                    &shared_secret.raw_secret_bytes()[..],
+                )))
+            }
+        }
+
+        impl $name {
+            fn generate_key_pair() -> Option<(SpdmDheExchangeStruct, Box<dyn SpdmDheKeyExchange>)> {
+                use $curve::elliptic_curve::sec1::ToEncodedPoint;
+
+                let private_key = $curve::ecdh::EphemeralSecret::random(&mut rand_core::OsRng);
+                let public_key_old = private_key.public_key().to_encoded_point(false);
+                let public_key = BytesMut::from(&public_key_old.as_bytes()[1..]);
+
+                let res: Box<dyn SpdmDheKeyExchange> = Box::new(Self(private_key));
+
+                Some((SpdmDheExchangeStruct::from(public_key), res))
+            }
+        }
+    };
+}
+
+ecdh_key_exchange!(SpdmDheKeyExchangeP256, p256);
+ecdh_key_exchange!(SpdmDheKeyExchangeP384, p384);
+ecdh_key_exchange!(SpdmDheKeyExchangeP521, p521);
+
+struct SpdmDheKeyExchangeFfdhe {
+    group: &'static FfdheGroup,
+    exponent: FfdheExponent,
+}
+
+impl SpdmDheKeyExchange for SpdmDheKeyExchangeFfdhe {
+    fn compute_final_key(
+        self: Box<Self>,
+        peer_pub_key: &SpdmDheExchangeStruct,
+    ) -> Option<SpdmDheFinalKeyStruct> {
+        let final_key = self
+            .group
+            .compute_shared_secret(&self.exponent, peer_pub_key.as_ref())?;
+        Some(SpdmDheFinalKeyStruct::from(BytesMut::from(
+            final_key.as_slice(),
+        )))
+    }
+}
+
+impl SpdmDheKeyExchangeFfdhe {
+    fn generate_key_pair(
+        group: &'static FfdheGroup,
+    ) -> Option<(SpdmDheExchangeStruct, Box<dyn SpdmDheKeyExchange>)> {
+        let mut random = [0u8; 64];
+        // retry on the (negligible) chance of an all zero exponent
+        loop {
+            get_random(&mut random).ok()?;
+            if let Some((exponent, public_key)) = group.generate_key_pair(&random) {
+                let res: Box<dyn SpdmDheKeyExchange> = Box::new(Self { group, exponent });
+                return Some((
+                    SpdmDheExchangeStruct::from(BytesMut::from(public_key.as_slice())),
+                    res,
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_case0_dhe() {
+        for dhe_algo in [
+            SpdmDheAlgo::SECP_256_R1,
+            SpdmDheAlgo::SECP_384_R1,
+            SpdmDheAlgo::SECP_521_R1,
+            SpdmDheAlgo::FFDHE_2048,
+            SpdmDheAlgo::FFDHE_3072,
+            SpdmDheAlgo::FFDHE_4096,
+        ]
+        .iter()
+        {
+            let (exchange1, private1) = generate_key_pair(*dhe_algo).unwrap();
+            let (exchange2, private2) = generate_key_pair(*dhe_algo).unwrap();
+
+            let peer1 = private1.compute_final_key(&exchange2).unwrap();
+            let peer2 = private2.compute_final_key(&exchange1).unwrap();
+
+            assert_eq!(exchange1.as_ref().len(), dhe_algo.get_size() as usize);
+            assert_eq!(peer1.as_ref(), peer2.as_ref());
+        }
+    }
+    #[test]
+    fn test_case1_dhe() {
+        assert!(generate_key_pair(SpdmDheAlgo::empty()).is_none());
+
+        // not a point on the curve
+        for dhe_algo in [SpdmDheAlgo::SECP_256_R1, SpdmDheAlgo::SECP_384_R1].iter() {
+            let (_, private) = generate_key_pair(*dhe_algo).unwrap();
+            let peer = vec![0x5au8; dhe_algo.get_size() as usize];
+            let peer = SpdmDheExchangeStruct::from(BytesMut::from(peer.as_slice()));
+            assert!(private.compute_final_key(&peer).is_none());
+        }
+    }
+    #[test]
+    fn test_case2_dhe() {
+        for group in [&ffdhe::FFDHE_2048, &ffdhe::FFDHE_3072, &ffdhe::FFDHE_4096].iter() {
+            let size = group.size();
+            let zero = vec![0u8; size];
+            let mut one = vec![0u8; size];
+            one[size - 1] = 1;
+            let mut p_minus_one = group.prime.to_vec();
+            p_minus_one[size - 1] -= 1;
+            let short = vec![2u8; size - 1];
+
+            for peer in [zero, one, p_minus_one, group.prime.to_vec(), short].iter() {
+                let private: Box<dyn SpdmDheKeyExchange> = Box::new(SpdmDheKeyExchangeFfdhe {
+                    group,
+                    exponent: FfdheExponent::ONE,
+                });
+                let peer = SpdmDheExchangeStruct::from(BytesMut::from(peer.as_slice()));
+                assert!(private.compute_final_key(&peer).is_none());
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::crypto::SpdmHash;
+use crate::msgs::{SpdmBaseHashAlgo, SpdmDigestStruct};
+use sha2::Digest;
+
+pub static DEFAULT: SpdmHash = SpdmHash {
+    hash_all_cb: hash_all,
+    supported_algo_cb: supported_algo,
+};
+
+fn supported_algo() -> SpdmBaseHashAlgo {
+    SpdmBaseHashAlgo::TPM_ALG_SHA_256
+        | SpdmBaseHashAlgo::TPM_ALG_SHA_384
+        | SpdmBaseHashAlgo::TPM_ALG_SHA_512
+        | SpdmBaseHashAlgo::TPM_ALG_SHA3_256
+        | SpdmBaseHashAlgo::TPM_ALG_SHA3_384
+        | SpdmBaseHashAlgo::TPM_ALG_SHA3_512
+}
+
+pub(crate) fn hash_all(base_hash_algo: SpdmBaseHashAlgo, data: &[u8]) -> Option<SpdmDigestStruct> {
+    match base_hash_algo {
+        SpdmBaseHashAlgo::TPM_ALG_SHA_256 => Some(digest::<sha2::Sha256>(data)),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_384 => Some(digest::<sha2::Sha384>(data)),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_512 => Some(digest::<sha2::Sha512>(data)),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => Some(digest::<sha3::Sha3_256>(data)),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => Some(digest::<sha3::Sha3_384>(data)),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => Some(digest::<sha3::Sha3_512>(data)),
+        _ => None,
+    }
+}
+
+fn digest<D: Digest>(data: &[u8]) -> SpdmDigestStruct {
+    SpdmDigestStruct::from(&D::digest(data)[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case0_hash_all() {
+        // FIPS 180-2 SHA-256("abc")
+        let expected = [
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+            0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+            0xf2, 0x00, 0x15, 0xad,
+        ];
+        let digest = hash_all(SpdmBaseHashAlgo::TPM_ALG_SHA_256, b"abc").unwrap();
+        assert_eq!(digest.as_ref(), &expected[..]);
+
+        for base_hash_algo in [
+            SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_512,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+        ]
+        .iter()
+        {
+            let digest = hash_all(*base_hash_algo, b"abc").unwrap();
+            assert_eq!(digest.data_size, base_hash_algo.get_size());
+        }
+    }
+    #[test]
+    fn test_case1_hash_all() {
+        let base_hash_algo = SpdmBaseHashAlgo::empty();
+        let data = &mut [0u8; 64];
+
+        let digest = hash_all(base_hash_algo, data);
+        assert!(digest.is_none());
+    }
+}
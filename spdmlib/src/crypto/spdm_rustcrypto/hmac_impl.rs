@@ -0,0 +1,113 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::crypto::SpdmHmac;
+use crate::error::SpdmResult;
+use crate::msgs::{SpdmBaseHashAlgo, SpdmDigestStruct};
+use hmac::digest::core_api::BlockSizeUser;
+use hmac::digest::Digest;
+use hmac::{Mac, SimpleHmac};
+
+pub static DEFAULT: SpdmHmac = SpdmHmac {
+    hmac_cb: hmac,
+    hmac_verify_cb: hmac_verify,
+};
+
+pub(crate) fn hmac(
+    base_hash_algo: SpdmBaseHashAlgo,
+    key: &[u8],
+    data: &[u8],
+) -> Option<SpdmDigestStruct> {
+    match base_hash_algo {
+        SpdmBaseHashAlgo::TPM_ALG_SHA_256 => hmac_with::<sha2::Sha256>(key, data),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_384 => hmac_with::<sha2::Sha384>(key, data),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_512 => hmac_with::<sha2::Sha512>(key, data),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => hmac_with::<sha3::Sha3_256>(key, data),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => hmac_with::<sha3::Sha3_384>(key, data),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => hmac_with::<sha3::Sha3_512>(key, data),
+        _ => None,
+    }
+}
+
+pub(crate) fn hmac_verify(
+    base_hash_algo: SpdmBaseHashAlgo,
+    key: &[u8],
+    data: &[u8],
+    hmac: &SpdmDigestStruct,
+) -> SpdmResult {
+    match base_hash_algo {
+        SpdmBaseHashAlgo::TPM_ALG_SHA_256 => hmac_verify_with::<sha2::Sha256>(key, data, hmac),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_384 => hmac_verify_with::<sha2::Sha384>(key, data, hmac),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_512 => hmac_verify_with::<sha2::Sha512>(key, data, hmac),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => hmac_verify_with::<sha3::Sha3_256>(key, data, hmac),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => hmac_verify_with::<sha3::Sha3_384>(key, data, hmac),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => hmac_verify_with::<sha3::Sha3_512>(key, data, hmac),
+        _ => spdm_result_err!(EINVAL),
+    }
+}
+
+fn hmac_with<D: Digest + BlockSizeUser>(key: &[u8], data: &[u8]) -> Option<SpdmDigestStruct> {
+    let mut mac = <SimpleHmac<D> as Mac>::new_from_slice(key).ok()?;
+    mac.update(data);
+    Some(SpdmDigestStruct::from(&mac.finalize().into_bytes()[..]))
+}
+
+fn hmac_verify_with<D: Digest + BlockSizeUser>(
+    key: &[u8],
+    data: &[u8],
+    hmac: &SpdmDigestStruct,
+) -> SpdmResult {
+    let mut mac = <SimpleHmac<D> as Mac>::new_from_slice(key).map_err(|_| spdm_err!(EFAULT))?;
+    mac.update(data);
+    mac.verify_slice(&hmac.data[..(hmac.data_size as usize)])
+        .map_err(|_| spdm_err!(EFAULT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case0_hmac_verify() {
+        // RFC 4231 test case 2
+        let expected = [
+            0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+            0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+            0x64, 0xec, 0x38, 0x43,
+        ];
+        let spdm_digest = hmac(
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            b"Jefe",
+            b"what do ya want for nothing?",
+        )
+        .unwrap();
+        assert_eq!(spdm_digest.as_ref(), &expected[..]);
+
+        let key = &mut [10u8; 128];
+        let data = &mut [10u8; 128];
+        for base_hash_algo in [
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_512,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+        ]
+        .iter()
+        {
+            let spdm_digest = hmac(*base_hash_algo, key, data).unwrap();
+            assert_eq!(spdm_digest.data_size, base_hash_algo.get_size());
+            assert!(hmac_verify(*base_hash_algo, key, data, &spdm_digest).is_ok());
+            assert!(hmac_verify(*base_hash_algo, key, &data[1..], &spdm_digest).is_err());
+        }
+    }
+    #[test]
+    fn test_case1_hmac_verify() {
+        let base_hash_algo = SpdmBaseHashAlgo::empty();
+        let key = &mut [10u8; 128];
+        let data = &mut [10u8; 128];
+        assert!(hmac(base_hash_algo, key, data).is_none());
+        assert!(hmac_verify(base_hash_algo, key, data, &SpdmDigestStruct::default()).is_err());
+    }
+}
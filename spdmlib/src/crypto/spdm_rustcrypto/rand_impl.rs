@@ -0,0 +1,27 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::crypto::SpdmCryptoRandom;
+use crate::error::SpdmResult;
+
+pub static DEFAULT: SpdmCryptoRandom = SpdmCryptoRandom {
+    get_random_cb: get_random,
+};
+
+pub(crate) fn get_random(data: &mut [u8]) -> SpdmResult<usize> {
+    getrandom::getrandom(data).map_err(|_| spdm_err!(EIO))?;
+    Ok(data.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case0_get_random() {
+        let data = &mut [0u8; 80];
+        assert_eq!(get_random(data).unwrap(), 80);
+        assert!(data.iter().any(|byte| *byte != 0));
+    }
+}
@@ -2,6 +2,7 @@
 //
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
+use crate::cmds::error::SpdmErrorResponsePayload;
 use core::fmt::{Debug, Formatter, Result};
 
 /// POSIX errno
@@ -22,6 +23,8 @@ pub enum SpdmErrorNum {
     EINVAL = 22,
     ERANGE = 34,
     ENOSYS = 38,
+    EPROTO = 71,
+    ENOTSUP = 95,
 }
 
 pub struct SpdmError {
@@ -30,6 +33,8 @@ pub struct SpdmError {
     loc_line: u32,
     loc_col: u32,
     msg: &'static str,
+    // the ERROR response of the peer, for EPROTO
+    peer_error: Option<SpdmErrorResponsePayload>,
 }
 
 pub type SpdmResult<T = ()> = core::result::Result<T, SpdmError>;
@@ -51,6 +56,8 @@ impl SpdmErrorNum {
             EINVAL => "Invalid argument",
             ERANGE => "Math result not representable",
             ENOSYS => "Function not implemented",
+            EPROTO => "Protocol error",
+            ENOTSUP => "Operation not supported",
         }
     }
 }
@@ -69,12 +76,42 @@ impl SpdmError {
             loc_line,
             loc_col,
             msg,
+            peer_error: None,
+        }
+    }
+
+    /// An error for the ERROR response `peer_error` of the peer.
+    pub fn new_peer_error(
+        peer_error: SpdmErrorResponsePayload,
+        loc_file: &'static str,
+        loc_line: u32,
+        loc_col: u32,
+    ) -> Self {
+        Self {
+            peer_error: Some(peer_error),
+            ..Self::new(
+                SpdmErrorNum::EPROTO,
+                loc_file,
+                loc_line,
+                loc_col,
+                "ERROR response",
+            )
         }
     }
 
     pub fn code(&self) -> i32 {
         -(self.num as u32 as i32)
     }
+
+    pub fn num(&self) -> SpdmErrorNum {
+        self.num
+    }
+
+    /// The error code, error data and extended data the peer responded
+    /// with, or None for a local failure.
+    pub fn peer_error(&self) -> Option<&SpdmErrorResponsePayload> {
+        self.peer_error.as_ref()
+    }
 }
 
 impl Debug for SpdmError {
@@ -88,6 +125,13 @@ impl Debug for SpdmError {
             self.num.as_str(),
             self.msg
         )?;
+        if let Some(peer_error) = &self.peer_error {
+            write!(
+                f,
+                " {:?} ({:#x}) {:?}",
+                peer_error.error_code, peer_error.error_data, peer_error.extended_data
+            )?;
+        }
         Ok(())
     }
 }
@@ -104,6 +148,14 @@ macro_rules! spdm_err {
     }};
 }
 
+#[macro_export]
+macro_rules! spdm_peer_err {
+    ($peer_error: expr) => {{
+        use $crate::error::SpdmError;
+        SpdmError::new_peer_error($peer_error, file!(), line!(), column!())
+    }};
+}
+
 #[macro_export]
 macro_rules! spdm_result_err {
     ($num: ident) => {
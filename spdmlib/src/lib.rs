@@ -11,6 +11,9 @@ extern crate log;
 #[macro_use]
 extern crate bitflags;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 #[macro_use]
 pub mod error;
 
@@ -13,6 +13,7 @@ pub const SHA512_DIGEST_SIZE: usize = 64;
 pub const SHA3_256_DIGEST_SIZE: usize = 32;
 pub const SHA3_384_DIGEST_SIZE: usize = 48;
 pub const SHA3_512_DIGEST_SIZE: usize = 64;
+pub const SM3_256_DIGEST_SIZE: usize = 32;
 
 pub const RSASSA_2048_KEY_SIZE: usize = 256;
 pub const RSASSA_3072_KEY_SIZE: usize = 384;
@@ -24,6 +25,9 @@ pub const RSAPSS_4096_KEY_SIZE: usize = 512;
 pub const ECDSA_ECC_NIST_P256_KEY_SIZE: usize = 32 * 2;
 pub const ECDSA_ECC_NIST_P384_KEY_SIZE: usize = 48 * 2;
 pub const ECDSA_ECC_NIST_P521_KEY_SIZE: usize = 66 * 2;
+pub const SM2_ECC_SM2_P256_KEY_SIZE: usize = 32 * 2;
+pub const EDDSA_ED25519_KEY_SIZE: usize = 32 * 2;
+pub const EDDSA_ED448_KEY_SIZE: usize = 57 * 2;
 
 pub const FFDHE_2048_KEY_SIZE: usize = 256;
 pub const FFDHE_3072_KEY_SIZE: usize = 384;
@@ -32,22 +36,27 @@ pub const FFDHE_4096_KEY_SIZE: usize = 512;
 pub const SECP_256_R1_KEY_SIZE: usize = 32 * 2;
 pub const SECP_384_R1_KEY_SIZE: usize = 48 * 2;
 pub const SECP_521_R1_KEY_SIZE: usize = 66 * 2;
+pub const SM2_P256_KEY_SIZE: usize = 32 * 2;
 
 pub const AEAD_AES_128_GCM_KEY_SIZE: usize = 16;
 pub const AEAD_AES_256_GCM_KEY_SIZE: usize = 32;
 pub const AEAD_CHACHA20_POLY1305_KEY_SIZE: usize = 32;
+pub const AEAD_SM4_GCM_KEY_SIZE: usize = 16;
 
 pub const AEAD_AES_128_GCM_BLOCK_SIZE: usize = 16;
 pub const AEAD_AES_256_GCM_BLOCK_SIZE: usize = 16;
 pub const AEAD_CHACHA20_POLY1305_BLOCK_SIZE: usize = 16;
+pub const AEAD_SM4_GCM_BLOCK_SIZE: usize = 16;
 
 pub const AEAD_AES_128_GCM_IV_SIZE: usize = 12;
 pub const AEAD_AES_256_GCM_IV_SIZE: usize = 12;
 pub const AEAD_CHACHA20_POLY1305_IV_SIZE: usize = 12;
+pub const AEAD_SM4_GCM_IV_SIZE: usize = 12;
 
 pub const AEAD_AES_128_GCM_TAG_SIZE: usize = 16;
 pub const AEAD_AES_256_GCM_TAG_SIZE: usize = 16;
 pub const AEAD_CHACHA20_POLY1305_TAG_SIZE: usize = 16;
+pub const AEAD_SM4_GCM_TAG_SIZE: usize = 16;
 
 pub const SPDM_NONCE_SIZE: usize = 32;
 pub const SPDM_RANDOM_SIZE: usize = 32;
@@ -57,6 +66,55 @@ pub const SPDM_MAX_DHE_KEY_SIZE: usize = 512;
 pub const SPDM_MAX_AEAD_KEY_SIZE: usize = 32;
 pub const SPDM_MAX_AEAD_IV_SIZE: usize = 12;
 
+/// This is used in the algorithm preference lists of SpdmConfigInfo
+pub const SPDM_MAX_ALGO_PRIORITY_COUNT: usize = 16;
+
+/// Preference list and minimum strength support for the algorithm bitflags.
+macro_rules! impl_algo_priority {
+    ($algo:ident) => {
+        impl $algo {
+            /// The algorithms of `self` that are allowed: the ones at least
+            /// `min_strength` bits strong, and only the ones listed in
+            /// `priority` unless it is empty.
+            pub fn acceptable(&self, priority: &[$algo], min_strength: u16) -> $algo {
+                let listed = priority
+                    .iter()
+                    .fold($algo::empty(), |listed, v| listed | *v);
+                let mut acceptable = if listed.is_empty() {
+                    *self
+                } else {
+                    *self & listed
+                };
+                for i in 0..(core::mem::size_of_val(&self.bits()) * 8) {
+                    let v = $algo::from_bits_truncate(acceptable.bits() & (1 << i));
+                    if !v.is_empty() && v.get_security_strength() < min_strength {
+                        acceptable.remove(v);
+                    }
+                }
+                acceptable
+            }
+
+            /// Choose the first algorithm of `priority` that both sides
+            /// support, or as prioritize() does if `priority` is empty.
+            pub fn prioritize_by(&mut self, peer: $algo, priority: &[$algo]) {
+                if priority.iter().all(|v| v.is_empty()) {
+                    self.prioritize(peer);
+                    return;
+                }
+                *self &= peer;
+                let common = *self;
+                *self = $algo::empty();
+                for v in priority.iter() {
+                    if v.bits().count_ones() == 1 && common.contains(*v) {
+                        *self = *v;
+                        break;
+                    }
+                }
+            }
+        }
+    };
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct SpdmDigestStruct {
     pub data_size: u16,
@@ -135,6 +193,7 @@ bitflags! {
         const TPM_ALG_SHA3_256 = 0b0001_0000;
         const TPM_ALG_SHA3_384 = 0b0010_0000;
         const TPM_ALG_SHA3_512 = 0b0100_0000;
+        const TPM_ALG_SM3_256 = 0b1000_0000;
     }
 }
 
@@ -147,13 +206,56 @@ impl SpdmMeasurementHashAlgo {
             SpdmMeasurementHashAlgo::TPM_ALG_SHA3_384 => SHA3_384_DIGEST_SIZE as u16,
             SpdmMeasurementHashAlgo::TPM_ALG_SHA_512 => SHA512_DIGEST_SIZE as u16,
             SpdmMeasurementHashAlgo::TPM_ALG_SHA3_512 => SHA3_512_DIGEST_SIZE as u16,
+            SpdmMeasurementHashAlgo::TPM_ALG_SM3_256 => SM3_256_DIGEST_SIZE as u16,
             SpdmMeasurementHashAlgo::RAW_BIT_STREAM => 0u16,
             _ => {
                 panic!("invalid MeasurementHashAlgo");
             }
         }
     }
+
+    /// The measurement hash is not negotiated, the responder picks it out
+    /// of its own algorithms in this order.
+    pub fn prioritize(&mut self, peer: SpdmMeasurementHashAlgo) {
+        let prio_table = [
+            SpdmMeasurementHashAlgo::TPM_ALG_SHA_512,
+            SpdmMeasurementHashAlgo::TPM_ALG_SHA_384,
+            SpdmMeasurementHashAlgo::TPM_ALG_SHA_256,
+            SpdmMeasurementHashAlgo::TPM_ALG_SHA3_512,
+            SpdmMeasurementHashAlgo::TPM_ALG_SHA3_384,
+            SpdmMeasurementHashAlgo::TPM_ALG_SHA3_256,
+            SpdmMeasurementHashAlgo::TPM_ALG_SM3_256,
+            SpdmMeasurementHashAlgo::RAW_BIT_STREAM,
+        ];
+
+        *self &= peer;
+        for v in prio_table.iter() {
+            if self.bits() & v.bits() != 0 {
+                *self = *v;
+                break;
+            }
+        }
+    }
+
+    /// The security strength in bits, see NIST SP 800-57. A raw bit stream
+    /// is not hashed, so no minimum rules it out.
+    pub fn get_security_strength(&self) -> u16 {
+        match *self {
+            SpdmMeasurementHashAlgo::TPM_ALG_SHA_256
+            | SpdmMeasurementHashAlgo::TPM_ALG_SHA3_256
+            | SpdmMeasurementHashAlgo::TPM_ALG_SM3_256 => 128,
+            SpdmMeasurementHashAlgo::TPM_ALG_SHA_384
+            | SpdmMeasurementHashAlgo::TPM_ALG_SHA3_384 => 192,
+            SpdmMeasurementHashAlgo::TPM_ALG_SHA_512
+            | SpdmMeasurementHashAlgo::TPM_ALG_SHA3_512 => 256,
+            SpdmMeasurementHashAlgo::RAW_BIT_STREAM => u16::MAX,
+            _ => 0,
+        }
+    }
 }
+
+impl_algo_priority!(SpdmMeasurementHashAlgo);
+
 impl Codec for SpdmMeasurementHashAlgo {
     fn encode(&self, bytes: &mut Writer) {
         self.bits().encode(bytes);
@@ -178,6 +280,9 @@ bitflags! {
         const TPM_ALG_RSAPSS_4096 = 0b0100_0000;
         const TPM_ALG_ECDSA_ECC_NIST_P384 = 0b1000_0000;
         const TPM_ALG_ECDSA_ECC_NIST_P521 = 0b0000_0001_0000_0000;
+        const TPM_ALG_SM2_ECC_SM2_P256 = 0b0000_0010_0000_0000;
+        const EDDSA_ED25519 = 0b0000_0100_0000_0000;
+        const EDDSA_ED448 = 0b0000_1000_0000_0000;
     }
 }
 
@@ -185,6 +290,7 @@ impl SpdmBaseAsymAlgo {
     pub fn prioritize(&mut self, peer: SpdmBaseAsymAlgo) {
         let prio_table = [
             SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
             SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256,
             SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096,
             SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072,
@@ -192,6 +298,9 @@ impl SpdmBaseAsymAlgo {
             SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096,
             SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072,
             SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048,
+            SpdmBaseAsymAlgo::EDDSA_ED448,
+            SpdmBaseAsymAlgo::EDDSA_ED25519,
+            SpdmBaseAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256,
         ];
 
         *self &= peer;
@@ -213,13 +322,33 @@ impl SpdmBaseAsymAlgo {
             SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256 => ECDSA_ECC_NIST_P256_KEY_SIZE as u16,
             SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384 => ECDSA_ECC_NIST_P384_KEY_SIZE as u16,
             SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521 => ECDSA_ECC_NIST_P521_KEY_SIZE as u16,
+            SpdmBaseAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256 => SM2_ECC_SM2_P256_KEY_SIZE as u16,
+            SpdmBaseAsymAlgo::EDDSA_ED25519 => EDDSA_ED25519_KEY_SIZE as u16,
+            SpdmBaseAsymAlgo::EDDSA_ED448 => EDDSA_ED448_KEY_SIZE as u16,
             _ => {
                 panic!("invalid AsymAlgo");
             }
         }
     }
+
+    /// The security strength in bits, see NIST SP 800-57.
+    pub fn get_security_strength(&self) -> u16 {
+        match *self {
+            SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048 | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_2048 => 112,
+            SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072 | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072 => 128,
+            SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096 | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096 => 152,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256 => 128,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384 => 192,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521 => 256,
+            SpdmBaseAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256 | SpdmBaseAsymAlgo::EDDSA_ED25519 => 128,
+            SpdmBaseAsymAlgo::EDDSA_ED448 => 224,
+            _ => 0,
+        }
+    }
 }
 
+impl_algo_priority!(SpdmBaseAsymAlgo);
+
 impl Codec for SpdmBaseAsymAlgo {
     fn encode(&self, bytes: &mut Writer) {
         self.bits().encode(bytes);
@@ -241,6 +370,7 @@ bitflags! {
         const TPM_ALG_SHA3_256 = 0b0000_1000;
         const TPM_ALG_SHA3_384 = 0b0001_0000;
         const TPM_ALG_SHA3_512 = 0b0010_0000;
+        const TPM_ALG_SM3_256 = 0b0100_0000;
     }
 }
 
@@ -250,6 +380,10 @@ impl SpdmBaseHashAlgo {
             SpdmBaseHashAlgo::TPM_ALG_SHA_512,
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
             SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256,
+            SpdmBaseHashAlgo::TPM_ALG_SM3_256,
         ];
 
         *self &= peer;
@@ -268,13 +402,28 @@ impl SpdmBaseHashAlgo {
             SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => SHA3_384_DIGEST_SIZE as u16,
             SpdmBaseHashAlgo::TPM_ALG_SHA_512 => SHA512_DIGEST_SIZE as u16,
             SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => SHA3_512_DIGEST_SIZE as u16,
+            SpdmBaseHashAlgo::TPM_ALG_SM3_256 => SM3_256_DIGEST_SIZE as u16,
             _ => {
                 panic!("invalid HashAlgo");
             }
         }
     }
+
+    /// The security strength in bits, see NIST SP 800-57.
+    pub fn get_security_strength(&self) -> u16 {
+        match *self {
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256
+            | SpdmBaseHashAlgo::TPM_ALG_SHA3_256
+            | SpdmBaseHashAlgo::TPM_ALG_SM3_256 => 128,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_384 | SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => 192,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_512 | SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => 256,
+            _ => 0,
+        }
+    }
 }
 
+impl_algo_priority!(SpdmBaseHashAlgo);
+
 impl Codec for SpdmBaseHashAlgo {
     fn encode(&self, bytes: &mut Writer) {
         self.bits().encode(bytes);
@@ -303,7 +452,7 @@ enum_builder! {
     }
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
 pub struct SpdmExtAlgStruct {
     pub registry_id: SpdmStandardId,
     pub reserved: u8,
@@ -330,6 +479,18 @@ impl Codec for SpdmExtAlgStruct {
     }
 }
 
+/// Read `count` extended algorithms, keeping the ones that fit in
+/// `ext_algs`. The number kept is returned.
+pub fn read_ext_algs(r: &mut Reader, count: u8, ext_algs: &mut [SpdmExtAlgStruct]) -> Option<u8> {
+    for i in 0..(count as usize) {
+        let ext_alg = SpdmExtAlgStruct::read(r)?;
+        if let Some(v) = ext_algs.get_mut(i) {
+            *v = ext_alg;
+        }
+    }
+    Some(core::cmp::min(count as usize, ext_algs.len()) as u8)
+}
+
 bitflags! {
     #[derive(Default)]
     pub struct SpdmDheAlgo: u16 {
@@ -339,6 +500,7 @@ bitflags! {
         const SECP_256_R1 = 0b0000_1000;
         const SECP_384_R1 = 0b0001_0000;
         const SECP_521_R1 = 0b0010_0000;
+        const SM2_P256 = 0b0100_0000;
     }
 }
 
@@ -346,10 +508,12 @@ impl SpdmDheAlgo {
     pub fn prioritize(&mut self, peer: SpdmDheAlgo) {
         let prio_table = [
             SpdmDheAlgo::SECP_384_R1,
+            SpdmDheAlgo::SECP_521_R1,
             SpdmDheAlgo::SECP_256_R1,
             SpdmDheAlgo::FFDHE_4096,
             SpdmDheAlgo::FFDHE_3072,
             SpdmDheAlgo::FFDHE_2048,
+            SpdmDheAlgo::SM2_P256,
         ];
 
         *self &= peer;
@@ -368,13 +532,29 @@ impl SpdmDheAlgo {
             SpdmDheAlgo::SECP_256_R1 => SECP_256_R1_KEY_SIZE as u16,
             SpdmDheAlgo::SECP_384_R1 => SECP_384_R1_KEY_SIZE as u16,
             SpdmDheAlgo::SECP_521_R1 => SECP_521_R1_KEY_SIZE as u16,
+            SpdmDheAlgo::SM2_P256 => SM2_P256_KEY_SIZE as u16,
             _ => {
                 panic!("invalid DheAlgo");
             }
         }
     }
+
+    /// The security strength in bits, see NIST SP 800-57.
+    pub fn get_security_strength(&self) -> u16 {
+        match *self {
+            SpdmDheAlgo::FFDHE_2048 => 112,
+            SpdmDheAlgo::FFDHE_3072 => 128,
+            SpdmDheAlgo::FFDHE_4096 => 152,
+            SpdmDheAlgo::SECP_256_R1 | SpdmDheAlgo::SM2_P256 => 128,
+            SpdmDheAlgo::SECP_384_R1 => 192,
+            SpdmDheAlgo::SECP_521_R1 => 256,
+            _ => 0,
+        }
+    }
 }
 
+impl_algo_priority!(SpdmDheAlgo);
+
 impl Codec for SpdmDheAlgo {
     fn encode(&self, bytes: &mut Writer) {
         self.bits().encode(bytes);
@@ -393,6 +573,7 @@ bitflags! {
         const AES_128_GCM = 0b0000_0001;
         const AES_256_GCM = 0b0000_0010;
         const CHACHA20_POLY1305 = 0b0000_0100;
+        const SM4_GCM = 0b0000_1000;
     }
 }
 
@@ -402,6 +583,7 @@ impl SpdmAeadAlgo {
             SpdmAeadAlgo::AES_256_GCM,
             SpdmAeadAlgo::AES_128_GCM,
             SpdmAeadAlgo::CHACHA20_POLY1305,
+            SpdmAeadAlgo::SM4_GCM,
         ];
 
         *self &= peer;
@@ -417,6 +599,7 @@ impl SpdmAeadAlgo {
             SpdmAeadAlgo::AES_128_GCM => AEAD_AES_128_GCM_KEY_SIZE as u16,
             SpdmAeadAlgo::AES_256_GCM => AEAD_AES_256_GCM_KEY_SIZE as u16,
             SpdmAeadAlgo::CHACHA20_POLY1305 => AEAD_CHACHA20_POLY1305_KEY_SIZE as u16,
+            SpdmAeadAlgo::SM4_GCM => AEAD_SM4_GCM_KEY_SIZE as u16,
             _ => {
                 panic!("invalid AeadAlgo");
             }
@@ -427,6 +610,7 @@ impl SpdmAeadAlgo {
             SpdmAeadAlgo::AES_128_GCM => AEAD_AES_128_GCM_IV_SIZE as u16,
             SpdmAeadAlgo::AES_256_GCM => AEAD_AES_256_GCM_IV_SIZE as u16,
             SpdmAeadAlgo::CHACHA20_POLY1305 => AEAD_CHACHA20_POLY1305_IV_SIZE as u16,
+            SpdmAeadAlgo::SM4_GCM => AEAD_SM4_GCM_IV_SIZE as u16,
             _ => {
                 panic!("invalid AeadAlgo");
             }
@@ -437,13 +621,25 @@ impl SpdmAeadAlgo {
             SpdmAeadAlgo::AES_128_GCM => AEAD_AES_128_GCM_TAG_SIZE as u16,
             SpdmAeadAlgo::AES_256_GCM => AEAD_AES_256_GCM_TAG_SIZE as u16,
             SpdmAeadAlgo::CHACHA20_POLY1305 => AEAD_CHACHA20_POLY1305_TAG_SIZE as u16,
+            SpdmAeadAlgo::SM4_GCM => AEAD_SM4_GCM_TAG_SIZE as u16,
             _ => {
                 panic!("invalid AeadAlgo");
             }
         }
     }
+
+    /// The security strength in bits, i.e. the key size.
+    pub fn get_security_strength(&self) -> u16 {
+        match *self {
+            SpdmAeadAlgo::AES_128_GCM | SpdmAeadAlgo::SM4_GCM => 128,
+            SpdmAeadAlgo::AES_256_GCM | SpdmAeadAlgo::CHACHA20_POLY1305 => 256,
+            _ => 0,
+        }
+    }
 }
 
+impl_algo_priority!(SpdmAeadAlgo);
+
 impl Codec for SpdmAeadAlgo {
     fn encode(&self, bytes: &mut Writer) {
         self.bits().encode(bytes);
@@ -468,6 +664,9 @@ bitflags! {
         const TPM_ALG_RSAPSS_4096 = 0b0100_0000;
         const TPM_ALG_ECDSA_ECC_NIST_P384 = 0b1000_0000;
         const TPM_ALG_ECDSA_ECC_NIST_P521 = 0b0000_0001_0000_0000;
+        const TPM_ALG_SM2_ECC_SM2_P256 = 0b0000_0010_0000_0000;
+        const EDDSA_ED25519 = 0b0000_0100_0000_0000;
+        const EDDSA_ED448 = 0b0000_1000_0000_0000;
     }
 }
 
@@ -481,7 +680,11 @@ impl SpdmReqAsymAlgo {
             SpdmReqAsymAlgo::TPM_ALG_RSASSA_3072,
             SpdmReqAsymAlgo::TPM_ALG_RSASSA_2048,
             SpdmReqAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+            SpdmReqAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
             SpdmReqAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256,
+            SpdmReqAsymAlgo::EDDSA_ED448,
+            SpdmReqAsymAlgo::EDDSA_ED25519,
+            SpdmReqAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256,
         ];
 
         *self &= peer;
@@ -503,13 +706,33 @@ impl SpdmReqAsymAlgo {
             SpdmReqAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256 => ECDSA_ECC_NIST_P256_KEY_SIZE as u16,
             SpdmReqAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384 => ECDSA_ECC_NIST_P384_KEY_SIZE as u16,
             SpdmReqAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521 => ECDSA_ECC_NIST_P521_KEY_SIZE as u16,
+            SpdmReqAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256 => SM2_ECC_SM2_P256_KEY_SIZE as u16,
+            SpdmReqAsymAlgo::EDDSA_ED25519 => EDDSA_ED25519_KEY_SIZE as u16,
+            SpdmReqAsymAlgo::EDDSA_ED448 => EDDSA_ED448_KEY_SIZE as u16,
             _ => {
                 panic!("invalid ReqAsymAlgo");
             }
         }
     }
+
+    /// The security strength in bits, see NIST SP 800-57.
+    pub fn get_security_strength(&self) -> u16 {
+        match *self {
+            SpdmReqAsymAlgo::TPM_ALG_RSASSA_2048 | SpdmReqAsymAlgo::TPM_ALG_RSAPSS_2048 => 112,
+            SpdmReqAsymAlgo::TPM_ALG_RSASSA_3072 | SpdmReqAsymAlgo::TPM_ALG_RSAPSS_3072 => 128,
+            SpdmReqAsymAlgo::TPM_ALG_RSASSA_4096 | SpdmReqAsymAlgo::TPM_ALG_RSAPSS_4096 => 152,
+            SpdmReqAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256 => 128,
+            SpdmReqAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384 => 192,
+            SpdmReqAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521 => 256,
+            SpdmReqAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256 | SpdmReqAsymAlgo::EDDSA_ED25519 => 128,
+            SpdmReqAsymAlgo::EDDSA_ED448 => 224,
+            _ => 0,
+        }
+    }
 }
 
+impl_algo_priority!(SpdmReqAsymAlgo);
+
 impl Codec for SpdmReqAsymAlgo {
     fn encode(&self, bytes: &mut Writer) {
         self.bits().encode(bytes);
@@ -596,14 +819,15 @@ pub struct SpdmAlgStruct {
     pub alg_type: SpdmAlgType,
     pub alg_fixed_count: u8,
     pub alg_supported: SpdmAlg,
-    pub alg_ext_count: u8, // for output only. Always treat as 0 on input.
+    pub alg_ext_count: u8,
+    pub alg_ext: [SpdmExtAlgStruct; config::MAX_SPDM_EXTEND_ALG_STRUCT_COUNT],
 }
 
 impl Codec for SpdmAlgStruct {
     fn encode(&self, bytes: &mut Writer) {
-        assert_eq!(self.alg_ext_count, 0);
+        assert!(self.alg_ext_count as usize <= config::MAX_SPDM_EXTEND_ALG_STRUCT_COUNT);
         self.alg_type.encode(bytes);
-        let alg_count = ((self.alg_fixed_count as u32) << 4) as u8;
+        let alg_count = ((self.alg_fixed_count as u32) << 4) as u8 | self.alg_ext_count;
         alg_count.encode(bytes);
 
         if self.alg_fixed_count == 2 {
@@ -625,6 +849,10 @@ impl Codec for SpdmAlgStruct {
                 }
             }
         }
+
+        for alg_ext in self.alg_ext.iter().take(self.alg_ext_count as usize) {
+            alg_ext.encode(bytes);
+        }
     }
 
     fn read(r: &mut Reader) -> Option<SpdmAlgStruct> {
@@ -642,20 +870,24 @@ impl Codec for SpdmAlgStruct {
             SpdmAlgType::SpdmAlgTypeKeySchedule => {
                 Some(SpdmAlg::SpdmAlgoKeySchedule(SpdmKeyScheduleAlgo::read(r)?))
             }
-            _ => Some(SpdmAlg::SpdmAlgoUnknown(SpdmUnknownAlgo {})),
+            _ => {
+                r.take(alg_fixed_count as usize)?;
+                Some(SpdmAlg::SpdmAlgoUnknown(SpdmUnknownAlgo {}))
+            }
         };
 
         let alg_supported = alg_supported?;
 
-        for _ in 0..(alg_ext_count as usize) {
-            SpdmExtAlgStruct::read(r)?;
-        }
+        // the extended algorithms beyond ours are skipped
+        let mut alg_ext = [SpdmExtAlgStruct::default(); config::MAX_SPDM_EXTEND_ALG_STRUCT_COUNT];
+        let alg_ext_count = read_ext_algs(r, alg_ext_count, &mut alg_ext)?;
 
         Some(SpdmAlgStruct {
             alg_type,
             alg_fixed_count,
             alg_supported,
             alg_ext_count,
+            alg_ext,
         })
     }
 }
@@ -1187,6 +1419,7 @@ mod tests {
             alg_fixed_count: 2,
             alg_supported: SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::FFDHE_2048),
             alg_ext_count: 0,
+            ..Default::default()
         };
         value.encode(&mut writer);
 
@@ -1212,6 +1445,7 @@ mod tests {
             alg_fixed_count: 0,
             alg_supported: SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::FFDHE_2048),
             alg_ext_count: 0,
+            ..Default::default()
         };
         value.encode(&mut writer);
 
@@ -1236,6 +1470,7 @@ mod tests {
             alg_fixed_count: 0,
             alg_supported: SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::FFDHE_2048),
             alg_ext_count: 100,
+            ..Default::default()
         };
         value.encode(&mut writer);
     }
@@ -1249,6 +1484,7 @@ mod tests {
             alg_fixed_count: 2,
             alg_supported: SpdmAlg::SpdmAlgoUnknown(SpdmUnknownAlgo {}),
             alg_ext_count: 0,
+            ..Default::default()
         };
         value.encode(&mut writer);
 
@@ -1319,6 +1555,9 @@ mod tests {
         value = SpdmMeasurementHashAlgo::TPM_ALG_SHA3_512;
         assert_eq!(value.get_size(), SHA3_512_DIGEST_SIZE as u16);
 
+        value = SpdmMeasurementHashAlgo::TPM_ALG_SM3_256;
+        assert_eq!(value.get_size(), SM3_256_DIGEST_SIZE as u16);
+
         value = SpdmMeasurementHashAlgo::RAW_BIT_STREAM;
         assert_eq!(value.get_size(), 0u16);
 
@@ -1354,6 +1593,15 @@ mod tests {
 
         value = SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521;
         assert_eq!(value.get_size(), ECDSA_ECC_NIST_P521_KEY_SIZE as u16);
+
+        value = SpdmBaseAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256;
+        assert_eq!(value.get_size(), SM2_ECC_SM2_P256_KEY_SIZE as u16);
+
+        value = SpdmBaseAsymAlgo::EDDSA_ED25519;
+        assert_eq!(value.get_size(), EDDSA_ED25519_KEY_SIZE as u16);
+
+        value = SpdmBaseAsymAlgo::EDDSA_ED448;
+        assert_eq!(value.get_size(), EDDSA_ED448_KEY_SIZE as u16);
         value = SpdmBaseAsymAlgo::empty();
         value.get_size();
     }
@@ -1378,6 +1626,9 @@ mod tests {
         value = SpdmDheAlgo::SECP_521_R1;
         assert_eq!(value.get_size(), SECP_521_R1_KEY_SIZE as u16);
 
+        value = SpdmDheAlgo::SM2_P256;
+        assert_eq!(value.get_size(), SM2_P256_KEY_SIZE as u16);
+
         value = SpdmDheAlgo::empty();
         value.get_size();
     }
@@ -1393,6 +1644,9 @@ mod tests {
         value = SpdmAeadAlgo::CHACHA20_POLY1305;
         assert_eq!(value.get_key_size(), AEAD_CHACHA20_POLY1305_KEY_SIZE as u16);
 
+        value = SpdmAeadAlgo::SM4_GCM;
+        assert_eq!(value.get_key_size(), AEAD_SM4_GCM_KEY_SIZE as u16);
+
         value = SpdmAeadAlgo::empty();
         value.get_key_size();
     }
@@ -1408,6 +1662,9 @@ mod tests {
         value = SpdmAeadAlgo::CHACHA20_POLY1305;
         assert_eq!(value.get_key_size(), AEAD_CHACHA20_POLY1305_KEY_SIZE as u16);
 
+        value = SpdmAeadAlgo::SM4_GCM;
+        assert_eq!(value.get_key_size(), AEAD_SM4_GCM_KEY_SIZE as u16);
+
         value = SpdmAeadAlgo::empty();
         value.get_key_size();
     }
@@ -1423,6 +1680,9 @@ mod tests {
         value = SpdmAeadAlgo::CHACHA20_POLY1305;
         assert_eq!(value.get_iv_size(), AEAD_CHACHA20_POLY1305_IV_SIZE as u16);
 
+        value = SpdmAeadAlgo::SM4_GCM;
+        assert_eq!(value.get_iv_size(), AEAD_SM4_GCM_IV_SIZE as u16);
+
         value = SpdmAeadAlgo::empty();
         value.get_iv_size();
     }
@@ -1438,6 +1698,9 @@ mod tests {
         value = SpdmAeadAlgo::CHACHA20_POLY1305;
         assert_eq!(value.get_tag_size(), AEAD_CHACHA20_POLY1305_TAG_SIZE as u16);
 
+        value = SpdmAeadAlgo::SM4_GCM;
+        assert_eq!(value.get_tag_size(), AEAD_SM4_GCM_TAG_SIZE as u16);
+
         value = SpdmAeadAlgo::empty();
         value.get_tag_size();
     }
@@ -1471,6 +1734,15 @@ mod tests {
         value = SpdmReqAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521;
         assert_eq!(value.get_size(), ECDSA_ECC_NIST_P521_KEY_SIZE as u16);
 
+        value = SpdmReqAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256;
+        assert_eq!(value.get_size(), SM2_ECC_SM2_P256_KEY_SIZE as u16);
+
+        value = SpdmReqAsymAlgo::EDDSA_ED25519;
+        assert_eq!(value.get_size(), EDDSA_ED25519_KEY_SIZE as u16);
+
+        value = SpdmReqAsymAlgo::EDDSA_ED448;
+        assert_eq!(value.get_size(), EDDSA_ED448_KEY_SIZE as u16);
+
         value = SpdmReqAsymAlgo::empty();
         value.get_size();
     }
@@ -1483,4 +1755,89 @@ mod tests {
         let mut reader = Reader::init(u8_slice);
         SpdmUnknownAlgo::read(&mut reader);
     }
+    #[test]
+    fn test_case0_spdm_algo_priority() {
+        let mut priority = [SpdmDheAlgo::empty(); SPDM_MAX_ALGO_PRIORITY_COUNT];
+        let supported =
+            SpdmDheAlgo::FFDHE_3072 | SpdmDheAlgo::SECP_256_R1 | SpdmDheAlgo::SECP_384_R1;
+
+        assert_eq!(supported.acceptable(&priority, 0), supported);
+        assert_eq!(
+            supported.acceptable(&priority, 192),
+            SpdmDheAlgo::SECP_384_R1
+        );
+        let mut dhe_sel = supported;
+        dhe_sel.prioritize_by(SpdmDheAlgo::all(), &priority);
+        assert_eq!(dhe_sel, SpdmDheAlgo::SECP_384_R1);
+
+        priority[0] = SpdmDheAlgo::SECP_256_R1;
+        priority[1] = SpdmDheAlgo::FFDHE_3072;
+        assert_eq!(
+            supported.acceptable(&priority, 0),
+            SpdmDheAlgo::FFDHE_3072 | SpdmDheAlgo::SECP_256_R1
+        );
+        let mut dhe_sel = supported;
+        dhe_sel.prioritize_by(SpdmDheAlgo::all(), &priority);
+        assert_eq!(dhe_sel, SpdmDheAlgo::SECP_256_R1);
+        let mut dhe_sel = supported;
+        dhe_sel.prioritize_by(SpdmDheAlgo::FFDHE_3072, &priority);
+        assert_eq!(dhe_sel, SpdmDheAlgo::FFDHE_3072);
+        let mut dhe_sel = supported;
+        dhe_sel.prioritize_by(SpdmDheAlgo::SECP_384_R1, &priority);
+        assert_eq!(dhe_sel, SpdmDheAlgo::empty());
+    }
+    #[test]
+    fn test_case1_spdm_algo_priority() {
+        let mut base_hash_sel =
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256 | SpdmBaseHashAlgo::TPM_ALG_SHA3_384;
+        base_hash_sel.prioritize(SpdmBaseHashAlgo::all());
+        assert_eq!(base_hash_sel, SpdmBaseHashAlgo::TPM_ALG_SHA3_384);
+
+        let mut base_asym_sel = SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256
+            | SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521;
+        base_asym_sel.prioritize(SpdmBaseAsymAlgo::all());
+        assert_eq!(base_asym_sel, SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521);
+
+        let mut dhe_sel = SpdmDheAlgo::SECP_256_R1 | SpdmDheAlgo::SECP_521_R1;
+        dhe_sel.prioritize(SpdmDheAlgo::all());
+        assert_eq!(dhe_sel, SpdmDheAlgo::SECP_521_R1);
+    }
+    #[test]
+    fn test_case2_spdm_algo_priority() {
+        // the SPDM 1.2 algorithms are only chosen when nothing older is common
+        let mut base_asym_sel = SpdmBaseAsymAlgo::EDDSA_ED25519 | SpdmBaseAsymAlgo::EDDSA_ED448;
+        base_asym_sel.prioritize(SpdmBaseAsymAlgo::all());
+        assert_eq!(base_asym_sel, SpdmBaseAsymAlgo::EDDSA_ED448);
+        let mut base_asym_sel =
+            SpdmBaseAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256 | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048;
+        base_asym_sel.prioritize(SpdmBaseAsymAlgo::all());
+        assert_eq!(base_asym_sel, SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048);
+
+        let mut base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SM3_256;
+        base_hash_sel.prioritize(SpdmBaseHashAlgo::all());
+        assert_eq!(base_hash_sel, SpdmBaseHashAlgo::TPM_ALG_SM3_256);
+
+        let mut aead_sel = SpdmAeadAlgo::SM4_GCM | SpdmAeadAlgo::CHACHA20_POLY1305;
+        aead_sel.prioritize(SpdmAeadAlgo::all());
+        assert_eq!(aead_sel, SpdmAeadAlgo::CHACHA20_POLY1305);
+
+        let priority = [SpdmDheAlgo::SM2_P256, SpdmDheAlgo::SECP_256_R1];
+        let mut dhe_sel = SpdmDheAlgo::SECP_256_R1 | SpdmDheAlgo::SM2_P256;
+        dhe_sel.prioritize_by(SpdmDheAlgo::all(), &priority);
+        assert_eq!(dhe_sel, SpdmDheAlgo::SM2_P256);
+        assert_eq!(
+            SpdmDheAlgo::all().acceptable(&priority, 128),
+            SpdmDheAlgo::SECP_256_R1 | SpdmDheAlgo::SM2_P256
+        );
+
+        let u8_slice = &mut [0u8; 4];
+        let mut writer = Writer::init(u8_slice);
+        (SpdmReqAsymAlgo::EDDSA_ED448 | SpdmReqAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256)
+            .encode(&mut writer);
+        let mut reader = Reader::init(u8_slice);
+        assert_eq!(
+            SpdmReqAsymAlgo::read(&mut reader).unwrap(),
+            SpdmReqAsymAlgo::EDDSA_ED448 | SpdmReqAsymAlgo::TPM_ALG_SM2_ECC_SM2_P256
+        );
+    }
 }
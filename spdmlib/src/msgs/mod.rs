@@ -500,7 +500,9 @@ mod tests {
                         alg_fixed_count: 2,
                         alg_supported: SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::FFDHE_2048),
                         alg_ext_count: 0,
+                        ..Default::default()
                     }; crate::config::MAX_SPDM_ALG_STRUCT_COUNT],
+                    ..Default::default()
                 },
             ),
         };
@@ -553,7 +555,9 @@ mod tests {
                     alg_fixed_count: 2,
                     alg_supported: SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::FFDHE_2048),
                     alg_ext_count: 0,
+                    ..Default::default()
                 }; MAX_SPDM_ALG_STRUCT_COUNT],
+                ..Default::default()
             }),
         };
         let context = new_context(my_spdm_device_io, pcidoe_transport_encap);
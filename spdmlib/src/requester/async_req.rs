@@ -0,0 +1,304 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+//! The async requester API.
+//!
+//! The flows are shared with the blocking API, see the `_with` methods, so
+//! the retry policy applies the same way. A context for the async API only
+//! is created with RequesterContext::new_without_device_io.
+
+use crate::common::{SpdmAppMessageType, SpdmAsyncDeviceIo};
+use crate::error::SpdmResult;
+use crate::requester::*;
+
+impl<'a> RequesterContext<'a> {
+    pub async fn init_connection_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+    ) -> SpdmResult {
+        self.init_connection_with(device_io).await
+    }
+
+    pub async fn start_session_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        use_psk: bool,
+        slot_id: u8,
+        measurement_summary_hash_type: SpdmMeasurementSummaryHashType,
+    ) -> SpdmResult<u32> {
+        self.start_session_with(device_io, use_psk, slot_id, measurement_summary_hash_type)
+            .await
+    }
+
+    pub async fn end_session_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        session_id: u32,
+    ) -> SpdmResult {
+        let _result = self
+            .send_receive_spdm_end_session_with(device_io, session_id)
+            .await;
+        Ok(())
+    }
+
+    pub async fn send_message_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        send_buffer: &[u8],
+    ) -> SpdmResult {
+        self.send_message_with(device_io, send_buffer).await
+    }
+
+    pub async fn send_secured_message_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        session_id: u32,
+        send_buffer: &[u8],
+    ) -> SpdmResult {
+        self.send_secured_message_with(device_io, session_id, send_buffer)
+            .await
+    }
+
+    pub async fn receive_message_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        self.receive_message_with(device_io, receive_buffer).await
+    }
+
+    pub async fn receive_secured_message_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        session_id: u32,
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        self.receive_secured_message_with(device_io, session_id, receive_buffer)
+            .await
+    }
+
+    pub async fn send_receive_message_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        send_buffer: &[u8],
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        self.send_receive_message_with(device_io, send_buffer, receive_buffer)
+            .await
+    }
+
+    pub async fn send_receive_secured_message_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        session_id: u32,
+        send_buffer: &[u8],
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        self.send_receive_secured_message_with(device_io, session_id, send_buffer, receive_buffer)
+            .await
+    }
+
+    pub async fn send_secured_app_message_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        session_id: u32,
+        message_type: u8,
+        send_buffer: &[u8],
+    ) -> SpdmResult {
+        self.send_secured_app_message_with(device_io, session_id, message_type, send_buffer)
+            .await
+    }
+
+    pub async fn receive_secured_app_message_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        session_id: u32,
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<(usize, SpdmAppMessageType)> {
+        self.receive_secured_app_message_with(device_io, session_id, receive_buffer)
+            .await
+    }
+
+    pub async fn send_receive_secured_app_message_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        session_id: u32,
+        message_type: u8,
+        send_buffer: &[u8],
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        self.send_receive_secured_app_message_with(
+            device_io,
+            session_id,
+            message_type,
+            send_buffer,
+            receive_buffer,
+        )
+        .await
+    }
+
+    pub async fn send_receive_spdm_version_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+    ) -> SpdmResult {
+        self.send_receive_spdm_version_with(device_io).await
+    }
+
+    pub async fn send_receive_spdm_capability_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+    ) -> SpdmResult {
+        self.send_receive_spdm_capability_with(device_io).await
+    }
+
+    pub async fn send_receive_spdm_algorithm_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+    ) -> SpdmResult {
+        self.send_receive_spdm_algorithm_with(device_io).await
+    }
+
+    pub async fn send_receive_spdm_digest_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+    ) -> SpdmResult {
+        self.send_receive_spdm_digest_with(device_io).await
+    }
+
+    pub async fn send_receive_spdm_certificate_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        slot_id: u8,
+    ) -> SpdmResult {
+        self.send_receive_spdm_certificate_with(device_io, slot_id)
+            .await
+    }
+
+    pub async fn send_receive_spdm_challenge_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        slot_id: u8,
+        measurement_summary_hash_type: SpdmMeasurementSummaryHashType,
+    ) -> SpdmResult {
+        self.send_receive_spdm_challenge_with(device_io, slot_id, measurement_summary_hash_type)
+            .await
+    }
+
+    pub async fn send_receive_spdm_measurement_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        measurement_operation: SpdmMeasurementOperation,
+        slot_id: u8,
+    ) -> SpdmResult {
+        self.send_receive_spdm_measurement_with(device_io, measurement_operation, slot_id)
+            .await
+    }
+
+    pub async fn send_receive_spdm_key_exchange_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        slot_id: u8,
+        measurement_summary_hash_type: SpdmMeasurementSummaryHashType,
+    ) -> SpdmResult<u32> {
+        self.send_receive_spdm_key_exchange_with(device_io, slot_id, measurement_summary_hash_type)
+            .await
+    }
+
+    pub async fn send_receive_spdm_finish_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        session_id: u32,
+    ) -> SpdmResult {
+        self.send_receive_spdm_finish_with(device_io, session_id)
+            .await
+    }
+
+    pub async fn send_receive_spdm_psk_exchange_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        measurement_summary_hash_type: SpdmMeasurementSummaryHashType,
+    ) -> SpdmResult<u32> {
+        self.send_receive_spdm_psk_exchange_with(device_io, measurement_summary_hash_type)
+            .await
+    }
+
+    pub async fn send_receive_spdm_psk_finish_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        session_id: u32,
+    ) -> SpdmResult {
+        self.send_receive_spdm_psk_finish_with(device_io, session_id)
+            .await
+    }
+
+    pub async fn send_receive_spdm_heartbeat_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        session_id: u32,
+    ) -> SpdmResult {
+        self.send_receive_spdm_heartbeat_with(device_io, session_id)
+            .await
+    }
+
+    pub async fn send_receive_spdm_key_update_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        session_id: u32,
+        key_update_operation: SpdmKeyUpdateOperation,
+    ) -> SpdmResult {
+        self.send_receive_spdm_key_update_with(device_io, session_id, key_update_operation)
+            .await
+    }
+
+    pub async fn send_receive_spdm_end_session_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+        session_id: u32,
+    ) -> SpdmResult {
+        self.send_receive_spdm_end_session_with(device_io, session_id)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::SpdmBlockingDeviceIo;
+    use crate::testlib::*;
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[test]
+    fn test_case0_async_send() {
+        let (config_info, provision_info) = create_info();
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+        let mut my_spdm_device_io = MySpdmDeviceIo;
+        let mut device_io = SpdmBlockingDeviceIo {
+            device_io: &mut my_spdm_device_io,
+            delay: None,
+        };
+
+        let mut requester = RequesterContext::new_without_device_io(
+            pcidoe_transport_encap,
+            config_info,
+            provision_info,
+        );
+
+        // the futures can be spawned on a multi-threaded executor
+        assert_send(&requester.init_connection_async(&mut device_io));
+        assert_send(&requester.start_session_async(
+            &mut device_io,
+            false,
+            0,
+            SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+        ));
+        let mut receive_buffer = [0u8; 16];
+        assert_send(&requester.send_receive_secured_message_async(
+            &mut device_io,
+            0,
+            &[],
+            &mut receive_buffer,
+        ));
+    }
+}
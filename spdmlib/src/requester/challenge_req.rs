@@ -12,15 +12,28 @@ impl<'a> RequesterContext<'a> {
         slot_id: u8,
         measurement_summary_hash_type: SpdmMeasurementSummaryHashType,
     ) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self.send_receive_spdm_challenge_with(
+            &mut device_io,
+            slot_id,
+            measurement_summary_hash_type
+        ))
+    }
+
+    pub(crate) async fn send_receive_spdm_challenge_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        slot_id: u8,
+        measurement_summary_hash_type: SpdmMeasurementSummaryHashType,
+    ) -> SpdmResult {
+        self.check_request_supported(SpdmResponseResponseCode::SpdmRequestChallenge)?;
         info!("send spdm challenge\n");
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let send_used =
             self.encode_spdm_challenge(slot_id, measurement_summary_hash_type, &mut send_buffer)?;
-        self.send_message(&send_buffer[..send_used])?;
-
-        // Receive
         let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let used = self.receive_message(&mut receive_buffer)?;
+        let used = self
+            .send_receive_message_with(device_io, &send_buffer[..send_used], &mut receive_buffer)
+            .await?;
         self.handle_spdm_challenge_response(
             measurement_summary_hash_type,
             &send_buffer[..send_used],
@@ -81,8 +94,7 @@ impl<'a> RequesterContext<'a> {
                         debug!("!!! challenge_auth : {:02x?}\n", challenge_auth);
 
                         // verify signature
-                        let base_asym_size =
-                            self.common.negotiate_info.base_asym_sel.get_size() as usize;
+                        let base_asym_size = self.common.get_asym_key_size() as usize;
                         let temp_used = used - base_asym_size;
 
                         let message_c = &mut self.common.runtime_info.message_c;
@@ -110,6 +122,9 @@ impl<'a> RequesterContext<'a> {
                         spdm_result_err!(EFAULT)
                     }
                 }
+                SpdmResponseResponseCode::SpdmResponseError => {
+                    self.handle_spdm_error_response(&mut reader)
+                }
                 _ => spdm_result_err!(EINVAL),
             },
             None => spdm_result_err!(EIO),
@@ -141,6 +156,7 @@ mod tests_requester {
             rsp_config_info,
             rsp_provision_info,
         );
+        select_capabilities(&mut responder.common);
 
         responder.common.reset_runtime_info();
         responder.common.provision_info.my_cert_chain = Some(SpdmCertChainData {
@@ -153,6 +169,9 @@ mod tests_requester {
             SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
         responder.common.runtime_info.need_measurement_summary_hash = true;
 
+        responder.common.runtime_info.connection_state =
+            crate::common::SpdmConnectionState::SpdmConnectionNegotiated;
+
         let pcidoe_transport_encap2 = &mut PciDoeTransportEncap {};
         let mut device_io_requester = FakeSpdmDeviceIo::new(&shared_buffer, &mut responder);
 
@@ -162,6 +181,7 @@ mod tests_requester {
             req_config_info,
             req_provision_info,
         );
+        select_capabilities(&mut requester.common);
         requester.common.reset_runtime_info();
 
         requester
@@ -2,19 +2,68 @@
 //
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
-use crate::common::{self, SpdmDeviceIo, SpdmTransportEncap};
+extern crate alloc;
+use alloc::boxed::Box;
+use core::future::Future;
+use core::pin::Pin;
+
+pub use crate::common::SpdmDelay;
+use crate::common::{
+    self, SpdmAppMessageType, SpdmAsyncDelay, SpdmAsyncDeviceIo, SpdmAsyncReceive, SpdmAsyncSend,
+    SpdmDeviceIo, SpdmTransportEncap,
+};
 use crate::config;
 use crate::error::SpdmResult;
 use crate::msgs::*;
+use codec::{Codec, Reader};
+
+/// How the requester reacts to a responder that can not answer now, and
+/// to device IO failures.
+///
+/// Other errors, e.g. InvalidRequest or DecryptError from the responder,
+/// fail the request at once.
+#[derive(Debug, Copy, Clone)]
+pub struct SpdmRetryPolicy {
+    /// Send a request answered with Busy again up to this many times.
+    pub busy_retry_count: u8,
+    /// Wait before the first Busy retry, doubled for each next one.
+    pub busy_backoff_us: u64,
+    /// The wait of the blocking API. The async API waits with
+    /// SpdmAsyncDeviceIo::poll_delay instead. Busy is not retried without
+    /// a way to wait.
+    pub delay: Option<SpdmDelay>,
+    /// On RequestResynch, run GET_VERSION, GET_CAPABILITIES and
+    /// NEGOTIATE_ALGORITHMS again, and tear down the sessions. The request
+    /// fails with the RequestResynch error either way, and is not sent
+    /// again.
+    pub resynch: bool,
+    /// Send a request again up to this many times if sending it fails. A
+    /// failed receive is not retried, as the responder may have handled the
+    /// request.
+    pub transport_retry_count: u8,
+}
+
+impl Default for SpdmRetryPolicy {
+    fn default() -> Self {
+        SpdmRetryPolicy {
+            busy_retry_count: 3,
+            busy_backoff_us: 1000,
+            delay: None,
+            resynch: true,
+            transport_retry_count: 0,
+        }
+    }
+}
 
 pub struct RequesterContext<'a> {
     pub common: common::SpdmContext<'a>,
+    pub retry_policy: SpdmRetryPolicy,
 }
 
 impl<'a> RequesterContext<'a> {
     pub fn new(
-        device_io: &'a mut dyn SpdmDeviceIo,
-        transport_encap: &'a mut dyn SpdmTransportEncap,
+        device_io: &'a mut (dyn SpdmDeviceIo + Send),
+        transport_encap: &'a mut (dyn SpdmTransportEncap + Send),
         config_info: common::SpdmConfigInfo,
         provision_info: common::SpdmProvisionInfo,
     ) -> Self {
@@ -25,19 +74,37 @@ impl<'a> RequesterContext<'a> {
                 config_info,
                 provision_info,
             ),
+            retry_policy: SpdmRetryPolicy::default(),
         }
     }
 
-    pub fn init_connection(&mut self) -> SpdmResult {
-        let result = self.send_receive_spdm_version();
-        if result.is_err() {
-            return result;
-        }
-        let result = self.send_receive_spdm_capability();
-        if result.is_err() {
-            return result;
+    /// A requester driven with the async API only.
+    pub fn new_without_device_io(
+        transport_encap: &'a mut (dyn SpdmTransportEncap + Send),
+        config_info: common::SpdmConfigInfo,
+        provision_info: common::SpdmProvisionInfo,
+    ) -> Self {
+        RequesterContext {
+            common: common::SpdmContext::new_without_device_io(
+                transport_encap,
+                config_info,
+                provision_info,
+            ),
+            retry_policy: SpdmRetryPolicy::default(),
         }
-        self.send_receive_spdm_algorithm()
+    }
+
+    pub fn init_connection(&mut self) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self.init_connection_with(&mut device_io))
+    }
+
+    pub(crate) async fn init_connection_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+    ) -> SpdmResult {
+        self.send_receive_spdm_version_with(device_io).await?;
+        self.send_receive_spdm_capability_with(device_io).await?;
+        self.send_receive_spdm_algorithm_with(device_io).await
     }
 
     pub fn start_session(
@@ -45,32 +112,40 @@ impl<'a> RequesterContext<'a> {
         use_psk: bool,
         slot_id: u8,
         measurement_summary_hash_type: SpdmMeasurementSummaryHashType,
+    ) -> SpdmResult<u32> {
+        block_on_device_io!(self, |device_io| self.start_session_with(
+            &mut device_io,
+            use_psk,
+            slot_id,
+            measurement_summary_hash_type
+        ))
+    }
+
+    pub(crate) async fn start_session_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        use_psk: bool,
+        slot_id: u8,
+        measurement_summary_hash_type: SpdmMeasurementSummaryHashType,
     ) -> SpdmResult<u32> {
         if !use_psk {
-            let result =
-                self.send_receive_spdm_key_exchange(slot_id, measurement_summary_hash_type);
-            if let Ok(session_id) = result {
-                let result = self.send_receive_spdm_finish(session_id);
-                if result.is_ok() {
-                    Ok(session_id)
-                } else {
-                    spdm_result_err!(EIO)
-                }
-            } else {
-                spdm_result_err!(EIO)
-            }
+            let session_id = self
+                .send_receive_spdm_key_exchange_with(
+                    device_io,
+                    slot_id,
+                    measurement_summary_hash_type,
+                )
+                .await?;
+            self.send_receive_spdm_finish_with(device_io, session_id)
+                .await?;
+            Ok(session_id)
         } else {
-            let result = self.send_receive_spdm_psk_exchange(measurement_summary_hash_type);
-            if let Ok(session_id) = result {
-                let result = self.send_receive_spdm_psk_finish(session_id);
-                if result.is_ok() {
-                    Ok(session_id)
-                } else {
-                    spdm_result_err!(EIO)
-                }
-            } else {
-                spdm_result_err!(EIO)
-            }
+            let session_id = self
+                .send_receive_spdm_psk_exchange_with(device_io, measurement_summary_hash_type)
+                .await?;
+            self.send_receive_spdm_psk_finish_with(device_io, session_id)
+                .await?;
+            Ok(session_id)
         }
     }
 
@@ -79,13 +154,50 @@ impl<'a> RequesterContext<'a> {
         Ok(())
     }
 
+    /// Refuse a request the negotiated capabilities do not allow, before
+    /// anything is sent.
+    pub fn check_request_supported(
+        &self,
+        request_response_code: SpdmResponseResponseCode,
+    ) -> SpdmResult {
+        if self.common.is_request_supported(request_response_code) {
+            Ok(())
+        } else {
+            error!("!!! unsupported request : {:?}\n", request_response_code);
+            spdm_result_err!(ENOSYS)
+        }
+    }
+
     pub fn send_message(&mut self, send_buffer: &[u8]) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self
+            .send_message_with(&mut device_io, send_buffer))
+    }
+
+    pub(crate) async fn send_message_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        send_buffer: &[u8],
+    ) -> SpdmResult {
         let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let used = self.common.encap(send_buffer, &mut transport_buffer)?;
-        self.common.device_io.send(&transport_buffer[..used])
+        self.send_transport_message_with(device_io, &transport_buffer[..used])
+            .await
     }
 
     pub fn send_secured_message(&mut self, session_id: u32, send_buffer: &[u8]) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self.send_secured_message_with(
+            &mut device_io,
+            session_id,
+            send_buffer
+        ))
+    }
+
+    pub(crate) async fn send_secured_message_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        session_id: u32,
+        send_buffer: &[u8],
+    ) -> SpdmResult {
         let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let used = self.common.encode_secured_message(
             session_id,
@@ -93,17 +205,175 @@ impl<'a> RequesterContext<'a> {
             &mut transport_buffer,
             true,
         )?;
-        self.common.device_io.send(&transport_buffer[..used])
+        self.send_transport_message_with(device_io, &transport_buffer[..used])
+            .await
+    }
+
+    // A failed send is retried with the same transport message, which for
+    // a secured message keeps its sequence number.
+    async fn send_transport_message_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        transport_buffer: &[u8],
+    ) -> SpdmResult {
+        let mut transport_retry = 0u8;
+        loop {
+            match SpdmAsyncSend::new(device_io, transport_buffer).await {
+                Err(_) if transport_retry < self.retry_policy.transport_retry_count => {
+                    transport_retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Send a request and receive its response, retrying as the retry
+    /// policy says.
+    pub fn send_receive_message(
+        &mut self,
+        send_buffer: &[u8],
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        block_on_device_io!(self, |device_io| self.send_receive_message_with(
+            &mut device_io,
+            send_buffer,
+            receive_buffer
+        ))
+    }
+
+    pub(crate) async fn send_receive_message_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        send_buffer: &[u8],
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        let mut busy_retry = 0u8;
+        loop {
+            self.send_message_with(device_io, send_buffer).await?;
+            let used = self.receive_message_with(device_io, receive_buffer).await?;
+            if self
+                .retry_busy(
+                    device_io,
+                    &receive_buffer[..used],
+                    &mut busy_retry,
+                    send_buffer,
+                )
+                .await?
+            {
+                continue;
+            }
+            return Ok(used);
+        }
+    }
+
+    /// send_receive_message in a session.
+    pub fn send_receive_secured_message(
+        &mut self,
+        session_id: u32,
+        send_buffer: &[u8],
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        block_on_device_io!(self, |device_io| self.send_receive_secured_message_with(
+            &mut device_io,
+            session_id,
+            send_buffer,
+            receive_buffer
+        ))
+    }
+
+    pub(crate) async fn send_receive_secured_message_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        session_id: u32,
+        send_buffer: &[u8],
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        let mut busy_retry = 0u8;
+        loop {
+            self.send_secured_message_with(device_io, session_id, send_buffer)
+                .await?;
+            let used = self
+                .receive_secured_message_with(device_io, session_id, receive_buffer)
+                .await?;
+            if self
+                .retry_busy(
+                    device_io,
+                    &receive_buffer[..used],
+                    &mut busy_retry,
+                    send_buffer,
+                )
+                .await?
+            {
+                continue;
+            }
+            return Ok(used);
+        }
+    }
+
+    // Whether to send the request again after a Busy response. A
+    // RequestResynch response fails the request, after the resynch.
+    async fn retry_busy<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        response: &[u8],
+        busy_retry: &mut u8,
+        send_buffer: &[u8],
+    ) -> SpdmResult<bool> {
+        match read_error_code(response) {
+            Some(SpdmErrorCode::SpdmErrorBusy)
+                if *busy_retry < self.retry_policy.busy_retry_count =>
+            {
+                let us = self
+                    .retry_policy
+                    .busy_backoff_us
+                    .checked_shl(*busy_retry as u32)
+                    .unwrap_or(u64::MAX);
+                if !SpdmAsyncDelay::new(device_io, us).await {
+                    return Ok(false);
+                }
+                *busy_retry += 1;
+                Ok(true)
+            }
+            Some(SpdmErrorCode::SpdmErrorRequestResynch) => {
+                if self.retry_policy.resynch && !is_vca_request(send_buffer) {
+                    self.resynch_with(device_io).await?;
+                }
+                let mut reader = Reader::init(response);
+                SpdmMessageHeader::read(&mut reader);
+                self.handle_spdm_error_response(&mut reader)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    // The VCA flows send their requests with send_receive_message_with,
+    // which resynchs, so the future is boxed.
+    fn resynch_with<'b, D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &'b mut self,
+        device_io: &'b mut D,
+    ) -> Pin<Box<dyn Future<Output = SpdmResult> + Send + 'b>> {
+        Box::pin(async move {
+            info!("resynch!\n");
+            self.common.teardown_sessions();
+            self.init_connection_with(device_io).await
+        })
     }
 
     pub fn receive_message(&mut self, receive_buffer: &mut [u8]) -> SpdmResult<usize> {
+        block_on_device_io!(self, |device_io| self
+            .receive_message_with(&mut device_io, receive_buffer))
+    }
+
+    pub(crate) async fn receive_message_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
         info!("receive_message!\n");
 
         let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let used = self
-            .common
-            .device_io
-            .receive(&mut transport_buffer)
+        let used = SpdmAsyncReceive::new(device_io, &mut transport_buffer)
+            .await
             .map_err(|_| spdm_err!(EIO))?;
 
         self.common.decap(&transport_buffer[..used], receive_buffer)
@@ -113,29 +383,169 @@ impl<'a> RequesterContext<'a> {
         &mut self,
         session_id: u32,
         receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        block_on_device_io!(self, |device_io| self.receive_secured_message_with(
+            &mut device_io,
+            session_id,
+            receive_buffer
+        ))
+    }
+
+    pub(crate) async fn receive_secured_message_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        session_id: u32,
+        receive_buffer: &mut [u8],
     ) -> SpdmResult<usize> {
         info!("receive_secured_message!\n");
 
         let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-
-        let used = self
-            .common
-            .device_io
-            .receive(&mut transport_buffer)
+        let used = SpdmAsyncReceive::new(device_io, &mut transport_buffer)
+            .await
             .map_err(|_| spdm_err!(EIO))?;
 
         self.common
             .decode_secured_message(session_id, &transport_buffer[..used], receive_buffer)
     }
+
+    /// Send a non-SPDM application message, e.g. PLDM over MCTP, in an
+    /// established session.
+    pub fn send_secured_app_message(
+        &mut self,
+        session_id: u32,
+        message_type: u8,
+        send_buffer: &[u8],
+    ) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self.send_secured_app_message_with(
+            &mut device_io,
+            session_id,
+            message_type,
+            send_buffer
+        ))
+    }
+
+    pub(crate) async fn send_secured_app_message_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        session_id: u32,
+        message_type: u8,
+        send_buffer: &[u8],
+    ) -> SpdmResult {
+        let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let used = self.common.encode_secured_app_message(
+            session_id,
+            message_type,
+            send_buffer,
+            &mut transport_buffer,
+            true,
+        )?;
+        self.send_transport_message_with(device_io, &transport_buffer[..used])
+            .await
+    }
+
+    pub fn receive_secured_app_message(
+        &mut self,
+        session_id: u32,
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<(usize, SpdmAppMessageType)> {
+        block_on_device_io!(self, |device_io| self.receive_secured_app_message_with(
+            &mut device_io,
+            session_id,
+            receive_buffer
+        ))
+    }
+
+    pub(crate) async fn receive_secured_app_message_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        session_id: u32,
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<(usize, SpdmAppMessageType)> {
+        info!("receive_secured_app_message!\n");
+
+        let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let used = SpdmAsyncReceive::new(device_io, &mut transport_buffer)
+            .await
+            .map_err(|_| spdm_err!(EIO))?;
+
+        self.common.decode_secured_app_message(
+            session_id,
+            &transport_buffer[..used],
+            receive_buffer,
+        )
+    }
+
+    /// Send a non-SPDM application message and receive the response of the
+    /// same message type.
+    pub fn send_receive_secured_app_message(
+        &mut self,
+        session_id: u32,
+        message_type: u8,
+        send_buffer: &[u8],
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        block_on_device_io!(self, |device_io| self
+            .send_receive_secured_app_message_with(
+                &mut device_io,
+                session_id,
+                message_type,
+                send_buffer,
+                receive_buffer
+            ))
+    }
+
+    pub(crate) async fn send_receive_secured_app_message_with<
+        D: SpdmAsyncDeviceIo + Send + ?Sized,
+    >(
+        &mut self,
+        device_io: &mut D,
+        session_id: u32,
+        message_type: u8,
+        send_buffer: &[u8],
+        receive_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        self.send_secured_app_message_with(device_io, session_id, message_type, send_buffer)
+            .await?;
+        let (used, response_type) = self
+            .receive_secured_app_message_with(device_io, session_id, receive_buffer)
+            .await?;
+        if response_type != SpdmAppMessageType::Other(message_type) {
+            return spdm_result_err!(EIO);
+        }
+        Ok(used)
+    }
+}
+
+// the error code of an ERROR response
+fn read_error_code(receive_buffer: &[u8]) -> Option<SpdmErrorCode> {
+    let mut reader = Reader::init(receive_buffer);
+    let message_header = SpdmMessageHeader::read(&mut reader)?;
+    if message_header.request_response_code != SpdmResponseResponseCode::SpdmResponseError {
+        return None;
+    }
+    SpdmErrorCode::read(&mut reader)
+}
+
+fn is_vca_request(send_buffer: &[u8]) -> bool {
+    let mut reader = Reader::init(send_buffer);
+    matches!(
+        SpdmMessageHeader::read(&mut reader).map(|header| header.request_response_code),
+        Some(SpdmResponseResponseCode::SpdmRequestGetVersion)
+            | Some(SpdmResponseResponseCode::SpdmRequestGetCapabilities)
+            | Some(SpdmResponseResponseCode::SpdmRequestNegotiateAlgorithms)
+    )
 }
 
 #[cfg(test)]
 mod tests_requester {
     use super::*;
-    use crate::session::SpdmSession;
+    use crate::error::SpdmErrorNum;
+    use crate::session::SpdmSessionTable;
     use crate::testlib::*;
     use crate::{crypto, responder};
     use codec::Writer;
+    use core::task::{Context, Poll};
+    use std::sync::atomic::{AtomicU64, Ordering};
 
     #[test]
     fn test_case0_start_session() {
@@ -217,7 +627,7 @@ mod tests_requester {
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         let rsp_session_id = 0xffu16;
         let session_id = (0xffu32 << 16) + rsp_session_id as u32;
-        responder.common.session = [SpdmSession::new(); 4];
+        responder.common.session = SpdmSessionTable::new();
         responder.common.session[0].setup(session_id).unwrap();
         responder.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -241,7 +651,7 @@ mod tests_requester {
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         let rsp_session_id = 0xffu16;
         let session_id = (0xffu32 << 16) + rsp_session_id as u32;
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(session_id).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -278,4 +688,361 @@ mod tests_requester {
             .is_ok();
         assert!(status);
     }
+
+    static DELAY_COUNT: AtomicU64 = AtomicU64::new(0);
+    static DELAY_TOTAL_US: AtomicU64 = AtomicU64::new(0);
+
+    fn record_delay(us: u64) {
+        DELAY_COUNT.fetch_add(1, Ordering::SeqCst);
+        DELAY_TOTAL_US.fetch_add(us, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_case0_retry_policy() {
+        let (rsp_config_info, rsp_provision_info) = create_info();
+        let (req_config_info, req_provision_info) = create_info();
+
+        let shared_buffer = SharedBuffer::new();
+        let mut device_io_responder = FakeSpdmDeviceIoReceve::new(&shared_buffer);
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+
+        crypto::asym_sign::register(ASYM_SIGN_IMPL);
+
+        let mut responder = responder::ResponderContext::new(
+            &mut device_io_responder,
+            pcidoe_transport_encap,
+            rsp_config_info,
+            rsp_provision_info,
+        );
+
+        let faults = FakeFaults::default();
+        let pcidoe_transport_encap2 = &mut PciDoeTransportEncap {};
+        let mut device_io_requester =
+            FakeFaultyDeviceIo::new(&shared_buffer, &mut responder, &faults);
+
+        let mut requester = RequesterContext::new(
+            &mut device_io_requester,
+            pcidoe_transport_encap2,
+            req_config_info,
+            req_provision_info,
+        );
+        requester.retry_policy.delay = Some(record_delay);
+        assert!(requester.init_connection().is_ok());
+
+        // Busy is retried with backoff
+        faults.faults.lock().unwrap().extend(
+            [
+                FakeFault::Error(SpdmErrorCode::SpdmErrorBusy),
+                FakeFault::Error(SpdmErrorCode::SpdmErrorBusy),
+            ]
+            .iter(),
+        );
+        faults.request_codes.lock().unwrap().clear();
+        assert!(requester.send_receive_spdm_digest().is_ok());
+        assert_eq!(*faults.request_codes.lock().unwrap(), [0x81, 0x81, 0x81]);
+        // 1000 and 2000
+        assert_eq!(DELAY_COUNT.load(Ordering::SeqCst), 2);
+        assert_eq!(DELAY_TOTAL_US.load(Ordering::SeqCst), 3000);
+
+        // until the retries run out
+        requester.retry_policy.busy_retry_count = 1;
+        faults.faults.lock().unwrap().extend(
+            [
+                FakeFault::Error(SpdmErrorCode::SpdmErrorBusy),
+                FakeFault::Error(SpdmErrorCode::SpdmErrorBusy),
+            ]
+            .iter(),
+        );
+        let error = requester.send_receive_spdm_digest().unwrap_err();
+        assert_eq!(
+            error.peer_error().unwrap().error_code,
+            SpdmErrorCode::SpdmErrorBusy
+        );
+
+        // other errors are not retried
+        faults
+            .faults
+            .lock()
+            .unwrap()
+            .push_back(FakeFault::Error(SpdmErrorCode::SpdmErrorInvalidRequest));
+        faults.request_codes.lock().unwrap().clear();
+        let error = requester.send_receive_spdm_digest().unwrap_err();
+        assert_eq!(
+            error.peer_error().unwrap().error_code,
+            SpdmErrorCode::SpdmErrorInvalidRequest
+        );
+        assert_eq!(*faults.request_codes.lock().unwrap(), [0x81]);
+
+        // without a delay Busy is not retried
+        requester.retry_policy.delay = None;
+        requester.retry_policy.busy_retry_count = 3;
+        faults
+            .faults
+            .lock()
+            .unwrap()
+            .push_back(FakeFault::Error(SpdmErrorCode::SpdmErrorBusy));
+        faults.request_codes.lock().unwrap().clear();
+        let error = requester.send_receive_spdm_digest().unwrap_err();
+        assert_eq!(
+            error.peer_error().unwrap().error_code,
+            SpdmErrorCode::SpdmErrorBusy
+        );
+        assert_eq!(*faults.request_codes.lock().unwrap(), [0x81]);
+    }
+
+    #[test]
+    fn test_case1_retry_policy() {
+        let (rsp_config_info, rsp_provision_info) = create_info();
+        let (req_config_info, req_provision_info) = create_info();
+
+        let shared_buffer = SharedBuffer::new();
+        let mut device_io_responder = FakeSpdmDeviceIoReceve::new(&shared_buffer);
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+
+        crypto::asym_sign::register(ASYM_SIGN_IMPL);
+
+        let mut responder = responder::ResponderContext::new(
+            &mut device_io_responder,
+            pcidoe_transport_encap,
+            rsp_config_info,
+            rsp_provision_info,
+        );
+
+        let faults = FakeFaults::default();
+        let pcidoe_transport_encap2 = &mut PciDoeTransportEncap {};
+        let mut device_io_requester =
+            FakeFaultyDeviceIo::new(&shared_buffer, &mut responder, &faults);
+
+        let mut requester = RequesterContext::new(
+            &mut device_io_requester,
+            pcidoe_transport_encap2,
+            req_config_info,
+            req_provision_info,
+        );
+        assert!(requester.init_connection().is_ok());
+
+        // RequestResynch runs VCA again, and the request fails
+        faults
+            .faults
+            .lock()
+            .unwrap()
+            .push_back(FakeFault::Error(SpdmErrorCode::SpdmErrorRequestResynch));
+        faults.request_codes.lock().unwrap().clear();
+        let error = requester.send_receive_spdm_digest().unwrap_err();
+        assert_eq!(
+            error.peer_error().unwrap().error_code,
+            SpdmErrorCode::SpdmErrorRequestResynch
+        );
+        assert_eq!(
+            *faults.request_codes.lock().unwrap(),
+            [0x81, 0x84, 0xE1, 0xE3]
+        );
+        assert!(requester.send_receive_spdm_digest().is_ok());
+
+        requester.retry_policy.resynch = false;
+        faults
+            .faults
+            .lock()
+            .unwrap()
+            .push_back(FakeFault::Error(SpdmErrorCode::SpdmErrorRequestResynch));
+        assert!(requester.send_receive_spdm_digest().is_err());
+
+        // device IO failures
+        faults
+            .faults
+            .lock()
+            .unwrap()
+            .push_back(FakeFault::SendFailure);
+        let error = requester.send_receive_spdm_digest().unwrap_err();
+        assert!(error.peer_error().is_none());
+
+        requester.retry_policy.transport_retry_count = 1;
+        faults
+            .faults
+            .lock()
+            .unwrap()
+            .push_back(FakeFault::SendFailure);
+        assert!(requester.send_receive_spdm_digest().is_ok());
+
+        // a request without response is not sent again
+        faults
+            .faults
+            .lock()
+            .unwrap()
+            .push_back(FakeFault::NoResponse);
+        faults.request_codes.lock().unwrap().clear();
+        assert!(requester.send_receive_spdm_digest().is_err());
+        assert_eq!(*faults.request_codes.lock().unwrap(), [0x81]);
+    }
+
+    #[test]
+    fn test_case2_retry_policy() {
+        let (rsp_config_info, rsp_provision_info) = create_info();
+        let (req_config_info, req_provision_info) = create_info();
+
+        let shared_buffer = SharedBuffer::new();
+        let mut device_io_responder = FakeSpdmDeviceIoReceve::new(&shared_buffer);
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+
+        crypto::asym_sign::register(ASYM_SIGN_IMPL);
+
+        let mut responder = responder::ResponderContext::new(
+            &mut device_io_responder,
+            pcidoe_transport_encap,
+            rsp_config_info,
+            rsp_provision_info,
+        );
+
+        let faults = FakeFaults::default();
+        let pcidoe_transport_encap2 = &mut PciDoeTransportEncap {};
+        let mut device_io_requester =
+            FakeFaultyDeviceIo::new(&shared_buffer, &mut responder, &faults);
+        let mut device_io = FakeTimerDeviceIo {
+            device_io: common::SpdmBlockingDeviceIo {
+                device_io: &mut device_io_requester,
+                delay: None,
+            },
+            timer: false,
+            pending: false,
+            waits: Vec::new(),
+        };
+
+        // the flows of the async API follow the retry policy too
+        let mut requester = RequesterContext::new_without_device_io(
+            pcidoe_transport_encap2,
+            req_config_info,
+            req_provision_info,
+        );
+        assert!(common::block_on(requester.init_connection_with(&mut device_io)).is_ok());
+
+        // Busy is not retried without a timer
+        faults
+            .faults
+            .lock()
+            .unwrap()
+            .push_back(FakeFault::Error(SpdmErrorCode::SpdmErrorBusy));
+        faults.request_codes.lock().unwrap().clear();
+        let error =
+            common::block_on(requester.send_receive_spdm_digest_with(&mut device_io)).unwrap_err();
+        assert_eq!(
+            error.peer_error().unwrap().error_code,
+            SpdmErrorCode::SpdmErrorBusy
+        );
+        assert_eq!(*faults.request_codes.lock().unwrap(), [0x81]);
+
+        // and is retried once the timer completes
+        device_io.timer = true;
+        faults.faults.lock().unwrap().extend(
+            [
+                FakeFault::Error(SpdmErrorCode::SpdmErrorBusy),
+                FakeFault::SendFailure,
+            ]
+            .iter(),
+        );
+        faults.request_codes.lock().unwrap().clear();
+        requester.retry_policy.transport_retry_count = 1;
+        assert!(common::block_on(requester.send_receive_spdm_digest_with(&mut device_io)).is_ok());
+        assert_eq!(*faults.request_codes.lock().unwrap(), [0x81, 0x81, 0x81]);
+        assert_eq!(device_io.waits, [1000]);
+    }
+
+    // An async device IO whose timer is pending on its first poll.
+    struct FakeTimerDeviceIo<'a, 'b> {
+        device_io: common::SpdmBlockingDeviceIo<'a, 'b>,
+        timer: bool,
+        pending: bool,
+        waits: Vec<u64>,
+    }
+
+    impl SpdmAsyncDeviceIo for FakeTimerDeviceIo<'_, '_> {
+        fn poll_send(&mut self, cx: &mut Context<'_>, buffer: &[u8]) -> Poll<SpdmResult> {
+            self.device_io.poll_send(cx, buffer)
+        }
+
+        fn poll_receive(
+            &mut self,
+            cx: &mut Context<'_>,
+            buffer: &mut [u8],
+        ) -> Poll<Result<usize, usize>> {
+            self.device_io.poll_receive(cx, buffer)
+        }
+
+        fn poll_delay(&mut self, cx: &mut Context<'_>, us: u64) -> Poll<bool> {
+            if !self.timer {
+                return Poll::Ready(false);
+            }
+            if !self.pending {
+                self.pending = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.pending = false;
+            self.waits.push(us);
+            Poll::Ready(true)
+        }
+    }
+    #[test]
+    fn test_case0_check_request_supported() {
+        let (mut rsp_config_info, rsp_provision_info) = create_info();
+        let (req_config_info, req_provision_info) = create_info();
+        rsp_config_info.rsp_capabilities -= SpdmResponseCapabilityFlags::CHAL_CAP;
+
+        let shared_buffer = SharedBuffer::new();
+        let mut device_io_responder = FakeSpdmDeviceIoReceve::new(&shared_buffer);
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+
+        crypto::asym_sign::register(ASYM_SIGN_IMPL);
+
+        let mut responder = responder::ResponderContext::new(
+            &mut device_io_responder,
+            pcidoe_transport_encap,
+            rsp_config_info,
+            rsp_provision_info,
+        );
+
+        let faults = FakeFaults::default();
+        let pcidoe_transport_encap2 = &mut PciDoeTransportEncap {};
+        let mut device_io_requester =
+            FakeFaultyDeviceIo::new(&shared_buffer, &mut responder, &faults);
+
+        let mut requester = RequesterContext::new(
+            &mut device_io_requester,
+            pcidoe_transport_encap2,
+            req_config_info,
+            req_provision_info,
+        );
+        assert!(requester.init_connection().is_ok());
+        assert!(requester.send_receive_spdm_digest().is_ok());
+        assert!(requester.send_receive_spdm_certificate(0).is_ok());
+
+        // the responder did not advertise CHAL_CAP, nothing is sent
+        faults.request_codes.lock().unwrap().clear();
+        let error = requester
+            .send_receive_spdm_challenge(
+                0,
+                SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+            )
+            .unwrap_err();
+        assert_eq!(error.num(), SpdmErrorNum::ENOSYS);
+        assert!(faults.request_codes.lock().unwrap().is_empty());
+
+        // the responder refuses it as well
+        requester.common.negotiate_info.rsp_capabilities_sel |=
+            SpdmResponseCapabilityFlags::CHAL_CAP;
+        let error = requester
+            .send_receive_spdm_challenge(
+                0,
+                SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+            )
+            .unwrap_err();
+        let peer_error = error.peer_error().unwrap();
+        assert_eq!(
+            peer_error.error_code,
+            SpdmErrorCode::SpdmErrorUnsupportedRequest
+        );
+        assert_eq!(
+            peer_error.error_data,
+            SpdmResponseResponseCode::SpdmRequestChallenge.get_u8()
+        );
+    }
 }
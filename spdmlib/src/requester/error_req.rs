@@ -0,0 +1,75 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::error::SpdmResult;
+use crate::requester::*;
+
+impl<'a> RequesterContext<'a> {
+    /// Fail with the ERROR response of the responder, whose header has
+    /// been read from `reader`.
+    pub fn handle_spdm_error_response<T>(&mut self, reader: &mut Reader) -> SpdmResult<T> {
+        match SpdmErrorResponsePayload::spdm_read(&mut self.common, reader) {
+            Some(error_response) => {
+                error!("!!! error response : {:02x?}\n", error_response);
+                Err(spdm_peer_err!(error_response))
+            }
+            None => spdm_result_err!(EIO),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_requester {
+    use super::*;
+    use crate::error::SpdmErrorNum;
+    use crate::testlib::*;
+
+    #[test]
+    fn test_case0_handle_spdm_error_response() {
+        let (req_config_info, req_provision_info) = create_info();
+        let mut device_io_requester = MySpdmDeviceIo;
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+        let mut requester = RequesterContext::new(
+            &mut device_io_requester,
+            pcidoe_transport_encap,
+            req_config_info,
+            req_provision_info,
+        );
+
+        // Busy
+        let error = requester
+            .handle_spdm_version_response(&[], &[0x10, 0x7F, 0x03, 0x00])
+            .unwrap_err();
+        assert_eq!(error.num(), SpdmErrorNum::EPROTO);
+        let peer_error = error.peer_error().unwrap();
+        assert_eq!(peer_error.error_code, SpdmErrorCode::SpdmErrorBusy);
+        assert_eq!(peer_error.error_data, 0);
+
+        // ResponseNotReady, with its extended data
+        let error = requester
+            .handle_spdm_digest_response(&[], &[0x11, 0x7F, 0x42, 0x00, 0x05, 0x81, 0x01, 0x02])
+            .unwrap_err();
+        let peer_error = error.peer_error().unwrap();
+        assert_eq!(
+            peer_error.error_code,
+            SpdmErrorCode::SpdmErrorResponseNotReady
+        );
+        assert_eq!(
+            peer_error.extended_data,
+            SpdmErrorResponseExtData::SpdmErrorExtDataNotReady(SpdmErrorResponseNotReadyExtData {
+                rdt_exponent: 0x05,
+                request_code: 0x81,
+                token: 0x01,
+                tdtm: 0x02,
+            })
+        );
+
+        // truncated, and not an error of the peer
+        let error = requester
+            .handle_spdm_digest_response(&[], &[0x11, 0x7F, 0x42, 0x00, 0x05])
+            .unwrap_err();
+        assert_eq!(error.num(), SpdmErrorNum::EIO);
+        assert!(error.peer_error().is_none());
+    }
+}
@@ -7,12 +7,20 @@ use crate::requester::*;
 
 impl<'a> RequesterContext<'a> {
     pub fn send_receive_spdm_capability(&mut self) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self
+            .send_receive_spdm_capability_with(&mut device_io))
+    }
+
+    pub(crate) async fn send_receive_spdm_capability_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+    ) -> SpdmResult {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let send_used = self.encode_spdm_capability(&mut send_buffer);
-        self.send_message(&send_buffer[..send_used])?;
-
         let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let used = self.receive_message(&mut receive_buffer)?;
+        let used = self
+            .send_receive_message_with(device_io, &send_buffer[..send_used], &mut receive_buffer)
+            .await?;
         self.handle_spdm_capability_response(&send_buffer[..send_used], &receive_buffer[..used])
     }
 
@@ -67,6 +75,9 @@ impl<'a> RequesterContext<'a> {
                         spdm_result_err!(EFAULT)
                     }
                 }
+                SpdmResponseResponseCode::SpdmResponseError => {
+                    self.handle_spdm_error_response(&mut reader)
+                }
                 _ => spdm_result_err!(EINVAL),
             },
             None => spdm_result_err!(EIO),
@@ -98,6 +109,9 @@ mod tests_requester {
             rsp_provision_info,
         );
 
+        responder.common.runtime_info.connection_state =
+            crate::common::SpdmConnectionState::SpdmConnectionAfterVersion;
+
         let pcidoe_transport_encap2 = &mut PciDoeTransportEncap {};
         let mut device_io_requester = FakeSpdmDeviceIo::new(&shared_buffer, &mut responder);
 
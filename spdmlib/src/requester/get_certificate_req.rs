@@ -7,8 +7,9 @@ use crate::error::SpdmResult;
 use crate::requester::*;
 
 impl<'a> RequesterContext<'a> {
-    fn send_receive_spdm_certificate_partial(
+    async fn send_receive_spdm_certificate_partial_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
         &mut self,
+        device_io: &mut D,
         slot_id: u8,
         offset: u16,
         length: u16,
@@ -17,10 +18,10 @@ impl<'a> RequesterContext<'a> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let send_used =
             self.encode_spdm_certificate_partial(slot_id, offset, length, &mut send_buffer);
-        self.send_message(&send_buffer[..send_used])?;
-
         let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let used = self.receive_message(&mut receive_buffer)?;
+        let used = self
+            .send_receive_message_with(device_io, &send_buffer[..send_used], &mut receive_buffer)
+            .await?;
         self.handle_spdm_certificate_partial_response(
             offset,
             &send_buffer[..send_used],
@@ -97,6 +98,9 @@ impl<'a> RequesterContext<'a> {
                         spdm_result_err!(EFAULT)
                     }
                 }
+                SpdmResponseResponseCode::SpdmResponseError => {
+                    self.handle_spdm_error_response(&mut reader)
+                }
                 _ => spdm_result_err!(EINVAL),
             },
             None => spdm_result_err!(EIO),
@@ -104,10 +108,22 @@ impl<'a> RequesterContext<'a> {
     }
 
     pub fn send_receive_spdm_certificate(&mut self, slot_id: u8) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self
+            .send_receive_spdm_certificate_with(&mut device_io, slot_id))
+    }
+
+    pub(crate) async fn send_receive_spdm_certificate_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        slot_id: u8,
+    ) -> SpdmResult {
+        self.check_request_supported(SpdmResponseResponseCode::SpdmRequestGetCertificate)?;
         let mut offset = 0u16;
         let mut length = config::MAX_SPDM_CERT_PORTION_LEN as u16;
         while length != 0 {
-            let result = self.send_receive_spdm_certificate_partial(slot_id, offset, length);
+            let result = self
+                .send_receive_spdm_certificate_partial_with(device_io, slot_id, offset, length)
+                .await;
             match result {
                 Ok((portion_length, remainder_length)) => {
                     offset += portion_length;
@@ -116,7 +132,7 @@ impl<'a> RequesterContext<'a> {
                         length = config::MAX_SPDM_CERT_PORTION_LEN as u16;
                     }
                 }
-                Err(_) => return spdm_result_err!(EIO),
+                Err(e) => return Err(e),
             }
         }
         self.verify_spdm_certificate_chain()
@@ -129,18 +145,18 @@ impl<'a> RequesterContext<'a> {
             // TBD: Verify cert chain
             //
             if self.common.peer_info.peer_cert_chain.cert_chain.data_size
-                <= (4 + self.common.negotiate_info.base_hash_sel.get_size())
+                <= (4 + self.common.get_hash_size())
             {
                 return spdm_result_err!(EIO);
             }
 
             let data_size = self.common.peer_info.peer_cert_chain.cert_chain.data_size
                 - 4
-                - self.common.negotiate_info.base_hash_sel.get_size();
+                - self.common.get_hash_size();
             let mut data = [0u8; config::MAX_SPDM_CERT_CHAIN_DATA_SIZE];
             data[0..(data_size as usize)].copy_from_slice(
                 &self.common.peer_info.peer_cert_chain.cert_chain.data[(4usize
-                    + self.common.negotiate_info.base_hash_sel.get_size() as usize)
+                    + self.common.get_hash_size() as usize)
                     ..(self.common.peer_info.peer_cert_chain.cert_chain.data_size as usize)],
             );
             let runtime_peer_cert_chain_data = SpdmCertChainData { data_size, data };
@@ -152,12 +168,10 @@ impl<'a> RequesterContext<'a> {
                     0,
                 )?;
             let root_cert = &runtime_peer_cert_chain_data.data[root_cert_begin..root_cert_end];
-            let root_hash =
-                crypto::hash::hash_all(self.common.negotiate_info.base_hash_sel, root_cert)
-                    .unwrap();
+            let root_hash = self.common.hash_all(root_cert).unwrap();
             if root_hash.data[..(root_hash.data_size as usize)]
-                != self.common.peer_info.peer_cert_chain.cert_chain.data[4usize
-                    ..(4usize + self.common.negotiate_info.base_hash_sel.get_size() as usize)]
+                != self.common.peer_info.peer_cert_chain.cert_chain.data
+                    [4usize..(4usize + self.common.get_hash_size() as usize)]
             {
                 error!("root_hash - fail!\n");
                 return spdm_result_err!(EINVAL);
@@ -219,6 +233,7 @@ mod tests_requester {
             rsp_config_info,
             rsp_provision_info,
         );
+        select_capabilities(&mut responder.common);
 
         responder.common.reset_runtime_info();
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
@@ -226,6 +241,9 @@ mod tests_requester {
             SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
         responder.common.provision_info.my_cert_chain = Some(REQ_CERT_CHAIN_DATA);
 
+        responder.common.runtime_info.connection_state =
+            crate::common::SpdmConnectionState::SpdmConnectionNegotiated;
+
         let pcidoe_transport_encap2 = &mut PciDoeTransportEncap {};
         let mut device_io_requester = FakeSpdmDeviceIo::new(&shared_buffer, &mut responder);
 
@@ -235,6 +253,7 @@ mod tests_requester {
             req_config_info,
             req_provision_info,
         );
+        select_capabilities(&mut requester.common);
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         requester.common.negotiate_info.base_asym_sel =
@@ -7,13 +7,22 @@ use crate::requester::*;
 
 impl<'a> RequesterContext<'a> {
     pub fn send_receive_spdm_digest(&mut self) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self
+            .send_receive_spdm_digest_with(&mut device_io))
+    }
+
+    pub(crate) async fn send_receive_spdm_digest_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+    ) -> SpdmResult {
+        self.check_request_supported(SpdmResponseResponseCode::SpdmRequestGetDigests)?;
         info!("send spdm digest\n");
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let send_used = self.encode_spdm_digest(&mut send_buffer);
-        self.send_message(&send_buffer[..send_used])?;
-
         let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let used = self.receive_message(&mut receive_buffer)?;
+        let used = self
+            .send_receive_message_with(device_io, &send_buffer[..send_used], &mut receive_buffer)
+            .await?;
         self.handle_spdm_digest_response(&send_buffer[..send_used], &receive_buffer[..used])
     }
 
@@ -57,6 +66,9 @@ impl<'a> RequesterContext<'a> {
                         spdm_result_err!(EFAULT)
                     }
                 }
+                SpdmResponseResponseCode::SpdmResponseError => {
+                    self.handle_spdm_error_response(&mut reader)
+                }
                 _ => spdm_result_err!(EINVAL),
             },
             None => spdm_result_err!(EIO),
@@ -87,12 +99,16 @@ mod tests_requester {
             rsp_config_info,
             rsp_provision_info,
         );
+        select_capabilities(&mut responder.common);
         responder.common.provision_info.my_cert_chain = Some(SpdmCertChainData {
             data_size: 512u16,
             data: [0u8; config::MAX_SPDM_CERT_CHAIN_DATA_SIZE],
         });
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
 
+        responder.common.runtime_info.connection_state =
+            crate::common::SpdmConnectionState::SpdmConnectionNegotiated;
+
         let pcidoe_transport_encap2 = &mut PciDoeTransportEncap {};
         let mut device_io_requester = FakeSpdmDeviceIo::new(&shared_buffer, &mut responder);
 
@@ -102,6 +118,7 @@ mod tests_requester {
             req_config_info,
             req_provision_info,
         );
+        select_capabilities(&mut requester.common);
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
 
         let status = requester.send_receive_spdm_digest().is_ok();
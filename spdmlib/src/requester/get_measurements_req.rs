@@ -7,12 +7,14 @@ use crate::error::SpdmResult;
 use crate::requester::*;
 
 impl<'a> RequesterContext<'a> {
-    fn send_receive_spdm_measurement_record(
+    async fn send_receive_spdm_measurement_record_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
         &mut self,
+        device_io: &mut D,
         measurement_attributes: SpdmMeasurementeAttributes,
         measurement_operation: SpdmMeasurementOperation,
         slot_id: u8,
     ) -> SpdmResult<u8> {
+        self.check_request_supported(SpdmResponseResponseCode::SpdmRequestGetMeasurements)?;
         info!("send spdm measurement\n");
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let send_used = self.encode_spdm_measurement_record(
@@ -21,11 +23,10 @@ impl<'a> RequesterContext<'a> {
             slot_id,
             &mut send_buffer,
         )?;
-        self.send_message(&send_buffer[..send_used])?;
-
-        // Receive
         let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let used = self.receive_message(&mut receive_buffer)?;
+        let used = self
+            .send_receive_message_with(device_io, &send_buffer[..send_used], &mut receive_buffer)
+            .await?;
         self.handle_spdm_measurement_record_response(
             measurement_attributes,
             measurement_operation,
@@ -90,8 +91,7 @@ impl<'a> RequesterContext<'a> {
                         if measurement_attributes
                             .contains(SpdmMeasurementeAttributes::INCLUDE_SIGNATURE)
                         {
-                            let base_asym_size =
-                                self.common.negotiate_info.base_asym_sel.get_size() as usize;
+                            let base_asym_size = self.common.get_asym_key_size() as usize;
                             let temp_used = used - base_asym_size;
 
                             let message_m = &mut self.common.runtime_info.message_m;
@@ -137,6 +137,9 @@ impl<'a> RequesterContext<'a> {
                         spdm_result_err!(EFAULT)
                     }
                 }
+                SpdmResponseResponseCode::SpdmResponseError => {
+                    self.handle_spdm_error_response(&mut reader)
+                }
                 _ => spdm_result_err!(EINVAL),
             },
             None => spdm_result_err!(EIO),
@@ -147,48 +150,62 @@ impl<'a> RequesterContext<'a> {
         &mut self,
         measurement_operation: SpdmMeasurementOperation,
         slot_id: u8,
+    ) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self.send_receive_spdm_measurement_with(
+            &mut device_io,
+            measurement_operation,
+            slot_id
+        ))
+    }
+
+    pub(crate) async fn send_receive_spdm_measurement_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        measurement_operation: SpdmMeasurementOperation,
+        slot_id: u8,
     ) -> SpdmResult {
         match measurement_operation {
             SpdmMeasurementOperation::SpdmMeasurementRequestAll => self
-                .send_receive_spdm_measurement_record(
+                .send_receive_spdm_measurement_record_with(
+                    device_io,
                     SpdmMeasurementeAttributes::INCLUDE_SIGNATURE,
                     SpdmMeasurementOperation::SpdmMeasurementRequestAll,
                     slot_id,
                 )
+                .await
                 .and(Ok(())),
             SpdmMeasurementOperation::SpdmMeasurementQueryTotalNumber => {
-                if let Ok(total_number) = self.send_receive_spdm_measurement_record(
-                    SpdmMeasurementeAttributes::empty(),
-                    SpdmMeasurementOperation::SpdmMeasurementQueryTotalNumber,
-                    slot_id,
-                ) {
-                    for block_i in 1..total_number.checked_add(1).ok_or(spdm_err!(ENOMEM))? {
-                        if self
-                            .send_receive_spdm_measurement_record(
-                                if block_i == total_number {
-                                    SpdmMeasurementeAttributes::INCLUDE_SIGNATURE
-                                } else {
-                                    SpdmMeasurementeAttributes::empty()
-                                },
-                                SpdmMeasurementOperation::Unknown(block_i as u8),
-                                slot_id,
-                            )
-                            .is_err()
-                        {
-                            return spdm_result_err!(EFAULT);
-                        }
-                    }
-                    Ok(())
-                } else {
-                    spdm_result_err!(EFAULT)
+                let total_number = self
+                    .send_receive_spdm_measurement_record_with(
+                        device_io,
+                        SpdmMeasurementeAttributes::empty(),
+                        SpdmMeasurementOperation::SpdmMeasurementQueryTotalNumber,
+                        slot_id,
+                    )
+                    .await?;
+                for block_i in 1..total_number.checked_add(1).ok_or(spdm_err!(ENOMEM))? {
+                    self.send_receive_spdm_measurement_record_with(
+                        device_io,
+                        if block_i == total_number {
+                            SpdmMeasurementeAttributes::INCLUDE_SIGNATURE
+                        } else {
+                            SpdmMeasurementeAttributes::empty()
+                        },
+                        SpdmMeasurementOperation::Unknown(block_i as u8),
+                        slot_id,
+                    )
+                    .await?;
                 }
+                Ok(())
             }
             SpdmMeasurementOperation::Unknown(index) => self
-                .send_receive_spdm_measurement_record(
+                .send_receive_spdm_measurement_record_with(
+                    device_io,
                     SpdmMeasurementeAttributes::INCLUDE_SIGNATURE,
                     SpdmMeasurementOperation::Unknown(index as u8),
                     slot_id,
                 )
+                .await
                 .and(Ok(())),
         }
     }
@@ -223,7 +240,7 @@ mod tests_requester {
 
         responder.common.negotiate_info.rsp_ct_exponent_sel = 0;
         responder.common.negotiate_info.rsp_capabilities_sel =
-            SpdmResponseCapabilityFlags::CERT_CAP;
+            SpdmResponseCapabilityFlags::CERT_CAP | SpdmResponseCapabilityFlags::MEAS_CAP_SIG;
 
         responder
             .common
@@ -243,6 +260,9 @@ mod tests_requester {
             .append_message(message_m);
         responder.common.reset_runtime_info();
 
+        responder.common.runtime_info.connection_state =
+            crate::common::SpdmConnectionState::SpdmConnectionNegotiated;
+
         let pcidoe_transport_encap2 = &mut PciDoeTransportEncap {};
         let mut device_io_requester = FakeSpdmDeviceIo::new(&shared_buffer, &mut responder);
 
@@ -258,7 +278,7 @@ mod tests_requester {
 
         requester.common.negotiate_info.rsp_ct_exponent_sel = 0;
         requester.common.negotiate_info.rsp_capabilities_sel =
-            SpdmResponseCapabilityFlags::CERT_CAP;
+            SpdmResponseCapabilityFlags::CERT_CAP | SpdmResponseCapabilityFlags::MEAS_CAP_SIG;
         requester
             .common
             .negotiate_info
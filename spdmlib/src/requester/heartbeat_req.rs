@@ -7,14 +7,28 @@ use crate::requester::*;
 
 impl<'a> RequesterContext<'a> {
     pub fn send_receive_spdm_heartbeat(&mut self, session_id: u32) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self
+            .send_receive_spdm_heartbeat_with(&mut device_io, session_id))
+    }
+
+    pub(crate) async fn send_receive_spdm_heartbeat_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        session_id: u32,
+    ) -> SpdmResult {
+        self.check_request_supported(SpdmResponseResponseCode::SpdmRequestHeartbeat)?;
         info!("send spdm heartbeat\n");
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let used = self.encode_spdm_heartbeat(&mut send_buffer);
-        self.send_secured_message(session_id, &send_buffer[..used])?;
-
-        // Receive
         let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let used = self.receive_secured_message(session_id, &mut receive_buffer)?;
+        let used = self
+            .send_receive_secured_message_with(
+                device_io,
+                session_id,
+                &send_buffer[..used],
+                &mut receive_buffer,
+            )
+            .await?;
         self.handle_spdm_heartbeat_response(&receive_buffer[..used])
     }
 
@@ -46,6 +60,9 @@ impl<'a> RequesterContext<'a> {
                         spdm_result_err!(EFAULT)
                     }
                 }
+                SpdmResponseResponseCode::SpdmResponseError => {
+                    self.handle_spdm_error_response(&mut reader)
+                }
                 _ => spdm_result_err!(EINVAL),
             },
             None => spdm_result_err!(EIO),
@@ -56,7 +73,7 @@ impl<'a> RequesterContext<'a> {
 #[cfg(test)]
 mod tests_requester {
     use super::*;
-    use crate::session::SpdmSession;
+    use crate::session::SpdmSessionTable;
     use crate::testlib::*;
     use crate::{crypto, responder};
 
@@ -77,11 +94,12 @@ mod tests_requester {
             rsp_config_info,
             rsp_provision_info,
         );
+        select_capabilities(&mut responder.common);
 
         let rsp_session_id = 0x11u16;
         let session_id = (0x11u32 << 16) + rsp_session_id as u32;
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
-        responder.common.session = [SpdmSession::new(); 4];
+        responder.common.session = SpdmSessionTable::new();
         responder.common.session[0].setup(session_id).unwrap();
         responder.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -101,11 +119,12 @@ mod tests_requester {
             req_config_info,
             req_provision_info,
         );
+        select_capabilities(&mut requester.common);
 
         let rsp_session_id = 0x11u16;
         let session_id = (0x11u32 << 16) + rsp_session_id as u32;
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(session_id).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -20,6 +20,20 @@ impl<'a> RequesterContext<'a> {
         slot_id: u8,
         measurement_summary_hash_type: SpdmMeasurementSummaryHashType,
     ) -> SpdmResult<u32> {
+        block_on_device_io!(self, |device_io| self.send_receive_spdm_key_exchange_with(
+            &mut device_io,
+            slot_id,
+            measurement_summary_hash_type
+        ))
+    }
+
+    pub(crate) async fn send_receive_spdm_key_exchange_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        slot_id: u8,
+        measurement_summary_hash_type: SpdmMeasurementSummaryHashType,
+    ) -> SpdmResult<u32> {
+        self.check_request_supported(SpdmResponseResponseCode::SpdmRequestKeyExchange)?;
         info!("send spdm key exchange\n");
 
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
@@ -28,11 +42,10 @@ impl<'a> RequesterContext<'a> {
             slot_id,
             measurement_summary_hash_type,
         )?;
-        self.send_message(&send_buffer[..send_used])?;
-
-        // Receive
         let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let receive_used = self.receive_message(&mut receive_buffer)?;
+        let receive_used = self
+            .send_receive_message_with(device_io, &send_buffer[..send_used], &mut receive_buffer)
+            .await?;
         self.handle_spdm_key_exhcange_response(
             &send_buffer[..send_used],
             &receive_buffer[..receive_used],
@@ -121,8 +134,7 @@ impl<'a> RequesterContext<'a> {
                         debug!("!!! final_key : {:02x?}\n", final_key.as_ref());
 
                         // verify signature
-                        let base_asym_size =
-                            self.common.negotiate_info.base_asym_sel.get_size() as usize;
+                        let base_asym_size = self.common.get_asym_key_size() as usize;
                         let base_hash_size =
                             self.common.negotiate_info.base_hash_sel.get_size() as usize;
 
@@ -221,6 +233,9 @@ impl<'a> RequesterContext<'a> {
                         spdm_result_err!(EFAULT)
                     }
                 }
+                SpdmResponseResponseCode::SpdmResponseError => {
+                    self.handle_spdm_error_response(&mut reader)
+                }
                 _ => spdm_result_err!(EINVAL),
             },
             None => spdm_result_err!(EIO),
@@ -255,6 +270,7 @@ mod tests_requester {
             rsp_config_info,
             rsp_provision_info,
         );
+        select_capabilities(&mut responder.common);
 
         responder.common.provision_info.my_cert_chain = Some(SpdmCertChainData {
             data_size: 512u16,
@@ -276,6 +292,9 @@ mod tests_requester {
             .append_message(message_m);
         responder.common.peer_info.peer_cert_chain.cert_chain = REQ_CERT_CHAIN_DATA;
 
+        responder.common.runtime_info.connection_state =
+            crate::common::SpdmConnectionState::SpdmConnectionNegotiated;
+
         let pcidoe_transport_encap2 = &mut PciDoeTransportEncap {};
         let mut device_io_requester = FakeSpdmDeviceIo::new(&shared_buffer, &mut responder);
 
@@ -285,6 +304,7 @@ mod tests_requester {
             req_config_info,
             req_provision_info,
         );
+        select_capabilities(&mut requester.common);
 
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         requester.common.negotiate_info.aead_sel = SpdmAeadAlgo::AES_128_GCM;
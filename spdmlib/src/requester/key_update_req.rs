@@ -6,8 +6,9 @@ use crate::error::SpdmResult;
 use crate::requester::*;
 
 impl<'a> RequesterContext<'a> {
-    fn send_receive_spdm_key_update_op(
+    async fn send_receive_spdm_key_update_op_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
         &mut self,
+        device_io: &mut D,
         session_id: u32,
         key_update_operation: SpdmKeyUpdateOperation,
         tag: u8,
@@ -15,10 +16,15 @@ impl<'a> RequesterContext<'a> {
         info!("send spdm key_update\n");
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let used = self.encode_spdm_key_update_op(key_update_operation, tag, &mut send_buffer);
-        self.send_secured_message(session_id, &send_buffer[..used])?;
-
         let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let used = self.receive_secured_message(session_id, &mut receive_buffer)?;
+        let used = self
+            .send_receive_secured_message_with(
+                device_io,
+                session_id,
+                &send_buffer[..used],
+                &mut receive_buffer,
+            )
+            .await?;
         self.handle_spdm_key_update_op_response(
             session_id,
             key_update_operation,
@@ -85,6 +91,9 @@ impl<'a> RequesterContext<'a> {
                         spdm_result_err!(EFAULT)
                     }
                 }
+                SpdmResponseResponseCode::SpdmResponseError => {
+                    self.handle_spdm_error_response(&mut reader)
+                }
                 _ => spdm_result_err!(EINVAL),
             },
             None => spdm_result_err!(EIO),
@@ -96,24 +105,41 @@ impl<'a> RequesterContext<'a> {
         session_id: u32,
         key_update_operation: SpdmKeyUpdateOperation,
     ) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self.send_receive_spdm_key_update_with(
+            &mut device_io,
+            session_id,
+            key_update_operation
+        ))
+    }
+
+    pub(crate) async fn send_receive_spdm_key_update_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        session_id: u32,
+        key_update_operation: SpdmKeyUpdateOperation,
+    ) -> SpdmResult {
+        self.check_request_supported(SpdmResponseResponseCode::SpdmRequestKeyUpdate)?;
         if key_update_operation != SpdmKeyUpdateOperation::SpdmUpdateAllKeys
             && key_update_operation != SpdmKeyUpdateOperation::SpdmUpdateSingleKey
         {
             return spdm_result_err!(EINVAL);
         }
-        self.send_receive_spdm_key_update_op(session_id, key_update_operation, 1)?;
-        self.send_receive_spdm_key_update_op(
+        self.send_receive_spdm_key_update_op_with(device_io, session_id, key_update_operation, 1)
+            .await?;
+        self.send_receive_spdm_key_update_op_with(
+            device_io,
             session_id,
             SpdmKeyUpdateOperation::SpdmVerifyNewKey,
             2,
         )
+        .await
     }
 }
 
 #[cfg(test)]
 mod tests_requester {
     use super::*;
-    use crate::session::SpdmSession;
+    use crate::session::SpdmSessionTable;
     use crate::testlib::*;
     use crate::{crypto, responder};
 
@@ -134,11 +160,12 @@ mod tests_requester {
             rsp_config_info,
             rsp_provision_info,
         );
+        select_capabilities(&mut responder.common);
 
         let rsp_session_id = 0xFFFEu16;
         let session_id = (0xffu32 << 16) + rsp_session_id as u32;
         responder.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
-        responder.common.session = [SpdmSession::new(); 4];
+        responder.common.session = SpdmSessionTable::new();
         responder.common.session[0].setup(session_id).unwrap();
         responder.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -158,11 +185,12 @@ mod tests_requester {
             req_config_info,
             req_provision_info,
         );
+        select_capabilities(&mut requester.common);
 
         let rsp_session_id = 0xFFFEu16;
         let session_id = (0xffu32 << 16) + rsp_session_id as u32;
         requester.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(session_id).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -2,10 +2,32 @@
 //
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
+// Each flow is written once, over an SpdmAsyncDeviceIo, as the
+// `_with` method. The blocking API runs it with the device IO of the
+// context, the async API with the given SpdmAsyncDeviceIo.
+macro_rules! block_on_device_io {
+    ($context:expr, |$device_io:ident| $flow:expr) => {
+        match $context.common.device_io.take() {
+            Some(device_io) => {
+                let mut $device_io = crate::common::SpdmBlockingDeviceIo {
+                    device_io,
+                    delay: $context.retry_policy.delay,
+                };
+                let result = crate::common::block_on($flow);
+                $context.common.device_io = Some($device_io.device_io);
+                result
+            }
+            None => spdm_result_err!(ENODEV),
+        }
+    };
+}
+
+mod async_req;
 mod context;
 
 mod challenge_req;
 mod end_session_req;
+mod error_req;
 mod finish_req;
 mod get_capabilities_req;
 mod get_certificate_req;
@@ -19,8 +41,9 @@ mod negotiate_algorithms_req;
 mod psk_exchange_req;
 mod psk_finish_req;
 
-pub use context::RequesterContext;
+pub use context::{RequesterContext, SpdmDelay, SpdmRetryPolicy};
 
+use crate::common::SpdmAsyncDeviceIo;
 use crate::config;
 use crate::msgs::*;
 use codec::{Codec, Reader, Writer};
@@ -2,17 +2,30 @@
 //
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
+use crate::crypto;
 use crate::error::SpdmResult;
 use crate::requester::*;
 
 impl<'a> RequesterContext<'a> {
     pub fn send_receive_spdm_algorithm(&mut self) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self
+            .send_receive_spdm_algorithm_with(&mut device_io))
+    }
+
+    pub(crate) async fn send_receive_spdm_algorithm_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+    ) -> SpdmResult {
+        if !self.has_acceptable_algorithms() {
+            error!("!!! negotiate_algorithms : no acceptable algorithm to offer !!!\n");
+            return spdm_result_err!(ENOTSUP);
+        }
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let send_used = self.encode_spdm_algorithm(&mut send_buffer);
-        self.send_message(&send_buffer[..send_used])?;
-
         let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let used = self.receive_message(&mut receive_buffer)?;
+        let used = self
+            .send_receive_message_with(device_io, &send_buffer[..send_used], &mut receive_buffer)
+            .await?;
         self.handle_spdm_algorithm_response(&send_buffer[..send_used], &receive_buffer[..used])
     }
 
@@ -26,29 +39,40 @@ impl<'a> RequesterContext<'a> {
             payload: SpdmMessagePayload::SpdmNegotiateAlgorithmsRequest(
                 SpdmNegotiateAlgorithmsRequestPayload {
                     measurement_specification: self.common.config_info.measurement_specification,
-                    base_asym_algo: self.common.config_info.base_asym_algo,
-                    base_hash_algo: self.common.config_info.base_hash_algo,
+                    base_asym_algo: self.common.config_info.get_acceptable_base_asym_algo(),
+                    base_hash_algo: self.common.config_info.get_acceptable_base_hash_algo(),
+                    ext_asym_count: self.common.config_info.ext_asym_algo_count,
+                    ext_asym: self.common.config_info.ext_asym_algo,
+                    ext_hash_count: self.common.config_info.ext_hash_algo_count,
+                    ext_hash: self.common.config_info.ext_hash_algo,
                     alg_struct_count: 4,
                     alg_struct: [
                         SpdmAlgStruct {
                             alg_type: SpdmAlgType::SpdmAlgTypeDHE,
                             alg_fixed_count: 2,
-                            alg_supported: SpdmAlg::SpdmAlgoDhe(self.common.config_info.dhe_algo),
+                            alg_supported: SpdmAlg::SpdmAlgoDhe(
+                                self.common.config_info.get_acceptable_dhe_algo(),
+                            ),
                             alg_ext_count: 0,
+                            ..Default::default()
                         },
                         SpdmAlgStruct {
                             alg_type: SpdmAlgType::SpdmAlgTypeAEAD,
                             alg_fixed_count: 2,
-                            alg_supported: SpdmAlg::SpdmAlgoAead(self.common.config_info.aead_algo),
+                            alg_supported: SpdmAlg::SpdmAlgoAead(
+                                self.common.config_info.get_acceptable_aead_algo(),
+                            ),
                             alg_ext_count: 0,
+                            ..Default::default()
                         },
                         SpdmAlgStruct {
                             alg_type: SpdmAlgType::SpdmAlgTypeReqAsym,
                             alg_fixed_count: 2,
                             alg_supported: SpdmAlg::SpdmAlgoReqAsym(
-                                self.common.config_info.req_asym_algo,
+                                self.common.config_info.get_acceptable_req_asym_algo(),
                             ),
                             alg_ext_count: 0,
+                            ..Default::default()
                         },
                         SpdmAlgStruct {
                             alg_type: SpdmAlgType::SpdmAlgTypeKeySchedule,
@@ -57,6 +81,7 @@ impl<'a> RequesterContext<'a> {
                                 self.common.config_info.key_schedule_algo,
                             ),
                             alg_ext_count: 0,
+                            ..Default::default()
                         },
                     ],
                 },
@@ -80,28 +105,9 @@ impl<'a> RequesterContext<'a> {
                     let used = reader.used();
                     if let Some(algorithms) = algorithms {
                         debug!("!!! algorithms : {:02x?}\n", algorithms);
-                        self.common.negotiate_info.measurement_specification_sel =
-                            algorithms.measurement_specification_sel;
-                        self.common.negotiate_info.measurement_hash_sel =
-                            algorithms.measurement_hash_algo;
-                        self.common.negotiate_info.base_hash_sel = algorithms.base_hash_sel;
-                        self.common.negotiate_info.base_asym_sel = algorithms.base_asym_sel;
-                        for alg in algorithms
-                            .alg_struct
-                            .iter()
-                            .take(algorithms.alg_struct_count as usize)
-                        {
-                            match alg.alg_supported {
-                                SpdmAlg::SpdmAlgoDhe(v) => self.common.negotiate_info.dhe_sel = v,
-                                SpdmAlg::SpdmAlgoAead(v) => self.common.negotiate_info.aead_sel = v,
-                                SpdmAlg::SpdmAlgoReqAsym(v) => {
-                                    self.common.negotiate_info.req_asym_sel = v
-                                }
-                                SpdmAlg::SpdmAlgoKeySchedule(v) => {
-                                    self.common.negotiate_info.key_schedule_sel = v
-                                }
-                                SpdmAlg::SpdmAlgoUnknown(_v) => {}
-                            }
+                        if let Err(e) = self.select_algorithms(&algorithms) {
+                            self.common.reset_runtime_info();
+                            return Err(e);
                         }
 
                         let message_a = &mut self.common.runtime_info.message_a;
@@ -115,16 +121,227 @@ impl<'a> RequesterContext<'a> {
                     error!("!!! algorithms : fail !!!\n");
                     spdm_result_err!(EFAULT)
                 }
+                SpdmResponseResponseCode::SpdmResponseError => {
+                    self.handle_spdm_error_response(&mut reader)
+                }
                 _ => spdm_result_err!(EINVAL),
             },
             None => spdm_result_err!(EIO),
         }
     }
+
+    /// Whether there is an acceptable algorithm to offer for every class the
+    /// negotiated capabilities need, after our preference lists, minimum
+    /// security strength and crypto support.
+    fn has_acceptable_algorithms(&self) -> bool {
+        let config_info = &self.common.config_info;
+        let required = self.common.get_required_algorithms();
+        (!config_info.get_acceptable_base_hash_algo().is_empty()
+            || config_info.ext_hash_algo_count != 0)
+            && (!required.base_asym
+                || !config_info.get_acceptable_base_asym_algo().is_empty()
+                || config_info.ext_asym_algo_count != 0)
+            && (!required.dhe || !config_info.get_acceptable_dhe_algo().is_empty())
+            && (!required.aead || !config_info.get_acceptable_aead_algo().is_empty())
+            && (!required.req_asym || !config_info.get_acceptable_req_asym_algo().is_empty())
+    }
+
+    /// Take the algorithms the responder selected, if every selection is
+    /// valid: one bit out of what was offered, and present when the
+    /// negotiated capabilities need it. Nothing is taken otherwise, and a
+    /// selection our preference lists or minimum security strength turn
+    /// down fails with ENOTSUP.
+    fn select_algorithms(&mut self, algorithms: &SpdmAlgorithmsResponsePayload) -> SpdmResult {
+        let mut dhe_sel = None;
+        let mut aead_sel = None;
+        let mut req_asym_sel = None;
+        let mut key_schedule_sel = None;
+        for alg in algorithms
+            .alg_struct
+            .iter()
+            .take(algorithms.alg_struct_count as usize)
+        {
+            // we offer no extended algorithm in these
+            if alg.alg_ext_count != 0 {
+                return spdm_result_err!(EFAULT);
+            }
+            // each algorithm type at most once
+            let duplicated = match alg.alg_supported {
+                SpdmAlg::SpdmAlgoDhe(v) => dhe_sel.replace(v).is_some(),
+                SpdmAlg::SpdmAlgoAead(v) => aead_sel.replace(v).is_some(),
+                SpdmAlg::SpdmAlgoReqAsym(v) => req_asym_sel.replace(v).is_some(),
+                SpdmAlg::SpdmAlgoKeySchedule(v) => key_schedule_sel.replace(v).is_some(),
+                SpdmAlg::SpdmAlgoUnknown(_v) => false,
+            };
+            if duplicated {
+                return spdm_result_err!(EFAULT);
+            }
+        }
+        let dhe_sel = dhe_sel.unwrap_or_default();
+        let aead_sel = aead_sel.unwrap_or_default();
+        let req_asym_sel = req_asym_sel.unwrap_or_default();
+        let key_schedule_sel = key_schedule_sel.unwrap_or_default();
+
+        let config_info = &self.common.config_info;
+        let required = self.common.get_required_algorithms();
+
+        // the measurement hash is not offered, any acceptable one is taken
+        let measurement_hash_acceptable = SpdmMeasurementHashAlgo::all().acceptable(
+            &config_info.measurement_hash_priority,
+            config_info.min_security_strength,
+        );
+        if is_turned_down(
+            algorithms.measurement_hash_algo.bits(),
+            SpdmMeasurementHashAlgo::all().bits(),
+            measurement_hash_acceptable.bits(),
+        ) || is_turned_down(
+            algorithms.base_hash_sel.bits(),
+            config_info.base_hash_algo.bits(),
+            config_info.get_acceptable_base_hash_algo().bits(),
+        ) || is_turned_down(
+            algorithms.base_asym_sel.bits(),
+            config_info.base_asym_algo.bits(),
+            config_info.get_acceptable_base_asym_algo().bits(),
+        ) || is_turned_down(
+            dhe_sel.bits() as u32,
+            config_info.dhe_algo.bits() as u32,
+            config_info.get_acceptable_dhe_algo().bits() as u32,
+        ) || is_turned_down(
+            aead_sel.bits() as u32,
+            config_info.aead_algo.bits() as u32,
+            config_info.get_acceptable_aead_algo().bits() as u32,
+        ) || is_turned_down(
+            req_asym_sel.bits() as u32,
+            config_info.req_asym_algo.bits() as u32,
+            config_info.get_acceptable_req_asym_algo().bits() as u32,
+        ) {
+            error!("!!! algorithms : selection below our policy !!!\n");
+            return spdm_result_err!(ENOTSUP);
+        }
+        let ext_asym_sel = if algorithms.ext_asym_sel_count == 1 {
+            Some(algorithms.ext_asym_sel)
+        } else {
+            None
+        };
+        let ext_hash_sel = if algorithms.ext_hash_sel_count == 1 {
+            Some(algorithms.ext_hash_sel)
+        } else {
+            None
+        };
+        let base_asym_valid = match &ext_asym_sel {
+            Some(ext_asym_sel) => is_valid_ext_selection(
+                ext_asym_sel,
+                algorithms.base_asym_sel.bits(),
+                &config_info.ext_asym_algo[..(config_info.ext_asym_algo_count as usize)],
+                crypto::ext_alg::get_signature_size,
+            ),
+            None => is_valid_selection(
+                algorithms.base_asym_sel.bits(),
+                config_info.get_acceptable_base_asym_algo().bits(),
+                required.base_asym,
+            ),
+        };
+        let base_hash_valid = match &ext_hash_sel {
+            // signatures and sessions need a base hash
+            Some(ext_hash_sel) => {
+                !(required.base_asym || required.aead || required.key_schedule)
+                    && is_valid_ext_selection(
+                        ext_hash_sel,
+                        algorithms.base_hash_sel.bits(),
+                        &config_info.ext_hash_algo[..(config_info.ext_hash_algo_count as usize)],
+                        crypto::ext_alg::get_hash_size,
+                    )
+            }
+            None => is_valid_selection(
+                algorithms.base_hash_sel.bits(),
+                config_info.get_acceptable_base_hash_algo().bits(),
+                true,
+            ),
+        };
+
+        // the measurement hash is not offered, only reported with MEAS_CAP
+        let measurement_hash_valid = if required.measurement {
+            algorithms.measurement_hash_algo.bits().count_ones() == 1
+        } else {
+            algorithms.measurement_hash_algo.is_empty()
+        };
+        if !(measurement_hash_valid
+            && is_valid_selection(
+                algorithms.measurement_specification_sel.bits() as u32,
+                config_info.measurement_specification.bits() as u32,
+                required.measurement,
+            )
+            && base_hash_valid
+            && base_asym_valid
+            && is_valid_selection(
+                dhe_sel.bits() as u32,
+                config_info.get_acceptable_dhe_algo().bits() as u32,
+                required.dhe,
+            )
+            && is_valid_selection(
+                aead_sel.bits() as u32,
+                config_info.get_acceptable_aead_algo().bits() as u32,
+                required.aead,
+            )
+            && is_valid_selection(
+                req_asym_sel.bits() as u32,
+                config_info.get_acceptable_req_asym_algo().bits() as u32,
+                required.req_asym,
+            )
+            && is_valid_selection(
+                key_schedule_sel.bits() as u32,
+                config_info.key_schedule_algo.bits() as u32,
+                required.key_schedule,
+            ))
+        {
+            error!("!!! algorithms : invalid selection !!!\n");
+            return spdm_result_err!(EFAULT);
+        }
+
+        let negotiate_info = &mut self.common.negotiate_info;
+        negotiate_info.measurement_specification_sel = algorithms.measurement_specification_sel;
+        negotiate_info.measurement_hash_sel = algorithms.measurement_hash_algo;
+        negotiate_info.base_hash_sel = algorithms.base_hash_sel;
+        negotiate_info.base_asym_sel = algorithms.base_asym_sel;
+        negotiate_info.ext_asym_sel = ext_asym_sel;
+        negotiate_info.ext_hash_sel = ext_hash_sel;
+        negotiate_info.dhe_sel = dhe_sel;
+        negotiate_info.aead_sel = aead_sel;
+        negotiate_info.req_asym_sel = req_asym_sel;
+        negotiate_info.key_schedule_sel = key_schedule_sel;
+        Ok(())
+    }
+}
+
+/// A selection of ours that our policy or crypto support turns down.
+fn is_turned_down(selected: u32, configured: u32, acceptable: u32) -> bool {
+    selected & configured & !acceptable != 0
+}
+
+/// A selection is one of the offered bits, or none if it is not required.
+fn is_valid_selection(selected: u32, offered: u32, required: bool) -> bool {
+    if selected == 0 {
+        !required
+    } else {
+        selected.count_ones() == 1 && selected & !offered == 0
+    }
+}
+
+/// An extended selection replaces the base one, and is one we offered and
+/// crypto::ext_alg supports.
+fn is_valid_ext_selection(
+    selected: &SpdmExtAlgStruct,
+    base_selected: u32,
+    offered: &[SpdmExtAlgStruct],
+    get_size: fn(&SpdmExtAlgStruct) -> Option<u16>,
+) -> bool {
+    base_selected == 0 && offered.contains(selected) && get_size(selected).is_some()
 }
 
 #[cfg(test)]
 mod tests_requester {
     use super::*;
+    use crate::error::SpdmErrorNum;
     use crate::testlib::*;
     use crate::{crypto, responder};
 
@@ -146,6 +363,9 @@ mod tests_requester {
             rsp_provision_info,
         );
 
+        responder.common.runtime_info.connection_state =
+            crate::common::SpdmConnectionState::SpdmConnectionAfterCapabilities;
+
         let pcidoe_transport_encap2 = &mut PciDoeTransportEncap {};
         let mut device_io_requester = FakeSpdmDeviceIo::new(&shared_buffer, &mut responder);
 
@@ -155,8 +375,156 @@ mod tests_requester {
             req_config_info,
             req_provision_info,
         );
+        select_capabilities(&mut requester.common);
 
         let status = requester.send_receive_spdm_algorithm().is_ok();
         assert!(status);
     }
+
+    #[test]
+    fn test_case0_handle_spdm_algorithm_response() {
+        let (config_info, provision_info) = create_info();
+        let shared_buffer = SharedBuffer::new();
+        let mut device_io = FakeSpdmDeviceIoReceve::new(&shared_buffer);
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+        let mut requester = RequesterContext::new(
+            &mut device_io,
+            pcidoe_transport_encap,
+            config_info,
+            provision_info,
+        );
+        select_capabilities(&mut requester.common);
+
+        let valid = SpdmAlgorithmsResponsePayload {
+            measurement_specification_sel: SpdmMeasurementSpecification::DMTF,
+            measurement_hash_algo: SpdmMeasurementHashAlgo::TPM_ALG_SHA_384,
+            base_asym_sel: SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+            base_hash_sel: SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+            alg_struct_count: 4,
+            alg_struct: [
+                SpdmAlgStruct {
+                    alg_type: SpdmAlgType::SpdmAlgTypeDHE,
+                    alg_fixed_count: 2,
+                    alg_supported: SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::SECP_384_R1),
+                    alg_ext_count: 0,
+                    ..Default::default()
+                },
+                SpdmAlgStruct {
+                    alg_type: SpdmAlgType::SpdmAlgTypeAEAD,
+                    alg_fixed_count: 2,
+                    alg_supported: SpdmAlg::SpdmAlgoAead(SpdmAeadAlgo::AES_256_GCM),
+                    alg_ext_count: 0,
+                    ..Default::default()
+                },
+                SpdmAlgStruct {
+                    alg_type: SpdmAlgType::SpdmAlgTypeReqAsym,
+                    alg_fixed_count: 2,
+                    alg_supported: SpdmAlg::SpdmAlgoReqAsym(SpdmReqAsymAlgo::TPM_ALG_RSAPSS_2048),
+                    alg_ext_count: 0,
+                    ..Default::default()
+                },
+                SpdmAlgStruct {
+                    alg_type: SpdmAlgType::SpdmAlgTypeKeySchedule,
+                    alg_fixed_count: 2,
+                    alg_supported: SpdmAlg::SpdmAlgoKeySchedule(
+                        SpdmKeyScheduleAlgo::SPDM_KEY_SCHEDULE,
+                    ),
+                    alg_ext_count: 0,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let mut invalid = [valid; 6];
+        // two hash algorithms
+        invalid[0].base_hash_sel =
+            SpdmBaseHashAlgo::TPM_ALG_SHA_384 | SpdmBaseHashAlgo::TPM_ALG_SHA_256;
+        // a hash algorithm that was not offered
+        invalid[1].base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_256;
+        // no signing algorithm with CHAL_CAP
+        invalid[2].base_asym_sel = SpdmBaseAsymAlgo::empty();
+        // no DHE group with KEY_EX_CAP
+        invalid[3].alg_struct_count = 1;
+        invalid[3].alg_struct[0] = valid.alg_struct[1];
+        // the DHE group twice
+        invalid[4].alg_struct[1] = valid.alg_struct[0];
+        // no measurement hash with MEAS_CAP
+        invalid[5].measurement_hash_algo = SpdmMeasurementHashAlgo::empty();
+
+        let mut response = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        for algorithms in invalid.iter() {
+            let used = encode_algorithms(&mut requester, algorithms, &mut response);
+            assert!(requester
+                .handle_spdm_algorithm_response(&[], &response[..used])
+                .is_err());
+            // nothing was taken
+            assert!(requester.common.negotiate_info.base_hash_sel.is_empty());
+        }
+
+        // a measurement hash without MEAS_CAP
+        requester.common.negotiate_info.rsp_capabilities_sel -=
+            SpdmResponseCapabilityFlags::MEAS_CAP_MASK;
+        let used = encode_algorithms(&mut requester, &valid, &mut response);
+        assert!(requester
+            .handle_spdm_algorithm_response(&[], &response[..used])
+            .is_err());
+
+        requester.common.negotiate_info.rsp_capabilities_sel =
+            requester.common.config_info.rsp_capabilities;
+
+        // a hash algorithm we have below the minimum strength
+        requester.common.config_info.base_hash_algo |= SpdmBaseHashAlgo::TPM_ALG_SHA_256;
+        requester.common.config_info.min_security_strength = 192;
+        let used = encode_algorithms(&mut requester, &invalid[1], &mut response);
+        let error = requester
+            .handle_spdm_algorithm_response(&[], &response[..used])
+            .unwrap_err();
+        assert_eq!(error.num(), SpdmErrorNum::ENOTSUP);
+
+        // a measurement hash below the minimum strength
+        let mut weak_measurement_hash = valid;
+        weak_measurement_hash.measurement_hash_algo = SpdmMeasurementHashAlgo::TPM_ALG_SHA_256;
+        let used = encode_algorithms(&mut requester, &weak_measurement_hash, &mut response);
+        let error = requester
+            .handle_spdm_algorithm_response(&[], &response[..used])
+            .unwrap_err();
+        assert_eq!(error.num(), SpdmErrorNum::ENOTSUP);
+
+        // nothing acceptable to offer
+        requester.common.config_info.min_security_strength = 256;
+        let error = requester.send_receive_spdm_algorithm().unwrap_err();
+        assert_eq!(error.num(), SpdmErrorNum::ENOTSUP);
+        requester.common.config_info.min_security_strength = 0;
+
+        let used = encode_algorithms(&mut requester, &valid, &mut response);
+        assert!(requester
+            .handle_spdm_algorithm_response(&[], &response[..used])
+            .is_ok());
+        assert_eq!(
+            requester.common.negotiate_info.base_hash_sel,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_384
+        );
+        assert_eq!(
+            requester.common.negotiate_info.dhe_sel,
+            SpdmDheAlgo::SECP_384_R1
+        );
+    }
+
+    fn encode_algorithms(
+        requester: &mut RequesterContext,
+        algorithms: &SpdmAlgorithmsResponsePayload,
+        buffer: &mut [u8],
+    ) -> usize {
+        let mut writer = Writer::init(buffer);
+        SpdmMessage {
+            header: SpdmMessageHeader {
+                version: SpdmVersion::SpdmVersion11,
+                request_response_code: SpdmResponseResponseCode::SpdmResponseAlgorithms,
+            },
+            payload: SpdmMessagePayload::SpdmAlgorithmsResponse(*algorithms),
+        }
+        .spdm_encode(&mut requester.common, &mut writer);
+        writer.used()
+    }
 }
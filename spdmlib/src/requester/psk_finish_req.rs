@@ -9,13 +9,27 @@ use crate::common::ManagedBuffer;
 
 impl<'a> RequesterContext<'a> {
     pub fn send_receive_spdm_psk_finish(&mut self, session_id: u32) -> SpdmResult {
+        block_on_device_io!(self, |device_io| self
+            .send_receive_spdm_psk_finish_with(&mut device_io, session_id))
+    }
+
+    pub(crate) async fn send_receive_spdm_psk_finish_with<D: SpdmAsyncDeviceIo + Send + ?Sized>(
+        &mut self,
+        device_io: &mut D,
+        session_id: u32,
+    ) -> SpdmResult {
         info!("send spdm psk_finish\n");
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let (send_used, message_f) = self.encode_spdm_psk_finish(session_id, &mut send_buffer)?;
-        self.send_secured_message(session_id, &send_buffer[..send_used])?;
-
         let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let receive_used = self.receive_secured_message(session_id, &mut receive_buffer)?;
+        let receive_used = self
+            .send_receive_secured_message_with(
+                device_io,
+                session_id,
+                &send_buffer[..send_used],
+                &mut receive_buffer,
+            )
+            .await?;
         self.handle_spdm_psk_finish_response(session_id, message_f, &receive_buffer[..receive_used])
     }
 
@@ -115,6 +129,9 @@ impl<'a> RequesterContext<'a> {
                         spdm_result_err!(EFAULT)
                     }
                 }
+                SpdmResponseResponseCode::SpdmResponseError => {
+                    self.handle_spdm_error_response(&mut reader)
+                }
                 _ => spdm_result_err!(EINVAL),
             },
             None => spdm_result_err!(EIO),
@@ -125,7 +142,7 @@ impl<'a> RequesterContext<'a> {
 #[cfg(test)]
 mod tests_requester {
     use super::*;
-    use crate::session::SpdmSession;
+    use crate::session::SpdmSessionTable;
     use crate::testlib::*;
     use crate::{crypto, responder};
 
@@ -164,7 +181,7 @@ mod tests_requester {
 
         // let rsp_session_id = 0x11u16;
         // let session_id = (0x11u32 << 16) + rsp_session_id as u32;
-        responder.common.session = [SpdmSession::new(); 4];
+        responder.common.session = SpdmSessionTable::new();
         responder.common.session[0].setup(4294901758).unwrap();
         responder.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -192,7 +209,7 @@ mod tests_requester {
 
         // let rsp_session_id = 0x11u16;
         // let session_id = (0x11u32 << 16) + rsp_session_id as u32;
-        requester.common.session = [SpdmSession::new(); 4];
+        requester.common.session = SpdmSessionTable::new();
         requester.common.session[0].setup(4294901758).unwrap();
         requester.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -2,15 +2,25 @@
 //
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
+use crate::common::SpdmNegotiateInfo;
 use crate::crypto;
 use crate::responder::*;
 
 impl<'a> ResponderContext<'a> {
     pub fn handle_spdm_algorithm(&mut self, bytes: &[u8]) {
+        let _ =
+            self.send_response(|context, response| context.process_spdm_algorithm(bytes, response));
+    }
+
+    pub fn process_spdm_algorithm(
+        &mut self,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<usize> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         self.write_spdm_algorithm(bytes, &mut writer);
-        let _ = self.send_message(writer.used_slice());
+        self.encode_message(writer.used_slice(), response)
     }
 
     pub fn write_spdm_algorithm(&mut self, bytes: &[u8], writer: &mut Writer) {
@@ -19,7 +29,7 @@ impl<'a> ResponderContext<'a> {
 
         let negotiate_algorithms =
             SpdmNegotiateAlgorithmsRequestPayload::spdm_read(&mut self.common, &mut reader);
-        if let Some(negotiate_algorithms) = negotiate_algorithms {
+        let negotiate_algorithms = if let Some(negotiate_algorithms) = negotiate_algorithms {
             debug!("!!! negotiate_algorithms : {:02x?}\n", negotiate_algorithms);
             self.common.negotiate_info.measurement_specification_sel =
                 negotiate_algorithms.measurement_specification;
@@ -40,11 +50,12 @@ impl<'a> ResponderContext<'a> {
                     SpdmAlg::SpdmAlgoUnknown(_v) => {}
                 }
             }
+            negotiate_algorithms
         } else {
             error!("!!! negotiate_algorithms : fail !!!\n");
             self.write_spdm_error(SpdmErrorCode::SpdmErrorInvalidRequest, 0, writer);
             return;
-        }
+        };
 
         if self
             .common
@@ -57,37 +68,96 @@ impl<'a> ResponderContext<'a> {
             return;
         }
 
+        let offered = self.common.negotiate_info.clone();
         self.common
             .negotiate_info
             .measurement_specification_sel
             .prioritize(self.common.config_info.measurement_specification);
-        self.common.negotiate_info.measurement_hash_sel =
-            self.common.config_info.measurement_hash_algo;
-        self.common
-            .negotiate_info
-            .base_hash_sel
-            .prioritize(self.common.config_info.base_hash_algo);
-        self.common
-            .negotiate_info
-            .base_asym_sel
-            .prioritize(self.common.config_info.base_asym_algo);
-        self.common
-            .negotiate_info
-            .dhe_sel
-            .prioritize(self.common.config_info.dhe_algo);
-        self.common
-            .negotiate_info
-            .aead_sel
-            .prioritize(self.common.config_info.aead_algo);
-        self.common
-            .negotiate_info
-            .req_asym_sel
-            .prioritize(self.common.config_info.req_asym_algo);
+        // no measurement hash without MEAS_CAP
+        self.common.negotiate_info.measurement_hash_sel = if self
+            .common
+            .config_info
+            .rsp_capabilities
+            .intersects(SpdmResponseCapabilityFlags::MEAS_CAP_MASK)
+        {
+            let mut measurement_hash_sel = self
+                .common
+                .config_info
+                .get_acceptable_measurement_hash_algo();
+            measurement_hash_sel.prioritize_by(
+                measurement_hash_sel,
+                &self.common.config_info.measurement_hash_priority,
+            );
+            measurement_hash_sel
+        } else {
+            SpdmMeasurementHashAlgo::empty()
+        };
+        self.common.negotiate_info.base_hash_sel.prioritize_by(
+            self.common.config_info.get_acceptable_base_hash_algo(),
+            &self.common.config_info.base_hash_priority,
+        );
+        self.common.negotiate_info.base_asym_sel.prioritize_by(
+            self.common.config_info.get_acceptable_base_asym_algo(),
+            &self.common.config_info.base_asym_priority,
+        );
+        self.common.negotiate_info.dhe_sel.prioritize_by(
+            self.common.config_info.get_acceptable_dhe_algo(),
+            &self.common.config_info.dhe_priority,
+        );
+        self.common.negotiate_info.aead_sel.prioritize_by(
+            self.common.config_info.get_acceptable_aead_algo(),
+            &self.common.config_info.aead_priority,
+        );
+        self.common.negotiate_info.req_asym_sel.prioritize_by(
+            self.common.config_info.get_acceptable_req_asym_algo(),
+            &self.common.config_info.req_asym_priority,
+        );
         self.common
             .negotiate_info
             .key_schedule_sel
             .prioritize(self.common.config_info.key_schedule_algo);
 
+        // an extended algorithm only if there is no common base one
+        let config_info = &self.common.config_info;
+        let required = self.common.get_required_algorithms();
+        self.common.negotiate_info.ext_asym_sel =
+            if self.common.negotiate_info.base_asym_sel.is_empty() {
+                select_ext_algo(
+                    &config_info.ext_asym_algo[..(config_info.ext_asym_algo_count as usize)],
+                    &negotiate_algorithms.ext_asym
+                        [..(negotiate_algorithms.ext_asym_count as usize)],
+                    crypto::ext_alg::get_signature_size,
+                )
+            } else {
+                None
+            };
+        // signatures and sessions need a base hash
+        self.common.negotiate_info.ext_hash_sel =
+            if self.common.negotiate_info.base_hash_sel.is_empty()
+                && !(required.base_asym || required.aead || required.key_schedule)
+            {
+                select_ext_algo(
+                    &config_info.ext_hash_algo[..(config_info.ext_hash_algo_count as usize)],
+                    &negotiate_algorithms.ext_hash
+                        [..(negotiate_algorithms.ext_hash_count as usize)],
+                    crypto::ext_alg::get_hash_size,
+                )
+            } else {
+                None
+            };
+
+        if let Some(error_code) = self.check_algorithm_selection(&offered) {
+            self.common.reset_runtime_info();
+            // UnsupportedRequest carries the request code
+            let error_data = if error_code == SpdmErrorCode::SpdmErrorUnsupportedRequest {
+                SpdmResponseResponseCode::SpdmRequestNegotiateAlgorithms.get_u8()
+            } else {
+                0
+            };
+            self.write_spdm_error(error_code, error_data, writer);
+            return;
+        }
+
         //
         // update cert chain - append root cert hash
         //
@@ -102,9 +172,7 @@ impl<'a> ResponderContext<'a> {
                 )
                 .unwrap();
             let root_cert = &cert_chain.data[root_cert_begin..root_cert_end];
-            if let Some(root_hash) =
-                crypto::hash::hash_all(self.common.negotiate_info.base_hash_sel, root_cert)
-            {
+            if let Some(root_hash) = self.common.hash_all(root_cert) {
                 let data_size = 4 + root_hash.data_size + cert_chain.data_size;
                 let mut data = [0u8; config::MAX_SPDM_CERT_CHAIN_DATA_SIZE];
                 data[0] = (data_size & 0xFF) as u8;
@@ -135,6 +203,10 @@ impl<'a> ResponderContext<'a> {
                 measurement_hash_algo: self.common.negotiate_info.measurement_hash_sel,
                 base_asym_sel: self.common.negotiate_info.base_asym_sel,
                 base_hash_sel: self.common.negotiate_info.base_hash_sel,
+                ext_asym_sel_count: self.common.negotiate_info.ext_asym_sel.is_some() as u8,
+                ext_asym_sel: self.common.negotiate_info.ext_asym_sel.unwrap_or_default(),
+                ext_hash_sel_count: self.common.negotiate_info.ext_hash_sel.is_some() as u8,
+                ext_hash_sel: self.common.negotiate_info.ext_hash_sel.unwrap_or_default(),
                 alg_struct_count: 4,
                 alg_struct: [
                     SpdmAlgStruct {
@@ -142,12 +214,14 @@ impl<'a> ResponderContext<'a> {
                         alg_fixed_count: 2,
                         alg_supported: SpdmAlg::SpdmAlgoDhe(self.common.negotiate_info.dhe_sel),
                         alg_ext_count: 0,
+                        ..Default::default()
                     },
                     SpdmAlgStruct {
                         alg_type: SpdmAlgType::SpdmAlgTypeAEAD,
                         alg_fixed_count: 2,
                         alg_supported: SpdmAlg::SpdmAlgoAead(self.common.negotiate_info.aead_sel),
                         alg_ext_count: 0,
+                        ..Default::default()
                     },
                     SpdmAlgStruct {
                         alg_type: SpdmAlgType::SpdmAlgTypeReqAsym,
@@ -156,6 +230,7 @@ impl<'a> ResponderContext<'a> {
                             self.common.negotiate_info.req_asym_sel,
                         ),
                         alg_ext_count: 0,
+                        ..Default::default()
                     },
                     SpdmAlgStruct {
                         alg_type: SpdmAlgType::SpdmAlgTypeKeySchedule,
@@ -164,6 +239,7 @@ impl<'a> ResponderContext<'a> {
                             self.common.negotiate_info.key_schedule_sel,
                         ),
                         alg_ext_count: 0,
+                        ..Default::default()
                     },
                 ],
             }),
@@ -174,9 +250,89 @@ impl<'a> ResponderContext<'a> {
             .runtime_info
             .message_a
             .append_message(writer.used_slice());
+
+        self.common.runtime_info.connection_state = SpdmConnectionState::SpdmConnectionNegotiated;
+    }
+
+    /// The ERROR to answer with if a class the negotiated capabilities need
+    /// has no common acceptable algorithm in the selection: UnsupportedRequest
+    /// if only our preference lists, minimum security strength or crypto
+    /// support turn down the algorithms `offered` in common, InvalidRequest
+    /// if there are none.
+    fn check_algorithm_selection(&self, offered: &SpdmNegotiateInfo) -> Option<SpdmErrorCode> {
+        let required = self.common.get_required_algorithms();
+        let config_info = &self.common.config_info;
+        let negotiate_info = &self.common.negotiate_info;
+        // (class, no acceptable algorithm, an algorithm in common)
+        let classes = [
+            (
+                "base hash",
+                negotiate_info.base_hash_sel.is_empty() && negotiate_info.ext_hash_sel.is_none(),
+                offered.base_hash_sel.intersects(config_info.base_hash_algo),
+            ),
+            (
+                "measurement specification",
+                required.measurement && negotiate_info.measurement_specification_sel.is_empty(),
+                false,
+            ),
+            (
+                "measurement hash",
+                required.measurement && negotiate_info.measurement_hash_sel.is_empty(),
+                !config_info.measurement_hash_algo.is_empty(),
+            ),
+            (
+                "base asym",
+                required.base_asym
+                    && negotiate_info.base_asym_sel.is_empty()
+                    && negotiate_info.ext_asym_sel.is_none(),
+                offered.base_asym_sel.intersects(config_info.base_asym_algo),
+            ),
+            (
+                "dhe",
+                required.dhe && negotiate_info.dhe_sel.is_empty(),
+                offered.dhe_sel.intersects(config_info.dhe_algo),
+            ),
+            (
+                "aead",
+                required.aead && negotiate_info.aead_sel.is_empty(),
+                offered.aead_sel.intersects(config_info.aead_algo),
+            ),
+            (
+                "req asym",
+                required.req_asym && negotiate_info.req_asym_sel.is_empty(),
+                offered.req_asym_sel.intersects(config_info.req_asym_algo),
+            ),
+            (
+                "key schedule",
+                required.key_schedule && negotiate_info.key_schedule_sel.is_empty(),
+                false,
+            ),
+        ];
+        let (missing, _, in_common) = classes.iter().find(|(_, missing, _)| *missing)?;
+        error!(
+            "!!! negotiate_algorithms : no acceptable {} algorithm !!!\n",
+            missing
+        );
+        if *in_common {
+            Some(SpdmErrorCode::SpdmErrorUnsupportedRequest)
+        } else {
+            Some(SpdmErrorCode::SpdmErrorInvalidRequest)
+        }
     }
 }
 
+/// Our most preferred extended algorithm that the peer offered as well and
+/// that crypto::ext_alg supports, i.e. it knows the size of.
+fn select_ext_algo(
+    ours: &[SpdmExtAlgStruct],
+    peer: &[SpdmExtAlgStruct],
+    get_size: fn(&SpdmExtAlgStruct) -> Option<u16>,
+) -> Option<SpdmExtAlgStruct> {
+    ours.iter()
+        .find(|algo| peer.contains(algo) && get_size(algo).is_some())
+        .copied()
+}
+
 #[cfg(test)]
 mod tests_responder {
     use super::*;
@@ -222,7 +378,9 @@ mod tests_responder {
                 alg_fixed_count: 2,
                 alg_supported: SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::FFDHE_2048),
                 alg_ext_count: 0,
+                ..Default::default()
             }; config::MAX_SPDM_ALG_STRUCT_COUNT],
+            ..Default::default()
         };
         value.spdm_encode(&mut context.common, &mut writer);
 
@@ -342,4 +500,267 @@ mod tests_responder {
             assert_eq!(payload.alg_struct[3].alg_ext_count, 0);
         }
     }
+
+    #[test]
+    fn test_case0_algorithm_priority() {
+        let (config_info, provision_info) = create_info();
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+
+        crypto::asym_sign::register(ASYM_SIGN_IMPL);
+
+        let shared_buffer = SharedBuffer::new();
+        let mut socket_io_transport = FakeSpdmDeviceIoReceve::new(&shared_buffer);
+
+        let mut context = responder::ResponderContext::new(
+            &mut socket_io_transport,
+            pcidoe_transport_encap,
+            config_info,
+            provision_info,
+        );
+        context.common.config_info.base_hash_algo =
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256 | SpdmBaseHashAlgo::TPM_ALG_SHA_384;
+        context.common.config_info.base_hash_priority[0] = SpdmBaseHashAlgo::TPM_ALG_SHA_256;
+        context.common.config_info.base_hash_priority[1] = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
+
+        fn negotiate(
+            context: &mut responder::ResponderContext,
+            base_hash_algo: SpdmBaseHashAlgo,
+        ) -> SpdmMessage {
+            context.common.reset_runtime_info();
+            let bytes = &mut [0u8; 1024];
+            let mut writer = Writer::init(bytes);
+            let request = SpdmMessage {
+                header: SpdmMessageHeader {
+                    version: SpdmVersion::SpdmVersion11,
+                    request_response_code: SpdmResponseResponseCode::SpdmRequestNegotiateAlgorithms,
+                },
+                payload: SpdmMessagePayload::SpdmNegotiateAlgorithmsRequest(
+                    SpdmNegotiateAlgorithmsRequestPayload {
+                        measurement_specification: SpdmMeasurementSpecification::DMTF,
+                        base_asym_algo: SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+                        base_hash_algo,
+                        alg_struct_count: 0,
+                        alg_struct: [SpdmAlgStruct::default(); config::MAX_SPDM_ALG_STRUCT_COUNT],
+                        ..Default::default()
+                    },
+                ),
+            };
+            request.spdm_encode(&mut context.common, &mut writer);
+            let used = writer.used();
+
+            let response = &mut [0u8; 1024];
+            let mut writer = Writer::init(response);
+            context.write_spdm_algorithm(&bytes[..used], &mut writer);
+            let used = writer.used();
+            let mut reader = Reader::init(&response[..used]);
+            SpdmMessage::spdm_read(&mut context.common, &mut reader).unwrap()
+        }
+
+        // our order, not the built-in one
+        let message = negotiate(
+            &mut context,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256 | SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+        );
+        if let SpdmMessagePayload::SpdmAlgorithmsResponse(payload) = &message.payload {
+            assert_eq!(payload.base_hash_sel, SpdmBaseHashAlgo::TPM_ALG_SHA_256);
+        } else {
+            panic!("unexpected response {:?}", message.header);
+        }
+
+        // SHA256 is below the floor
+        context.common.config_info.min_security_strength = 192;
+        let message = negotiate(
+            &mut context,
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256 | SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+        );
+        if let SpdmMessagePayload::SpdmAlgorithmsResponse(payload) = &message.payload {
+            assert_eq!(payload.base_hash_sel, SpdmBaseHashAlgo::TPM_ALG_SHA_384);
+        } else {
+            panic!("unexpected response {:?}", message.header);
+        }
+        assert_eq!(
+            context.common.runtime_info.connection_state,
+            SpdmConnectionState::SpdmConnectionNegotiated
+        );
+
+        // only one below the floor in common
+        let message = negotiate(&mut context, SpdmBaseHashAlgo::TPM_ALG_SHA_256);
+        assert_eq!(
+            message.header.request_response_code,
+            SpdmResponseResponseCode::SpdmResponseError
+        );
+        if let SpdmMessagePayload::SpdmErrorResponse(payload) = &message.payload {
+            assert_eq!(
+                payload.error_code,
+                SpdmErrorCode::SpdmErrorUnsupportedRequest
+            );
+            assert_eq!(
+                payload.error_data,
+                SpdmResponseResponseCode::SpdmRequestNegotiateAlgorithms.get_u8()
+            );
+        } else {
+            panic!("unexpected response {:?}", message.header);
+        }
+        assert_eq!(
+            context.common.runtime_info.connection_state,
+            SpdmConnectionState::SpdmConnectionNotStarted
+        );
+
+        // nothing in common
+        let message = negotiate(&mut context, SpdmBaseHashAlgo::TPM_ALG_SHA_512);
+        if let SpdmMessagePayload::SpdmErrorResponse(payload) = &message.payload {
+            assert_eq!(payload.error_code, SpdmErrorCode::SpdmErrorInvalidRequest);
+        } else {
+            panic!("unexpected response {:?}", message.header);
+        }
+    }
+
+    #[test]
+    fn test_case0_measurement_hash_priority() {
+        let (config_info, provision_info) = create_info();
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+
+        crypto::asym_sign::register(ASYM_SIGN_IMPL);
+
+        let shared_buffer = SharedBuffer::new();
+        let mut socket_io_transport = FakeSpdmDeviceIoReceve::new(&shared_buffer);
+
+        let mut context = responder::ResponderContext::new(
+            &mut socket_io_transport,
+            pcidoe_transport_encap,
+            config_info,
+            provision_info,
+        );
+        context.common.config_info.measurement_hash_algo = SpdmMeasurementHashAlgo::RAW_BIT_STREAM
+            | SpdmMeasurementHashAlgo::TPM_ALG_SHA_256
+            | SpdmMeasurementHashAlgo::TPM_ALG_SHA_384;
+
+        fn measurement_hash_sel(
+            context: &mut responder::ResponderContext,
+        ) -> SpdmMeasurementHashAlgo {
+            context.common.reset_runtime_info();
+            let bytes = &mut [0u8; 1024];
+            let mut writer = Writer::init(bytes);
+            let request = SpdmMessage {
+                header: SpdmMessageHeader {
+                    version: SpdmVersion::SpdmVersion11,
+                    request_response_code: SpdmResponseResponseCode::SpdmRequestNegotiateAlgorithms,
+                },
+                payload: SpdmMessagePayload::SpdmNegotiateAlgorithmsRequest(
+                    SpdmNegotiateAlgorithmsRequestPayload {
+                        measurement_specification: SpdmMeasurementSpecification::DMTF,
+                        base_asym_algo: SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+                        base_hash_algo: SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+                        ..Default::default()
+                    },
+                ),
+            };
+            request.spdm_encode(&mut context.common, &mut writer);
+            let used = writer.used();
+
+            let response = &mut [0u8; 1024];
+            let mut writer = Writer::init(response);
+            context.write_spdm_algorithm(&bytes[..used], &mut writer);
+            let used = writer.used();
+            let mut reader = Reader::init(&response[..used]);
+            match SpdmMessage::spdm_read(&mut context.common, &mut reader)
+                .unwrap()
+                .payload
+            {
+                SpdmMessagePayload::SpdmAlgorithmsResponse(payload) => {
+                    payload.measurement_hash_algo
+                }
+                _ => panic!("no ALGORITHMS"),
+            }
+        }
+
+        // the built-in order
+        assert_eq!(
+            measurement_hash_sel(&mut context),
+            SpdmMeasurementHashAlgo::TPM_ALG_SHA_384
+        );
+
+        // our order
+        context.common.config_info.measurement_hash_priority[0] =
+            SpdmMeasurementHashAlgo::TPM_ALG_SHA_256;
+        context.common.config_info.measurement_hash_priority[1] =
+            SpdmMeasurementHashAlgo::RAW_BIT_STREAM;
+        assert_eq!(
+            measurement_hash_sel(&mut context),
+            SpdmMeasurementHashAlgo::TPM_ALG_SHA_256
+        );
+
+        // a raw bit stream is not below any floor
+        context.common.config_info.min_security_strength = 192;
+        assert_eq!(
+            measurement_hash_sel(&mut context),
+            SpdmMeasurementHashAlgo::RAW_BIT_STREAM
+        );
+    }
+    #[test]
+    fn test_case0_supported_algorithm() {
+        let (config_info, provision_info) = create_info();
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+
+        crypto::asym_sign::register(ASYM_SIGN_IMPL);
+
+        let shared_buffer = SharedBuffer::new();
+        let mut socket_io_transport = FakeSpdmDeviceIoReceve::new(&shared_buffer);
+
+        let mut context = responder::ResponderContext::new(
+            &mut socket_io_transport,
+            pcidoe_transport_encap,
+            config_info,
+            provision_info,
+        );
+        // no backend has the SM2 key exchange, preferred or not
+        context.common.config_info.dhe_algo = SpdmDheAlgo::SM2_P256 | SpdmDheAlgo::SECP_384_R1;
+        context.common.config_info.dhe_priority[0] = SpdmDheAlgo::SM2_P256;
+        context.common.config_info.dhe_priority[1] = SpdmDheAlgo::SECP_384_R1;
+        assert!(!crypto::dhe::supported_algo().contains(SpdmDheAlgo::SM2_P256));
+
+        let bytes = &mut [0u8; 1024];
+        let mut writer = Writer::init(bytes);
+        let mut alg_struct = [SpdmAlgStruct::default(); config::MAX_SPDM_ALG_STRUCT_COUNT];
+        alg_struct[0] = SpdmAlgStruct {
+            alg_type: SpdmAlgType::SpdmAlgTypeDHE,
+            alg_fixed_count: 2,
+            alg_supported: SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::SM2_P256 | SpdmDheAlgo::SECP_384_R1),
+            alg_ext_count: 0,
+            ..Default::default()
+        };
+        let request = SpdmMessage {
+            header: SpdmMessageHeader {
+                version: SpdmVersion::SpdmVersion11,
+                request_response_code: SpdmResponseResponseCode::SpdmRequestNegotiateAlgorithms,
+            },
+            payload: SpdmMessagePayload::SpdmNegotiateAlgorithmsRequest(
+                SpdmNegotiateAlgorithmsRequestPayload {
+                    measurement_specification: SpdmMeasurementSpecification::DMTF,
+                    base_asym_algo: SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+                    base_hash_algo: SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+                    alg_struct_count: 1,
+                    alg_struct,
+                    ..Default::default()
+                },
+            ),
+        };
+        request.spdm_encode(&mut context.common, &mut writer);
+        let used = writer.used();
+
+        let response = &mut [0u8; 1024];
+        let mut writer = Writer::init(response);
+        context.write_spdm_algorithm(&bytes[..used], &mut writer);
+        let used = writer.used();
+        let mut reader = Reader::init(&response[..used]);
+        let message = SpdmMessage::spdm_read(&mut context.common, &mut reader).unwrap();
+        if let SpdmMessagePayload::SpdmAlgorithmsResponse(payload) = &message.payload {
+            assert_eq!(
+                payload.alg_struct[0].alg_supported,
+                SpdmAlg::SpdmAlgoDhe(SpdmDheAlgo::SECP_384_R1)
+            );
+        } else {
+            panic!("unexpected response {:?}", message.header);
+        }
+    }
 }
@@ -6,10 +6,19 @@ use crate::responder::*;
 
 impl<'a> ResponderContext<'a> {
     pub fn handle_spdm_capability(&mut self, bytes: &[u8]) {
+        let _ = self
+            .send_response(|context, response| context.process_spdm_capability(bytes, response));
+    }
+
+    pub fn process_spdm_capability(
+        &mut self,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<usize> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         self.write_spdm_capability_response(bytes, &mut writer);
-        let _ = self.send_message(writer.used_slice());
+        self.encode_message(writer.used_slice(), response)
     }
 
     pub fn write_spdm_capability_response(&mut self, bytes: &[u8], writer: &mut Writer) {
@@ -63,6 +72,9 @@ impl<'a> ResponderContext<'a> {
             .runtime_info
             .message_a
             .append_message(writer.used_slice());
+
+        self.common.runtime_info.connection_state =
+            SpdmConnectionState::SpdmConnectionAfterCapabilities;
     }
 }
 
@@ -6,13 +6,28 @@ use crate::responder::*;
 
 impl<'a> ResponderContext<'a> {
     pub fn handle_spdm_certificate(&mut self, bytes: &[u8]) {
+        let _ = self
+            .send_response(|context, response| context.process_spdm_certificate(bytes, response));
+    }
+
+    pub fn process_spdm_certificate(
+        &mut self,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<usize> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         self.write_spdm_certificate_response(bytes, &mut writer);
-        let _ = self.send_message(writer.used_slice());
+        self.encode_message(writer.used_slice(), response)
     }
 
     pub fn write_spdm_certificate_response(&mut self, bytes: &[u8], writer: &mut Writer) {
+        if !self
+            .check_request_supported(SpdmResponseResponseCode::SpdmRequestGetCertificate, writer)
+        {
+            return;
+        }
+
         let mut reader = Reader::init(bytes);
         SpdmMessageHeader::read(&mut reader);
 
@@ -107,6 +122,7 @@ mod tests_responder {
             config_info,
             provision_info,
         );
+        select_capabilities(&mut context.common);
         context.common.provision_info.my_cert_chain = Some(SpdmCertChainData {
             data_size: 512u16,
             data: [0u8; config::MAX_SPDM_CERT_CHAIN_DATA_SIZE],
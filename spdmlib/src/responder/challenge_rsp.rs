@@ -7,13 +7,26 @@ use crate::responder::*;
 
 impl<'a> ResponderContext<'a> {
     pub fn handle_spdm_challenge(&mut self, bytes: &[u8]) {
+        let _ =
+            self.send_response(|context, response| context.process_spdm_challenge(bytes, response));
+    }
+
+    pub fn process_spdm_challenge(
+        &mut self,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<usize> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         self.write_spdm_challenge_response(bytes, &mut writer);
-        let _ = self.send_message(writer.used_slice());
+        self.encode_message(writer.used_slice(), response)
     }
 
     pub fn write_spdm_challenge_response(&mut self, bytes: &[u8], writer: &mut Writer) {
+        if !self.check_request_supported(SpdmResponseResponseCode::SpdmRequestChallenge, writer) {
+            return;
+        }
+
         let mut reader = Reader::init(bytes);
         SpdmMessageHeader::read(&mut reader);
 
@@ -53,11 +66,7 @@ impl<'a> ResponderContext<'a> {
         let _ = crypto::rand::get_random(&mut nonce);
 
         let my_cert_chain = self.common.provision_info.my_cert_chain.unwrap();
-        let cert_chain_hash = crypto::hash::hash_all(
-            self.common.negotiate_info.base_hash_sel,
-            my_cert_chain.as_ref(),
-        )
-        .unwrap();
+        let cert_chain_hash = self.common.hash_all(my_cert_chain.as_ref()).unwrap();
 
         let response = SpdmMessage {
             header: SpdmMessageHeader {
@@ -72,7 +81,7 @@ impl<'a> ResponderContext<'a> {
                     cert_chain_hash,
                     nonce: SpdmNonceStruct { data: nonce },
                     measurement_summary_hash: SpdmDigestStruct {
-                        data_size: self.common.negotiate_info.base_hash_sel.get_size(),
+                        data_size: self.common.get_hash_size(),
                         data: [0xaa; SPDM_MAX_HASH_SIZE],
                     },
                     opaque: SpdmOpaqueStruct {
@@ -80,7 +89,7 @@ impl<'a> ResponderContext<'a> {
                         data: [0u8; config::MAX_SPDM_OPAQUE_SIZE],
                     },
                     signature: SpdmSignatureStruct {
-                        data_size: self.common.negotiate_info.base_asym_sel.get_size(),
+                        data_size: self.common.get_asym_key_size(),
                         data: [0xbb; SPDM_MAX_ASYM_KEY_SIZE],
                     },
                 },
@@ -90,7 +99,7 @@ impl<'a> ResponderContext<'a> {
         let used = writer.used();
 
         // generat signature
-        let base_asym_size = self.common.negotiate_info.base_asym_sel.get_size() as usize;
+        let base_asym_size = self.common.get_asym_key_size() as usize;
         let temp_used = used - base_asym_size;
         self.common
             .runtime_info
@@ -99,12 +108,17 @@ impl<'a> ResponderContext<'a> {
 
         let signature = self.common.generate_challenge_auth_signature();
         if signature.is_err() {
-            self.send_spdm_error(SpdmErrorCode::SpdmErrorInvalidRequest, 0);
+            // an error instead of the response written so far
+            writer.clear();
+            self.write_spdm_error(SpdmErrorCode::SpdmErrorInvalidRequest, 0, writer);
             return;
         }
         let signature = signature.unwrap();
         // patch the message before send
         writer.mut_used_slice()[(used - base_asym_size)..used].copy_from_slice(signature.as_ref());
+
+        self.common.runtime_info.connection_state =
+            SpdmConnectionState::SpdmConnectionAuthenticated;
     }
 }
 
@@ -132,6 +146,7 @@ mod tests_responder {
             config_info,
             provision_info,
         );
+        select_capabilities(&mut context.common);
         context.common.provision_info.my_cert_chain = Some(SpdmCertChainData {
             data_size: 512u16,
             data: [0u8; config::MAX_SPDM_CERT_CHAIN_DATA_SIZE],
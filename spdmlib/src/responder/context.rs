@@ -2,20 +2,31 @@
 //
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
-use crate::common::{self, SpdmDeviceIo, SpdmTransportEncap};
+use crate::common::{
+    self, SpdmAppMessageType, SpdmAsyncDeviceIo, SpdmAsyncReceive, SpdmAsyncSend,
+    SpdmConnectionState, SpdmDeviceIo, SpdmTransportEncap,
+};
 use crate::config;
 use crate::error::SpdmResult;
 use crate::msgs::*;
+use crate::session::SpdmSessionState;
 use codec::{Codec, Reader};
 
+/// Handle a non-SPDM application message received in an established session.
+/// Return the size of the response, of the same message type, or None if
+/// nothing is sent back.
+pub type SpdmAppMessageHandler =
+    fn(session_id: u32, message_type: u8, request: &[u8], response: &mut [u8]) -> Option<usize>;
+
 pub struct ResponderContext<'a> {
     pub common: common::SpdmContext<'a>,
+    pub app_message_handler: Option<SpdmAppMessageHandler>,
 }
 
 impl<'a> ResponderContext<'a> {
     pub fn new(
-        device_io: &'a mut dyn SpdmDeviceIo,
-        transport_encap: &'a mut dyn SpdmTransportEncap,
+        device_io: &'a mut (dyn SpdmDeviceIo + Send),
+        transport_encap: &'a mut (dyn SpdmTransportEncap + Send),
         config_info: common::SpdmConfigInfo,
         provision_info: common::SpdmProvisionInfo,
     ) -> Self {
@@ -26,228 +37,503 @@ impl<'a> ResponderContext<'a> {
                 config_info,
                 provision_info,
             ),
+            app_message_handler: None,
         }
     }
 
-    pub fn send_message(&mut self, send_buffer: &[u8]) -> SpdmResult {
-        let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let used = self.common.encap(send_buffer, &mut transport_buffer)?;
-        self.common.device_io.send(&transport_buffer[..used])
+    /// A responder driven with process_transport_message only.
+    pub fn new_without_device_io(
+        transport_encap: &'a mut (dyn SpdmTransportEncap + Send),
+        config_info: common::SpdmConfigInfo,
+        provision_info: common::SpdmProvisionInfo,
+    ) -> Self {
+        ResponderContext {
+            common: common::SpdmContext::new_without_device_io(
+                transport_encap,
+                config_info,
+                provision_info,
+            ),
+            app_message_handler: None,
+        }
     }
 
-    pub fn send_secured_message(&mut self, session_id: u32, send_buffer: &[u8]) -> SpdmResult {
-        let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let used = self.common.encode_secured_message(
+    pub fn register_app_message_handler(&mut self, handler: SpdmAppMessageHandler) {
+        self.app_message_handler = Some(handler);
+    }
+
+    /// Encode the SPDM message in `send_buffer` into `transport_buffer`,
+    /// return the size of the transport message.
+    pub fn encode_message(
+        &mut self,
+        send_buffer: &[u8],
+        transport_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        self.common.encap(send_buffer, transport_buffer)
+    }
+
+    pub fn encode_secured_message(
+        &mut self,
+        session_id: u32,
+        send_buffer: &[u8],
+        transport_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        self.common
+            .encode_secured_message(session_id, send_buffer, transport_buffer, false)
+    }
+
+    pub fn encode_secured_app_message(
+        &mut self,
+        session_id: u32,
+        message_type: u8,
+        send_buffer: &[u8],
+        transport_buffer: &mut [u8],
+    ) -> SpdmResult<usize> {
+        self.common.encode_secured_app_message(
             session_id,
+            message_type,
             send_buffer,
-            &mut transport_buffer,
+            transport_buffer,
             false,
-        )?;
-        self.common.device_io.send(&transport_buffer[..used])
+        )
+    }
+
+    /// Send the transport message `encode` writes, if any, with the device IO.
+    pub fn send_response<F>(&mut self, encode: F) -> SpdmResult
+    where
+        F: FnOnce(&mut Self, &mut [u8]) -> SpdmResult<usize>,
+    {
+        let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let used = encode(self, &mut transport_buffer)?;
+        if used == 0 {
+            return Ok(());
+        }
+        self.common.device_io_send(&transport_buffer[..used])
     }
 
+    pub fn send_message(&mut self, send_buffer: &[u8]) -> SpdmResult {
+        self.send_response(|context, transport_buffer| {
+            context.encode_message(send_buffer, transport_buffer)
+        })
+    }
+
+    pub fn send_secured_message(&mut self, session_id: u32, send_buffer: &[u8]) -> SpdmResult {
+        self.send_response(|context, transport_buffer| {
+            context.encode_secured_message(session_id, send_buffer, transport_buffer)
+        })
+    }
+
+    pub fn send_secured_app_message(
+        &mut self,
+        session_id: u32,
+        message_type: u8,
+        send_buffer: &[u8],
+    ) -> SpdmResult {
+        self.send_response(|context, transport_buffer| {
+            context.encode_secured_app_message(
+                session_id,
+                message_type,
+                send_buffer,
+                transport_buffer,
+            )
+        })
+    }
+
+    /// Receive one message with the device IO, process it and send the
+    /// response. The message is returned if it can not be processed, or
+    /// the response can not be sent.
     pub fn process_message(&mut self) -> Result<bool, (usize, [u8; 1024])> {
+        info!("receive_message!\n");
         let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        match self.receive_message(&mut receive_buffer[..]) {
-            Ok((used, secured_message)) => {
-                if secured_message {
-                    let mut read = Reader::init(&receive_buffer[0..used]);
-                    let session_id = u32::read(&mut read).ok_or((used, receive_buffer))?;
+        let used = self
+            .common
+            .device_io_receive(&mut receive_buffer)
+            .map_err(|used| (used, receive_buffer))?;
 
-                    let spdm_session = self
-                        .common
-                        .get_session_via_id(session_id)
-                        .ok_or((used, receive_buffer))?;
-
-                    let mut app_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-
-                    let decode_size = spdm_session.decode_spdm_secured_message(
-                        &receive_buffer[..used],
-                        &mut app_buffer,
-                        true,
-                    );
-                    if decode_size.is_err() {
-                        return Err((used, receive_buffer));
-                    }
-                    let decode_size = decode_size.unwrap();
-
-                    let mut spdm_buffer = [0u8; config::MAX_SPDM_MESSAGE_BUFFER_SIZE];
-                    let decode_size = self
-                        .common
-                        .transport_encap
-                        .decap_app(&app_buffer[0..decode_size], &mut spdm_buffer);
-                    if decode_size.is_err() {
-                        return Err((used, receive_buffer));
-                    }
-                    let decode_size = decode_size.unwrap();
-
-                    Ok(self.dispatch_secured_message(session_id, &spdm_buffer[0..decode_size]))
-                } else {
-                    Ok(self.dispatch_message(&receive_buffer[0..used]))
-                }
-            }
-            Err(used) => Err((used, receive_buffer)),
+        let mut response_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let (response_size, handled) = self
+            .process_transport_message(&receive_buffer[..used], &mut response_buffer)
+            .map_err(|_| (used, receive_buffer))?;
+        if response_size != 0 {
+            self.common
+                .device_io_send(&response_buffer[..response_size])
+                .map_err(|_| (used, receive_buffer))?;
         }
+        Ok(handled)
     }
 
-    fn receive_message(&mut self, receive_buffer: &mut [u8]) -> Result<(usize, bool), usize> {
-        info!("receive_message!\n");
+    /// process_message with an SpdmAsyncDeviceIo.
+    pub async fn process_message_async(
+        &mut self,
+        device_io: &mut (dyn SpdmAsyncDeviceIo + Send),
+    ) -> Result<bool, (usize, [u8; config::MAX_SPDM_TRANSPORT_SIZE])> {
+        info!("receive_message_async!\n");
+        let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let used = match SpdmAsyncReceive::new(device_io, &mut receive_buffer).await {
+            Ok(used) => used,
+            Err(used) => return Err((used, receive_buffer)),
+        };
 
-        let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let used = self.common.device_io.receive(receive_buffer)?;
+        let mut response_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let (response_size, handled) = self
+            .process_transport_message(&receive_buffer[..used], &mut response_buffer)
+            .map_err(|_| (used, receive_buffer))?;
+        if response_size != 0 {
+            SpdmAsyncSend::new(device_io, &response_buffer[..response_size])
+                .await
+                .map_err(|_| (used, receive_buffer))?;
+        }
+        Ok(handled)
+    }
 
+    /// Process one transport message from the requester, without device IO.
+    ///
+    /// The transport message to send back is written to `response`. Return
+    /// its size, 0 if nothing is sent back, and whether the request was
+    /// handled. It is an error if the message can not be decoded, or it is
+    /// secured and there is no such session, or the response does not fit.
+    pub fn process_transport_message(
+        &mut self,
+        request: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<(usize, bool)> {
+        let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let (used, secured_message) = self
             .common
             .transport_encap
-            .decap(&receive_buffer[..used], &mut transport_buffer)
-            .map_err(|_| used)?;
+            .decap(request, &mut receive_buffer)?;
+        if !secured_message {
+            return self.dispatch_message(&receive_buffer[0..used], response);
+        }
+
+        let mut read = Reader::init(&receive_buffer[0..used]);
+        let session_id = u32::read(&mut read).ok_or(spdm_err!(EIO))?;
+
+        let spdm_session = self
+            .common
+            .get_session_via_id(session_id)
+            .ok_or(spdm_err!(EINVAL))?;
+        let established =
+            spdm_session.get_session_state() == SpdmSessionState::SpdmSessionEstablished;
+
+        // A session in handshake carries SPDM only, and what a message
+        // carries is only known after decryption. Decrypt with a copy of such
+        // a session, so that anything else does not advance its sequence
+        // number.
+        let mut app_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let mut handshake_session = None;
+        let decode_size = if established {
+            spdm_session.decode_spdm_secured_message(
+                &receive_buffer[..used],
+                &mut app_buffer,
+                true,
+            )?
+        } else {
+            let mut session = *spdm_session;
+            let decode_size = session.decode_spdm_secured_message(
+                &receive_buffer[..used],
+                &mut app_buffer,
+                true,
+            )?;
+            handshake_session = Some(session);
+            decode_size
+        };
+
+        let mut spdm_buffer = [0u8; config::MAX_SPDM_MESSAGE_BUFFER_SIZE];
+        let (decode_size, message_type) = self
+            .common
+            .transport_encap
+            .decap_app_message(&app_buffer[0..decode_size], &mut spdm_buffer)?;
+
+        match message_type {
+            SpdmAppMessageType::Spdm => {
+                if let Some(session) = handshake_session {
+                    *self
+                        .common
+                        .get_session_via_id(session_id)
+                        .ok_or(spdm_err!(EINVAL))? = session;
+                }
+                self.dispatch_secured_message(session_id, &spdm_buffer[0..decode_size], response)
+            }
+            SpdmAppMessageType::Other(_) if !established => Ok((0, false)),
+            SpdmAppMessageType::Other(message_type) => self.dispatch_secured_app_message(
+                session_id,
+                message_type,
+                &spdm_buffer[0..decode_size],
+                response,
+            ),
+        }
+    }
+
+    fn dispatch_secured_app_message(
+        &mut self,
+        session_id: u32,
+        message_type: u8,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<(usize, bool)> {
+        let handler = match self.app_message_handler {
+            Some(handler) => handler,
+            None => return Ok((0, false)),
+        };
 
-        receive_buffer[..used].copy_from_slice(&transport_buffer[..used]);
-        Ok((used, secured_message))
+        let mut app_response = [0u8; config::MAX_SPDM_MESSAGE_BUFFER_SIZE];
+        match handler(session_id, message_type, bytes, &mut app_response) {
+            Some(used) if used > app_response.len() => {
+                error!(
+                    "!!! app message handler : response size {:x} too large !!!\n",
+                    used
+                );
+                Ok((0, false))
+            }
+            Some(used) => Ok((
+                self.encode_secured_app_message(
+                    session_id,
+                    message_type,
+                    &app_response[..used],
+                    response,
+                )?,
+                true,
+            )),
+            None => Ok((0, true)),
+        }
     }
 
-    fn dispatch_secured_message(&mut self, session_id: u32, bytes: &[u8]) -> bool {
+    fn dispatch_secured_message(
+        &mut self,
+        session_id: u32,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<(usize, bool)> {
         let mut reader = Reader::init(bytes);
         match SpdmMessageHeader::read(&mut reader) {
             Some(message_header) => match message_header.request_response_code {
-                SpdmResponseResponseCode::SpdmRequestGetVersion => false,
-                SpdmResponseResponseCode::SpdmRequestGetCapabilities => false,
-                SpdmResponseResponseCode::SpdmRequestNegotiateAlgorithms => false,
-                SpdmResponseResponseCode::SpdmRequestGetDigests => false,
-                SpdmResponseResponseCode::SpdmRequestGetCertificate => false,
-                SpdmResponseResponseCode::SpdmRequestChallenge => false,
-                SpdmResponseResponseCode::SpdmRequestGetMeasurements => false,
+                SpdmResponseResponseCode::SpdmRequestGetVersion => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmRequestGetCapabilities => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmRequestNegotiateAlgorithms => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmRequestGetDigests => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmRequestGetCertificate => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmRequestChallenge => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmRequestGetMeasurements => Ok((0, false)),
 
-                SpdmResponseResponseCode::SpdmRequestKeyExchange => false,
+                SpdmResponseResponseCode::SpdmRequestKeyExchange => Ok((0, false)),
 
                 SpdmResponseResponseCode::SpdmRequestFinish => {
-                    self.handle_spdm_finish(session_id, bytes);
-                    true
+                    Ok((self.process_spdm_finish(session_id, bytes, response)?, true))
                 }
 
-                SpdmResponseResponseCode::SpdmRequestPskExchange => false,
+                SpdmResponseResponseCode::SpdmRequestPskExchange => Ok((0, false)),
 
-                SpdmResponseResponseCode::SpdmRequestPskFinish => {
-                    self.handle_spdm_psk_finish(session_id, bytes);
-                    true
-                }
+                SpdmResponseResponseCode::SpdmRequestPskFinish => Ok((
+                    self.process_spdm_psk_finish(session_id, bytes, response)?,
+                    true,
+                )),
 
-                SpdmResponseResponseCode::SpdmRequestHeartbeat => {
-                    self.handle_spdm_heartbeat(session_id, bytes);
-                    true
-                }
+                SpdmResponseResponseCode::SpdmRequestHeartbeat => Ok((
+                    self.process_spdm_heartbeat(session_id, bytes, response)?,
+                    true,
+                )),
 
-                SpdmResponseResponseCode::SpdmRequestKeyUpdate => {
-                    self.handle_spdm_key_update(session_id, bytes);
-                    true
-                }
+                SpdmResponseResponseCode::SpdmRequestKeyUpdate => Ok((
+                    self.process_spdm_key_update(session_id, bytes, response)?,
+                    true,
+                )),
 
-                SpdmResponseResponseCode::SpdmRequestEndSession => {
-                    self.handle_spdm_end_session(session_id, bytes);
-                    true
-                }
+                SpdmResponseResponseCode::SpdmRequestEndSession => Ok((
+                    self.process_spdm_end_session(session_id, bytes, response)?,
+                    true,
+                )),
 
-                SpdmResponseResponseCode::SpdmResponseDigests => false,
-                SpdmResponseResponseCode::SpdmResponseCertificate => false,
-                SpdmResponseResponseCode::SpdmResponseChallengeAuth => false,
-                SpdmResponseResponseCode::SpdmResponseVersion => false,
-                SpdmResponseResponseCode::SpdmResponseMeasurements => false,
-                SpdmResponseResponseCode::SpdmResponseCapabilities => false,
-                SpdmResponseResponseCode::SpdmResponseAlgorithms => false,
-                SpdmResponseResponseCode::SpdmResponseKeyExchangeRsp => false,
-                SpdmResponseResponseCode::SpdmResponseFinishRsp => false,
-                SpdmResponseResponseCode::SpdmResponsePskExchangeRsp => false,
-                SpdmResponseResponseCode::SpdmResponsePskFinishRsp => false,
-                SpdmResponseResponseCode::SpdmResponseHeartbeatAck => false,
-                SpdmResponseResponseCode::SpdmResponseKeyUpdateAck => false,
-                SpdmResponseResponseCode::SpdmResponseEndSessionAck => false,
-                SpdmResponseResponseCode::SpdmResponseError => false,
-                SpdmResponseResponseCode::Unknown(_) => false,
+                SpdmResponseResponseCode::SpdmResponseDigests => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseCertificate => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseChallengeAuth => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseVersion => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseMeasurements => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseCapabilities => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseAlgorithms => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseKeyExchangeRsp => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseFinishRsp => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponsePskExchangeRsp => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponsePskFinishRsp => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseHeartbeatAck => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseKeyUpdateAck => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseEndSessionAck => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseError => Ok((0, false)),
+                SpdmResponseResponseCode::Unknown(_) => Ok((0, false)),
             },
-            None => false,
+            None => Ok((0, false)),
+        }
+    }
+
+    /// Whether a request may come in the connection state: VCA in order,
+    /// and the other requests after the algorithms are negotiated. The
+    /// requests of a session are never expected outside a session.
+    fn is_expected_request(&self, request_response_code: SpdmResponseResponseCode) -> bool {
+        let connection_state = self.common.runtime_info.connection_state;
+        match request_response_code {
+            SpdmResponseResponseCode::SpdmRequestGetVersion => true,
+            SpdmResponseResponseCode::SpdmRequestGetCapabilities => {
+                connection_state == SpdmConnectionState::SpdmConnectionAfterVersion
+            }
+            SpdmResponseResponseCode::SpdmRequestNegotiateAlgorithms => {
+                connection_state == SpdmConnectionState::SpdmConnectionAfterCapabilities
+            }
+            SpdmResponseResponseCode::SpdmRequestGetDigests
+            | SpdmResponseResponseCode::SpdmRequestGetCertificate
+            | SpdmResponseResponseCode::SpdmRequestChallenge
+            | SpdmResponseResponseCode::SpdmRequestGetMeasurements
+            | SpdmResponseResponseCode::SpdmRequestKeyExchange
+            | SpdmResponseResponseCode::SpdmRequestPskExchange => {
+                connection_state >= SpdmConnectionState::SpdmConnectionNegotiated
+            }
+            SpdmResponseResponseCode::SpdmRequestFinish
+            | SpdmResponseResponseCode::SpdmRequestPskFinish
+            | SpdmResponseResponseCode::SpdmRequestHeartbeat
+            | SpdmResponseResponseCode::SpdmRequestKeyUpdate
+            | SpdmResponseResponseCode::SpdmRequestEndSession
+            | SpdmResponseResponseCode::SpdmResponseDigests
+            | SpdmResponseResponseCode::SpdmResponseCertificate
+            | SpdmResponseResponseCode::SpdmResponseChallengeAuth
+            | SpdmResponseResponseCode::SpdmResponseVersion
+            | SpdmResponseResponseCode::SpdmResponseMeasurements
+            | SpdmResponseResponseCode::SpdmResponseCapabilities
+            | SpdmResponseResponseCode::SpdmResponseAlgorithms
+            | SpdmResponseResponseCode::SpdmResponseKeyExchangeRsp
+            | SpdmResponseResponseCode::SpdmResponseFinishRsp
+            | SpdmResponseResponseCode::SpdmResponsePskExchangeRsp
+            | SpdmResponseResponseCode::SpdmResponsePskFinishRsp
+            | SpdmResponseResponseCode::SpdmResponseHeartbeatAck
+            | SpdmResponseResponseCode::SpdmResponseKeyUpdateAck
+            | SpdmResponseResponseCode::SpdmResponseEndSessionAck
+            | SpdmResponseResponseCode::SpdmResponseError
+            | SpdmResponseResponseCode::Unknown(_) => false,
         }
     }
 
-    pub fn dispatch_message(&mut self, bytes: &[u8]) -> bool {
+    pub fn dispatch_message(
+        &mut self,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<(usize, bool)> {
         let mut reader = Reader::init(bytes);
-        match SpdmMessageHeader::read(&mut reader) {
+        let message_header = SpdmMessageHeader::read(&mut reader);
+        if let Some(message_header) = message_header {
+            if !is_implemented_request(message_header.request_response_code) {
+                error!(
+                    "!!! unsupported request : {:?}\n",
+                    message_header.request_response_code
+                );
+                return Ok((
+                    self.encode_spdm_error(
+                        SpdmErrorCode::SpdmErrorUnsupportedRequest,
+                        message_header.request_response_code.get_u8(),
+                        response,
+                    )?,
+                    true,
+                ));
+            }
+            if !self.is_expected_request(message_header.request_response_code) {
+                error!(
+                    "!!! unexpected request : {:?}\n",
+                    message_header.request_response_code
+                );
+                return Ok((
+                    self.encode_spdm_error(SpdmErrorCode::SpdmErrorUnexpectedRequest, 0, response)?,
+                    true,
+                ));
+            }
+        }
+        match message_header {
             Some(message_header) => match message_header.request_response_code {
                 SpdmResponseResponseCode::SpdmRequestGetVersion => {
-                    self.handle_spdm_version(bytes);
-                    true
+                    Ok((self.process_spdm_version(bytes, response)?, true))
                 }
                 SpdmResponseResponseCode::SpdmRequestGetCapabilities => {
-                    self.handle_spdm_capability(bytes);
-                    true
+                    Ok((self.process_spdm_capability(bytes, response)?, true))
                 }
                 SpdmResponseResponseCode::SpdmRequestNegotiateAlgorithms => {
-                    self.handle_spdm_algorithm(bytes);
-                    true
+                    Ok((self.process_spdm_algorithm(bytes, response)?, true))
                 }
                 SpdmResponseResponseCode::SpdmRequestGetDigests => {
-                    self.handle_spdm_digest(bytes);
-                    true
+                    Ok((self.process_spdm_digest(bytes, response)?, true))
                 }
                 SpdmResponseResponseCode::SpdmRequestGetCertificate => {
-                    self.handle_spdm_certificate(bytes);
-                    true
+                    Ok((self.process_spdm_certificate(bytes, response)?, true))
                 }
                 SpdmResponseResponseCode::SpdmRequestChallenge => {
-                    self.handle_spdm_challenge(bytes);
-                    true
+                    Ok((self.process_spdm_challenge(bytes, response)?, true))
                 }
                 SpdmResponseResponseCode::SpdmRequestGetMeasurements => {
-                    self.handle_spdm_measurement(bytes);
-                    true
+                    Ok((self.process_spdm_measurement(bytes, response)?, true))
                 }
 
                 SpdmResponseResponseCode::SpdmRequestKeyExchange => {
-                    self.handle_spdm_key_exchange(bytes);
-                    true
+                    Ok((self.process_spdm_key_exchange(bytes, response)?, true))
                 }
 
-                SpdmResponseResponseCode::SpdmRequestFinish => false,
+                SpdmResponseResponseCode::SpdmRequestFinish => Ok((0, false)),
 
                 SpdmResponseResponseCode::SpdmRequestPskExchange => {
-                    self.handle_spdm_psk_exchange(bytes);
-                    true
+                    Ok((self.process_spdm_psk_exchange(bytes, response)?, true))
                 }
 
-                SpdmResponseResponseCode::SpdmRequestPskFinish => false,
-
-                SpdmResponseResponseCode::SpdmRequestHeartbeat => false,
-
-                SpdmResponseResponseCode::SpdmRequestKeyUpdate => false,
-
-                SpdmResponseResponseCode::SpdmRequestEndSession => false,
-
-                SpdmResponseResponseCode::SpdmResponseDigests => false,
-                SpdmResponseResponseCode::SpdmResponseCertificate => false,
-                SpdmResponseResponseCode::SpdmResponseChallengeAuth => false,
-                SpdmResponseResponseCode::SpdmResponseVersion => false,
-                SpdmResponseResponseCode::SpdmResponseMeasurements => false,
-                SpdmResponseResponseCode::SpdmResponseCapabilities => false,
-                SpdmResponseResponseCode::SpdmResponseAlgorithms => false,
-                SpdmResponseResponseCode::SpdmResponseKeyExchangeRsp => false,
-                SpdmResponseResponseCode::SpdmResponseFinishRsp => false,
-                SpdmResponseResponseCode::SpdmResponsePskExchangeRsp => false,
-                SpdmResponseResponseCode::SpdmResponsePskFinishRsp => false,
-                SpdmResponseResponseCode::SpdmResponseHeartbeatAck => false,
-                SpdmResponseResponseCode::SpdmResponseKeyUpdateAck => false,
-                SpdmResponseResponseCode::SpdmResponseEndSessionAck => false,
-                SpdmResponseResponseCode::SpdmResponseError => false,
-                SpdmResponseResponseCode::Unknown(_) => false,
+                SpdmResponseResponseCode::SpdmRequestPskFinish => Ok((0, false)),
+
+                SpdmResponseResponseCode::SpdmRequestHeartbeat => Ok((0, false)),
+
+                SpdmResponseResponseCode::SpdmRequestKeyUpdate => Ok((0, false)),
+
+                SpdmResponseResponseCode::SpdmRequestEndSession => Ok((0, false)),
+
+                SpdmResponseResponseCode::SpdmResponseDigests => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseCertificate => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseChallengeAuth => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseVersion => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseMeasurements => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseCapabilities => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseAlgorithms => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseKeyExchangeRsp => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseFinishRsp => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponsePskExchangeRsp => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponsePskFinishRsp => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseHeartbeatAck => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseKeyUpdateAck => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseEndSessionAck => Ok((0, false)),
+                SpdmResponseResponseCode::SpdmResponseError => Ok((0, false)),
+                SpdmResponseResponseCode::Unknown(_) => Ok((0, false)),
             },
-            None => false,
+            None => Ok((0, false)),
         }
     }
 }
 
+/// Whether the code is a request the responder implements. Others, such
+/// as VENDOR_DEFINED_REQUEST, RESPOND_IF_READY, GET_ENCAPSULATED_REQUEST,
+/// responses and unknown codes, are answered with UnsupportedRequest.
+fn is_implemented_request(request_response_code: SpdmResponseResponseCode) -> bool {
+    matches!(
+        request_response_code,
+        SpdmResponseResponseCode::SpdmRequestGetVersion
+            | SpdmResponseResponseCode::SpdmRequestGetCapabilities
+            | SpdmResponseResponseCode::SpdmRequestNegotiateAlgorithms
+            | SpdmResponseResponseCode::SpdmRequestGetDigests
+            | SpdmResponseResponseCode::SpdmRequestGetCertificate
+            | SpdmResponseResponseCode::SpdmRequestChallenge
+            | SpdmResponseResponseCode::SpdmRequestGetMeasurements
+            | SpdmResponseResponseCode::SpdmRequestKeyExchange
+            | SpdmResponseResponseCode::SpdmRequestFinish
+            | SpdmResponseResponseCode::SpdmRequestPskExchange
+            | SpdmResponseResponseCode::SpdmRequestPskFinish
+            | SpdmResponseResponseCode::SpdmRequestHeartbeat
+            | SpdmResponseResponseCode::SpdmRequestKeyUpdate
+            | SpdmResponseResponseCode::SpdmRequestEndSession
+    )
+}
+
 #[cfg(test)]
 mod tests_responder {
     use super::*;
     use crate::msgs::SpdmMessageHeader;
-    use crate::session::SpdmSession;
+    use crate::session::SpdmSessionTable;
     use crate::testlib::*;
     use crate::{crypto, responder};
     use codec::Writer;
@@ -270,7 +556,7 @@ mod tests_responder {
         context.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         let rsp_session_id = 0xffu16;
         let session_id = (0xffu32 << 16) + rsp_session_id as u32;
-        context.common.session = [SpdmSession::new(); 4];
+        context.common.session = SpdmSessionTable::new();
         context.common.session[0].setup(session_id).unwrap();
         context.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -333,23 +619,113 @@ mod tests_responder {
         assert!(status);
     }
     #[test]
-    fn test_case0_receive_message() {
-        let receive_buffer = &mut [0u8; 1024];
-        let mut writer = Writer::init(receive_buffer);
-        let value = PciDoeMessageHeader {
+    fn test_case0_process_transport_message() {
+        let (config_info, provision_info) = create_info();
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+        let shared_buffer = SharedBuffer::new();
+        let mut socket_io_transport = FakeSpdmDeviceIoReceve::new(&shared_buffer);
+        crypto::asym_sign::register(ASYM_SIGN_IMPL);
+        let mut context = responder::ResponderContext::new(
+            &mut socket_io_transport,
+            pcidoe_transport_encap,
+            config_info,
+            provision_info,
+        );
+
+        let mut request = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let used = context
+            .common
+            .encap(&[0x10, 0x84, 0x00, 0x00], &mut request)
+            .unwrap();
+        let mut response = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let (response_size, handled) = context
+            .process_transport_message(&request[..used], &mut response)
+            .unwrap();
+        assert!(handled);
+        let mut spdm_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let (spdm_size, secured_message) = context
+            .common
+            .transport_encap
+            .decap(&response[..response_size], &mut spdm_buffer)
+            .unwrap();
+        assert!(!secured_message);
+        let mut reader = Reader::init(&spdm_buffer[..spdm_size]);
+        assert_eq!(
+            SpdmMessageHeader::read(&mut reader)
+                .unwrap()
+                .request_response_code,
+            SpdmResponseResponseCode::SpdmResponseVersion
+        );
+        // nothing went to the device IO
+        assert_eq!(shared_buffer.get_buffer(&mut spdm_buffer), 0);
+
+        // the response does not fit
+        assert!(context
+            .process_transport_message(&request[..used], &mut response[..4])
+            .is_err());
+    }
+    #[test]
+    fn test_case1_process_transport_message() {
+        let (config_info, provision_info) = create_info();
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+        let shared_buffer = SharedBuffer::new();
+        let mut socket_io_transport = FakeSpdmDeviceIoReceve::new(&shared_buffer);
+        let mut context = responder::ResponderContext::new(
+            &mut socket_io_transport,
+            pcidoe_transport_encap,
+            config_info,
+            provision_info,
+        );
+        let mut response = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+
+        // a response is answered with UnsupportedRequest
+        let mut request = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let used = context
+            .common
+            .encap(&[0x10, 0x04, 0x00, 0x00], &mut request)
+            .unwrap();
+        let (response_size, handled) = context
+            .process_transport_message(&request[..used], &mut response)
+            .unwrap();
+        assert!(handled);
+        let mut spdm_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        context
+            .common
+            .transport_encap
+            .decap(&response[..response_size], &mut spdm_buffer)
+            .unwrap();
+        assert_eq!(
+            spdm_buffer[1..4],
+            [
+                0x7F,
+                SpdmErrorCode::SpdmErrorUnsupportedRequest.get_u8(),
+                0x04
+            ]
+        );
+
+        // secured, without a session
+        let mut writer = Writer::init(&mut request);
+        PciDoeMessageHeader {
             vendor_id: PciDoeVendorId::PciDoeVendorIdPciSig,
             data_object_type: PciDoeDataObjectType::PciDoeDataObjectTypeSecuredSpdm,
             payload_length: 100,
-        };
-        value.encode(&mut writer);
+        }
+        .encode(&mut writer);
+        assert!(context
+            .process_transport_message(&request[..108], &mut response)
+            .is_err());
 
+        // not a transport message
+        assert!(context
+            .process_transport_message(&request[..2], &mut response)
+            .is_err());
+    }
+    #[test]
+    fn test_case0_connection_state() {
         let (config_info, provision_info) = create_info();
         let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
         let shared_buffer = SharedBuffer::new();
-        shared_buffer.set_buffer(receive_buffer);
-
         let mut socket_io_transport = FakeSpdmDeviceIoReceve::new(&shared_buffer);
-        crypto::asym_sign::register(ASYM_SIGN_IMPL);
         let mut context = responder::ResponderContext::new(
             &mut socket_io_transport,
             pcidoe_transport_encap,
@@ -357,9 +733,114 @@ mod tests_responder {
             provision_info,
         );
 
-        let mut receive_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
-        let status = context.receive_message(&mut receive_buffer[..]).is_ok();
-        assert!(status);
+        let mut request = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let mut response = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let mut spdm_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+
+        // NEGOTIATE_ALGORITHMS, CHALLENGE and GET_CAPABILITIES before GET_VERSION
+        for spdm_request in [
+            &[0x11, 0xE3, 0x00, 0x00][..],
+            &[0x11, 0x83, 0x00, 0x00][..],
+            &[0x11, 0xE1, 0x00, 0x00][..],
+        ]
+        .iter()
+        {
+            let used = context.common.encap(spdm_request, &mut request).unwrap();
+            let (response_size, handled) = context
+                .process_transport_message(&request[..used], &mut response)
+                .unwrap();
+            assert!(handled);
+            let (spdm_size, _) = context
+                .common
+                .transport_encap
+                .decap(&response[..response_size], &mut spdm_buffer)
+                .unwrap();
+            assert!(spdm_size >= 4);
+            assert_eq!(spdm_buffer[1], 0x7F);
+            assert_eq!(
+                spdm_buffer[2],
+                SpdmErrorCode::SpdmErrorUnexpectedRequest.get_u8()
+            );
+            assert_eq!(
+                context.common.runtime_info.connection_state,
+                SpdmConnectionState::SpdmConnectionNotStarted
+            );
+        }
+
+        // GET_VERSION starts the connection over, whatever the state
+        let session_id = 0xFFFF_FFFEu32;
+        context.common.session[0].setup(session_id).unwrap();
+        context.common.runtime_info.connection_state =
+            SpdmConnectionState::SpdmConnectionAuthenticated;
+        let used = context
+            .common
+            .encap(&[0x10, 0x84, 0x00, 0x00], &mut request)
+            .unwrap();
+        let (_, handled) = context
+            .process_transport_message(&request[..used], &mut response)
+            .unwrap();
+        assert!(handled);
+        assert_eq!(
+            context.common.runtime_info.connection_state,
+            SpdmConnectionState::SpdmConnectionAfterVersion
+        );
+        assert!(context.common.get_session_via_id(session_id).is_none());
+
+        // FINISH outside a session
+        context.common.runtime_info.connection_state =
+            SpdmConnectionState::SpdmConnectionNegotiated;
+        let used = context
+            .common
+            .encap(&[0x11, 0xE5, 0x00, 0x00], &mut request)
+            .unwrap();
+        let (response_size, handled) = context
+            .process_transport_message(&request[..used], &mut response)
+            .unwrap();
+        assert!(handled);
+        let (spdm_size, _) = context
+            .common
+            .transport_encap
+            .decap(&response[..response_size], &mut spdm_buffer)
+            .unwrap();
+        assert!(spdm_size >= 4);
+        assert_eq!(spdm_buffer[1], 0x7F);
+        assert_eq!(
+            spdm_buffer[2],
+            SpdmErrorCode::SpdmErrorUnexpectedRequest.get_u8()
+        );
+
+        // a response, VENDOR_DEFINED_REQUEST, RESPOND_IF_READY,
+        // GET_ENCAPSULATED_REQUEST and an unknown code, in any state
+        for connection_state in [
+            SpdmConnectionState::SpdmConnectionNotStarted,
+            SpdmConnectionState::SpdmConnectionNegotiated,
+        ]
+        .iter()
+        {
+            context.common.runtime_info.connection_state = *connection_state;
+            for code in [0x01u8, 0xFE, 0xFF, 0xEA, 0xF0].iter() {
+                let used = context
+                    .common
+                    .encap(&[0x11, *code, 0x00, 0x00], &mut request)
+                    .unwrap();
+                let (response_size, handled) = context
+                    .process_transport_message(&request[..used], &mut response)
+                    .unwrap();
+                assert!(handled);
+                let (spdm_size, _) = context
+                    .common
+                    .transport_encap
+                    .decap(&response[..response_size], &mut spdm_buffer)
+                    .unwrap();
+                assert!(spdm_size >= 4);
+                assert_eq!(spdm_buffer[1], 0x7F);
+                assert_eq!(
+                    spdm_buffer[2],
+                    SpdmErrorCode::SpdmErrorUnsupportedRequest.get_u8()
+                );
+                assert_eq!(spdm_buffer[3], *code);
+            }
+        }
     }
     #[test]
     fn test_case0_process_message() {
@@ -388,7 +869,7 @@ mod tests_responder {
         let rsp_session_id = 0xFFFEu16;
         let session_id = (0xffu32 << 16) + rsp_session_id as u32;
         context.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
-        context.common.session = [SpdmSession::new(); 4];
+        context.common.session = SpdmSessionTable::new();
         context.common.session[0].setup(session_id).unwrap();
         context.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -402,6 +883,88 @@ mod tests_responder {
         let status = context.process_message().is_err();
         assert!(status);
     }
+    #[test]
+    fn test_case1_process_message() {
+        struct FailedSendDeviceIo<'a> {
+            request: &'a [u8],
+        }
+        impl SpdmDeviceIo for FailedSendDeviceIo<'_> {
+            fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+                buffer[..self.request.len()].copy_from_slice(self.request);
+                Ok(self.request.len())
+            }
+            fn send(&mut self, _buffer: &[u8]) -> SpdmResult {
+                spdm_result_err!(EIO)
+            }
+            fn flush_all(&mut self) -> SpdmResult {
+                Ok(())
+            }
+        }
+
+        let (config_info, provision_info) = create_info();
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+        let mut context = responder::ResponderContext::new_without_device_io(
+            pcidoe_transport_encap,
+            config_info,
+            provision_info,
+        );
+        let mut request = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let used = context
+            .common
+            .encap(&[0x10, 0x84, 0x00, 0x00], &mut request)
+            .unwrap();
+        // no device IO
+        assert!(context.process_message().is_err());
+
+        let mut device_io = FailedSendDeviceIo {
+            request: &request[..used],
+        };
+        context.common.device_io = Some(&mut device_io);
+        let (size, buffer) = context.process_message().unwrap_err();
+        assert_eq!(&buffer[..size], &request[..used]);
+    }
+    fn oversized_app_message_handler(
+        _session_id: u32,
+        _message_type: u8,
+        _request: &[u8],
+        response: &mut [u8],
+    ) -> Option<usize> {
+        Some(response.len() + 1)
+    }
+
+    #[test]
+    fn test_case0_dispatch_secured_app_message() {
+        let (config_info, provision_info) = create_info();
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+        let shared_buffer = SharedBuffer::new();
+        let mut socket_io_transport = FakeSpdmDeviceIoReceve::new(&shared_buffer);
+
+        let mut context = responder::ResponderContext::new(
+            &mut socket_io_transport,
+            pcidoe_transport_encap,
+            config_info,
+            provision_info,
+        );
+
+        let mut response = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        // no handler
+        assert_eq!(
+            context
+                .dispatch_secured_app_message(0xFFFE_FFFE, 0x01, &[0x80], &mut response)
+                .unwrap(),
+            (0, false)
+        );
+
+        // a handler claiming more than the response buffer
+        context.register_app_message_handler(oversized_app_message_handler);
+        assert_eq!(
+            context
+                .dispatch_secured_app_message(0xFFFE_FFFE, 0x01, &[0x80], &mut response)
+                .unwrap(),
+            (0, false)
+        );
+    }
+
     #[test]
     fn test_case0_dispatch_secured_message() {
         let (config_info, provision_info) = create_info();
@@ -425,7 +988,7 @@ mod tests_responder {
 
         let rsp_session_id = 0xFFFEu16;
         let session_id = (0xffu32 << 16) + rsp_session_id as u32;
-        context.common.session = [SpdmSession::new(); 4];
+        context.common.session = SpdmSessionTable::new();
         context.common.session[0].setup(session_id).unwrap();
         context.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -440,6 +1003,7 @@ mod tests_responder {
         context.common.session[0]
             .set_session_state(crate::session::SpdmSessionState::SpdmSessionHandshaking);
 
+        let mut response = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         for i in 0..5 {
             let bytes = &mut [0u8; 4];
             let mut writer = Writer::init(bytes);
@@ -448,7 +1012,9 @@ mod tests_responder {
                 request_response_code: dispatch_secured_data(i, true),
             };
             value.encode(&mut writer);
-            let status_secured = context.dispatch_secured_message(session_id, bytes);
+            let (_, status_secured) = context
+                .dispatch_secured_message(session_id, bytes, &mut response)
+                .unwrap();
             assert!(status_secured);
         }
         for i in 0..25 {
@@ -459,7 +1025,9 @@ mod tests_responder {
                 request_response_code: dispatch_secured_data(i, false),
             };
             value.encode(&mut writer);
-            let status_secured = context.dispatch_secured_message(session_id, bytes);
+            let (_, status_secured) = context
+                .dispatch_secured_message(session_id, bytes, &mut response)
+                .unwrap();
             assert!(!status_secured);
         }
         for i in 0..9 {
@@ -470,7 +1038,7 @@ mod tests_responder {
                 request_response_code: dispatc_data(i, true),
             };
             value.encode(&mut writer);
-            let status = context.dispatch_message(bytes);
+            let (_, status) = context.dispatch_message(bytes, &mut response).unwrap();
             assert!(status);
         }
         for i in 0..21 {
@@ -481,8 +1049,9 @@ mod tests_responder {
                 request_response_code: dispatc_data(i, false),
             };
             value.encode(&mut writer);
-            let status = context.dispatch_message(bytes);
-            assert!(!status);
+            // answered with UnexpectedRequest
+            let (_, status) = context.dispatch_message(bytes, &mut response).unwrap();
+            assert!(status);
         }
     }
 
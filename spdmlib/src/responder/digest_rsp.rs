@@ -2,18 +2,26 @@
 //
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
-use crate::crypto;
 use crate::responder::*;
 
 impl<'a> ResponderContext<'a> {
     pub fn handle_spdm_digest(&mut self, bytes: &[u8]) {
+        let _ =
+            self.send_response(|context, response| context.process_spdm_digest(bytes, response));
+    }
+
+    pub fn process_spdm_digest(&mut self, bytes: &[u8], response: &mut [u8]) -> SpdmResult<usize> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         self.write_spdm_digest_response(bytes, &mut writer);
-        let _ = self.send_message(writer.used_slice());
+        self.encode_message(writer.used_slice(), response)
     }
 
     pub fn write_spdm_digest_response(&mut self, bytes: &[u8], writer: &mut Writer) {
+        if !self.check_request_supported(SpdmResponseResponseCode::SpdmRequestGetDigests, writer) {
+            return;
+        }
+
         let mut reader = Reader::init(bytes);
         SpdmMessageHeader::read(&mut reader);
 
@@ -37,7 +45,7 @@ impl<'a> ResponderContext<'a> {
             return;
         }
 
-        let digest_size = self.common.negotiate_info.base_hash_sel.get_size();
+        let digest_size = self.common.get_hash_size();
 
         info!("send spdm digest\n");
         let response = SpdmMessage {
@@ -57,11 +65,7 @@ impl<'a> ResponderContext<'a> {
         response.spdm_encode(&mut self.common, writer);
 
         let my_cert_chain = self.common.provision_info.my_cert_chain.unwrap();
-        let cert_chain_hash = crypto::hash::hash_all(
-            self.common.negotiate_info.base_hash_sel,
-            my_cert_chain.as_ref(),
-        )
-        .unwrap();
+        let cert_chain_hash = self.common.hash_all(my_cert_chain.as_ref()).unwrap();
 
         // patch the message before send
         let used = writer.used();
@@ -27,12 +27,42 @@ impl<'a> ResponderContext<'a> {
         error.spdm_encode(&mut self.common, writer);
     }
 
+    /// Check the request against the negotiated capabilities, writing
+    /// ERROR UnsupportedRequest if it is not allowed.
+    pub fn check_request_supported(
+        &mut self,
+        request_response_code: SpdmResponseResponseCode,
+        writer: &mut Writer,
+    ) -> bool {
+        if self.common.is_request_supported(request_response_code) {
+            return true;
+        }
+        error!("!!! unsupported request : {:?}\n", request_response_code);
+        self.write_spdm_error(
+            SpdmErrorCode::SpdmErrorUnsupportedRequest,
+            request_response_code.get_u8(),
+            writer,
+        );
+        false
+    }
+
     pub fn send_spdm_error(&mut self, error_code: SpdmErrorCode, error_data: u8) {
-        info!("send spdm version\n");
+        let _ = self.send_response(|context, response| {
+            context.encode_spdm_error(error_code, error_data, response)
+        });
+    }
+
+    pub fn encode_spdm_error(
+        &mut self,
+        error_code: SpdmErrorCode,
+        error_data: u8,
+        response: &mut [u8],
+    ) -> SpdmResult<usize> {
+        info!("send spdm error\n");
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         self.write_spdm_error(error_code, error_data, &mut writer);
-        let _ = self.send_message(writer.used_slice());
+        self.encode_message(writer.used_slice(), response)
     }
 }
 
@@ -59,4 +89,75 @@ mod tests_responder {
 
         context.send_spdm_error(SpdmErrorCode::SpdmErrorInvalidRequest, 0);
     }
+    #[test]
+    fn test_case0_check_request_supported() {
+        let (config_info, provision_info) = create_info();
+        let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
+        let shared_buffer = SharedBuffer::new();
+        let mut socket_io_transport = FakeSpdmDeviceIoReceve::new(&shared_buffer);
+        let mut context = responder::ResponderContext::new(
+            &mut socket_io_transport,
+            pcidoe_transport_encap,
+            config_info,
+            provision_info,
+        );
+
+        context.common.negotiate_info.rsp_capabilities_sel = SpdmResponseCapabilityFlags::CERT_CAP
+            | SpdmResponseCapabilityFlags::KEY_EX_CAP
+            | SpdmResponseCapabilityFlags::HBEAT_CAP;
+        context.common.negotiate_info.req_capabilities_sel = SpdmRequestCapabilityFlags::KEY_EX_CAP;
+
+        let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let mut writer = Writer::init(&mut send_buffer);
+        assert!(context
+            .check_request_supported(SpdmResponseResponseCode::SpdmRequestGetDigests, &mut writer));
+        assert!(context.check_request_supported(
+            SpdmResponseResponseCode::SpdmRequestKeyExchange,
+            &mut writer
+        ));
+        assert_eq!(writer.used(), 0);
+
+        // the requester did not advertise HBEAT_CAP
+        assert!(!context
+            .check_request_supported(SpdmResponseResponseCode::SpdmRequestHeartbeat, &mut writer));
+        assert_eq!(
+            writer.used_slice(),
+            [
+                0x11,
+                0x7F,
+                SpdmErrorCode::SpdmErrorUnsupportedRequest.get_u8(),
+                SpdmResponseResponseCode::SpdmRequestHeartbeat.get_u8()
+            ]
+        );
+
+        let mut writer = Writer::init(&mut send_buffer);
+        assert!(!context.check_request_supported(
+            SpdmResponseResponseCode::SpdmRequestGetMeasurements,
+            &mut writer
+        ));
+        assert!(!context
+            .check_request_supported(SpdmResponseResponseCode::SpdmRequestChallenge, &mut writer));
+
+        // VCA and END_SESSION are always allowed, FINISH follows KEY_EXCHANGE
+        assert!(context
+            .common
+            .is_request_supported(SpdmResponseResponseCode::SpdmRequestGetVersion));
+        assert!(context
+            .common
+            .is_request_supported(SpdmResponseResponseCode::SpdmRequestEndSession));
+        assert!(context
+            .common
+            .is_request_supported(SpdmResponseResponseCode::SpdmRequestFinish));
+        assert!(!context
+            .common
+            .is_request_supported(SpdmResponseResponseCode::SpdmRequestPskFinish));
+
+        // anything else is not
+        assert!(!context
+            .common
+            .is_request_supported(SpdmResponseResponseCode::SpdmResponseDigests));
+        assert!(!context
+            .common
+            .is_request_supported(SpdmResponseResponseCode::Unknown(0xF0)));
+    }
 }
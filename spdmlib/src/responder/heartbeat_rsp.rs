@@ -6,14 +6,31 @@ use crate::responder::*;
 
 impl<'a> ResponderContext<'a> {
     pub fn handle_spdm_heartbeat(&mut self, session_id: u32, bytes: &[u8]) {
+        let _ = self.send_response(|context, response| {
+            context.process_spdm_heartbeat(session_id, bytes, response)
+        });
+    }
+
+    pub fn process_spdm_heartbeat(
+        &mut self,
+        session_id: u32,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<usize> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         if self.write_spdm_heartbeat_response(bytes, &mut writer) {
-            let _ = self.send_secured_message(session_id, writer.used_slice());
+            self.encode_secured_message(session_id, writer.used_slice(), response)
+        } else {
+            Ok(0)
         }
     }
 
     pub fn write_spdm_heartbeat_response(&mut self, bytes: &[u8], writer: &mut Writer) -> bool {
+        if !self.check_request_supported(SpdmResponseResponseCode::SpdmRequestHeartbeat, writer) {
+            return true;
+        }
+
         let mut reader = Reader::init(bytes);
         SpdmMessageHeader::read(&mut reader);
 
@@ -42,7 +59,7 @@ impl<'a> ResponderContext<'a> {
 mod tests_responder {
     use super::*;
     use crate::msgs::SpdmMessageHeader;
-    use crate::session::SpdmSession;
+    use crate::session::SpdmSessionTable;
     use crate::testlib::*;
     use crate::{crypto, responder};
     use codec::{Codec, Writer};
@@ -64,7 +81,7 @@ mod tests_responder {
         let rsp_session_id = 0xffu16;
         let session_id = (0xffu32 << 16) + rsp_session_id as u32;
         context.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
-        context.common.session = [SpdmSession::new(); 4];
+        context.common.session = SpdmSessionTable::new();
         context.common.session[0].setup(session_id).unwrap();
         context.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
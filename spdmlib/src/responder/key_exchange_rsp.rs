@@ -10,13 +10,26 @@ use crate::crypto;
 
 impl<'a> ResponderContext<'a> {
     pub fn handle_spdm_key_exchange(&mut self, bytes: &[u8]) {
+        let _ = self
+            .send_response(|context, response| context.process_spdm_key_exchange(bytes, response));
+    }
+
+    pub fn process_spdm_key_exchange(
+        &mut self,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<usize> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         self.write_spdm_key_exchange_response(bytes, &mut writer);
-        let _ = self.send_message(writer.used_slice());
+        self.encode_message(writer.used_slice(), response)
     }
 
     pub fn write_spdm_key_exchange_response(&mut self, bytes: &[u8], writer: &mut Writer) {
+        if !self.check_request_supported(SpdmResponseResponseCode::SpdmRequestKeyExchange, writer) {
+            return;
+        }
+
         let mut reader = Reader::init(bytes);
         SpdmMessageHeader::read(&mut reader);
 
@@ -89,7 +102,7 @@ impl<'a> ResponderContext<'a> {
                 },
                 opaque,
                 signature: SpdmSignatureStruct {
-                    data_size: self.common.negotiate_info.base_asym_sel.get_size(),
+                    data_size: self.common.get_asym_key_size(),
                     data: [0xbb; SPDM_MAX_ASYM_KEY_SIZE],
                 },
                 verify_data: SpdmDigestStruct {
@@ -103,7 +116,7 @@ impl<'a> ResponderContext<'a> {
         let used = writer.used();
 
         // generate signature
-        let base_asym_size = self.common.negotiate_info.base_asym_sel.get_size() as usize;
+        let base_asym_size = self.common.get_asym_key_size() as usize;
         let base_hash_size = self.common.negotiate_info.base_hash_sel.get_size() as usize;
 
         let mut message_k = ManagedBuffer::default();
@@ -207,7 +220,6 @@ mod tests_responder {
     use crate::msgs::SpdmMessageHeader;
     use crate::testlib::*;
     use crate::{crypto, responder};
-    use bytes::BytesMut;
     use codec::{Codec, Writer};
 
     #[test]
@@ -241,13 +253,8 @@ mod tests_responder {
         };
         value.encode(&mut writer);
 
-        let rng = ring::rand::SystemRandom::new();
-        let private_key =
-            ring::agreement::EphemeralPrivateKey::generate(&ring::agreement::ECDH_P256, &rng)
-                .ok()
-                .unwrap();
-        let public_key_old = private_key.compute_public_key().ok().unwrap();
-        let public_key = BytesMut::from(&public_key_old.as_ref()[1..]);
+        let (exchange, _) =
+            crate::crypto::dhe::generate_key_pair(SpdmDheAlgo::SECP_256_R1).unwrap();
 
         let key_exchange: &mut [u8; 1024] = &mut [0u8; 1024];
         let mut writer = Writer::init(key_exchange);
@@ -259,7 +266,7 @@ mod tests_responder {
             random: SpdmRandomStruct {
                 data: [100u8; SPDM_RANDOM_SIZE],
             },
-            exchange: SpdmDheExchangeStruct::from(public_key),
+            exchange,
             opaque: SpdmOpaqueStruct {
                 data_size: 64u16,
                 data: [100u8; crate::config::MAX_SPDM_OPAQUE_SIZE],
@@ -6,12 +6,23 @@ use crate::responder::*;
 
 impl<'a> ResponderContext<'a> {
     pub fn handle_spdm_key_update(&mut self, session_id: u32, bytes: &[u8]) {
+        let _ = self.send_response(|context, response| {
+            context.process_spdm_key_update(session_id, bytes, response)
+        });
+    }
+
+    pub fn process_spdm_key_update(
+        &mut self,
+        session_id: u32,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<usize> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         if self.write_spdm_key_update_response(session_id, bytes, &mut writer) {
-            let _ = self.send_secured_message(session_id, writer.used_slice());
+            self.encode_secured_message(session_id, writer.used_slice(), response)
         } else {
-            let _ = self.send_message(writer.used_slice());
+            self.encode_message(writer.used_slice(), response)
         }
     }
 
@@ -21,6 +32,10 @@ impl<'a> ResponderContext<'a> {
         bytes: &[u8],
         writer: &mut Writer,
     ) -> bool {
+        if !self.check_request_supported(SpdmResponseResponseCode::SpdmRequestKeyUpdate, writer) {
+            return true;
+        }
+
         let mut reader = Reader::init(bytes);
         SpdmMessageHeader::read(&mut reader);
 
@@ -74,7 +89,7 @@ impl<'a> ResponderContext<'a> {
 mod tests_responder {
     use super::*;
     use crate::msgs::SpdmMessageHeader;
-    use crate::session::SpdmSession;
+    use crate::session::SpdmSessionTable;
     use crate::testlib::*;
     use crate::{crypto, responder};
     use codec::{Codec, Writer};
@@ -97,7 +112,7 @@ mod tests_responder {
         let rsp_session_id = 0xFFFEu16;
         let session_id = (0xffu32 << 16) + rsp_session_id as u32;
         context.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
-        context.common.session = [SpdmSession::new(); 4];
+        context.common.session = SpdmSessionTable::new();
         context.common.session[0].setup(session_id).unwrap();
         context.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -148,7 +163,7 @@ mod tests_responder {
         let rsp_session_id = 0xFFFEu16;
         let session_id = (0xffu32 << 16) + rsp_session_id as u32;
         context.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
-        context.common.session = [SpdmSession::new(); 4];
+        context.common.session = SpdmSessionTable::new();
         context.common.session[0].setup(session_id).unwrap();
         context.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
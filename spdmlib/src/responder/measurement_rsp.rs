@@ -7,13 +7,28 @@ use crate::responder::*;
 
 impl<'a> ResponderContext<'a> {
     pub fn handle_spdm_measurement(&mut self, bytes: &[u8]) {
+        let _ = self
+            .send_response(|context, response| context.process_spdm_measurement(bytes, response));
+    }
+
+    pub fn process_spdm_measurement(
+        &mut self,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<usize> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         self.write_spdm_measurement_response(bytes, &mut writer);
-        let _ = self.send_message(writer.used_slice());
+        self.encode_message(writer.used_slice(), response)
     }
 
     pub fn write_spdm_measurement_response(&mut self, bytes: &[u8], writer: &mut Writer) {
+        if !self
+            .check_request_supported(SpdmResponseResponseCode::SpdmRequestGetMeasurements, writer)
+        {
+            return;
+        }
+
         let mut reader = Reader::init(bytes);
         SpdmMessageHeader::read(&mut reader);
 
@@ -29,7 +44,7 @@ impl<'a> ResponderContext<'a> {
         let get_measurements = get_measurements.unwrap();
 
         let measurement_digest_size = self.common.negotiate_info.measurement_hash_sel.get_size();
-        let signature_size = self.common.negotiate_info.base_asym_sel.get_size();
+        let signature_size = self.common.get_asym_key_size();
 
         if get_measurements
             .measurement_attributes
@@ -196,7 +211,7 @@ impl<'a> ResponderContext<'a> {
             .measurement_attributes
             .contains(SpdmMeasurementeAttributes::INCLUDE_SIGNATURE)
         {
-            let base_asym_size = self.common.negotiate_info.base_asym_sel.get_size() as usize;
+            let base_asym_size = self.common.get_asym_key_size() as usize;
             let temp_used = used - base_asym_size;
             self.common
                 .runtime_info
@@ -205,7 +220,9 @@ impl<'a> ResponderContext<'a> {
 
             let signature = self.common.generate_measurement_signature();
             if signature.is_err() {
-                self.send_spdm_error(SpdmErrorCode::SpdmErrorInvalidRequest, 0);
+                // an error instead of the response written so far
+                writer.clear();
+                self.write_spdm_error(SpdmErrorCode::SpdmErrorInvalidRequest, 0, writer);
                 return;
             }
             let signature = signature.unwrap();
@@ -242,6 +259,7 @@ mod tests_responder {
             config_info,
             provision_info,
         );
+        select_capabilities(&mut context.common);
 
         crypto::asym_sign::register(ASYM_SIGN_IMPL);
 
@@ -369,6 +387,7 @@ mod tests_responder {
             config_info,
             provision_info,
         );
+        select_capabilities(&mut context.common);
 
         crypto::asym_sign::register(ASYM_SIGN_IMPL);
 
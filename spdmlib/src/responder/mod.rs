@@ -23,6 +23,8 @@ mod error_rsp;
 
 pub use context::ResponderContext;
 
+use crate::common::SpdmConnectionState;
 use crate::config;
+use crate::error::SpdmResult;
 use crate::msgs::*;
 use codec::{Codec, Reader, Writer};
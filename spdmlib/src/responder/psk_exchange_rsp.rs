@@ -11,13 +11,26 @@ use crate::common::ManagedBuffer;
 
 impl<'a> ResponderContext<'a> {
     pub fn handle_spdm_psk_exchange(&mut self, bytes: &[u8]) {
+        let _ = self
+            .send_response(|context, response| context.process_spdm_psk_exchange(bytes, response));
+    }
+
+    pub fn process_spdm_psk_exchange(
+        &mut self,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<usize> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         self.write_spdm_psk_exchange_response(bytes, &mut writer);
-        let _ = self.send_message(writer.used_slice());
+        self.encode_message(writer.used_slice(), response)
     }
 
     pub fn write_spdm_psk_exchange_response(&mut self, bytes: &[u8], writer: &mut Writer) {
+        if !self.check_request_supported(SpdmResponseResponseCode::SpdmRequestPskExchange, writer) {
+            return;
+        }
+
         let mut reader = Reader::init(bytes);
         SpdmMessageHeader::read(&mut reader);
 
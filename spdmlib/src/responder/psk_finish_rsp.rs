@@ -8,15 +8,27 @@ use crate::common::ManagedBuffer;
 
 impl<'a> ResponderContext<'a> {
     pub fn handle_spdm_psk_finish(&mut self, session_id: u32, bytes: &[u8]) {
+        let _ = self.send_response(|context, response| {
+            context.process_spdm_psk_finish(session_id, bytes, response)
+        });
+    }
+
+    pub fn process_spdm_psk_finish(
+        &mut self,
+        session_id: u32,
+        bytes: &[u8],
+        response: &mut [u8],
+    ) -> SpdmResult<usize> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         if self.write_spdm_psk_finish_response(session_id, bytes, &mut writer) {
-            let _ = self.send_secured_message(session_id, writer.used_slice());
-            // change state after message is sent.
+            let used = self.encode_secured_message(session_id, writer.used_slice(), response)?;
+            // change state after the response is encoded.
             let session = self.common.get_session_via_id(session_id).unwrap();
             session.set_session_state(crate::session::SpdmSessionState::SpdmSessionEstablished);
+            Ok(used)
         } else {
-            let _ = self.send_message(writer.used_slice());
+            self.encode_message(writer.used_slice(), response)
         }
     }
 
@@ -123,7 +135,7 @@ impl<'a> ResponderContext<'a> {
 mod tests_responder {
     use super::*;
     use crate::msgs::SpdmMessageHeader;
-    use crate::session::SpdmSession;
+    use crate::session::SpdmSessionTable;
     use crate::testlib::*;
     use crate::{crypto, responder};
     use codec::{Codec, Writer};
@@ -147,7 +159,7 @@ mod tests_responder {
         context.common.negotiate_info.base_hash_sel = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
         context.common.negotiate_info.base_asym_sel = SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
         context.common.negotiate_info.aead_sel = SpdmAeadAlgo::AES_128_GCM;
-        context.common.session = [SpdmSession::new(); 4];
+        context.common.session = SpdmSessionTable::new();
         context.common.session[0].setup(4294901758).unwrap();
         context.common.session[0].set_crypto_param(
             SpdmBaseHashAlgo::TPM_ALG_SHA_384,
@@ -6,10 +6,15 @@ use crate::responder::*;
 
 impl<'a> ResponderContext<'a> {
     pub fn handle_spdm_version(&mut self, bytes: &[u8]) {
+        let _ =
+            self.send_response(|context, response| context.process_spdm_version(bytes, response));
+    }
+
+    pub fn process_spdm_version(&mut self, bytes: &[u8], response: &mut [u8]) -> SpdmResult<usize> {
         let mut send_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
         let mut writer = Writer::init(&mut send_buffer);
         self.write_spdm_version_response(bytes, &mut writer);
-        let _ = self.send_message(writer.used_slice());
+        self.encode_message(writer.used_slice(), response)
     }
 
     pub fn write_spdm_version_response(&mut self, bytes: &[u8], writer: &mut Writer) {
@@ -25,8 +30,9 @@ impl<'a> ResponderContext<'a> {
             return;
         }
 
-        // clear cache data
+        // clear cache data, the connection starts over
         self.common.reset_runtime_info();
+        self.common.teardown_sessions();
 
         if self
             .common
@@ -65,6 +71,8 @@ impl<'a> ResponderContext<'a> {
             .runtime_info
             .message_a
             .append_message(writer.used_slice());
+
+        self.common.runtime_info.connection_state = SpdmConnectionState::SpdmConnectionAfterVersion;
     }
 }
 
@@ -8,12 +8,16 @@ use crate::msgs::*;
 use crate::config;
 use crate::error::SpdmResult;
 use crate::key_schedule::SpdmKeySchedule;
+use core::sync::atomic::{AtomicU64, Ordering};
 
 use codec::enum_builder;
 use codec::{Codec, Reader, Writer};
 
 use crate::common::ManagedBuffer;
 
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+
 enum_builder! {
     @U8
     EnumName: SpdmSessionState;
@@ -204,6 +208,10 @@ impl SpdmSession {
         self.session_state = session_state;
     }
 
+    pub fn get_session_state(&self) -> SpdmSessionState {
+        self.session_state
+    }
+
     pub fn generate_handshake_secret(&mut self, th1: &SpdmDigestStruct) -> SpdmResult {
         // generate key
         info!("!!! generate_handshake_secret !!!:\n");
@@ -304,10 +312,7 @@ impl SpdmSession {
 
         self.handshake_secret.export_master_secret = self
             .key_schedule
-            .derive_export_master_secret(
-                hash_algo,
-                self.handshake_secret.export_master_secret.as_ref(),
-            )
+            .derive_export_master_secret(hash_algo, self.master_secret.master_secret.as_ref())
             .unwrap();
 
         Ok(())
@@ -836,6 +841,178 @@ impl SpdmSession {
     }
 }
 
+/// Storage for all sessions of one SpdmContext.
+///
+/// Without the `alloc` feature the table is a fixed array of
+/// `config::MAX_SPDM_SESSION_COUNT` slots, as chosen in etc/config.json.
+/// With `alloc` the slots live on the heap and the capacity is chosen at
+/// runtime via `with_capacity`.
+///
+/// Every successful lookup by session ID marks the session as active. Idle
+/// time is not wall-clock time: it is the number of lookups and allocations
+/// on the table since the session was last active. When idle eviction is
+/// enabled and no slot is free, `allocate` tears down the session that has
+/// been idle the longest to make room for a new one.
+pub struct SpdmSessionTable {
+    #[cfg(feature = "alloc")]
+    sessions: Vec<SpdmSession>,
+    #[cfg(feature = "alloc")]
+    last_active: Vec<AtomicU64>,
+    #[cfg(not(feature = "alloc"))]
+    sessions: [SpdmSession; config::MAX_SPDM_SESSION_COUNT],
+    #[cfg(not(feature = "alloc"))]
+    last_active: [AtomicU64; config::MAX_SPDM_SESSION_COUNT],
+    // atomics, so that a lookup through `get` counts as activity too and
+    // the table stays Sync
+    tick: AtomicU64,
+    idle_eviction: bool,
+}
+
+impl Default for SpdmSessionTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpdmSessionTable {
+    #[cfg(not(feature = "alloc"))]
+    pub fn new() -> Self {
+        SpdmSessionTable {
+            sessions: [SpdmSession::new(); config::MAX_SPDM_SESSION_COUNT],
+            last_active: [0u64; config::MAX_SPDM_SESSION_COUNT].map(AtomicU64::new),
+            tick: AtomicU64::new(0),
+            idle_eviction: false,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn new() -> Self {
+        Self::with_capacity(config::MAX_SPDM_SESSION_COUNT)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn with_capacity(capacity: usize) -> Self {
+        SpdmSessionTable {
+            sessions: vec![SpdmSession::new(); capacity],
+            last_active: (0..capacity).map(|_| AtomicU64::new(0)).collect(),
+            tick: AtomicU64::new(0),
+            idle_eviction: false,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Enable or disable tearing down the least recently used session
+    /// when `allocate` finds the table full.
+    pub fn set_idle_eviction(&mut self, idle_eviction: bool) {
+        self.idle_eviction = idle_eviction;
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, SpdmSession> {
+        self.sessions.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> core::slice::IterMut<'_, SpdmSession> {
+        self.sessions.iter_mut()
+    }
+
+    pub fn get(&self, session_id: u32) -> Option<&SpdmSession> {
+        if session_id == 0 {
+            return None;
+        }
+        let index = self
+            .sessions
+            .iter()
+            .position(|session| session.get_session_id() == session_id)?;
+        self.touch(index);
+        Some(&self.sessions[index])
+    }
+
+    pub fn get_mut(&mut self, session_id: u32) -> Option<&mut SpdmSession> {
+        if session_id == 0 {
+            return None;
+        }
+        let index = self
+            .sessions
+            .iter()
+            .position(|session| session.get_session_id() == session_id)?;
+        self.touch(index);
+        Some(&mut self.sessions[index])
+    }
+
+    /// Return a free slot, ready for `SpdmSession::setup`.
+    ///
+    /// If the table is full and idle eviction is enabled, the least recently
+    /// used session is torn down and its slot returned instead.
+    pub fn allocate(&mut self) -> Option<&mut SpdmSession> {
+        let index = match self
+            .sessions
+            .iter()
+            .position(|session| session.get_session_id() == 0)
+        {
+            Some(index) => index,
+            None => {
+                if !self.idle_eviction {
+                    return None;
+                }
+                let index = self.least_recently_used()?;
+                let session_id = self.sessions[index].get_session_id();
+                info!("evict idle session {:08x?}\n", session_id);
+                self.sessions[index].teardown(session_id).ok()?;
+                index
+            }
+        };
+        self.touch(index);
+        Some(&mut self.sessions[index])
+    }
+
+    /// Tear down every session that has not been active within the last
+    /// `max_idle` lookups and allocations on the table, and return how many
+    /// sessions were evicted.
+    pub fn evict_idle(&mut self, max_idle: u64) -> usize {
+        let tick = self.tick.load(Ordering::Relaxed);
+        let mut evicted = 0;
+        for index in 0..self.sessions.len() {
+            let session_id = self.sessions[index].get_session_id();
+            if session_id == 0 || tick - self.last_active[index].load(Ordering::Relaxed) <= max_idle
+            {
+                continue;
+            }
+            info!("evict idle session {:08x?}\n", session_id);
+            if self.sessions[index].teardown(session_id).is_ok() {
+                evicted += 1;
+            }
+        }
+        evicted
+    }
+
+    fn touch(&self, index: usize) {
+        let tick = self.tick.fetch_add(1, Ordering::Relaxed) + 1;
+        self.last_active[index].store(tick, Ordering::Relaxed);
+    }
+
+    fn least_recently_used(&self) -> Option<usize> {
+        (0..self.sessions.len())
+            .min_by_key(|index| self.last_active[*index].load(Ordering::Relaxed))
+    }
+}
+
+impl core::ops::Index<usize> for SpdmSessionTable {
+    type Output = SpdmSession;
+
+    fn index(&self, index: usize) -> &SpdmSession {
+        &self.sessions[index]
+    }
+}
+
+impl core::ops::IndexMut<usize> for SpdmSessionTable {
+    fn index_mut(&mut self, index: usize) -> &mut SpdmSession {
+        &mut self.sessions[index]
+    }
+}
+
 #[cfg(test)]
 mod tests_session {
     use super::*;
@@ -949,4 +1126,70 @@ mod tests_session {
         let session_id = 4294901758u32;
         let _ = session.teardown(session_id).is_err();
     }
+    #[test]
+    fn test_case0_session_table_allocate() {
+        let mut table = SpdmSessionTable::new();
+        for i in 0..table.capacity() {
+            let session = table.allocate().unwrap();
+            session.setup(0x10000 + i as u32).unwrap();
+        }
+        assert!(table.allocate().is_none());
+        assert!(table.get(0).is_none());
+        assert!(table.get(0x10000).is_some());
+
+        // every session but the first one is used again
+        for i in 1..table.capacity() {
+            assert!(table.get_mut(0x10000 + i as u32).is_some());
+        }
+        table.set_idle_eviction(true);
+        let session = table.allocate().unwrap();
+        assert_eq!(session.get_session_id(), 0);
+        session.setup(0x20000).unwrap();
+        assert!(table.get(0x10000).is_none());
+        assert!(table.get(0x20000).is_some());
+    }
+    #[test]
+    fn test_case0_session_table_evict_idle() {
+        let mut table = SpdmSessionTable::new();
+        table.allocate().unwrap().setup(0x10001).unwrap();
+        table.allocate().unwrap().setup(0x10002).unwrap();
+        for _ in 0..3 {
+            assert!(table.get_mut(0x10002).is_some());
+        }
+        assert_eq!(table.evict_idle(2), 1);
+        assert!(table.get(0x10001).is_none());
+        assert!(table.get(0x10002).is_some());
+        assert_eq!(table.evict_idle(2), 0);
+    }
+    #[test]
+    fn test_case0_session_table_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<SpdmSessionTable>();
+    }
+    #[test]
+    fn test_case1_session_table_allocate() {
+        let mut table = SpdmSessionTable::new();
+        for i in 0..table.capacity() {
+            table.allocate().unwrap().setup(0x10000 + i as u32).unwrap();
+        }
+        // a read-only lookup keeps the first session from being evicted
+        for i in (0..table.capacity()).rev() {
+            assert!(table.get(0x10000 + i as u32).is_some());
+        }
+        table.set_idle_eviction(true);
+        table.allocate().unwrap().setup(0x20000).unwrap();
+        assert!(table.get(0x10000).is_some());
+        assert!(table.get(0x10000 + table.capacity() as u32 - 1).is_none());
+    }
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_case0_session_table_with_capacity() {
+        let mut table = SpdmSessionTable::with_capacity(300);
+        assert_eq!(table.capacity(), 300);
+        for i in 0..300u32 {
+            table.allocate().unwrap().setup(0x10000 + i).unwrap();
+        }
+        assert!(table.allocate().is_none());
+        assert!(table.get_mut(0x10000 + 299).is_some());
+    }
 }
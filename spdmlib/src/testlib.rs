@@ -11,9 +11,9 @@ use crate::msgs::*;
 use crate::{spdm_err, spdm_result_err};
 use codec::enum_builder;
 use codec::{Codec, Reader, Writer};
-use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::path::PathBuf;
+use std::sync::Mutex;
 
 pub fn get_test_key_directory() -> PathBuf {
     let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -44,9 +44,23 @@ pub fn new_spdm_message(value: SpdmMessage, mut context: SpdmContext) -> SpdmMes
     spdm_message
 }
 
+pub fn select_capabilities(context: &mut SpdmContext) {
+    context.negotiate_info.req_capabilities_sel = context.config_info.req_capabilities;
+    context.negotiate_info.rsp_capabilities_sel = context.config_info.rsp_capabilities;
+}
+
 pub fn create_info() -> (common::SpdmConfigInfo, common::SpdmProvisionInfo) {
     let config_info = common::SpdmConfigInfo {
         spdm_version: [SpdmVersion::SpdmVersion10, SpdmVersion::SpdmVersion11],
+        req_capabilities: SpdmRequestCapabilityFlags::CERT_CAP
+            | SpdmRequestCapabilityFlags::CHAL_CAP
+            | SpdmRequestCapabilityFlags::ENCRYPT_CAP
+            | SpdmRequestCapabilityFlags::MAC_CAP
+            | SpdmRequestCapabilityFlags::KEY_EX_CAP
+            | SpdmRequestCapabilityFlags::PSK_CAP
+            | SpdmRequestCapabilityFlags::ENCAP_CAP
+            | SpdmRequestCapabilityFlags::HBEAT_CAP
+            | SpdmRequestCapabilityFlags::KEY_UPD_CAP,
         rsp_capabilities: SpdmResponseCapabilityFlags::CERT_CAP
             | SpdmResponseCapabilityFlags::CHAL_CAP
             | SpdmResponseCapabilityFlags::MEAS_CAP_SIG
@@ -260,17 +274,54 @@ impl SpdmTransportEncap for PciDoeTransportEncap {
 
 pub static ASYM_SIGN_IMPL: SpdmAsymSign = SpdmAsymSign { sign_cb: asym_sign };
 
+macro_rules! sign_ecdsa_asym_algo {
+    ($curve:ident, $key_file:expr, $base_hash_algo:expr, $data:expr) => {{
+        use $curve::ecdsa::signature::hazmat::PrehashSigner;
+        use $curve::pkcs8::DecodePrivateKey;
+
+        let crate_dir = get_test_key_directory();
+        let key_file_path = crate_dir.join($key_file);
+        let der_file = std::fs::read(key_file_path).expect("unable to read key der!");
+        let key_bytes = der_file.as_slice();
+
+        let key_pair = $curve::ecdsa::SigningKey::from_pkcs8_der(key_bytes).unwrap();
+
+        let digest = crate::crypto::hash::hash_all($base_hash_algo, $data)?;
+        let signature: $curve::ecdsa::Signature = key_pair.sign_prehash(digest.as_ref()).unwrap();
+        let signature = signature.to_bytes();
+
+        let mut full_signature: [u8; SPDM_MAX_ASYM_KEY_SIZE] = [0u8; SPDM_MAX_ASYM_KEY_SIZE];
+        full_signature[..signature.len()].copy_from_slice(&signature);
+
+        Some(SpdmSignatureStruct {
+            data_size: signature.len() as u16,
+            data: full_signature,
+        })
+    }};
+}
+
 fn asym_sign(
     base_hash_algo: SpdmBaseHashAlgo,
     base_asym_algo: SpdmBaseAsymAlgo,
+    _context: &[u8],
     data: &[u8],
 ) -> Option<SpdmSignatureStruct> {
     match (base_hash_algo, base_asym_algo) {
         (SpdmBaseHashAlgo::TPM_ALG_SHA_256, SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256) => {
-            sign_ecdsa_asym_algo(&ring::signature::ECDSA_P256_SHA256_FIXED_SIGNING, data)
+            sign_ecdsa_asym_algo!(
+                p256,
+                "test_key/EcP256/end_responder.key.p8",
+                base_hash_algo,
+                data
+            )
         }
         (SpdmBaseHashAlgo::TPM_ALG_SHA_384, SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384) => {
-            sign_ecdsa_asym_algo(&ring::signature::ECDSA_P384_SHA384_FIXED_SIGNING, data)
+            sign_ecdsa_asym_algo!(
+                p384,
+                "test_key/EcP384/end_responder.key.p8",
+                base_hash_algo,
+                data
+            )
         }
         _ => {
             panic!();
@@ -278,35 +329,6 @@ fn asym_sign(
     }
 }
 
-fn sign_ecdsa_asym_algo(
-    algorithm: &'static ring::signature::EcdsaSigningAlgorithm,
-    data: &[u8],
-) -> Option<SpdmSignatureStruct> {
-    let crate_dir = get_test_key_directory();
-    let key_file_path = crate_dir.join("test_key/EcP384/end_responder.key.p8");
-    let der_file = std::fs::read(key_file_path).expect("unable to read key der!");
-    let key_bytes = der_file.as_slice();
-
-    let key_pair: ring::signature::EcdsaKeyPair =
-        ring::signature::EcdsaKeyPair::from_pkcs8(algorithm, key_bytes).unwrap();
-
-    let rng = ring::rand::SystemRandom::new();
-
-    let signature = key_pair.sign(&rng, data).unwrap();
-    let signature = signature.as_ref();
-
-    let mut full_signature: [u8; SPDM_MAX_ASYM_KEY_SIZE] = [0u8; SPDM_MAX_ASYM_KEY_SIZE];
-    full_signature[..signature.len()].copy_from_slice(signature);
-
-    //debug!("ecdsa signature len - 0x{:x?}\n", signature.len());
-    //debug!("ecdsa signature - {:x?}\n", signature);
-
-    Some(SpdmSignatureStruct {
-        data_size: signature.len() as u16,
-        data: full_signature,
-    })
-}
-
 pub struct FakeSpdmDeviceIo<'a> {
     pub data: &'a SharedBuffer,
     pub responder: &'a mut responder::ResponderContext<'a>,
@@ -343,6 +365,91 @@ impl SpdmDeviceIo for FakeSpdmDeviceIo<'_> {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FakeFault {
+    SendFailure,
+    NoResponse,
+    Error(SpdmErrorCode),
+}
+
+/// The faults for the next requests, and the codes of the requests seen.
+#[derive(Default)]
+pub struct FakeFaults {
+    pub faults: Mutex<VecDeque<FakeFault>>,
+    pub request_codes: Mutex<Vec<u8>>,
+}
+
+/// FakeSpdmDeviceIo which answers the next requests with the queued
+/// faults instead of passing them to the responder.
+pub struct FakeFaultyDeviceIo<'a> {
+    pub data: &'a SharedBuffer,
+    pub responder: &'a mut responder::ResponderContext<'a>,
+    pub faults: &'a FakeFaults,
+}
+
+impl<'a> FakeFaultyDeviceIo<'a> {
+    pub fn new(
+        data: &'a SharedBuffer,
+        responder: &'a mut responder::ResponderContext<'a>,
+        faults: &'a FakeFaults,
+    ) -> Self {
+        FakeFaultyDeviceIo {
+            data,
+            responder,
+            faults,
+        }
+    }
+}
+
+impl SpdmDeviceIo for FakeFaultyDeviceIo<'_> {
+    fn receive(&mut self, read_buffer: &mut [u8]) -> Result<usize, usize> {
+        let len = self.data.get_buffer(read_buffer);
+        if len == 0 {
+            return Err(0);
+        }
+        Ok(len)
+    }
+
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        let mut transport_encap = PciDoeTransportEncap {};
+        let mut spdm_buffer = [0u8; 1024];
+        if let Ok((used, false)) = transport_encap.decap(buffer, &mut spdm_buffer) {
+            if used >= 2 {
+                self.faults
+                    .request_codes
+                    .lock()
+                    .unwrap()
+                    .push(spdm_buffer[1]);
+            }
+        }
+
+        let fault = self.faults.faults.lock().unwrap().pop_front();
+        match fault {
+            Some(FakeFault::SendFailure) => spdm_result_err!(EIO),
+            Some(FakeFault::NoResponse) => Ok(()),
+            Some(FakeFault::Error(error_code)) => {
+                let mut transport_buffer = [0u8; 1024];
+                let used = transport_encap.encap(
+                    &[0x11, 0x7F, error_code.get_u8(), 0x00],
+                    &mut transport_buffer,
+                    false,
+                )?;
+                self.data.set_buffer(&transport_buffer[..used]);
+                Ok(())
+            }
+            None => {
+                self.data.set_buffer(buffer);
+                let _ = self.responder.process_message();
+                Ok(())
+            }
+        }
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        Ok(())
+    }
+}
+
 pub struct SpdmDeviceIoReceve<'a> {
     data: &'a SharedBuffer,
     fuzzdata: &'a [u8],
@@ -404,25 +511,25 @@ impl SpdmDeviceIo for FakeSpdmDeviceIoReceve<'_> {
 }
 
 pub struct SharedBuffer {
-    queue: RefCell<VecDeque<u8>>,
+    queue: Mutex<VecDeque<u8>>,
 }
 
 impl SharedBuffer {
     pub fn new() -> Self {
         SharedBuffer {
-            queue: RefCell::new(VecDeque::<u8>::new()),
+            queue: Mutex::new(VecDeque::<u8>::new()),
         }
     }
     pub fn set_buffer(&self, b: &[u8]) {
         log::info!("send    {:02x?}\n", b);
-        let mut queue = self.queue.borrow_mut();
+        let mut queue = self.queue.lock().unwrap();
         for i in b {
             queue.push_back(*i);
         }
     }
 
     pub fn get_buffer(&self, b: &mut [u8]) -> usize {
-        let mut queue = self.queue.borrow_mut();
+        let mut queue = self.queue.lock().unwrap();
         let mut len = 0usize;
         for i in b.iter_mut() {
             if queue.is_empty() {
@@ -0,0 +1,149 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+extern crate alloc;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::future::Future;
+use core::pin::pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+use spdmlib::common::SpdmAsyncDeviceIo;
+use spdmlib::error::SpdmResult;
+use std::sync::Mutex;
+use std::task::Wake;
+use std::thread::{self, Thread};
+
+/// Two message queues between a requester and a responder, one for each
+/// direction, and the wakers of the pending receives.
+pub struct FakeAsyncChannel {
+    queues: [Mutex<VecDeque<Vec<u8>>>; 2],
+    wakers: [Mutex<Option<Waker>>; 2],
+    closed: AtomicBool,
+}
+
+impl FakeAsyncChannel {
+    pub fn new() -> Self {
+        FakeAsyncChannel {
+            queues: [Mutex::new(VecDeque::new()), Mutex::new(VecDeque::new())],
+            wakers: [Mutex::new(None), Mutex::new(None)],
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Fail the receives once the queues are empty.
+    fn close(&self) {
+        for side in 0..2 {
+            // under the queue lock, so a receive that found the queue empty
+            // has registered its waker before it is woken
+            let _queue = self.queues[side].lock().unwrap();
+            self.closed.store(true, Ordering::SeqCst);
+            self.wake(side);
+        }
+    }
+
+    fn wake(&self, side: usize) {
+        if let Some(waker) = self.wakers[side].lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+}
+
+/// One end of a FakeAsyncChannel. A receive is pending until the other
+/// end has sent a message, and wakes its task then. Dropping an end
+/// closes the channel.
+pub struct FakeAsyncDeviceIo<'a> {
+    pub channel: &'a FakeAsyncChannel,
+    pub side: usize,
+}
+
+impl<'a> FakeAsyncDeviceIo<'a> {
+    pub fn new(channel: &'a FakeAsyncChannel, side: usize) -> Self {
+        FakeAsyncDeviceIo { channel, side }
+    }
+}
+
+impl Drop for FakeAsyncDeviceIo<'_> {
+    fn drop(&mut self) {
+        self.channel.close();
+    }
+}
+
+impl SpdmAsyncDeviceIo for FakeAsyncDeviceIo<'_> {
+    fn poll_send(&mut self, _cx: &mut Context<'_>, buffer: &[u8]) -> Poll<SpdmResult> {
+        self.channel.queues[1 - self.side]
+            .lock()
+            .unwrap()
+            .push_back(buffer.to_vec());
+        self.channel.wake(1 - self.side);
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_receive(
+        &mut self,
+        cx: &mut Context<'_>,
+        buffer: &mut [u8],
+    ) -> Poll<Result<usize, usize>> {
+        let mut queue = self.channel.queues[self.side].lock().unwrap();
+        match queue.pop_front() {
+            Some(message) => {
+                if buffer.len() < message.len() {
+                    return Poll::Ready(Err(message.len()));
+                }
+                buffer[..message.len()].copy_from_slice(&message);
+                Poll::Ready(Ok(message.len()))
+            }
+            None if self.channel.closed.load(Ordering::SeqCst) => Poll::Ready(Err(0)),
+            None => {
+                // registered before the queue is unlocked, so a send can
+                // not slip in between
+                *self.channel.wakers[self.side].lock().unwrap() = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Poll `main` and `background` in turn on this thread until `main` is
+/// done, and return its output.
+pub fn run_with_background<T>(
+    main: impl Future<Output = T>,
+    background: impl Future<Output = ()>,
+) -> T {
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut main = Box::pin(main);
+    let mut background = Box::pin(background);
+    let mut background_done = false;
+    loop {
+        if let Poll::Ready(output) = main.as_mut().poll(&mut cx) {
+            return output;
+        }
+        if !background_done {
+            background_done = background.as_mut().poll(&mut cx).is_ready();
+        }
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Run `future` on this thread, parking it while the future is pending
+/// until its waker is called.
+pub fn block_on_thread<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
@@ -2,81 +2,106 @@
 //
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
-use spdmlib::crypto::SpdmAsymSign;
+use spdmlib::crypto::{SpdmAsymSign, SpdmExtAlg};
+use spdmlib::error::SpdmResult;
 
 use spdmlib::msgs::{
-    SpdmBaseAsymAlgo, SpdmBaseHashAlgo, SpdmSignatureStruct, SPDM_MAX_ASYM_KEY_SIZE,
+    SpdmBaseAsymAlgo, SpdmBaseHashAlgo, SpdmDigestStruct, SpdmExtAlgStruct, SpdmSignatureStruct,
+    SpdmStandardId, ECDSA_ECC_NIST_P384_KEY_SIZE, SPDM_MAX_ASYM_KEY_SIZE,
 };
 
 use super::utils::get_test_key_directory;
 
 pub static ASYM_SIGN_IMPL: SpdmAsymSign = SpdmAsymSign { sign_cb: asym_sign };
 
+/// A made-up extended signature algorithm, ECDSA P-384 underneath.
+pub const EXT_ASYM_ALGO_TEST: SpdmExtAlgStruct = SpdmExtAlgStruct {
+    registry_id: SpdmStandardId::SpdmStandardIdIANA,
+    reserved: 0,
+    algorithm_id: 0x8001,
+};
+
+/// A made-up extended signature algorithm with signatures too large for
+/// SpdmSignatureStruct.
+pub const EXT_ASYM_ALGO_TOO_LARGE: SpdmExtAlgStruct = SpdmExtAlgStruct {
+    registry_id: SpdmStandardId::SpdmStandardIdIANA,
+    reserved: 0,
+    algorithm_id: 0x8002,
+};
+
+pub static EXT_ALG_IMPL: SpdmExtAlg = SpdmExtAlg {
+    hash_size_cb: |_ext_hash_algo: &SpdmExtAlgStruct| -> Option<u16> { None },
+    signature_size_cb: |ext_asym_algo: &SpdmExtAlgStruct| -> Option<u16> {
+        if *ext_asym_algo == EXT_ASYM_ALGO_TEST {
+            Some(ECDSA_ECC_NIST_P384_KEY_SIZE as u16)
+        } else if *ext_asym_algo == EXT_ASYM_ALGO_TOO_LARGE {
+            Some(SPDM_MAX_ASYM_KEY_SIZE as u16 + 1)
+        } else {
+            None
+        }
+    },
+    hash_all_cb: |_ext_hash_algo: &SpdmExtAlgStruct, _data: &[u8]| -> Option<SpdmDigestStruct> {
+        None
+    },
+    sign_cb: ext_asym_sign,
+    verify_cb: ext_asym_verify,
+};
+
+fn ext_asym_sign(
+    base_hash_algo: SpdmBaseHashAlgo,
+    ext_asym_algo: &SpdmExtAlgStruct,
+    data: &[u8],
+) -> Option<SpdmSignatureStruct> {
+    if *ext_asym_algo != EXT_ASYM_ALGO_TEST {
+        return None;
+    }
+    asym_sign(
+        base_hash_algo,
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+        &[],
+        data,
+    )
+}
+
+fn ext_asym_verify(
+    base_hash_algo: SpdmBaseHashAlgo,
+    ext_asym_algo: &SpdmExtAlgStruct,
+    public_cert_der: &[u8],
+    data: &[u8],
+    signature: &SpdmSignatureStruct,
+) -> SpdmResult {
+    if *ext_asym_algo != EXT_ASYM_ALGO_TEST {
+        return Err(spdmlib::spdm_err!(ENOSYS));
+    }
+    spdmlib::crypto::asym_verify::verify(
+        base_hash_algo,
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+        &[],
+        public_cert_der,
+        data,
+        signature,
+    )
+}
+
 fn asym_sign(
     base_hash_algo: SpdmBaseHashAlgo,
     base_asym_algo: SpdmBaseAsymAlgo,
+    _context: &[u8],
     data: &[u8],
 ) -> Option<SpdmSignatureStruct> {
-    match (base_hash_algo, base_asym_algo) {
-        (SpdmBaseHashAlgo::TPM_ALG_SHA_256, SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256) => {
-            sign_ecdsa_asym_algo(&ring::signature::ECDSA_P256_SHA256_FIXED_SIGNING, data)
-        }
-        (SpdmBaseHashAlgo::TPM_ALG_SHA_384, SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384) => {
-            sign_ecdsa_asym_algo(&ring::signature::ECDSA_P384_SHA384_FIXED_SIGNING, data)
-        }
-        (SpdmBaseHashAlgo::TPM_ALG_SHA_256, SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048)
-        | (SpdmBaseHashAlgo::TPM_ALG_SHA_256, SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072)
-        | (SpdmBaseHashAlgo::TPM_ALG_SHA_256, SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096) => {
-            sign_rsa_asym_algo(
-                &ring::signature::RSA_PKCS1_SHA256,
-                base_asym_algo.get_size() as usize,
-                data,
-            )
-        }
-        (SpdmBaseHashAlgo::TPM_ALG_SHA_256, SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_2048)
-        | (SpdmBaseHashAlgo::TPM_ALG_SHA_256, SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072)
-        | (SpdmBaseHashAlgo::TPM_ALG_SHA_256, SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096) => {
-            sign_rsa_asym_algo(
-                &ring::signature::RSA_PSS_SHA256,
-                base_asym_algo.get_size() as usize,
-                data,
-            )
-        }
-        (SpdmBaseHashAlgo::TPM_ALG_SHA_384, SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048)
-        | (SpdmBaseHashAlgo::TPM_ALG_SHA_384, SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072)
-        | (SpdmBaseHashAlgo::TPM_ALG_SHA_384, SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096) => {
-            sign_rsa_asym_algo(
-                &ring::signature::RSA_PKCS1_SHA384,
-                base_asym_algo.get_size() as usize,
-                data,
-            )
+    match base_asym_algo {
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256
+        | SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384
+        | SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521 => {
+            sign_ecdsa_asym_algo(base_hash_algo, base_asym_algo, data)
         }
-        (SpdmBaseHashAlgo::TPM_ALG_SHA_384, SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_2048)
-        | (SpdmBaseHashAlgo::TPM_ALG_SHA_384, SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072)
-        | (SpdmBaseHashAlgo::TPM_ALG_SHA_384, SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096) => {
-            sign_rsa_asym_algo(
-                &ring::signature::RSA_PSS_SHA384,
-                base_asym_algo.get_size() as usize,
-                data,
-            )
-        }
-        (SpdmBaseHashAlgo::TPM_ALG_SHA_512, SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048)
-        | (SpdmBaseHashAlgo::TPM_ALG_SHA_512, SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072)
-        | (SpdmBaseHashAlgo::TPM_ALG_SHA_512, SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096) => {
-            sign_rsa_asym_algo(
-                &ring::signature::RSA_PKCS1_SHA512,
-                base_asym_algo.get_size() as usize,
-                data,
-            )
-        }
-        (SpdmBaseHashAlgo::TPM_ALG_SHA_512, SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_2048)
-        | (SpdmBaseHashAlgo::TPM_ALG_SHA_512, SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072)
-        | (SpdmBaseHashAlgo::TPM_ALG_SHA_512, SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096) => {
-            sign_rsa_asym_algo(
-                &ring::signature::RSA_PSS_SHA512,
-                base_asym_algo.get_size() as usize,
-                data,
-            )
+        SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048
+        | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072
+        | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_2048
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072
+        | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096 => {
+            sign_rsa_asym_algo(base_hash_algo, base_asym_algo, data)
         }
         _ => {
             panic!();
@@ -84,33 +109,53 @@ fn asym_sign(
     }
 }
 
+/// Sign over the digest so that every hash goes with every curve.
 fn sign_ecdsa_asym_algo(
-    algorithm: &'static ring::signature::EcdsaSigningAlgorithm,
+    base_hash_algo: SpdmBaseHashAlgo,
+    base_asym_algo: SpdmBaseAsymAlgo,
     data: &[u8],
 ) -> Option<SpdmSignatureStruct> {
     // openssl genpkey -algorithm ec -pkeyopt ec_paramgen_curve:P-256 -pkeyopt ec_param_enc:named_curve -outform DER > private.der
     // or  openssl.exe ecparam -name prime256v1 -genkey -out private.der -outform der
     // openssl.exe pkcs8 -in private.der -inform DER -topk8 -nocrypt -outform DER > private.p8
+    use p384::ecdsa::signature::hazmat::PrehashSigner;
+    use p384::pkcs8::DecodePrivateKey;
+
+    let digest = spdmlib::crypto::hash::hash_all(base_hash_algo, data)?;
+    // a digest shorter than the group order is used as is, left pad it
+    let field_size = base_asym_algo.get_size() as usize / 2;
+    let mut prehash = vec![0u8; field_size.saturating_sub(digest.as_ref().len())];
+    prehash.extend_from_slice(digest.as_ref());
 
     let crate_dir = get_test_key_directory();
-    let key_file_path = if super::USE_ECDSA {
-        crate_dir.join("test_key/EcP384/end_responder.key.p8")
-    } else {
-        crate_dir.join("test_key/Rsa3072/end_responder.key.der")
+    let signature = match base_asym_algo {
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256 => {
+            let der_file = std::fs::read(crate_dir.join("test_key/EcP256/end_responder.key.p8"))
+                .expect("unable to read key der!");
+            let key_pair = p256::ecdsa::SigningKey::from_pkcs8_der(&der_file).unwrap();
+            let signature: p256::ecdsa::Signature = key_pair.sign_prehash(&prehash).unwrap();
+            signature.to_vec()
+        }
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384 => {
+            let der_file = std::fs::read(crate_dir.join("test_key/EcP384/end_responder.key.p8"))
+                .expect("unable to read key der!");
+            let key_pair = p384::ecdsa::SigningKey::from_pkcs8_der(&der_file).unwrap();
+            let signature: p384::ecdsa::Signature = key_pair.sign_prehash(&prehash).unwrap();
+            signature.to_vec()
+        }
+        SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521 => {
+            let der_file = std::fs::read(crate_dir.join("test_key/EcP521/end_responder.key.p8"))
+                .expect("unable to read key der!");
+            let secret_key = p521::SecretKey::from_pkcs8_der(&der_file).unwrap();
+            let key_pair = p521::ecdsa::SigningKey::from_bytes(&secret_key.to_bytes()).unwrap();
+            let signature: p521::ecdsa::Signature = key_pair.sign_prehash(&prehash).unwrap();
+            signature.to_vec()
+        }
+        _ => return None,
     };
-    let der_file = std::fs::read(key_file_path).expect("unable to read key der!");
-    let key_bytes = der_file.as_slice();
-
-    let key_pair: ring::signature::EcdsaKeyPair =
-        ring::signature::EcdsaKeyPair::from_pkcs8(algorithm, key_bytes).unwrap();
-
-    let rng = ring::rand::SystemRandom::new();
-
-    let signature = key_pair.sign(&rng, data).unwrap();
-    let signature = signature.as_ref();
 
     let mut full_signature: [u8; SPDM_MAX_ASYM_KEY_SIZE] = [0u8; SPDM_MAX_ASYM_KEY_SIZE];
-    full_signature[..signature.len()].copy_from_slice(signature);
+    full_signature[..signature.len()].copy_from_slice(&signature);
 
     Some(SpdmSignatureStruct {
         data_size: signature.len() as u16,
@@ -118,35 +163,60 @@ fn sign_ecdsa_asym_algo(
     })
 }
 
+macro_rules! rsa_sign {
+    ($private_key:expr, $pss:expr, $digest:ty, $hashed:expr) => {
+        if $pss {
+            $private_key.sign_with_rng(
+                &mut rsa::rand_core::OsRng,
+                rsa::Pss::new::<$digest>(),
+                $hashed,
+            )
+        } else {
+            $private_key.sign(rsa::Pkcs1v15Sign::new::<$digest>(), $hashed)
+        }
+    };
+}
+
 fn sign_rsa_asym_algo(
-    padding_alg: &'static dyn ring::signature::RsaEncoding,
-    key_len: usize,
+    base_hash_algo: SpdmBaseHashAlgo,
+    base_asym_algo: SpdmBaseAsymAlgo,
     data: &[u8],
 ) -> Option<SpdmSignatureStruct> {
     // openssl.exe genpkey -algorithm rsa -pkeyopt rsa_keygen_bits:2048 -pkeyopt rsa_keygen_pubexp:65537 -outform DER > private.der
+    use rsa::pkcs1::DecodeRsaPrivateKey;
+    use rsa::traits::PublicKeyParts;
 
     let crate_dir = get_test_key_directory();
-    let key_file_path = if super::USE_ECDSA {
-        crate_dir.join("test_key/EcP384/end_responder.key.p8")
-    } else {
-        crate_dir.join("test_key/Rsa3072/end_responder.key.der")
-    };
+    let key_file_path = crate_dir.join("test_key/Rsa3072/end_responder.key.der");
     let der_file = std::fs::read(key_file_path).expect("unable to read key der!");
-    let key_bytes = der_file.as_slice();
-
-    let key_pair: ring::signature::RsaKeyPair =
-        ring::signature::RsaKeyPair::from_der(key_bytes).unwrap();
+    let private_key = rsa::RsaPrivateKey::from_pkcs1_der(&der_file).unwrap();
 
-    if key_len != key_pair.public_modulus_len() {
+    let key_len = base_asym_algo.get_size() as usize;
+    if key_len != private_key.size() {
         panic!();
     }
 
-    let rng = ring::rand::SystemRandom::new();
+    let pss = matches!(
+        base_asym_algo,
+        SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_2048
+            | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072
+            | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096
+    );
+    let digest = spdmlib::crypto::hash::hash_all(base_hash_algo, data)?;
+    let digest = digest.as_ref();
+    let signature = match base_hash_algo {
+        SpdmBaseHashAlgo::TPM_ALG_SHA_256 => rsa_sign!(private_key, pss, sha2::Sha256, digest),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_384 => rsa_sign!(private_key, pss, sha2::Sha384, digest),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_512 => rsa_sign!(private_key, pss, sha2::Sha512, digest),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => rsa_sign!(private_key, pss, sha3::Sha3_256, digest),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => rsa_sign!(private_key, pss, sha3::Sha3_384, digest),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => rsa_sign!(private_key, pss, sha3::Sha3_512, digest),
+        _ => return None,
+    }
+    .unwrap();
 
     let mut full_sign = [0u8; SPDM_MAX_ASYM_KEY_SIZE];
-    key_pair
-        .sign(padding_alg, &rng, data, &mut full_sign[0..key_len])
-        .unwrap();
+    full_sign[..key_len].copy_from_slice(&signature);
 
     Some(SpdmSignatureStruct {
         data_size: key_len as u16,
@@ -5,9 +5,12 @@
 #![forbid(unsafe_code)]
 
 use super::shared_buffer::SharedBuffer;
+use pcidoe_transport::PciDoeHostIo;
 use spdmlib::common::SpdmDeviceIo;
+use spdmlib::config;
 use spdmlib::error::SpdmResult;
 use spdmlib::responder;
+use storage_transport::StorageHostIo;
 
 pub struct FakeSpdmDeviceIoReceve<'a> {
     data: &'a SharedBuffer,
@@ -68,6 +71,101 @@ impl SpdmDeviceIo for FakeSpdmDeviceIo<'_> {
     }
 }
 
+/// Drive the responder without its device IO: each request is passed to
+/// process_transport_message and the response kept for the next receive.
+pub struct FakeSansIoDeviceIo<'a> {
+    pub data: &'a SharedBuffer,
+    pub responder: &'a mut responder::ResponderContext<'a>,
+}
+
+impl<'a> FakeSansIoDeviceIo<'a> {
+    pub fn new(data: &'a SharedBuffer, responder: &'a mut responder::ResponderContext<'a>) -> Self {
+        FakeSansIoDeviceIo { data, responder }
+    }
+}
+
+impl SpdmDeviceIo for FakeSansIoDeviceIo<'_> {
+    fn receive(&mut self, read_buffer: &mut [u8]) -> Result<usize, usize> {
+        let len = self.data.get_buffer(read_buffer);
+        if len == 0 {
+            return Err(0);
+        }
+        Ok(len)
+    }
+
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        let mut response = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let (used, _) = self
+            .responder
+            .process_transport_message(buffer, &mut response)?;
+        self.data.set_buffer(&response[..used]);
+        Ok(())
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        Ok(())
+    }
+}
+
+pub struct FakePciDoeDeviceIo<'a> {
+    pub host: PciDoeHostIo<'a>,
+    pub responder: &'a mut responder::ResponderContext<'a>,
+}
+
+impl<'a> FakePciDoeDeviceIo<'a> {
+    pub fn new(host: PciDoeHostIo<'a>, responder: &'a mut responder::ResponderContext<'a>) -> Self {
+        FakePciDoeDeviceIo { host, responder }
+    }
+}
+
+impl SpdmDeviceIo for FakePciDoeDeviceIo<'_> {
+    fn receive(&mut self, read_buffer: &mut [u8]) -> Result<usize, usize> {
+        self.host.receive(read_buffer)
+    }
+
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        self.host.send(buffer)?;
+
+        let _res = self.responder.process_message();
+        Ok(())
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        Ok(())
+    }
+}
+
+pub struct FakeStorageDeviceIo<'a> {
+    pub host: StorageHostIo<'a>,
+    pub responder: &'a mut responder::ResponderContext<'a>,
+}
+
+impl<'a> FakeStorageDeviceIo<'a> {
+    pub fn new(
+        host: StorageHostIo<'a>,
+        responder: &'a mut responder::ResponderContext<'a>,
+    ) -> Self {
+        FakeStorageDeviceIo { host, responder }
+    }
+}
+
+impl SpdmDeviceIo for FakeStorageDeviceIo<'_> {
+    fn receive(&mut self, read_buffer: &mut [u8]) -> Result<usize, usize> {
+        self.host.receive(read_buffer)
+    }
+
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        self.host.send(buffer)?;
+
+        let _res = self.responder.process_message();
+        Ok(())
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        Ok(())
+    }
+}
+
 #[test]
 fn test_fake_device_io() {
     let buffer = SharedBuffer::new();
@@ -2,6 +2,7 @@
 //
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
+pub mod async_device_io;
 pub mod crypto_callbacks;
 pub mod fake_device_io;
 pub mod shared_buffer;
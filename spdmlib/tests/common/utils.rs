@@ -14,6 +14,21 @@ pub fn get_test_key_directory() -> PathBuf {
     crate_dir.to_path_buf()
 }
 
+/// Load the responder certificate chain (CA, intermediate and leaf) from test_key/`key_dir`
+pub fn responder_cert_chain_data(key_dir: &str) -> SpdmCertChainData {
+    let key_dir = get_test_key_directory().join("test_key").join(key_dir);
+    let mut cert_chain_data = SpdmCertChainData {
+        ..Default::default()
+    };
+    for cert_file in ["ca.cert.der", "inter.cert.der", "end_responder.cert.der"].iter() {
+        let cert = std::fs::read(key_dir.join(cert_file)).expect("unable to read cert!");
+        let offset = cert_chain_data.data_size as usize;
+        cert_chain_data.data[offset..(offset + cert.len())].copy_from_slice(cert.as_ref());
+        cert_chain_data.data_size += cert.len() as u16;
+    }
+    cert_chain_data
+}
+
 /// Create requester config and provision info
 pub fn req_create_info() -> (common::SpdmConfigInfo, common::SpdmProvisionInfo) {
     let config_info = common::SpdmConfigInfo {
@@ -3,13 +3,37 @@
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
 mod common;
-use common::fake_device_io::{FakeSpdmDeviceIo, FakeSpdmDeviceIoReceve};
+use common::async_device_io::{
+    block_on_thread, run_with_background, FakeAsyncChannel, FakeAsyncDeviceIo,
+};
+use common::fake_device_io::{
+    FakePciDoeDeviceIo, FakeSansIoDeviceIo, FakeSpdmDeviceIo, FakeSpdmDeviceIoReceve,
+    FakeStorageDeviceIo,
+};
 use common::shared_buffer::SharedBuffer;
+use spin::Mutex;
 
-use pcidoe_transport::PciDoeTransportEncap;
-use spdmlib::msgs::SpdmMeasurementSummaryHashType;
+use common::crypto_callbacks::{EXT_ASYM_ALGO_TEST, EXT_ASYM_ALGO_TOO_LARGE};
+#[cfg(target_os = "linux")]
+use mctp_socket::{mctp_socket_pair, MctpSocketIo, MCTP_ADDR_ANY};
+use mctp_transport::{MctpMessageType, MctpTransportEncap};
+use pcidoe_transport::{
+    PciDoeDeviceIo, PciDoeHostIo, PciDoeMailboxModel, PciDoeTransportEncap,
+    PCI_DOE_DEFAULT_POLL_LIMIT,
+};
+use spdmlib::common::SpdmAppMessageType;
+use spdmlib::msgs::{
+    SpdmBaseAsymAlgo, SpdmBaseHashAlgo, SpdmDheAlgo, SpdmMeasurementOperation,
+    SpdmMeasurementSummaryHashType,
+};
 use spdmlib::requester;
 use spdmlib::responder;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use storage_transport::{
+    StorageDeviceIo, StorageDriveModel, StorageHostIo, StorageSpdmOperation, StorageTransportEncap,
+};
+use tcp_transport::{TcpDeviceIo, TcpRole, TcpTransportEncap};
 
 #[test]
 fn intergration_client_server() {
@@ -82,3 +106,817 @@ fn intergration_client_server() {
         log::info!("\nSession session_id not got ????? \n");
     }
 }
+
+#[test]
+fn intergration_client_server_ext_asym() {
+    spdmlib::crypto::asym_sign::register(common::crypto_callbacks::ASYM_SIGN_IMPL);
+    spdmlib::crypto::ext_alg::register(common::crypto_callbacks::EXT_ALG_IMPL);
+
+    let shared_buffer = SharedBuffer::new();
+    let device_io_responder = &mut FakeSpdmDeviceIoReceve::new(&shared_buffer);
+    let transport_encap_responder = &mut PciDoeTransportEncap {};
+
+    // no base signature algorithm in common, so the extended one is used,
+    // not the one with too large signatures
+    assert!(spdmlib::crypto::ext_alg::get_signature_size(&EXT_ASYM_ALGO_TOO_LARGE).is_none());
+    let (mut config_info, provision_info) = common::utils::rsp_create_info();
+    config_info.base_asym_algo = SpdmBaseAsymAlgo::empty();
+    config_info.ext_asym_algo_count = 2;
+    config_info.ext_asym_algo[0] = EXT_ASYM_ALGO_TOO_LARGE;
+    config_info.ext_asym_algo[1] = EXT_ASYM_ALGO_TEST;
+    let mut responder_context = responder::ResponderContext::new(
+        device_io_responder,
+        transport_encap_responder,
+        config_info,
+        provision_info,
+    );
+
+    let device_io_requester = &mut FakeSpdmDeviceIo::new(&shared_buffer, &mut responder_context);
+    let transport_encap_requester = &mut PciDoeTransportEncap {};
+
+    let (mut config_info, provision_info) = common::utils::req_create_info();
+    config_info.ext_asym_algo_count = 2;
+    config_info.ext_asym_algo[0] = EXT_ASYM_ALGO_TOO_LARGE;
+    config_info.ext_asym_algo[1] = EXT_ASYM_ALGO_TEST;
+    let mut requester_context = requester::RequesterContext::new(
+        device_io_requester,
+        transport_encap_requester,
+        config_info,
+        provision_info,
+    );
+
+    assert!(requester_context.init_connection().is_ok());
+    assert!(requester_context
+        .common
+        .negotiate_info
+        .base_asym_sel
+        .is_empty());
+    assert_eq!(
+        requester_context.common.negotiate_info.ext_asym_sel,
+        Some(EXT_ASYM_ALGO_TEST)
+    );
+
+    assert!(requester_context.send_receive_spdm_digest().is_ok());
+    assert!(requester_context.send_receive_spdm_certificate(0).is_ok());
+    assert!(requester_context
+        .send_receive_spdm_challenge(
+            0,
+            SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+        )
+        .is_ok());
+    assert!(requester_context
+        .start_session(
+            false,
+            0,
+            SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+        )
+        .is_ok());
+}
+
+#[test]
+fn intergration_client_server_ffdhe() {
+    spdmlib::crypto::asym_sign::register(common::crypto_callbacks::ASYM_SIGN_IMPL);
+
+    let shared_buffer = SharedBuffer::new();
+    let device_io_responder = &mut FakeSpdmDeviceIoReceve::new(&shared_buffer);
+    let transport_encap_responder = &mut PciDoeTransportEncap {};
+
+    let (mut config_info, provision_info) = common::utils::rsp_create_info();
+    config_info.dhe_algo = SpdmDheAlgo::FFDHE_2048;
+    let mut responder_context = responder::ResponderContext::new(
+        device_io_responder,
+        transport_encap_responder,
+        config_info,
+        provision_info,
+    );
+
+    let device_io_requester = &mut FakeSpdmDeviceIo::new(&shared_buffer, &mut responder_context);
+    let transport_encap_requester = &mut PciDoeTransportEncap {};
+
+    let (mut config_info, provision_info) = common::utils::req_create_info();
+    config_info.dhe_algo = SpdmDheAlgo::FFDHE_2048 | SpdmDheAlgo::SECP_384_R1;
+    let mut requester_context = requester::RequesterContext::new(
+        device_io_requester,
+        transport_encap_requester,
+        config_info,
+        provision_info,
+    );
+
+    assert!(requester_context.init_connection().is_ok());
+    assert_eq!(
+        requester_context.common.negotiate_info.dhe_sel,
+        SpdmDheAlgo::FFDHE_2048
+    );
+
+    assert!(requester_context.send_receive_spdm_digest().is_ok());
+    assert!(requester_context.send_receive_spdm_certificate(0).is_ok());
+    assert!(requester_context
+        .start_session(
+            false,
+            0,
+            SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+        )
+        .is_ok());
+}
+
+#[test]
+fn intergration_client_server_sha3_p521() {
+    spdmlib::crypto::asym_sign::register(common::crypto_callbacks::ASYM_SIGN_IMPL);
+
+    for (base_hash_algo, base_asym_algo, dhe_algo, key_dir) in [
+        (
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+            SpdmDheAlgo::SECP_384_R1,
+            "EcP384",
+        ),
+        (
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+            SpdmDheAlgo::SECP_384_R1,
+            "EcP384",
+        ),
+        (
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+            SpdmDheAlgo::SECP_521_R1,
+            "EcP384",
+        ),
+        (
+            SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
+            SpdmDheAlgo::SECP_521_R1,
+            "EcP521",
+        ),
+        (
+            SpdmBaseHashAlgo::TPM_ALG_SHA_384,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
+            SpdmDheAlgo::SECP_384_R1,
+            "EcP521",
+        ),
+        (
+            SpdmBaseHashAlgo::TPM_ALG_SHA_512,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
+            SpdmDheAlgo::SECP_521_R1,
+            "EcP521",
+        ),
+        (
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_256,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
+            SpdmDheAlgo::SECP_521_R1,
+            "EcP521",
+        ),
+        (
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_384,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
+            SpdmDheAlgo::SECP_521_R1,
+            "EcP521",
+        ),
+        (
+            SpdmBaseHashAlgo::TPM_ALG_SHA3_512,
+            SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
+            SpdmDheAlgo::SECP_521_R1,
+            "EcP521",
+        ),
+    ]
+    .iter()
+    {
+        let shared_buffer = SharedBuffer::new();
+        let device_io_responder = &mut FakeSpdmDeviceIoReceve::new(&shared_buffer);
+        let transport_encap_responder = &mut PciDoeTransportEncap {};
+
+        let (mut config_info, mut provision_info) = common::utils::rsp_create_info();
+        config_info.base_hash_algo = *base_hash_algo;
+        config_info.base_asym_algo = *base_asym_algo;
+        config_info.dhe_algo = *dhe_algo;
+        provision_info.my_cert_chain_data = Some(common::utils::responder_cert_chain_data(key_dir));
+        let mut responder_context = responder::ResponderContext::new(
+            device_io_responder,
+            transport_encap_responder,
+            config_info,
+            provision_info,
+        );
+
+        let device_io_requester =
+            &mut FakeSpdmDeviceIo::new(&shared_buffer, &mut responder_context);
+        let transport_encap_requester = &mut PciDoeTransportEncap {};
+
+        let (mut config_info, mut provision_info) = common::utils::req_create_info();
+        config_info.base_hash_algo = *base_hash_algo;
+        config_info.base_asym_algo = *base_asym_algo;
+        config_info.dhe_algo = *dhe_algo;
+        provision_info.peer_cert_chain_data =
+            Some(common::utils::responder_cert_chain_data(key_dir));
+        let mut requester_context = requester::RequesterContext::new(
+            device_io_requester,
+            transport_encap_requester,
+            config_info,
+            provision_info,
+        );
+
+        assert!(requester_context.init_connection().is_ok());
+        assert_eq!(
+            requester_context.common.negotiate_info.base_hash_sel,
+            *base_hash_algo
+        );
+        assert_eq!(
+            requester_context.common.negotiate_info.base_asym_sel,
+            *base_asym_algo
+        );
+
+        assert!(requester_context.send_receive_spdm_digest().is_ok());
+        assert!(requester_context.send_receive_spdm_certificate(0).is_ok());
+        assert!(requester_context
+            .send_receive_spdm_challenge(
+                0,
+                SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+            )
+            .is_ok());
+        assert!(requester_context
+            .start_session(
+                false,
+                0,
+                SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+            )
+            .is_ok());
+    }
+}
+
+#[test]
+fn intergration_client_server_sans_io() {
+    spdmlib::crypto::asym_sign::register(common::crypto_callbacks::ASYM_SIGN_IMPL);
+
+    let transport_encap_responder = &mut PciDoeTransportEncap {};
+
+    let (config_info, provision_info) = common::utils::rsp_create_info();
+    let mut responder_context = responder::ResponderContext::new_without_device_io(
+        transport_encap_responder,
+        config_info,
+        provision_info,
+    );
+
+    let shared_buffer = SharedBuffer::new();
+    let device_io_requester = &mut FakeSansIoDeviceIo::new(&shared_buffer, &mut responder_context);
+    let transport_encap_requester = &mut PciDoeTransportEncap {};
+
+    let (config_info, provision_info) = common::utils::req_create_info();
+    let mut requester_context = requester::RequesterContext::new(
+        device_io_requester,
+        transport_encap_requester,
+        config_info,
+        provision_info,
+    );
+
+    assert!(requester_context.init_connection().is_ok());
+    assert!(requester_context.send_receive_spdm_certificate(0).is_ok());
+
+    let session_id = requester_context
+        .start_session(
+            false,
+            0,
+            SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+        )
+        .unwrap();
+    assert!(requester_context
+        .send_receive_spdm_heartbeat(session_id)
+        .is_ok());
+    assert!(requester_context.end_session(session_id).is_ok());
+}
+
+#[test]
+fn intergration_client_server_async() {
+    spdmlib::crypto::asym_sign::register(common::crypto_callbacks::ASYM_SIGN_IMPL);
+
+    let channel = FakeAsyncChannel::new();
+
+    let transport_encap_responder = &mut PciDoeTransportEncap {};
+    let (config_info, provision_info) = common::utils::rsp_create_info();
+    let mut responder_context = responder::ResponderContext::new_without_device_io(
+        transport_encap_responder,
+        config_info,
+        provision_info,
+    );
+    let mut async_io_responder = FakeAsyncDeviceIo::new(&channel, 1);
+
+    let transport_encap_requester = &mut PciDoeTransportEncap {};
+    let (config_info, provision_info) = common::utils::req_create_info();
+    let mut requester_context = requester::RequesterContext::new_without_device_io(
+        transport_encap_requester,
+        config_info,
+        provision_info,
+    );
+    let mut async_io_requester = FakeAsyncDeviceIo::new(&channel, 0);
+
+    let requester = async {
+        let io = &mut async_io_requester;
+        requester_context.init_connection_async(io).await?;
+        requester_context.send_receive_spdm_digest_async(io).await?;
+        requester_context
+            .send_receive_spdm_certificate_async(io, 0)
+            .await?;
+        requester_context
+            .send_receive_spdm_challenge_async(
+                io,
+                0,
+                SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+            )
+            .await?;
+        requester_context
+            .send_receive_spdm_measurement_async(
+                io,
+                SpdmMeasurementOperation::SpdmMeasurementQueryTotalNumber,
+                0,
+            )
+            .await?;
+        for use_psk in [false, true].iter() {
+            let session_id = requester_context
+                .start_session_async(
+                    io,
+                    *use_psk,
+                    0,
+                    SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+                )
+                .await?;
+            requester_context.end_session_async(io, session_id).await?;
+        }
+        spdmlib::error::SpdmResult::Ok(())
+    };
+    let responder = async {
+        while responder_context
+            .process_message_async(&mut async_io_responder)
+            .await
+            .is_ok()
+        {}
+    };
+
+    assert!(run_with_background(requester, responder).is_ok());
+    // there is no blocking device IO
+    assert!(requester_context.init_connection().is_err());
+}
+
+#[test]
+fn intergration_client_server_async_threads() {
+    spdmlib::crypto::asym_sign::register(common::crypto_callbacks::ASYM_SIGN_IMPL);
+
+    let channel = FakeAsyncChannel::new();
+
+    let transport_encap_responder = &mut MctpTransportEncap {};
+    let (config_info, provision_info) = common::utils::rsp_create_info();
+    let mut responder_context = responder::ResponderContext::new_without_device_io(
+        transport_encap_responder,
+        config_info,
+        provision_info,
+    );
+    responder_context.register_app_message_handler(pldm_echo_handler);
+    let mut async_io_responder = FakeAsyncDeviceIo::new(&channel, 1);
+
+    let transport_encap_requester = &mut MctpTransportEncap {};
+    let (config_info, provision_info) = common::utils::req_create_info();
+    let mut requester_context = requester::RequesterContext::new_without_device_io(
+        transport_encap_requester,
+        config_info,
+        provision_info,
+    );
+    let mut async_io_requester = FakeAsyncDeviceIo::new(&channel, 0);
+
+    let pldm = MctpMessageType::MctpMessageTypePldm.get_u8();
+    // the requester end is dropped with the future, which closes the
+    // channel and stops the responder
+    let requester = async move {
+        let io = &mut async_io_requester;
+        requester_context.init_connection_async(io).await?;
+        requester_context.send_receive_spdm_digest_async(io).await?;
+        requester_context
+            .send_receive_spdm_certificate_async(io, 0)
+            .await?;
+        let session_id = requester_context
+            .start_session_async(
+                io,
+                false,
+                0,
+                SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+            )
+            .await?;
+
+        // HEARTBEAT
+        let mut response = [0u8; 64];
+        let used = requester_context
+            .send_receive_secured_message_async(
+                io,
+                session_id,
+                &[0x11, 0xE8, 0x00, 0x00],
+                &mut response,
+            )
+            .await?;
+        assert_eq!(&response[..used], &[0x11, 0x68, 0x00, 0x00]);
+
+        let request = [0x80, 0x00, 0x02];
+        let used = requester_context
+            .send_receive_secured_app_message_async(io, session_id, pldm, &request, &mut response)
+            .await?;
+        assert_eq!(&response[..used], &request[..]);
+
+        requester_context
+            .send_secured_app_message_async(io, session_id, pldm, &request)
+            .await?;
+        let (used, message_type) = requester_context
+            .receive_secured_app_message_async(io, session_id, &mut response)
+            .await?;
+        assert_eq!(message_type, SpdmAppMessageType::Other(pldm));
+        assert_eq!(&response[..used], &request[..]);
+
+        requester_context.end_session_async(io, session_id).await?;
+        spdmlib::error::SpdmResult::Ok(())
+    };
+    let responder = async {
+        while responder_context
+            .process_message_async(&mut async_io_responder)
+            .await
+            .is_ok()
+        {}
+    };
+
+    // the futures are moved to other threads, and woken when a message
+    // arrives
+    thread::scope(|scope| {
+        let responder = scope.spawn(move || block_on_thread(responder));
+        let requester = scope.spawn(move || block_on_thread(requester));
+        assert!(requester.join().unwrap().is_ok());
+        responder.join().unwrap();
+    });
+}
+
+#[test]
+fn intergration_client_server_pcidoe_mailbox() {
+    spdmlib::crypto::asym_sign::register(common::crypto_callbacks::ASYM_SIGN_IMPL);
+
+    let mailbox = Mutex::new(PciDoeMailboxModel::new());
+    let device_io_responder = &mut PciDoeDeviceIo::new(&mailbox);
+    let transport_encap_responder = &mut PciDoeTransportEncap {};
+
+    let (config_info, provision_info) = common::utils::rsp_create_info();
+    let mut responder_context = responder::ResponderContext::new(
+        device_io_responder,
+        transport_encap_responder,
+        config_info,
+        provision_info,
+    );
+
+    let device_io_requester =
+        &mut FakePciDoeDeviceIo::new(PciDoeHostIo::new(&mailbox), &mut responder_context);
+    let transport_encap_requester = &mut PciDoeTransportEncap {};
+
+    let (config_info, provision_info) = common::utils::req_create_info();
+    let mut requester_context = requester::RequesterContext::new(
+        device_io_requester,
+        transport_encap_requester,
+        config_info,
+        provision_info,
+    );
+
+    mailbox.lock().inject_busy(PCI_DOE_DEFAULT_POLL_LIMIT - 1);
+    assert!(requester_context.init_connection().is_ok());
+
+    mailbox.lock().inject_error();
+    assert!(requester_context.send_receive_spdm_digest().is_ok());
+
+    // the mailbox stays busy, the request never reaches the responder
+    mailbox.lock().inject_busy(PCI_DOE_DEFAULT_POLL_LIMIT);
+    assert!(requester_context.send_receive_spdm_certificate(0).is_err());
+
+    assert!(requester_context.send_receive_spdm_certificate(0).is_ok());
+
+    let result = requester_context.start_session(
+        false,
+        0,
+        SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn intergration_client_server_storage() {
+    spdmlib::crypto::asym_sign::register(common::crypto_callbacks::ASYM_SIGN_IMPL);
+
+    let drive = Mutex::new(StorageDriveModel::new());
+    let device_io_responder = &mut StorageDeviceIo::new(&drive);
+    let transport_encap_responder = &mut StorageTransportEncap { connection_id: 1 };
+
+    let (config_info, provision_info) = common::utils::rsp_create_info();
+    let mut responder_context = responder::ResponderContext::new(
+        device_io_responder,
+        transport_encap_responder,
+        config_info,
+        provision_info,
+    );
+
+    let mut host = StorageHostIo::new(&drive);
+    let discovery = host.discover().unwrap();
+    assert!(discovery.supports(StorageSpdmOperation::StorageSpdmOperationSecuredMessage));
+
+    let device_io_requester = &mut FakeStorageDeviceIo::new(host, &mut responder_context);
+    let transport_encap_requester = &mut StorageTransportEncap { connection_id: 1 };
+
+    let (config_info, provision_info) = common::utils::req_create_info();
+    let mut requester_context = requester::RequesterContext::new(
+        device_io_requester,
+        transport_encap_requester,
+        config_info,
+        provision_info,
+    );
+
+    assert!(requester_context.init_connection().is_ok());
+    assert!(requester_context.send_receive_spdm_digest().is_ok());
+    assert!(requester_context.send_receive_spdm_certificate(0).is_ok());
+
+    let session_id = requester_context
+        .start_session(
+            false,
+            0,
+            SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+        )
+        .unwrap();
+    assert!(requester_context
+        .send_receive_spdm_heartbeat(session_id)
+        .is_ok());
+    assert!(requester_context.end_session(session_id).is_ok());
+}
+
+#[test]
+fn intergration_client_server_tcp() {
+    spdmlib::crypto::asym_sign::register(common::crypto_callbacks::ASYM_SIGN_IMPL);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+    let responder = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let device_io_responder = &mut TcpDeviceIo::new(&mut stream, TcpRole::Responder);
+        let transport_encap_responder = &mut TcpTransportEncap {};
+
+        let (config_info, provision_info) = common::utils::rsp_create_info();
+        let mut responder_context = responder::ResponderContext::new(
+            device_io_responder,
+            transport_encap_responder,
+            config_info,
+            provision_info,
+        );
+        // until the requester disconnects
+        while responder_context.process_message().is_ok() {}
+    });
+
+    let device_io_requester = &mut TcpDeviceIo::new(&mut stream, TcpRole::Requester);
+    assert_eq!(
+        device_io_requester.role_inquiry().unwrap(),
+        TcpRole::Responder
+    );
+    let transport_encap_requester = &mut TcpTransportEncap {};
+
+    let (config_info, provision_info) = common::utils::req_create_info();
+    let mut requester_context = requester::RequesterContext::new(
+        device_io_requester,
+        transport_encap_requester,
+        config_info,
+        provision_info,
+    );
+
+    assert!(requester_context.init_connection().is_ok());
+    assert!(requester_context.send_receive_spdm_digest().is_ok());
+    assert!(requester_context.send_receive_spdm_certificate(0).is_ok());
+
+    let session_id = requester_context
+        .start_session(
+            false,
+            0,
+            SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+        )
+        .unwrap();
+    assert!(requester_context
+        .send_receive_spdm_heartbeat(session_id)
+        .is_ok());
+    assert!(requester_context.end_session(session_id).is_ok());
+
+    drop(requester_context);
+    assert!(device_io_requester.disconnect().is_ok());
+    responder.join().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn intergration_client_server_mctp_socket() {
+    spdmlib::crypto::asym_sign::register(common::crypto_callbacks::ASYM_SIGN_IMPL);
+
+    let (mut requester_socket, mut responder_socket) = mctp_socket_pair(1, 8, 9).unwrap();
+
+    let responder = thread::spawn(move || {
+        let device_io_responder =
+            &mut MctpSocketIo::new(&mut responder_socket, 1, MCTP_ADDR_ANY, false);
+        let transport_encap_responder = &mut MctpTransportEncap {};
+
+        let (config_info, provision_info) = common::utils::rsp_create_info();
+        let mut responder_context = responder::ResponderContext::new(
+            device_io_responder,
+            transport_encap_responder,
+            config_info,
+            provision_info,
+        );
+        // until the requester socket is closed
+        while responder_context.process_message().is_ok() {}
+    });
+
+    let device_io_requester = &mut MctpSocketIo::new(&mut requester_socket, 1, 9, true);
+    let transport_encap_requester = &mut MctpTransportEncap {};
+
+    let (config_info, provision_info) = common::utils::req_create_info();
+    let mut requester_context = requester::RequesterContext::new(
+        device_io_requester,
+        transport_encap_requester,
+        config_info,
+        provision_info,
+    );
+
+    assert!(requester_context.init_connection().is_ok());
+    assert!(requester_context.send_receive_spdm_digest().is_ok());
+    assert!(requester_context.send_receive_spdm_certificate(0).is_ok());
+
+    let session_id = requester_context
+        .start_session(
+            false,
+            0,
+            SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+        )
+        .unwrap();
+    assert!(requester_context
+        .send_receive_spdm_heartbeat(session_id)
+        .is_ok());
+    assert!(requester_context.end_session(session_id).is_ok());
+
+    drop(requester_context);
+    drop(requester_socket);
+    responder.join().unwrap();
+}
+
+// echo PLDM, drop anything else
+fn pldm_echo_handler(
+    _session_id: u32,
+    message_type: u8,
+    request: &[u8],
+    response: &mut [u8],
+) -> Option<usize> {
+    if message_type != MctpMessageType::MctpMessageTypePldm.get_u8() {
+        return None;
+    }
+    response[..request.len()].copy_from_slice(request);
+    Some(request.len())
+}
+
+#[test]
+fn intergration_client_server_secured_app_message() {
+    spdmlib::crypto::asym_sign::register(common::crypto_callbacks::ASYM_SIGN_IMPL);
+
+    let shared_buffer = SharedBuffer::new();
+    let device_io_responder = &mut FakeSpdmDeviceIoReceve::new(&shared_buffer);
+    let transport_encap_responder = &mut MctpTransportEncap {};
+
+    let (config_info, provision_info) = common::utils::rsp_create_info();
+    let mut responder_context = responder::ResponderContext::new(
+        device_io_responder,
+        transport_encap_responder,
+        config_info,
+        provision_info,
+    );
+    responder_context.register_app_message_handler(pldm_echo_handler);
+
+    let device_io_requester = &mut FakeSpdmDeviceIo::new(&shared_buffer, &mut responder_context);
+    let transport_encap_requester = &mut MctpTransportEncap {};
+
+    let (config_info, provision_info) = common::utils::req_create_info();
+    let mut requester_context = requester::RequesterContext::new(
+        device_io_requester,
+        transport_encap_requester,
+        config_info,
+        provision_info,
+    );
+
+    assert!(requester_context.init_connection().is_ok());
+    assert!(requester_context.send_receive_spdm_digest().is_ok());
+    assert!(requester_context.send_receive_spdm_certificate(0).is_ok());
+
+    let pldm = MctpMessageType::MctpMessageTypePldm.get_u8();
+    let request = [0x80, 0x00, 0x02];
+    let mut response = [0u8; 64];
+
+    // no session yet
+    assert!(requester_context
+        .send_secured_app_message(0xFFFE_FFFE, pldm, &request)
+        .is_err());
+
+    let session_id = requester_context
+        .start_session(
+            false,
+            0,
+            SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+        )
+        .unwrap();
+
+    let used = requester_context
+        .send_receive_secured_app_message(session_id, pldm, &request, &mut response)
+        .unwrap();
+    assert_eq!(&response[..used], &request[..]);
+
+    // SPDM still works in the same session
+    assert!(requester_context
+        .send_receive_spdm_heartbeat(session_id)
+        .is_ok());
+
+    let used = requester_context
+        .send_receive_secured_app_message(session_id, pldm, &request, &mut response)
+        .unwrap();
+    assert_eq!(&response[..used], &request[..]);
+
+    assert!(requester_context.end_session(session_id).is_ok());
+    assert!(requester_context
+        .send_secured_app_message(session_id, pldm, &request)
+        .is_err());
+}
+
+#[test]
+fn intergration_client_server_secured_app_message_in_handshake() {
+    spdmlib::crypto::asym_sign::register(common::crypto_callbacks::ASYM_SIGN_IMPL);
+
+    let shared_buffer = SharedBuffer::new();
+    let device_io_responder = &mut FakeSpdmDeviceIoReceve::new(&shared_buffer);
+    let transport_encap_responder = &mut MctpTransportEncap {};
+
+    let (config_info, provision_info) = common::utils::rsp_create_info();
+    let mut responder_context = responder::ResponderContext::new(
+        device_io_responder,
+        transport_encap_responder,
+        config_info,
+        provision_info,
+    );
+    responder_context.register_app_message_handler(pldm_echo_handler);
+
+    let device_io_requester = &mut FakeSpdmDeviceIo::new(&shared_buffer, &mut responder_context);
+    let transport_encap_requester = &mut MctpTransportEncap {};
+
+    let (config_info, provision_info) = common::utils::req_create_info();
+    let mut requester_context = requester::RequesterContext::new(
+        device_io_requester,
+        transport_encap_requester,
+        config_info,
+        provision_info,
+    );
+
+    assert!(requester_context.init_connection().is_ok());
+    assert!(requester_context.send_receive_spdm_digest().is_ok());
+    assert!(requester_context.send_receive_spdm_certificate(0).is_ok());
+    let session_id = requester_context
+        .send_receive_spdm_key_exchange(
+            0,
+            SpdmMeasurementSummaryHashType::SpdmMeasurementSummaryHashTypeNone,
+        )
+        .unwrap();
+
+    let pldm = MctpMessageType::MctpMessageTypePldm.get_u8();
+    let mut response = [0u8; 64];
+    assert!(requester_context
+        .send_receive_secured_app_message(session_id, pldm, &[0x80, 0x00, 0x02], &mut response)
+        .is_err());
+
+    // Send it anyway, with the handshake keys on a copy of the session. The
+    // responder drops it without using up a sequence number, so FINISH
+    // still decrypts.
+    let mut app_buffer = [0u8; 64];
+    let used = requester_context
+        .common
+        .transport_encap
+        .encap_app_message(pldm, &[0x80, 0x00, 0x02], &mut app_buffer)
+        .unwrap();
+    let mut session = *requester_context
+        .common
+        .get_session_via_id(session_id)
+        .unwrap();
+    let mut secured_buffer = [0u8; 256];
+    let secured_used = session
+        .encode_spdm_secured_message(&app_buffer[..used], &mut secured_buffer, true)
+        .unwrap();
+    let mut transport_buffer = [0u8; 256];
+    let used = requester_context
+        .common
+        .transport_encap
+        .encap(&secured_buffer[..secured_used], &mut transport_buffer, true)
+        .unwrap();
+    assert!(requester_context
+        .common
+        .device_io_send(&transport_buffer[..used])
+        .is_ok());
+
+    assert!(requester_context
+        .send_receive_spdm_finish(session_id)
+        .is_ok());
+    assert!(requester_context
+        .send_receive_secured_app_message(session_id, pldm, &[0x80, 0x00, 0x02], &mut response)
+        .is_ok());
+    assert!(requester_context.end_session(session_id).is_ok());
+}
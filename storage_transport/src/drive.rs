@@ -0,0 +1,458 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::header::*;
+use codec::{Codec, Reader, Writer};
+use spdmlib::common::SpdmDeviceIo;
+use spdmlib::config;
+use spdmlib::error::SpdmResult;
+use spdmlib::{spdm_err, spdm_result_err};
+use spin::Mutex;
+
+pub const STORAGE_DRIVE_QUEUE_DEPTH: usize = 4;
+
+const STORAGE_MESSAGE_HEADER_SIZE: usize = 8;
+
+#[derive(Copy, Clone)]
+struct StorageQueueEntry {
+    operation: StorageSpdmOperation,
+    connection_id: u8,
+    size: usize,
+    data: [u8; config::MAX_SPDM_TRANSPORT_SIZE],
+}
+
+impl Default for StorageQueueEntry {
+    fn default() -> Self {
+        StorageQueueEntry {
+            operation: StorageSpdmOperation::default(),
+            connection_id: 0,
+            size: 0,
+            data: [0u8; config::MAX_SPDM_TRANSPORT_SIZE],
+        }
+    }
+}
+
+struct StorageQueue {
+    entries: [StorageQueueEntry; STORAGE_DRIVE_QUEUE_DEPTH],
+    count: usize,
+}
+
+impl StorageQueue {
+    fn new() -> Self {
+        StorageQueue {
+            entries: [StorageQueueEntry::default(); STORAGE_DRIVE_QUEUE_DEPTH],
+            count: 0,
+        }
+    }
+
+    fn push(
+        &mut self,
+        operation: StorageSpdmOperation,
+        connection_id: u8,
+        data: &[u8],
+    ) -> SpdmResult {
+        if self.count == STORAGE_DRIVE_QUEUE_DEPTH {
+            return spdm_result_err!(EBUSY);
+        }
+        if data.len() > config::MAX_SPDM_TRANSPORT_SIZE {
+            return spdm_result_err!(EINVAL);
+        }
+        let entry = &mut self.entries[self.count];
+        entry.operation = operation;
+        entry.connection_id = connection_id;
+        entry.size = data.len();
+        entry.data[..data.len()].copy_from_slice(data);
+        self.count += 1;
+        Ok(())
+    }
+
+    fn find(&self, connection_id: u8) -> Option<usize> {
+        self.entries[..self.count]
+            .iter()
+            .position(|entry| entry.connection_id == connection_id)
+    }
+
+    fn remove(&mut self, index: usize) -> StorageQueueEntry {
+        let entry = self.entries[index];
+        for i in index..(self.count - 1) {
+            self.entries[i] = self.entries[i + 1];
+        }
+        self.count -= 1;
+        entry
+    }
+}
+
+/// A simulated drive answering DMTF security protocol commands.
+///
+/// Security Send queues a request for the device side, which posts the
+/// response to be read by Security Receive. Up to STORAGE_DRIVE_QUEUE_DEPTH
+/// requests and responses can be outstanding, a full queue fails the
+/// command with EBUSY.
+pub struct StorageDriveModel {
+    pub max_connection_id: u8,
+    requests: StorageQueue,
+    responses: StorageQueue,
+}
+
+impl Default for StorageDriveModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StorageDriveModel {
+    pub fn new() -> Self {
+        StorageDriveModel {
+            max_connection_id: STORAGE_MAX_CONNECTION_ID,
+            requests: StorageQueue::new(),
+            responses: StorageQueue::new(),
+        }
+    }
+
+    fn check_command(
+        &self,
+        security_protocol: u8,
+        security_protocol_specific: u16,
+    ) -> SpdmResult<(StorageSpdmOperation, u8)> {
+        let spsp = security_protocol_specific.to_le_bytes();
+        let mut reader = Reader::init(&spsp[..1]);
+        let operation = StorageSpdmOperation::read(&mut reader).ok_or(spdm_err!(EINVAL))?;
+        let connection_id = (security_protocol_specific >> 8) as u8;
+        if security_protocol != STORAGE_SECURITY_PROTOCOL_DMTF
+            || connection_id > self.max_connection_id
+        {
+            return spdm_result_err!(EINVAL);
+        }
+        Ok((operation, connection_id))
+    }
+
+    // host side
+
+    pub fn security_send(
+        &mut self,
+        security_protocol: u8,
+        security_protocol_specific: u16,
+        data: &[u8],
+    ) -> SpdmResult {
+        let (operation, connection_id) =
+            self.check_command(security_protocol, security_protocol_specific)?;
+        match operation {
+            StorageSpdmOperation::StorageSpdmOperationMessage
+            | StorageSpdmOperation::StorageSpdmOperationSecuredMessage => {
+                self.requests.push(operation, connection_id, data)
+            }
+            _ => spdm_result_err!(EINVAL),
+        }
+    }
+
+    /// The allocation length is the size of `data`.
+    pub fn security_receive(
+        &mut self,
+        security_protocol: u8,
+        security_protocol_specific: u16,
+        data: &mut [u8],
+    ) -> SpdmResult<usize> {
+        let (operation, connection_id) =
+            self.check_command(security_protocol, security_protocol_specific)?;
+        let allocation_length = data.len();
+        let mut writer = Writer::init(&mut *data);
+        match operation {
+            StorageSpdmOperation::StorageSpdmOperationDiscovery => {
+                if allocation_length < STORAGE_DISCOVERY_RESPONSE_SIZE as usize {
+                    return spdm_result_err!(EINVAL);
+                }
+                let mut supported_operations = [0u8; STORAGE_MAX_SUPPORTED_OPERATION_COUNT];
+                supported_operations[..4].copy_from_slice(&[
+                    StorageSpdmOperation::StorageSpdmOperationDiscovery.get_u8(),
+                    StorageSpdmOperation::StorageSpdmOperationPendingInfo.get_u8(),
+                    StorageSpdmOperation::StorageSpdmOperationMessage.get_u8(),
+                    StorageSpdmOperation::StorageSpdmOperationSecuredMessage.get_u8(),
+                ]);
+                StorageDiscoveryResponse {
+                    binding_version: STORAGE_BINDING_VERSION,
+                    max_connection_id: self.max_connection_id,
+                    supported_operations,
+                }
+                .encode(&mut writer);
+                Ok(writer.used())
+            }
+            StorageSpdmOperation::StorageSpdmOperationPendingInfo => {
+                if allocation_length < STORAGE_PENDING_INFO_RESPONSE_SIZE as usize {
+                    return spdm_result_err!(EINVAL);
+                }
+                let response_length = match self.responses.find(connection_id) {
+                    Some(index) => self.responses.entries[index].size as u32,
+                    None => 0,
+                };
+                StoragePendingInfoResponse {
+                    binding_version: STORAGE_BINDING_VERSION,
+                    pending: response_length != 0,
+                    response_length,
+                }
+                .encode(&mut writer);
+                Ok(writer.used())
+            }
+            _ => {
+                let index = match self.responses.find(connection_id) {
+                    Some(index) => index,
+                    // no data is transferred
+                    None => return Ok(0),
+                };
+                let entry = &self.responses.entries[index];
+                if entry.operation != operation || data.len() < entry.size {
+                    return spdm_result_err!(EINVAL);
+                }
+                let entry = self.responses.remove(index);
+                data[..entry.size].copy_from_slice(&entry.data[..entry.size]);
+                Ok(entry.size)
+            }
+        }
+    }
+
+    // device side
+
+    /// Take the oldest request, in the StorageTransportEncap format.
+    pub fn take_request(&mut self, buffer: &mut [u8]) -> Option<usize> {
+        if self.requests.count == 0 {
+            return None;
+        }
+        let size = self.requests.entries[0].size;
+        if buffer.len() < STORAGE_MESSAGE_HEADER_SIZE + size {
+            return None;
+        }
+        let entry = self.requests.remove(0);
+        let mut writer = Writer::init(&mut *buffer);
+        StorageMessageHeader {
+            security_protocol: STORAGE_SECURITY_PROTOCOL_DMTF,
+            operation: entry.operation,
+            connection_id: entry.connection_id,
+            length: size as u32,
+        }
+        .encode(&mut writer);
+        buffer[STORAGE_MESSAGE_HEADER_SIZE..(STORAGE_MESSAGE_HEADER_SIZE + size)]
+            .copy_from_slice(&entry.data[..size]);
+        Some(STORAGE_MESSAGE_HEADER_SIZE + size)
+    }
+
+    /// Queue a response in the StorageTransportEncap format.
+    pub fn post_response(&mut self, buffer: &[u8]) -> SpdmResult {
+        let mut reader = Reader::init(buffer);
+        let header = StorageMessageHeader::read(&mut reader).ok_or(spdm_err!(EINVAL))?;
+        let data = &buffer[reader.used()..];
+        if header.length as usize != data.len() {
+            return spdm_result_err!(EINVAL);
+        }
+        self.responses
+            .push(header.operation, header.connection_id, data)
+    }
+}
+
+/// Host side SpdmDeviceIo, issuing security commands to the drive.
+///
+/// A response is received with the operation of the last request, after
+/// the pending info operation reports its length.
+pub struct StorageHostIo<'a> {
+    pub drive: &'a Mutex<StorageDriveModel>,
+    last_request: StorageMessageHeader,
+}
+
+impl<'a> StorageHostIo<'a> {
+    pub fn new(drive: &'a Mutex<StorageDriveModel>) -> Self {
+        StorageHostIo {
+            drive,
+            last_request: StorageMessageHeader::default(),
+        }
+    }
+
+    pub fn discover(&mut self) -> SpdmResult<StorageDiscoveryResponse> {
+        let mut data = [0u8; STORAGE_DISCOVERY_RESPONSE_SIZE as usize];
+        let used = self.drive.lock().security_receive(
+            STORAGE_SECURITY_PROTOCOL_DMTF,
+            StorageSpdmOperation::StorageSpdmOperationDiscovery.get_u8() as u16,
+            &mut data,
+        )?;
+        let mut reader = Reader::init(&data[..used]);
+        StorageDiscoveryResponse::read(&mut reader).ok_or(spdm_err!(EIO))
+    }
+
+    fn pending_info(&mut self) -> SpdmResult<StoragePendingInfoResponse> {
+        let header = StorageMessageHeader {
+            operation: StorageSpdmOperation::StorageSpdmOperationPendingInfo,
+            ..self.last_request
+        };
+        let mut data = [0u8; STORAGE_PENDING_INFO_RESPONSE_SIZE as usize];
+        let used = self.drive.lock().security_receive(
+            header.security_protocol,
+            header.security_protocol_specific(),
+            &mut data,
+        )?;
+        let mut reader = Reader::init(&data[..used]);
+        StoragePendingInfoResponse::read(&mut reader).ok_or(spdm_err!(EIO))
+    }
+}
+
+impl SpdmDeviceIo for StorageHostIo<'_> {
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        let mut reader = Reader::init(buffer);
+        let header = StorageMessageHeader::read(&mut reader).ok_or(spdm_err!(EINVAL))?;
+        let data = &buffer[reader.used()..];
+        if header.length as usize != data.len() {
+            return spdm_result_err!(EINVAL);
+        }
+        self.drive.lock().security_send(
+            header.security_protocol,
+            header.security_protocol_specific(),
+            data,
+        )?;
+        self.last_request = header;
+        Ok(())
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+        let pending_info = self.pending_info().map_err(|_| 0usize)?;
+        if !pending_info.pending {
+            return Err(0);
+        }
+        let length = pending_info.response_length as usize;
+        if buffer.len() < STORAGE_MESSAGE_HEADER_SIZE + length {
+            return Err(STORAGE_MESSAGE_HEADER_SIZE + length);
+        }
+
+        let header = StorageMessageHeader {
+            length: length as u32,
+            ..self.last_request
+        };
+        let used = self
+            .drive
+            .lock()
+            .security_receive(
+                header.security_protocol,
+                header.security_protocol_specific(),
+                &mut buffer[STORAGE_MESSAGE_HEADER_SIZE..(STORAGE_MESSAGE_HEADER_SIZE + length)],
+            )
+            .map_err(|_| 0usize)?;
+        if used != length {
+            return Err(0);
+        }
+        let mut writer = Writer::init(&mut *buffer);
+        header.encode(&mut writer);
+        Ok(STORAGE_MESSAGE_HEADER_SIZE + length)
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        Ok(())
+    }
+}
+
+/// Device side SpdmDeviceIo, for a responder in the drive.
+pub struct StorageDeviceIo<'a> {
+    pub drive: &'a Mutex<StorageDriveModel>,
+}
+
+impl<'a> StorageDeviceIo<'a> {
+    pub fn new(drive: &'a Mutex<StorageDriveModel>) -> Self {
+        StorageDeviceIo { drive }
+    }
+}
+
+impl SpdmDeviceIo for StorageDeviceIo<'_> {
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        self.drive.lock().post_response(buffer)
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+        self.drive.lock().take_request(buffer).ok_or(0usize)
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spdmlib::common::SpdmTransportEncap;
+
+    #[test]
+    fn test_case0_storage_drive_exchange() {
+        let drive = Mutex::new(StorageDriveModel::new());
+        let mut host = StorageHostIo::new(&drive);
+        let mut device = StorageDeviceIo::new(&drive);
+        let mut transport_encap = StorageTransportEncap { connection_id: 1 };
+
+        let discovery = host.discover().unwrap();
+        assert_eq!(discovery.binding_version, STORAGE_BINDING_VERSION);
+        assert!(discovery.supports(StorageSpdmOperation::StorageSpdmOperationSecuredMessage));
+
+        let mut request = [0u8; 64];
+        let request_size = transport_encap
+            .encap(&[0x11, 0x84, 0x00, 0x00], &mut request, false)
+            .unwrap();
+        host.send(&request[..request_size]).unwrap();
+
+        // no response yet
+        let mut buffer = [0u8; 64];
+        assert_eq!(host.receive(&mut buffer), Err(0));
+
+        assert_eq!(device.receive(&mut buffer), Ok(request_size));
+        assert_eq!(&buffer[..request_size], &request[..request_size]);
+        assert_eq!(device.receive(&mut buffer), Err(0));
+
+        let mut response = [0u8; 64];
+        let response_size = transport_encap
+            .encap(&[0x11, 0x04, 0x00, 0x00, 0x00, 0x00], &mut response, false)
+            .unwrap();
+        device.send(&response[..response_size]).unwrap();
+        // too small a buffer
+        assert_eq!(host.receive(&mut buffer[..10]), Err(response_size));
+        assert_eq!(host.receive(&mut buffer), Ok(response_size));
+        assert_eq!(&buffer[..response_size], &response[..response_size]);
+    }
+    #[test]
+    fn test_case0_storage_drive_commands() {
+        let mut drive = StorageDriveModel::new();
+        let message = StorageSpdmOperation::StorageSpdmOperationMessage.get_u8() as u16;
+        let secured_message =
+            StorageSpdmOperation::StorageSpdmOperationSecuredMessage.get_u8() as u16;
+
+        // wrong protocol, connection or operation
+        assert!(drive.security_send(0xEF, message, &[0x11]).is_err());
+        assert!(drive
+            .security_send(0xE8, message | 0x0400, &[0x11])
+            .is_err());
+        assert!(drive.security_send(0xE8, 0x0001, &[0x11]).is_err());
+        assert!(drive.security_send(0xE8, 0x0003, &[0x11]).is_err());
+
+        for _ in 0..STORAGE_DRIVE_QUEUE_DEPTH {
+            drive.security_send(0xE8, message, &[0x11]).unwrap();
+        }
+        assert!(drive.security_send(0xE8, message, &[0x11]).is_err());
+
+        // responses are returned per connection
+        let mut buffer = [0u8; 64];
+        let mut transport_encap = StorageTransportEncap { connection_id: 1 };
+        let used = transport_encap
+            .encap(&[0x22, 0x22], &mut buffer, true)
+            .unwrap();
+        drive.post_response(&buffer[..used]).unwrap();
+        assert_eq!(
+            drive.security_receive(0xE8, message, &mut buffer).unwrap(),
+            0
+        );
+        assert!(drive
+            .security_receive(0xE8, message | 0x0100, &mut buffer)
+            .is_err());
+        assert!(drive
+            .security_receive(0xE8, secured_message | 0x0100, &mut buffer[..1])
+            .is_err());
+        assert_eq!(
+            drive
+                .security_receive(0xE8, secured_message | 0x0100, &mut buffer)
+                .unwrap(),
+            2
+        );
+        assert_eq!(&buffer[..2], &[0x22, 0x22]);
+    }
+}
@@ -0,0 +1,370 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use codec::enum_builder;
+use codec::{Codec, Reader, Writer};
+use spdmlib::common::SpdmTransportEncap;
+use spdmlib::error::SpdmResult;
+use spdmlib::{spdm_err, spdm_result_err};
+
+/// DSP0286 SPDM over storage.
+///
+/// SPDM messages travel in the data of Security Send (IF_SEND) and
+/// Security Receive (IF_RECV) commands, with the DMTF security protocol.
+/// The security protocol specific field carries the operation in SPSP0
+/// and the connection ID in SPSP1.
+pub const STORAGE_SECURITY_PROTOCOL_DMTF: u8 = 0xE8;
+pub const STORAGE_BINDING_VERSION: u16 = 0x1000;
+pub const STORAGE_MAX_CONNECTION_ID: u8 = 0x3;
+
+enum_builder! {
+    @U8
+    EnumName: StorageSpdmOperation;
+    EnumVal{
+        StorageSpdmOperationDiscovery => 0x01,
+        StorageSpdmOperationPendingInfo => 0x02,
+        StorageSpdmOperationMessage => 0x05,
+        StorageSpdmOperationSecuredMessage => 0x06
+    }
+}
+
+/// The command parameters of a storage transport message, followed by
+/// the data of the command.
+///
+/// It is not sent to the drive. The device IO builds the Security Send
+/// or Security Receive command from it.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct StorageMessageHeader {
+    pub security_protocol: u8,
+    pub operation: StorageSpdmOperation,
+    pub connection_id: u8,
+    pub length: u32, // transfer length in bytes
+}
+
+impl StorageMessageHeader {
+    pub fn security_protocol_specific(&self) -> u16 {
+        (self.operation.get_u8() as u16) | ((self.connection_id as u16) << 8)
+    }
+}
+
+impl Codec for StorageMessageHeader {
+    fn encode(&self, bytes: &mut Writer) {
+        self.security_protocol.encode(bytes);
+        self.operation.encode(bytes); // SPSP0
+        self.connection_id.encode(bytes); // SPSP1
+        0u8.encode(bytes);
+        self.length.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<StorageMessageHeader> {
+        let security_protocol = u8::read(r)?;
+        let operation = StorageSpdmOperation::read(r)?;
+        let connection_id = u8::read(r)?;
+        u8::read(r)?;
+        let length = u32::read(r)?;
+        if connection_id > STORAGE_MAX_CONNECTION_ID {
+            return None;
+        }
+        Some(StorageMessageHeader {
+            security_protocol,
+            operation,
+            connection_id,
+            length,
+        })
+    }
+}
+
+pub const STORAGE_DISCOVERY_RESPONSE_SIZE: u16 = 32;
+pub const STORAGE_MAX_SUPPORTED_OPERATION_COUNT: usize = 8;
+
+/// IF_RECV data of the discovery operation.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct StorageDiscoveryResponse {
+    pub binding_version: u16,
+    pub max_connection_id: u8,
+    pub supported_operations: [u8; STORAGE_MAX_SUPPORTED_OPERATION_COUNT],
+}
+
+impl StorageDiscoveryResponse {
+    pub fn supports(&self, operation: StorageSpdmOperation) -> bool {
+        self.supported_operations.contains(&operation.get_u8())
+    }
+}
+
+impl Codec for StorageDiscoveryResponse {
+    fn encode(&self, bytes: &mut Writer) {
+        STORAGE_DISCOVERY_RESPONSE_SIZE.encode(bytes);
+        self.binding_version.encode(bytes);
+        self.max_connection_id.encode(bytes);
+        for _ in 0..3 {
+            0u8.encode(bytes);
+        }
+        for operation in self.supported_operations.iter() {
+            operation.encode(bytes);
+        }
+        for _ in 0..16 {
+            0u8.encode(bytes);
+        }
+    }
+
+    fn read(r: &mut Reader) -> Option<StorageDiscoveryResponse> {
+        let data_length = u16::read(r)?;
+        if data_length != STORAGE_DISCOVERY_RESPONSE_SIZE {
+            return None;
+        }
+        let binding_version = u16::read(r)?;
+        let max_connection_id = u8::read(r)?;
+        for _ in 0..3 {
+            u8::read(r)?;
+        }
+        let mut supported_operations = [0u8; STORAGE_MAX_SUPPORTED_OPERATION_COUNT];
+        for operation in supported_operations.iter_mut() {
+            *operation = u8::read(r)?;
+        }
+        for _ in 0..16 {
+            u8::read(r)?;
+        }
+        Some(StorageDiscoveryResponse {
+            binding_version,
+            max_connection_id,
+            supported_operations,
+        })
+    }
+}
+
+pub const STORAGE_PENDING_INFO_RESPONSE_SIZE: u16 = 12;
+
+/// IF_RECV data of the pending info operation.
+/// response_length is the size of the SPDM response waiting to be received.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct StoragePendingInfoResponse {
+    pub binding_version: u16,
+    pub pending: bool,
+    pub response_length: u32,
+}
+
+impl Codec for StoragePendingInfoResponse {
+    fn encode(&self, bytes: &mut Writer) {
+        STORAGE_PENDING_INFO_RESPONSE_SIZE.encode(bytes);
+        self.binding_version.encode(bytes);
+        (self.pending as u8).encode(bytes);
+        for _ in 0..3 {
+            0u8.encode(bytes);
+        }
+        self.response_length.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<StoragePendingInfoResponse> {
+        let data_length = u16::read(r)?;
+        if data_length != STORAGE_PENDING_INFO_RESPONSE_SIZE {
+            return None;
+        }
+        let binding_version = u16::read(r)?;
+        let pending = u8::read(r)? & 1 != 0;
+        for _ in 0..3 {
+            u8::read(r)?;
+        }
+        let response_length = u32::read(r)?;
+        Some(StoragePendingInfoResponse {
+            binding_version,
+            pending,
+            response_length,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct StorageTransportEncap {
+    pub connection_id: u8,
+}
+
+impl SpdmTransportEncap for StorageTransportEncap {
+    fn encap(
+        &mut self,
+        spdm_buffer: &[u8],
+        transport_buffer: &mut [u8],
+        secured_message: bool,
+    ) -> SpdmResult<usize> {
+        if self.connection_id > STORAGE_MAX_CONNECTION_ID {
+            return spdm_result_err!(EINVAL);
+        }
+        let payload_len = spdm_buffer.len();
+        let mut writer = Writer::init(&mut *transport_buffer);
+        let storage_header = StorageMessageHeader {
+            security_protocol: STORAGE_SECURITY_PROTOCOL_DMTF,
+            operation: if secured_message {
+                StorageSpdmOperation::StorageSpdmOperationSecuredMessage
+            } else {
+                StorageSpdmOperation::StorageSpdmOperationMessage
+            },
+            connection_id: self.connection_id,
+            length: payload_len as u32,
+        };
+        storage_header.encode(&mut writer);
+        let header_size = writer.used();
+        if transport_buffer.len() < header_size + payload_len {
+            return spdm_result_err!(EINVAL);
+        }
+        transport_buffer[header_size..(header_size + payload_len)].copy_from_slice(spdm_buffer);
+        Ok(header_size + payload_len)
+    }
+
+    fn decap(
+        &mut self,
+        transport_buffer: &[u8],
+        spdm_buffer: &mut [u8],
+    ) -> SpdmResult<(usize, bool)> {
+        let mut reader = Reader::init(transport_buffer);
+        let storage_header = StorageMessageHeader::read(&mut reader).ok_or(spdm_err!(EIO))?;
+        if storage_header.security_protocol != STORAGE_SECURITY_PROTOCOL_DMTF
+            || storage_header.connection_id != self.connection_id
+        {
+            return spdm_result_err!(EINVAL);
+        }
+        let secured_message = match storage_header.operation {
+            StorageSpdmOperation::StorageSpdmOperationMessage => false,
+            StorageSpdmOperation::StorageSpdmOperationSecuredMessage => true,
+            _ => return spdm_result_err!(EINVAL),
+        };
+        let header_size = reader.used();
+        let payload_size = transport_buffer.len() - header_size;
+        if payload_size != storage_header.length as usize {
+            return spdm_result_err!(EINVAL);
+        }
+        if spdm_buffer.len() < payload_size {
+            return spdm_result_err!(EINVAL);
+        }
+        let payload = &transport_buffer[header_size..];
+        spdm_buffer[..payload_size].copy_from_slice(payload);
+        Ok((payload_size, secured_message))
+    }
+
+    fn encap_app(&mut self, spdm_buffer: &[u8], app_buffer: &mut [u8]) -> SpdmResult<usize> {
+        if app_buffer.len() < spdm_buffer.len() {
+            return spdm_result_err!(EINVAL);
+        }
+        app_buffer[0..spdm_buffer.len()].copy_from_slice(spdm_buffer);
+        Ok(spdm_buffer.len())
+    }
+
+    fn decap_app(&mut self, app_buffer: &[u8], spdm_buffer: &mut [u8]) -> SpdmResult<usize> {
+        if spdm_buffer.len() < app_buffer.len() {
+            return spdm_result_err!(EINVAL);
+        }
+        spdm_buffer[0..app_buffer.len()].copy_from_slice(app_buffer);
+        Ok(app_buffer.len())
+    }
+
+    fn get_sequence_number_count(&mut self) -> u8 {
+        0
+    }
+    fn get_max_random_count(&mut self) -> u16 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spdmlib::config;
+
+    #[test]
+    fn test_case0_storage_message_header() {
+        let u8_slice = &mut [0u8; 8];
+        let mut writer = Writer::init(u8_slice);
+        let value = StorageMessageHeader {
+            security_protocol: STORAGE_SECURITY_PROTOCOL_DMTF,
+            operation: StorageSpdmOperation::StorageSpdmOperationSecuredMessage,
+            connection_id: 2,
+            length: 0x100,
+        };
+        value.encode(&mut writer);
+        assert_eq!(value.security_protocol_specific(), 0x0206);
+        assert_eq!(u8_slice, &[0xE8, 0x06, 0x02, 0x00, 0x00, 0x01, 0x00, 0x00]);
+
+        let mut reader = Reader::init(u8_slice);
+        let header = StorageMessageHeader::read(&mut reader).unwrap();
+        assert_eq!(0, reader.left());
+        assert_eq!(
+            header.operation,
+            StorageSpdmOperation::StorageSpdmOperationSecuredMessage
+        );
+        assert_eq!(header.connection_id, 2);
+        assert_eq!(header.length, 0x100);
+
+        u8_slice[2] = 4;
+        let mut reader = Reader::init(u8_slice);
+        assert!(StorageMessageHeader::read(&mut reader).is_none());
+    }
+    #[test]
+    fn test_case0_storage_discovery_pending_info() {
+        let u8_slice = &mut [0u8; 32];
+        let mut writer = Writer::init(u8_slice);
+        let mut supported_operations = [0u8; STORAGE_MAX_SUPPORTED_OPERATION_COUNT];
+        supported_operations[0] = StorageSpdmOperation::StorageSpdmOperationMessage.get_u8();
+        StorageDiscoveryResponse {
+            binding_version: STORAGE_BINDING_VERSION,
+            max_connection_id: 1,
+            supported_operations,
+        }
+        .encode(&mut writer);
+        assert_eq!(0, writer.left());
+        let mut reader = Reader::init(u8_slice);
+        let discovery = StorageDiscoveryResponse::read(&mut reader).unwrap();
+        assert_eq!(discovery.binding_version, STORAGE_BINDING_VERSION);
+        assert_eq!(discovery.max_connection_id, 1);
+        assert!(discovery.supports(StorageSpdmOperation::StorageSpdmOperationMessage));
+        assert!(!discovery.supports(StorageSpdmOperation::StorageSpdmOperationSecuredMessage));
+
+        let u8_slice = &mut [0u8; 12];
+        let mut writer = Writer::init(u8_slice);
+        StoragePendingInfoResponse {
+            binding_version: STORAGE_BINDING_VERSION,
+            pending: true,
+            response_length: 0x30,
+        }
+        .encode(&mut writer);
+        let mut reader = Reader::init(u8_slice);
+        let pending_info = StoragePendingInfoResponse::read(&mut reader).unwrap();
+        assert!(pending_info.pending);
+        assert_eq!(pending_info.response_length, 0x30);
+        u8_slice[0] = 0;
+        let mut reader = Reader::init(u8_slice);
+        assert!(StoragePendingInfoResponse::read(&mut reader).is_none());
+    }
+    #[test]
+    fn test_case0_encap_decap() {
+        let mut storage_transport_encap = StorageTransportEncap { connection_id: 1 };
+        let mut transport_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+        let mut spdm_buffer = [0u8; config::MAX_SPDM_TRANSPORT_SIZE];
+
+        let used = storage_transport_encap
+            .encap(&[0x11, 0x84, 0x00, 0x00], &mut transport_buffer, true)
+            .unwrap();
+        assert_eq!(used, 12);
+        let (size, secured_message) = storage_transport_encap
+            .decap(&transport_buffer[..used], &mut spdm_buffer)
+            .unwrap();
+        assert!(secured_message);
+        assert_eq!(&spdm_buffer[..size], &[0x11, 0x84, 0x00, 0x00]);
+
+        // the length does not match the data
+        assert!(storage_transport_encap
+            .decap(&transport_buffer[..(used - 1)], &mut spdm_buffer)
+            .is_err());
+        // another connection
+        let mut other_encap = StorageTransportEncap { connection_id: 0 };
+        assert!(other_encap
+            .decap(&transport_buffer[..used], &mut spdm_buffer)
+            .is_err());
+
+        assert!(storage_transport_encap
+            .encap(&spdm_buffer, &mut transport_buffer, false)
+            .is_err());
+        let mut bad_encap = StorageTransportEncap { connection_id: 4 };
+        assert!(bad_encap
+            .encap(&[0x11, 0x84], &mut transport_buffer, false)
+            .is_err());
+    }
+}
@@ -0,0 +1,12 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+#![forbid(unsafe_code)]
+#![no_std]
+
+mod header;
+pub use header::*;
+
+mod drive;
+pub use drive::*;
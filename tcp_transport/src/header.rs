@@ -0,0 +1,257 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use codec::enum_builder;
+use codec::{Codec, Reader, Writer};
+use spdmlib::common::SpdmTransportEncap;
+use spdmlib::error::SpdmResult;
+use spdmlib::{spdm_err, spdm_result_err};
+
+/// DSP0287 SPDM over TCP.
+///
+/// Each message on the stream starts with the binding header. The payload
+/// length counts the binding version and message type bytes and the
+/// message after them.
+pub const TCP_BINDING_VERSION: u8 = 0x01;
+pub const TCP_BINDING_HEADER_SIZE: usize = 4;
+pub const TCP_MAX_MESSAGE_SIZE: usize = 0xFFFF - 2;
+
+enum_builder! {
+    @U8
+    EnumName: TcpMessageType;
+    EnumVal{
+        TcpMessageTypeOutOfSession => 0x05,
+        TcpMessageTypeInSession => 0x06,
+        TcpMessageTypeDisconnect => 0xBE,
+        TcpMessageTypeRoleInquiry => 0xBF,
+        TcpMessageTypeErrorTooLarge => 0xC0,
+        TcpMessageTypeErrorNotSupported => 0xC1,
+        TcpMessageTypeErrorCannotOperateAsRequester => 0xC2,
+        TcpMessageTypeErrorCannotOperateAsResponder => 0xC3
+    }
+}
+
+impl TcpMessageType {
+    /// Binding messages carry no SPDM message and are handled by the
+    /// device IO.
+    pub fn is_control(&self) -> bool {
+        !matches!(
+            self,
+            TcpMessageType::TcpMessageTypeOutOfSession | TcpMessageType::TcpMessageTypeInSession
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TcpMessageHeader {
+    pub payload_length: u16,
+    pub binding_version: u8,
+    pub message_type: TcpMessageType,
+}
+
+impl TcpMessageHeader {
+    pub fn new(message_type: TcpMessageType, message_size: usize) -> Self {
+        TcpMessageHeader {
+            payload_length: (message_size + 2) as u16,
+            binding_version: TCP_BINDING_VERSION,
+            message_type,
+        }
+    }
+
+    /// The size of the message following the header.
+    pub fn message_size(&self) -> usize {
+        self.payload_length as usize - 2
+    }
+}
+
+impl Codec for TcpMessageHeader {
+    fn encode(&self, bytes: &mut Writer) {
+        self.payload_length.encode(bytes);
+        self.binding_version.encode(bytes);
+        self.message_type.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<TcpMessageHeader> {
+        let payload_length = u16::read(r)?;
+        let binding_version = u8::read(r)?;
+        let message_type = TcpMessageType::read(r)?;
+        if payload_length < 2 || binding_version != TCP_BINDING_VERSION {
+            return None;
+        }
+        Some(TcpMessageHeader {
+            payload_length,
+            binding_version,
+            message_type,
+        })
+    }
+}
+
+/// Encode a binding message, which has no SPDM message.
+pub fn tcp_encode_control_message(
+    message_type: TcpMessageType,
+    transport_buffer: &mut [u8],
+) -> SpdmResult<usize> {
+    if !message_type.is_control() || transport_buffer.len() < TCP_BINDING_HEADER_SIZE {
+        return spdm_result_err!(EINVAL);
+    }
+    let mut writer = Writer::init(transport_buffer);
+    TcpMessageHeader::new(message_type, 0).encode(&mut writer);
+    Ok(writer.used())
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TcpTransportEncap {}
+
+impl SpdmTransportEncap for TcpTransportEncap {
+    fn encap(
+        &mut self,
+        spdm_buffer: &[u8],
+        transport_buffer: &mut [u8],
+        secured_message: bool,
+    ) -> SpdmResult<usize> {
+        let payload_len = spdm_buffer.len();
+        if payload_len > TCP_MAX_MESSAGE_SIZE
+            || transport_buffer.len() < TCP_BINDING_HEADER_SIZE + payload_len
+        {
+            return spdm_result_err!(EINVAL);
+        }
+        let mut writer = Writer::init(&mut *transport_buffer);
+        TcpMessageHeader::new(
+            if secured_message {
+                TcpMessageType::TcpMessageTypeInSession
+            } else {
+                TcpMessageType::TcpMessageTypeOutOfSession
+            },
+            payload_len,
+        )
+        .encode(&mut writer);
+        let header_size = writer.used();
+        transport_buffer[header_size..(header_size + payload_len)].copy_from_slice(spdm_buffer);
+        Ok(header_size + payload_len)
+    }
+
+    fn decap(
+        &mut self,
+        transport_buffer: &[u8],
+        spdm_buffer: &mut [u8],
+    ) -> SpdmResult<(usize, bool)> {
+        let mut reader = Reader::init(transport_buffer);
+        let tcp_header = TcpMessageHeader::read(&mut reader).ok_or(spdm_err!(EIO))?;
+        let secured_message = match tcp_header.message_type {
+            TcpMessageType::TcpMessageTypeOutOfSession => false,
+            TcpMessageType::TcpMessageTypeInSession => true,
+            _ => return spdm_result_err!(EINVAL),
+        };
+        let header_size = reader.used();
+        let payload_size = transport_buffer.len() - header_size;
+        if payload_size != tcp_header.message_size() {
+            return spdm_result_err!(EINVAL);
+        }
+        if spdm_buffer.len() < payload_size {
+            return spdm_result_err!(EINVAL);
+        }
+        let payload = &transport_buffer[header_size..];
+        spdm_buffer[..payload_size].copy_from_slice(payload);
+        Ok((payload_size, secured_message))
+    }
+
+    fn encap_app(&mut self, spdm_buffer: &[u8], app_buffer: &mut [u8]) -> SpdmResult<usize> {
+        if app_buffer.len() < spdm_buffer.len() {
+            return spdm_result_err!(EINVAL);
+        }
+        app_buffer[0..spdm_buffer.len()].copy_from_slice(spdm_buffer);
+        Ok(spdm_buffer.len())
+    }
+
+    fn decap_app(&mut self, app_buffer: &[u8], spdm_buffer: &mut [u8]) -> SpdmResult<usize> {
+        if spdm_buffer.len() < app_buffer.len() {
+            return spdm_result_err!(EINVAL);
+        }
+        spdm_buffer[0..app_buffer.len()].copy_from_slice(app_buffer);
+        Ok(app_buffer.len())
+    }
+
+    fn get_sequence_number_count(&mut self) -> u8 {
+        0
+    }
+    fn get_max_random_count(&mut self) -> u16 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case0_tcp_message_header() {
+        let u8_slice = &mut [0u8; 4];
+        let mut writer = Writer::init(u8_slice);
+        TcpMessageHeader::new(TcpMessageType::TcpMessageTypeInSession, 0x100).encode(&mut writer);
+        assert_eq!(u8_slice, &[0x02, 0x01, 0x01, 0x06]);
+
+        let mut reader = Reader::init(u8_slice);
+        let header = TcpMessageHeader::read(&mut reader).unwrap();
+        assert_eq!(header.message_size(), 0x100);
+        assert_eq!(header.message_type, TcpMessageType::TcpMessageTypeInSession);
+
+        // wrong binding version
+        u8_slice[2] = 0x02;
+        let mut reader = Reader::init(u8_slice);
+        assert!(TcpMessageHeader::read(&mut reader).is_none());
+        // too short a payload length
+        u8_slice[..3].copy_from_slice(&[0x01, 0x00, 0x01]);
+        let mut reader = Reader::init(u8_slice);
+        assert!(TcpMessageHeader::read(&mut reader).is_none());
+    }
+    #[test]
+    fn test_case0_tcp_encap_decap() {
+        let mut transport_encap = TcpTransportEncap {};
+        let mut transport_buffer = [0u8; 16];
+        let mut spdm_buffer = [0u8; 16];
+
+        let used = transport_encap
+            .encap(&[0x11, 0x84, 0x00, 0x00], &mut transport_buffer, false)
+            .unwrap();
+        assert_eq!(used, 8);
+        assert_eq!(&transport_buffer[..4], &[0x06, 0x00, 0x01, 0x05]);
+        let (size, secured_message) = transport_encap
+            .decap(&transport_buffer[..used], &mut spdm_buffer)
+            .unwrap();
+        assert_eq!(size, 4);
+        assert!(!secured_message);
+        // truncated
+        assert!(transport_encap
+            .decap(&transport_buffer[..used - 1], &mut spdm_buffer)
+            .is_err());
+
+        let used = transport_encap
+            .encap(&[0x11, 0x84], &mut transport_buffer, true)
+            .unwrap();
+        let (_, secured_message) = transport_encap
+            .decap(&transport_buffer[..used], &mut spdm_buffer)
+            .unwrap();
+        assert!(secured_message);
+
+        assert!(transport_encap
+            .encap(&[0u8; 16], &mut transport_buffer, false)
+            .is_err());
+
+        // binding messages are not SPDM
+        let used = tcp_encode_control_message(
+            TcpMessageType::TcpMessageTypeRoleInquiry,
+            &mut transport_buffer,
+        )
+        .unwrap();
+        assert_eq!(&transport_buffer[..used], &[0x02, 0x00, 0x01, 0xBF]);
+        assert!(transport_encap
+            .decap(&transport_buffer[..used], &mut spdm_buffer)
+            .is_err());
+        assert!(tcp_encode_control_message(
+            TcpMessageType::TcpMessageTypeOutOfSession,
+            &mut transport_buffer
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::header::*;
+use codec::{Codec, Reader};
+use spdmlib::common::SpdmDeviceIo;
+use spdmlib::error::SpdmResult;
+use spdmlib::{spdm_err, spdm_result_err};
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TcpRole {
+    Requester,
+    Responder,
+}
+
+/// SpdmDeviceIo over a TCP connection, for use with TcpTransportEncap.
+///
+/// Binding messages are handled while receiving: a role inquiry is
+/// answered from `role`, a disconnect ends the connection and a message
+/// that does not fit the receive buffer is dropped with an error reply.
+pub struct TcpDeviceIo<'a> {
+    pub stream: &'a mut TcpStream,
+    pub role: TcpRole,
+    disconnected: bool,
+}
+
+impl<'a> TcpDeviceIo<'a> {
+    pub fn new(stream: &'a mut TcpStream, role: TcpRole) -> Self {
+        TcpDeviceIo {
+            stream,
+            role,
+            disconnected: false,
+        }
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    /// Ask the peer for its role.
+    ///
+    /// A responder answers with a role inquiry, a requester with the
+    /// cannot operate as responder error.
+    pub fn role_inquiry(&mut self) -> SpdmResult<TcpRole> {
+        self.send_control_message(TcpMessageType::TcpMessageTypeRoleInquiry)?;
+        let mut buffer = [0u8; TCP_BINDING_HEADER_SIZE];
+        let header = self.read_message(&mut buffer)?;
+        match header.message_type {
+            TcpMessageType::TcpMessageTypeRoleInquiry => Ok(TcpRole::Responder),
+            TcpMessageType::TcpMessageTypeErrorCannotOperateAsResponder => Ok(TcpRole::Requester),
+            _ => spdm_result_err!(EIO),
+        }
+    }
+
+    /// Tell the peer no more messages follow, and close our side.
+    pub fn disconnect(&mut self) -> SpdmResult {
+        if self.disconnected {
+            return Ok(());
+        }
+        self.send_control_message(TcpMessageType::TcpMessageTypeDisconnect)?;
+        self.disconnected = true;
+        self.stream
+            .shutdown(Shutdown::Write)
+            .map_err(|_| spdm_err!(EIO))
+    }
+
+    fn send_control_message(&mut self, message_type: TcpMessageType) -> SpdmResult {
+        let mut buffer = [0u8; TCP_BINDING_HEADER_SIZE];
+        let used = tcp_encode_control_message(message_type, &mut buffer)?;
+        self.write_all(&buffer[..used])
+    }
+
+    fn write_all(&mut self, buffer: &[u8]) -> SpdmResult {
+        if self.disconnected {
+            return spdm_result_err!(EIO);
+        }
+        self.stream.write_all(buffer).map_err(|_| spdm_err!(EIO))
+    }
+
+    fn read_exact(&mut self, buffer: &mut [u8]) -> SpdmResult {
+        if self.stream.read_exact(buffer).is_err() {
+            // the peer is gone
+            self.disconnected = true;
+            return spdm_result_err!(EIO);
+        }
+        Ok(())
+    }
+
+    /// Read one whole message, binding header included, into `buffer`.
+    fn read_message(&mut self, buffer: &mut [u8]) -> SpdmResult<TcpMessageHeader> {
+        if self.disconnected {
+            return spdm_result_err!(EIO);
+        }
+        let mut header_buffer = [0u8; TCP_BINDING_HEADER_SIZE];
+        self.read_exact(&mut header_buffer)?;
+        let mut reader = Reader::init(&header_buffer);
+        let header = match TcpMessageHeader::read(&mut reader) {
+            Some(header) => header,
+            None => {
+                // the stream can not be resynchronized
+                let _ = self.disconnect();
+                return spdm_result_err!(EIO);
+            }
+        };
+
+        let message_size = header.message_size();
+        if buffer.len() < TCP_BINDING_HEADER_SIZE + message_size {
+            let mut discard = [0u8; 256];
+            let mut remaining = message_size;
+            while remaining != 0 {
+                let size = remaining.min(discard.len());
+                self.read_exact(&mut discard[..size])?;
+                remaining -= size;
+            }
+            self.send_control_message(TcpMessageType::TcpMessageTypeErrorTooLarge)?;
+            return spdm_result_err!(E2BIG);
+        }
+        buffer[..TCP_BINDING_HEADER_SIZE].copy_from_slice(&header_buffer);
+        self.read_exact(
+            &mut buffer[TCP_BINDING_HEADER_SIZE..(TCP_BINDING_HEADER_SIZE + message_size)],
+        )?;
+        Ok(header)
+    }
+}
+
+impl SpdmDeviceIo for TcpDeviceIo<'_> {
+    fn send(&mut self, buffer: &[u8]) -> SpdmResult {
+        self.write_all(buffer)
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+        loop {
+            let header = self.read_message(buffer).map_err(|_| 0usize)?;
+            match header.message_type {
+                TcpMessageType::TcpMessageTypeOutOfSession
+                | TcpMessageType::TcpMessageTypeInSession => {
+                    return Ok(TCP_BINDING_HEADER_SIZE + header.message_size())
+                }
+                TcpMessageType::TcpMessageTypeRoleInquiry => {
+                    let reply = match self.role {
+                        TcpRole::Responder => TcpMessageType::TcpMessageTypeRoleInquiry,
+                        TcpRole::Requester => {
+                            TcpMessageType::TcpMessageTypeErrorCannotOperateAsResponder
+                        }
+                    };
+                    self.send_control_message(reply).map_err(|_| 0usize)?;
+                }
+                TcpMessageType::TcpMessageTypeDisconnect => {
+                    self.disconnected = true;
+                    return Err(0);
+                }
+                TcpMessageType::TcpMessageTypeErrorTooLarge
+                | TcpMessageType::TcpMessageTypeErrorNotSupported
+                | TcpMessageType::TcpMessageTypeErrorCannotOperateAsRequester
+                | TcpMessageType::TcpMessageTypeErrorCannotOperateAsResponder => {
+                    return Err(0);
+                }
+                _ => {
+                    self.send_control_message(TcpMessageType::TcpMessageTypeErrorNotSupported)
+                        .map_err(|_| 0usize)?;
+                }
+            }
+        }
+    }
+
+    fn flush_all(&mut self) -> SpdmResult {
+        self.stream.flush().map_err(|_| spdm_err!(EIO))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spdmlib::common::SpdmTransportEncap;
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn tcp_loopback() -> (TcpListener, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        (listener, stream)
+    }
+
+    #[test]
+    fn test_case0_tcp_device_io() {
+        let (listener, mut stream) = tcp_loopback();
+        let responder = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut device_io = TcpDeviceIo::new(&mut stream, TcpRole::Responder);
+            let mut buffer = [0u8; 64];
+            // the role inquiry is answered while receiving
+            let used = device_io.receive(&mut buffer).unwrap();
+            device_io.send(&buffer[..used]).unwrap();
+            assert_eq!(device_io.receive(&mut buffer), Err(0));
+            assert!(device_io.is_disconnected());
+        });
+
+        let mut device_io = TcpDeviceIo::new(&mut stream, TcpRole::Requester);
+        assert_eq!(device_io.role_inquiry().unwrap(), TcpRole::Responder);
+
+        let mut transport_encap = TcpTransportEncap {};
+        let mut transport_buffer = [0u8; 64];
+        let used = transport_encap
+            .encap(&[0x11, 0x84, 0x00, 0x00], &mut transport_buffer, false)
+            .unwrap();
+        device_io.send(&transport_buffer[..used]).unwrap();
+        let mut receive_buffer = [0u8; 64];
+        assert_eq!(device_io.receive(&mut receive_buffer), Ok(used));
+        assert_eq!(&receive_buffer[..used], &transport_buffer[..used]);
+
+        device_io.disconnect().unwrap();
+        assert!(device_io.send(&transport_buffer[..used]).is_err());
+        responder.join().unwrap();
+    }
+    #[test]
+    fn test_case1_tcp_device_io() {
+        let (listener, mut stream) = tcp_loopback();
+        let peer = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut device_io = TcpDeviceIo::new(&mut stream, TcpRole::Requester);
+            let mut buffer = [0u8; 16];
+            // too large, then a role inquiry for a requester
+            assert_eq!(device_io.receive(&mut buffer), Err(0));
+            assert_eq!(device_io.receive(&mut buffer), Err(0));
+        });
+
+        let mut device_io = TcpDeviceIo::new(&mut stream, TcpRole::Responder);
+        let mut transport_encap = TcpTransportEncap {};
+        let mut transport_buffer = [0u8; 64];
+        let used = transport_encap
+            .encap(&[0u8; 32], &mut transport_buffer, true)
+            .unwrap();
+        device_io.send(&transport_buffer[..used]).unwrap();
+        let mut receive_buffer = [0u8; 64];
+        assert_eq!(device_io.receive(&mut receive_buffer), Err(0));
+        assert_eq!(
+            &receive_buffer[..TCP_BINDING_HEADER_SIZE],
+            &[0x02, 0x00, 0x01, 0xC0]
+        );
+
+        assert_eq!(device_io.role_inquiry().unwrap(), TcpRole::Requester);
+        stream.shutdown(Shutdown::Both).unwrap();
+        peer.join().unwrap();
+    }
+}
@@ -2,7 +2,9 @@
 //
 // SPDX-License-Identifier: BSD-2-Clause-Patent
 
+use pkcs11_sign::Pkcs11KeyConfig;
 use spdmlib::crypto::SpdmAsymSign;
+use tpm_sign::{TpmKeyConfig, TpmStream, TpmTransport};
 
 use spdmlib::msgs::{
     SpdmBaseAsymAlgo, SpdmBaseHashAlgo, SpdmSignatureStruct, SPDM_MAX_ASYM_KEY_SIZE,
@@ -10,9 +12,76 @@ use spdmlib::msgs::{
 
 pub static ASYM_SIGN_IMPL: SpdmAsymSign = SpdmAsymSign { sign_cb: asym_sign };
 
+// Set `SPDM_PKCS11_MODULE`, `SPDM_PKCS11_SLOT`, `SPDM_PKCS11_KEY_LABEL` and,
+// if the token needs one, `SPDM_PKCS11_PIN` to sign with a key on a PKCS#11
+// token instead of the key file.
+//
+// Or set `SPDM_TPM_DEVICE` (e.g. /dev/tpmrm0) or `SPDM_TPM_SIMULATOR` (the
+// swtpm server address), `SPDM_TPM_KEY_HANDLE` and, if the key has one,
+// `SPDM_TPM_KEY_AUTH` to sign with a key in a TPM.
+//
+// The key can only be set once, so call this once per process.
+pub fn asym_sign_from_env() -> SpdmAsymSign {
+    if let Some(asym_sign) = tpm_sign_from_env() {
+        return asym_sign;
+    }
+    let module = match std::env::var("SPDM_PKCS11_MODULE") {
+        Ok(module) => module,
+        Err(_) => return ASYM_SIGN_IMPL,
+    };
+    let slot = std::env::var("SPDM_PKCS11_SLOT")
+        .ok()
+        .and_then(|slot| slot.parse().ok())
+        .expect("SPDM_PKCS11_SLOT is not a slot number!");
+    let key_label =
+        std::env::var("SPDM_PKCS11_KEY_LABEL").expect("SPDM_PKCS11_KEY_LABEL is not set!");
+    let pin = std::env::var("SPDM_PKCS11_PIN").ok();
+    pkcs11_sign::set_pkcs11_key(&Pkcs11KeyConfig {
+        module: &module,
+        slot,
+        key_label: &key_label,
+        pin: pin.as_deref(),
+    })
+    .expect("unable to open the PKCS#11 key!");
+    pkcs11_sign::PKCS11
+}
+
+fn tpm_sign_from_env() -> Option<SpdmAsymSign> {
+    let transport: Box<dyn TpmTransport + Send> =
+        if let Ok(device) = std::env::var("SPDM_TPM_DEVICE") {
+            let device = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(device)
+                .expect("unable to open the TPM device!");
+            Box::new(TpmStream::new(device))
+        } else if let Ok(simulator) = std::env::var("SPDM_TPM_SIMULATOR") {
+            let stream =
+                std::net::TcpStream::connect(simulator).expect("unable to connect to the TPM!");
+            Box::new(TpmStream::new(stream))
+        } else {
+            return None;
+        };
+    let handle = std::env::var("SPDM_TPM_KEY_HANDLE")
+        .ok()
+        .and_then(|handle| u32::from_str_radix(handle.trim_start_matches("0x"), 16).ok())
+        .expect("SPDM_TPM_KEY_HANDLE is not a handle!");
+    let auth = std::env::var("SPDM_TPM_KEY_AUTH").unwrap_or_default();
+    tpm_sign::set_tpm_key(
+        transport,
+        &TpmKeyConfig {
+            handle,
+            auth: auth.as_bytes(),
+        },
+    )
+    .expect("unable to open the TPM key!");
+    Some(tpm_sign::TPM)
+}
+
 fn asym_sign(
     base_hash_algo: SpdmBaseHashAlgo,
     base_asym_algo: SpdmBaseAsymAlgo,
+    _context: &[u8],
     data: &[u8],
 ) -> Option<SpdmSignatureStruct> {
     match (base_hash_algo, base_asym_algo) {
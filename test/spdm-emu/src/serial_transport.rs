@@ -0,0 +1,43 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+
+use mctp_transport::MctpSerialPort;
+use spdmlib::error::SpdmResult;
+use spdmlib::{spdm_err, spdm_result_err};
+
+pub const SERIAL_REQUESTER_EID: u8 = 8;
+pub const SERIAL_RESPONDER_EID: u8 = 9;
+
+// Set `SPDM_SERIAL` to a serial device (e.g. one end of a pty pair) to run
+// the emulator over the DSP0253 MCTP serial binding instead of the socket.
+pub fn serial_path_from_env() -> Option<String> {
+    std::env::var("SPDM_SERIAL").ok()
+}
+
+pub struct SerialPortTransport {
+    pub file: File,
+}
+
+impl SerialPortTransport {
+    pub fn open(path: &str) -> std::io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(SerialPortTransport { file })
+    }
+}
+
+impl MctpSerialPort for SerialPortTransport {
+    fn write(&mut self, bytes: &[u8]) -> SpdmResult {
+        if self.file.write_all(bytes).is_err() || self.file.flush().is_err() {
+            return spdm_result_err!(EIO);
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, bytes: &mut [u8]) -> SpdmResult<usize> {
+        self.file.read(bytes).map_err(|_| spdm_err!(EIO))
+    }
+}
@@ -15,15 +15,20 @@ use spdmlib::config;
 use spdmlib::msgs::*;
 use spdmlib::requester;
 
-use mctp_transport::MctpTransportEncap;
+use mctp_transport::{MctpPacketIo, MctpSerialLink, MctpTransportEncap};
 use pcidoe_transport::PciDoeTransportEncap;
+use spdm_emu::serial_transport::*;
 use spdm_emu::socket_io_transport::SocketIoTransport;
 use spdm_emu::spdm_emu::*;
 use std::net::TcpStream;
 
+fn sleep_us(us: u64) {
+    std::thread::sleep(std::time::Duration::from_micros(us));
+}
+
 fn send_receive_hello(
     stream: &mut TcpStream,
-    transport_encap: &mut dyn common::SpdmTransportEncap,
+    transport_encap: &mut (dyn common::SpdmTransportEncap + Send),
     transport_type: u32,
 ) {
     println!("send test");
@@ -46,7 +51,7 @@ fn send_receive_hello(
 
 fn send_receive_stop(
     stream: &mut TcpStream,
-    transport_encap: &mut dyn common::SpdmTransportEncap,
+    transport_encap: &mut (dyn common::SpdmTransportEncap + Send),
     transport_type: u32,
 ) {
     println!("send stop");
@@ -67,8 +72,8 @@ fn send_receive_stop(
 }
 
 fn test_spdm(
-    socket_io_transport: &mut SocketIoTransport,
-    transport_encap: &mut dyn SpdmTransportEncap,
+    device_io: &mut (dyn common::SpdmDeviceIo + Send),
+    transport_encap: &mut (dyn SpdmTransportEncap + Send),
 ) {
     let config_info = common::SpdmConfigInfo {
         spdm_version: [SpdmVersion::SpdmVersion10, SpdmVersion::SpdmVersion11],
@@ -148,12 +153,9 @@ fn test_spdm(
         peer_cert_chain_root_hash: None,
     };
 
-    let mut context = requester::RequesterContext::new(
-        socket_io_transport,
-        transport_encap,
-        config_info,
-        provision_info,
-    );
+    let mut context =
+        requester::RequesterContext::new(device_io, transport_encap, config_info, provision_info);
+    context.retry_policy.delay = Some(sleep_us);
 
     if context.init_connection().is_err() {
         return;
@@ -269,13 +271,22 @@ fn main() {
         .expect("Time went backwards");
     println!("current unit time epoch - {:?}", since_the_epoch.as_secs());
 
+    if let Some(path) = serial_path_from_env() {
+        let mut port = SerialPortTransport::open(&path).expect("Couldn't open the serial port...");
+        let mut link = MctpSerialLink::new(&mut port);
+        let mut packet_io =
+            MctpPacketIo::new(&mut link, SERIAL_REQUESTER_EID, SERIAL_RESPONDER_EID, true);
+        test_spdm(&mut packet_io, &mut MctpTransportEncap {});
+        return;
+    }
+
     let mut socket =
         TcpStream::connect("127.0.0.1:2323").expect("Couldn't connect to the server...");
 
     let pcidoe_transport_encap = &mut PciDoeTransportEncap {};
     let mctp_transport_encap = &mut MctpTransportEncap {};
 
-    let transport_encap: &mut dyn SpdmTransportEncap = if USE_PCIDOE {
+    let transport_encap: &mut (dyn SpdmTransportEncap + Send) = if USE_PCIDOE {
         pcidoe_transport_encap
     } else {
         mctp_transport_encap
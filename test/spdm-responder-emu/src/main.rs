@@ -11,17 +11,19 @@ use std::net::{TcpListener, TcpStream};
 
 use codec::{Codec, Reader};
 use common::SpdmTransportEncap;
-use mctp_transport::MctpTransportEncap;
+use mctp_transport::{MctpPacketIo, MctpSerialLink, MctpTransportEncap};
 use pcidoe_transport::PciDoeTransportEncap;
-use spdm_emu::crypto_callback::ASYM_SIGN_IMPL;
+use spdm_emu::crypto_callback::asym_sign_from_env;
+use spdm_emu::serial_transport::*;
 use spdm_emu::socket_io_transport::SocketIoTransport;
 use spdm_emu::spdm_emu::*;
+use spdmlib::crypto::SpdmAsymSign;
 use spdmlib::msgs::*;
 use spdmlib::{common, responder};
 
 fn process_socket_message(
     stream: &mut TcpStream,
-    transport_encap: &mut dyn SpdmTransportEncap,
+    transport_encap: &mut (dyn SpdmTransportEncap + Send),
     buffer: &[u8],
 ) -> bool {
     if buffer.len() < SOCKET_HEADER_LEN {
@@ -73,6 +75,14 @@ fn new_logger_from_env() -> SimpleLogger {
 fn main() {
     new_logger_from_env().init().unwrap();
 
+    // the key is opened once, handle_message runs again after every socket command
+    let asym_sign = asym_sign_from_env();
+
+    if let Some(path) = serial_path_from_env() {
+        serve_serial(&path, asym_sign);
+        return;
+    }
+
     let listener = TcpListener::bind("127.0.0.1:2323").expect("Couldn't bind to the server");
     println!("server start!");
 
@@ -85,12 +95,13 @@ fn main() {
         let mut need_continue;
         loop {
             let res = handle_message(
-                &mut stream,
+                &mut SocketIoTransport::new(&mut stream),
                 if USE_PCIDOE {
                     pcidoe_transport_encap
                 } else {
                     mctp_transport_encap
                 },
+                asym_sign,
             );
             match res {
                 Ok(_spdm_result) => {
@@ -116,12 +127,29 @@ fn main() {
     }
 }
 
+fn serve_serial(path: &str, asym_sign: SpdmAsymSign) {
+    let mut port = SerialPortTransport::open(path).expect("Couldn't open the serial port");
+    println!("server start on {}!", path);
+
+    let mut link = MctpSerialLink::new(&mut port);
+    let mut packet_io =
+        MctpPacketIo::new(&mut link, SERIAL_RESPONDER_EID, SERIAL_REQUESTER_EID, false);
+    let mctp_transport_encap = &mut MctpTransportEncap {};
+    loop {
+        match handle_message(&mut packet_io, mctp_transport_encap, asym_sign) {
+            // no socket commands on a serial link, 0 means nothing could be received
+            Err((0, _)) => return,
+            _ => continue,
+        }
+    }
+}
+
 fn handle_message(
-    stream: &mut TcpStream,
-    transport_encap: &mut dyn SpdmTransportEncap,
+    device_io: &mut (dyn common::SpdmDeviceIo + Send),
+    transport_encap: &mut (dyn SpdmTransportEncap + Send),
+    asym_sign: SpdmAsymSign,
 ) -> Result<bool, (usize, [u8; 1024])> {
     println!("handle_message!");
-    let mut socket_io_transport = SocketIoTransport::new(stream);
 
     let config_info = common::SpdmConfigInfo {
         spdm_version: [SpdmVersion::SpdmVersion10, SpdmVersion::SpdmVersion11],
@@ -204,14 +232,10 @@ fn handle_message(
         peer_cert_chain_root_hash: None,
     };
 
-    spdmlib::crypto::asym_sign::register(ASYM_SIGN_IMPL);
+    spdmlib::crypto::asym_sign::register(asym_sign);
 
-    let mut context = responder::ResponderContext::new(
-        &mut socket_io_transport,
-        transport_encap,
-        config_info,
-        provision_info,
-    );
+    let mut context =
+        responder::ResponderContext::new(device_io, transport_encap, config_info, provision_info);
 
     loop {
         // if failed, receieved message can't be processed. then the message will need caller to deal.
@@ -235,7 +259,7 @@ fn handle_message(
 
 pub fn send_hello(
     stream: &mut TcpStream,
-    transport_encap: &mut dyn SpdmTransportEncap,
+    transport_encap: &mut (dyn SpdmTransportEncap + Send),
     tranport_type: u32,
 ) {
     println!("get hello");
@@ -256,7 +280,7 @@ pub fn send_hello(
 
 pub fn send_unknown(
     stream: &mut TcpStream,
-    transport_encap: &mut dyn SpdmTransportEncap,
+    transport_encap: &mut (dyn SpdmTransportEncap + Send),
     transport_type: u32,
 ) {
     println!("get unknown");
@@ -275,7 +299,7 @@ pub fn send_unknown(
 
 pub fn send_stop(
     stream: &mut TcpStream,
-    transport_encap: &mut dyn SpdmTransportEncap,
+    transport_encap: &mut (dyn SpdmTransportEncap + Send),
     transport_type: u32,
 ) {
     println!("get stop");
@@ -0,0 +1,27 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+//! An `SpdmAsymSign` that signs with a key held by a TPM 2.0, e.g. a
+//! hardware TPM through /dev/tpmrm0 or swtpm.
+//!
+//! The TPM2_ReadPublic, TPM2_StartAuthSession, TPM2_Sign and
+//! TPM2_FlushContext commands are marshalled here rather than sent through
+//! a TSS such as tss-esapi, which binds the C tpm2-tss libraries every build
+//! would then need. TPM2_Sign is authorized in an HMAC session, so the key's
+//! auth value never crosses the interface to the TPM. The session is neither
+//! salted nor bound, and someone who sees the commands go by can still test
+//! guesses of a weak auth value against the HMAC. Salted sessions and
+//! parameter encryption are left to a TSS.
+
+#![forbid(unsafe_code)]
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate spdmlib;
+
+mod sign;
+mod tpm;
+pub use sign::*;
+pub use tpm::{TpmStream, TpmTransport};
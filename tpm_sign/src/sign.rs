@@ -0,0 +1,555 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+use crate::tpm::*;
+use conquer_once::spin::OnceCell;
+use spdmlib::crypto::{self, SpdmAsymSign};
+use spdmlib::error::SpdmResult;
+use spdmlib::msgs::{
+    SpdmBaseAsymAlgo, SpdmBaseHashAlgo, SpdmSignatureStruct, SPDM_MAX_ASYM_KEY_SIZE,
+};
+use std::sync::Mutex;
+
+static TPM_KEY: OnceCell<Mutex<TpmKey>> = OnceCell::uninit();
+
+/// Signs with the key given to `set_tpm_key`. ECDSA and RSASSA/RSAPSS,
+/// the message is hashed here and the TPM signs the digest with TPM2_Sign,
+/// in an HMAC session started for that one command.
+pub static TPM: SpdmAsymSign = SpdmAsymSign { sign_cb: asym_sign };
+
+/// A signing key loaded in the TPM.
+pub struct TpmKeyConfig<'a> {
+    /// Usually a persistent handle, e.g. 0x81000001
+    pub handle: u32,
+    /// The key's auth value, empty if it has none
+    pub auth: &'a [u8],
+}
+
+/// Open the key `TPM` signs with. It can be set once.
+pub fn set_tpm_key(transport: Box<dyn TpmTransport + Send>, config: &TpmKeyConfig) -> SpdmResult {
+    let key = TpmKey::open(transport, config)?;
+    TPM_KEY
+        .try_init_once(|| Mutex::new(key))
+        .map_err(|_| spdm_err!(EBUSY))
+}
+
+fn asym_sign(
+    base_hash_algo: SpdmBaseHashAlgo,
+    base_asym_algo: SpdmBaseAsymAlgo,
+    _context: &[u8],
+    data: &[u8],
+) -> Option<SpdmSignatureStruct> {
+    TPM_KEY
+        .get()?
+        .lock()
+        .ok()?
+        .sign(base_hash_algo, base_asym_algo, data)
+}
+
+struct TpmKey {
+    transport: Box<dyn TpmTransport + Send>,
+    handle: u32,
+    name: Vec<u8>,
+    auth: Vec<u8>,
+    public: TpmPublicKey,
+}
+
+impl TpmKey {
+    fn open(
+        mut transport: Box<dyn TpmTransport + Send>,
+        config: &TpmKeyConfig,
+    ) -> SpdmResult<Self> {
+        let response = transport.transmit(&read_public_command(config.handle))?;
+        let (public, name) = parse_read_public_response(&response)?;
+        Ok(TpmKey {
+            transport,
+            handle: config.handle,
+            name,
+            auth: config.auth.to_vec(),
+            public,
+        })
+    }
+
+    fn sign(
+        &mut self,
+        base_hash_algo: SpdmBaseHashAlgo,
+        base_asym_algo: SpdmBaseAsymAlgo,
+        data: &[u8],
+    ) -> Option<SpdmSignatureStruct> {
+        let key_size = base_asym_algo.get_size() as usize;
+        let sig_scheme = match (base_asym_algo, self.public) {
+            (
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256,
+                TpmPublicKey::Ecc {
+                    curve_id: TPM_ECC_NIST_P256,
+                },
+            )
+            | (
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384,
+                TpmPublicKey::Ecc {
+                    curve_id: TPM_ECC_NIST_P384,
+                },
+            )
+            | (
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P521,
+                TpmPublicKey::Ecc {
+                    curve_id: TPM_ECC_NIST_P521,
+                },
+            ) => TPM_ALG_ECDSA,
+            (
+                SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048
+                | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072
+                | SpdmBaseAsymAlgo::TPM_ALG_RSASSA_4096,
+                TpmPublicKey::Rsa { key_bits },
+            ) if key_bits as usize == key_size * 8 => TPM_ALG_RSASSA,
+            (
+                SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_2048
+                | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072
+                | SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_4096,
+                TpmPublicKey::Rsa { key_bits },
+            ) if key_bits as usize == key_size * 8 => TPM_ALG_RSAPSS,
+            _ => return None,
+        };
+        let hash_alg = tpm_hash_alg(base_hash_algo)?;
+        let digest = crypto::hash::hash_all(base_hash_algo, data)?;
+
+        let mut nonce_caller = [0u8; TPM_SESSION_NONCE_SIZE];
+        crypto::rand::get_random(&mut nonce_caller).ok()?;
+        let response = self
+            .transport
+            .transmit(&start_auth_session_command(&nonce_caller))
+            .ok()?;
+        let session = parse_start_auth_session_response(&response, &nonce_caller).ok()?;
+        let signature = self.sign_digest(&session, digest.as_ref(), sig_scheme, hash_alg);
+        if signature.is_err() {
+            // The TPM only flushes the session when the command succeeds
+            let _ = self
+                .transport
+                .transmit(&flush_context_command(session.handle));
+        }
+        spdm_signature(signature.ok()?, sig_scheme, key_size)
+    }
+
+    fn sign_digest(
+        &mut self,
+        session: &TpmHmacSession,
+        digest: &[u8],
+        sig_scheme: u16,
+        hash_alg: u16,
+    ) -> SpdmResult<TpmSignature> {
+        let command = sign_command(
+            self.handle,
+            &self.name,
+            session,
+            &self.auth,
+            digest,
+            sig_scheme,
+            hash_alg,
+        )?;
+        let response = self.transport.transmit(&command)?;
+        parse_sign_response(&response, session, &self.auth)
+    }
+}
+
+/// SPDM wants ECDSA r and s at their full width, back to back, and the
+/// TPM2B_ECC_PARAMETERs may have lost their leading zeros. The RSA signature
+/// is used as is.
+fn spdm_signature(
+    signature: TpmSignature,
+    sig_scheme: u16,
+    key_size: usize,
+) -> Option<SpdmSignatureStruct> {
+    if key_size > SPDM_MAX_ASYM_KEY_SIZE {
+        return None;
+    }
+    let mut data = [0u8; SPDM_MAX_ASYM_KEY_SIZE];
+    match signature {
+        TpmSignature::Ecdsa { r, s } if sig_scheme == TPM_ALG_ECDSA => {
+            let half = key_size / 2;
+            if r.len() > half || s.len() > half {
+                return None;
+            }
+            data[half - r.len()..half].copy_from_slice(&r);
+            data[key_size - s.len()..key_size].copy_from_slice(&s);
+        }
+        TpmSignature::Rsa { sig } if sig_scheme != TPM_ALG_ECDSA && sig.len() == key_size => {
+            data[..key_size].copy_from_slice(&sig);
+        }
+        _ => return None,
+    }
+    Some(SpdmSignatureStruct {
+        data_size: key_size as u16,
+        data,
+    })
+}
+
+fn tpm_hash_alg(base_hash_algo: SpdmBaseHashAlgo) -> Option<u16> {
+    match base_hash_algo {
+        SpdmBaseHashAlgo::TPM_ALG_SHA_256 => Some(TPM_ALG_SHA256),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_384 => Some(TPM_ALG_SHA384),
+        SpdmBaseHashAlgo::TPM_ALG_SHA_512 => Some(TPM_ALG_SHA512),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_256 => Some(TPM_ALG_SHA3_256),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_384 => Some(TPM_ALG_SHA3_384),
+        SpdmBaseHashAlgo::TPM_ALG_SHA3_512 => Some(TPM_ALG_SHA3_512),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn test_key_directory() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../test_key")
+    }
+
+    fn response(tag: u16, parameters: &[u8]) -> Vec<u8> {
+        let mut response = Vec::new();
+        response.extend_from_slice(&tag.to_be_bytes());
+        response.extend_from_slice(&((10 + parameters.len()) as u32).to_be_bytes());
+        response.extend_from_slice(&0u32.to_be_bytes());
+        response.extend_from_slice(parameters);
+        response
+    }
+
+    fn tpm2b(data: &[u8]) -> Vec<u8> {
+        let mut tpm2b = (data.len() as u16).to_be_bytes().to_vec();
+        tpm2b.extend_from_slice(data);
+        tpm2b
+    }
+
+    fn error(response_code: u32) -> Vec<u8> {
+        let mut response = response(TPM_ST_NO_SESSIONS, &[]);
+        response[6..10].copy_from_slice(&response_code.to_be_bytes());
+        response
+    }
+
+    fn session_hmac(key: &[u8], p: &[u8], newer: &[u8], older: &[u8], attributes: u8) -> Vec<u8> {
+        use hmac::{Hmac, Mac};
+        use sha2::{Digest, Sha256};
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+        mac.update(&Sha256::digest(p));
+        mac.update(newer);
+        mac.update(older);
+        mac.update(&[attributes]);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    const KEY_NAME: [u8; 0x22] = [0x11; 0x22];
+    const SESSION_HANDLE: u32 = 0x0200_0000;
+
+    // Answers TPM2_ReadPublic, TPM2_StartAuthSession, TPM2_Sign and
+    // TPM2_FlushContext for one of the test keys, with room for one session.
+    struct FakeTpm {
+        key_dir: &'static str,
+        auth: &'static [u8],
+        // nonceCaller and nonceTPM
+        session: Option<(Vec<u8>, Vec<u8>)>,
+    }
+
+    impl FakeTpm {
+        fn new(key_dir: &'static str, auth: &'static [u8]) -> Self {
+            FakeTpm {
+                key_dir,
+                auth,
+                session: None,
+            }
+        }
+
+        fn public_area(&self) -> Vec<u8> {
+            let (key_type, parameters) = match self.key_dir {
+                "EcP384" => (TPM_ALG_ECC, TPM_ECC_NIST_P384.to_be_bytes()),
+                _ => (TPM_ALG_RSA, 3072u16.to_be_bytes()),
+            };
+            let mut public = key_type.to_be_bytes().to_vec();
+            public.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+            public.extend_from_slice(&0x0004_0072u32.to_be_bytes());
+            public.extend_from_slice(&tpm2b(&[]));
+            public.extend_from_slice(&TPM_ALG_NULL.to_be_bytes());
+            public.extend_from_slice(&TPM_ALG_NULL.to_be_bytes());
+            public.extend_from_slice(&parameters);
+            // The rest of TPMT_PUBLIC is not read
+            public.extend_from_slice(&[0u8; 8]);
+            public
+        }
+
+        fn sign(&self, digest: &[u8], sig_scheme: u16) -> Vec<u8> {
+            use p384::ecdsa::signature::hazmat::PrehashSigner;
+            use p384::pkcs8::DecodePrivateKey;
+            use rsa::pkcs1::DecodeRsaPrivateKey;
+
+            let key_dir = test_key_directory().join(self.key_dir);
+            let mut signature = sig_scheme.to_be_bytes().to_vec();
+            signature.extend_from_slice(&TPM_ALG_SHA384.to_be_bytes());
+            match sig_scheme {
+                TPM_ALG_ECDSA => {
+                    let der = std::fs::read(key_dir.join("end_responder.key.p8")).unwrap();
+                    let key_pair = p384::ecdsa::SigningKey::from_pkcs8_der(&der).unwrap();
+                    let ecdsa: p384::ecdsa::Signature = key_pair.sign_prehash(digest).unwrap();
+                    let (r, s) = ecdsa.split_bytes();
+                    // The way a TPM may send them
+                    let r: Vec<u8> = r.iter().copied().skip_while(|byte| *byte == 0).collect();
+                    let s: Vec<u8> = s.iter().copied().skip_while(|byte| *byte == 0).collect();
+                    signature.extend_from_slice(&tpm2b(&r));
+                    signature.extend_from_slice(&tpm2b(&s));
+                }
+                _ => {
+                    let der = std::fs::read(key_dir.join("end_responder.key.der")).unwrap();
+                    let private_key = rsa::RsaPrivateKey::from_pkcs1_der(&der).unwrap();
+                    let rsa = if sig_scheme == TPM_ALG_RSAPSS {
+                        private_key.sign_with_rng(
+                            &mut rsa::rand_core::OsRng,
+                            rsa::Pss::new::<sha2::Sha384>(),
+                            digest,
+                        )
+                    } else {
+                        private_key.sign(rsa::Pkcs1v15Sign::new::<sha2::Sha384>(), digest)
+                    };
+                    signature.extend_from_slice(&tpm2b(&rsa.unwrap()));
+                }
+            }
+            signature
+        }
+    }
+
+    impl TpmTransport for FakeTpm {
+        fn transmit(&mut self, command: &[u8]) -> SpdmResult<Vec<u8>> {
+            let command_code = u32::from_be_bytes([command[6], command[7], command[8], command[9]]);
+            match command_code {
+                TPM_CC_READ_PUBLIC => {
+                    let mut parameters = tpm2b(&self.public_area());
+                    parameters.extend_from_slice(&tpm2b(&KEY_NAME));
+                    parameters.extend_from_slice(&tpm2b(&[0x22; 0x22]));
+                    Ok(response(TPM_ST_NO_SESSIONS, &parameters))
+                }
+                TPM_CC_START_AUTH_SESSION => {
+                    if self.session.is_some() {
+                        // TPM_RC_SESSION_MEMORY
+                        return Ok(error(0x0903));
+                    }
+                    let nonce_size = u16::from_be_bytes([command[18], command[19]]) as usize;
+                    let nonce_caller = command[20..20 + nonce_size].to_vec();
+                    let nonce_tpm = vec![0x77; 0x20];
+                    let mut parameters = SESSION_HANDLE.to_be_bytes().to_vec();
+                    parameters.extend_from_slice(&tpm2b(&nonce_tpm));
+                    self.session = Some((nonce_caller, nonce_tpm));
+                    Ok(response(TPM_ST_NO_SESSIONS, &parameters))
+                }
+                TPM_CC_FLUSH_CONTEXT => {
+                    self.session = None;
+                    Ok(response(TPM_ST_NO_SESSIONS, &[]))
+                }
+                TPM_CC_SIGN => {
+                    let (_, nonce_tpm) = self.session.clone().unwrap();
+                    let auth_size =
+                        u32::from_be_bytes([command[14], command[15], command[16], command[17]])
+                            as usize;
+                    let auth_area = &command[18..18 + auth_size];
+                    let parameters = &command[18 + auth_size..];
+                    assert_eq!(&auth_area[..4], &SESSION_HANDLE.to_be_bytes());
+
+                    // TPMS_AUTH_COMMAND: handle, nonce, attributes, hmac
+                    let nonce_size = u16::from_be_bytes([auth_area[4], auth_area[5]]) as usize;
+                    let nonce_caller = &auth_area[6..6 + nonce_size];
+                    let attributes = auth_area[6 + nonce_size];
+                    let hmac = &auth_area[6 + nonce_size + 3..];
+                    let mut cp = TPM_CC_SIGN.to_be_bytes().to_vec();
+                    cp.extend_from_slice(&KEY_NAME);
+                    cp.extend_from_slice(parameters);
+                    if hmac
+                        != &session_hmac(self.auth, &cp, nonce_caller, &nonce_tpm, attributes)[..]
+                    {
+                        // TPM_RC_AUTH_FAIL for session 1, which stays loaded
+                        return Ok(error(0x098e));
+                    }
+
+                    let digest_size = u16::from_be_bytes([parameters[0], parameters[1]]) as usize;
+                    let digest = &parameters[2..2 + digest_size];
+                    let sig_scheme = u16::from_be_bytes([
+                        parameters[2 + digest_size],
+                        parameters[3 + digest_size],
+                    ]);
+                    let signature = self.sign(digest, sig_scheme);
+
+                    let mut rp = 0u32.to_be_bytes().to_vec();
+                    rp.extend_from_slice(&TPM_CC_SIGN.to_be_bytes());
+                    rp.extend_from_slice(&signature);
+                    let nonce_tpm = [0x78; 0x20];
+                    let mut parameters = (signature.len() as u32).to_be_bytes().to_vec();
+                    parameters.extend_from_slice(&signature);
+                    parameters.extend_from_slice(&tpm2b(&nonce_tpm));
+                    parameters.push(0);
+                    parameters.extend_from_slice(&tpm2b(&session_hmac(
+                        self.auth,
+                        &rp,
+                        &nonce_tpm,
+                        nonce_caller,
+                        0,
+                    )));
+                    // continueSession is clear
+                    self.session = None;
+                    Ok(response(TPM_ST_SESSIONS, &parameters))
+                }
+                // TPM_RC_COMMAND_CODE
+                _ => Ok(error(0x0143)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_case0_asym_sign() {
+        let data = &[0x10u8; 4096];
+        for &(key_dir, base_asym_algo) in [
+            ("EcP384", SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384),
+            ("Rsa3072", SpdmBaseAsymAlgo::TPM_ALG_RSASSA_3072),
+            ("Rsa3072", SpdmBaseAsymAlgo::TPM_ALG_RSAPSS_3072),
+        ]
+        .iter()
+        {
+            let base_hash_algo = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
+            let mut key = TpmKey::open(
+                Box::new(FakeTpm::new(key_dir, b"ab")),
+                &TpmKeyConfig {
+                    handle: 0x8100_0001,
+                    auth: b"ab",
+                },
+            )
+            .unwrap();
+            let signature = key.sign(base_hash_algo, base_asym_algo, data).unwrap();
+            assert_eq!(signature.data_size, base_asym_algo.get_size());
+
+            let cert_chain = std::fs::read(
+                test_key_directory()
+                    .join(key_dir)
+                    .join("bundle_responder.certchain.der"),
+            )
+            .unwrap();
+            assert!(crypto::asym_verify::verify(
+                base_hash_algo,
+                base_asym_algo,
+                &[],
+                &cert_chain,
+                data,
+                &signature
+            )
+            .is_ok());
+
+            // The key does not fit these
+            for other_asym_algo in [
+                SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P256,
+                SpdmBaseAsymAlgo::TPM_ALG_RSASSA_2048,
+                SpdmBaseAsymAlgo::EDDSA_ED25519,
+            ]
+            .iter()
+            {
+                assert!(key.sign(base_hash_algo, *other_asym_algo, data).is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn test_case1_asym_sign() {
+        let signature = spdm_signature(
+            TpmSignature::Ecdsa {
+                r: vec![0x11; 31],
+                s: vec![0x22; 32],
+            },
+            TPM_ALG_ECDSA,
+            64,
+        )
+        .unwrap();
+        assert_eq!(signature.data_size, 64);
+        assert_eq!(signature.data[0], 0);
+        assert_eq!(&signature.data[1..32], &[0x11; 31][..]);
+        assert_eq!(&signature.data[32..64], &[0x22; 32][..]);
+
+        assert!(spdm_signature(
+            TpmSignature::Ecdsa {
+                r: vec![0x11; 33],
+                s: vec![0x22; 32],
+            },
+            TPM_ALG_ECDSA,
+            64,
+        )
+        .is_none());
+        assert!(spdm_signature(
+            TpmSignature::Rsa {
+                sig: vec![0x33; 255]
+            },
+            TPM_ALG_RSASSA,
+            256
+        )
+        .is_none());
+        assert!(spdm_signature(
+            TpmSignature::Rsa {
+                sig: vec![0x33; 256]
+            },
+            TPM_ALG_ECDSA,
+            256
+        )
+        .is_none());
+
+        // The TPM turns down the wrong auth value, and the session is
+        // flushed so the next signature can have one
+        let mut key = TpmKey::open(
+            Box::new(FakeTpm::new("EcP384", b"ab")),
+            &TpmKeyConfig {
+                handle: 0x8100_0001,
+                auth: b"ac",
+            },
+        )
+        .unwrap();
+        let base_hash_algo = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
+        let base_asym_algo = SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
+        assert!(key.sign(base_hash_algo, base_asym_algo, &[0x10]).is_none());
+        key.auth = b"ab".to_vec();
+        assert!(key.sign(base_hash_algo, base_asym_algo, &[0x10]).is_some());
+
+        // No response to read
+        assert!(TpmKey::open(
+            Box::new(TpmStream::new(std::io::Cursor::new(Vec::new()))),
+            &TpmKeyConfig {
+                handle: 0x8100_0001,
+                auth: b"",
+            },
+        )
+        .is_err());
+    }
+
+    // Needs swtpm with a test key made persistent, see readme.md:
+    // SPDM_TPM_SIMULATOR=localhost:2321 SPDM_TPM_KEY_HANDLE=0x81000001 \
+    //     cargo test -p tpm_sign -- --ignored
+    #[test]
+    #[ignore]
+    fn test_case2_asym_sign() {
+        let simulator = std::env::var("SPDM_TPM_SIMULATOR").unwrap();
+        let handle = std::env::var("SPDM_TPM_KEY_HANDLE").unwrap();
+        let handle = u32::from_str_radix(handle.trim_start_matches("0x"), 16).unwrap();
+        let stream = std::net::TcpStream::connect(simulator).unwrap();
+        let mut key = TpmKey::open(
+            Box::new(TpmStream::new(stream)),
+            &TpmKeyConfig { handle, auth: b"" },
+        )
+        .unwrap();
+
+        let data = &[0x10u8; 4096];
+        let base_hash_algo = SpdmBaseHashAlgo::TPM_ALG_SHA_384;
+        let base_asym_algo = SpdmBaseAsymAlgo::TPM_ALG_ECDSA_ECC_NIST_P384;
+        let signature = key.sign(base_hash_algo, base_asym_algo, data).unwrap();
+        let cert_chain =
+            std::fs::read(test_key_directory().join("EcP384/bundle_responder.certchain.der"))
+                .unwrap();
+        assert!(crypto::asym_verify::verify(
+            base_hash_algo,
+            base_asym_algo,
+            &[],
+            &cert_chain,
+            data,
+            &signature
+        )
+        .is_ok());
+    }
+}
@@ -0,0 +1,612 @@
+// Copyright (c) 2021 Intel Corporation
+//
+// SPDX-License-Identifier: BSD-2-Clause-Patent
+
+//! The few TPM 2.0 commands the signer sends, marshalled as in the TPM 2.0
+//! Library specification part 3. TPM structures are big endian.
+
+use std::io::{Read, Write};
+
+use spdmlib::crypto;
+use spdmlib::error::SpdmResult;
+use spdmlib::msgs::{SpdmBaseHashAlgo, SpdmDigestStruct};
+
+pub const TPM_ST_NO_SESSIONS: u16 = 0x8001;
+pub const TPM_ST_SESSIONS: u16 = 0x8002;
+pub const TPM_ST_HASHCHECK: u16 = 0x8024;
+
+pub const TPM_CC_FLUSH_CONTEXT: u32 = 0x0000_0165;
+pub const TPM_CC_SIGN: u32 = 0x0000_015d;
+pub const TPM_CC_READ_PUBLIC: u32 = 0x0000_0173;
+pub const TPM_CC_START_AUTH_SESSION: u32 = 0x0000_0176;
+
+pub const TPM_SE_HMAC: u8 = 0x00;
+
+pub const TPM_RH_NULL: u32 = 0x4000_0007;
+
+pub const TPM_ALG_RSA: u16 = 0x0001;
+pub const TPM_ALG_SHA256: u16 = 0x000b;
+pub const TPM_ALG_SHA384: u16 = 0x000c;
+pub const TPM_ALG_SHA512: u16 = 0x000d;
+pub const TPM_ALG_NULL: u16 = 0x0010;
+pub const TPM_ALG_RSASSA: u16 = 0x0014;
+pub const TPM_ALG_RSAES: u16 = 0x0015;
+pub const TPM_ALG_RSAPSS: u16 = 0x0016;
+pub const TPM_ALG_ECDSA: u16 = 0x0018;
+pub const TPM_ALG_ECDAA: u16 = 0x001a;
+pub const TPM_ALG_ECC: u16 = 0x0023;
+pub const TPM_ALG_SHA3_256: u16 = 0x0027;
+pub const TPM_ALG_SHA3_384: u16 = 0x0028;
+pub const TPM_ALG_SHA3_512: u16 = 0x0029;
+
+pub const TPM_ECC_NIST_P256: u16 = 0x0003;
+pub const TPM_ECC_NIST_P384: u16 = 0x0004;
+pub const TPM_ECC_NIST_P521: u16 = 0x0005;
+
+const TPM_HEADER_SIZE: usize = 10;
+
+/// The size of the caller nonce, that of a SHA-256 digest
+pub const TPM_SESSION_NONCE_SIZE: usize = 32;
+
+// continueSession is clear, the TPM flushes the session after the command
+const TPM_SESSION_ATTRIBUTES: u8 = 0;
+
+/// Sends a command to the TPM and returns its response.
+pub trait TpmTransport {
+    fn transmit(&mut self, command: &[u8]) -> SpdmResult<Vec<u8>>;
+}
+
+/// A TPM behind a character device (e.g. /dev/tpmrm0) or the swtpm TCP
+/// server port, both of which take the command bytes as they are.
+pub struct TpmStream<T: Read + Write> {
+    pub stream: T,
+}
+
+impl<T: Read + Write> TpmStream<T> {
+    pub fn new(stream: T) -> Self {
+        TpmStream { stream }
+    }
+}
+
+impl<T: Read + Write> TpmTransport for TpmStream<T> {
+    fn transmit(&mut self, command: &[u8]) -> SpdmResult<Vec<u8>> {
+        if self.stream.write_all(command).is_err() || self.stream.flush().is_err() {
+            return spdm_result_err!(EIO);
+        }
+        // A TPM device hands out the whole response in one read, a socket
+        // may take several
+        let mut response = Vec::new();
+        let mut buffer = [0u8; 4096];
+        loop {
+            let used = self.stream.read(&mut buffer).map_err(|_| spdm_err!(EIO))?;
+            if used == 0 {
+                return spdm_result_err!(EIO);
+            }
+            response.extend_from_slice(&buffer[..used]);
+            if response.len() >= TPM_HEADER_SIZE {
+                let size = u32::from_be_bytes([response[2], response[3], response[4], response[5]])
+                    as usize;
+                if response.len() >= size {
+                    response.truncate(size);
+                    return Ok(response);
+                }
+            }
+        }
+    }
+}
+
+/// The public part of a key, as far as the signer cares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TpmPublicKey {
+    Rsa { key_bits: u16 },
+    Ecc { curve_id: u16 },
+}
+
+/// An HMAC session with neither a salt nor a bind entity (part 1, 19.6).
+/// Its session key is empty, so a command is authorized with an HMAC keyed
+/// by the auth value alone and the auth value itself is never sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TpmHmacSession {
+    pub handle: u32,
+    pub nonce_caller: Vec<u8>,
+    pub nonce_tpm: Vec<u8>,
+}
+
+/// A TPMT_SIGNATURE.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TpmSignature {
+    Ecdsa { r: Vec<u8>, s: Vec<u8> },
+    Rsa { sig: Vec<u8> },
+}
+
+pub fn read_public_command(key_handle: u32) -> Vec<u8> {
+    let mut command = Vec::new();
+    command.extend_from_slice(&TPM_ST_NO_SESSIONS.to_be_bytes());
+    command.extend_from_slice(&0u32.to_be_bytes());
+    command.extend_from_slice(&TPM_CC_READ_PUBLIC.to_be_bytes());
+    command.extend_from_slice(&key_handle.to_be_bytes());
+    finish(command)
+}
+
+/// TPM2_StartAuthSession of an unsalted, unbound SHA-256 HMAC session
+/// without parameter encryption.
+pub fn start_auth_session_command(nonce_caller: &[u8]) -> Vec<u8> {
+    let mut command = Vec::new();
+    command.extend_from_slice(&TPM_ST_NO_SESSIONS.to_be_bytes());
+    command.extend_from_slice(&0u32.to_be_bytes());
+    command.extend_from_slice(&TPM_CC_START_AUTH_SESSION.to_be_bytes());
+    // tpmKey and bind
+    command.extend_from_slice(&TPM_RH_NULL.to_be_bytes());
+    command.extend_from_slice(&TPM_RH_NULL.to_be_bytes());
+    put_tpm2b(&mut command, nonce_caller);
+    // encryptedSalt
+    put_tpm2b(&mut command, &[]);
+    command.push(TPM_SE_HMAC);
+    // symmetric, authHash
+    command.extend_from_slice(&TPM_ALG_NULL.to_be_bytes());
+    command.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+    finish(command)
+}
+
+pub fn flush_context_command(handle: u32) -> Vec<u8> {
+    let mut command = Vec::new();
+    command.extend_from_slice(&TPM_ST_NO_SESSIONS.to_be_bytes());
+    command.extend_from_slice(&0u32.to_be_bytes());
+    command.extend_from_slice(&TPM_CC_FLUSH_CONTEXT.to_be_bytes());
+    command.extend_from_slice(&handle.to_be_bytes());
+    finish(command)
+}
+
+/// TPM2_Sign of `digest`, authorized in `session` with the key's `auth`
+/// value. `key_name` is the Name TPM2_ReadPublic returned for the key.
+/// `sig_scheme` is TPM_ALG_ECDSA, TPM_ALG_RSASSA or TPM_ALG_RSAPSS.
+pub fn sign_command(
+    key_handle: u32,
+    key_name: &[u8],
+    session: &TpmHmacSession,
+    auth: &[u8],
+    digest: &[u8],
+    sig_scheme: u16,
+    hash_alg: u16,
+) -> SpdmResult<Vec<u8>> {
+    let mut parameters = Vec::new();
+    put_tpm2b(&mut parameters, digest);
+    parameters.extend_from_slice(&sig_scheme.to_be_bytes());
+    parameters.extend_from_slice(&hash_alg.to_be_bytes());
+    // A NULL ticket, the key is not restricted
+    parameters.extend_from_slice(&TPM_ST_HASHCHECK.to_be_bytes());
+    parameters.extend_from_slice(&TPM_RH_NULL.to_be_bytes());
+    parameters.extend_from_slice(&0u16.to_be_bytes());
+
+    // cpHash covers the command code, the Name of each handle and the
+    // parameters
+    let mut cp = TPM_CC_SIGN.to_be_bytes().to_vec();
+    cp.extend_from_slice(key_name);
+    cp.extend_from_slice(&parameters);
+    let hmac = crypto::hmac::hmac(
+        SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+        hmac_key(auth),
+        &hmac_data(
+            &cp,
+            &session.nonce_caller,
+            &session.nonce_tpm,
+            TPM_SESSION_ATTRIBUTES,
+        )?,
+    )
+    .ok_or(spdm_err!(EFAULT))?;
+
+    let mut command = Vec::new();
+    command.extend_from_slice(&TPM_ST_SESSIONS.to_be_bytes());
+    command.extend_from_slice(&0u32.to_be_bytes());
+    command.extend_from_slice(&TPM_CC_SIGN.to_be_bytes());
+    command.extend_from_slice(&key_handle.to_be_bytes());
+
+    // TPMS_AUTH_COMMAND
+    let auth_size = 4 + 2 + session.nonce_caller.len() + 1 + 2 + hmac.as_ref().len();
+    command.extend_from_slice(&(auth_size as u32).to_be_bytes());
+    command.extend_from_slice(&session.handle.to_be_bytes());
+    put_tpm2b(&mut command, &session.nonce_caller);
+    command.push(TPM_SESSION_ATTRIBUTES);
+    put_tpm2b(&mut command, hmac.as_ref());
+
+    command.extend_from_slice(&parameters);
+    Ok(finish(command))
+}
+
+pub fn parse_start_auth_session_response(
+    response: &[u8],
+    nonce_caller: &[u8],
+) -> SpdmResult<TpmHmacSession> {
+    let mut reader = response_parameters(response)?;
+    let handle = reader.u32().ok_or(spdm_err!(EIO))?;
+    let nonce_tpm = reader.tpm2b().ok_or(spdm_err!(EIO))?;
+    Ok(TpmHmacSession {
+        handle,
+        nonce_caller: nonce_caller.to_vec(),
+        nonce_tpm: nonce_tpm.to_vec(),
+    })
+}
+
+/// The public area and the Name of a key.
+pub fn parse_read_public_response(response: &[u8]) -> SpdmResult<(TpmPublicKey, Vec<u8>)> {
+    let mut reader = response_parameters(response)?;
+    let mut public = TpmReader::new(reader.tpm2b().ok_or(spdm_err!(EIO))?);
+    let public = read_public_area(&mut public).ok_or(spdm_err!(EIO))?;
+    let name = reader.tpm2b().ok_or(spdm_err!(EIO))?;
+    Ok((public, name.to_vec()))
+}
+
+/// The signature, once the response HMAC shows it came from the TPM that
+/// knows `auth`.
+pub fn parse_sign_response(
+    response: &[u8],
+    session: &TpmHmacSession,
+    auth: &[u8],
+) -> SpdmResult<TpmSignature> {
+    let mut reader = response_parameters(response)?;
+    let tag = u16::from_be_bytes([response[0], response[1]]);
+    if tag != TPM_ST_SESSIONS {
+        return spdm_result_err!(EIO);
+    }
+    let parameter_size = reader.u32().ok_or(spdm_err!(EIO))? as usize;
+    let parameters = reader.bytes(parameter_size).ok_or(spdm_err!(EIO))?;
+
+    // TPMS_AUTH_RESPONSE
+    let nonce_tpm = reader.tpm2b().ok_or(spdm_err!(EIO))?;
+    let attributes = reader.u8().ok_or(spdm_err!(EIO))?;
+    let hmac = reader.tpm2b().ok_or(spdm_err!(EIO))?;
+    if hmac.len() != SpdmBaseHashAlgo::TPM_ALG_SHA_256.get_size() as usize {
+        return spdm_result_err!(EIO);
+    }
+    // rpHash covers the response code, the command code and the parameters
+    let mut rp = 0u32.to_be_bytes().to_vec();
+    rp.extend_from_slice(&TPM_CC_SIGN.to_be_bytes());
+    rp.extend_from_slice(parameters);
+    crypto::hmac::hmac_verify(
+        SpdmBaseHashAlgo::TPM_ALG_SHA_256,
+        hmac_key(auth),
+        &hmac_data(&rp, nonce_tpm, &session.nonce_caller, attributes)?,
+        &SpdmDigestStruct::from(hmac),
+    )
+    .map_err(|_| spdm_err!(EIO))?;
+
+    read_signature(&mut TpmReader::new(parameters)).ok_or(spdm_err!(EIO))
+}
+
+// The session key is empty, and trailing zeros of the auth value are not
+// part of the HMAC key (part 1, 19.6.5).
+fn hmac_key(auth: &[u8]) -> &[u8] {
+    let size = auth
+        .iter()
+        .rposition(|byte| *byte != 0)
+        .map_or(0, |last| last + 1);
+    &auth[..size]
+}
+
+// pHash || nonceNewer || nonceOlder || sessionAttributes
+fn hmac_data(
+    p: &[u8],
+    nonce_newer: &[u8],
+    nonce_older: &[u8],
+    attributes: u8,
+) -> SpdmResult<Vec<u8>> {
+    let p_hash =
+        crypto::hash::hash_all(SpdmBaseHashAlgo::TPM_ALG_SHA_256, p).ok_or(spdm_err!(EFAULT))?;
+    let mut data = p_hash.as_ref().to_vec();
+    data.extend_from_slice(nonce_newer);
+    data.extend_from_slice(nonce_older);
+    data.push(attributes);
+    Ok(data)
+}
+
+fn finish(mut command: Vec<u8>) -> Vec<u8> {
+    let size = command.len() as u32;
+    command[2..6].copy_from_slice(&size.to_be_bytes());
+    command
+}
+
+fn put_tpm2b(command: &mut Vec<u8>, data: &[u8]) {
+    command.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    command.extend_from_slice(data);
+}
+
+// Checks the header and response code, and skips to the handles or the
+// parameters.
+fn response_parameters(response: &[u8]) -> SpdmResult<TpmReader<'_>> {
+    let mut reader = TpmReader::new(response);
+    let _tag = reader.u16().ok_or(spdm_err!(EIO))?;
+    let size = reader.u32().ok_or(spdm_err!(EIO))? as usize;
+    let response_code = reader.u32().ok_or(spdm_err!(EIO))?;
+    if size != response.len() {
+        return spdm_result_err!(EIO);
+    }
+    if response_code != 0 {
+        error!("TPM response code - {:x?}\n", response_code);
+        return spdm_result_err!(EIO);
+    }
+    Ok(reader)
+}
+
+// TPMT_PUBLIC up to the key size or curve
+fn read_public_area(reader: &mut TpmReader) -> Option<TpmPublicKey> {
+    let key_type = reader.u16()?;
+    let _name_alg = reader.u16()?;
+    let _object_attributes = reader.u32()?;
+    let _auth_policy = reader.tpm2b()?;
+
+    // TPMT_SYM_DEF_OBJECT, NULL for a signing key
+    if reader.u16()? != TPM_ALG_NULL {
+        let _key_bits = reader.u16()?;
+        let _mode = reader.u16()?;
+    }
+    let scheme = reader.u16()?;
+    match scheme {
+        TPM_ALG_NULL | TPM_ALG_RSAES => {}
+        TPM_ALG_ECDAA => {
+            let _hash_alg = reader.u16()?;
+            let _count = reader.u16()?;
+        }
+        _ => {
+            let _hash_alg = reader.u16()?;
+        }
+    }
+
+    match key_type {
+        TPM_ALG_RSA => Some(TpmPublicKey::Rsa {
+            key_bits: reader.u16()?,
+        }),
+        TPM_ALG_ECC => Some(TpmPublicKey::Ecc {
+            curve_id: reader.u16()?,
+        }),
+        _ => None,
+    }
+}
+
+fn read_signature(reader: &mut TpmReader) -> Option<TpmSignature> {
+    let sig_alg = reader.u16()?;
+    let _hash = reader.u16()?;
+    match sig_alg {
+        TPM_ALG_ECDSA => Some(TpmSignature::Ecdsa {
+            r: reader.tpm2b()?.to_vec(),
+            s: reader.tpm2b()?.to_vec(),
+        }),
+        TPM_ALG_RSASSA | TPM_ALG_RSAPSS => Some(TpmSignature::Rsa {
+            sig: reader.tpm2b()?.to_vec(),
+        }),
+        _ => None,
+    }
+}
+
+struct TpmReader<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> TpmReader<'a> {
+    fn new(buffer: &'a [u8]) -> Self {
+        TpmReader { buffer }
+    }
+
+    fn bytes(&mut self, size: usize) -> Option<&'a [u8]> {
+        if self.buffer.len() < size {
+            return None;
+        }
+        let (bytes, rest) = self.buffer.split_at(size);
+        self.buffer = rest;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let bytes = self.bytes(2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.bytes(4)?;
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn tpm2b(&mut self) -> Option<&'a [u8]> {
+        let size = self.u16()? as usize;
+        self.bytes(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hmac::{Hmac, Mac};
+    use sha2::{Digest, Sha256};
+
+    fn session() -> TpmHmacSession {
+        TpmHmacSession {
+            handle: 0x0200_0000,
+            nonce_caller: vec![0x01; TPM_SESSION_NONCE_SIZE],
+            nonce_tpm: vec![0x02; TPM_SESSION_NONCE_SIZE],
+        }
+    }
+
+    fn session_hmac(key: &[u8], p: &[u8], newer: &[u8], older: &[u8], attributes: u8) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+        mac.update(&Sha256::digest(p));
+        mac.update(newer);
+        mac.update(older);
+        mac.update(&[attributes]);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_case0_sign_command() {
+        let session = session();
+        let key_name = [0x33; 0x22];
+        let command = sign_command(
+            0x8100_0001,
+            &key_name,
+            &session,
+            b"ab\0",
+            &[0x5a; 4],
+            TPM_ALG_ECDSA,
+            TPM_ALG_SHA384,
+        )
+        .unwrap();
+        let parameters = [
+            0x00, 0x04, 0x5a, 0x5a, 0x5a, 0x5a, // digest
+            0x00, 0x18, 0x00, 0x0c, // TPM_ALG_ECDSA, TPM_ALG_SHA384
+            0x80, 0x24, // TPM_ST_HASHCHECK
+            0x40, 0x00, 0x00, 0x07, // TPM_RH_NULL
+            0x00, 0x00, // digest
+        ];
+        let mut expected = vec![
+            0x80, 0x02, // TPM_ST_SESSIONS
+            0x00, 0x00, 0x00, 0x6d, // commandSize
+            0x00, 0x00, 0x01, 0x5d, // TPM_CC_Sign
+            0x81, 0x00, 0x00, 0x01, // keyHandle
+            0x00, 0x00, 0x00, 0x49, // authorizationSize
+            0x02, 0x00, 0x00, 0x00, // sessionHandle
+            0x00, 0x20, // nonceCaller
+        ];
+        expected.extend_from_slice(&[0x01; 0x20]);
+        expected.push(0x00); // sessionAttributes
+        expected.extend_from_slice(&[0x00, 0x20]);
+        let mut cp = TPM_CC_SIGN.to_be_bytes().to_vec();
+        cp.extend_from_slice(&key_name);
+        cp.extend_from_slice(&parameters);
+        // The trailing zero of the auth value is not part of the key
+        expected.extend_from_slice(&session_hmac(
+            b"ab",
+            &cp,
+            &session.nonce_caller,
+            &session.nonce_tpm,
+            0,
+        ));
+        expected.extend_from_slice(&parameters);
+        assert_eq!(command.len(), 0x6d);
+        assert_eq!(command, expected);
+
+        assert_eq!(
+            read_public_command(0x8100_0001),
+            [0x80, 0x01, 0x00, 0x00, 0x00, 0x0e, 0x00, 0x00, 0x01, 0x73, 0x81, 0x00, 0x00, 0x01]
+        );
+        let mut expected = vec![
+            0x80, 0x01, 0x00, 0x00, 0x00, 0x3b, 0x00, 0x00, 0x01, 0x76, // header
+            0x40, 0x00, 0x00, 0x07, 0x40, 0x00, 0x00, 0x07, // tpmKey, bind
+            0x00, 0x20, // nonceCaller
+        ];
+        expected.extend_from_slice(&[0x01; 0x20]);
+        expected.extend_from_slice(&[
+            0x00, 0x00, // encryptedSalt
+            0x00, // TPM_SE_HMAC
+            0x00, 0x10, // TPM_ALG_NULL
+            0x00, 0x0b, // TPM_ALG_SHA256
+        ]);
+        assert_eq!(start_auth_session_command(&[0x01; 0x20]), expected);
+        assert_eq!(
+            flush_context_command(0x0200_0000),
+            [0x80, 0x01, 0x00, 0x00, 0x00, 0x0e, 0x00, 0x00, 0x01, 0x65, 0x02, 0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_case0_parse_read_public_response() {
+        // An unrestricted ECDSA P-384 signing key, from TPM2_ReadPublic
+        let mut public = Vec::new();
+        public.extend_from_slice(&TPM_ALG_ECC.to_be_bytes());
+        public.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+        public.extend_from_slice(&0x0004_0072u32.to_be_bytes());
+        public.extend_from_slice(&0u16.to_be_bytes());
+        public.extend_from_slice(&TPM_ALG_NULL.to_be_bytes());
+        public.extend_from_slice(&TPM_ALG_ECDSA.to_be_bytes());
+        public.extend_from_slice(&TPM_ALG_SHA384.to_be_bytes());
+        public.extend_from_slice(&TPM_ECC_NIST_P384.to_be_bytes());
+        public.extend_from_slice(&TPM_ALG_NULL.to_be_bytes());
+        public.extend_from_slice(&[0x00, 0x30]);
+        public.extend_from_slice(&[0x11; 0x30]);
+        public.extend_from_slice(&[0x00, 0x30]);
+        public.extend_from_slice(&[0x22; 0x30]);
+
+        let mut response = Vec::new();
+        response.extend_from_slice(&TPM_ST_NO_SESSIONS.to_be_bytes());
+        response.extend_from_slice(&0u32.to_be_bytes());
+        response.extend_from_slice(&0u32.to_be_bytes());
+        put_tpm2b(&mut response, &public);
+        put_tpm2b(&mut response, &[0x33; 0x22]);
+        put_tpm2b(&mut response, &[0x44; 0x22]);
+        let response = finish(response);
+        assert_eq!(
+            parse_read_public_response(&response).unwrap(),
+            (
+                TpmPublicKey::Ecc {
+                    curve_id: TPM_ECC_NIST_P384
+                },
+                vec![0x33; 0x22]
+            )
+        );
+
+        // TPM_RC_HANDLE for handle 1
+        let response = [0x80, 0x01, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x01, 0x8b];
+        assert!(parse_read_public_response(&response).is_err());
+        assert!(parse_read_public_response(&response[..8]).is_err());
+
+        let mut response = Vec::new();
+        response.extend_from_slice(&TPM_ST_NO_SESSIONS.to_be_bytes());
+        response.extend_from_slice(&0u32.to_be_bytes());
+        response.extend_from_slice(&0u32.to_be_bytes());
+        response.extend_from_slice(&0x0200_0000u32.to_be_bytes());
+        put_tpm2b(&mut response, &[0x02; 0x20]);
+        let response = finish(response);
+        assert_eq!(
+            parse_start_auth_session_response(&response, &[0x01; 0x20]).unwrap(),
+            session()
+        );
+    }
+
+    #[test]
+    fn test_case0_parse_sign_response() {
+        let session = session();
+        let mut parameters = Vec::new();
+        parameters.extend_from_slice(&TPM_ALG_RSAPSS.to_be_bytes());
+        parameters.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+        put_tpm2b(&mut parameters, &[0x55; 256]);
+
+        let sign_response = |parameters: &[u8], auth: &[u8]| {
+            let mut rp = 0u32.to_be_bytes().to_vec();
+            rp.extend_from_slice(&TPM_CC_SIGN.to_be_bytes());
+            rp.extend_from_slice(parameters);
+            let nonce_tpm = [0x03; 0x20];
+
+            let mut response = Vec::new();
+            response.extend_from_slice(&TPM_ST_SESSIONS.to_be_bytes());
+            response.extend_from_slice(&0u32.to_be_bytes());
+            response.extend_from_slice(&0u32.to_be_bytes());
+            response.extend_from_slice(&(parameters.len() as u32).to_be_bytes());
+            response.extend_from_slice(parameters);
+            // TPMS_AUTH_RESPONSE: nonce, attributes, hmac
+            put_tpm2b(&mut response, &nonce_tpm);
+            response.push(0);
+            put_tpm2b(
+                &mut response,
+                &session_hmac(auth, &rp, &nonce_tpm, &session.nonce_caller, 0),
+            );
+            finish(response)
+        };
+
+        let response = sign_response(&parameters, b"ab");
+        assert_eq!(
+            parse_sign_response(&response, &session, b"ab").unwrap(),
+            TpmSignature::Rsa {
+                sig: vec![0x55; 256]
+            }
+        );
+        // Not from a TPM that knows the auth value
+        assert!(parse_sign_response(&response, &session, b"ac").is_err());
+        let mut altered = response.clone();
+        altered[20] ^= 1;
+        assert!(parse_sign_response(&altered, &session, b"ab").is_err());
+
+        let response = sign_response(&parameters[..parameters.len() - 10], b"ab");
+        assert!(parse_sign_response(&response, &session, b"ab").is_err());
+    }
+}